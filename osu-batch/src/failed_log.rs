@@ -0,0 +1,285 @@
+//! Persistent list of permanently failed items
+//!
+//! Entries are skipped on later runs. The log is written as `name: reason` lines
+//! (the original format), or as JSON/CSV records with a reason category and
+//! timestamp for triaging large runs.
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// On-disk format of the failed log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum FailedFormat {
+    /// `name: reason` lines
+    Txt,
+    /// JSON array of records
+    Json,
+    /// CSV with a header row
+    Csv,
+}
+
+impl FailedFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            FailedFormat::Txt => "txt",
+            FailedFormat::Json => "json",
+            FailedFormat::Csv => "csv",
+        }
+    }
+}
+
+/// One failed item
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FailedRecord {
+    /// Item name: the archive file stem in the extractor, the folder name in the builder
+    pub name: String,
+    /// Coarse reason chosen by the tool (`timeout`, `extract`, `parse`, ...), or `unknown`
+    /// for entries read from txt
+    pub category: String,
+    /// Unix time in seconds when the failure was recorded (0 if unknown)
+    pub timestamp: u64,
+    /// Full error message
+    pub detail: String,
+}
+
+/// Failed records keyed by name, loaded from and saved back to one file
+pub struct FailedLog {
+    path: PathBuf,
+    format: FailedFormat,
+    records: BTreeMap<String, FailedRecord>,
+    initial_count: usize,
+}
+
+impl FailedLog {
+    /// Load the log at `path`, or start empty if it doesn't exist
+    pub fn load(path: &Path, format: FailedFormat) -> Result<Self> {
+        let records: Vec<FailedRecord> = if path.exists() {
+            let content = fs::read_to_string(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            match format {
+                FailedFormat::Txt => parse_txt(&content),
+                FailedFormat::Json => serde_json::from_str(&content)
+                    .with_context(|| format!("Failed to parse {}", path.display()))?,
+                FailedFormat::Csv => parse_csv(&content),
+            }
+        } else {
+            Vec::new()
+        };
+
+        let records: BTreeMap<String, FailedRecord> =
+            records.into_iter().map(|r| (r.name.clone(), r)).collect();
+        Ok(Self {
+            path: path.to_path_buf(),
+            format,
+            initial_count: records.len(),
+            records,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.records.contains_key(name)
+    }
+
+    /// Number of entries loaded from disk
+    pub fn initial_count(&self) -> usize {
+        self.initial_count
+    }
+
+    /// Number of entries added during this run
+    pub fn new_count(&self) -> usize {
+        self.records.len() - self.initial_count
+    }
+
+    /// Record a failure, replacing any earlier entry for the same name
+    pub fn record(&mut self, name: &str, category: &str, error: &anyhow::Error) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        self.records.insert(
+            name.to_string(),
+            FailedRecord {
+                name: name.to_string(),
+                category: category.to_string(),
+                timestamp,
+                // Keep every record on one line for txt/csv
+                detail: error.to_string().replace(['\r', '\n'], " "),
+            },
+        );
+    }
+
+    /// Write all records back to the log file
+    pub fn save(&self) -> Result<()> {
+        let content = match self.format {
+            FailedFormat::Txt => self
+                .records
+                .values()
+                .map(|r| format!("{}: {}\n", r.name, r.detail))
+                .collect(),
+            FailedFormat::Json => {
+                let records: Vec<&FailedRecord> = self.records.values().collect();
+                serde_json::to_string_pretty(&records)?
+            }
+            FailedFormat::Csv => {
+                let mut content = String::from("name,category,timestamp,detail\n");
+                for r in self.records.values() {
+                    content.push_str(&format!(
+                        "{},{},{},{}\n",
+                        csv_field(&r.name),
+                        csv_field(&r.category),
+                        r.timestamp,
+                        csv_field(&r.detail)
+                    ));
+                }
+                content
+            }
+        };
+        fs::write(&self.path, content).with_context(|| format!("Failed to write {}", self.path.display()))
+    }
+}
+
+/// Parse `name: reason` lines
+fn parse_txt(content: &str) -> Vec<FailedRecord> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let (name, detail) = line.split_once(':').unwrap_or((line, ""));
+            FailedRecord {
+                name: name.trim().to_string(),
+                category: "unknown".to_string(),
+                timestamp: 0,
+                detail: detail.trim().to_string(),
+            }
+        })
+        .filter(|r| !r.name.is_empty())
+        .collect()
+}
+
+/// Parse the CSV written by `save` (header row, one record per line)
+fn parse_csv(content: &str) -> Vec<FailedRecord> {
+    content
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields = split_csv_line(line);
+            let [name, category, timestamp, detail] = fields.as_slice() else {
+                return None;
+            };
+            Some(FailedRecord {
+                name: name.clone(),
+                category: category.clone(),
+                timestamp: timestamp.parse().unwrap_or(0),
+                detail: detail.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Quote a CSV field if it contains a delimiter or quote
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Split one CSV line into fields, handling quoted fields with `""` escapes
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn log_path(format: FailedFormat) -> PathBuf {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "osu-batch-test-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        dir.join(format!("failed.{}", format.extension()))
+    }
+
+    fn round_trip(format: FailedFormat) -> FailedLog {
+        let path = log_path(format);
+        let mut log = FailedLog::load(&path, format).unwrap();
+        log.record("123 Artist - Title", "parse", &anyhow::anyhow!("bad line \"3,4\"\nat 12"));
+        log.record("456", "timeout", &anyhow::anyhow!("took too long"));
+        assert_eq!(log.new_count(), 2);
+        log.save().unwrap();
+        FailedLog::load(&path, format).unwrap()
+    }
+
+    #[test]
+    fn structured_formats_keep_every_field() {
+        for format in [FailedFormat::Json, FailedFormat::Csv] {
+            let log = round_trip(format);
+            assert_eq!(log.initial_count(), 2);
+            assert_eq!(log.new_count(), 0);
+
+            let record = &log.records["123 Artist - Title"];
+            assert_eq!(record.category, "parse");
+            assert_eq!(record.detail, "bad line \"3,4\" at 12");
+            assert!(record.timestamp > 0);
+        }
+    }
+
+    #[test]
+    fn txt_keeps_names_and_details() {
+        let log = round_trip(FailedFormat::Txt);
+        assert!(log.contains("456"));
+        let record = &log.records["123 Artist - Title"];
+        assert_eq!((record.category.as_str(), record.timestamp), ("unknown", 0));
+        assert_eq!(record.detail, "bad line \"3,4\" at 12");
+    }
+
+    #[test]
+    fn recording_again_replaces_the_entry() {
+        let mut log = FailedLog::load(&log_path(FailedFormat::Json), FailedFormat::Json).unwrap();
+        log.record("1", "extract", &anyhow::anyhow!("first"));
+        log.record("1", "timeout", &anyhow::anyhow!("second"));
+        assert_eq!(log.new_count(), 1);
+        assert_eq!(log.records["1"].category, "timeout");
+    }
+
+    #[test]
+    fn csv_fields_with_quotes_and_commas_split_back() {
+        assert_eq!(
+            split_csv_line(&format!("a,{},{}", csv_field("b,c"), csv_field("say \"hi\""))),
+            vec!["a", "b,c", "say \"hi\""]
+        );
+    }
+}
@@ -0,0 +1,12 @@
+//! osu-batch: Run bookkeeping shared by the extractor, builder and enricher
+//!
+//! Each tool processes many independent items (archives, folders, beatmapsets)
+//! and keeps going when one fails. This crate holds the parts of that loop that
+//! must behave the same in every tool, such as the persistent failed log and
+//! the `--quiet` output with its JSON run summary.
+
+pub mod failed_log;
+pub mod summary;
+
+pub use failed_log::{FailedFormat, FailedLog, FailedRecord};
+pub use summary::RunSummary;
@@ -0,0 +1,83 @@
+//! `--quiet` mode: no progress output, one JSON summary line on stdout
+//!
+//! Status lines go through [`say!`](crate::say) and progress bars through
+//! [`progress_bar`], so scripts only see the final [`RunSummary`]. Warnings on
+//! stderr are kept. Every CLI prints the same summary shape.
+
+use anyhow::Result;
+use indicatif::ProgressBar;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// `println!` that is silenced by `--quiet`
+#[macro_export]
+macro_rules! say {
+    ($($arg:tt)*) => {
+        if !$crate::summary::is_quiet() {
+            println!($($arg)*);
+        }
+    };
+}
+
+/// Progress bar that is hidden (including its `println`s) with `--quiet`
+pub fn progress_bar(len: u64) -> ProgressBar {
+    if is_quiet() {
+        ProgressBar::hidden()
+    } else {
+        ProgressBar::new(len)
+    }
+}
+
+/// Machine-readable result of a run, printed as one JSON line with `--quiet`
+#[derive(Debug, Default, Serialize)]
+pub struct RunSummary {
+    /// Items attempted this run (skipped ones are not counted)
+    pub processed: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    /// Rows per output table or file
+    pub rows: BTreeMap<String, usize>,
+    /// Whether Ctrl+C stopped the run early
+    pub interrupted: bool,
+}
+
+impl RunSummary {
+    /// Print the summary line if `--quiet` is set
+    pub fn print(&self) -> Result<()> {
+        if is_quiet() {
+            println!("{}", serde_json::to_string(self)?);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summary_serializes_to_one_flat_json_object() {
+        let summary = RunSummary {
+            processed: 3,
+            succeeded: 2,
+            failed: 1,
+            rows: BTreeMap::from([("beatmaps".to_string(), 5)]),
+            interrupted: false,
+        };
+        assert_eq!(
+            serde_json::to_string(&summary).unwrap(),
+            r#"{"processed":3,"succeeded":2,"failed":1,"rows":{"beatmaps":5},"interrupted":false}"#
+        );
+    }
+}
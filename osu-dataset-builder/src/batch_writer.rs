@@ -1,64 +1,212 @@
-//! Batch-wise parquet writers for memory-efficient data export
+//! Batch-wise parquet (or Arrow IPC) writers for memory-efficient data export
 //! 
-//! Writes new data to temp files, then merges with existing parquet on close.
+//! Writes new data to temp files, then merges with existing data on close.
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use arrow::array::*;
-use arrow::datatypes::{DataType, Field, Schema};
-use arrow::record_batch::RecordBatch;
+use arrow::compute::filter_record_batch;
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::ipc::reader::FileReader;
+use arrow::ipc::writer::FileWriter;
+use arrow::record_batch::{RecordBatch, RecordBatchReader};
+use clap::ValueEnum;
 use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
 use parquet::arrow::ArrowWriter;
-use parquet::file::properties::WriterProperties;
+use parquet::file::metadata::KeyValue;
+use parquet::file::properties::{WriterProperties, WriterPropertiesBuilder};
+use parquet::schema::types::ColumnPath;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::fs::{self, File};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 use crate::{
     BeatmapRow, HitObjectRow, TimingPointRow, StoryboardElementRow, 
     StoryboardCommandRow, SliderControlPointRow, SliderDataRow,
     BreakRow, ComboColorRow, HitSampleRow, StoryboardLoopRow, StoryboardTriggerRow,
+    AssetRow, EventRow, ExtraFieldRow, CustomFeatureRow,
 };
 
 const DEFAULT_BATCH_SIZE: usize = 1000;
 
-/// Merge existing parquet file with new temp file, writing result to final path
-fn merge_parquet_files(existing_path: &Path, temp_path: &Path, schema: Arc<Schema>) -> Result<usize> {
+/// On-disk format of the dataset tables
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Snappy-compressed parquet
+    Parquet,
+    /// Uncompressed Arrow IPC file (Feather v2): larger on disk, but loads without decoding and can be memory-mapped
+    Arrow,
+}
+
+impl OutputFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Parquet => "parquet",
+            OutputFormat::Arrow => "arrow",
+        }
+    }
+
+    /// Format of a table file by extension (`.arrow` and `.feather` are Arrow IPC, anything else parquet)
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("arrow") | Some("feather") => OutputFormat::Arrow,
+            _ => OutputFormat::Parquet,
+        }
+    }
+
+    /// Path of `table` inside `dir`
+    pub fn table_path(self, dir: &Path, table: &str) -> PathBuf {
+        dir.join(format!("{}.{}", table, self.extension()))
+    }
+}
+
+/// Whether parquet files get a Bloom filter on `folder_id` (`--bloom`)
+static FOLDER_ID_BLOOM: AtomicBool = AtomicBool::new(false);
+
+/// Distinct folder_ids a row group's Bloom filter is sized for
+const BLOOM_FILTER_NDV: u64 = 100_000;
+/// False positive rate of the Bloom filter at `BLOOM_FILTER_NDV` folders
+const BLOOM_FILTER_FPP: f64 = 0.01;
+
+pub fn set_folder_id_bloom(enabled: bool) {
+    FOLDER_ID_BLOOM.store(enabled, Ordering::Relaxed);
+}
+
+/// Parquet writer settings: snappy, plus the `folder_id` Bloom filter with `--bloom`
+///
+/// The filter lets readers skip row groups that can't contain a folder without
+/// decoding them; tables without a `folder_id` column are written as usual.
+pub fn writer_properties_builder(schema: &Schema) -> WriterPropertiesBuilder {
+    let mut props = WriterProperties::builder().set_compression(parquet::basic::Compression::SNAPPY);
+    if FOLDER_ID_BLOOM.load(Ordering::Relaxed) && schema.column_with_name("folder_id").is_some() {
+        let column = ColumnPath::from("folder_id");
+        props = props
+            .set_column_bloom_filter_enabled(column.clone(), true)
+            .set_column_bloom_filter_ndv(column.clone(), BLOOM_FILTER_NDV)
+            .set_column_bloom_filter_fpp(column, BLOOM_FILTER_FPP);
+    }
+    props
+}
+
+/// Build provenance stored in the footer of every table file (see `manifest::provenance`)
+///
+/// Parquet files keep it as key-value metadata, Arrow IPC files as custom metadata,
+/// so it stays with a file copied out of the dataset directory.
+pub type Provenance = BTreeMap<String, String>;
+
+/// Provenance as parquet key-value metadata, `None` when empty
+pub fn provenance_key_values(provenance: &Provenance) -> Option<Vec<KeyValue>> {
+    let key_values: Vec<KeyValue> =
+        provenance.iter().map(|(key, value)| KeyValue::new(key.clone(), value.clone())).collect();
+    (!key_values.is_empty()).then_some(key_values)
+}
+
+/// Writer for one table file in either format
+enum TableWriter {
+    Parquet(ArrowWriter<File>),
+    Arrow(FileWriter<File>),
+}
+
+impl TableWriter {
+    fn create(path: &Path, schema: Arc<Schema>, format: OutputFormat, provenance: &Provenance) -> Result<Self> {
+        let file = File::create(path)?;
+        Ok(match format {
+            OutputFormat::Parquet => {
+                let props = writer_properties_builder(&schema)
+                    .set_key_value_metadata(provenance_key_values(provenance))
+                    .build();
+                TableWriter::Parquet(ArrowWriter::try_new(file, schema, Some(props))?)
+            }
+            OutputFormat::Arrow => {
+                let mut writer = FileWriter::try_new(file, &schema)?;
+                for (key, value) in provenance {
+                    writer.write_metadata(key, value);
+                }
+                TableWriter::Arrow(writer)
+            }
+        })
+    }
+
+    fn write(&mut self, batch: &RecordBatch) -> Result<()> {
+        match self {
+            TableWriter::Parquet(writer) => writer.write(batch)?,
+            TableWriter::Arrow(writer) => writer.write(batch)?,
+        }
+        Ok(())
+    }
+
+    fn close(self) -> Result<()> {
+        match self {
+            TableWriter::Parquet(writer) => {
+                writer.close()?;
+            }
+            TableWriter::Arrow(mut writer) => writer.finish()?,
+        }
+        Ok(())
+    }
+}
+
+/// Read every batch of a table file written in `format`
+pub fn read_table_batches(path: &Path, format: OutputFormat) -> Result<Vec<RecordBatch>> {
+    Ok(read_table_with_schema(path, format)?.1)
+}
+
+/// Read a table file with its schema, which a file without rows still has
+pub fn read_table_with_schema(path: &Path, format: OutputFormat) -> Result<(SchemaRef, Vec<RecordBatch>)> {
+    let file = File::open(path)?;
+    Ok(match format {
+        OutputFormat::Parquet => {
+            let reader = ParquetRecordBatchReaderBuilder::try_new(file)?.build()?;
+            (reader.schema(), reader.collect::<Result<Vec<_>, _>>()?)
+        }
+        OutputFormat::Arrow => {
+            let reader = FileReader::try_new(file, None)?;
+            (reader.schema(), reader.collect::<Result<Vec<_>, _>>()?)
+        }
+    })
+}
+
+/// Merge existing table file with new temp file, writing result to final path
+///
+/// Existing rows of the `replaced` folders are dropped, so their new rows take
+/// their place. The merged file carries `provenance`, i.e. that of the latest
+/// build appending to it.
+fn merge_table_files(
+    existing_path: &Path,
+    temp_path: &Path,
+    schema: Arc<Schema>,
+    format: OutputFormat,
+    provenance: &Provenance,
+    replaced: &HashSet<String>,
+) -> Result<usize> {
     let mut all_batches: Vec<RecordBatch> = Vec::new();
     
-    // Read existing file if it exists
+    // Read existing file if it exists; its rows must fit the columns written now
     if existing_path.exists() {
-        let file = File::open(existing_path)?;
-        let reader = ParquetRecordBatchReaderBuilder::try_new(file)?.build()?;
-        for batch in reader {
-            all_batches.push(batch?);
+        let (existing_schema, batches) = read_table_with_schema(existing_path, format)?;
+        check_same_columns(existing_path, &existing_schema, &schema)?;
+        for batch in batches {
+            all_batches.push(drop_folders(existing_path, batch, replaced)?);
         }
     }
     
     // Read temp file
     if temp_path.exists() {
-        let file = File::open(temp_path)?;
-        let reader = ParquetRecordBatchReaderBuilder::try_new(file)?.build()?;
-        for batch in reader {
-            all_batches.push(batch?);
-        }
+        all_batches.extend(read_table_batches(temp_path, format)?);
     }
     
     // Count total rows
     let total_rows: usize = all_batches.iter().map(|b| b.num_rows()).sum();
     
-    if total_rows == 0 {
+    if total_rows == 0 && !existing_path.exists() {
         // No data - remove temp file if exists
         let _ = fs::remove_file(temp_path);
         return Ok(0);
     }
     
     // Write merged result
-    let file = File::create(existing_path)?;
-    let props = WriterProperties::builder()
-        .set_compression(parquet::basic::Compression::SNAPPY)
-        .build();
-    let mut writer = ArrowWriter::try_new(file, schema, Some(props))?;
-    
+    let mut writer = TableWriter::create(existing_path, schema, format, provenance)?;
     for batch in &all_batches {
         writer.write(batch)?;
     }
@@ -70,10 +218,42 @@ fn merge_parquet_files(existing_path: &Path, temp_path: &Path, schema: Arc<Schem
     Ok(total_rows)
 }
 
-/// Generic batch writer for parquet files
+/// Remove the rows of `folder_ids` from a batch of an existing table file
+fn drop_folders(path: &Path, batch: RecordBatch, folder_ids: &HashSet<String>) -> Result<RecordBatch> {
+    if folder_ids.is_empty() {
+        return Ok(batch);
+    }
+    let Some(folder_id) = batch.column_by_name("folder_id").and_then(|col| col.as_any().downcast_ref::<StringArray>()) else {
+        bail!("{} has no string folder_id column to replace folders by", path.display());
+    };
+    let keep: BooleanArray = folder_id.iter().map(|id| Some(!id.is_some_and(|id| folder_ids.contains(id)))).collect();
+    Ok(filter_record_batch(&batch, &keep)?)
+}
+
+/// Fail unless an existing table file has exactly the columns this build writes
+///
+/// Appending to a file from another schema version would otherwise fail deep in
+/// the writer, or silently mix column meanings.
+fn check_same_columns(path: &Path, existing: &Schema, expected: &Schema) -> Result<()> {
+    let names = |schema: &Schema| -> BTreeSet<String> { schema.fields().iter().map(|f| f.name().clone()).collect() };
+    let (existing, expected) = (names(existing), names(expected));
+    if existing != expected {
+        let missing: Vec<&String> = expected.difference(&existing).collect();
+        let unexpected: Vec<&String> = existing.difference(&expected).collect();
+        bail!(
+            "{} was written with other columns (missing: {:?}, unexpected: {:?}); rebuild the dataset into a new directory instead of appending",
+            path.display(),
+            missing,
+            unexpected
+        );
+    }
+    Ok(())
+}
+
+/// Generic batch writer for parquet or Arrow IPC files, chosen by the path's extension
 /// Writes to a temp file, then merges with existing data on close()
 pub struct BatchWriter<T, F: Fn(&[T]) -> Result<RecordBatch>> {
-    writer: ArrowWriter<File>,
+    writer: TableWriter,
     buffer: Vec<T>,
     batch_size: usize,
     to_batch: F,
@@ -82,21 +262,26 @@ pub struct BatchWriter<T, F: Fn(&[T]) -> Result<RecordBatch>> {
     final_path: PathBuf,
     temp_path: PathBuf,
     schema: Arc<Schema>,
+    format: OutputFormat,
+    provenance: Provenance,
 }
 
 impl<T, F: Fn(&[T]) -> Result<RecordBatch>> BatchWriter<T, F> {
-    pub fn new(path: &Path, schema: Arc<Schema>, to_batch: F) -> Result<Self> {
-        Self::with_batch_size(path, schema, to_batch, DEFAULT_BATCH_SIZE)
+    pub fn new(path: &Path, schema: Arc<Schema>, to_batch: F, provenance: Provenance) -> Result<Self> {
+        Self::with_batch_size(path, schema, to_batch, provenance, DEFAULT_BATCH_SIZE)
     }
 
-    pub fn with_batch_size(path: &Path, schema: Arc<Schema>, to_batch: F, batch_size: usize) -> Result<Self> {
+    pub fn with_batch_size(
+        path: &Path,
+        schema: Arc<Schema>,
+        to_batch: F,
+        provenance: Provenance,
+        batch_size: usize,
+    ) -> Result<Self> {
         // Write to temp file, not the final path
-        let temp_path = path.with_extension("parquet.tmp");
-        let file = File::create(&temp_path)?;
-        let props = WriterProperties::builder()
-            .set_compression(parquet::basic::Compression::SNAPPY)
-            .build();
-        let writer = ArrowWriter::try_new(file, schema.clone(), Some(props))?;
+        let format = OutputFormat::from_path(path);
+        let temp_path = path.with_extension(format!("{}.tmp", format.extension()));
+        let writer = TableWriter::create(&temp_path, schema.clone(), format, &provenance)?;
         
         Ok(Self {
             writer,
@@ -107,6 +292,8 @@ impl<T, F: Fn(&[T]) -> Result<RecordBatch>> BatchWriter<T, F> {
             final_path: path.to_path_buf(),
             temp_path,
             schema,
+            format,
+            provenance,
         })
     }
 
@@ -129,31 +316,111 @@ impl<T, F: Fn(&[T]) -> Result<RecordBatch>> BatchWriter<T, F> {
         Ok(())
     }
 
-    /// Close the writer and merge temp file with existing data
-    /// Skips merge if no new data was written
-    pub fn close(mut self) -> Result<usize> {
+    /// Close the writer and merge temp file with existing data, dropping the
+    /// existing rows of the `replaced` folders
+    /// Skips merge if there is nothing to add or drop
+    pub fn close(mut self, replaced: &HashSet<String>) -> Result<usize> {
         self.flush()?;
         self.writer.close()?;
         
         // If no new rows, just clean up temp file and return existing count
-        if self.total_rows == 0 {
+        if self.total_rows == 0 && replaced.is_empty() {
             let _ = fs::remove_file(&self.temp_path);
-            // Count existing rows if file exists
-            if self.final_path.exists() {
-                let file = File::open(&self.final_path)?;
-                let reader = ParquetRecordBatchReaderBuilder::try_new(file)?.build()?;
-                let count: usize = reader.map(|b| b.map(|b| b.num_rows()).unwrap_or(0)).sum();
-                return Ok(count);
-            }
-            return Ok(0);
+            return count_existing_rows(&self.final_path);
         }
         
         // Merge temp file with existing data
-        let total = merge_parquet_files(&self.final_path, &self.temp_path, self.schema)?;
+        let total = merge_table_files(&self.final_path, &self.temp_path, self.schema, self.format, &self.provenance, replaced)?;
         Ok(total)
     }
 }
 
+/// Count the rows of an existing table file (0 if it does not exist)
+fn count_existing_rows(path: &Path) -> Result<usize> {
+    if !path.exists() {
+        return Ok(0);
+    }
+    let batches = read_table_batches(path, OutputFormat::from_path(path))?;
+    Ok(batches.iter().map(|b| b.num_rows()).sum())
+}
+
+// ============ Table Selection ============
+
+/// All dataset tables (parquet file stems) in write order
+pub const ALL_TABLES: [&str; 16] = [
+    "beatmaps",
+    "hit_objects",
+    "timing_points",
+    "storyboard_elements",
+    "storyboard_commands",
+    "slider_control_points",
+    "slider_data",
+    "breaks",
+    "events",
+    "extra_fields",
+    "custom_features",
+    "combo_colors",
+    "hit_samples",
+    "storyboard_loops",
+    "storyboard_triggers",
+    "assets",
+];
+
+/// Tables filled from parsed storyboards
+const STORYBOARD_TABLES: [&str; 4] = [
+    "storyboard_elements",
+    "storyboard_commands",
+    "storyboard_loops",
+    "storyboard_triggers",
+];
+
+/// Set of tables a build writes
+///
+/// Disabled tables get no writer, and the extraction feeding them is skipped.
+#[derive(Debug, Clone)]
+pub struct TableSelection {
+    enabled: HashSet<&'static str>,
+}
+
+impl TableSelection {
+    /// Build from `--only-tables` / `--skip-tables` lists (empty means no restriction)
+    pub fn new(only: &[String], skip: &[String]) -> Result<Self> {
+        for name in only.iter().chain(skip) {
+            if !ALL_TABLES.contains(&name.as_str()) {
+                bail!("Unknown table '{}' (expected one of: {})", name, ALL_TABLES.join(", "));
+            }
+        }
+
+        let enabled: HashSet<&'static str> = ALL_TABLES
+            .iter()
+            .copied()
+            .filter(|table| only.is_empty() || only.iter().any(|name| name == table))
+            .filter(|table| !skip.iter().any(|name| name == table))
+            .collect();
+
+        // Resuming reads processed folder ids from beatmaps.parquet
+        if !enabled.contains("beatmaps") {
+            bail!("The beatmaps table is always written: it records which folders have been processed");
+        }
+
+        Ok(Self { enabled })
+    }
+
+    pub fn contains(&self, table: &str) -> bool {
+        self.enabled.contains(table)
+    }
+
+    /// Whether storyboards need to be parsed at all
+    pub fn any_storyboard(&self) -> bool {
+        STORYBOARD_TABLES.iter().any(|table| self.contains(table))
+    }
+
+    /// Enabled tables in write order
+    pub fn names(&self) -> Vec<&'static str> {
+        ALL_TABLES.iter().copied().filter(|table| self.contains(table)).collect()
+    }
+}
+
 // ============ Schema Definitions ============
 
 pub fn beatmap_schema() -> Arc<Schema> {
@@ -191,6 +458,7 @@ pub fn beatmap_schema() -> Arc<Schema> {
         Field::new("version", DataType::Utf8, false),
         Field::new("source", DataType::Utf8, false),
         Field::new("tags", DataType::Utf8, false),
+        Field::new("tags_list", DataType::List(Arc::new(Field::new("item", DataType::Utf8, true))), false),
         Field::new("beatmap_id", DataType::Int32, false),
         Field::new("beatmap_set_id", DataType::Int32, false),
         // Difficulty section
@@ -204,6 +472,29 @@ pub fn beatmap_schema() -> Arc<Schema> {
         Field::new("background_file", DataType::Utf8, false),
         Field::new("audio_path", DataType::Utf8, false),
         Field::new("background_path", DataType::Utf8, false),
+        // Local difficulty (--compute-stars)
+        Field::new("stars_calc", DataType::Float64, true),
+        Field::new("max_pp", DataType::Float64, true),
+        Field::new("stars_suspicious", DataType::Boolean, true),
+        Field::new("has_offscreen_objects", DataType::Boolean, false),
+        Field::new("total_spinner_duration", DataType::Float64, false),
+        Field::new("is_virtual_audio", DataType::Boolean, false),
+        // Skin settings from [General]
+        Field::new("overlay_position", DataType::Utf8, false),
+        Field::new("skin_preference", DataType::Utf8, false),
+        Field::new("use_skin_sprites", DataType::Boolean, false),
+        Field::new("always_show_playfield", DataType::Boolean, false),
+        Field::new("skipped_too_large", DataType::Boolean, false),
+        // Mania key layout
+        Field::new("key_count", DataType::Int32, true),
+        Field::new("columns_out_of_range", DataType::Boolean, false),
+        Field::new("is_storyboard_only", DataType::Boolean, false),
+        // Original background size, before any --background-max-dim downscaling
+        Field::new("background_width", DataType::Int32, true),
+        Field::new("background_height", DataType::Int32, true),
+        // Declared audio/background names with their on-disk casing
+        Field::new("audio_file_resolved", DataType::Utf8, false),
+        Field::new("background_file_resolved", DataType::Utf8, false),
     ]))
 }
 
@@ -222,6 +513,11 @@ pub fn hit_object_schema() -> Arc<Schema> {
         Field::new("slides", DataType::Int32, true),
         Field::new("length", DataType::Float64, true),
         Field::new("end_time", DataType::Float64, true),
+        Field::new("duration", DataType::Float64, true),
+        Field::new("column", DataType::Int32, true),
+        Field::new("delta_time", DataType::Float64, true),
+        Field::new("delta_distance", DataType::Float64, true),
+        Field::new("required_spins", DataType::Int32, true),
     ]))
 }
 
@@ -237,6 +533,8 @@ pub fn timing_point_schema() -> Arc<Schema> {
         Field::new("kiai", DataType::Boolean, true),
         Field::new("sample_bank", DataType::Utf8, true),
         Field::new("sample_volume", DataType::Int32, true),
+        Field::new("sample_index", DataType::Int32, true),
+        Field::new("order_index", DataType::Int32, true),
     ]))
 }
 
@@ -255,6 +553,7 @@ pub fn storyboard_element_schema() -> Arc<Schema> {
         Field::new("frame_delay", DataType::Float64, true),
         Field::new("loop_type", DataType::Utf8, true),
         Field::new("is_embedded", DataType::Boolean, false),
+        Field::new("element_path_original", DataType::Utf8, true),
     ]))
 }
 
@@ -305,6 +604,38 @@ pub fn break_schema() -> Arc<Schema> {
     ]))
 }
 
+pub fn event_schema() -> Arc<Schema> {
+    Arc::new(Schema::new(vec![
+        Field::new("folder_id", DataType::Utf8, false),
+        Field::new("osu_file", DataType::Utf8, false),
+        Field::new("event_index", DataType::Int32, false),
+        Field::new("event_type", DataType::Utf8, false),
+        Field::new("start_time", DataType::Float64, true),
+        Field::new("params", DataType::Utf8, false),
+    ]))
+}
+
+pub fn extra_field_schema() -> Arc<Schema> {
+    Arc::new(Schema::new(vec![
+        Field::new("folder_id", DataType::Utf8, false),
+        Field::new("osu_file", DataType::Utf8, false),
+        Field::new("section", DataType::Utf8, false),
+        Field::new("field_index", DataType::Int32, false),
+        Field::new("key", DataType::Utf8, false),
+        Field::new("value", DataType::Utf8, false),
+    ]))
+}
+
+pub fn custom_feature_schema() -> Arc<Schema> {
+    Arc::new(Schema::new(vec![
+        Field::new("folder_id", DataType::Utf8, false),
+        Field::new("osu_file", DataType::Utf8, false),
+        Field::new("name", DataType::Utf8, false),
+        Field::new("value_f64", DataType::Float64, true),
+        Field::new("value_str", DataType::Utf8, true),
+    ]))
+}
+
 pub fn combo_color_schema() -> Arc<Schema> {
     Arc::new(Schema::new(vec![
         Field::new("folder_id", DataType::Utf8, false),
@@ -328,6 +659,8 @@ pub fn hit_sample_schema() -> Arc<Schema> {
         Field::new("bank", DataType::Utf8, false),
         Field::new("suffix", DataType::Utf8, true),
         Field::new("volume", DataType::Int32, false),
+        Field::new("scope", DataType::Utf8, false),
+        Field::new("node_index", DataType::Int32, true),
     ]))
 }
 
@@ -357,8 +690,30 @@ pub fn storyboard_trigger_schema() -> Arc<Schema> {
     ]))
 }
 
+pub fn asset_schema() -> Arc<Schema> {
+    Arc::new(Schema::new(vec![
+        Field::new("folder_id", DataType::Utf8, false),
+        Field::new("original_path", DataType::Utf8, false),
+        Field::new("stored_path", DataType::Utf8, false),
+        Field::new("kind", DataType::Utf8, false),
+        Field::new("size_bytes", DataType::Int64, false),
+    ]))
+}
+
 // ============ Batch Conversion Functions ============
 
+/// Build a ListArray<Utf8> with one (non-null) list per row
+fn string_list_array<'a>(lists: impl Iterator<Item = &'a Vec<String>>) -> ListArray {
+    let mut builder = ListBuilder::new(StringBuilder::new());
+    for list in lists {
+        for value in list {
+            builder.values().append_value(value);
+        }
+        builder.append(true);
+    }
+    builder.finish()
+}
+
 pub fn beatmap_rows_to_batch(rows: &[BeatmapRow]) -> Result<RecordBatch> {
     Ok(RecordBatch::try_new(
         beatmap_schema(),
@@ -396,6 +751,7 @@ pub fn beatmap_rows_to_batch(rows: &[BeatmapRow]) -> Result<RecordBatch> {
             Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.version.as_str()))),
             Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.source.as_str()))),
             Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.tags.as_str()))),
+            Arc::new(string_list_array(rows.iter().map(|r| &r.tags_list))),
             Arc::new(Int32Array::from_iter_values(rows.iter().map(|r| r.beatmap_id))),
             Arc::new(Int32Array::from_iter_values(rows.iter().map(|r| r.beatmap_set_id))),
             // Difficulty section
@@ -409,6 +765,29 @@ pub fn beatmap_rows_to_batch(rows: &[BeatmapRow]) -> Result<RecordBatch> {
             Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.background_file.as_str()))),
             Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.audio_path.as_str()))),
             Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.background_path.as_str()))),
+            // Local difficulty (--compute-stars)
+            Arc::new(Float64Array::from_iter(rows.iter().map(|r| r.stars_calc))),
+            Arc::new(Float64Array::from_iter(rows.iter().map(|r| r.max_pp))),
+            Arc::new(BooleanArray::from_iter(rows.iter().map(|r| r.stars_suspicious))),
+            Arc::new(BooleanArray::from_iter(rows.iter().map(|r| Some(r.has_offscreen_objects)))),
+            Arc::new(Float64Array::from_iter_values(rows.iter().map(|r| r.total_spinner_duration))),
+            Arc::new(BooleanArray::from_iter(rows.iter().map(|r| Some(r.is_virtual_audio)))),
+            // Skin settings from [General]
+            Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.overlay_position.as_str()))),
+            Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.skin_preference.as_str()))),
+            Arc::new(BooleanArray::from_iter(rows.iter().map(|r| Some(r.use_skin_sprites)))),
+            Arc::new(BooleanArray::from_iter(rows.iter().map(|r| Some(r.always_show_playfield)))),
+            Arc::new(BooleanArray::from_iter(rows.iter().map(|r| Some(r.skipped_too_large)))),
+            // Mania key layout
+            Arc::new(Int32Array::from_iter(rows.iter().map(|r| r.key_count))),
+            Arc::new(BooleanArray::from_iter(rows.iter().map(|r| Some(r.columns_out_of_range)))),
+            Arc::new(BooleanArray::from_iter(rows.iter().map(|r| Some(r.is_storyboard_only)))),
+            // Original background size
+            Arc::new(Int32Array::from_iter(rows.iter().map(|r| r.background_width))),
+            Arc::new(Int32Array::from_iter(rows.iter().map(|r| r.background_height))),
+            // Declared audio/background names with their on-disk casing
+            Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.audio_file_resolved.as_str()))),
+            Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.background_file_resolved.as_str()))),
         ],
     )?)
 }
@@ -430,6 +809,11 @@ pub fn hit_object_rows_to_batch(rows: &[HitObjectRow]) -> Result<RecordBatch> {
             Arc::new(Int32Array::from(rows.iter().map(|r| r.slides).collect::<Vec<_>>())),
             Arc::new(Float64Array::from(rows.iter().map(|r| r.length).collect::<Vec<_>>())),
             Arc::new(Float64Array::from(rows.iter().map(|r| r.end_time).collect::<Vec<_>>())),
+            Arc::new(Float64Array::from(rows.iter().map(|r| r.duration).collect::<Vec<_>>())),
+            Arc::new(Int32Array::from(rows.iter().map(|r| r.column).collect::<Vec<_>>())),
+            Arc::new(Float64Array::from(rows.iter().map(|r| r.delta_time).collect::<Vec<_>>())),
+            Arc::new(Float64Array::from(rows.iter().map(|r| r.delta_distance).collect::<Vec<_>>())),
+            Arc::new(Int32Array::from(rows.iter().map(|r| r.required_spins).collect::<Vec<_>>())),
         ],
     )?)
 }
@@ -448,6 +832,8 @@ pub fn timing_point_rows_to_batch(rows: &[TimingPointRow]) -> Result<RecordBatch
             Arc::new(BooleanArray::from(rows.iter().map(|r| r.kiai).collect::<Vec<_>>())),
             Arc::new(StringArray::from(rows.iter().map(|r| r.sample_bank.as_deref()).collect::<Vec<_>>())),
             Arc::new(Int32Array::from(rows.iter().map(|r| r.sample_volume).collect::<Vec<_>>())),
+            Arc::new(Int32Array::from(rows.iter().map(|r| r.sample_index).collect::<Vec<_>>())),
+            Arc::new(Int32Array::from(rows.iter().map(|r| r.order_index).collect::<Vec<_>>())),
         ],
     )?)
 }
@@ -469,6 +855,7 @@ pub fn storyboard_element_rows_to_batch(rows: &[StoryboardElementRow]) -> Result
             Arc::new(Float64Array::from(rows.iter().map(|r| r.frame_delay).collect::<Vec<_>>())),
             Arc::new(StringArray::from(rows.iter().map(|r| r.loop_type.as_deref()).collect::<Vec<_>>())),
             Arc::new(BooleanArray::from_iter(rows.iter().map(|r| Some(r.is_embedded)))),
+            Arc::new(StringArray::from(rows.iter().map(|r| r.element_path_original.as_deref()).collect::<Vec<_>>())),
         ],
     )?)
 }
@@ -532,6 +919,47 @@ pub fn break_rows_to_batch(rows: &[BreakRow]) -> Result<RecordBatch> {
     )?)
 }
 
+pub fn event_rows_to_batch(rows: &[EventRow]) -> Result<RecordBatch> {
+    Ok(RecordBatch::try_new(
+        event_schema(),
+        vec![
+            Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.folder_id.as_str()))),
+            Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.osu_file.as_str()))),
+            Arc::new(Int32Array::from_iter_values(rows.iter().map(|r| r.event_index))),
+            Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.event_type.as_str()))),
+            Arc::new(Float64Array::from(rows.iter().map(|r| r.start_time).collect::<Vec<_>>())),
+            Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.params.as_str()))),
+        ],
+    )?)
+}
+
+pub fn extra_field_rows_to_batch(rows: &[ExtraFieldRow]) -> Result<RecordBatch> {
+    Ok(RecordBatch::try_new(
+        extra_field_schema(),
+        vec![
+            Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.folder_id.as_str()))),
+            Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.osu_file.as_str()))),
+            Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.section.as_str()))),
+            Arc::new(Int32Array::from_iter_values(rows.iter().map(|r| r.field_index))),
+            Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.key.as_str()))),
+            Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.value.as_str()))),
+        ],
+    )?)
+}
+
+pub fn custom_feature_rows_to_batch(rows: &[CustomFeatureRow]) -> Result<RecordBatch> {
+    Ok(RecordBatch::try_new(
+        custom_feature_schema(),
+        vec![
+            Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.folder_id.as_str()))),
+            Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.osu_file.as_str()))),
+            Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.name.as_str()))),
+            Arc::new(Float64Array::from(rows.iter().map(|r| r.value_f64).collect::<Vec<_>>())),
+            Arc::new(StringArray::from(rows.iter().map(|r| r.value_str.as_deref()).collect::<Vec<_>>())),
+        ],
+    )?)
+}
+
 pub fn combo_color_rows_to_batch(rows: &[ComboColorRow]) -> Result<RecordBatch> {
     Ok(RecordBatch::try_new(
         combo_color_schema(),
@@ -560,6 +988,8 @@ pub fn hit_sample_rows_to_batch(rows: &[HitSampleRow]) -> Result<RecordBatch> {
             Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.bank.as_str()))),
             Arc::new(StringArray::from(rows.iter().map(|r| r.suffix.as_deref()).collect::<Vec<_>>())),
             Arc::new(Int32Array::from_iter_values(rows.iter().map(|r| r.volume))),
+            Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.scope))),
+            Arc::new(Int32Array::from(rows.iter().map(|r| r.node_index).collect::<Vec<_>>())),
         ],
     )?)
 }
@@ -596,6 +1026,19 @@ pub fn storyboard_trigger_rows_to_batch(rows: &[StoryboardTriggerRow]) -> Result
     )?)
 }
 
+pub fn asset_rows_to_batch(rows: &[AssetRow]) -> Result<RecordBatch> {
+    Ok(RecordBatch::try_new(
+        asset_schema(),
+        vec![
+            Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.folder_id.as_str()))),
+            Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.original_path.as_str()))),
+            Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.stored_path.as_str()))),
+            Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.kind.as_str()))),
+            Arc::new(Int64Array::from_iter_values(rows.iter().map(|r| r.size_bytes))),
+        ],
+    )?)
+}
+
 // ============ Convenience Type Aliases ============
 
 pub type BeatmapWriter = BatchWriter<BeatmapRow, fn(&[BeatmapRow]) -> Result<RecordBatch>>;
@@ -606,111 +1049,392 @@ pub type StoryboardCommandWriter = BatchWriter<StoryboardCommandRow, fn(&[Storyb
 pub type SliderControlPointWriter = BatchWriter<SliderControlPointRow, fn(&[SliderControlPointRow]) -> Result<RecordBatch>>;
 pub type SliderDataWriter = BatchWriter<SliderDataRow, fn(&[SliderDataRow]) -> Result<RecordBatch>>;
 pub type BreakWriter = BatchWriter<BreakRow, fn(&[BreakRow]) -> Result<RecordBatch>>;
+pub type EventWriter = BatchWriter<EventRow, fn(&[EventRow]) -> Result<RecordBatch>>;
+pub type ExtraFieldWriter = BatchWriter<ExtraFieldRow, fn(&[ExtraFieldRow]) -> Result<RecordBatch>>;
+pub type CustomFeatureWriter = BatchWriter<CustomFeatureRow, fn(&[CustomFeatureRow]) -> Result<RecordBatch>>;
 pub type ComboColorWriter = BatchWriter<ComboColorRow, fn(&[ComboColorRow]) -> Result<RecordBatch>>;
 pub type HitSampleWriter = BatchWriter<HitSampleRow, fn(&[HitSampleRow]) -> Result<RecordBatch>>;
 pub type StoryboardLoopWriter = BatchWriter<StoryboardLoopRow, fn(&[StoryboardLoopRow]) -> Result<RecordBatch>>;
 pub type StoryboardTriggerWriter = BatchWriter<StoryboardTriggerRow, fn(&[StoryboardTriggerRow]) -> Result<RecordBatch>>;
+pub type AssetWriter = BatchWriter<AssetRow, fn(&[AssetRow]) -> Result<RecordBatch>>;
+
+// ============ Per-Folder Row Buffers ============
+
+/// In-memory row buffer with the same `write` interface as BatchWriter
+pub struct RowBuffer<T> {
+    rows: Vec<T>,
+}
+
+impl<T> Default for RowBuffer<T> {
+    fn default() -> Self {
+        Self { rows: Vec::new() }
+    }
+}
+
+impl<T> RowBuffer<T> {
+    pub fn write(&mut self, row: T) -> Result<()> {
+        self.rows.push(row);
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.rows.iter()
+    }
+}
+
+/// All rows produced by a single folder, committed to DatasetWriters as a unit
+/// so a failed or timed-out folder never leaves partial rows behind
+pub struct FolderRows {
+    pub beatmaps: RowBuffer<BeatmapRow>,
+    pub hit_objects: RowBuffer<HitObjectRow>,
+    pub timing_points: RowBuffer<TimingPointRow>,
+    pub storyboard_elements: RowBuffer<StoryboardElementRow>,
+    pub storyboard_commands: RowBuffer<StoryboardCommandRow>,
+    pub slider_control_points: RowBuffer<SliderControlPointRow>,
+    pub slider_data: RowBuffer<SliderDataRow>,
+    pub breaks: RowBuffer<BreakRow>,
+    pub events: RowBuffer<EventRow>,
+    pub extra_fields: RowBuffer<ExtraFieldRow>,
+    pub custom_features: RowBuffer<CustomFeatureRow>,
+    pub combo_colors: RowBuffer<ComboColorRow>,
+    pub hit_samples: RowBuffer<HitSampleRow>,
+    pub storyboard_loops: RowBuffer<StoryboardLoopRow>,
+    pub storyboard_triggers: RowBuffer<StoryboardTriggerRow>,
+    pub assets: RowBuffer<AssetRow>,
+}
+
+impl Default for FolderRows {
+    fn default() -> Self {
+        Self {
+            beatmaps: RowBuffer::default(),
+            hit_objects: RowBuffer::default(),
+            timing_points: RowBuffer::default(),
+            storyboard_elements: RowBuffer::default(),
+            storyboard_commands: RowBuffer::default(),
+            slider_control_points: RowBuffer::default(),
+            slider_data: RowBuffer::default(),
+            breaks: RowBuffer::default(),
+            events: RowBuffer::default(),
+            extra_fields: RowBuffer::default(),
+            custom_features: RowBuffer::default(),
+            combo_colors: RowBuffer::default(),
+            hit_samples: RowBuffer::default(),
+            storyboard_loops: RowBuffer::default(),
+            storyboard_triggers: RowBuffer::default(),
+            assets: RowBuffer::default(),
+        }
+    }
+}
+
+impl FolderRows {
+    /// Row counts keyed by table name, in write order (for --folder reports)
+    pub fn table_counts(&self) -> Vec<(&'static str, usize)> {
+        vec![
+            ("beatmaps", self.beatmaps.len()),
+            ("hit_objects", self.hit_objects.len()),
+            ("timing_points", self.timing_points.len()),
+            ("storyboard_elements", self.storyboard_elements.len()),
+            ("storyboard_commands", self.storyboard_commands.len()),
+            ("slider_control_points", self.slider_control_points.len()),
+            ("slider_data", self.slider_data.len()),
+            ("breaks", self.breaks.len()),
+            ("events", self.events.len()),
+            ("extra_fields", self.extra_fields.len()),
+            ("custom_features", self.custom_features.len()),
+            ("combo_colors", self.combo_colors.len()),
+            ("hit_samples", self.hit_samples.len()),
+            ("storyboard_loops", self.storyboard_loops.len()),
+            ("storyboard_triggers", self.storyboard_triggers.len()),
+            ("assets", self.assets.len()),
+        ]
+    }
+}
 
 /// Create all batch writers for the dataset
+///
+/// Tables excluded by the `TableSelection` have no writer; their rows are dropped.
 pub struct DatasetWriters {
-    pub beatmaps: BeatmapWriter,
-    pub hit_objects: HitObjectWriter,
-    pub timing_points: TimingPointWriter,
-    pub storyboard_elements: StoryboardElementWriter,
-    pub storyboard_commands: StoryboardCommandWriter,
-    pub slider_control_points: SliderControlPointWriter,
-    pub slider_data: SliderDataWriter,
-    pub breaks: BreakWriter,
-    pub combo_colors: ComboColorWriter,
-    pub hit_samples: HitSampleWriter,
-    pub storyboard_loops: StoryboardLoopWriter,
-    pub storyboard_triggers: StoryboardTriggerWriter,
+    output_dir: PathBuf,
+    format: OutputFormat,
+    /// Folders whose existing rows are dropped on close (`--folder --replace`)
+    replaced: HashSet<String>,
+    pub beatmaps: Option<BeatmapWriter>,
+    pub hit_objects: Option<HitObjectWriter>,
+    pub timing_points: Option<TimingPointWriter>,
+    pub storyboard_elements: Option<StoryboardElementWriter>,
+    pub storyboard_commands: Option<StoryboardCommandWriter>,
+    pub slider_control_points: Option<SliderControlPointWriter>,
+    pub slider_data: Option<SliderDataWriter>,
+    pub breaks: Option<BreakWriter>,
+    pub events: Option<EventWriter>,
+    pub extra_fields: Option<ExtraFieldWriter>,
+    pub custom_features: Option<CustomFeatureWriter>,
+    pub combo_colors: Option<ComboColorWriter>,
+    pub hit_samples: Option<HitSampleWriter>,
+    pub storyboard_loops: Option<StoryboardLoopWriter>,
+    pub storyboard_triggers: Option<StoryboardTriggerWriter>,
+    pub assets: Option<AssetWriter>,
 }
 
 impl DatasetWriters {
-    pub fn new(output_dir: &Path) -> Result<Self> {
+    pub fn new(
+        output_dir: &Path,
+        tables: &TableSelection,
+        format: OutputFormat,
+        provenance: &Provenance,
+    ) -> Result<Self> {
         Ok(Self {
-            beatmaps: BatchWriter::new(
-                &output_dir.join("beatmaps.parquet"),
+            output_dir: output_dir.to_path_buf(),
+            format,
+            replaced: HashSet::new(),
+            beatmaps: open_writer(
+                output_dir,
+                format,
+                tables,
+                provenance,
+                "beatmaps",
                 beatmap_schema(),
                 beatmap_rows_to_batch as fn(&[BeatmapRow]) -> Result<RecordBatch>,
             )?,
-            hit_objects: BatchWriter::new(
-                &output_dir.join("hit_objects.parquet"),
+            hit_objects: open_writer(
+                output_dir,
+                format,
+                tables,
+                provenance,
+                "hit_objects",
                 hit_object_schema(),
                 hit_object_rows_to_batch as fn(&[HitObjectRow]) -> Result<RecordBatch>,
             )?,
-            timing_points: BatchWriter::new(
-                &output_dir.join("timing_points.parquet"),
+            timing_points: open_writer(
+                output_dir,
+                format,
+                tables,
+                provenance,
+                "timing_points",
                 timing_point_schema(),
                 timing_point_rows_to_batch as fn(&[TimingPointRow]) -> Result<RecordBatch>,
             )?,
-            storyboard_elements: BatchWriter::new(
-                &output_dir.join("storyboard_elements.parquet"),
+            storyboard_elements: open_writer(
+                output_dir,
+                format,
+                tables,
+                provenance,
+                "storyboard_elements",
                 storyboard_element_schema(),
                 storyboard_element_rows_to_batch as fn(&[StoryboardElementRow]) -> Result<RecordBatch>,
             )?,
-            storyboard_commands: BatchWriter::new(
-                &output_dir.join("storyboard_commands.parquet"),
+            storyboard_commands: open_writer(
+                output_dir,
+                format,
+                tables,
+                provenance,
+                "storyboard_commands",
                 storyboard_command_schema(),
                 storyboard_command_rows_to_batch as fn(&[StoryboardCommandRow]) -> Result<RecordBatch>,
             )?,
-            slider_control_points: BatchWriter::new(
-                &output_dir.join("slider_control_points.parquet"),
+            slider_control_points: open_writer(
+                output_dir,
+                format,
+                tables,
+                provenance,
+                "slider_control_points",
                 slider_control_point_schema(),
                 slider_control_point_rows_to_batch as fn(&[SliderControlPointRow]) -> Result<RecordBatch>,
             )?,
-            slider_data: BatchWriter::new(
-                &output_dir.join("slider_data.parquet"),
+            slider_data: open_writer(
+                output_dir,
+                format,
+                tables,
+                provenance,
+                "slider_data",
                 slider_data_schema(),
                 slider_data_rows_to_batch as fn(&[SliderDataRow]) -> Result<RecordBatch>,
             )?,
-            breaks: BatchWriter::new(
-                &output_dir.join("breaks.parquet"),
+            breaks: open_writer(
+                output_dir,
+                format,
+                tables,
+                provenance,
+                "breaks",
                 break_schema(),
                 break_rows_to_batch as fn(&[BreakRow]) -> Result<RecordBatch>,
             )?,
-            combo_colors: BatchWriter::new(
-                &output_dir.join("combo_colors.parquet"),
+            events: open_writer(
+                output_dir,
+                format,
+                tables,
+                provenance,
+                "events",
+                event_schema(),
+                event_rows_to_batch as fn(&[EventRow]) -> Result<RecordBatch>,
+            )?,
+            extra_fields: open_writer(
+                output_dir,
+                format,
+                tables,
+                provenance,
+                "extra_fields",
+                extra_field_schema(),
+                extra_field_rows_to_batch as fn(&[ExtraFieldRow]) -> Result<RecordBatch>,
+            )?,
+            custom_features: open_writer(
+                output_dir,
+                format,
+                tables,
+                provenance,
+                "custom_features",
+                custom_feature_schema(),
+                custom_feature_rows_to_batch as fn(&[CustomFeatureRow]) -> Result<RecordBatch>,
+            )?,
+            combo_colors: open_writer(
+                output_dir,
+                format,
+                tables,
+                provenance,
+                "combo_colors",
                 combo_color_schema(),
                 combo_color_rows_to_batch as fn(&[ComboColorRow]) -> Result<RecordBatch>,
             )?,
-            hit_samples: BatchWriter::new(
-                &output_dir.join("hit_samples.parquet"),
+            hit_samples: open_writer(
+                output_dir,
+                format,
+                tables,
+                provenance,
+                "hit_samples",
                 hit_sample_schema(),
                 hit_sample_rows_to_batch as fn(&[HitSampleRow]) -> Result<RecordBatch>,
             )?,
-            storyboard_loops: BatchWriter::new(
-                &output_dir.join("storyboard_loops.parquet"),
+            storyboard_loops: open_writer(
+                output_dir,
+                format,
+                tables,
+                provenance,
+                "storyboard_loops",
                 storyboard_loop_schema(),
                 storyboard_loop_rows_to_batch as fn(&[StoryboardLoopRow]) -> Result<RecordBatch>,
             )?,
-            storyboard_triggers: BatchWriter::new(
-                &output_dir.join("storyboard_triggers.parquet"),
+            storyboard_triggers: open_writer(
+                output_dir,
+                format,
+                tables,
+                provenance,
+                "storyboard_triggers",
                 storyboard_trigger_schema(),
                 storyboard_trigger_rows_to_batch as fn(&[StoryboardTriggerRow]) -> Result<RecordBatch>,
             )?,
+            assets: open_writer(
+                output_dir,
+                format,
+                tables,
+                provenance,
+                "assets",
+                asset_schema(),
+                asset_rows_to_batch as fn(&[AssetRow]) -> Result<RecordBatch>,
+            )?,
         })
     }
 
+    /// Write the buffered rows of one processed folder
+    pub fn write_folder(&mut self, rows: FolderRows) -> Result<()> {
+        write_rows(&mut self.beatmaps, rows.beatmaps)?;
+        write_rows(&mut self.hit_objects, rows.hit_objects)?;
+        write_rows(&mut self.timing_points, rows.timing_points)?;
+        write_rows(&mut self.storyboard_elements, rows.storyboard_elements)?;
+        write_rows(&mut self.storyboard_commands, rows.storyboard_commands)?;
+        write_rows(&mut self.slider_control_points, rows.slider_control_points)?;
+        write_rows(&mut self.slider_data, rows.slider_data)?;
+        write_rows(&mut self.breaks, rows.breaks)?;
+        write_rows(&mut self.events, rows.events)?;
+        write_rows(&mut self.extra_fields, rows.extra_fields)?;
+        write_rows(&mut self.custom_features, rows.custom_features)?;
+        write_rows(&mut self.combo_colors, rows.combo_colors)?;
+        write_rows(&mut self.hit_samples, rows.hit_samples)?;
+        write_rows(&mut self.storyboard_loops, rows.storyboard_loops)?;
+        write_rows(&mut self.storyboard_triggers, rows.storyboard_triggers)?;
+        write_rows(&mut self.assets, rows.assets)?;
+        Ok(())
+    }
+
+    /// Drop the rows `folder_id` already has in the written tables when closing
+    ///
+    /// Call it once the folder's new rows were written, so a folder that fails
+    /// to rebuild keeps its old rows. Disabled tables keep theirs either way.
+    pub fn replace_folder(&mut self, folder_id: &str) {
+        self.replaced.insert(folder_id.to_string());
+    }
+
+    /// Close all writers; disabled tables report the rows already on disk
     pub fn close(self) -> Result<DatasetStats> {
+        let dir = &self.output_dir;
+        let format = self.format;
+        let replaced = &self.replaced;
         Ok(DatasetStats {
-            beatmaps: self.beatmaps.close()?,
-            hit_objects: self.hit_objects.close()?,
-            timing_points: self.timing_points.close()?,
-            storyboard_elements: self.storyboard_elements.close()?,
-            storyboard_commands: self.storyboard_commands.close()?,
-            slider_control_points: self.slider_control_points.close()?,
-            slider_data: self.slider_data.close()?,
-            breaks: self.breaks.close()?,
-            combo_colors: self.combo_colors.close()?,
-            hit_samples: self.hit_samples.close()?,
-            storyboard_loops: self.storyboard_loops.close()?,
-            storyboard_triggers: self.storyboard_triggers.close()?,
+            beatmaps: close_writer(replaced, self.beatmaps, dir, format, "beatmaps")?,
+            hit_objects: close_writer(replaced, self.hit_objects, dir, format, "hit_objects")?,
+            timing_points: close_writer(replaced, self.timing_points, dir, format, "timing_points")?,
+            storyboard_elements: close_writer(replaced, self.storyboard_elements, dir, format, "storyboard_elements")?,
+            storyboard_commands: close_writer(replaced, self.storyboard_commands, dir, format, "storyboard_commands")?,
+            slider_control_points: close_writer(replaced, self.slider_control_points, dir, format, "slider_control_points")?,
+            slider_data: close_writer(replaced, self.slider_data, dir, format, "slider_data")?,
+            breaks: close_writer(replaced, self.breaks, dir, format, "breaks")?,
+            events: close_writer(replaced, self.events, dir, format, "events")?,
+            extra_fields: close_writer(replaced, self.extra_fields, dir, format, "extra_fields")?,
+            custom_features: close_writer(replaced, self.custom_features, dir, format, "custom_features")?,
+            combo_colors: close_writer(replaced, self.combo_colors, dir, format, "combo_colors")?,
+            hit_samples: close_writer(replaced, self.hit_samples, dir, format, "hit_samples")?,
+            storyboard_loops: close_writer(replaced, self.storyboard_loops, dir, format, "storyboard_loops")?,
+            storyboard_triggers: close_writer(replaced, self.storyboard_triggers, dir, format, "storyboard_triggers")?,
+            assets: close_writer(replaced, self.assets, dir, format, "assets")?,
         })
     }
 }
 
+/// Create the writer for `table` if it is enabled
+fn open_writer<T, F: Fn(&[T]) -> Result<RecordBatch>>(
+    output_dir: &Path,
+    format: OutputFormat,
+    tables: &TableSelection,
+    provenance: &Provenance,
+    table: &str,
+    schema: Arc<Schema>,
+    to_batch: F,
+) -> Result<Option<BatchWriter<T, F>>> {
+    if !tables.contains(table) {
+        return Ok(None);
+    }
+    let path = format.table_path(output_dir, table);
+    Ok(Some(BatchWriter::new(&path, schema, to_batch, provenance.clone())?))
+}
+
+fn write_rows<T, F: Fn(&[T]) -> Result<RecordBatch>>(
+    writer: &mut Option<BatchWriter<T, F>>,
+    rows: RowBuffer<T>,
+) -> Result<()> {
+    if let Some(writer) = writer {
+        for row in rows.rows {
+            writer.write(row)?;
+        }
+    }
+    Ok(())
+}
+
+fn close_writer<T, F: Fn(&[T]) -> Result<RecordBatch>>(
+    replaced: &HashSet<String>,
+    writer: Option<BatchWriter<T, F>>,
+    output_dir: &Path,
+    format: OutputFormat,
+    table: &str,
+) -> Result<usize> {
+    match writer {
+        Some(writer) => writer.close(replaced),
+        None => count_existing_rows(&format.table_path(output_dir, table)),
+    }
+}
+
 pub struct DatasetStats {
     pub beatmaps: usize,
     pub hit_objects: usize,
@@ -720,8 +1444,116 @@ pub struct DatasetStats {
     pub slider_control_points: usize,
     pub slider_data: usize,
     pub breaks: usize,
+    pub events: usize,
+    pub extra_fields: usize,
+    pub custom_features: usize,
     pub combo_colors: usize,
     pub hit_samples: usize,
     pub storyboard_loops: usize,
     pub storyboard_triggers: usize,
+    pub assets: usize,
+}
+
+impl DatasetStats {
+    /// Row counts keyed by table name (parquet file stem)
+    pub fn tables(&self) -> Vec<(&'static str, usize)> {
+        vec![
+            ("beatmaps", self.beatmaps),
+            ("hit_objects", self.hit_objects),
+            ("timing_points", self.timing_points),
+            ("storyboard_elements", self.storyboard_elements),
+            ("storyboard_commands", self.storyboard_commands),
+            ("slider_control_points", self.slider_control_points),
+            ("slider_data", self.slider_data),
+            ("breaks", self.breaks),
+            ("events", self.events),
+            ("extra_fields", self.extra_fields),
+            ("custom_features", self.custom_features),
+            ("combo_colors", self.combo_colors),
+            ("hit_samples", self.hit_samples),
+            ("storyboard_loops", self.storyboard_loops),
+            ("storyboard_triggers", self.storyboard_triggers),
+            ("assets", self.assets),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema(columns: &[&str]) -> Arc<Schema> {
+        Arc::new(Schema::new(
+            columns.iter().map(|name| Field::new(*name, DataType::Int32, false)).collect::<Vec<_>>(),
+        ))
+    }
+
+    /// Write a table file with one row per value in every column
+    fn write_table(path: &Path, schema: Arc<Schema>, values: &[i32]) {
+        let columns: Vec<ArrayRef> = schema
+            .fields()
+            .iter()
+            .map(|_| Arc::new(Int32Array::from(values.to_vec())) as ArrayRef)
+            .collect();
+        let batch = RecordBatch::try_new(schema.clone(), columns).unwrap();
+        let mut writer = TableWriter::create(path, schema, OutputFormat::Parquet, &Provenance::new()).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+    }
+
+    #[test]
+    fn merge_appends_rows_with_matching_columns() {
+        let dir = crate::test_dir();
+        let (existing, temp) = (dir.join("t.parquet"), dir.join("t.parquet.tmp"));
+        write_table(&existing, schema(&["a", "b"]), &[1, 2]);
+        write_table(&temp, schema(&["a", "b"]), &[3]);
+
+        let total = merge_table_files(&existing, &temp, schema(&["a", "b"]), OutputFormat::Parquet, &Provenance::new(), &HashSet::new()).unwrap();
+        assert_eq!(total, 3);
+        assert!(!temp.exists());
+        assert_eq!(count_existing_rows(&existing).unwrap(), 3);
+    }
+
+    #[test]
+    fn merge_refuses_a_file_with_other_columns() {
+        let dir = crate::test_dir();
+        let (existing, temp) = (dir.join("t.parquet"), dir.join("t.parquet.tmp"));
+        write_table(&existing, schema(&["a", "old"]), &[1, 2]);
+        write_table(&temp, schema(&["a", "new"]), &[3]);
+
+        let err = merge_table_files(&existing, &temp, schema(&["a", "new"]), OutputFormat::Parquet, &Provenance::new(), &HashSet::new())
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("\"new\"") && err.contains("\"old\""), "{}", err);
+        // The existing file is left as it was
+        assert_eq!(count_existing_rows(&existing).unwrap(), 2);
+    }
+
+    #[test]
+    fn merge_drops_the_existing_rows_of_replaced_folders() {
+        let dir = crate::test_dir();
+        let (existing, temp) = (dir.join("t.parquet"), dir.join("t.parquet.tmp"));
+        let schema = Arc::new(Schema::new(vec![Field::new("folder_id", DataType::Utf8, false)]));
+        let write = |path: &Path, ids: &[&str]| {
+            let batch = RecordBatch::try_new(schema.clone(), vec![Arc::new(StringArray::from(ids.to_vec())) as ArrayRef]).unwrap();
+            let mut writer = TableWriter::create(path, schema.clone(), OutputFormat::Parquet, &Provenance::new()).unwrap();
+            writer.write(&batch).unwrap();
+            writer.close().unwrap();
+        };
+        write(&existing, &["a", "b", "a"]);
+        write(&temp, &["a"]);
+
+        let replaced = HashSet::from(["a".to_string()]);
+        let total = merge_table_files(&existing, &temp, schema.clone(), OutputFormat::Parquet, &Provenance::new(), &replaced).unwrap();
+        assert_eq!(total, 2);
+        let ids: Vec<String> = read_table_batches(&existing, OutputFormat::Parquet)
+            .unwrap()
+            .iter()
+            .flat_map(|batch| {
+                let column = batch.column(0).as_any().downcast_ref::<StringArray>().unwrap();
+                column.iter().map(|id| id.unwrap().to_string()).collect::<Vec<_>>()
+            })
+            .collect();
+        assert_eq!(ids, vec!["b", "a"]);
+    }
 }
@@ -0,0 +1,57 @@
+//! User-defined per-difficulty features (`--custom-features`)
+//!
+//! Each registered [`FeatureExtractor`] sees every parsed difficulty and returns
+//! named values, which are stored one per row in `custom_features`. New features
+//! need no schema change: implement the trait and add the extractor to
+//! [`EXTRACTORS`].
+
+use rosu_map::section::hit_objects::{HitObject, HitObjectKind};
+use rosu_map::Beatmap;
+
+/// Value of one custom feature; numbers land in `value_f64`, text in `value_str`
+#[derive(Debug, Clone, PartialEq)]
+pub enum FeatureValue {
+    Number(f64),
+    Text(String),
+}
+
+/// Computes named features of a difficulty
+///
+/// Extractors run on the builder's folder threads, so they must be `Sync`.
+/// Returning no values for a difficulty writes no rows for it.
+pub trait FeatureExtractor: Sync {
+    fn on_beatmap(&self, beatmap: &Beatmap) -> Vec<(String, FeatureValue)>;
+}
+
+/// Extractors run by `--custom-features`, in order
+pub static EXTRACTORS: &[&dyn FeatureExtractor] = &[&AverageObjectDistance];
+
+/// Mean distance in osu!pixels between the positions of consecutive objects
+///
+/// Holds have no position and are skipped; difficulties with fewer than two
+/// positioned objects get no value.
+pub struct AverageObjectDistance;
+
+impl FeatureExtractor for AverageObjectDistance {
+    fn on_beatmap(&self, beatmap: &Beatmap) -> Vec<(String, FeatureValue)> {
+        let positions: Vec<(f32, f32)> = beatmap.hit_objects.iter().filter_map(position).collect();
+        if positions.len() < 2 {
+            return Vec::new();
+        }
+        let total: f64 = positions
+            .windows(2)
+            .map(|pair| ((pair[1].0 - pair[0].0) as f64).hypot((pair[1].1 - pair[0].1) as f64))
+            .sum();
+        let mean = total / (positions.len() - 1) as f64;
+        vec![("avg_object_distance".to_string(), FeatureValue::Number(mean))]
+    }
+}
+
+fn position(ho: &HitObject) -> Option<(f32, f32)> {
+    match &ho.kind {
+        HitObjectKind::Circle(c) => Some((c.pos.x, c.pos.y)),
+        HitObjectKind::Slider(s) => Some((s.pos.x, s.pos.y)),
+        HitObjectKind::Spinner(sp) => Some((sp.pos.x, sp.pos.y)),
+        HitObjectKind::Hold(_) => None,
+    }
+}
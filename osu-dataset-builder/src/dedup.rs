@@ -0,0 +1,463 @@
+//! Duplicate difficulty detection
+//!
+//! Scraped corpora contain re-uploads of the same map under different folders.
+//! Each difficulty's hit-object sequence is normalized and hashed; difficulties
+//! with identical hashes form a duplicate group with one representative.
+
+use anyhow::{Context, Result};
+use arrow::array::*;
+use arrow::compute::filter_record_batch;
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::ArrowWriter;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{self, File};
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::batch_writer::{provenance_key_values, writer_properties_builder, Provenance, ALL_TABLES};
+use crate::manifest;
+
+/// Output filename of the duplicate analysis
+pub const DUPLICATE_GROUPS_FILE: &str = "duplicate_groups.parquet";
+
+/// (folder_id, osu_file)
+type DifficultyKey = (String, String);
+
+/// Running hash of one difficulty's hit objects
+struct SequenceHash {
+    hash: u64,
+    object_count: i32,
+    first_time: f64,
+}
+
+/// One difficulty that has at least one identical copy elsewhere in the dataset
+pub struct DuplicateMember {
+    pub group_id: i32,
+    pub content_hash: u64,
+    pub folder_id: String,
+    pub osu_file: String,
+    pub object_count: i32,
+    pub is_representative: bool,
+}
+
+/// 64-bit FNV-1a, stable across runs and platforms unlike `DefaultHasher`
+fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+
+/// Hash every difficulty in hit_objects.parquet and group identical ones
+///
+/// Normalization: times are taken relative to the difficulty's first object and
+/// rounded to whole ms, end times become durations, and slider lengths are rounded
+/// to 0.01 osupixels, so a re-upload with a shifted offset still matches.
+/// Within a group the representative is the lowest (folder_id, osu_file).
+pub fn find_duplicate_groups(dataset_dir: &Path) -> Result<Vec<DuplicateMember>> {
+    let path = dataset_dir.join("hit_objects.parquet");
+    let file = File::open(&path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let reader = ParquetRecordBatchReaderBuilder::try_new(file)?.with_batch_size(8192).build()?;
+
+    let mut hashes: HashMap<DifficultyKey, SequenceHash> = HashMap::new();
+    // Rows of a difficulty are contiguous, so hash into a local and only touch the map on a change
+    let mut current: Option<(DifficultyKey, SequenceHash)> = None;
+
+    for batch in reader {
+        let batch = batch?;
+        let folder_id = string_column(&batch, "folder_id")?;
+        let osu_file = string_column(&batch, "osu_file")?;
+        let start_time = f64_column(&batch, "start_time")?;
+        let object_type = string_column(&batch, "object_type")?;
+        let pos_x = i32_column(&batch, "pos_x")?;
+        let pos_y = i32_column(&batch, "pos_y")?;
+        let new_combo = bool_column(&batch, "new_combo")?;
+        let curve_type = string_column(&batch, "curve_type")?;
+        let slides = i32_column(&batch, "slides")?;
+        let length = f64_column(&batch, "length")?;
+        let end_time = f64_column(&batch, "end_time")?;
+
+        for i in 0..batch.num_rows() {
+            let time = start_time.value(i);
+            let same_difficulty = current
+                .as_ref()
+                .is_some_and(|((folder, file), _)| folder == folder_id.value(i) && file == osu_file.value(i));
+            if !same_difficulty {
+                if let Some((key, sequence)) = current.take() {
+                    hashes.insert(key, sequence);
+                }
+                let key = (folder_id.value(i).to_string(), osu_file.value(i).to_string());
+                let sequence = hashes.remove(&key).unwrap_or(SequenceHash {
+                    hash: FNV_OFFSET_BASIS,
+                    object_count: 0,
+                    first_time: time,
+                });
+                current = Some((key, sequence));
+            }
+            let (_, sequence) = current.as_mut().expect("current difficulty set above");
+
+            let mut hash = sequence.hash;
+            hash = fnv1a(hash, &((time - sequence.first_time).round() as i64).to_le_bytes());
+            hash = fnv1a(hash, object_type.value(i).as_bytes());
+            hash = fnv1a(hash, &optional_i32(pos_x, i).to_le_bytes());
+            hash = fnv1a(hash, &optional_i32(pos_y, i).to_le_bytes());
+            hash = fnv1a(hash, &[new_combo.value(i) as u8]);
+            if !curve_type.is_null(i) {
+                hash = fnv1a(hash, curve_type.value(i).as_bytes());
+            }
+            hash = fnv1a(hash, &optional_i32(slides, i).to_le_bytes());
+            if !length.is_null(i) {
+                hash = fnv1a(hash, &((length.value(i) * 100.0).round() as i64).to_le_bytes());
+            }
+            if !end_time.is_null(i) {
+                hash = fnv1a(hash, &((end_time.value(i) - time).round() as i64).to_le_bytes());
+            }
+            // Object separator so bytes can't shift between adjacent objects
+            hash = fnv1a(hash, &[0xff]);
+
+            sequence.hash = hash;
+            sequence.object_count += 1;
+        }
+    }
+    if let Some((key, sequence)) = current {
+        hashes.insert(key, sequence);
+    }
+
+    let mut by_hash: HashMap<u64, Vec<(DifficultyKey, i32)>> = HashMap::new();
+    for (key, sequence) in hashes {
+        // The object count is part of the key so a hash collision needs equal lengths too
+        by_hash
+            .entry(fnv1a(sequence.hash, &sequence.object_count.to_le_bytes()))
+            .or_default()
+            .push((key, sequence.object_count));
+    }
+
+    let mut groups: Vec<(u64, Vec<(DifficultyKey, i32)>)> = by_hash
+        .into_iter()
+        .filter(|(_, members)| members.len() > 1)
+        .collect();
+    for (_, members) in &mut groups {
+        members.sort();
+    }
+    // Deterministic group ids: ordered by representative
+    groups.sort_by(|a, b| a.1[0].0.cmp(&b.1[0].0));
+
+    let mut result = Vec::new();
+    for (group_id, (content_hash, members)) in groups.into_iter().enumerate() {
+        for (idx, ((folder_id, osu_file), object_count)) in members.into_iter().enumerate() {
+            result.push(DuplicateMember {
+                group_id: group_id as i32,
+                content_hash,
+                folder_id,
+                osu_file,
+                object_count,
+                is_representative: idx == 0,
+            });
+        }
+    }
+    Ok(result)
+}
+
+pub fn duplicate_group_schema() -> Arc<Schema> {
+    Arc::new(Schema::new(vec![
+        Field::new("group_id", DataType::Int32, false),
+        Field::new("content_hash", DataType::Utf8, false),
+        Field::new("folder_id", DataType::Utf8, false),
+        Field::new("osu_file", DataType::Utf8, false),
+        Field::new("object_count", DataType::Int32, false),
+        Field::new("is_representative", DataType::Boolean, false),
+    ]))
+}
+
+/// Write the groups to duplicate_groups.parquet, replacing any previous analysis
+pub fn write_duplicate_groups(dataset_dir: &Path, members: &[DuplicateMember], provenance: &Provenance) -> Result<()> {
+    let batch = RecordBatch::try_new(
+        duplicate_group_schema(),
+        vec![
+            Arc::new(Int32Array::from_iter_values(members.iter().map(|m| m.group_id))),
+            Arc::new(StringArray::from_iter_values(members.iter().map(|m| format!("{:016x}", m.content_hash)))),
+            Arc::new(StringArray::from_iter_values(members.iter().map(|m| m.folder_id.as_str()))),
+            Arc::new(StringArray::from_iter_values(members.iter().map(|m| m.osu_file.as_str()))),
+            Arc::new(Int32Array::from_iter_values(members.iter().map(|m| m.object_count))),
+            Arc::new(BooleanArray::from_iter(members.iter().map(|m| Some(m.is_representative)))),
+        ],
+    )?;
+
+    let path = dataset_dir.join(DUPLICATE_GROUPS_FILE);
+    let file = File::create(&path).with_context(|| format!("Failed to create {}", path.display()))?;
+    let schema = duplicate_group_schema();
+    let props = writer_properties_builder(&schema).set_key_value_metadata(provenance_key_values(provenance)).build();
+    let mut writer = ArrowWriter::try_new(file, schema.clone(), Some(props))?;
+    writer.write(&batch)?;
+    writer.close()?;
+    Ok(())
+}
+
+/// Copy every table into `output_dir`, keeping one representative per duplicate group
+///
+/// Rows keyed by difficulty (`osu_file`, or `source_file` for embedded storyboards)
+/// are dropped for non-representatives. Folder-level rows (assets, `.osb`
+/// storyboards) are dropped only when every difficulty of the folder is dropped.
+/// Asset files are not copied. Each copy keeps the provenance of its source
+/// file. Returns rows kept per table.
+pub fn write_deduplicated(
+    dataset_dir: &Path,
+    output_dir: &Path,
+    members: &[DuplicateMember],
+) -> Result<BTreeMap<&'static str, usize>> {
+    if output_dir == dataset_dir {
+        anyhow::bail!("--dedup-output must differ from the dataset directory");
+    }
+    fs::create_dir_all(output_dir)?;
+
+    let dropped: HashSet<(&str, &str)> = members
+        .iter()
+        .filter(|m| !m.is_representative)
+        .map(|m| (m.folder_id.as_str(), m.osu_file.as_str()))
+        .collect();
+    let dropped_folders = fully_dropped_folders(dataset_dir, &dropped)?;
+
+    let mut counts = BTreeMap::new();
+    for table in ALL_TABLES {
+        let source = dataset_dir.join(format!("{}.parquet", table));
+        if !source.exists() {
+            continue;
+        }
+
+        let file = File::open(&source).with_context(|| format!("Failed to open {}", source.display()))?;
+        let builder = ParquetRecordBatchReaderBuilder::try_new(file)?;
+        let schema = builder.schema().clone();
+        // The writer embeds its own ARROW:schema entry
+        let key_values: Option<Vec<_>> = builder.metadata().file_metadata().key_value_metadata().map(|kvs| {
+            kvs.iter().filter(|kv| kv.key != "ARROW:schema").cloned().collect()
+        });
+        let reader = builder.with_batch_size(8192).build()?;
+
+        let target = output_dir.join(format!("{}.parquet", table));
+        let props = writer_properties_builder(&schema).set_key_value_metadata(key_values).build();
+        let mut writer = ArrowWriter::try_new(File::create(&target)?, schema, Some(props))?;
+        let mut kept = 0;
+
+        for batch in reader {
+            let batch = batch?;
+            let folder_id = string_column(&batch, "folder_id")?;
+            let difficulty = batch
+                .column_by_name("osu_file")
+                .or_else(|| batch.column_by_name("source_file"))
+                .and_then(|col| col.as_any().downcast_ref::<StringArray>());
+
+            let keep: BooleanArray = (0..batch.num_rows())
+                .map(|i| {
+                    let folder = folder_id.value(i);
+                    let row_dropped = match difficulty {
+                        Some(file) => {
+                            dropped.contains(&(folder, file.value(i)))
+                                || (!file.value(i).ends_with(".osu") && dropped_folders.contains(folder))
+                        }
+                        None => dropped_folders.contains(folder),
+                    };
+                    Some(!row_dropped)
+                })
+                .collect();
+
+            let filtered = filter_record_batch(&batch, &keep)?;
+            kept += filtered.num_rows();
+            writer.write(&filtered)?;
+        }
+        writer.close()?;
+        counts.insert(table, kept);
+    }
+
+    manifest::write_manifest_counts(output_dir, counts.clone())?;
+    Ok(counts)
+}
+
+/// Folders whose every difficulty is a dropped duplicate
+fn fully_dropped_folders<'a>(dataset_dir: &Path, dropped: &HashSet<(&'a str, &str)>) -> Result<HashSet<&'a str>> {
+    let candidates: HashSet<&str> = dropped.iter().map(|(folder, _)| *folder).collect();
+    let mut kept_folders: HashSet<String> = HashSet::new();
+
+    let path = dataset_dir.join("beatmaps.parquet");
+    let file = File::open(&path).with_context(|| format!("Failed to open {}", path.display()))?;
+    for batch in ParquetRecordBatchReaderBuilder::try_new(file)?.build()? {
+        let batch = batch?;
+        let folder_id = string_column(&batch, "folder_id")?;
+        let osu_file = string_column(&batch, "osu_file")?;
+        for i in 0..batch.num_rows() {
+            let folder = folder_id.value(i);
+            if candidates.contains(folder) && !dropped.contains(&(folder, osu_file.value(i))) {
+                kept_folders.insert(folder.to_string());
+            }
+        }
+    }
+
+    Ok(candidates.into_iter().filter(|folder| !kept_folders.contains(*folder)).collect())
+}
+
+fn optional_i32(array: &Int32Array, i: usize) -> i32 {
+    if array.is_null(i) { i32::MIN } else { array.value(i) }
+}
+
+fn string_column<'a>(batch: &'a RecordBatch, name: &str) -> Result<&'a StringArray> {
+    batch
+        .column_by_name(name)
+        .and_then(|col| col.as_any().downcast_ref::<StringArray>())
+        .with_context(|| format!("Missing or invalid column: {}", name))
+}
+
+fn i32_column<'a>(batch: &'a RecordBatch, name: &str) -> Result<&'a Int32Array> {
+    batch
+        .column_by_name(name)
+        .and_then(|col| col.as_any().downcast_ref::<Int32Array>())
+        .with_context(|| format!("Missing or invalid column: {}", name))
+}
+
+fn f64_column<'a>(batch: &'a RecordBatch, name: &str) -> Result<&'a Float64Array> {
+    batch
+        .column_by_name(name)
+        .and_then(|col| col.as_any().downcast_ref::<Float64Array>())
+        .with_context(|| format!("Missing or invalid column: {}", name))
+}
+
+fn bool_column<'a>(batch: &'a RecordBatch, name: &str) -> Result<&'a BooleanArray> {
+    batch
+        .column_by_name(name)
+        .and_then(|col| col.as_any().downcast_ref::<BooleanArray>())
+        .with_context(|| format!("Missing or invalid column: {}", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// One hit_objects row: (folder_id, osu_file, start_time, pos_x, is_slider)
+    type ObjectRow = (&'static str, &'static str, f64, i32, bool);
+
+    /// A circle then a slider, starting at `offset`
+    fn difficulty(folder: &'static str, osu_file: &'static str, offset: f64, x: i32) -> Vec<ObjectRow> {
+        vec![(folder, osu_file, offset, x, false), (folder, osu_file, offset + 500.0, x + 10, true)]
+    }
+
+    fn write_table(dir: &Path, table: &str, columns: Vec<(&str, ArrayRef)>) {
+        let batch = RecordBatch::try_from_iter(columns).unwrap();
+        let file = File::create(dir.join(format!("{}.parquet", table))).unwrap();
+        let mut writer = ArrowWriter::try_new(file, batch.schema(), None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+    }
+
+    fn strings<'a>(values: impl IntoIterator<Item = &'a str>) -> ArrayRef {
+        Arc::new(StringArray::from_iter_values(values))
+    }
+
+    fn dataset(rows: &[ObjectRow]) -> PathBuf {
+        let dir = crate::test_dir();
+        let slider = |row: &ObjectRow| row.4;
+        write_table(
+            &dir,
+            "hit_objects",
+            vec![
+                ("folder_id", strings(rows.iter().map(|r| r.0))),
+                ("osu_file", strings(rows.iter().map(|r| r.1))),
+                ("start_time", Arc::new(Float64Array::from_iter_values(rows.iter().map(|r| r.2)))),
+                ("object_type", strings(rows.iter().map(|r| if slider(r) { "slider" } else { "circle" }))),
+                ("pos_x", Arc::new(Int32Array::from_iter_values(rows.iter().map(|r| r.3)))),
+                ("pos_y", Arc::new(Int32Array::from_iter_values(rows.iter().map(|_| 192)))),
+                ("new_combo", Arc::new(BooleanArray::from_iter(rows.iter().map(|_| Some(false))))),
+                ("curve_type", Arc::new(StringArray::from_iter(rows.iter().map(|r| slider(r).then_some("B"))))),
+                ("slides", Arc::new(Int32Array::from_iter(rows.iter().map(|r| slider(r).then_some(1))))),
+                ("length", Arc::new(Float64Array::from_iter(rows.iter().map(|r| slider(r).then_some(100.0))))),
+                ("end_time", Arc::new(Float64Array::from_iter(rows.iter().map(|r| slider(r).then_some(r.2 + 300.0))))),
+            ],
+        );
+
+        let mut difficulties: Vec<(&str, &str)> = rows.iter().map(|r| (r.0, r.1)).collect();
+        difficulties.dedup();
+        write_table(
+            &dir,
+            "beatmaps",
+            vec![
+                ("folder_id", strings(difficulties.iter().map(|d| d.0))),
+                ("osu_file", strings(difficulties.iter().map(|d| d.1))),
+            ],
+        );
+        dir
+    }
+
+    fn group_of<'a>(members: &'a [DuplicateMember], folder: &str, osu_file: &str) -> Option<&'a DuplicateMember> {
+        members.iter().find(|m| m.folder_id == folder && m.osu_file == osu_file)
+    }
+
+    #[test]
+    fn offset_shifted_reuploads_hash_equal_and_moved_objects_do_not() {
+        let mut rows = difficulty("b", "easy.osu", 1250.0, 100);
+        rows.extend(difficulty("a", "easy.osu", 1000.0, 100));
+        rows.extend(difficulty("c", "easy.osu", 1000.0, 101));
+        let members = find_duplicate_groups(&dataset(&rows)).unwrap();
+
+        let (a, b) = (group_of(&members, "a", "easy.osu").unwrap(), group_of(&members, "b", "easy.osu").unwrap());
+        assert_eq!((a.group_id, a.content_hash, a.object_count), (b.group_id, b.content_hash, 2));
+        // One pixel is a different map
+        assert!(group_of(&members, "c", "easy.osu").is_none());
+        // The lowest key represents the group even though its rows came second
+        assert!(a.is_representative && !b.is_representative);
+        assert_eq!(members.len(), 2);
+    }
+
+    #[test]
+    fn folder_level_rows_go_only_with_the_last_difficulty_of_a_folder() {
+        let mut rows = difficulty("a", "easy.osu", 1000.0, 100);
+        rows.extend(difficulty("a", "hard.osu", 1000.0, 300));
+        rows.extend(difficulty("b", "easy.osu", 2000.0, 100));
+        rows.extend(difficulty("b", "hard.osu", 2000.0, 300));
+        rows.extend(difficulty("d", "easy.osu", 500.0, 100));
+        rows.extend(difficulty("d", "extra.osu", 500.0, 50));
+        let dir = dataset(&rows);
+        let folders = ["a", "b", "d"];
+        write_table(&dir, "assets", vec![("folder_id", strings(folders)), ("file_name", strings(["bg.jpg"; 3]))]);
+        write_table(
+            &dir,
+            "storyboard_elements",
+            vec![
+                ("folder_id", strings(["a", "b", "b", "d"])),
+                ("source_file", strings(["set.osb", "set.osb", "hard.osu", "set.osb"])),
+            ],
+        );
+
+        let members = find_duplicate_groups(&dir).unwrap();
+        let representatives: Vec<(&str, &str)> = members
+            .iter()
+            .filter(|m| m.is_representative)
+            .map(|m| (m.folder_id.as_str(), m.osu_file.as_str()))
+            .collect();
+        assert_eq!(representatives, [("a", "easy.osu"), ("a", "hard.osu")]);
+
+        let output = crate::test_dir();
+        let counts = write_deduplicated(&dir, &output, &members).unwrap();
+        // b is dropped entirely; d keeps extra.osu and with it its folder-level rows
+        assert_eq!(counts["beatmaps"], 3);
+        assert_eq!(counts["hit_objects"], 6);
+        assert_eq!(counts["assets"], 2);
+        assert_eq!(counts["storyboard_elements"], 2);
+
+        let kept = |table: &str| -> Vec<String> {
+            let file = File::open(output.join(format!("{}.parquet", table))).unwrap();
+            let mut folders = Vec::new();
+            for batch in ParquetRecordBatchReaderBuilder::try_new(file).unwrap().build().unwrap() {
+                let batch = batch.unwrap();
+                let folder_id = string_column(&batch, "folder_id").unwrap();
+                folders.extend(folder_id.iter().flatten().map(str::to_string));
+            }
+            folders
+        };
+        assert_eq!(kept("assets"), ["a", "d"]);
+        assert_eq!(kept("storyboard_elements"), ["a", "d"]);
+        assert_eq!(kept("beatmaps"), ["a", "a", "d"]);
+    }
+}
@@ -1,20 +1,40 @@
 use anyhow::{Context, Result};
 use clap::Parser;
-use indicatif::{ProgressBar, ProgressStyle};
-use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use indicatif::ProgressStyle;
 use arrow::array::{Array, StringArray};
+use arrow::datatypes::DataType;
 use rosu_map::Beatmap;
+use rosu_map::section::hit_objects::hit_samples::HitSampleInfo;
+use rosu_map::section::general::GameMode;
+use rosu_map::section::hit_objects::CurveBuffers;
 use rosu_storyboard::Storyboard;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs::{self, File};
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
 use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
 use walkdir::WalkDir;
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
-use rand::rng;
+use rand::{Rng, SeedableRng};
 
 mod batch_writer;
+mod custom_features;
+mod dedup;
+mod manifest;
+mod profile;
+
+use batch_writer::OutputFormat;
+use custom_features::FeatureValue;
+use osu_batch::summary::{self, RunSummary};
+use osu_batch::{say, FailedFormat, FailedLog};
+use profile::Phase;
+
+/// Hit object count above which a difficulty's objects are not extracted (troll/tech maps)
+const DEFAULT_MAX_OBJECTS: usize = 100_000;
 
 /// Build parquet dataset from osu! beatmap folders
 #[derive(Parser, Debug)]
@@ -28,6 +48,14 @@ struct Args {
     #[arg(long, default_value = r"E:\osu_model\dataset")]
     output_dir: PathBuf,
 
+    /// Table file format. Arrow IPC (`.arrow`) is uncompressed, so files are several times larger than parquet
+    #[arg(long, value_enum, default_value_t = OutputFormat::Parquet)]
+    format: OutputFormat,
+
+    /// Write a Bloom filter on folder_id into each parquet row group, so single-folder reads skip row groups without it
+    #[arg(long)]
+    bloom: bool,
+
     /// Force rebuild, ignoring existing parquet data
     #[arg(long, short)]
     force: bool,
@@ -35,75 +63,233 @@ struct Args {
     /// Test mode: only process 10 random folders
     #[arg(long)]
     test: bool,
+
+    /// Only process this folder under input_dir (repeatable), even if already processed or failed,
+    /// printing per-table row counts for each
+    #[arg(long = "folder", value_name = "NAME", conflicts_with_all = ["test", "reservoir"])]
+    folders: Vec<String>,
+
+    /// With --folder, rebuild named folders already in the dataset, replacing their rows
+    /// (without it they are skipped)
+    #[arg(long, requires = "folders")]
+    replace: bool,
+
+    /// Parse and report without writing tables, copying assets or updating the failed list
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Store compressible assets (PNG/BMP/WAV) as zstd-compressed `.zst` files
+    #[arg(long)]
+    compress_assets: bool,
+
+    /// Copy assets even if an up-to-date copy (same mtime, and same size unless compressed) exists
+    #[arg(long)]
+    overwrite_assets: bool,
+
+    /// Copy each folder's assets on N threads
+    #[arg(long, value_name = "N", default_value_t = 1)]
+    asset_copy_threads: usize,
+
+    /// Downscale copied backgrounds larger than N pixels on either side to fit N x N
+    #[arg(long, value_name = "N")]
+    background_max_dim: Option<u32>,
+
+    /// Compute nomod star rating and max pp per difficulty with rosu-pp (adds CPU time per map)
+    #[arg(long)]
+    compute_stars: bool,
+
+    /// Store only the beatmaps row, flagged `skipped_too_large`, for difficulties with more than N hit objects
+    #[arg(long, value_name = "N", default_value_t = DEFAULT_MAX_OBJECTS)]
+    max_objects: usize,
+
+    /// Give up on a folder after this many seconds and record it as a timeout failure
+    #[arg(long)]
+    per_item_timeout_secs: Option<u64>,
+
+    /// Process a uniform random sample of N folders, chosen in one pass over the input directory
+    #[arg(long, value_name = "N")]
+    reservoir: Option<usize>,
+
+    /// Seed for --reservoir and --test sampling (random if not set)
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Only write these tables (comma-separated parquet file stems, e.g. beatmaps,hit_objects)
+    #[arg(long, value_delimiter = ',', conflicts_with = "skip_tables")]
+    only_tables: Vec<String>,
+
+    /// Skip these tables (comma-separated), e.g. storyboard_commands
+    #[arg(long, value_delimiter = ',')]
+    skip_tables: Vec<String>,
+
+    /// Don't copy audio/background/storyboard files (implies skipping the assets table)
+    #[arg(long)]
+    no_assets: bool,
+
+    /// Scan [General]/[Metadata] for keys rosu-map drops and store them in extra_fields
+    #[arg(long)]
+    capture_extra: bool,
+
+    /// Run the extractors registered in custom_features.rs and store their values in custom_features
+    #[arg(long)]
+    custom_features: bool,
+
+    /// Path of the failed list (default: failed_folders.<format extension> in output_dir)
+    #[arg(long, value_name = "PATH")]
+    failed_log: Option<PathBuf>,
+
+    /// Format of the failed list
+    #[arg(long, value_enum, default_value_t = FailedFormat::Txt)]
+    failed_format: FailedFormat,
+
+    /// After building, group difficulties with identical hit objects into duplicate_groups.parquet
+    #[arg(long)]
+    find_duplicates: bool,
+
+    /// Write a copy of the dataset with one representative per duplicate group (implies --find-duplicates)
+    #[arg(long, value_name = "DIR")]
+    dedup_output: Option<PathBuf>,
+
+    /// No progress bar or per-folder messages; print one JSON summary line at the end
+    #[arg(long, short)]
+    quiet: bool,
+
+    /// Time parsing, row extraction, storyboards, asset copying and table writing, and print a breakdown at the end
+    #[arg(long)]
+    profile: bool,
+}
+
+/// zstd level used for --compress-assets
+const ASSET_ZSTD_LEVEL: i32 = 10;
+
+/// How assets are stored and copied (--compress-assets, --overwrite-assets, --asset-copy-threads, --background-max-dim)
+#[derive(Debug, Clone, Copy)]
+struct AssetOptions {
+    compress: bool,
+    overwrite: bool,
+    copy_threads: usize,
+    background_max_dim: Option<u32>,
+}
+
+impl AssetOptions {
+    fn from_args(args: &Args) -> Self {
+        Self {
+            compress: args.compress_assets,
+            overwrite: args.overwrite_assets,
+            copy_threads: args.asset_copy_threads.max(1),
+            background_max_dim: args.background_max_dim.filter(|&dim| dim > 0),
+        }
+    }
 }
 
 fn main() -> Result<()> {
     let args = Args::parse();
+    summary::set_quiet(args.quiet);
+    profile::set_enabled(args.profile);
+    batch_writer::set_folder_id_bloom(args.bloom);
+    let mut skip_tables = args.skip_tables.clone();
+    if args.no_assets || args.dry_run {
+        skip_tables.push("assets".to_string());
+    }
+    if !args.capture_extra {
+        skip_tables.push("extra_fields".to_string());
+    }
+    if !args.custom_features {
+        skip_tables.push("custom_features".to_string());
+    }
+    let tables = batch_writer::TableSelection::new(&args.only_tables, &skip_tables)?;
+    if args.format != OutputFormat::Parquet && (args.find_duplicates || args.dedup_output.is_some()) {
+        anyhow::bail!("--find-duplicates and --dedup-output need --format parquet");
+    }
     
     let assets_dir = args.output_dir.join("assets");
-    fs::create_dir_all(&args.output_dir)?;
-    fs::create_dir_all(&assets_dir)?;
+    if !args.dry_run {
+        fs::create_dir_all(&args.output_dir)?;
+    }
+    if tables.contains("assets") {
+        fs::create_dir_all(&assets_dir)?;
+    }
+
+    // New rows are appended to the existing tables, which must share this build's schema
+    manifest::check_appendable(&args.output_dir)?;
 
     // Read existing processed folder_ids unless --force
     let existing_folder_ids: HashSet<String> = if !args.force {
-        read_existing_folder_ids(&args.output_dir)
+        read_existing_folder_ids(&args.output_dir, args.format)?
     } else {
         HashSet::new()
     };
 
-    // Load failed folders list (format: "folder_name: reason")
-    let failed_path = args.output_dir.join("failed_folders.txt");
-    let failed_lines: Vec<String> = if failed_path.exists() {
-        std::fs::read_to_string(&failed_path)
-            .unwrap_or_default()
-            .lines()
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty())
-            .collect()
-    } else {
-        Vec::new()
-    };
-    let failed_folder_set: HashSet<String> = failed_lines
-        .iter()
-        .map(|line| line.split(':').next().unwrap_or("").trim().to_string())
-        .filter(|s| !s.is_empty())
-        .collect();
-    let mut failed_folders: HashSet<String> = failed_lines.into_iter().collect();
-    let initial_failed_count = failed_folder_set.len();
+    // Load failed folders list
+    let failed_log_path = args
+        .failed_log
+        .clone()
+        .unwrap_or_else(|| args.output_dir.join(format!("failed_folders.{}", args.failed_format.extension())));
+    let mut failed_log = FailedLog::load(&failed_log_path, args.failed_format)?;
+    let initial_failed_count = failed_log.initial_count();
 
     if !existing_folder_ids.is_empty() {
-        println!("Found {} already processed folders (use --force to rebuild)", existing_folder_ids.len());
+        say!("Found {} already processed folders (use --force to rebuild)", existing_folder_ids.len());
     }
     if initial_failed_count > 0 {
-        println!("Skipping {} permanently failed folders", initial_failed_count);
+        say!("Skipping {} permanently failed folders", initial_failed_count);
     }
 
-    let mut folders: Vec<PathBuf> = fs::read_dir(&args.input_dir)?
+    let mut rng = match args.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_os_rng(),
+    };
+
+    let candidates = fs::read_dir(&args.input_dir)?
         .filter_map(|e| e.ok())
         .filter(|e| e.path().is_dir())
         .map(|e| e.path())
         .filter(|p| {
             // Skip already processed and failed folders
             let folder_name = p.file_name().unwrap_or_default().to_string_lossy().to_string();
-            !existing_folder_ids.contains(&folder_name) && !failed_folder_set.contains(&folder_name)
-        })
-        .collect();
+            !existing_folder_ids.contains(&folder_name) && !failed_log.contains(&folder_name)
+        });
+
+    let named_folders = named_folders(&args, &existing_folder_ids)?;
+
+    let mut folders: Vec<PathBuf> = match args.reservoir {
+        Some(n) => {
+            let (mut sample, seen) = reservoir_sample(candidates, n, &mut rng);
+            sample.sort();
+            say!("RESERVOIR MODE: Sampled {} of {} candidate folders", sample.len(), seen);
+            let sample_path = args.output_dir.join("reservoir_folders.txt");
+            let content: String = sample
+                .iter()
+                .map(|p| format!("{}\n", p.file_name().unwrap_or_default().to_string_lossy()))
+                .collect();
+            fs::write(&sample_path, content)?;
+            say!("Chosen folders written to {}", sample_path.display());
+            sample
+        }
+        None if !named_folders.is_empty() => named_folders,
+        None => candidates.collect(),
+    };
 
     if args.test {
-        let mut rng = rng();
         folders.shuffle(&mut rng);
         folders.truncate(10);
-        println!("TEST MODE: Processing 10 random folders");
+        say!("TEST MODE: Processing 10 random folders");
     }
 
     if folders.is_empty() {
-        println!("No new beatmap folders to process.");
-        return Ok(());
+        say!("No new beatmap folders to process.");
+        let mut summary = RunSummary::default();
+        run_duplicate_pass(&args, &mut summary)?;
+        return summary.print();
     }
 
-    println!("Found {} new beatmap folders to process", folders.len());
+    say!("Found {} new beatmap folders to process", folders.len());
+
+    if args.dry_run {
+        return dry_run(&args, &folders, &assets_dir, &tables);
+    }
 
-    let pb = ProgressBar::new(folders.len() as u64);
+    let pb = summary::progress_bar(folders.len() as u64);
     pb.set_style(
         ProgressStyle::default_bar()
             .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} ({eta})")
@@ -113,13 +299,17 @@ fn main() -> Result<()> {
 
     // Initialize batch writers for memory-efficient parquet writing
     // Append mode: existing parquet files will have new data appended
-    let mut writers = batch_writer::DatasetWriters::new(&args.output_dir)?;
+    let provenance = manifest::provenance(&args.input_dir);
+    let mut writers = batch_writer::DatasetWriters::new(&args.output_dir, &tables, args.format, &provenance)?;
+    if tables.names().len() < batch_writer::ALL_TABLES.len() {
+        say!("Writing tables: {}", tables.names().join(", "));
+    }
 
     // Set up graceful shutdown
     let shutdown_requested = Arc::new(AtomicBool::new(false));
     let shutdown_clone = shutdown_requested.clone();
     ctrlc::set_handler(move || {
-        println!("\n⏳ Ctrl+C received! Finishing current folder then stopping...");
+        say!("\n⏳ Ctrl+C received! Finishing current folder then stopping...");
         shutdown_clone.store(true, Ordering::SeqCst);
     }).expect("Error setting Ctrl+C handler");
 
@@ -136,53 +326,190 @@ fn main() -> Result<()> {
         }
 
         pb.inc(1);
-        match process_folder_batch(folder, &mut writers, &assets_dir) {
-            Ok(()) => success_count += 1,
+        match process_folder_with_timeout(folder, &assets_dir, AssetOptions::from_args(&args), args.compute_stars, args.max_objects, &tables, args.per_item_timeout_secs) {
+            Ok(rows) => {
+                if !args.folders.is_empty() {
+                    pb.println(folder_report(folder, &rows));
+                }
+                let mut timer = profile::Timer::start();
+                let folder_name = folder.file_name().unwrap_or_default().to_string_lossy().to_string();
+                if args.replace && existing_folder_ids.contains(&folder_name) {
+                    writers.replace_folder(&folder_name);
+                }
+                writers.write_folder(rows)?;
+                timer.lap(Phase::Flush);
+                success_count += 1;
+            }
             Err(e) => {
                 failure_count += 1;
                 let folder_name = folder.file_name().unwrap_or_default().to_string_lossy().to_string();
-                failed_folders.insert(format!("{}: {}", folder_name, e));
-                pb.println(format!("Error: {}: {}", folder.display(), e));
+                failed_log.record(&folder_name, failure_category(&e), &e);
+                if args.folders.is_empty() {
+                    pb.println(format!("Error: {}: {}", folder.display(), e));
+                } else {
+                    pb.println(format!("Error: {}: {:#}", folder.display(), e));
+                }
             }
         }
     }
 
     pb.finish_with_message("Processing complete!");
 
-    println!("\n=== Writing Parquet Files ===");
+    say!("\n=== Writing {} Files ===", if args.format == OutputFormat::Arrow { "Arrow IPC" } else { "Parquet" });
+    let mut timer = profile::Timer::start();
     let stats = writers.close()?;
-    println!("  beatmaps.parquet: {} rows", stats.beatmaps);
-    println!("  hit_objects.parquet: {} rows", stats.hit_objects);
-    println!("  timing_points.parquet: {} rows", stats.timing_points);
-    println!("  storyboard_elements.parquet: {} rows", stats.storyboard_elements);
-    println!("  storyboard_commands.parquet: {} rows", stats.storyboard_commands);
-    println!("  slider_control_points.parquet: {} rows", stats.slider_control_points);
-    println!("  slider_data.parquet: {} rows", stats.slider_data);
-    println!("  breaks.parquet: {} rows", stats.breaks);
-    println!("  combo_colors.parquet: {} rows", stats.combo_colors);
-    println!("  hit_samples.parquet: {} rows", stats.hit_samples);
-    println!("  storyboard_loops.parquet: {} rows", stats.storyboard_loops);
-    println!("  storyboard_triggers.parquet: {} rows", stats.storyboard_triggers);
-
-    println!("\n=== Results ===");
-    println!("Success: {}", success_count);
-    println!("Failed: {}", failure_count);
+    timer.lap(Phase::Flush);
+    for (table, rows) in stats.tables() {
+        say!("  {}.{}: {} rows", table, args.format.extension(), rows);
+    }
+    profile::print();
+    manifest::write_manifest(&args.output_dir, &stats)?;
+    say!("  {} (schema v{})", manifest::MANIFEST_FILE, manifest::SCHEMA_VERSION);
+
+    say!("\n=== Results ===");
+    say!("Success: {}", success_count);
+    say!("Failed: {}", failure_count);
     if interrupted {
-        println!("⚠ Run was interrupted by Ctrl+C");
+        say!("⚠ Run was interrupted by Ctrl+C");
     }
 
     // Save failed list if there are new failures
-    let new_failures = failed_folders.len() - initial_failed_count;
+    let new_failures = failed_log.new_count();
     if new_failures > 0 {
-        let content: String = failed_folders.iter().map(|s| format!("{}\n", s)).collect();
-        let _ = std::fs::write(&failed_path, content);
-        println!("Added {} folders to failed_folders.txt", new_failures);
+        match failed_log.save() {
+            Ok(()) => say!("Added {} folders to {}", new_failures, failed_log.path().display()),
+            Err(e) => eprintln!("⚠ {}", e),
+        }
     }
 
     // Note: Round-trip verification is not available in batch mode
     // since data is written directly to parquet files.
     // Use osu-reconstructor library to verify data integrity.
 
+    let mut summary = RunSummary {
+        processed: success_count + failure_count,
+        succeeded: success_count,
+        failed: failure_count,
+        rows: stats.tables().into_iter().map(|(table, rows)| (table.to_string(), rows)).collect(),
+        interrupted,
+    };
+    run_duplicate_pass(&args, &mut summary)?;
+    summary.print()
+}
+
+/// Resolve `--folder` names under input_dir, bypassing the processed and failed skip sets
+///
+/// Folders already in the dataset are only kept with `--replace`, so re-running one
+/// never duplicates its rows.
+fn named_folders(args: &Args, existing_folder_ids: &HashSet<String>) -> Result<Vec<PathBuf>> {
+    let mut folders = Vec::with_capacity(args.folders.len());
+    for name in &args.folders {
+        let path = args.input_dir.join(name);
+        if !path.is_dir() {
+            anyhow::bail!("Folder not found: {}", path.display());
+        }
+        if existing_folder_ids.contains(name) && !args.dry_run {
+            if !args.replace {
+                say!("⚠ {} is already in the dataset; skipping it (use --replace to rebuild it)", name);
+                continue;
+            }
+            say!("{} is already in the dataset; its rows will be replaced", name);
+        }
+        folders.push(path);
+    }
+    if !folders.is_empty() {
+        say!("FOLDER MODE: Processing {} named folder(s)", folders.len());
+    }
+    Ok(folders)
+}
+
+/// Per-table row counts and difficulties parsed from one folder
+fn folder_report(folder: &Path, rows: &batch_writer::FolderRows) -> String {
+    let mut report = format!("{}:", folder.display());
+    for beatmap in rows.beatmaps.iter() {
+        if beatmap.skipped_too_large {
+            report.push_str(&format!("\n  {} (mode {}): over --max-objects, hit objects skipped", beatmap.osu_file, beatmap.mode));
+            continue;
+        }
+        let objects = rows.hit_objects.iter().filter(|o| o.osu_file == beatmap.osu_file).count();
+        report.push_str(&format!("\n  {} (mode {}): {} hit objects", beatmap.osu_file, beatmap.mode, objects));
+        if beatmap.columns_out_of_range {
+            report.push_str(&format!(", objects outside the {} key columns", beatmap.key_count.unwrap_or_default()));
+        }
+    }
+    for (table, count) in rows.table_counts() {
+        if count > 0 {
+            report.push_str(&format!("\n    {}: {} rows", table, count));
+        }
+    }
+    report
+}
+
+/// Parse the selected folders and report what would be written, touching nothing on disk
+fn dry_run(args: &Args, folders: &[PathBuf], assets_dir: &Path, tables: &batch_writer::TableSelection) -> Result<()> {
+    say!("DRY RUN: nothing will be written and assets are not copied");
+    let mut success_count = 0;
+    let mut failure_count = 0;
+    let mut table_rows = BTreeMap::new();
+
+    for folder in folders {
+        match process_folder_with_timeout(folder, assets_dir, AssetOptions::from_args(args), args.compute_stars, args.max_objects, tables, args.per_item_timeout_secs) {
+            Ok(rows) => {
+                say!("{}", folder_report(folder, &rows));
+                for (table, count) in rows.table_counts() {
+                    *table_rows.entry(table.to_string()).or_insert(0) += count;
+                }
+                success_count += 1;
+            }
+            Err(e) => {
+                say!("Error: {}: {:#}", folder.display(), e);
+                failure_count += 1;
+            }
+        }
+    }
+    profile::print();
+
+    say!("\n=== Results ===");
+    say!("Success: {}", success_count);
+    say!("Failed: {}", failure_count);
+    RunSummary {
+        processed: success_count + failure_count,
+        succeeded: success_count,
+        failed: failure_count,
+        rows: table_rows,
+        interrupted: false,
+    }
+    .print()
+}
+
+/// Run the duplicate analysis over the whole dataset if requested
+fn run_duplicate_pass(args: &Args, summary: &mut RunSummary) -> Result<()> {
+    if !args.find_duplicates && args.dedup_output.is_none() {
+        return Ok(());
+    }
+
+    say!("\n=== Finding Duplicate Difficulties ===");
+    let members = dedup::find_duplicate_groups(&args.output_dir)?;
+    dedup::write_duplicate_groups(&args.output_dir, &members, &manifest::provenance(&args.input_dir))?;
+    let groups = members.iter().filter(|m| m.is_representative).count();
+    summary.rows.insert("duplicate_groups".to_string(), members.len());
+    say!(
+        "  {} difficulties in {} duplicate groups ({} redundant) written to {}",
+        members.len(),
+        groups,
+        members.len() - groups,
+        dedup::DUPLICATE_GROUPS_FILE
+    );
+
+    if let Some(dedup_dir) = &args.dedup_output {
+        say!("\n=== Writing Deduplicated Dataset ===");
+        let counts = dedup::write_deduplicated(&args.output_dir, dedup_dir, &members)?;
+        for (table, rows) in counts {
+            say!("  {}.parquet: {} rows", table, rows);
+        }
+        say!("  Written to {} (asset files are not copied)", dedup_dir.display());
+    }
+
     Ok(())
 }
 
@@ -222,6 +549,7 @@ struct BeatmapRow {
     version: String,
     source: String,
     tags: String,
+    tags_list: Vec<String>,  // tags split on whitespace
     beatmap_id: i32,
     beatmap_set_id: i32,
     // Difficulty section
@@ -235,6 +563,34 @@ struct BeatmapRow {
     background_file: String,
     audio_path: String,
     background_path: String,
+    // Local difficulty (--compute-stars, None otherwise)
+    stars_calc: Option<f64>,
+    max_pp: Option<f64>,
+    stars_suspicious: Option<bool>,
+    // Any object placed outside the playfield (see has_offscreen_objects)
+    has_offscreen_objects: bool,
+    // Sum of all spinner durations in ms
+    total_spinner_duration: f64,
+    // AudioFilename is `virtual` or empty (storyboard-only maps, see is_virtual_audio)
+    is_virtual_audio: bool,
+    // General keys rosu-map doesn't parse (see SkinSettings)
+    overlay_position: String,
+    skin_preference: String,
+    use_skin_sprites: bool,
+    always_show_playfield: bool,
+    // More hit objects than --max-objects; hit_objects, slider_* and hit_samples have no rows for it
+    skipped_too_large: bool,
+    // Mania only: rounded circle size and whether any x falls outside its columns (see columns_out_of_range)
+    key_count: Option<i32>,
+    columns_out_of_range: bool,
+    // Storyboard with at most STORYBOARD_ONLY_MAX_OBJECTS hit objects (see is_storyboard_only)
+    is_storyboard_only: bool,
+    // Original background size in pixels, also with --background-max-dim (None if unreadable)
+    background_width: Option<i32>,
+    background_height: Option<i32>,
+    // audio_file/background_file with their on-disk casing (see resolve_file_case)
+    audio_file_resolved: String,
+    background_file_resolved: String,
 }
 
 struct HitObjectRow {
@@ -250,10 +606,19 @@ struct HitObjectRow {
     combo_offset: i32,  // How many combo colors to skip
     // Slider specific
     curve_type: Option<String>,
-    slides: Option<i32>,
+    slides: Option<i32>,  // As in the .osu line: repeats + 1 (see slider_slides)
     length: Option<f64>,
     // Spinner specific
     end_time: Option<f64>,
+    // Total active time in ms (sliders, spinners, holds)
+    duration: Option<f64>,
+    // Mania key column (0-based), derived from pos_x
+    column: Option<i32>,
+    // Relative to the previous object in the same difficulty (None for the first)
+    delta_time: Option<f64>,
+    delta_distance: Option<f64>,
+    // Full spins needed to clear a spinner (osu! mode only, see required_spins)
+    required_spins: Option<i32>,
 }
 
 struct TimingPointRow {
@@ -268,6 +633,8 @@ struct TimingPointRow {
     // Sample settings (from SamplePoint)
     sample_bank: Option<String>,
     sample_volume: Option<i32>,
+    sample_index: Option<i32>,  // Custom sample bank index
+    order_index: Option<i32>,  // [TimingPoints] line this point came from (None if unmatched)
 }
 
 struct StoryboardElementRow {
@@ -275,7 +642,8 @@ struct StoryboardElementRow {
     source_file: String,
     element_index: i32,
     layer_name: String,
-    element_path: String,
+    element_path: String,  // Forward slashes (see normalize_separators)
+    element_path_original: Option<String>,  // As written, when it used backslashes
     element_type: String,
     // Sprite data
     origin: String,  // "TopLeft", "Centre", etc.
@@ -334,6 +702,35 @@ struct BreakRow {
     end_time: f64,
 }
 
+// Raw [Events] lines not covered by the structured tables (e.g. background colour transforms)
+struct EventRow {
+    folder_id: String,
+    osu_file: String,
+    event_index: i32,  // Position among the top-level lines of [Events]
+    event_type: String,  // Type field as written, e.g. "3" or "Colour"
+    start_time: Option<f64>,  // Second field, when numeric
+    params: String,  // Everything after the type field, verbatim
+}
+
+// [General]/[Metadata] keys rosu-map doesn't model (--capture-extra)
+struct ExtraFieldRow {
+    folder_id: String,
+    osu_file: String,
+    section: String,  // "General" or "Metadata"
+    field_index: i32,  // Position among the key lines of the section
+    key: String,
+    value: String,  // Trimmed value as written
+}
+
+// Values of user-defined extractors (--custom-features)
+struct CustomFeatureRow {
+    folder_id: String,
+    osu_file: String,
+    name: String,
+    value_f64: Option<f64>,  // Set for FeatureValue::Number
+    value_str: Option<String>,  // Set for FeatureValue::Text
+}
+
 // Combo colors
 struct ComboColorRow {
     folder_id: String,
@@ -356,6 +753,8 @@ struct HitSampleRow {
     bank: String,  // "Normal", "Soft", "Drum"
     suffix: Option<String>,  // Custom sample suffix
     volume: i32,
+    scope: &'static str,  // "head", "body", "repeat" or "tail"
+    node_index: Option<i32>,  // Slider node (0 = head, last = tail) for head/repeat/tail samples of sliders
 }
 
 // Storyboard loops
@@ -382,43 +781,174 @@ struct StoryboardTriggerRow {
     is_embedded: bool,
 }
 
+// Copied asset files
+struct AssetRow {
+    folder_id: String,
+    original_path: String,  // Path as referenced by the .osu/.osb
+    stored_path: String,  // Path relative to the dataset root
+    kind: String,  // "audio", "background", "storyboard", "video"
+    size_bytes: i64,
+}
+
 // ============ Processing ============
 
-/// Read existing folder_ids from beatmaps.parquet
-fn read_existing_folder_ids(output_dir: &Path) -> HashSet<String> {
-    let beatmaps_path = output_dir.join("beatmaps.parquet");
+/// Choose up to `n` items uniformly at random in a single pass (Vitter's Algorithm R)
+///
+/// Only the reservoir is kept in memory, so this works on directories too large to
+/// collect and shuffle. Returns the sample and the number of items seen.
+fn reservoir_sample<T>(items: impl Iterator<Item = T>, n: usize, rng: &mut impl Rng) -> (Vec<T>, usize) {
+    let mut reservoir = Vec::with_capacity(n);
+    let mut seen = 0;
+
+    for item in items {
+        if reservoir.len() < n {
+            reservoir.push(item);
+        } else {
+            // Keep the i-th item with probability n / (i + 1)
+            let j = rng.random_range(0..=seen);
+            if j < n {
+                reservoir[j] = item;
+            }
+        }
+        seen += 1;
+    }
+
+    (reservoir, seen)
+}
+
+/// Coarse failure reason for the failed log, derived from the top-level error message
+fn failure_category(error: &anyhow::Error) -> &'static str {
+    let message = error.to_string();
+    if message.starts_with("timeout after") {
+        "timeout"
+    } else if message.starts_with("No .osu files") {
+        "no_osu_files"
+    } else if message.starts_with("Failed to parse") {
+        "parse"
+    } else if message.ends_with("panicked") {
+        "panic"
+    } else {
+        "error"
+    }
+}
+
+/// Read existing folder_ids from the beatmaps table
+///
+/// A missing or empty table means a fresh start. A table that can't be read or
+/// lacks a string `folder_id` column is an error: treating it as empty would
+/// silently reprocess every folder.
+fn read_existing_folder_ids(output_dir: &Path, format: OutputFormat) -> Result<HashSet<String>> {
+    let beatmaps_path = format.table_path(output_dir, "beatmaps");
     if !beatmaps_path.exists() {
-        return HashSet::new();
+        return Ok(HashSet::new());
+    }
+
+    let (schema, batches) = batch_writer::read_table_with_schema(&beatmaps_path, format)
+        .with_context(|| format!("Failed to read {} (corrupt? use --force to rebuild)", beatmaps_path.display()))?;
+    match schema.field_with_name("folder_id").map(|field| field.data_type()) {
+        Ok(DataType::Utf8) => {}
+        Ok(other) => anyhow::bail!("{} has folder_id of type {}, expected Utf8", beatmaps_path.display(), other),
+        Err(_) => anyhow::bail!("{} has no folder_id column", beatmaps_path.display()),
     }
 
     let mut folder_ids = HashSet::new();
-    
-    if let Ok(file) = File::open(&beatmaps_path) {
-        if let Ok(reader) = ParquetRecordBatchReaderBuilder::try_new(file) {
-            if let Ok(reader) = reader.build() {
-                for batch in reader.flatten() {
-                    if let Some(col) = batch.column_by_name("folder_id") {
-                        if let Some(arr) = col.as_any().downcast_ref::<StringArray>() {
-                            for i in 0..arr.len() {
-                                if !arr.is_null(i) {
-                                    folder_ids.insert(arr.value(i).to_string());
-                                }
-                            }
-                        }
-                    }
-                }
+    for batch in batches {
+        let arr = batch
+            .column_by_name("folder_id")
+            .and_then(|col| col.as_any().downcast_ref::<StringArray>())
+            .context("folder_id column changed type between batches")?;
+        for i in 0..arr.len() {
+            if !arr.is_null(i) {
+                folder_ids.insert(arr.value(i).to_string());
             }
         }
     }
 
-    folder_ids
+    Ok(folder_ids)
 }
 
-/// Batch version of process_folder that writes directly to parquet writers
+/// Process a folder on a worker thread, giving up after `timeout_secs` if set.
+///
+/// A timed-out worker can't be killed, so it is told to stop: it checks the flag
+/// between difficulties and before each asset copy. Its assets are staged and only
+/// moved into place if it finishes before the timeout (see settle_assets).
+fn process_folder_with_timeout(
+    source_folder: &Path,
+    assets_dir: &Path,
+    asset_options: AssetOptions,
+    compute_stars: bool,
+    max_objects: usize,
+    tables: &batch_writer::TableSelection,
+    timeout_secs: Option<u64>,
+) -> Result<batch_writer::FolderRows> {
+    let Some(secs) = timeout_secs else {
+        let never = AtomicBool::new(false);
+        return collect_folder_rows(source_folder, assets_dir, asset_options, compute_stars, max_objects, tables, &never);
+    };
+
+    let (tx, rx) = mpsc::channel();
+    let source_folder_owned = source_folder.to_path_buf();
+    let assets_dir_owned = assets_dir.to_path_buf();
+    let tables_owned = tables.clone();
+    let cancel = Arc::new(AtomicBool::new(false));
+    let worker_cancel = Arc::clone(&cancel);
+
+    thread::spawn(move || {
+        let _ = tx.send(collect_folder_rows(&source_folder_owned, &assets_dir_owned, asset_options, compute_stars, max_objects, &tables_owned, &worker_cancel));
+    });
+
+    match rx.recv_timeout(Duration::from_secs(secs)) {
+        Ok(result) => result,
+        Err(RecvTimeoutError::Timeout) => {
+            if cancel.swap(true, Ordering::SeqCst) {
+                // The worker finished just in time and is moving its assets into place
+                return rx.recv().unwrap_or_else(|_| Err(anyhow::anyhow!("Folder worker panicked")));
+            }
+            anyhow::bail!("timeout after {}s", secs)
+        }
+        Err(RecvTimeoutError::Disconnected) => anyhow::bail!("Folder worker panicked"),
+    }
+}
+
+/// Process a folder into an in-memory row buffer
+///
+/// Stops with an error once `cancel` is set (see process_folder_with_timeout).
+fn collect_folder_rows(
+    source_folder: &Path,
+    assets_dir: &Path,
+    asset_options: AssetOptions,
+    compute_stars: bool,
+    max_objects: usize,
+    tables: &batch_writer::TableSelection,
+    cancel: &AtomicBool,
+) -> Result<batch_writer::FolderRows> {
+    let mut rows = batch_writer::FolderRows::default();
+    process_folder_batch(source_folder, &mut rows, assets_dir, asset_options, compute_stars, max_objects, tables, cancel)?;
+    Ok(rows)
+}
+
+/// Fail once a timed-out folder's worker has been told to stop
+fn check_cancelled(cancel: &AtomicBool) -> Result<()> {
+    if cancel.load(Ordering::SeqCst) {
+        anyhow::bail!("Cancelled after timeout");
+    }
+    Ok(())
+}
+
+/// Batch version of process_folder that writes into the folder's row buffers
+///
+/// Extraction for tables excluded by `tables` is skipped, as are the per-object tables
+/// and star rating of difficulties with more than `max_objects` hit objects.
+#[allow(clippy::too_many_arguments)]
 fn process_folder_batch(
     source_folder: &Path,
-    writers: &mut batch_writer::DatasetWriters,
+    writers: &mut batch_writer::FolderRows,
     assets_dir: &Path,
+    asset_options: AssetOptions,
+    compute_stars: bool,
+    max_objects: usize,
+    tables: &batch_writer::TableSelection,
+    cancel: &AtomicBool,
 ) -> Result<()> {
     let folder_id = source_folder
         .file_name()
@@ -427,7 +957,13 @@ fn process_folder_batch(
         .to_string();
 
     let assets_folder = assets_dir.join(&folder_id);
-    let mut assets: HashSet<String> = HashSet::new();
+    let parse_storyboards = tables.any_storyboard();
+    let write_commands = tables.contains("storyboard_commands");
+    // Assets are only copied when their index table is written
+    let copy_assets = tables.contains("assets");
+    // Asset path -> kind (first classification wins)
+    let mut assets: HashMap<String, &'static str> = HashMap::new();
+    let mut timer = profile::Timer::start();
 
     // Find all .osu files
     let mut osu_files: Vec<PathBuf> = Vec::new();
@@ -447,33 +983,80 @@ fn process_folder_batch(
         anyhow::bail!("No .osu files found");
     }
 
+    // A folder's .osb storyboard plays with every difficulty
+    let folder_storyboard = osb_has_storyboard_sprites(source_folder)?;
+    // Difficulties usually share a background; read each one's header once
+    let mut background_sizes: HashMap<String, Option<(u32, u32)>> = HashMap::new();
+
     // Process each .osu file
     for osu_path in &osu_files {
+        check_cancelled(cancel)?;
         let osu_filename = osu_path.file_name().unwrap().to_string_lossy().to_string();
 
         // Parse beatmap
         let beatmap: Beatmap = rosu_map::from_path(osu_path)
             .with_context(|| format!("Failed to parse: {}", osu_path.display()))?;
 
+        // Keep the names as declared and look up their on-disk casing separately, so
+        // assets are found on case-sensitive filesystems without rewriting the .osu values
+        let virtual_audio = is_virtual_audio(&beatmap.audio_file);
+        let skin = SkinSettings::read(osu_path)?;
+        let audio_file_resolved = if virtual_audio {
+            beatmap.audio_file.clone()
+        } else {
+            resolve_file_case(source_folder, &beatmap.audio_file)
+        };
+        let background_file_resolved = resolve_file_case(source_folder, &beatmap.background_file);
+
         // Collect assets
-        if !beatmap.audio_file.is_empty() {
-            assets.insert(beatmap.audio_file.clone());
+        if copy_assets && !virtual_audio {
+            assets.entry(audio_file_resolved.clone()).or_insert("audio");
         }
-        if !beatmap.background_file.is_empty() {
-            assets.insert(beatmap.background_file.clone());
+        if copy_assets && !background_file_resolved.is_empty() {
+            assets.entry(background_file_resolved.clone()).or_insert("background");
         }
 
-        // Build asset paths
-        let audio_path = if !beatmap.audio_file.is_empty() {
-            format!("assets/{}/{}", folder_id, beatmap.audio_file)
+        // Build asset paths (recorded even with --no-assets, pointing where they would be stored)
+        let audio_path = if !virtual_audio {
+            let stored = stored_asset_name(source_folder, &audio_file_resolved, asset_options.compress);
+            format!("assets/{}/{}", folder_id, stored)
         } else {
             String::new()
         };
-        let background_path = if !beatmap.background_file.is_empty() {
-            format!("assets/{}/{}", folder_id, beatmap.background_file)
+        let background_path = if !background_file_resolved.is_empty() {
+            let stored = stored_asset_name(source_folder, &background_file_resolved, asset_options.compress);
+            format!("assets/{}/{}", folder_id, stored)
         } else {
             String::new()
         };
+        let background_size = if !background_file_resolved.is_empty() {
+            *background_sizes
+                .entry(background_file_resolved.clone())
+                .or_insert_with(|| image_dimensions(&source_folder.join(&background_file_resolved)))
+        } else {
+            None
+        };
+
+        timer.lap(Phase::Parse);
+
+        // Degenerate maps would dominate the run; only their beatmaps row is kept
+        let too_large = beatmap.hit_objects.len() > max_objects;
+        let key_count = matches!(beatmap.mode, GameMode::Mania).then(|| mania_key_count(beatmap.circle_size));
+        let storyboard_only = beatmap.hit_objects.len() <= STORYBOARD_ONLY_MAX_OBJECTS
+            && (folder_storyboard || osu_has_storyboard_sprites(osu_path)?);
+
+        // Local star rating; suspicious maps get null values and the flag set
+        let (stars_calc, max_pp, stars_suspicious) = if compute_stars && !too_large {
+            match osu_pp::nomod_rating(osu_path) {
+                Ok(Some(rating)) => (Some(rating.stars), Some(rating.max_pp), Some(false)),
+                Ok(None) => (None, None, Some(true)),
+                // rosu-pp is stricter than rosu-map; don't fail the folder over it
+                Err(_) => (None, None, None),
+            }
+        } else {
+            (None, None, None)
+        };
+        timer.lap(Phase::Stars);
 
         // Write beatmap row
         writers.beatmaps.write(BeatmapRow {
@@ -510,6 +1093,7 @@ fn process_folder_batch(
             version: beatmap.version.clone(),
             source: beatmap.source.clone(),
             tags: beatmap.tags.clone(),
+            tags_list: beatmap.tags.split_whitespace().map(str::to_string).collect(),
             beatmap_id: beatmap.beatmap_id,
             beatmap_set_id: beatmap.beatmap_set_id,
             // Difficulty section
@@ -523,156 +1107,317 @@ fn process_folder_batch(
             background_file: beatmap.background_file.clone(),
             audio_path,
             background_path,
+            stars_calc,
+            max_pp,
+            stars_suspicious,
+            has_offscreen_objects: has_offscreen_objects(&beatmap.hit_objects),
+            total_spinner_duration: total_spinner_duration(&beatmap.hit_objects),
+            is_virtual_audio: virtual_audio,
+            overlay_position: skin.overlay_position,
+            skin_preference: skin.skin_preference,
+            use_skin_sprites: skin.use_skin_sprites,
+            always_show_playfield: skin.always_show_playfield,
+            skipped_too_large: too_large,
+            key_count,
+            columns_out_of_range: key_count.is_some_and(|keys| columns_out_of_range(&beatmap.hit_objects, keys)),
+            is_storyboard_only: storyboard_only,
+            background_width: background_size.map(|(width, _)| width as i32),
+            background_height: background_size.map(|(_, height)| height as i32),
+            audio_file_resolved,
+            background_file_resolved,
         })?;
 
         // Write hit objects
-        for (idx, ho) in beatmap.hit_objects.iter().enumerate() {
-            let (obj_type, pos_x, pos_y, new_combo, curve_type, slides, length, end_time) =
-                extract_hit_object_info(ho);
-
-            writers.hit_objects.write(HitObjectRow {
-                folder_id: folder_id.clone(),
-                osu_file: osu_filename.clone(),
-                index: idx as i32,
-                start_time: ho.start_time,
-                object_type: obj_type,
-                pos_x,
-                pos_y,
-                new_combo,
-                combo_offset: extract_combo_offset(ho),
-                curve_type: curve_type.clone(),
-                slides,
-                length,
-                end_time,
-            })?;
-
-            // Write slider data if applicable
-            if let rosu_map::section::hit_objects::HitObjectKind::Slider(s) = &ho.kind {
-                writers.slider_data.write(SliderDataRow {
+        let write_objects = tables.contains("hit_objects") || tables.contains("slider_data") || tables.contains("slider_control_points");
+        if write_objects && !too_large {
+            let mut prev_object: Option<(f64, Option<i32>, Option<i32>)> = None;
+            let mut curve_buffers = CurveBuffers::default();
+            let mut anchor_lines = if tables.contains("slider_control_points") {
+                SliderAnchorLines::read(osu_path)?
+            } else {
+                SliderAnchorLines::default()
+            };
+            for (idx, ho) in beatmap.hit_objects.iter().enumerate() {
+                let (obj_type, pos_x, pos_y, new_combo, curve_type, slides, length, end_time) =
+                    extract_hit_object_info(ho);
+
+                let delta_time = prev_object.map(|(prev_time, _, _)| ho.start_time - prev_time);
+                let delta_distance = prev_object.and_then(|(_, prev_x, prev_y)| {
+                    let (dx, dy) = (pos_x? - prev_x?, pos_y? - prev_y?);
+                    Some((dx as f64).hypot(dy as f64))
+                });
+                prev_object = Some((ho.start_time, pos_x, pos_y));
+
+                writers.hit_objects.write(HitObjectRow {
                     folder_id: folder_id.clone(),
                     osu_file: osu_filename.clone(),
-                    hit_object_index: idx as i32,
-                    repeat_count: s.repeat_count,
-                    velocity: s.velocity,
-                    expected_dist: s.path.expected_dist(),
+                    index: idx as i32,
+                    start_time: ho.start_time,
+                    object_type: obj_type,
+                    pos_x,
+                    pos_y,
+                    new_combo,
+                    combo_offset: extract_combo_offset(ho),
+                    curve_type: curve_type.clone(),
+                    slides,
+                    length,
+                    end_time,
+                    duration: extract_duration(ho, &mut curve_buffers),
+                    column: key_count.zip(pos_x).map(|(keys, x)| mania_column(x, keys)),
+                    delta_time,
+                    delta_distance,
+                    required_spins: match &ho.kind {
+                        rosu_map::section::hit_objects::HitObjectKind::Spinner(sp) if beatmap.mode == GameMode::Osu => {
+                            Some(required_spins(sp.duration, beatmap.overall_difficulty))
+                        }
+                        _ => None,
+                    },
                 })?;
 
-                for (cp_idx, cp) in s.path.control_points().iter().enumerate() {
-                    writers.slider_control_points.write(SliderControlPointRow {
+                // Write slider data if applicable
+                if let rosu_map::section::hit_objects::HitObjectKind::Slider(s) = &ho.kind {
+                    writers.slider_data.write(SliderDataRow {
                         folder_id: folder_id.clone(),
                         osu_file: osu_filename.clone(),
                         hit_object_index: idx as i32,
-                        point_index: cp_idx as i32,
-                        pos_x: cp.pos.x,
-                        pos_y: cp.pos.y,
-                        path_type: cp.path_type.map(|pt| format!("{:?}", pt)),
+                        repeat_count: s.repeat_count.max(0),
+                        velocity: s.velocity,
+                        expected_dist: s.path.expected_dist(),
                     })?;
+
+                    // Anchors as written, falling back to rosu-map's points if the line wasn't found
+                    let points: Vec<(f32, f32, Option<String>)> = match anchor_lines.take((s.pos.x, s.pos.y)) {
+                        Some(anchors) => anchors
+                            .into_iter()
+                            .map(|a| (a.pos.0, a.pos.1, a.path_type.map(str::to_string)))
+                            .collect(),
+                        None => s
+                            .path
+                            .control_points()
+                            .iter()
+                            .map(|cp| (cp.pos.x, cp.pos.y, cp.path_type.map(|pt| format!("{:?}", pt))))
+                            .collect(),
+                    };
+                    for (cp_idx, (pos_x, pos_y, path_type)) in points.into_iter().enumerate() {
+                        writers.slider_control_points.write(SliderControlPointRow {
+                            folder_id: folder_id.clone(),
+                            osu_file: osu_filename.clone(),
+                            hit_object_index: idx as i32,
+                            point_index: cp_idx as i32,
+                            pos_x,
+                            pos_y,
+                            path_type,
+                        })?;
+                    }
                 }
             }
         }
 
         // Write timing points
-        for tp in &beatmap.control_points.timing_points {
-            writers.timing_points.write(TimingPointRow {
-                folder_id: folder_id.clone(),
-                osu_file: osu_filename.clone(),
-                time: tp.time,
-                point_type: "timing".to_string(),
-                beat_length: Some(tp.beat_len),
-                time_signature: Some(format!("{:?}", tp.time_signature)),
-                slider_velocity: None,
-                kiai: None,
-                sample_bank: None,
-                sample_volume: None,
-            })?;
-        }
+        if tables.contains("timing_points") {
+            let timing_lines = TimingLines::read(osu_path)?;
+            for tp in &beatmap.control_points.timing_points {
+                writers.timing_points.write(TimingPointRow {
+                    folder_id: folder_id.clone(),
+                    osu_file: osu_filename.clone(),
+                    time: tp.time,
+                    point_type: "timing".to_string(),
+                    beat_length: Some(tp.beat_len),
+                    time_signature: Some(format!("{:?}", tp.time_signature)),
+                    slider_velocity: None,
+                    kiai: None,
+                    sample_bank: None,
+                    sample_volume: None,
+                    sample_index: None,
+                    order_index: timing_lines.order_index(tp.time, TimingLineKind::Uninherited),
+                })?;
+            }
 
-        for dp in &beatmap.control_points.difficulty_points {
-            writers.timing_points.write(TimingPointRow {
-                folder_id: folder_id.clone(),
-                osu_file: osu_filename.clone(),
-                time: dp.time,
-                point_type: "difficulty".to_string(),
-                beat_length: None,
-                time_signature: None,
-                slider_velocity: Some(dp.slider_velocity),
-                kiai: None,
-                sample_bank: None,
-                sample_volume: None,
-            })?;
-        }
+            for dp in &beatmap.control_points.difficulty_points {
+                writers.timing_points.write(TimingPointRow {
+                    folder_id: folder_id.clone(),
+                    osu_file: osu_filename.clone(),
+                    time: dp.time,
+                    point_type: "difficulty".to_string(),
+                    beat_length: None,
+                    time_signature: None,
+                    slider_velocity: Some(dp.slider_velocity),
+                    kiai: None,
+                    sample_bank: None,
+                    sample_volume: None,
+                    sample_index: None,
+                    order_index: timing_lines.order_index(dp.time, TimingLineKind::Inherited),
+                })?;
+            }
+
+            for ep in &beatmap.control_points.effect_points {
+                writers.timing_points.write(TimingPointRow {
+                    folder_id: folder_id.clone(),
+                    osu_file: osu_filename.clone(),
+                    time: ep.time,
+                    point_type: "effect".to_string(),
+                    beat_length: None,
+                    time_signature: None,
+                    slider_velocity: None,
+                    kiai: Some(ep.kiai),
+                    sample_bank: None,
+                    sample_volume: None,
+                    sample_index: None,
+                    order_index: timing_lines.order_index(ep.time, TimingLineKind::Last),
+                })?;
+            }
 
-        for ep in &beatmap.control_points.effect_points {
-            writers.timing_points.write(TimingPointRow {
-                folder_id: folder_id.clone(),
-                osu_file: osu_filename.clone(),
-                time: ep.time,
-                point_type: "effect".to_string(),
-                beat_length: None,
-                time_signature: None,
-                slider_velocity: None,
-                kiai: Some(ep.kiai),
-                sample_bank: None,
-                sample_volume: None,
-            })?;
+            for sp in &beatmap.control_points.sample_points {
+                writers.timing_points.write(TimingPointRow {
+                    folder_id: folder_id.clone(),
+                    osu_file: osu_filename.clone(),
+                    time: sp.time,
+                    point_type: "sample".to_string(),
+                    beat_length: None,
+                    time_signature: None,
+                    slider_velocity: None,
+                    kiai: None,
+                    sample_bank: Some(format!("{:?}", sp.sample_bank)),
+                    sample_volume: Some(sp.sample_volume),
+                    sample_index: Some(sp.custom_sample_bank),
+                    order_index: timing_lines.order_index(sp.time, TimingLineKind::Last),
+                })?;
+            }
         }
 
         // Write break periods
-        for break_period in &beatmap.breaks {
-            writers.breaks.write(BreakRow {
-                folder_id: folder_id.clone(),
-                osu_file: osu_filename.clone(),
-                start_time: break_period.start_time,
-                end_time: break_period.end_time,
-            })?;
+        if tables.contains("breaks") {
+            for break_period in &beatmap.breaks {
+                writers.breaks.write(BreakRow {
+                    folder_id: folder_id.clone(),
+                    osu_file: osu_filename.clone(),
+                    start_time: break_period.start_time,
+                    end_time: break_period.end_time,
+                })?;
+            }
         }
 
-        // Write combo colors
-        for (idx, color) in beatmap.custom_combo_colors.iter().enumerate() {
-            writers.combo_colors.write(ComboColorRow {
-                folder_id: folder_id.clone(),
-                osu_file: osu_filename.clone(),
-                color_index: idx as i32,
-                color_type: "combo".to_string(),
-                custom_name: None,
-                red: color.red() as i32,
-                green: color.green() as i32,
-                blue: color.blue() as i32,
-            })?;
+        // Write [Events] lines nothing else captures
+        if tables.contains("events") {
+            for event in read_uncovered_events(osu_path)? {
+                writers.events.write(EventRow {
+                    folder_id: folder_id.clone(),
+                    osu_file: osu_filename.clone(),
+                    ..event
+                })?;
+            }
         }
 
-        // Write custom colors (slider track, etc.)
-        for (idx, custom_color) in beatmap.custom_colors.iter().enumerate() {
-            writers.combo_colors.write(ComboColorRow {
-                folder_id: folder_id.clone(),
-                osu_file: osu_filename.clone(),
-                color_index: idx as i32,
-                color_type: "custom".to_string(),
-                custom_name: Some(custom_color.name.clone()),
-                red: custom_color.color.red() as i32,
-                green: custom_color.color.green() as i32,
-                blue: custom_color.color.blue() as i32,
-            })?;
+        // Write [General]/[Metadata] keys rosu-map doesn't model
+        if tables.contains("extra_fields") {
+            for field in read_extra_fields(osu_path)? {
+                writers.extra_fields.write(ExtraFieldRow {
+                    folder_id: folder_id.clone(),
+                    osu_file: osu_filename.clone(),
+                    ..field
+                })?;
+            }
         }
 
-        // Write hit samples for each hit object
-        for (ho_idx, ho) in beatmap.hit_objects.iter().enumerate() {
-            for (sample_idx, sample) in ho.samples.iter().enumerate() {
-                writers.hit_samples.write(HitSampleRow {
+        // Write values of the registered feature extractors
+        if tables.contains("custom_features") {
+            for extractor in custom_features::EXTRACTORS {
+                for (name, value) in extractor.on_beatmap(&beatmap) {
+                    let (value_f64, value_str) = match value {
+                        FeatureValue::Number(number) => (Some(number), None),
+                        FeatureValue::Text(text) => (None, Some(text)),
+                    };
+                    writers.custom_features.write(CustomFeatureRow {
+                        folder_id: folder_id.clone(),
+                        osu_file: osu_filename.clone(),
+                        name,
+                        value_f64,
+                        value_str,
+                    })?;
+                }
+            }
+        }
+
+        if tables.contains("combo_colors") {
+            // Write combo colors
+            for (idx, color) in beatmap.custom_combo_colors.iter().enumerate() {
+                writers.combo_colors.write(ComboColorRow {
+                    folder_id: folder_id.clone(),
+                    osu_file: osu_filename.clone(),
+                    color_index: idx as i32,
+                    color_type: "combo".to_string(),
+                    custom_name: None,
+                    red: color.red() as i32,
+                    green: color.green() as i32,
+                    blue: color.blue() as i32,
+                })?;
+            }
+
+            // Write custom colors (slider track, etc.)
+            for (idx, custom_color) in beatmap.custom_colors.iter().enumerate() {
+                writers.combo_colors.write(ComboColorRow {
                     folder_id: folder_id.clone(),
                     osu_file: osu_filename.clone(),
-                    hit_object_index: ho_idx as i32,
-                    sample_index: sample_idx as i32,
-                    name: format!("{:?}", sample.name),
-                    bank: format!("{:?}", sample.bank),
-                    suffix: sample.suffix.map(|s| s.get().to_string()),
-                    volume: sample.volume,
+                    color_index: idx as i32,
+                    color_type: "custom".to_string(),
+                    custom_name: Some(custom_color.name.clone()),
+                    red: custom_color.color.red() as i32,
+                    green: custom_color.color.green() as i32,
+                    blue: custom_color.color.blue() as i32,
                 })?;
             }
         }
 
+        // Write hit samples for each hit object
+        if tables.contains("hit_samples") && !too_large {
+            for (ho_idx, ho) in beatmap.hit_objects.iter().enumerate() {
+                // A slider's own samples play along its body; its edges carry per-node samples.
+                // Ticks have no samples of their own in .osu (they derive from the body's bank).
+                let mut sample_groups: Vec<(&'static str, Option<i32>, &[HitSampleInfo])> = Vec::new();
+                match &ho.kind {
+                    rosu_map::section::hit_objects::HitObjectKind::Slider(slider) => {
+                        sample_groups.push(("body", None, ho.samples.as_slice()));
+                        let last_node = slider.node_samples.len().saturating_sub(1);
+                        for (node_idx, node) in slider.node_samples.iter().enumerate() {
+                            let scope = match node_idx {
+                                0 => "head",
+                                n if n == last_node => "tail",
+                                _ => "repeat",
+                            };
+                            sample_groups.push((scope, Some(node_idx as i32), node.as_slice()));
+                        }
+                    }
+                    // Spinners sound when they end
+                    rosu_map::section::hit_objects::HitObjectKind::Spinner(_) => {
+                        sample_groups.push(("tail", None, ho.samples.as_slice()))
+                    }
+                    _ => sample_groups.push(("head", None, ho.samples.as_slice())),
+                }
+
+                for (scope, node_index, samples) in sample_groups {
+                    for (sample_idx, sample) in samples.iter().enumerate() {
+                        writers.hit_samples.write(HitSampleRow {
+                            folder_id: folder_id.clone(),
+                            osu_file: osu_filename.clone(),
+                            hit_object_index: ho_idx as i32,
+                            sample_index: sample_idx as i32,
+                            name: format!("{:?}", sample.name),
+                            bank: format!("{:?}", sample.bank),
+                            suffix: sample.suffix.map(|s| s.get().to_string()),
+                            volume: sample.volume,
+                            scope,
+                            node_index,
+                        })?;
+                    }
+                }
+            }
+        }
+
+        timer.lap(Phase::Rows);
+
         // Parse storyboard from .osu file (storyboards are often embedded in .osu files)
-        if let Ok(storyboard) = Storyboard::from_path(osu_path) {
+        let storyboard = if parse_storyboards { Storyboard::from_path(osu_path).ok() } else { None };
+        if let Some(storyboard) = storyboard {
             let mut element_index = 0i32;
             
             use rosu_storyboard::element::ElementKind;
@@ -714,8 +1459,11 @@ fn process_folder_batch(
                     };
                     
                     // Add asset path for sprites/animations/videos
-                    if !element.path.is_empty() {
-                        assets.insert(element.path.clone());
+                    let element_path = normalize_separators(&element.path);
+                    if copy_assets && !element_path.is_empty() {
+                        assets
+                            .entry(element_path.clone())
+                            .or_insert(if element_type == "video" { "video" } else { "storyboard" });
                     }
 
                     writers.storyboard_elements.write(StoryboardElementRow {
@@ -723,7 +1471,8 @@ fn process_folder_batch(
                         source_file: osu_filename.clone(),
                         element_index,
                         layer_name: layer_name.to_string(),
-                        element_path: element.path.clone(),
+                        element_path_original: (element_path != element.path).then(|| element.path.clone()),
+                        element_path,
                         element_type: element_type.to_string(),
                         origin,
                         initial_pos_x,
@@ -735,7 +1484,7 @@ fn process_folder_batch(
                     })?;
 
                     // Write commands for this element
-                    if let Some(tg) = tg {
+                    if let Some(tg) = tg.filter(|_| write_commands) {
                         macro_rules! add_commands {
                             ($cmd_type:expr, $timeline:expr, $format_fn:expr) => {
                                 for cmd in $timeline.commands() {
@@ -828,6 +1577,7 @@ fn process_folder_batch(
                 }
             }
         }
+        timer.lap(Phase::Storyboard);
     }
 
     // Process standalone .osb storyboard files
@@ -837,7 +1587,8 @@ fn process_folder_batch(
         if path.is_file() {
             if let Some(ext) = path.extension() {
                 if ext.to_string_lossy().to_lowercase() == "osb" {
-                    if let Ok(storyboard) = Storyboard::from_path(path) {
+                    let storyboard = if parse_storyboards { Storyboard::from_path(path).ok() } else { None };
+                    if let Some(storyboard) = storyboard {
                         let source_file = path.file_name().unwrap().to_string_lossy().to_string();
                         let mut element_index = 0i32;
 
@@ -880,8 +1631,11 @@ fn process_folder_batch(
                                 };
                                 
                                 // Add asset path for sprites/animations/videos
-                                if !element.path.is_empty() {
-                                    assets.insert(element.path.clone());
+                                let element_path = normalize_separators(&element.path);
+                                if copy_assets && !element_path.is_empty() {
+                                    assets
+                                        .entry(element_path.clone())
+                                        .or_insert(if element_type == "video" { "video" } else { "storyboard" });
                                 }
 
                                 writers.storyboard_elements.write(StoryboardElementRow {
@@ -889,7 +1643,8 @@ fn process_folder_batch(
                                     source_file: source_file.clone(),
                                     element_index,
                                     layer_name: layer_name.to_string(),
-                                    element_path: element.path.clone(),
+                                    element_path_original: (element_path != element.path).then(|| element.path.clone()),
+                                    element_path,
                                     element_type: element_type.to_string(),
                                     origin,
                                     initial_pos_x,
@@ -901,7 +1656,7 @@ fn process_folder_batch(
                                 })?;
 
                                 // Write commands for this element
-                                if let Some(tg) = tg {
+                                if let Some(tg) = tg.filter(|_| write_commands) {
                                     macro_rules! add_commands {
                                         ($cmd_type:expr, $timeline:expr, $format_fn:expr) => {
                                             for cmd in $timeline.commands() {
@@ -999,23 +1754,705 @@ fn process_folder_batch(
         }
     }
 
+    timer.lap(Phase::Storyboard);
+
     // Copy assets
-    fs::create_dir_all(&assets_folder)?;
-    for asset in &assets {
-        let source_path = source_folder.join(asset);
-        let dest_path = assets_folder.join(asset);
-        
-        if source_path.exists() {
-            if let Some(parent) = dest_path.parent() {
-                fs::create_dir_all(parent)?;
+    if !copy_assets {
+        return Ok(());
+    }
+    let assets: Vec<(String, &'static str)> = assets.into_iter().collect();
+    let staging_folder = assets_dir.join(format!(".{}.partial", folder_id));
+    let copied = copy_folder_assets(source_folder, &assets_folder, &staging_folder, &assets, asset_options, cancel);
+    let copied = settle_assets(&staging_folder, &assets_folder, copied, cancel)?;
+    for ((asset, kind), copied) in assets.into_iter().zip(copied) {
+        // Missing source files are referenced but not stored
+        let Some((stored_name, size_bytes)) = copied else {
+            continue;
+        };
+        writers.assets.write(AssetRow {
+            folder_id: folder_id.clone(),
+            original_path: asset,
+            stored_path: format!("assets/{}/{}", folder_id, stored_name),
+            kind: kind.to_string(),
+            size_bytes: size_bytes as i64,
+        })?;
+    }
+    timer.lap(Phase::Assets);
+
+    Ok(())
+}
+
+/// Whether an `AudioFilename` value means the map has no audio file
+///
+/// Storyboard-only and tutorial maps use `virtual` (or leave it empty); no
+/// audio asset is recorded for them.
+fn is_virtual_audio(audio_file: &str) -> bool {
+    let name = audio_file.trim();
+    name.is_empty() || name.eq_ignore_ascii_case("virtual")
+}
+
+/// Use forward slashes in a referenced path (`SB\bg.jpg` -> `SB/bg.jpg`)
+///
+/// osu! accepts either separator, but only `/` joins correctly on every platform.
+fn normalize_separators(path: &str) -> String {
+    path.replace('\\', "/")
+}
+
+/// Resolve a referenced path to its actual on-disk name, matching case-insensitively.
+/// Returns the path unchanged if no matching file exists.
+fn resolve_file_case(source_folder: &Path, referenced: &str) -> String {
+    if referenced.is_empty() {
+        return String::new();
+    }
+
+    let mut current = source_folder.to_path_buf();
+    let mut resolved: Vec<String> = Vec::new();
+
+    for component in referenced.split(['/', '\\']).filter(|c| !c.is_empty()) {
+        let entries: Vec<String> = match fs::read_dir(&current) {
+            Ok(entries) => entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.file_name().to_string_lossy().to_string())
+                .collect(),
+            Err(_) => return referenced.to_string(),
+        };
+
+        // Prefer an exact match, then fall back to a case-insensitive one
+        let matched = entries
+            .iter()
+            .find(|name| name.as_str() == component)
+            .or_else(|| entries.iter().find(|name| name.to_lowercase() == component.to_lowercase()));
+
+        match matched {
+            Some(name) => {
+                current.push(name);
+                resolved.push(name.clone());
             }
-            fs::copy(&source_path, &dest_path)?;
+            None => return referenced.to_string(),
         }
     }
 
-    Ok(())
+    resolved.join("/")
+}
+
+/// Copy a folder's assets into `staging_folder` on up to `options.copy_threads` threads
+///
+/// Assets already up to date in `assets_folder` are not copied again. Returns the
+/// stored name and size of each asset in input order, or None if its source file
+/// doesn't exist.
+fn copy_folder_assets(
+    source_folder: &Path,
+    assets_folder: &Path,
+    staging_folder: &Path,
+    assets: &[(String, &'static str)],
+    options: AssetOptions,
+    cancel: &AtomicBool,
+) -> Result<Vec<Option<(String, u64)>>> {
+    // A staging folder left by an interrupted run is stale
+    if staging_folder.exists() {
+        fs::remove_dir_all(staging_folder)
+            .with_context(|| format!("Failed to remove stale folder: {}", staging_folder.display()))?;
+    }
+    fs::create_dir_all(staging_folder)?;
+    let copy = |(asset, kind): &(String, &'static str)| {
+        check_cancelled(cancel)?;
+        copy_asset(source_folder, assets_folder, staging_folder, asset, kind, options)
+    };
+    let threads = options.copy_threads.min(assets.len());
+    if threads <= 1 {
+        return assets.iter().map(copy).collect();
+    }
+
+    let chunk_size = assets.len().div_ceil(threads);
+    thread::scope(|scope| {
+        let workers: Vec<_> = assets
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk.iter().map(copy).collect::<Result<Vec<_>>>()
+                })
+            })
+            .collect();
+        let mut copied = Vec::with_capacity(assets.len());
+        for worker in workers {
+            copied.extend(worker.join().map_err(|_| anyhow::anyhow!("Asset copy thread panicked"))??);
+        }
+        Ok(copied)
+    })
+}
+
+/// Move a folder's staged assets into `assets_folder` once the worker has finished
+///
+/// Whoever sets `cancel` first decides: a worker that finished copying commits its
+/// staged files, while one that was timed out (see process_folder_with_timeout) or
+/// failed discards them, so an abandoned folder leaves no assets without rows.
+fn settle_assets(
+    staging_folder: &Path,
+    assets_folder: &Path,
+    copied: Result<Vec<Option<(String, u64)>>>,
+    cancel: &AtomicBool,
+) -> Result<Vec<Option<(String, u64)>>> {
+    let copied = match copied {
+        Ok(copied) if !cancel.swap(true, Ordering::SeqCst) => copied,
+        copied => {
+            let _ = fs::remove_dir_all(staging_folder);
+            copied?;
+            anyhow::bail!("Cancelled after timeout");
+        }
+    };
+
+    fs::create_dir_all(assets_folder)?;
+    for entry in WalkDir::new(staging_folder).min_depth(1) {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let dest_path = assets_folder.join(entry.path().strip_prefix(staging_folder)?);
+        if let Some(parent) = dest_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(entry.path(), &dest_path)
+            .with_context(|| format!("Failed to move {} into place", entry.path().display()))?;
+    }
+    fs::remove_dir_all(staging_folder)
+        .with_context(|| format!("Failed to remove staging folder: {}", staging_folder.display()))?;
+    Ok(copied)
+}
+
+/// Copy (or compress) one asset, skipping it if an up-to-date copy already exists
+///
+/// Copies get the source's mtime, so a rerun can tell them apart from stale ones;
+/// compressed and downscaled copies are matched by mtime alone since their size differs.
+fn copy_asset(
+    source_folder: &Path,
+    assets_folder: &Path,
+    staging_folder: &Path,
+    asset: &str,
+    kind: &str,
+    options: AssetOptions,
+) -> Result<Option<(String, u64)>> {
+    let source_path = source_folder.join(asset);
+    let Ok(source_meta) = fs::metadata(&source_path) else {
+        return Ok(None);
+    };
+    let stored_name = stored_asset_name(source_folder, asset, options.compress);
+    let compressed = stored_name != asset;
+    let max_dim = options.background_max_dim.filter(|_| kind == "background");
+
+    if !options.overwrite {
+        if let Ok(dest_meta) = fs::metadata(assets_folder.join(&stored_name)) {
+            let same_mtime = matches!((source_meta.modified(), dest_meta.modified()), (Ok(a), Ok(b)) if a == b);
+            if same_mtime && (compressed || max_dim.is_some() || dest_meta.len() == source_meta.len()) {
+                return Ok(Some((stored_name, dest_meta.len())));
+            }
+        }
+    }
+    let dest_path = staging_folder.join(&stored_name);
+
+    // Storyboard assets may live in subfolders
+    if let Some(parent) = dest_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if let Some(image) = max_dim.and_then(|dim| downscaled_background(&source_path, dim)) {
+        if compressed {
+            zstd::stream::copy_encode(image.as_slice(), File::create(&dest_path)?, ASSET_ZSTD_LEVEL)
+                .with_context(|| format!("Failed to compress: {}", source_path.display()))?;
+        } else {
+            fs::write(&dest_path, image)?;
+        }
+    } else if compressed {
+        let source = File::open(&source_path)?;
+        let dest = File::create(&dest_path)?;
+        zstd::stream::copy_encode(source, &dest, ASSET_ZSTD_LEVEL)
+            .with_context(|| format!("Failed to compress: {}", source_path.display()))?;
+    } else {
+        fs::copy(&source_path, &dest_path)?;
+    }
+    if let Ok(modified) = source_meta.modified() {
+        File::options().write(true).open(&dest_path)?.set_modified(modified)?;
+    }
+    Ok(Some((stored_name, fs::metadata(&dest_path)?.len())))
+}
+
+/// Width and height from an image's header, or `None` if it can't be read
+///
+/// The format comes from the file contents, since backgrounds often carry the
+/// wrong extension (JPEGs named `.png` and the like).
+fn image_dimensions(path: &Path) -> Option<(u32, u32)> {
+    image::ImageReader::open(path).ok()?.with_guessed_format().ok()?.into_dimensions().ok()
+}
+
+/// Background re-encoded in its own format to fit within `max_dim` x `max_dim`
+///
+/// Returns `None` when the image already fits or can't be decoded or re-encoded,
+/// so the original is copied unchanged.
+fn downscaled_background(path: &Path, max_dim: u32) -> Option<Vec<u8>> {
+    let (width, height) = image_dimensions(path)?;
+    if width <= max_dim && height <= max_dim {
+        return None;
+    }
+    let bytes = fs::read(path).ok()?;
+    let format = image::guess_format(&bytes).ok()?;
+    let resized = image::load_from_memory_with_format(&bytes, format)
+        .ok()?
+        .resize(max_dim, max_dim, image::imageops::FilterType::Triangle);
+    // JPEG has no alpha channel
+    let resized = match format {
+        image::ImageFormat::Jpeg => image::DynamicImage::ImageRgb8(resized.to_rgb8()),
+        _ => resized,
+    };
+    let mut encoded = std::io::Cursor::new(Vec::new());
+    resized.write_to(&mut encoded, format).ok()?;
+    Some(encoded.into_inner())
+}
+
+/// Name an asset is stored under: `{asset}.zst` when compression is enabled and worthwhile
+fn stored_asset_name(source_folder: &Path, asset: &str, compress_assets: bool) -> String {
+    if compress_assets && is_compressible_asset(&source_folder.join(asset)) {
+        format!("{}.zst", asset)
+    } else {
+        asset.to_string()
+    }
+}
+
+/// Detect assets that benefit from zstd (uncompressed or lightly compressed formats).
+/// Already-compressed audio (mp3/ogg) and jpg images are stored as-is.
+fn is_compressible_asset(path: &Path) -> bool {
+    match infer::get_from_path(path) {
+        Ok(Some(kind)) => matches!(
+            kind.mime_type(),
+            "image/png" | "image/bmp" | "image/tiff" | "audio/x-wav"
+        ),
+        _ => false,
+    }
+}
+
+/// Difficulties with a storyboard and at most this many hit objects are storyboard-only
+const STORYBOARD_ONLY_MAX_OBJECTS: usize = 5;
+
+/// Storyboard element types that draw something
+const STORYBOARD_SPRITE_TYPES: [&str; 4] = ["4", "Sprite", "6", "Animation"];
+
+/// Whether a `.osu` file's `[Events]` place any storyboard sprite or animation
+fn osu_has_storyboard_sprites(osu_path: &Path) -> Result<bool> {
+    let bytes = fs::read(osu_path).with_context(|| format!("Failed to read: {}", osu_path.display()))?;
+    Ok(has_storyboard_sprites(&osu_text::normalize_osu_text(&bytes), false))
+}
+
+/// Whether any `.osb` file in the folder places a storyboard sprite or animation
+fn osb_has_storyboard_sprites(source_folder: &Path) -> Result<bool> {
+    for entry in WalkDir::new(source_folder).max_depth(1) {
+        let entry = entry?;
+        let path = entry.path();
+        let is_osb = path.extension().is_some_and(|ext| ext.to_string_lossy().eq_ignore_ascii_case("osb"));
+        if is_osb && path.is_file() {
+            let bytes = fs::read(path).with_context(|| format!("Failed to read: {}", path.display()))?;
+            if has_storyboard_sprites(&osu_text::normalize_osu_text(&bytes), true) {
+                return Ok(true);
+            }
+        }
+    }
+    Ok(false)
+}
+
+/// Scan top-level event lines for sprites and animations
+///
+/// `.osu` files only count lines in `[Events]`; every section of an `.osb` holds events.
+fn has_storyboard_sprites(content: &str, whole_file: bool) -> bool {
+    let mut in_events = whole_file;
+    for line in content.lines() {
+        let trimmed = line.trim_end();
+        if trimmed.starts_with('[') {
+            in_events = whole_file || trimmed == "[Events]";
+            continue;
+        }
+        if !in_events || trimmed.starts_with([' ', '\t', '_']) {
+            continue;
+        }
+        let event_type = trimmed.split(',').next().unwrap_or_default().trim();
+        if STORYBOARD_SPRITE_TYPES.contains(&event_type) {
+            return true;
+        }
+    }
+    false
+}
+
+/// Event types stored elsewhere: backgrounds in beatmaps, breaks in breaks, and
+/// storyboard sprites/animations in the storyboard tables
+///
+/// Videos and storyboard samples also have storyboard element rows, but those lack
+/// the video offset and the sample time and volume, so their lines are kept here.
+const STRUCTURED_EVENT_TYPES: [&str; 8] = ["0", "Background", "2", "Break", "4", "Sprite", "6", "Animation"];
+
+/// Collect the top-level `[Events]` lines of a `.osu` file that no structured table covers
+///
+/// Indented lines are storyboard commands and belong to the element above them.
+/// The returned rows have empty folder_id/osu_file for the caller to fill in.
+fn read_uncovered_events(osu_path: &Path) -> Result<Vec<EventRow>> {
+    let bytes = fs::read(osu_path).with_context(|| format!("Failed to read: {}", osu_path.display()))?;
+    let content = osu_text::normalize_osu_text(&bytes);
+
+    let mut events = Vec::new();
+    let mut in_events = false;
+    let mut event_index = 0;
+
+    for line in content.lines() {
+        let trimmed = line.trim_end();
+        if trimmed.starts_with('[') {
+            in_events = trimmed == "[Events]";
+            continue;
+        }
+        if !in_events
+            || trimmed.is_empty()
+            || trimmed.starts_with("//")
+            || trimmed.starts_with([' ', '\t', '_'])
+        {
+            continue;
+        }
+
+        let (event_type, params) = trimmed.split_once(',').unwrap_or((trimmed, ""));
+        if !STRUCTURED_EVENT_TYPES.contains(&event_type) {
+            let start_time = params.split(',').next().and_then(|t| t.trim().parse::<f64>().ok());
+            events.push(EventRow {
+                folder_id: String::new(),
+                osu_file: String::new(),
+                event_index,
+                event_type: event_type.to_string(),
+                start_time,
+                params: params.to_string(),
+            });
+        }
+        event_index += 1;
+    }
+
+    Ok(events)
+}
+
+/// Time and kind of every `[TimingPoints]` line, in file order
+///
+/// rosu-map splits lines into separate timing/difficulty/effect/sample lists, so
+/// each parsed point is matched back to the line it came from by time.
+struct TimingLines(Vec<(f64, bool)>);
+
+/// Which line at a given time a control point comes from
+#[derive(Clone, Copy)]
+enum TimingLineKind {
+    /// Timing points come from uninherited (red) lines
+    Uninherited,
+    /// Difficulty points come from inherited (green) lines
+    Inherited,
+    /// Effect and sample points take the last line at their time, which overrides earlier ones
+    Last,
+}
+
+impl TimingLines {
+    fn read(osu_path: &Path) -> Result<Self> {
+        let bytes = fs::read(osu_path).with_context(|| format!("Failed to read: {}", osu_path.display()))?;
+        let content = osu_text::normalize_osu_text(&bytes);
+
+        let mut lines = Vec::new();
+        let mut in_timing_points = false;
+        for line in content.lines() {
+            let trimmed = line.trim();
+            if trimmed.starts_with('[') {
+                in_timing_points = trimmed == "[TimingPoints]";
+                continue;
+            }
+            if !in_timing_points || trimmed.is_empty() || trimmed.starts_with("//") {
+                continue;
+            }
+
+            let fields: Vec<&str> = trimmed.split(',').collect();
+            let Ok(time) = fields[0].trim().parse::<f64>() else {
+                continue;
+            };
+            // Lines without the uninherited field are timing lines, as in osu!
+            let uninherited = fields.get(6).is_none_or(|field| field.trim().starts_with('1'));
+            lines.push((time, uninherited));
+        }
+        Ok(Self(lines))
+    }
+
+    /// Index of the line a control point at `time` came from
+    fn order_index(&self, time: f64, kind: TimingLineKind) -> Option<i32> {
+        let mut at_time = self.0.iter().enumerate().filter(|(_, (t, _))| *t == time);
+        let index = match kind {
+            TimingLineKind::Uninherited => at_time.find(|(_, (_, uninherited))| *uninherited).map(|(i, _)| i),
+            TimingLineKind::Inherited => {
+                let candidates: Vec<_> = at_time.collect();
+                candidates
+                    .iter()
+                    .find(|(_, (_, uninherited))| !uninherited)
+                    .or(candidates.first())
+                    .map(|(i, _)| *i)
+            }
+            TimingLineKind::Last => at_time.last().map(|(i, _)| i),
+        };
+        index.map(|i| i as i32)
+    }
+}
+
+/// Slider anchors exactly as written in `[HitObjects]`
+///
+/// rosu-map turns a repeated anchor (a red anchor, which starts a new curve
+/// segment) into a single typed point and drops an anchor repeating the head, so
+/// its control points can't be written back as they were. These lists keep every
+/// anchor in file order, relative to the head like rosu-map's points.
+#[derive(Default)]
+struct SliderAnchorLines {
+    /// Head position and anchors of each slider line, in rosu-map's object order
+    sliders: Vec<((f32, f32), Vec<SliderAnchor>)>,
+    /// Index of the next slider not yet taken
+    next: usize,
+}
+
+struct SliderAnchor {
+    pos: (f32, f32),
+    /// Curve type of the segment this anchor starts (the head, or an inline type letter)
+    path_type: Option<&'static str>,
+}
+
+impl SliderAnchorLines {
+    fn read(osu_path: &Path) -> Result<Self> {
+        let bytes = fs::read(osu_path).with_context(|| format!("Failed to read: {}", osu_path.display()))?;
+        let content = osu_text::normalize_osu_text(&bytes);
+
+        let mut sliders = Vec::new();
+        let mut in_hit_objects = false;
+        for line in content.lines() {
+            let trimmed = line.trim();
+            if trimmed.starts_with('[') {
+                in_hit_objects = trimmed == "[HitObjects]";
+                continue;
+            }
+            if !in_hit_objects || trimmed.is_empty() || trimmed.starts_with("//") {
+                continue;
+            }
+
+            let fields: Vec<&str> = trimmed.split(',').collect();
+            if fields.len() < 6 {
+                continue;
+            }
+            let (Ok(x), Ok(y), Ok(time), Ok(kind)) = (
+                fields[0].trim().parse::<f32>(),
+                fields[1].trim().parse::<f32>(),
+                fields[2].trim().parse::<f64>(),
+                fields[3].trim().parse::<i32>(),
+            ) else {
+                continue;
+            };
+            if kind & 2 == 0 {
+                continue;
+            }
+            sliders.push((time, (x, y), parse_slider_anchors(fields[5], (x, y))));
+        }
+
+        // rosu-map orders objects by start time, keeping file order for equal times
+        sliders.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Ok(Self {
+            sliders: sliders.into_iter().map(|(_, head, anchors)| (head, anchors)).collect(),
+            next: 0,
+        })
+    }
+
+    /// Anchors of the next slider line with this head position
+    ///
+    /// Lines rosu-map skipped are passed over; `None` if no later line matches.
+    fn take(&mut self, head: (f32, f32)) -> Option<Vec<SliderAnchor>> {
+        let offset = self.sliders[self.next.min(self.sliders.len())..]
+            .iter()
+            .position(|(pos, _)| *pos == head)?;
+        let idx = self.next + offset;
+        self.next = idx + 1;
+        Some(std::mem::take(&mut self.sliders[idx].1))
+    }
+}
+
+/// Parse a slider curve field (`B|x:y|x:y|...`) into the head and its anchors
+///
+/// The head comes first at (0, 0) with the leading curve type. Type letters
+/// later in the list (lazer's explicit segments) are kept on the anchor after them.
+fn parse_slider_anchors(curve: &str, head: (f32, f32)) -> Vec<SliderAnchor> {
+    let mut anchors = vec![SliderAnchor { pos: (0.0, 0.0), path_type: None }];
+    let mut pending_type = None;
+    for (i, token) in curve.split('|').enumerate() {
+        let token = token.trim();
+        match token.split_once(':') {
+            Some((x, y)) => {
+                let (Ok(x), Ok(y)) = (x.trim().parse::<f32>(), y.trim().parse::<f32>()) else {
+                    continue;
+                };
+                anchors.push(SliderAnchor {
+                    pos: (x - head.0, y - head.1),
+                    path_type: pending_type.take(),
+                });
+            }
+            None => {
+                let path_type = match token.chars().next() {
+                    Some('B') => Some("Bezier"),
+                    Some('L') => Some("Linear"),
+                    Some('C') => Some("Catmull"),
+                    Some('P') => Some("PerfectCurve"),
+                    _ => None,
+                };
+                if i == 0 {
+                    anchors[0].path_type = path_type;
+                } else {
+                    pending_type = path_type;
+                }
+            }
+        }
+    }
+    anchors
+}
+
+/// `[General]` keys rosu-map parses; anything else is captured by `read_extra_fields`
+const KNOWN_GENERAL_KEYS: &[&str] = &[
+    "AudioFilename",
+    "AudioLeadIn",
+    "PreviewTime",
+    "SampleSet",
+    "SampleVolume",
+    "StackLeniency",
+    "Mode",
+    "LetterboxInBreaks",
+    "SpecialStyle",
+    "WidescreenStoryboard",
+    "EpilepsyWarning",
+    "SamplesMatchPlaybackRate",
+    "Countdown",
+    "CountdownOffset",
+    // Read by SkinSettings
+    "OverlayPosition",
+    "SkinPreference",
+    "UseSkinSprites",
+    "AlwaysShowPlayfield",
+];
+
+/// `[General]` skin and overlay keys rosu-map doesn't parse
+///
+/// Most maps don't set them; the defaults are osu!'s. `UseSkinSprites` lets a
+/// storyboard use the player's skin elements, so it changes how the map renders.
+struct SkinSettings {
+    /// Where hit circle numbers/overlays draw relative to the circle: `NoChange`, `Below` or `Above`
+    overlay_position: String,
+    /// Skin the map asks to be played with (empty if none)
+    skin_preference: String,
+    use_skin_sprites: bool,
+    /// Keep the playfield visible in breaks and before the first object
+    always_show_playfield: bool,
 }
 
+impl SkinSettings {
+    fn read(osu_path: &Path) -> Result<Self> {
+        let bytes = fs::read(osu_path).with_context(|| format!("Failed to read: {}", osu_path.display()))?;
+        let content = osu_text::normalize_osu_text(&bytes);
+
+        let mut settings = SkinSettings {
+            overlay_position: "NoChange".to_string(),
+            skin_preference: String::new(),
+            use_skin_sprites: false,
+            always_show_playfield: false,
+        };
+        let mut in_general = false;
+        for line in content.lines() {
+            let trimmed = line.trim();
+            if trimmed.starts_with('[') {
+                in_general = trimmed == "[General]";
+                continue;
+            }
+            if !in_general {
+                continue;
+            }
+            let Some((key, value)) = trimmed.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            match key.trim() {
+                "OverlayPosition" if !value.is_empty() => settings.overlay_position = value.to_string(),
+                "SkinPreference" => settings.skin_preference = value.to_string(),
+                // osu! only checks for a leading 1
+                "UseSkinSprites" => settings.use_skin_sprites = value.starts_with('1'),
+                "AlwaysShowPlayfield" => settings.always_show_playfield = value.starts_with('1'),
+                _ => {}
+            }
+        }
+        Ok(settings)
+    }
+}
+
+/// `[Metadata]` keys rosu-map parses
+const KNOWN_METADATA_KEYS: &[&str] = &[
+    "Title",
+    "TitleUnicode",
+    "Artist",
+    "ArtistUnicode",
+    "Creator",
+    "Version",
+    "Source",
+    "Tags",
+    "BeatmapID",
+    "BeatmapSetID",
+];
+
+/// Collect `[General]`/`[Metadata]` keys of a `.osu` file that rosu-map discards
+/// (e.g. `AudioHash`, `StoryFireInFront`, `SkinPreference` or custom keys)
+///
+/// The returned rows have empty folder_id/osu_file for the caller to fill in.
+fn read_extra_fields(osu_path: &Path) -> Result<Vec<ExtraFieldRow>> {
+    let bytes = fs::read(osu_path).with_context(|| format!("Failed to read: {}", osu_path.display()))?;
+    let content = osu_text::normalize_osu_text(&bytes);
+
+    let mut fields = Vec::new();
+    let mut known: Option<(&str, &[&str])> = None;
+    let mut field_index = 0;
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') {
+            known = match trimmed {
+                "[General]" => Some(("General", KNOWN_GENERAL_KEYS)),
+                "[Metadata]" => Some(("Metadata", KNOWN_METADATA_KEYS)),
+                _ => None,
+            };
+            field_index = 0;
+            continue;
+        }
+        let Some((section, known_keys)) = known else {
+            continue;
+        };
+        if trimmed.starts_with("//") {
+            continue;
+        }
+        let Some((key, value)) = trimmed.split_once(':') else {
+            continue;
+        };
+
+        let key = key.trim();
+        if !known_keys.contains(&key) {
+            fields.push(ExtraFieldRow {
+                folder_id: String::new(),
+                osu_file: String::new(),
+                section: section.to_string(),
+                field_index,
+                key: key.to_string(),
+                value: value.trim().to_string(),
+            });
+        }
+        field_index += 1;
+    }
+
+    Ok(fields)
+}
+
+/// `slides` field of a `.osu` slider line for a repeat count
+///
+/// osu! writes the number of spans (`slides = repeats + 1`) and parses it back as
+/// `repeats = max(slides - 1, 0)`. slider_data stores the repeat count; hit_objects
+/// stores this value so both match what the file says.
+fn slider_slides(repeat_count: i32) -> i32 {
+    repeat_count.max(0) + 1
+}
 
 fn extract_hit_object_info(
     ho: &rosu_map::section::hit_objects::HitObject,
@@ -1036,7 +2473,7 @@ fn extract_hit_object_info(
             Some(s.pos.y as i32),
             s.new_combo,
             None,  // curve_type not directly accessible
-            Some(s.repeat_count),
+            Some(slider_slides(s.repeat_count)),
             s.path.expected_dist().or(Some(0.0)),
             None,
         ),
@@ -1044,9 +2481,9 @@ fn extract_hit_object_info(
             "spinner".to_string(),
             Some(sp.pos.x as i32),
             Some(sp.pos.y as i32),
-            sp.new_combo,
+            sp.new_combo,  // Raw flag; the parser forces it onto the next object
             None, None, None,
-            Some(sp.duration),
+            Some(ho.start_time + sp.duration),  // Absolute end time, not the duration
         ),
         HitObjectKind::Hold(h) => (
             "hold".to_string(),
@@ -1054,18 +2491,598 @@ fn extract_hit_object_info(
             None,  // Hold only has pos_x, no y
             false, // Hold has no new_combo
             None, None, None,
-            Some(h.duration),
+            Some(ho.start_time + h.duration),
         ),
     }
 }
 
+/// Mania key count: the circle size rounded to a whole number of keys (at least one)
+fn mania_key_count(circle_size: f32) -> i32 {
+    circle_size.round().max(1.0) as i32
+}
+
+/// Mania key column of an object at `x` before clamping: `floor(x * keys / 512)`
+fn raw_mania_column(x: i32, keys: i32) -> i32 {
+    (x as f32 * keys as f32 / PLAYFIELD_WIDTH).floor() as i32
+}
+
+/// Mania key column of an object at `x`, clamped to `[0, keys)`
+fn mania_column(x: i32, keys: i32) -> i32 {
+    raw_mania_column(x, keys).clamp(0, keys - 1)
+}
+
+/// Whether any note or hold maps to a column outside `[0, keys)`
+///
+/// osu! clamps such objects into the outer columns, and so does the `column`
+/// column; flagged maps can't be reconstructed from `column` alone.
+fn columns_out_of_range(hit_objects: &[rosu_map::section::hit_objects::HitObject], keys: i32) -> bool {
+    use rosu_map::section::hit_objects::HitObjectKind;
+
+    hit_objects.iter().any(|ho| {
+        let x = match &ho.kind {
+            HitObjectKind::Circle(c) => c.pos.x,
+            HitObjectKind::Slider(s) => s.pos.x,
+            HitObjectKind::Spinner(sp) => sp.pos.x,
+            HitObjectKind::Hold(h) => h.pos_x,
+        };
+        !(0..keys).contains(&raw_mania_column(x as i32, keys))
+    })
+}
+
+/// Playfield width in osu!pixels; x outside `0..=512` counts as offscreen
+const PLAYFIELD_WIDTH: f32 = 512.0;
+/// Playfield height in osu!pixels; y outside `0..=384` counts as offscreen
+const PLAYFIELD_HEIGHT: f32 = 384.0;
+
+/// Whether any object's written position lies outside the playfield
+///
+/// Only the `x,y` of each object line is checked (slider paths may leave the
+/// field legitimately). Holds have no y, so only their x is checked.
+fn has_offscreen_objects(hit_objects: &[rosu_map::section::hit_objects::HitObject]) -> bool {
+    use rosu_map::section::hit_objects::HitObjectKind;
+
+    let x_offscreen = |x: f32| !(0.0..=PLAYFIELD_WIDTH).contains(&x);
+    let y_offscreen = |y: f32| !(0.0..=PLAYFIELD_HEIGHT).contains(&y);
+
+    hit_objects.iter().any(|ho| match &ho.kind {
+        HitObjectKind::Circle(c) => x_offscreen(c.pos.x) || y_offscreen(c.pos.y),
+        HitObjectKind::Slider(s) => x_offscreen(s.pos.x) || y_offscreen(s.pos.y),
+        HitObjectKind::Spinner(sp) => x_offscreen(sp.pos.x) || y_offscreen(sp.pos.y),
+        HitObjectKind::Hold(h) => x_offscreen(h.pos_x),
+    })
+}
+
+/// Total length in ms of all spinners in a difficulty
+fn total_spinner_duration(hit_objects: &[rosu_map::section::hit_objects::HitObject]) -> f64 {
+    use rosu_map::section::hit_objects::HitObjectKind;
+
+    hit_objects
+        .iter()
+        .map(|ho| match &ho.kind {
+            HitObjectKind::Spinner(sp) => sp.duration,
+            _ => 0.0,
+        })
+        .sum()
+}
+
+/// Full spins osu! requires to clear a spinner of `duration` ms at `od`
+///
+/// Follows osu!lazer: the minimum spin rate scales from 1.5 (OD 0) over 2.5 (OD 5)
+/// to 3.75 (OD 10) rotations per second, i.e. 90 to 225 RPM, and the result is truncated.
+fn required_spins(duration: f64, od: f32) -> i32 {
+    let od = od as f64;
+    let rotations_per_second = if od > 5.0 {
+        2.5 + (3.75 - 2.5) * (od - 5.0) / 5.0
+    } else {
+        2.5 - (2.5 - 1.5) * (5.0 - od) / 5.0
+    };
+    (duration / 1000.0 * rotations_per_second) as i32
+}
+
+/// Active time in ms of sliders (all spans, at the velocity resolved from timing points), spinners and holds
+fn extract_duration(ho: &rosu_map::section::hit_objects::HitObject, curve_buffers: &mut CurveBuffers) -> Option<f64> {
+    use rosu_map::section::hit_objects::HitObjectKind;
+
+    match &ho.kind {
+        HitObjectKind::Circle(_) => None,
+        HitObjectKind::Slider(s) => Some(s.duration_with_bufs(curve_buffers)),
+        HitObjectKind::Spinner(sp) => Some(sp.duration),
+        HitObjectKind::Hold(h) => Some(h.duration),
+    }
+}
+
 fn extract_combo_offset(ho: &rosu_map::section::hit_objects::HitObject) -> i32 {
     use rosu_map::section::hit_objects::HitObjectKind;
     
     match &ho.kind {
         HitObjectKind::Circle(c) => c.combo_offset as i32,
         HitObjectKind::Slider(s) => s.combo_offset as i32,
-        HitObjectKind::Spinner(_) => 0,  // Spinners don't have combo offset
+        HitObjectKind::Spinner(_) => 0,  // Parser adds the spinner's offset to the next object
         HitObjectKind::Hold(_) => 0,  // Hold notes don't have combo offset
     }
 }
+
+/// Fresh directory under the system temp directory, for unit tests
+#[cfg(test)]
+fn test_dir() -> PathBuf {
+    use std::sync::atomic::AtomicUsize;
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let dir = std::env::temp_dir().join(format!(
+        "osu-dataset-builder-test-{}-{}",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{ArrayRef, Int32Array, RecordBatch};
+    use osu_text::testutil::SyntheticMap;
+
+    /// Write a synthetic map into its own beatmap folder, with `background` as its `[Events]` background
+    fn map_folder(map: &SyntheticMap, background: Option<&str>) -> PathBuf {
+        let folder = map.write_temp_folder().unwrap();
+        if let Some(background) = background {
+            let content = map.to_osu_string().replace(
+                "//Background and Video events\n",
+                &format!("//Background and Video events\n0,0,\"{}\",0,0\n", background),
+            );
+            fs::write(folder.join(map.file_name()), content).unwrap();
+        }
+        folder
+    }
+
+    /// `map` with its `[HitObjects]` replaced by `lines`, written into its own beatmap folder
+    fn folder_with_objects(map: &SyntheticMap, lines: &[&str]) -> PathBuf {
+        let folder = map.write_temp_folder().unwrap();
+        let content = map.to_osu_string();
+        let header_end = content.find("[HitObjects]\n").unwrap() + "[HitObjects]\n".len();
+        let content = format!("{}{}\n", &content[..header_end], lines.join("\n"));
+        fs::write(folder.join(map.file_name()), content).unwrap();
+        folder
+    }
+
+    /// `map` with its `[TimingPoints]` replaced by `lines`, written into its own beatmap folder
+    fn folder_with_timing_points(map: &SyntheticMap, lines: &[&str]) -> PathBuf {
+        let folder = map.write_temp_folder().unwrap();
+        let content = map.to_osu_string();
+        let start = content.find("[TimingPoints]\n").unwrap() + "[TimingPoints]\n".len();
+        let end = content.find("[HitObjects]\n").unwrap();
+        let content = format!("{}{}\n\n{}", &content[..start], lines.join("\n"), &content[end..]);
+        fs::write(folder.join(map.file_name()), content).unwrap();
+        folder
+    }
+
+    /// Uncompressed, single-threaded asset copies
+    fn plain_assets() -> AssetOptions {
+        AssetOptions {
+            compress: false,
+            overwrite: false,
+            copy_threads: 1,
+            background_max_dim: None,
+        }
+    }
+
+    /// Rows of one folder with every table enabled, assets copied next to the folder
+    fn collect(folder: &Path) -> batch_writer::FolderRows {
+        let tables = batch_writer::TableSelection::new(&[], &[]).unwrap();
+        let assets_dir = folder.with_extension("assets");
+        collect_folder_rows(folder, &assets_dir, plain_assets(), false, usize::MAX, &tables, &AtomicBool::new(false)).unwrap()
+    }
+
+    /// Copy of a fixture folder (relative to this crate), so the assets written next
+    /// to the folder stay out of the source tree
+    fn fixture_folder(relative: &str) -> PathBuf {
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join(relative);
+        let folder = crate::test_dir().join(fixture.file_name().unwrap());
+        fs::create_dir_all(&folder).unwrap();
+        for entry in fs::read_dir(&fixture).unwrap() {
+            let path = entry.unwrap().path();
+            fs::copy(&path, folder.join(path.file_name().unwrap())).unwrap();
+        }
+        folder
+    }
+
+    #[test]
+    fn declared_file_names_are_kept_next_to_their_on_disk_casing() {
+        let map = SyntheticMap {
+            audio_file: "audio.MP3".to_string(),
+            ..SyntheticMap::default()
+        };
+        let folder = map_folder(&map, Some("BG.JPG"));
+        fs::write(folder.join("Audio.mp3"), b"audio").unwrap();
+        fs::write(folder.join("bg.jpg"), b"image").unwrap();
+
+        let rows = collect(&folder);
+        let beatmap = rows.beatmaps.iter().next().unwrap();
+        assert_eq!(beatmap.audio_file, "audio.MP3");
+        assert_eq!(beatmap.audio_file_resolved, "Audio.mp3");
+        assert_eq!(beatmap.background_file, "BG.JPG");
+        assert_eq!(beatmap.background_file_resolved, "bg.jpg");
+        // Assets are stored under the name found on disk
+        assert!(beatmap.audio_path.ends_with("/Audio.mp3"));
+        assert!(beatmap.background_path.ends_with("/bg.jpg"));
+    }
+
+    #[test]
+    fn new_combo_spinner_passes_its_combo_to_the_next_object() {
+        // Spinner with new combo and a skip of 2 (8 | 4 | 2 << 4), then a circle without new combo
+        let folder = folder_with_objects(
+            &SyntheticMap::default(),
+            &["100,100,500,5,0,0:0:0:0:", "256,192,1000,44,0,2000,0:0:0:0:", "200,100,2500,1,0,0:0:0:0:"],
+        );
+
+        let rows = collect(&folder);
+        let objects: Vec<&HitObjectRow> = rows.hit_objects.iter().collect();
+        assert_eq!(objects[1].object_type, "spinner");
+        // The spinner keeps its raw flag but never carries an offset
+        assert!(objects[1].new_combo);
+        assert_eq!(objects[1].combo_offset, 0);
+        // The next circle is forced to start the combo and takes the spinner's skip
+        assert!(objects[2].new_combo);
+        assert_eq!(objects[2].combo_offset, 2);
+    }
+
+    #[test]
+    fn same_time_timing_lines_keep_their_file_order() {
+        // A green line before a red line at 2000
+        let folder = folder_with_timing_points(
+            &SyntheticMap::default(),
+            &["1000,500,4,1,0,60,1,0", "2000,-50,4,1,0,70,0,0", "2000,400,4,1,0,80,1,0"],
+        );
+        let osu_path = folder.join(SyntheticMap::default().file_name());
+        let lines = TimingLines::read(&osu_path).unwrap();
+        assert_eq!(lines.order_index(2000.0, TimingLineKind::Uninherited), Some(2));
+        assert_eq!(lines.order_index(2000.0, TimingLineKind::Inherited), Some(1));
+        assert_eq!(lines.order_index(2000.0, TimingLineKind::Last), Some(2));
+        assert_eq!(lines.order_index(1500.0, TimingLineKind::Last), None);
+
+        let rows = collect(&folder);
+        let order = |time: f64, point_type: &str| -> Vec<Option<i32>> {
+            rows.timing_points
+                .iter()
+                .filter(|tp| tp.time == time && tp.point_type == point_type)
+                .map(|tp| tp.order_index)
+                .collect()
+        };
+        assert_eq!(order(1000.0, "timing"), vec![Some(0)]);
+        assert_eq!(order(2000.0, "timing"), vec![Some(2)]);
+        assert_eq!(order(2000.0, "difficulty"), vec![Some(1)]);
+    }
+
+    #[test]
+    fn cancelled_folder_stops_without_copying_assets() {
+        let folder = map_folder(&SyntheticMap::default(), None);
+        fs::write(folder.join("audio.mp3"), b"audio").unwrap();
+        let tables = batch_writer::TableSelection::new(&[], &[]).unwrap();
+        let assets_dir = folder.with_extension("cancelled-assets");
+
+        let result = collect_folder_rows(&folder, &assets_dir, plain_assets(), false, usize::MAX, &tables, &AtomicBool::new(true));
+        assert!(result.is_err());
+        assert!(!assets_dir.exists());
+    }
+
+    #[test]
+    fn slider_samples_are_scoped_to_body_and_nodes() {
+        // Whistle on the body, whistle on the head, clap on the tail
+        let folder = folder_with_objects(&SyntheticMap::default(), &["100,100,1000,2,2,L|200:100,1,100,2|8,0:0|0:0,0:0:0:0:"]);
+
+        let rows = collect(&folder);
+        let mut samples: Vec<(&str, Option<i32>, String)> = rows
+            .hit_samples
+            .iter()
+            .filter(|sample| !sample.name.contains("Normal"))
+            .map(|sample| (sample.scope, sample.node_index, sample.name.clone()))
+            .collect();
+        samples.sort();
+        assert_eq!(
+            samples,
+            vec![
+                ("body", None, "Default(Whistle)".to_string()),
+                ("head", Some(0), "Default(Whistle)".to_string()),
+                ("tail", Some(1), "Default(Clap)".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn durations_cover_every_span_and_spinner_length() {
+        // 120 BPM with slider multiplier 1.4: 140 px per 500 ms beat
+        let folder = folder_with_objects(
+            &SyntheticMap::default(),
+            &["100,100,1000,2,0,L|200:100,2,100", "256,192,2000,12,0,3500,0:0:0:0:"],
+        );
+
+        let rows = collect(&folder);
+        let objects: Vec<&HitObjectRow> = rows.hit_objects.iter().collect();
+        let slider_duration = objects[0].duration.unwrap();
+        assert!((slider_duration - 2.0 * 100.0 / 140.0 * 500.0).abs() < 1e-6, "{}", slider_duration);
+        assert_eq!(objects[1].duration, Some(1500.0));
+        assert_eq!(objects[1].end_time, Some(3500.0));
+    }
+
+    #[test]
+    fn required_spins_follow_lazer_difficulty_range() {
+        // (int)(seconds * DifficultyRange(OD, 1.5, 2.5, 3.75))
+        assert_eq!(required_spins(2000.0, 0.0), 3);
+        assert_eq!(required_spins(2000.0, 5.0), 5);
+        assert_eq!(required_spins(2000.0, 10.0), 7);
+        // 1.999 s * 2.5 = 4.9975 and 1 s * 1.9 are truncated, not rounded
+        assert_eq!(required_spins(1999.0, 5.0), 4);
+        assert_eq!(required_spins(1000.0, 2.0), 1);
+    }
+
+    #[test]
+    fn only_osu_spinners_get_required_spins() {
+        let map = SyntheticMap { overall_difficulty: 10.0, ..SyntheticMap::default() };
+        let lines = ["256,192,1000,12,0,3000,0:0:0:0:", "256,192,4000,12,0,4500,0:0:0:0:"];
+        let osu = folder_with_objects(&map, &lines);
+
+        let rows = collect(&osu);
+        let spins: Vec<Option<i32>> = rows.hit_objects.iter().map(|ho| ho.required_spins).collect();
+        assert_eq!(spins, [Some(7), Some(1)]);
+        assert_eq!(rows.beatmaps.iter().next().unwrap().total_spinner_duration, 2500.0);
+
+        // Taiko swells have no spin requirement but still count as spinner time
+        let taiko = folder_with_objects(&map, &lines);
+        let path = taiko.join(map.file_name());
+        fs::write(&path, fs::read_to_string(&path).unwrap().replace("Mode: 0", "Mode: 1")).unwrap();
+        let rows = collect(&taiko);
+        assert!(rows.hit_objects.iter().all(|ho| ho.required_spins.is_none()));
+        assert_eq!(rows.beatmaps.iter().next().unwrap().total_spinner_duration, 2500.0);
+    }
+
+    #[test]
+    fn mania_holds_store_their_column_and_absolute_end_time() {
+        let rows = collect(&fixture_folder("../osu-reconstructor/fixtures/mania_4k"));
+        let beatmap = rows.beatmaps.iter().next().unwrap();
+        assert_eq!(beatmap.key_count, Some(4));
+        assert!(!beatmap.columns_out_of_range);
+
+        let objects: Vec<(&str, f64, Option<i32>, Option<f64>)> = rows
+            .hit_objects
+            .iter()
+            .map(|ho| (ho.object_type.as_str(), ho.start_time, ho.column, ho.end_time))
+            .collect();
+        assert_eq!(
+            objects,
+            [
+                ("circle", 1000.0, Some(0), None),
+                ("circle", 1250.0, Some(1), None),
+                ("circle", 1500.0, Some(2), None),
+                ("circle", 1750.0, Some(3), None),
+                ("hold", 2000.0, Some(0), Some(2500.0)),
+                ("hold", 2000.0, Some(1), Some(3000.0)),
+                ("hold", 2250.0, Some(2), Some(2750.0)),
+                ("hold", 2500.0, Some(3), Some(4000.0)),
+                // Off-centre x still lands in its column
+                ("hold", 4250.0, Some(0), Some(4500.0)),
+                ("circle", 4500.0, Some(3), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn mania_x_past_the_last_column_is_clamped_and_flagged() {
+        let rows = collect(&fixture_folder("../osu-reconstructor/fixtures/mania_7k"));
+        let beatmap = rows.beatmaps.iter().next().unwrap();
+        assert_eq!(beatmap.key_count, Some(7));
+        assert!(beatmap.columns_out_of_range);
+
+        let columns: HashSet<i32> = rows.hit_objects.iter().filter_map(|ho| ho.column).collect();
+        assert_eq!(columns, (0..7).collect());
+        // floor(512 * 7 / 512) = 7 is one past the last key; 511 is still inside
+        let column_at = |x: i32| rows.hit_objects.iter().find(|ho| ho.pos_x == Some(x)).unwrap().column;
+        assert_eq!(raw_mania_column(512, 7), 7);
+        assert_eq!(column_at(512), Some(6));
+        assert_eq!(column_at(511), Some(6));
+        assert_eq!(column_at(0), Some(0));
+    }
+
+    #[test]
+    fn objects_outside_the_playfield_flag_the_beatmap() {
+        let offscreen = folder_with_objects(&SyntheticMap::default(), &["100,100,1000,1,0,0:0:0:0:", "600,400,1500,1,0,0:0:0:0:"]);
+        assert!(collect(&offscreen).beatmaps.iter().next().unwrap().has_offscreen_objects);
+
+        // The playfield edges themselves are inside
+        let edges = folder_with_objects(&SyntheticMap::default(), &["0,0,1000,1,0,0:0:0:0:", "512,384,1500,1,0,0:0:0:0:"]);
+        assert!(!collect(&edges).beatmaps.iter().next().unwrap().has_offscreen_objects);
+    }
+
+    #[test]
+    fn unmatched_file_names_resolve_to_themselves() {
+        let folder = map_folder(&SyntheticMap::default(), None);
+
+        let rows = collect(&folder);
+        let beatmap = rows.beatmaps.iter().next().unwrap();
+        assert_eq!(beatmap.audio_file, "audio.mp3");
+        assert_eq!(beatmap.audio_file_resolved, "audio.mp3");
+        assert_eq!(beatmap.background_file_resolved, "");
+    }
+
+    #[test]
+    fn video_and_sample_event_lines_are_kept_as_raw_events() {
+        let osu_path = crate::test_dir().join("events.osu");
+        let content = "osu file format v14\n\n[Events]\n//Background and Video events\n0,0,\"bg.jpg\",0,0\nVideo,-200,\"intro.avi\"\n2,3000,4000\n4,0,0,\"star.png\",320,240\n F,0,0,1000,1,0\n5,1500,0,\"hit.wav\",70\n3,100,163,162,255\n\n[TimingPoints]\n";
+        fs::write(&osu_path, content).unwrap();
+
+        let events = read_uncovered_events(&osu_path).unwrap();
+        let kept: Vec<(i32, &str, Option<f64>)> =
+            events.iter().map(|e| (e.event_index, e.event_type.as_str(), e.start_time)).collect();
+        // Backgrounds, breaks and sprites (with their commands) have tables of their own
+        assert_eq!(kept, vec![(1, "Video", Some(-200.0)), (4, "5", Some(1500.0)), (5, "3", Some(100.0))]);
+        assert_eq!(events[1].params, "1500,0,\"hit.wav\",70");
+    }
+
+    #[test]
+    fn slides_are_stored_as_repeats_plus_one() {
+        let folder = folder_with_objects(
+            &SyntheticMap::default(),
+            &[
+                "100,100,1000,2,0,L|200:100,1,100",
+                "100,100,2000,2,0,L|200:100,2,100",
+                "100,100,4000,2,0,L|200:100,9,100",
+                // osu! reads a zero slides value as a single span
+                "100,100,9000,2,0,L|200:100,0,100",
+            ],
+        );
+
+        let rows = collect(&folder);
+        let slides: Vec<Option<i32>> = rows.hit_objects.iter().map(|o| o.slides).collect();
+        assert_eq!(slides, vec![Some(1), Some(2), Some(9), Some(1)]);
+        let repeats: Vec<i32> = rows.slider_data.iter().map(|s| s.repeat_count).collect();
+        assert_eq!(repeats, vec![0, 1, 8, 0]);
+    }
+
+    #[test]
+    fn red_anchors_are_stored_as_written() {
+        let folder = folder_with_objects(
+            &SyntheticMap::default(),
+            &["100,100,1000,2,0,B|150:100|200:100|200:100|250:150,1,200"],
+        );
+
+        let rows = collect(&folder);
+        let anchors: Vec<(f32, f32, Option<&str>)> = rows
+            .slider_control_points
+            .iter()
+            .map(|cp| (cp.pos_x, cp.pos_y, cp.path_type.as_deref()))
+            .collect();
+        // Relative to the head, the doubled anchor kept twice
+        assert_eq!(
+            anchors,
+            vec![
+                (0.0, 0.0, Some("Bezier")),
+                (50.0, 0.0, None),
+                (100.0, 0.0, None),
+                (100.0, 0.0, None),
+                (150.0, 50.0, None)
+            ]
+        );
+    }
+
+    #[test]
+    fn inline_type_letters_go_on_the_anchor_after_them() {
+        let anchors: Vec<((f32, f32), Option<&str>)> = parse_slider_anchors("P|150:100|L|200:100|x:y", (100.0, 100.0))
+            .into_iter()
+            .map(|anchor| (anchor.pos, anchor.path_type))
+            .collect();
+        assert_eq!(
+            anchors,
+            vec![((0.0, 0.0), Some("PerfectCurve")), ((50.0, 0.0), None), ((100.0, 0.0), Some("Linear"))]
+        );
+    }
+
+    #[test]
+    fn skin_settings_are_read_from_general() {
+        let map = SyntheticMap::default();
+        let folder = map.write_temp_folder().unwrap();
+        let content = map.to_osu_string().replace(
+            "WidescreenStoryboard: 0\n",
+            "WidescreenStoryboard: 0\nUseSkinSprites: 1\nOverlayPosition: Above\nCustomKey: 2\n",
+        );
+        fs::write(folder.join(map.file_name()), content).unwrap();
+
+        let rows = collect(&folder);
+        let beatmap = rows.beatmaps.iter().next().unwrap();
+        assert!(beatmap.use_skin_sprites);
+        assert!(!beatmap.always_show_playfield);
+        assert_eq!(beatmap.overlay_position, "Above");
+        assert_eq!(beatmap.skin_preference, "");
+        // Only keys without a column of their own go to extra_fields
+        let extra: Vec<&str> = rows.extra_fields.iter().map(|f| f.key.as_str()).collect();
+        assert_eq!(extra, vec!["CustomKey"]);
+
+        let plain = collect(&map_folder(&map, None));
+        let beatmap = plain.beatmaps.iter().next().unwrap();
+        assert!(!beatmap.use_skin_sprites);
+        assert_eq!(beatmap.overlay_position, "NoChange");
+    }
+
+    #[test]
+    fn only_the_storyboard_difficulty_is_tagged_storyboard_only() {
+        let rows = collect(&fixture_folder("fixtures/storyboard_only"));
+        let mut tagged: Vec<(String, bool)> =
+            rows.beatmaps.iter().map(|b| (b.version.clone(), b.is_storyboard_only)).collect();
+        tagged.sort();
+        assert_eq!(tagged, vec![("Gameplay".to_string(), false), ("Storyboard".to_string(), true)]);
+    }
+
+    #[test]
+    fn storyboard_sprites_are_found_in_events_or_anywhere_in_an_osb() {
+        let osu = "[General]\nSprite,Background\n\n[Events]\n0,0,\"bg.jpg\",0,0\n Sprite,Foreground\n";
+        assert!(!has_storyboard_sprites(osu, false));
+        assert!(has_storyboard_sprites("[Events]\n//Storyboard Layer 0 (Background)\n6,Background,Centre,\"a.png\",0,0,2,50\n", false));
+        assert!(has_storyboard_sprites("[Variables]\n$x=1\n[Events]\nSprite,Pass,Centre,\"b.png\",320,240\n", true));
+        assert!(!has_storyboard_sprites("[Events]\n5,0,0,\"hit.wav\"\n", true));
+    }
+
+    /// Output directory holding one batch as its beatmaps table
+    fn output_with_beatmaps(columns: Vec<(&str, ArrayRef)>) -> PathBuf {
+        let dir = crate::test_dir();
+        let batch = RecordBatch::try_from_iter(columns).unwrap();
+        let file = File::create(dir.join("beatmaps.parquet")).unwrap();
+        let mut writer = parquet::arrow::ArrowWriter::try_new(file, batch.schema(), None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+        dir
+    }
+
+    #[test]
+    fn existing_folder_ids_tell_an_empty_table_from_a_malformed_one() {
+        let read = |dir: &Path| read_existing_folder_ids(dir, OutputFormat::Parquet);
+        let strings = |ids: Vec<Option<&str>>| Arc::new(StringArray::from(ids)) as ArrayRef;
+
+        // A fresh output directory and a zero-row table have no folders
+        assert!(read(&crate::test_dir()).unwrap().is_empty());
+        assert!(read(&output_with_beatmaps(vec![("folder_id", strings(vec![]))])).unwrap().is_empty());
+        let ids = output_with_beatmaps(vec![("folder_id", strings(vec![Some("a"), None, Some("b"), Some("a")]))]);
+        assert_eq!(read(&ids).unwrap(), HashSet::from(["a".to_string(), "b".to_string()]));
+
+        let missing = output_with_beatmaps(vec![("osu_file", strings(vec![Some("map.osu")]))]);
+        assert!(read(&missing).unwrap_err().to_string().contains("has no folder_id column"));
+        let numeric = output_with_beatmaps(vec![("folder_id", Arc::new(Int32Array::from(vec![1])) as ArrayRef)]);
+        assert!(read(&numeric).unwrap_err().to_string().contains("expected Utf8"));
+        let corrupt = crate::test_dir();
+        fs::write(corrupt.join("beatmaps.parquet"), b"not parquet").unwrap();
+        assert!(read(&corrupt).unwrap_err().to_string().contains("corrupt?"));
+    }
+
+    #[test]
+    fn assets_are_staged_and_moved_into_place() {
+        let folder = map_folder(&SyntheticMap::default(), None);
+        fs::write(folder.join("audio.mp3"), b"audio").unwrap();
+        let folder_id = folder.file_name().unwrap().to_string_lossy().to_string();
+        let assets_dir = folder.with_extension("assets");
+
+        let rows = collect(&folder);
+        assert_eq!(rows.assets.iter().count(), 1);
+        assert_eq!(fs::read(assets_dir.join(&folder_id).join("audio.mp3")).unwrap(), b"audio");
+        assert!(!assets_dir.join(format!(".{}.partial", folder_id)).exists());
+    }
+
+    #[test]
+    fn a_timed_out_worker_discards_its_staged_assets() {
+        let dir = crate::test_dir();
+        let (staging, assets) = (dir.join(".f.partial"), dir.join("f"));
+        fs::create_dir_all(staging.join("sb")).unwrap();
+        fs::write(staging.join("sb/star.png"), b"png").unwrap();
+        let copied = vec![Some(("sb/star.png".to_string(), 3))];
+
+        // The timeout set the flag before the worker finished copying
+        let result = settle_assets(&staging, &assets, Ok(copied.clone()), &AtomicBool::new(true));
+        assert!(result.is_err());
+        assert!(!staging.exists() && !assets.exists());
+
+        fs::create_dir_all(staging.join("sb")).unwrap();
+        fs::write(staging.join("sb/star.png"), b"png").unwrap();
+        let cancel = AtomicBool::new(false);
+        assert_eq!(settle_assets(&staging, &assets, Ok(copied), &cancel).unwrap().len(), 1);
+        assert_eq!(fs::read(assets.join("sb/star.png")).unwrap(), b"png");
+        assert!(!staging.exists());
+        // A timeout arriving now finds the folder settled
+        assert!(cancel.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,196 @@
+//! Dataset manifest written next to the parquet files
+//!
+//! The manifest records the schema version the dataset was written with so
+//! readers know which optional columns to expect, plus the size and blake3
+//! hash of every table file so copies of the dataset can be verified.
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::batch_writer::{DatasetStats, Provenance};
+
+/// Manifest filename inside the output directory
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Current dataset schema version
+///
+/// Bump whenever a table or column is added, and describe the change in SCHEMA.md.
+/// - 1: initial tables
+/// - 2: timing_points sample rows and `sample_index`, assets.parquet, beatmaps `tags_list`
+/// - 3: hit_objects `end_time` is absolute for spinners and holds (was their duration)
+/// - 4: hit_objects `delta_time` and `delta_distance`
+/// - 5: events.parquet with raw `[Events]` lines the other tables don't cover
+/// - 6: hit_samples `scope` and `node_index` (slider head/repeat/tail samples)
+/// - 7: beatmaps `stars_calc`, `max_pp` and `stars_suspicious` (--compute-stars)
+/// - 8: hit_objects `duration`
+/// - 9: beatmaps `has_offscreen_objects`
+/// - 10: hit_objects `column` (mania)
+/// - 11: extra_fields.parquet with unmodelled [General]/[Metadata] keys (--capture-extra)
+/// - 12: timing_points `order_index` ([TimingPoints] line order)
+/// - 13: hit_objects `required_spins`, beatmaps `total_spinner_duration`
+/// - 14: storyboard_elements `element_path` uses `/`, original kept in `element_path_original`
+/// - 15: beatmaps `is_virtual_audio`
+/// - 16: events also holds video (`1`) and storyboard sample (`5`) lines
+/// - 17: hit_objects `slides` is the `.osu` slides value (repeats + 1; was the repeat count)
+/// - 18: slider_control_points holds anchors as written, including repeated (red) anchors
+/// - 19: beatmaps `overlay_position`, `skin_preference`, `use_skin_sprites`, `always_show_playfield`
+/// - 20: beatmaps `skipped_too_large` (--max-objects)
+/// - 21: beatmaps `key_count` and `columns_out_of_range` (mania)
+/// - 22: custom_features.parquet with values of user-defined extractors (--custom-features)
+/// - 23: beatmaps `is_storyboard_only`
+/// - 24: beatmaps `background_width` and `background_height`
+/// - 25: beatmaps `audio_file`/`background_file` as declared, on-disk casing in `*_resolved`
+pub const SCHEMA_VERSION: u32 = 25;
+
+#[derive(Serialize)]
+struct Manifest {
+    schema_version: u32,
+    builder_version: &'static str,
+    /// Row count per table (file stem -> rows)
+    tables: BTreeMap<&'static str, usize>,
+    /// Size and content hash per table file (file name -> checksum)
+    files: BTreeMap<String, FileChecksum>,
+}
+
+#[derive(Serialize)]
+struct FileChecksum {
+    size: u64,
+    /// Hex-encoded blake3 hash of the file contents
+    blake3: String,
+}
+
+/// Commit the builder was compiled from, set by build.rs ("unknown" outside a git checkout)
+const GIT_HASH: &str = env!("OSU_BUILDER_GIT_HASH");
+
+/// Key-value metadata written into the footer of every table file
+///
+/// The input directory is stored as a blake3 hash of its canonical path, so
+/// files built from the same input can be matched without exposing the path.
+pub fn provenance(input_dir: &Path) -> Provenance {
+    let input = input_dir.canonicalize().unwrap_or_else(|_| input_dir.to_path_buf());
+    let built_at = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    Provenance::from([
+        ("osu_dataset.builder_version".to_string(), env!("CARGO_PKG_VERSION").to_string()),
+        ("osu_dataset.git_hash".to_string(), GIT_HASH.to_string()),
+        ("osu_dataset.schema_version".to_string(), SCHEMA_VERSION.to_string()),
+        ("osu_dataset.built_at_unix".to_string(), built_at.to_string()),
+        (
+            "osu_dataset.input_path_blake3".to_string(),
+            blake3::hash(input.to_string_lossy().as_bytes()).to_hex().to_string(),
+        ),
+    ])
+}
+
+/// Schema version of an existing manifest.json
+#[derive(Deserialize)]
+struct ExistingManifest {
+    schema_version: u32,
+}
+
+/// Refuse to append to a dataset written with another schema version
+///
+/// New rows would mix column layouts (or meanings, like v3's absolute end times)
+/// within one table. Datasets without a manifest.json pass; merging still checks
+/// each table's columns.
+pub fn check_appendable(output_dir: &Path) -> Result<()> {
+    let path = output_dir.join(MANIFEST_FILE);
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e).context(format!("Failed to read {}", path.display())),
+    };
+    let existing: ExistingManifest =
+        serde_json::from_str(&content).context(format!("Failed to parse {}", path.display()))?;
+    if existing.schema_version != SCHEMA_VERSION {
+        bail!(
+            "{} is schema version {}, this builder writes version {}; appending would mix both in the same tables. \
+             Build into a new output directory (see SCHEMA.md for the changes)",
+            output_dir.display(),
+            existing.schema_version,
+            SCHEMA_VERSION
+        );
+    }
+    Ok(())
+}
+
+/// Table file extensions the builder can write (see `OutputFormat`)
+const TABLE_EXTENSIONS: [&str; 2] = ["parquet", "arrow"];
+
+/// Write manifest.json describing the dataset after all writers are closed
+pub fn write_manifest(output_dir: &Path, stats: &DatasetStats) -> Result<()> {
+    write_manifest_counts(output_dir, stats.tables().into_iter().collect())
+}
+
+/// Write manifest.json from explicit per-table row counts
+pub fn write_manifest_counts(output_dir: &Path, tables: BTreeMap<&'static str, usize>) -> Result<()> {
+    let files = table_checksums(output_dir, tables.keys().copied())?;
+    let manifest = Manifest {
+        schema_version: SCHEMA_VERSION,
+        builder_version: env!("CARGO_PKG_VERSION"),
+        tables,
+        files,
+    };
+
+    let path = output_dir.join(MANIFEST_FILE);
+    let json = serde_json::to_string_pretty(&manifest)?;
+    std::fs::write(&path, json).context(format!("Failed to write {}", path.display()))?;
+    Ok(())
+}
+
+/// Hash every existing table file; must run after all writers are closed
+fn table_checksums<'a>(
+    output_dir: &Path,
+    tables: impl Iterator<Item = &'a str>,
+) -> Result<BTreeMap<String, FileChecksum>> {
+    let mut files = BTreeMap::new();
+    for table in tables {
+        for ext in TABLE_EXTENSIONS {
+            let name = format!("{}.{}", table, ext);
+            let path = output_dir.join(&name);
+            if !path.exists() {
+                continue;
+            }
+            let file = File::open(&path).context(format!("Failed to open {}", path.display()))?;
+            let size = file.metadata()?.len();
+            let mut hasher = blake3::Hasher::new();
+            hasher.update_reader(file).context(format!("Failed to hash {}", path.display()))?;
+            files.insert(name, FileChecksum { size, blake3: hasher.finalize().to_hex().to_string() });
+        }
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dir_with_manifest(schema_version: u32) -> std::path::PathBuf {
+        let dir = crate::test_dir();
+        let json = format!("{{\"schema_version\": {}, \"tables\": {{}}}}", schema_version);
+        std::fs::write(dir.join(MANIFEST_FILE), json).unwrap();
+        dir
+    }
+
+    #[test]
+    fn appending_to_the_current_schema_is_allowed() {
+        assert!(check_appendable(&dir_with_manifest(SCHEMA_VERSION)).is_ok());
+        // No manifest yet: a fresh output directory
+        assert!(check_appendable(&crate::test_dir()).is_ok());
+    }
+
+    #[test]
+    fn appending_to_another_schema_is_refused() {
+        let err = check_appendable(&dir_with_manifest(SCHEMA_VERSION - 1)).unwrap_err();
+        assert!(err.to_string().contains(&format!("schema version {}", SCHEMA_VERSION - 1)));
+    }
+
+    #[test]
+    fn provenance_records_the_schema_version() {
+        let provenance = provenance(Path::new("."));
+        assert_eq!(provenance["osu_dataset.schema_version"], SCHEMA_VERSION.to_string());
+    }
+}
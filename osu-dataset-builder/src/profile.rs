@@ -0,0 +1,86 @@
+//! `--profile`: where build time goes, summed over all folders
+//!
+//! Phases are timed with a [`Timer`] that is inert unless profiling is on, so
+//! a normal run pays one atomic load per timer and no clock reads.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use osu_batch::say;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Accumulated nanoseconds per phase, indexed by `Phase as usize`
+static TOTALS: [AtomicU64; Phase::ALL.len()] = [const { AtomicU64::new(0) }; Phase::ALL.len()];
+
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Phase {
+    Parse,
+    Stars,
+    Rows,
+    Storyboard,
+    Assets,
+    Flush,
+}
+
+impl Phase {
+    const ALL: [Phase; 6] = [
+        Phase::Parse,
+        Phase::Stars,
+        Phase::Rows,
+        Phase::Storyboard,
+        Phase::Assets,
+        Phase::Flush,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Phase::Parse => "beatmap parsing",
+            Phase::Stars => "star rating",
+            Phase::Rows => "hit objects and other rows",
+            Phase::Storyboard => "storyboard parsing",
+            Phase::Assets => "asset copying",
+            Phase::Flush => "table writing",
+        }
+    }
+}
+
+/// Charges the time since the previous lap to a phase
+pub struct Timer(Option<Instant>);
+
+impl Timer {
+    pub fn start() -> Self {
+        Timer(ENABLED.load(Ordering::Relaxed).then(Instant::now))
+    }
+
+    /// Add the time since `start` or the last lap to `phase`
+    pub fn lap(&mut self, phase: Phase) {
+        if let Some(last) = self.0 {
+            let now = Instant::now();
+            TOTALS[phase as usize].fetch_add((now - last).as_nanos() as u64, Ordering::Relaxed);
+            self.0 = Some(now);
+        }
+    }
+}
+
+/// Print each phase's total and share of the measured time (no-op without `--profile`)
+pub fn print() {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    let totals: Vec<(Phase, Duration)> = Phase::ALL
+        .iter()
+        .map(|&phase| (phase, Duration::from_nanos(TOTALS[phase as usize].load(Ordering::Relaxed))))
+        .collect();
+    let measured: Duration = totals.iter().map(|(_, time)| *time).sum();
+
+    say!("\n=== Profile ===");
+    for (phase, time) in totals {
+        let share = if measured.is_zero() { 0.0 } else { time.as_secs_f64() / measured.as_secs_f64() * 100.0 };
+        say!("  {}: {:.1}s ({:.0}%)", phase.label(), time.as_secs_f64(), share);
+    }
+}
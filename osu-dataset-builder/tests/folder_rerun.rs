@@ -0,0 +1,56 @@
+//! Re-running `--folder` on a folder already in the dataset must not duplicate its rows
+
+use osu_text::testutil::SyntheticMap;
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn temp_root() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("osu-dataset-builder-folder-rerun-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn build(input: &Path, output: &Path, extra: &[&str]) {
+    let status = Command::new(env!("CARGO_BIN_EXE_osu-dataset-builder"))
+        .arg("--input-dir")
+        .arg(input)
+        .arg("--output-dir")
+        .arg(output)
+        .arg("--quiet")
+        .args(extra)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+fn row_count(output: &Path, table: &str) -> i64 {
+    let file = File::open(output.join(format!("{}.parquet", table))).unwrap();
+    SerializedFileReader::new(file).unwrap().metadata().file_metadata().num_rows()
+}
+
+#[test]
+fn rerunning_a_folder_keeps_one_copy_of_its_rows() {
+    let root = temp_root();
+    let (input, output) = (root.join("input"), root.join("dataset"));
+    SyntheticMap { object_count: 12, ..SyntheticMap::default() }.write_to(&input.join("synthetic")).unwrap();
+    SyntheticMap { seed: 1, version: "Other".to_string(), ..SyntheticMap::default() }
+        .write_to(&input.join("other"))
+        .unwrap();
+
+    build(&input, &output, &[]);
+    let (beatmaps, hit_objects) = (row_count(&output, "beatmaps"), row_count(&output, "hit_objects"));
+    assert_eq!(beatmaps, 2);
+
+    // Skipped without --replace
+    build(&input, &output, &["--folder", "synthetic"]);
+    assert_eq!(row_count(&output, "beatmaps"), beatmaps);
+    assert_eq!(row_count(&output, "hit_objects"), hit_objects);
+
+    // Rebuilt in place with --replace
+    build(&input, &output, &["--folder", "synthetic", "--replace"]);
+    assert_eq!(row_count(&output, "beatmaps"), beatmaps);
+    assert_eq!(row_count(&output, "hit_objects"), hit_objects);
+}
@@ -0,0 +1,310 @@
+//! Build a dataset with the real binary, reconstruct it and compare the maps
+//!
+//! Covers the slider edge cases in `osu-reconstructor/fixtures/tricky_sliders`
+//! (inherited SV mid-slider, a zero-length slider, a duplicated red anchor,
+//! perfect and catmull curves, 50 slides), a generated map with velocity
+//! changes, spinners and combos, the mania holds in `mania_4k` and
+//! `mania_7k`, a long `Tags:` line and a storyboard path with backslashes.
+
+use arrow::array::RecordBatch;
+use osu_reconstructor::{FolderReconstructor, ParquetReader};
+use osu_text::testutil::{SyntheticMap, SyntheticObject};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::ArrowWriter;
+use rosu_map::section::hit_objects::{HitObject, HitObjectKind};
+use rosu_map::Beatmap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Largest difference for times, positions and lengths to count as equal
+const EPSILON: f64 = 1e-3;
+
+/// Fresh directory per test, since tests in this file run in parallel
+fn temp_root(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("osu-dataset-builder-round-trip-{}-{}", test, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn copy_folder(from: &Path, to: &Path) {
+    fs::create_dir_all(to).unwrap();
+    for entry in fs::read_dir(from).unwrap() {
+        let entry = entry.unwrap();
+        fs::copy(entry.path(), to.join(entry.file_name())).unwrap();
+    }
+}
+
+fn copy_fixture(name: &str, input: &Path) {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("../osu-reconstructor/fixtures");
+    copy_folder(&fixtures.join(name), &input.join(name));
+}
+
+/// Run the builder binary over `input`, writing the dataset to `<root>/dataset`
+fn build_dataset(root: &Path, input: &Path) -> PathBuf {
+    let dataset = root.join("dataset");
+    let status = Command::new(env!("CARGO_BIN_EXE_osu-dataset-builder"))
+        .arg("--input-dir")
+        .arg(input)
+        .arg("--output-dir")
+        .arg(&dataset)
+        .arg("--quiet")
+        .status()
+        .unwrap();
+    assert!(status.success());
+    dataset
+}
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() <= EPSILON
+}
+
+/// Why two hit objects differ, or None when they match
+fn object_mismatch(original: &HitObject, rebuilt: &HitObject) -> Option<String> {
+    if !close(original.start_time, rebuilt.start_time) {
+        return Some(format!("start time {} vs {}", original.start_time, rebuilt.start_time));
+    }
+    match (&original.kind, &rebuilt.kind) {
+        (HitObjectKind::Circle(a), HitObjectKind::Circle(b)) => (a.pos != b.pos).then(|| format!("position {:?} vs {:?}", a.pos, b.pos)),
+        (HitObjectKind::Hold(a), HitObjectKind::Hold(b)) => {
+            if a.pos_x != b.pos_x {
+                return Some(format!("x {} vs {}", a.pos_x, b.pos_x));
+            }
+            (!close(a.duration, b.duration)).then(|| format!("duration {} vs {}", a.duration, b.duration))
+        }
+        (HitObjectKind::Spinner(a), HitObjectKind::Spinner(b)) => {
+            (!close(a.duration, b.duration)).then(|| format!("duration {} vs {}", a.duration, b.duration))
+        }
+        (HitObjectKind::Slider(a), HitObjectKind::Slider(b)) => {
+            if a.pos != b.pos {
+                return Some(format!("position {:?} vs {:?}", a.pos, b.pos));
+            }
+            if a.repeat_count != b.repeat_count {
+                return Some(format!("repeats {} vs {}", a.repeat_count, b.repeat_count));
+            }
+            let lengths = (a.path.expected_dist(), b.path.expected_dist());
+            let same_length = match lengths {
+                (Some(x), Some(y)) => close(x, y),
+                (x, y) => x == y,
+            };
+            if !same_length {
+                return Some(format!("length {:?} vs {:?}", lengths.0, lengths.1));
+            }
+            let points = |slider: &rosu_map::section::hit_objects::HitObjectSlider| -> Vec<_> {
+                slider.path.control_points().iter().map(|cp| (cp.pos, cp.path_type)).collect()
+            };
+            (points(a) != points(b)).then(|| format!("control points {:?} vs {:?}", points(a), points(b)))
+        }
+        (a, b) => Some(format!("kind {:?} vs {:?}", a, b)),
+    }
+}
+
+#[test]
+fn built_maps_reconstruct_to_the_same_hit_objects() {
+    let root = temp_root("objects");
+    let input = root.join("input");
+    copy_fixture("tricky_sliders", &input);
+    let synthetic = SyntheticMap {
+        seed: 7,
+        object_count: 40,
+        kinds: vec![SyntheticObject::Circle, SyntheticObject::Slider, SyntheticObject::Slider, SyntheticObject::Spinner],
+        velocity_changes: 3,
+        ..SyntheticMap::default()
+    };
+    synthetic.write_to(&input.join("synthetic")).unwrap();
+
+    let dataset = build_dataset(&root, &input);
+
+    let reader = ParquetReader::new(&dataset);
+    let reconstructor = FolderReconstructor::new(dataset.join("assets"));
+    let output = root.join("reconstructed");
+    let mut folder_ids = reader.load_folder_ids().unwrap();
+    folder_ids.sort();
+    assert_eq!(folder_ids, vec!["synthetic", "tricky_sliders"]);
+
+    for folder_id in &folder_ids {
+        let rows = reader.load_dataset_for_folder(folder_id).unwrap();
+        let folder = reconstructor.reconstruct_folder(folder_id, &output, &rows).unwrap();
+        assert_eq!(folder.osu_files.len(), 1);
+
+        for osu_file in &folder.osu_files {
+            let original: Beatmap = rosu_map::from_path(input.join(folder_id).join(osu_file)).unwrap();
+            let rebuilt: Beatmap = rosu_map::from_path(output.join(folder_id).join(osu_file)).unwrap();
+            assert_eq!(original.hit_objects.len(), rebuilt.hit_objects.len(), "{}", osu_file);
+
+            for (index, (a, b)) in original.hit_objects.iter().zip(&rebuilt.hit_objects).enumerate() {
+                if let Some(mismatch) = object_mismatch(a, b) {
+                    panic!("{}/{} object {}: {}", folder_id, osu_file, index, mismatch);
+                }
+            }
+        }
+    }
+}
+
+/// `[HitObjects]` lines of an `.osu` file
+fn hit_object_lines(path: &Path) -> Vec<String> {
+    let content = fs::read_to_string(path).unwrap();
+    content
+        .lines()
+        .skip_while(|line| line.trim() != "[HitObjects]")
+        .skip(1)
+        .filter(|line| !line.trim().is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// `x,y,time,type,hitSound` and the end time of a hold line
+fn hold_fields(line: &str) -> (Vec<&str>, &str) {
+    let fields: Vec<&str> = line.split(',').collect();
+    let end_time = fields[5].split(':').next().unwrap();
+    (fields[..5].to_vec(), end_time)
+}
+
+#[test]
+fn mania_holds_keep_their_columns_and_end_times() {
+    let root = temp_root("mania_4k");
+    let input = root.join("input");
+    copy_fixture("mania_4k", &input);
+    let dataset = build_dataset(&root, &input);
+
+    let reader = ParquetReader::new(&dataset);
+    let rows = reader.load_dataset_for_folder("mania_4k").unwrap();
+    let output = root.join("reconstructed");
+    let reconstructor = FolderReconstructor::new(dataset.join("assets"));
+    let folder = reconstructor.reconstruct_folder("mania_4k", &output, &rows).unwrap();
+    let osu_file = &folder.osu_files[0];
+    let mut objects: Vec<_> = rows.hit_objects.iter().collect();
+    objects.sort_by_key(|ho| ho.index);
+
+    let original = hit_object_lines(&input.join("mania_4k").join(osu_file));
+    let rebuilt = hit_object_lines(&output.join("mania_4k").join(osu_file));
+    assert_eq!(original.len(), rebuilt.len());
+    assert_eq!(objects.len(), rebuilt.len());
+
+    for ((original, rebuilt), row) in original.iter().zip(&rebuilt).zip(objects) {
+        let x: i32 = rebuilt.split(',').next().unwrap().parse().unwrap();
+        // The written x falls back into the stored column
+        assert_eq!(Some((x * 4 / 512).clamp(0, 3)), row.column, "{}", rebuilt);
+        if row.object_type == "hold" {
+            // x,y,time,128,hitSound,endTime:hitSample with the absolute end time
+            let (fields, end_time) = hold_fields(rebuilt);
+            assert_eq!(fields[3], "128");
+            assert_eq!(end_time.parse::<f64>().unwrap(), row.end_time.unwrap());
+            assert_eq!(hold_fields(original), (fields, end_time));
+        }
+    }
+}
+
+#[test]
+fn mania_7k_holds_round_trip_with_clamped_columns() {
+    let root = temp_root("mania_7k");
+    let input = root.join("input");
+    copy_fixture("mania_7k", &input);
+    let dataset = build_dataset(&root, &input);
+
+    let rows = ParquetReader::new(&dataset).load_dataset_for_folder("mania_7k").unwrap();
+    assert!(rows.beatmaps[0].columns_out_of_range);
+    let mut columns: Vec<i32> = rows.hit_objects.iter().filter_map(|ho| ho.column).collect();
+    columns.sort();
+    columns.dedup();
+    assert_eq!(columns, (0..7).collect::<Vec<_>>());
+
+    let output = root.join("reconstructed");
+    let reconstructor = FolderReconstructor::new(dataset.join("assets"));
+    let folder = reconstructor.reconstruct_folder("mania_7k", &output, &rows).unwrap();
+    let osu_file = &folder.osu_files[0];
+    let original: Beatmap = rosu_map::from_path(input.join("mania_7k").join(osu_file)).unwrap();
+    let rebuilt: Beatmap = rosu_map::from_path(output.join("mania_7k").join(osu_file)).unwrap();
+    assert_eq!(original.hit_objects.len(), rebuilt.hit_objects.len());
+    // x is kept as written, so the note at 512 stays outside the keys instead of moving to its clamped column
+    for (index, (a, b)) in original.hit_objects.iter().zip(&rebuilt.hit_objects).enumerate() {
+        if let Some(mismatch) = object_mismatch(a, b) {
+            panic!("mania_7k object {}: {}", index, mismatch);
+        }
+    }
+}
+
+/// The `Tags:` line of an `.osu` file
+fn tags_line(path: &Path) -> String {
+    let content = fs::read_to_string(path).unwrap();
+    content.lines().find(|line| line.starts_with("Tags:")).unwrap().to_string()
+}
+
+/// Rewrite `beatmaps.parquet` without its `tags_list` column, as datasets from before it was added
+fn drop_tags_list(dataset: &Path) {
+    let path = dataset.join("beatmaps.parquet");
+    let reader = ParquetRecordBatchReaderBuilder::try_new(fs::File::open(&path).unwrap()).unwrap().build().unwrap();
+    let batches: Vec<RecordBatch> = reader
+        .map(|batch| {
+            let batch = batch.unwrap();
+            let schema = batch.schema();
+            let keep: Vec<usize> =
+                (0..batch.num_columns()).filter(|&i| schema.field(i).name() != "tags_list").collect();
+            batch.project(&keep).unwrap()
+        })
+        .collect();
+    let mut writer = ArrowWriter::try_new(fs::File::create(&path).unwrap(), batches[0].schema(), None).unwrap();
+    for batch in &batches {
+        writer.write(batch).unwrap();
+    }
+    writer.close().unwrap();
+}
+
+#[test]
+fn many_tags_survive_the_round_trip() {
+    let root = temp_root("tags");
+    let input = root.join("input");
+    let map = SyntheticMap::default();
+    let folder = input.join("tagged");
+    map.write_to(&folder).unwrap();
+    let tags = "touhou  東方  game anime remix  bullet-hell 2hu 'quoted' (brackets) ZUN marisa reimu cirno flandre a b c d e f";
+    let osu_path = folder.join(map.file_name());
+    let content = fs::read_to_string(&osu_path).unwrap().replace("Tags:synthetic", &format!("Tags:{}", tags));
+    fs::write(&osu_path, content).unwrap();
+    let dataset = build_dataset(&root, &input);
+    let split: Vec<String> = tags.split_whitespace().map(str::to_string).collect();
+
+    let rows = ParquetReader::new(&dataset).load_dataset_for_folder("tagged").unwrap();
+    assert_eq!(rows.beatmaps[0].tags, tags);
+    assert_eq!(rows.beatmaps[0].tags_list, split);
+
+    let output = root.join("reconstructed");
+    let reconstructor = FolderReconstructor::new(dataset.join("assets"));
+    let folder_rows = reconstructor.reconstruct_folder("tagged", &output, &rows).unwrap();
+    let osu_file = &folder_rows.osu_files[0];
+    assert_eq!(tags_line(&output.join("tagged").join(osu_file)), tags_line(&osu_path));
+
+    // Without the column the reader derives the list from the raw string
+    drop_tags_list(&dataset);
+    let rows = ParquetReader::new(&dataset).load_dataset_for_folder("tagged").unwrap();
+    assert_eq!(rows.beatmaps[0].tags_list, split);
+}
+
+#[test]
+fn backslashed_storyboard_paths_are_normalized_and_written_back_as_found() {
+    let root = temp_root("storyboard");
+    let input = root.join("input");
+    let folder = input.join("storyboard");
+    SyntheticMap::default().write_to(&folder).unwrap();
+    let osb = "[Events]\n//Background and Video events\n//Storyboard Layer 0 (Background)\n//Storyboard Layer 1 (Fail)\n\
+               //Storyboard Layer 2 (Pass)\n//Storyboard Layer 3 (Foreground)\n\
+               Sprite,Foreground,Centre,\"SB\\elements\\x.png\",320,240\n F,0,1000,2000,0,1\n//Storyboard Sound Samples\n";
+    fs::write(folder.join("storyboard.osb"), osb).unwrap();
+    fs::create_dir_all(folder.join("SB/elements")).unwrap();
+    fs::write(folder.join("SB/elements/x.png"), b"png").unwrap();
+    let dataset = build_dataset(&root, &input);
+
+    let rows = ParquetReader::new(&dataset).load_dataset_for_folder("storyboard").unwrap();
+    let element = rows.storyboard_elements.iter().find(|e| e.source_file == "storyboard.osb").unwrap();
+    assert_eq!(element.element_path, "SB/elements/x.png");
+    assert_eq!(element.element_path_original.as_deref(), Some("SB\\elements\\x.png"));
+
+    let output = root.join("reconstructed");
+    let reconstructor = FolderReconstructor::new(dataset.join("assets"));
+    reconstructor.reconstruct_folder("storyboard", &output, &rows).unwrap();
+    let rebuilt = fs::read_to_string(output.join("storyboard/storyboard.osb")).unwrap();
+    assert!(rebuilt.contains("Sprite,Foreground,Centre,\"SB\\elements\\x.png\","), "{}", rebuilt);
+    // The asset itself lands at the normalized path
+    assert_eq!(fs::read(output.join("storyboard/SB/elements/x.png")).unwrap(), b"png");
+}
@@ -1,42 +1,61 @@
 //! Batch writers for memory-efficient parquet output
 //! Writes data in batches to temp files, then merges with existing on close.
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use arrow::array::*;
+use arrow::compute::filter_record_batch;
 use arrow::datatypes::{DataType, Field, Schema};
 use arrow::record_batch::RecordBatch;
 use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
 use parquet::arrow::ArrowWriter;
 use parquet::file::properties::WriterProperties;
+use std::collections::HashSet;
 use std::fs::{self, File};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use crate::{BeatmapRow, CommentRow};
+use crate::{BeatmapRow, CommentRow, ModSettingsRow};
 
 const BATCH_SIZE: usize = 100;
 
 /// Merge existing parquet file with new temp file, writing result to final path
-fn merge_parquet_files(existing_path: &Path, temp_path: &Path, schema: Arc<Schema>) -> Result<usize> {
-    let mut all_batches: Vec<RecordBatch> = Vec::new();
+///
+/// Existing rows whose `keys` column values also appear in the temp file are dropped,
+/// so re-fetched rows replace their old versions instead of duplicating them.
+fn merge_parquet_files(existing_path: &Path, temp_path: &Path, schema: Arc<Schema>, keys: &[&str]) -> Result<usize> {
+    let mut new_batches: Vec<RecordBatch> = Vec::new();
     
-    // Read existing file if it exists
-    if existing_path.exists() {
-        let file = File::open(existing_path)?;
+    // Read temp file
+    if temp_path.exists() {
+        let file = File::open(temp_path)?;
         let reader = ParquetRecordBatchReaderBuilder::try_new(file)?.build()?;
         for batch in reader {
-            all_batches.push(batch?);
+            new_batches.push(batch?);
         }
     }
+
+    let mut new_keys: HashSet<Vec<u32>> = HashSet::new();
+    for batch in &new_batches {
+        new_keys.extend(row_keys(batch, keys)?.into_iter().flatten());
+    }
+
+    let mut all_batches: Vec<RecordBatch> = Vec::new();
     
-    // Read temp file
-    if temp_path.exists() {
-        let file = File::open(temp_path)?;
+    // Read existing file if it exists, keeping rows that were not re-fetched
+    if existing_path.exists() {
+        let file = File::open(existing_path)?;
         let reader = ParquetRecordBatchReaderBuilder::try_new(file)?.build()?;
         for batch in reader {
-            all_batches.push(batch?);
+            let batch = batch?;
+            let keep: BooleanArray = row_keys(&batch, keys)?
+                .iter()
+                .map(|key| Some(key.as_ref().is_none_or(|key| !new_keys.contains(key))))
+                .collect();
+            all_batches.push(filter_record_batch(&batch, &keep)?);
         }
     }
+
+    all_batches.extend(new_batches);
     
     // Count total rows
     let total_rows: usize = all_batches.iter().map(|b| b.num_rows()).sum();
@@ -64,6 +83,26 @@ fn merge_parquet_files(existing_path: &Path, temp_path: &Path, schema: Arc<Schem
     Ok(total_rows)
 }
 
+/// Key values of every row, None where any key column is null
+fn row_keys(batch: &RecordBatch, keys: &[&str]) -> Result<Vec<Option<Vec<u32>>>> {
+    let columns = keys
+        .iter()
+        .map(|key| key_column(batch, key))
+        .collect::<Result<Vec<_>>>()?;
+    Ok((0..batch.num_rows())
+        .map(|i| columns.iter().map(|column| column.is_valid(i).then(|| column.value(i))).collect())
+        .collect())
+}
+
+fn key_column<'a>(batch: &'a RecordBatch, key: &str) -> Result<&'a UInt32Array> {
+    batch
+        .column_by_name(key)
+        .with_context(|| format!("Missing {} column", key))?
+        .as_any()
+        .downcast_ref::<UInt32Array>()
+        .with_context(|| format!("{} is not UInt32", key))
+}
+
 // ============ Enriched Beatmap Writer ============
 
 pub fn enriched_schema() -> Arc<Schema> {
@@ -152,6 +191,18 @@ pub fn enriched_schema() -> Arc<Schema> {
         
         // PP calculation status
         Field::new("pp_failed", DataType::Utf8, true),
+        
+        // API minus local difficulty settings
+        Field::new("ar_delta", DataType::Float32, true),
+        Field::new("cs_delta", DataType::Float32, true),
+        Field::new("od_delta", DataType::Float32, true),
+        Field::new("hp_delta", DataType::Float32, true),
+        Field::new("local_diverges", DataType::Boolean, true),
+
+        // Judgement counts
+        Field::new("osu_n_slider_ticks", DataType::UInt32, true),
+        Field::new("osu_n_judgements", DataType::UInt32, true),
+        Field::new("combo_diverges", DataType::Boolean, true),
     ]))
 }
 
@@ -258,6 +309,14 @@ impl EnrichedBatchWriter {
                 Arc::new(UInt32Array::from(rows.iter().map(|r| r.mania_n_hold_notes).collect::<Vec<_>>())),
                 Arc::new(BooleanArray::from(rows.iter().map(|r| r.is_convert).collect::<Vec<_>>())),
                 Arc::new(StringArray::from(rows.iter().map(|r| r.pp_failed.as_deref()).collect::<Vec<_>>())),
+                Arc::new(Float32Array::from(rows.iter().map(|r| r.ar_delta).collect::<Vec<_>>())),
+                Arc::new(Float32Array::from(rows.iter().map(|r| r.cs_delta).collect::<Vec<_>>())),
+                Arc::new(Float32Array::from(rows.iter().map(|r| r.od_delta).collect::<Vec<_>>())),
+                Arc::new(Float32Array::from(rows.iter().map(|r| r.hp_delta).collect::<Vec<_>>())),
+                Arc::new(BooleanArray::from(rows.iter().map(|r| r.local_diverges).collect::<Vec<_>>())),
+                Arc::new(UInt32Array::from(rows.iter().map(|r| r.osu_n_slider_ticks).collect::<Vec<_>>())),
+                Arc::new(UInt32Array::from(rows.iter().map(|r| r.osu_n_judgements).collect::<Vec<_>>())),
+                Arc::new(BooleanArray::from(rows.iter().map(|r| r.combo_diverges).collect::<Vec<_>>())),
             ],
         )?;
         
@@ -282,7 +341,7 @@ impl EnrichedBatchWriter {
             return Ok(0);
         }
         
-        merge_parquet_files(&self.final_path, &self.temp_path, self.schema)
+        merge_parquet_files(&self.final_path, &self.temp_path, self.schema, &["beatmap_id"])
     }
 }
 
@@ -395,6 +454,105 @@ impl CommentsBatchWriter {
             return Ok(0);
         }
         
-        merge_parquet_files(&self.final_path, &self.temp_path, self.schema)
+        merge_parquet_files(&self.final_path, &self.temp_path, self.schema, &["comment_id"])
+    }
+}
+
+// ============ Mod Settings Writer ============
+
+pub fn mod_settings_schema() -> Arc<Schema> {
+    Arc::new(Schema::new(vec![
+        Field::new("beatmap_id", DataType::UInt32, false),
+        Field::new("mods", DataType::UInt32, false),
+        Field::new("mods_acronyms", DataType::Utf8, false),
+        Field::new("ar_eff", DataType::Float64, false),
+        Field::new("cs_eff", DataType::Float64, false),
+        Field::new("od_eff", DataType::Float64, false),
+        Field::new("hp_eff", DataType::Float64, false),
+        Field::new("stars", DataType::Float64, false),
+        Field::new("max_pp", DataType::Float64, false),
+    ]))
+}
+
+pub struct ModSettingsBatchWriter {
+    writer: ArrowWriter<File>,
+    buffer: Vec<ModSettingsRow>,
+    total_rows: usize,
+    final_path: PathBuf,
+    temp_path: PathBuf,
+    schema: Arc<Schema>,
+}
+
+impl ModSettingsBatchWriter {
+    pub fn new(path: &Path) -> Result<Self> {
+        let schema = mod_settings_schema();
+        let temp_path = path.with_extension("parquet.tmp");
+        let file = File::create(&temp_path)?;
+        let props = WriterProperties::builder()
+            .set_compression(parquet::basic::Compression::SNAPPY)
+            .build();
+        let writer = ArrowWriter::try_new(file, schema.clone(), Some(props))?;
+
+        Ok(Self {
+            writer,
+            buffer: Vec::with_capacity(BATCH_SIZE),
+            total_rows: 0,
+            final_path: path.to_path_buf(),
+            temp_path,
+            schema,
+        })
+    }
+
+    pub fn write(&mut self, row: ModSettingsRow) -> Result<()> {
+        self.buffer.push(row);
+        if self.buffer.len() >= BATCH_SIZE {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+
+        let rows = &self.buffer;
+        let batch = RecordBatch::try_new(
+            self.schema.clone(),
+            vec![
+                Arc::new(UInt32Array::from_iter_values(rows.iter().map(|r| r.beatmap_id))),
+                Arc::new(UInt32Array::from_iter_values(rows.iter().map(|r| r.mods))),
+                Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.mods_acronyms.as_str()))),
+                Arc::new(Float64Array::from_iter_values(rows.iter().map(|r| r.ar_eff))),
+                Arc::new(Float64Array::from_iter_values(rows.iter().map(|r| r.cs_eff))),
+                Arc::new(Float64Array::from_iter_values(rows.iter().map(|r| r.od_eff))),
+                Arc::new(Float64Array::from_iter_values(rows.iter().map(|r| r.hp_eff))),
+                Arc::new(Float64Array::from_iter_values(rows.iter().map(|r| r.stars))),
+                Arc::new(Float64Array::from_iter_values(rows.iter().map(|r| r.max_pp))),
+            ],
+        )?;
+
+        self.total_rows += self.buffer.len();
+        self.writer.write(&batch)?;
+        self.buffer.clear();
+        Ok(())
+    }
+
+    pub fn close(mut self) -> Result<usize> {
+        self.flush()?;
+        self.writer.close()?;
+
+        if self.total_rows == 0 {
+            let _ = fs::remove_file(&self.temp_path);
+            if self.final_path.exists() {
+                let file = File::open(&self.final_path)?;
+                let reader = ParquetRecordBatchReaderBuilder::try_new(file)?.build()?;
+                let count: usize = reader.map(|b| b.map(|b| b.num_rows()).unwrap_or(0)).sum();
+                return Ok(count);
+            }
+            return Ok(0);
+        }
+
+        merge_parquet_files(&self.final_path, &self.temp_path, self.schema, &["beatmap_id", "mods"])
     }
 }
@@ -0,0 +1,9 @@
+//! osu-enricher: Enrich beatmap data with API metadata and PP calculations
+//!
+//! The library half exposes the PP calculation so other tools can score
+//! arbitrary plays; the binary does the actual dataset enrichment.
+
+/// PP helpers, shared with the dataset builder through the `osu-pp` crate
+pub use osu_pp as pp;
+
+pub use osu_pp::{calc_pp, calc_pp_for_map, load_beatmap};
@@ -11,14 +11,18 @@ use arrow::array::*;
 use clap::Parser;
 use indicatif::{ProgressBar, ProgressStyle};
 use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
-use rosu_pp::{Beatmap as PpBeatmap, Difficulty, Performance};
-use std::collections::HashSet;
+use osu_enricher::pp;
+use rosu_pp::model::hit_object::HitObjectKind;
+use rosu_pp::{Difficulty, Performance};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::path::{Path, PathBuf};
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use futures::stream::{self, StreamExt};
+use osu_batch::summary::{self, RunSummary};
+use osu_batch::say;
 
 /// Enrich beatmap data with osu! API metadata and PP calculations
 #[derive(Parser, Debug)]
@@ -39,6 +43,63 @@ struct Args {
     /// Force re-enrichment even if beatmap already exists in output
     #[arg(long, short)]
     force: bool,
+
+    /// Only enrich these beatmap IDs (comma-separated); requested IDs are re-fetched even if enriched
+    #[arg(long, value_delimiter = ',', value_name = "IDS")]
+    only_ids: Vec<u32>,
+
+    /// Only enrich the beatmap IDs listed in this file (one per line, `#` starts a comment)
+    #[arg(long, value_name = "PATH")]
+    ids_file: Option<PathBuf>,
+
+    /// With --only-ids/--ids-file, skip IDs already present in beatmap_enriched.parquet
+    #[arg(long)]
+    skip_enriched: bool,
+
+    /// Re-fetch enriched beatmaps last updated before this date (YYYY-MM-DD) or still Pending/WIP
+    #[arg(long, value_name = "DATE", value_parser = parse_date, conflicts_with = "force")]
+    refresh_since: Option<i64>,
+
+    /// Also rate each beatmap with these mod combinations (e.g. HR,DT,EZ,HDDT) into beatmap_mod_settings.parquet
+    #[arg(long, value_delimiter = ',', value_name = "MODS", value_parser = pp::parse_mods)]
+    mod_combos: Vec<u32>,
+
+    /// No progress bars or per-beatmap messages; print one JSON summary line at the end
+    #[arg(long, short)]
+    quiet: bool,
+}
+
+/// Parse a YYYY-MM-DD date into a unix timestamp at midnight UTC
+fn parse_date(s: &str) -> Result<i64> {
+    let date = time::Date::parse(s, time::macros::format_description!("[year]-[month]-[day]"))
+        .with_context(|| format!("Expected a YYYY-MM-DD date, got '{}'", s))?;
+    Ok(date.midnight().assume_utc().unix_timestamp())
+}
+
+/// Statuses whose API data is still expected to change regardless of last_updated
+const UNSETTLED_STATUSES: [&str; 2] = ["Pending", "WIP"];
+
+/// Beatmap IDs requested with --only-ids and --ids-file (None if neither was given)
+fn requested_ids(args: &Args) -> Result<Option<HashSet<u32>>> {
+    if args.only_ids.is_empty() && args.ids_file.is_none() {
+        return Ok(None);
+    }
+
+    let mut ids: HashSet<u32> = args.only_ids.iter().copied().collect();
+    if let Some(path) = &args.ids_file {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read ids file: {}", path.display()))?;
+        for line in content.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let id = line.parse::<u32>()
+                .with_context(|| format!("Invalid beatmap id '{}' in {}", line, path.display()))?;
+            ids.insert(id);
+        }
+    }
+    Ok(Some(ids))
 }
 
 fn read_credentials(path: &Path) -> Result<Vec<(u64, String)>> {
@@ -160,8 +221,41 @@ pub(crate) struct BeatmapRow {
     
     // PP calculation status
     pp_failed: Option<String>,  // Reason if PP calculation failed (e.g., "Suspicious map: Density")
+
+    // API minus local .osu difficulty settings (None if the API fetch failed)
+    ar_delta: Option<f32>,
+    cs_delta: Option<f32>,
+    od_delta: Option<f32>,
+    hp_delta: Option<f32>,
+    local_diverges: Option<bool>,
+
+    // Judgement counts from the local .osu (osu! only)
+    osu_n_slider_ticks: Option<u32>,
+    osu_n_judgements: Option<u32>,
+    combo_diverges: Option<bool>,  // osu_n_judgements differs from max_combo_api
 }
 
+/// Difficulty settings of the local `.osu`, read from beatmaps.parquet
+#[derive(Debug, Clone, Copy)]
+struct LocalDifficulty {
+    ar: f32,
+    cs: f32,
+    od: f32,
+    hp: f32,
+}
+
+/// Work for one beatmap folder: difficulties to enrich, then the comments of their beatmapsets
+#[derive(Default)]
+struct MapsetWork<'a> {
+    /// (beatmap_id, folder_id, osu_file, local difficulty) still to fetch
+    beatmaps: Vec<&'a (u32, String, String, LocalDifficulty)>,
+    /// Uncommented beatmapset IDs already known from beatmap_enriched.parquet
+    known_mapset_ids: BTreeSet<u32>,
+}
+
+/// Settings further apart than this count as a divergence (the API rounds to one decimal)
+const DIFFICULTY_DELTA_TOLERANCE: f32 = 0.05;
+
 pub(crate) struct CommentRow {
     beatmapset_id: u32,
     comment_id: u32,
@@ -181,27 +275,58 @@ pub(crate) struct CommentRow {
     deleted_at: Option<i64>,
 }
 
+/// Effective difficulty settings, stars and SS pp for one mod combination
+pub(crate) struct ModSettingsRow {
+    beatmap_id: u32,
+    mods: u32,             // Legacy mod bitflags
+    mods_acronyms: String, // e.g. "HDDT"
+    ar_eff: f64,
+    cs_eff: f64,
+    od_eff: f64,
+    hp_eff: f64,
+    stars: f64,
+    max_pp: f64,
+}
+
 // ============ Main ============
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let args = Arc::new(Args::parse());
+    summary::set_quiet(args.quiet);
+    let requested = requested_ids(&args)?;
 
     // Load API credentials from file
-    println!("Reading credentials from {}...", args.credentials.display());
+    say!("Reading credentials from {}...", args.credentials.display());
     let credentials = read_credentials(&args.credentials)?;
 
-    println!("Initializing {} osu! API clients...", credentials.len());
+    say!("Initializing {} osu! API clients...", credentials.len());
     let pool = clients::OsuClientPool::new(credentials).await?;
 
     // Read existing beatmap IDs from dataset
-    println!("Reading existing beatmap IDs from dataset...");
-    let all_beatmap_ids = read_beatmap_ids(&args.dataset_dir)?;
-    println!("Found {} beatmaps with valid IDs", all_beatmap_ids.len());
+    say!("Reading existing beatmap IDs from dataset...");
+    let mut all_beatmap_ids = read_beatmap_ids(&args.dataset_dir)?;
+    say!("Found {} beatmaps with valid IDs", all_beatmap_ids.len());
+
+    // Restrict to the requested subset, reporting requested IDs the dataset doesn't have
+    if let Some(requested) = &requested {
+        let available: HashSet<u32> = all_beatmap_ids.iter().map(|(id, _, _, _)| *id).collect();
+        let missing = requested.iter().filter(|id| !available.contains(id)).count();
+        all_beatmap_ids.retain(|(id, _, _, _)| requested.contains(id));
+        say!(
+            "Restricting to {} requested IDs ({} not in the dataset were filtered out)",
+            requested.len() - missing,
+            missing
+        );
+    }
 
-    // Read already-enriched beatmap IDs (unless --force)
-    let existing_enriched: HashSet<u32> = if !args.force {
-        read_existing_enriched_ids(&args.dataset_dir)
+    // Read already-enriched beatmap IDs (unless --force, or a requested subset without --skip-enriched)
+    let skip_enriched = !args.force && (requested.is_none() || args.skip_enriched);
+    let existing_enriched: HashSet<u32> = if skip_enriched {
+        match args.refresh_since {
+            Some(since) => read_fresh_enriched_ids(&args.dataset_dir, since),
+            None => read_existing_enriched_ids(&args.dataset_dir),
+        }
     } else {
         HashSet::new()
     };
@@ -226,136 +351,224 @@ async fn main() -> Result<()> {
     let initial_failed_count = failed_id_set.len();
 
     // Filter out already-enriched and failed beatmaps
+    let enriched_ids: HashSet<u32> = if skip_enriched && args.refresh_since.is_some() {
+        read_existing_enriched_ids(&args.dataset_dir)
+    } else {
+        HashSet::new()
+    };
+    let enriched_skip_count = all_beatmap_ids
+        .iter()
+        .filter(|(id, _, _, _)| existing_enriched.contains(id))
+        .count();
     let beatmap_ids: Vec<_> = all_beatmap_ids
         .into_iter()
-        .filter(|(id, _, _)| !existing_enriched.contains(id) && !failed_id_set.contains(id))
+        .filter(|(id, _, _, _)| !existing_enriched.contains(id) && !failed_id_set.contains(id))
         .collect();
 
-    if !existing_enriched.is_empty() {
-        println!("Skipping {} already enriched beatmaps (use --force to re-fetch)", existing_enriched.len());
+    if enriched_skip_count > 0 {
+        say!("Skipping {} already enriched beatmaps (use --force to re-fetch)", enriched_skip_count);
+    }
+    if skip_enriched && args.refresh_since.is_some() {
+        let stale_count = beatmap_ids
+            .iter()
+            .filter(|(id, _, _, _)| enriched_ids.contains(id))
+            .count();
+        say!("Refreshing {} stale beatmaps", stale_count);
     }
     if initial_failed_count > 0 {
-        println!("Skipping {} permanently failed beatmaps", initial_failed_count);
+        say!("Skipping {} permanently failed beatmaps", initial_failed_count);
     }
 
-    if beatmap_ids.is_empty() {
-        println!("No new beatmap IDs to enrich. Exiting.");
-        return Ok(());
+    // Work is grouped by beatmap folder (one beatmapset each) so a set's comments are fetched
+    // right after its difficulties; an interrupted run then loses at most the sets in flight.
+    // Sets enriched in earlier runs whose comments are still missing only fetch comments.
+    let commented: HashSet<u32> = if !args.force {
+        read_existing_commented_beatmapset_ids(&args.dataset_dir)
+    } else {
+        HashSet::new()
+    };
+    if !commented.is_empty() {
+        say!("Skipping comments of {} already-commented beatmapsets", commented.len());
+    }
+    let enriched_sets = read_enriched_beatmapset_ids_by_folder(&args.dataset_dir);
+    let mut sets: BTreeMap<&str, MapsetWork> = BTreeMap::new();
+    for entry in &beatmap_ids {
+        sets.entry(entry.1.as_str()).or_default().beatmaps.push(entry);
+    }
+    for (folder_id, mapset_ids) in &enriched_sets {
+        let uncommented: Vec<u32> = mapset_ids.iter().copied().filter(|id| !commented.contains(id)).collect();
+        if !uncommented.is_empty() {
+            sets.entry(folder_id.as_str()).or_default().known_mapset_ids.extend(uncommented);
+        }
     }
 
-    println!("Enriching {} new beatmaps", beatmap_ids.len());
+    if sets.is_empty() {
+        say!("No new beatmap IDs to enrich and no comments to fetch. Exiting.");
+        return RunSummary::default().print();
+    }
+
+    say!("Enriching {} new beatmaps in {} beatmapsets", beatmap_ids.len(), sets.len());
 
     // Prepare output paths
     let enriched_path = args.dataset_dir.join("beatmap_enriched.parquet");
     let comments_path = args.dataset_dir.join("beatmap_comments.parquet");
+    let mod_settings_path = args.dataset_dir.join("beatmap_mod_settings.parquet");
 
     // Initialize batch writers for streaming output
     let enriched_writer = Arc::new(Mutex::new(batch_writer::EnrichedBatchWriter::new(&enriched_path)?));
     let comments_writer = Arc::new(Mutex::new(batch_writer::CommentsBatchWriter::new(&comments_path)?));
+    let mod_settings_writer = if args.mod_combos.is_empty() {
+        None
+    } else {
+        Some(Arc::new(Mutex::new(batch_writer::ModSettingsBatchWriter::new(&mod_settings_path)?)))
+    };
 
     // Shared thread-safe collections
-    let beatmapset_ids = Arc::new(Mutex::new(HashSet::new()));
+    let commented = Arc::new(Mutex::new(commented));
     let failed_ids = Arc::new(Mutex::new(failed_ids));
+    let compared_count = Arc::new(AtomicUsize::new(0));
+    let diverging_count = Arc::new(AtomicUsize::new(0));
+    let processed_count = Arc::new(AtomicUsize::new(0));
+    let api_failed_count = Arc::new(AtomicUsize::new(0));
 
     // Set up graceful shutdown
     let shutdown_requested = Arc::new(AtomicBool::new(false));
     let shutdown_clone = shutdown_requested.clone();
     ctrlc::set_handler(move || {
-        println!("\n⏳ Ctrl+C received! Finishing current request then stopping...");
+        say!("\n⏳ Ctrl+C received! Finishing current request then stopping...");
         shutdown_clone.store(true, Ordering::SeqCst);
     }).expect("Error setting Ctrl+C handler");
 
     let mut interrupted = false;
 
-    let pb = ProgressBar::new(beatmap_ids.len() as u64);
+    let pb = summary::progress_bar(sets.len() as u64);
     pb.set_style(
         ProgressStyle::default_bar()
-            .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} ({eta}) {msg}")
+            .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} sets ({eta}) {msg}")
             .unwrap()
             .progress_chars("#>-"),
     );
 
-    // Fetch metadata for each beatmap in parallel
+    // Process beatmapsets in parallel; the rate limiters are shared by beatmap and comment requests
     let pool = Arc::new(pool);
     let parallelism = pool.client_count() * 2;
     
-    let mut stream = stream::iter(beatmap_ids.iter())
-        .map(|(beatmap_id, folder_id, osu_file)| {
+    let mut stream = stream::iter(sets.values())
+        .map(|set| {
             let pool = Arc::clone(&pool);
+            let compared_count = Arc::clone(&compared_count);
+            let diverging_count = Arc::clone(&diverging_count);
+            let processed_count = Arc::clone(&processed_count);
+            let api_failed_count = Arc::clone(&api_failed_count);
             let source_dir = args.source_dir.clone();
-            let beatmapset_ids = Arc::clone(&beatmapset_ids);
+            let commented = Arc::clone(&commented);
             let failed_ids = Arc::clone(&failed_ids);
             let enriched_writer = Arc::clone(&enriched_writer);
+            let comments_writer = Arc::clone(&comments_writer);
+            let mod_settings_writer = mod_settings_writer.clone();
+            let mod_combos = &args.mod_combos;
             let shutdown_requested = Arc::clone(&shutdown_requested);
             let pb = pb.clone();
             
             async move {
-                if shutdown_requested.load(Ordering::SeqCst) {
-                    return Ok(());
-                }
+                let mut mapset_ids = set.known_mapset_ids.clone();
 
-                pb.set_message(format!("Fetching {}", beatmap_id));
-                
-                let osu_client = pool.get_next();
-                osu_client.rate_limiter.until_ready().await;
-
-                let mut row = BeatmapRow {
-                    beatmap_id: *beatmap_id,
-                    folder_id: folder_id.clone(),
-                    osu_file: osu_file.clone(),
-                    ..Default::default()
-                };
-
-                match osu_client.client.beatmap().map_id(*beatmap_id).await {
-                    Ok(beatmap) => {
-                        beatmapset_ids.lock().unwrap().insert(beatmap.mapset_id);
-                        
-                        row.beatmapset_id = beatmap.mapset_id;
-                        row.mode = format!("{:?}", beatmap.mode).to_lowercase();
-                        row.version = beatmap.version.clone();
-                        row.url = beatmap.url.clone();
-                        row.status = format!("{:?}", beatmap.status);
-                        row.is_scoreable = beatmap.is_scoreable;
-                        row.convert = beatmap.convert;
-                        row.ar = beatmap.ar;
-                        row.cs = beatmap.cs;
-                        row.od = beatmap.od;
-                        row.hp = beatmap.hp;
-                        row.bpm = beatmap.bpm;
-                        row.count_circles = beatmap.count_circles;
-                        row.count_sliders = beatmap.count_sliders;
-                        row.count_spinners = beatmap.count_spinners;
-                        row.seconds_drain = beatmap.seconds_drain;
-                        row.seconds_total = beatmap.seconds_total;
-                        row.playcount = beatmap.playcount;
-                        row.passcount = beatmap.passcount;
-                        row.max_combo_api = beatmap.max_combo;
-                        row.stars_api = beatmap.stars;
-                        row.checksum = beatmap.checksum.unwrap_or_default();
-                        row.creator_id = beatmap.creator_id;
-                        row.last_updated = Some(beatmap.last_updated.unix_timestamp());
+                for (beatmap_id, folder_id, osu_file, local) in set.beatmaps.iter().copied() {
+                    if shutdown_requested.load(Ordering::SeqCst) {
+                        return Ok(());
                     }
-                    Err(e) => {
-                        let error_str = format!("{}", e);
-                        if error_str.contains("404") || error_str.contains("missing") {
-                            failed_ids.lock().unwrap().insert(format!("{}: {}", beatmap_id, e));
+
+                    pb.set_message(format!("Fetching {}", beatmap_id));
+                    
+                    let osu_client = pool.get_next();
+                    osu_client.rate_limiter.until_ready().await;
+
+                    let mut row = BeatmapRow {
+                        beatmap_id: *beatmap_id,
+                        folder_id: folder_id.clone(),
+                        osu_file: osu_file.clone(),
+                        ..Default::default()
+                    };
+
+                    match osu_client.client.beatmap().map_id(*beatmap_id).await {
+                        Ok(beatmap) => {
+                            mapset_ids.insert(beatmap.mapset_id);
+                            
+                            row.beatmapset_id = beatmap.mapset_id;
+                            row.mode = format!("{:?}", beatmap.mode).to_lowercase();
+                            row.version = beatmap.version.clone();
+                            row.url = beatmap.url.clone();
+                            row.status = format!("{:?}", beatmap.status);
+                            row.is_scoreable = beatmap.is_scoreable;
+                            row.convert = beatmap.convert;
+                            row.ar = beatmap.ar;
+                            row.cs = beatmap.cs;
+                            row.od = beatmap.od;
+                            row.hp = beatmap.hp;
+                            row.bpm = beatmap.bpm;
+                            row.count_circles = beatmap.count_circles;
+                            row.count_sliders = beatmap.count_sliders;
+                            row.count_spinners = beatmap.count_spinners;
+                            row.seconds_drain = beatmap.seconds_drain;
+                            row.seconds_total = beatmap.seconds_total;
+                            row.playcount = beatmap.playcount;
+                            row.passcount = beatmap.passcount;
+                            row.max_combo_api = beatmap.max_combo;
+                            row.stars_api = beatmap.stars;
+                            row.checksum = beatmap.checksum.unwrap_or_default();
+                            row.creator_id = beatmap.creator_id;
+                            row.last_updated = Some(beatmap.last_updated.unix_timestamp());
+
+                            set_difficulty_deltas(&mut row, local);
+                            compared_count.fetch_add(1, Ordering::Relaxed);
+                            if row.local_diverges == Some(true) {
+                                diverging_count.fetch_add(1, Ordering::Relaxed);
+                            }
+                        }
+                        Err(e) => {
+                            api_failed_count.fetch_add(1, Ordering::Relaxed);
+                            let error_str = format!("{}", e);
+                            if error_str.contains("404") || error_str.contains("missing") {
+                                failed_ids.lock().unwrap().insert(format!("{}: {}", beatmap_id, e));
+                            }
+                            pb.println(format!("⚠ Failed to fetch API data for {}: {}", beatmap_id, e));
                         }
-                        pb.println(format!("⚠ Failed to fetch API data for {}: {}", beatmap_id, e));
                     }
-                }
 
-                let osu_path = source_dir.join(&folder_id).join(&osu_file);
-                if osu_path.exists() {
-                    match calculate_difficulty(&osu_path, &mut row) {
-                        Ok(_) => {}
-                        Err(e) => {
-                            row.pp_failed = Some(format!("{}", e));
-                            pb.println(format!("⚠ Failed to calculate PP for {}: {}", osu_file, e));
+                    let osu_path = source_dir.join(folder_id).join(osu_file);
+                    if osu_path.exists() {
+                        match calculate_difficulty(&osu_path, &mut row, mod_combos) {
+                            Ok(mod_settings) => {
+                                if let Some(writer) = &mod_settings_writer {
+                                    let mut writer = writer.lock().unwrap_or_else(|e| e.into_inner());
+                                    for settings in mod_settings {
+                                        writer.write(settings)?;
+                                    }
+                                }
+                            }
+                            Err(e) => {
+                                row.pp_failed = Some(format!("{}", e));
+                                pb.println(format!("⚠ Failed to calculate PP for {}: {}", osu_file, e));
+                            }
                         }
                     }
+
+                    enriched_writer.lock().unwrap_or_else(|e| e.into_inner()).write(row)?;
+                    processed_count.fetch_add(1, Ordering::Relaxed);
+                }
+
+                for beatmapset_id in mapset_ids {
+                    if shutdown_requested.load(Ordering::SeqCst) {
+                        return Ok(());
+                    }
+                    // Claim the set so a mapset split over several folders is only fetched once
+                    if !commented.lock().unwrap_or_else(|e| e.into_inner()).insert(beatmapset_id) {
+                        continue;
+                    }
+                    pb.set_message(format!("Comments of {}", beatmapset_id));
+                    fetch_comments(&pool, &comments_writer, beatmapset_id, &pb).await?;
                 }
 
-                enriched_writer.lock().unwrap_or_else(|e| e.into_inner()).write(row)?;
                 pb.inc(1);
                 Ok::<(), anyhow::Error>(())
             }
@@ -375,138 +588,39 @@ async fn main() -> Result<()> {
     }
     drop(stream); // Release Arc references
 
-    pb.finish_with_message("Beatmap fetching complete");
-
-    // Get ALL beatmapset_ids from enriched data (including previous runs)
-    // Combined with beatmapset_ids from this run
-    let all_enriched_beatmapset_ids = read_all_enriched_beatmapset_ids(&args.dataset_dir);
-    let current_beatmapset_ids = beatmapset_ids.lock().unwrap_or_else(|e| e.into_inner());
-    let all_beatmapset_ids: HashSet<u32> = all_enriched_beatmapset_ids.union(&current_beatmapset_ids).copied().collect();
-    drop(current_beatmapset_ids);
-    
-    // Read already-commented beatmapset_ids (unless --force)
-    let existing_commented: HashSet<u32> = if !args.force {
-        read_existing_commented_beatmapset_ids(&args.dataset_dir)
-    } else {
-        HashSet::new()
-    };
-    
-    // Filter to only new beatmapset_ids
-    let new_beatmapset_ids: Vec<u32> = all_beatmapset_ids
-        .into_iter()
-        .filter(|id| !existing_commented.contains(id))
-        .collect();
-    
-    if !existing_commented.is_empty() {
-        println!("Skipping {} already-commented beatmapsets", existing_commented.len());
-    }
-
-    // Fetch comments for each beatmapset
-    println!("Fetching comments for {} new beatmapsets...", new_beatmapset_ids.len());
-    let pb2 = ProgressBar::new(new_beatmapset_ids.len() as u64);
-    pb2.set_style(
-        ProgressStyle::default_bar()
-            .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} ({eta})")
-            .unwrap()
-            .progress_chars("#>-"),
+    pb.finish_with_message("Enrichment complete");
+    say!(
+        "Local difficulty settings diverge from the API for {} of {} beatmaps",
+        diverging_count.load(Ordering::Relaxed),
+        compared_count.load(Ordering::Relaxed)
     );
 
-    // Fetch comments for each beatmapset in parallel
-    let mut comment_stream = stream::iter(new_beatmapset_ids.iter())
-        .map(|beatmapset_id| {
-            let pool = Arc::clone(&pool);
-            let comments_writer = Arc::clone(&comments_writer);
-            let shutdown_requested = Arc::clone(&shutdown_requested);
-            let pb2 = pb2.clone();
-            let beatmapset_id = *beatmapset_id;
-            
-            async move {
-                if shutdown_requested.load(Ordering::SeqCst) {
-                    return Ok(());
-                }
-
-                let osu_client = pool.get_next();
-                osu_client.rate_limiter.until_ready().await;
-
-                match osu_client.client
-                    .comments()
-                    .commentable_type("beatmapset")
-                    .commentable_id(beatmapset_id)
-                    .await
-                {
-                    Ok(bundle) => {
-                        let to_row = |comment: &rosu_v2::model::comments::Comment, mapset_id: u32| CommentRow {
-                            beatmapset_id: mapset_id,
-                            comment_id: comment.comment_id,
-                            parent_id: comment.parent_id,
-                            user_id: comment.user_id,
-                            legacy_name: comment.legacy_name.as_ref().map(|s| s.to_string()),
-                            message: comment.message.clone(),
-                            message_html: comment.message_html.clone(),
-                            votes_count: comment.votes_count,
-                            replies_count: comment.replies_count,
-                            pinned: comment.pinned,
-                            commentable_type: comment.commentable_type.clone(),
-                            created_at: comment.created_at.unix_timestamp(),
-                            updated_at: comment.updated_at.unix_timestamp(),
-                            edited_at: comment.edited_at.map(|t| t.unix_timestamp()),
-                            edited_by_id: comment.edited_by_id,
-                            deleted_at: comment.deleted_at.map(|t| t.unix_timestamp()),
-                        };
-
-                        let mut writer = comments_writer.lock().unwrap_or_else(|e| e.into_inner());
-                        for comment in &bundle.comments {
-                            writer.write(to_row(comment, beatmapset_id))?;
-                        }
-                        for comment in &bundle.included_comments {
-                            writer.write(to_row(comment, beatmapset_id))?;
-                        }
-                        if let Some(pinned) = &bundle.pinned_comments {
-                            for comment in pinned {
-                                if !bundle.comments.iter().any(|c| c.comment_id == comment.comment_id) {
-                                    writer.write(to_row(comment, beatmapset_id))?;
-                                }
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        pb2.println(format!("⚠ Failed to fetch comments for mapset {}: {}", beatmapset_id, e));
-                    }
-                }
-
-                pb2.inc(1);
-                Ok::<(), anyhow::Error>(())
-            }
-        })
-        .buffer_unordered(parallelism);
-
-    while let Some(res) = comment_stream.next().await {
-        if let Err(e) = res {
-            pb2.println(format!("🛑 Critical error in comment stream: {}", e));
-            break;
-        }
-        if shutdown_requested.load(Ordering::SeqCst) {
-            break;
-        }
-    }
-    drop(comment_stream); // Release Arc references
-
-    pb2.finish_with_message("Comment fetching complete");
-
     // Close batch writers and get totals (handles merge automatically)
-    println!("\n=== Writing Parquet Files ===");
+    say!("\n=== Writing Parquet Files ===");
     
+    let mut rows = BTreeMap::new();
     let enriched_total = match Arc::try_unwrap(enriched_writer) {
         Ok(mutex) => mutex.into_inner().unwrap_or_else(|e| e.into_inner()).close()?,
         Err(_) => anyhow::bail!("Failed to unwrap enriched_writer: active references remain"),
     };
-    println!("  beatmap_enriched.parquet: {} rows", enriched_total);
+    say!("  beatmap_enriched.parquet: {} rows", enriched_total);
+    rows.insert("beatmap_enriched".to_string(), enriched_total);
     
     let comments_total = match Arc::try_unwrap(comments_writer) {
         Ok(mutex) => mutex.into_inner().unwrap_or_else(|e| e.into_inner()).close()?,
         Err(_) => anyhow::bail!("Failed to unwrap comments_writer: active references remain"),
     };
-    println!("  beatmap_comments.parquet: {} rows", comments_total);
+    say!("  beatmap_comments.parquet: {} rows", comments_total);
+    rows.insert("beatmap_comments".to_string(), comments_total);
+
+    if let Some(mod_settings_writer) = mod_settings_writer {
+        let mod_settings_total = match Arc::try_unwrap(mod_settings_writer) {
+            Ok(mutex) => mutex.into_inner().unwrap_or_else(|e| e.into_inner()).close()?,
+            Err(_) => anyhow::bail!("Failed to unwrap mod_settings_writer: active references remain"),
+        };
+        say!("  beatmap_mod_settings.parquet: {} rows", mod_settings_total);
+        rows.insert("beatmap_mod_settings".to_string(), mod_settings_total);
+    }
 
     // Save failed list if there are new failures
     let final_failed_ids = failed_ids.lock().unwrap_or_else(|e| e.into_inner());
@@ -514,26 +628,110 @@ async fn main() -> Result<()> {
     if new_failures > 0 {
         let content: String = final_failed_ids.iter().map(|s| format!("{}\n", s)).collect();
         let _ = std::fs::write(&args.dataset_dir.join("failed_beatmaps.txt"), content);
-        println!("Added {} beatmaps to failed_beatmaps.txt", new_failures);
+        say!("Added {} beatmaps to failed_beatmaps.txt", new_failures);
     }
 
     if interrupted {
-        println!("\n⚠ Run was interrupted by Ctrl+C");
+        say!("\n⚠ Run was interrupted by Ctrl+C");
     } else {
-        println!("\nEnrichment complete!");
+        say!("\nEnrichment complete!");
+    }
+
+    let processed = processed_count.load(Ordering::Relaxed);
+    let failed = api_failed_count.load(Ordering::Relaxed);
+    RunSummary {
+        processed,
+        succeeded: processed - failed,
+        failed,
+        rows,
+        interrupted,
+    }
+    .print()
+}
+
+/// Fetch the comments of one beatmapset and write them
+///
+/// API failures are reported and skipped; only a failed write is returned as an error.
+async fn fetch_comments(
+    pool: &clients::OsuClientPool,
+    comments_writer: &Mutex<batch_writer::CommentsBatchWriter>,
+    beatmapset_id: u32,
+    pb: &ProgressBar,
+) -> Result<()> {
+    let osu_client = pool.get_next();
+    osu_client.rate_limiter.until_ready().await;
+
+    let bundle = match osu_client.client
+        .comments()
+        .commentable_type("beatmapset")
+        .commentable_id(beatmapset_id)
+        .await
+    {
+        Ok(bundle) => bundle,
+        Err(e) => {
+            pb.println(format!("⚠ Failed to fetch comments for mapset {}: {}", beatmapset_id, e));
+            return Ok(());
+        }
+    };
+
+    let to_row = |comment: &rosu_v2::model::comments::Comment| CommentRow {
+        beatmapset_id,
+        comment_id: comment.comment_id,
+        parent_id: comment.parent_id,
+        user_id: comment.user_id,
+        legacy_name: comment.legacy_name.as_ref().map(|s| s.to_string()),
+        message: comment.message.clone(),
+        message_html: comment.message_html.clone(),
+        votes_count: comment.votes_count,
+        replies_count: comment.replies_count,
+        pinned: comment.pinned,
+        commentable_type: comment.commentable_type.clone(),
+        created_at: comment.created_at.unix_timestamp(),
+        updated_at: comment.updated_at.unix_timestamp(),
+        edited_at: comment.edited_at.map(|t| t.unix_timestamp()),
+        edited_by_id: comment.edited_by_id,
+        deleted_at: comment.deleted_at.map(|t| t.unix_timestamp()),
+    };
+
+    let mut writer = comments_writer.lock().unwrap_or_else(|e| e.into_inner());
+    for comment in &bundle.comments {
+        writer.write(to_row(comment))?;
+    }
+    for comment in &bundle.included_comments {
+        writer.write(to_row(comment))?;
+    }
+    if let Some(pinned) = &bundle.pinned_comments {
+        for comment in pinned {
+            if !bundle.comments.iter().any(|c| c.comment_id == comment.comment_id) {
+                writer.write(to_row(comment))?;
+            }
+        }
     }
     Ok(())
 }
 
+/// Store API minus local AR/CS/OD/HP and flag the row if any differs beyond the tolerance
+///
+/// A divergence usually means the local `.osu` is an edited or outdated copy of the ranked map.
+fn set_difficulty_deltas(row: &mut BeatmapRow, local: &LocalDifficulty) {
+    let deltas = [
+        row.ar - local.ar,
+        row.cs - local.cs,
+        row.od - local.od,
+        row.hp - local.hp,
+    ];
+    row.ar_delta = Some(deltas[0]);
+    row.cs_delta = Some(deltas[1]);
+    row.od_delta = Some(deltas[2]);
+    row.hp_delta = Some(deltas[3]);
+    row.local_diverges = Some(deltas.iter().any(|delta| delta.abs() > DIFFICULTY_DELTA_TOLERANCE));
+}
+
 // ============ PP Calculation ============
 
-fn calculate_difficulty(osu_path: &Path, row: &mut BeatmapRow) -> Result<()> {
-    let map = PpBeatmap::from_path(osu_path)?;
-    
-    // Check for suspicious maps
-    if let Err(sus) = map.check_suspicion() {
-        anyhow::bail!("Suspicious map: {:?}", sus);
-    }
+/// Fill the nomod PP columns of `row` and rate the map with each of `mod_combos`
+fn calculate_difficulty(osu_path: &Path, row: &mut BeatmapRow, mod_combos: &[u32]) -> Result<Vec<ModSettingsRow>> {
+    let map = pp::load_beatmap(osu_path)?;
 
     // Calculate difficulty (nomod)
     let diff_attrs = Difficulty::new().calculate(&map);
@@ -559,6 +757,21 @@ fn calculate_difficulty(osu_path: &Path, row: &mut BeatmapRow) -> Result<()> {
             row.osu_ok_hit_window = Some(attrs.ok_hit_window);
             row.osu_meh_hit_window = Some(attrs.meh_hit_window);
             row.osu_n_large_ticks = Some(attrs.n_large_ticks);
+
+            // max_combo counts every judgement: circles, slider heads, ticks, repeats and
+            // tails, and spinners. n_large_ticks counts ticks and repeats together.
+            let repeats: u32 = map
+                .hit_objects
+                .iter()
+                .map(|h| match &h.kind {
+                    HitObjectKind::Slider(slider) => slider.repeats as u32,
+                    _ => 0,
+                })
+                .sum();
+            let n_judgements = attrs.max_combo;
+            row.osu_n_slider_ticks = Some(attrs.n_large_ticks.saturating_sub(repeats));
+            row.osu_n_judgements = Some(n_judgements);
+            row.combo_diverges = row.max_combo_api.map(|api| api != n_judgements);
         }
         rosu_pp::any::DifficultyAttributes::Taiko(attrs) => {
             row.taiko_stamina = Some(attrs.stamina);
@@ -584,7 +797,25 @@ fn calculate_difficulty(osu_path: &Path, row: &mut BeatmapRow) -> Result<()> {
         }
     }
 
-    Ok(())
+    let mod_settings = mod_combos
+        .iter()
+        .map(|&mods| {
+            let rating = pp::mod_rating_for_map(&map, mods);
+            ModSettingsRow {
+                beatmap_id: row.beatmap_id,
+                mods,
+                mods_acronyms: pp::mods_acronyms(mods),
+                ar_eff: rating.ar,
+                cs_eff: rating.cs,
+                od_eff: rating.od,
+                hp_eff: rating.hp,
+                stars: rating.stars,
+                max_pp: rating.max_pp,
+            }
+        })
+        .collect();
+
+    Ok(mod_settings)
 }
 
 // ============ Parquet Reading ============
@@ -619,6 +850,45 @@ fn read_existing_enriched_ids(dataset_dir: &Path) -> HashSet<u32> {
     ids
 }
 
+/// Read enriched beatmap_ids that don't need a --refresh-since re-fetch: last updated at or
+/// after `since` and no longer Pending/WIP. Rows without API data are always re-fetched.
+fn read_fresh_enriched_ids(dataset_dir: &Path, since: i64) -> HashSet<u32> {
+    let enriched_path = dataset_dir.join("beatmap_enriched.parquet");
+    if !enriched_path.exists() {
+        return HashSet::new();
+    }
+
+    let mut ids = HashSet::new();
+
+    if let Ok(file) = File::open(&enriched_path) {
+        if let Ok(reader) = ParquetRecordBatchReaderBuilder::try_new(file) {
+            if let Ok(reader) = reader.build() {
+                for batch in reader.flatten() {
+                    let id_col = batch.column_by_name("beatmap_id")
+                        .and_then(|c| c.as_any().downcast_ref::<UInt32Array>());
+                    let updated_col = batch.column_by_name("last_updated")
+                        .and_then(|c| c.as_any().downcast_ref::<Int64Array>());
+                    let status_col = batch.column_by_name("status")
+                        .and_then(|c| c.as_any().downcast_ref::<StringArray>());
+                    let (Some(id_col), Some(updated_col), Some(status_col)) = (id_col, updated_col, status_col) else {
+                        continue;
+                    };
+                    for i in 0..batch.num_rows() {
+                        if id_col.is_null(i) || updated_col.is_null(i) || status_col.is_null(i) {
+                            continue;
+                        }
+                        if updated_col.value(i) >= since && !UNSETTLED_STATUSES.contains(&status_col.value(i)) {
+                            ids.insert(id_col.value(i));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    ids
+}
+
 /// Read existing commented beatmapset_ids from beatmap_comments.parquet
 fn read_existing_commented_beatmapset_ids(dataset_dir: &Path) -> HashSet<u32> {
     let comments_path = dataset_dir.join("beatmap_comments.parquet");
@@ -649,27 +919,32 @@ fn read_existing_commented_beatmapset_ids(dataset_dir: &Path) -> HashSet<u32> {
     ids
 }
 
-/// Read all beatmapset_ids from beatmap_enriched.parquet
-fn read_all_enriched_beatmapset_ids(dataset_dir: &Path) -> HashSet<u32> {
+/// Read the beatmapset_ids in beatmap_enriched.parquet, keyed by folder_id
+fn read_enriched_beatmapset_ids_by_folder(dataset_dir: &Path) -> HashMap<String, HashSet<u32>> {
     let enriched_path = dataset_dir.join("beatmap_enriched.parquet");
     if !enriched_path.exists() {
-        return HashSet::new();
+        return HashMap::new();
     }
 
-    let mut ids = HashSet::new();
+    let mut ids: HashMap<String, HashSet<u32>> = HashMap::new();
     
     if let Ok(file) = File::open(&enriched_path) {
         if let Ok(reader) = ParquetRecordBatchReaderBuilder::try_new(file) {
             if let Ok(reader) = reader.build() {
                 for batch in reader.flatten() {
-                    if let Some(col) = batch.column_by_name("beatmapset_id") {
-                        if let Some(arr) = col.as_any().downcast_ref::<arrow::array::UInt32Array>() {
-                            for i in 0..arr.len() {
-                                if !arr.is_null(i) {
-                                    ids.insert(arr.value(i));
-                                }
-                            }
+                    let set_col = batch.column_by_name("beatmapset_id")
+                        .and_then(|c| c.as_any().downcast_ref::<UInt32Array>());
+                    let folder_col = batch.column_by_name("folder_id")
+                        .and_then(|c| c.as_any().downcast_ref::<StringArray>());
+                    let (Some(set_col), Some(folder_col)) = (set_col, folder_col) else {
+                        continue;
+                    };
+                    for i in 0..batch.num_rows() {
+                        // Rows whose API fetch failed have no beatmapset (0)
+                        if set_col.is_null(i) || folder_col.is_null(i) || set_col.value(i) == 0 {
+                            continue;
                         }
+                        ids.entry(folder_col.value(i).to_string()).or_default().insert(set_col.value(i));
                     }
                 }
             }
@@ -679,7 +954,8 @@ fn read_all_enriched_beatmapset_ids(dataset_dir: &Path) -> HashSet<u32> {
     ids
 }
 
-fn read_beatmap_ids(dataset_dir: &Path) -> Result<Vec<(u32, String, String)>> {
+/// Read (beatmap_id, folder_id, osu_file, local difficulty) of every beatmap with an ID
+fn read_beatmap_ids(dataset_dir: &Path) -> Result<Vec<(u32, String, String, LocalDifficulty)>> {
     let beatmaps_path = dataset_dir.join("beatmaps.parquet");
     let file = File::open(&beatmaps_path)
         .with_context(|| format!("Failed to open {}", beatmaps_path.display()))?;
@@ -714,6 +990,11 @@ fn read_beatmap_ids(dataset_dir: &Path) -> Result<Vec<(u32, String, String)>> {
             .downcast_ref::<StringArray>()
             .context("osu_file is not String")?;
 
+        let ar_col = float32_column(&batch, "approach_rate")?;
+        let cs_col = float32_column(&batch, "circle_size")?;
+        let od_col = float32_column(&batch, "overall_difficulty")?;
+        let hp_col = float32_column(&batch, "hp_drain_rate")?;
+
         for i in 0..batch.num_rows() {
             let beatmap_id = beatmap_id_col.value(i);
             if beatmap_id > 0 {
@@ -721,6 +1002,12 @@ fn read_beatmap_ids(dataset_dir: &Path) -> Result<Vec<(u32, String, String)>> {
                     beatmap_id as u32,
                     folder_id_col.value(i).to_string(),
                     osu_file_col.value(i).to_string(),
+                    LocalDifficulty {
+                        ar: ar_col.value(i),
+                        cs: cs_col.value(i),
+                        od: od_col.value(i),
+                        hp: hp_col.value(i),
+                    },
                 ));
             }
         }
@@ -728,3 +1015,65 @@ fn read_beatmap_ids(dataset_dir: &Path) -> Result<Vec<(u32, String, String)>> {
 
     Ok(results)
 }
+
+fn float32_column<'a>(batch: &'a RecordBatch, name: &str) -> Result<&'a Float32Array> {
+    batch
+        .column_by_name(name)
+        .with_context(|| format!("Missing {} column", name))?
+        .as_any()
+        .downcast_ref::<Float32Array>()
+        .with_context(|| format!("{} is not Float32", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 120 BPM at slider multiplier 1.4 and tick rate 1: one tick every 140 px
+    const TICKS_MAP: &str = "osu file format v14
+
+[General]
+AudioFilename: audio.mp3
+Mode: 0
+
+[Difficulty]
+HPDrainRate:5
+CircleSize:4
+OverallDifficulty:8
+ApproachRate:9
+SliderMultiplier:1.4
+SliderTickRate:1
+
+[TimingPoints]
+0,500,4,1,0,100,1,0
+
+[HitObjects]
+64,64,1000,1,0,0:0:0:0:
+100,100,2000,2,0,L|450:100,2,350
+100,300,6000,2,0,L|200:300,1,100
+256,192,8000,12,0,9000,0:0:0:0:
+";
+
+    #[test]
+    fn judgements_count_ticks_and_repeats() {
+        let dir = std::env::temp_dir().join(format!("osu-enricher-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let osu_path = dir.join("ticks.osu");
+        std::fs::write(&osu_path, TICKS_MAP).unwrap();
+
+        let mut row = BeatmapRow { max_combo_api: Some(11), ..BeatmapRow::default() };
+        calculate_difficulty(&osu_path, &mut row, &[]).unwrap();
+
+        // 350 px spans tick at 140 and 280 px, twice; the 100 px slider has none
+        assert_eq!(row.osu_n_large_ticks, Some(5));
+        assert_eq!(row.osu_n_slider_ticks, Some(4));
+        // Circle 1 + long slider (head, 4 ticks, repeat, tail) 7 + short slider 2 + spinner 1
+        assert_eq!(row.osu_n_judgements, Some(11));
+        assert_eq!(row.max_combo_calc, 11);
+        assert_eq!(row.combo_diverges, Some(false));
+
+        let mut edited = BeatmapRow { max_combo_api: Some(12), ..BeatmapRow::default() };
+        calculate_difficulty(&osu_path, &mut edited, &[]).unwrap();
+        assert_eq!(edited.combo_diverges, Some(true));
+    }
+}
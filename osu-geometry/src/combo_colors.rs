@@ -0,0 +1,129 @@
+//! Combo colours and the combo each hit object belongs to
+//!
+//! [`combo_info`] follows osu!lazer's `OsuHitObject.UpdateComboInformation`, so the
+//! colour an object gets matches the game, including colour skips (`combo_offset`).
+
+/// The default skin's `Combo1`-`Combo4` as RGB, cycled through by combo index
+pub const DEFAULT_COMBO_COLORS: [[u8; 3]; 4] = [
+    [255, 192, 0],
+    [0, 202, 0],
+    [18, 124, 255],
+    [242, 24, 57],
+];
+
+/// Combo fields of one hit object, as parsed from the `.osu` type bits
+///
+/// rosu-map (like osu!lazer's decoder) already forces a new combo on the object after a
+/// spinner and moves the spinner's colour skip onto that object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ComboFlags {
+    pub new_combo: bool,
+    /// Extra combo colours to skip when this object starts a new combo
+    pub combo_offset: u32,
+    pub is_spinner: bool,
+}
+
+/// Where a hit object sits in the map's combos
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ComboInfo {
+    /// Combos started up to and including this object's (1 for the first combo)
+    pub combo_index: u32,
+    /// `combo_index` plus every colour skip so far; selects the combo colour
+    pub combo_index_with_offsets: u32,
+    /// Position within the combo, 0 for its first object
+    pub index_in_combo: u32,
+}
+
+impl ComboInfo {
+    /// Number drawn on the object (1 for the first object of a combo)
+    pub fn combo_number(&self) -> u32 {
+        self.index_in_combo + 1
+    }
+
+    /// Index into a list of `colour_count` combo colours (0 without colours)
+    pub fn colour_index(&self, colour_count: usize) -> usize {
+        if colour_count == 0 {
+            0
+        } else {
+            self.combo_index_with_offsets as usize % colour_count
+        }
+    }
+}
+
+/// Combo position and colour slot of every object, in order
+///
+/// The first object always starts a combo, even without its new-combo bit, and so
+/// does the object after a spinner. Starting a combo advances the colour by
+/// `combo_offset + 1`, so the first combo already uses colour `1 + combo_offset`
+/// (`Combo2` for an unskipped map). Spinners never start a combo for colouring and
+/// inherit the previous object's (all zeros when the map starts with one).
+pub fn combo_info(objects: impl IntoIterator<Item = ComboFlags>) -> Vec<ComboInfo> {
+    let mut infos: Vec<ComboInfo> = Vec::new();
+    let mut last: Option<(ComboInfo, bool)> = None;
+
+    for object in objects {
+        let mut info = match last {
+            Some((prev, _)) => ComboInfo {
+                index_in_combo: prev.index_in_combo + 1,
+                ..prev
+            },
+            None => ComboInfo::default(),
+        };
+
+        let after_spinner = last.is_some_and(|(_, was_spinner)| was_spinner);
+        if !object.is_spinner && (object.new_combo || last.is_none() || after_spinner) {
+            info.index_in_combo = 0;
+            info.combo_index += 1;
+            info.combo_index_with_offsets += object.combo_offset + 1;
+        }
+
+        infos.push(info);
+        last = Some((info, object.is_spinner));
+    }
+
+    infos
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CIRCLE: ComboFlags = ComboFlags { new_combo: false, combo_offset: 0, is_spinner: false };
+    const NEW_COMBO: ComboFlags = ComboFlags { new_combo: true, ..CIRCLE };
+    const SPINNER: ComboFlags = ComboFlags { is_spinner: true, ..CIRCLE };
+
+    fn numbers_and_colours(infos: &[ComboInfo]) -> Vec<(u32, u32)> {
+        infos.iter().map(|info| (info.combo_number(), info.combo_index_with_offsets)).collect()
+    }
+
+    #[test]
+    fn first_combo_uses_the_second_colour() {
+        let infos = combo_info([CIRCLE, CIRCLE, NEW_COMBO]);
+        assert_eq!(numbers_and_colours(&infos), vec![(1, 1), (2, 1), (1, 2)]);
+        assert_eq!(infos[0].colour_index(4), 1);
+        assert_eq!(infos[0].colour_index(0), 0);
+        assert_eq!(DEFAULT_COMBO_COLORS[infos[0].colour_index(DEFAULT_COMBO_COLORS.len())], [0, 202, 0]);
+    }
+
+    #[test]
+    fn combo_offsets_skip_colours() {
+        let skip_two = ComboFlags { combo_offset: 2, ..NEW_COMBO };
+        let infos = combo_info([NEW_COMBO, skip_two, CIRCLE]);
+        assert_eq!(numbers_and_colours(&infos), vec![(1, 1), (1, 4), (2, 4)]);
+        assert_eq!(infos.iter().map(|info| info.combo_index).collect::<Vec<_>>(), vec![1, 2, 2]);
+    }
+
+    #[test]
+    fn spinners_keep_the_previous_colour_and_end_the_combo() {
+        let infos = combo_info([NEW_COMBO, CIRCLE, SPINNER, CIRCLE]);
+        assert_eq!(infos[2].combo_index_with_offsets, 1);
+        assert_eq!(numbers_and_colours(&infos)[3], (1, 2));
+    }
+
+    #[test]
+    fn a_leading_spinner_has_no_combo() {
+        let infos = combo_info([SPINNER, CIRCLE]);
+        assert_eq!(infos[0], ComboInfo::default());
+        assert_eq!(numbers_and_colours(&infos)[1], (1, 1));
+    }
+}
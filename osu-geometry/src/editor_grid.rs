@@ -0,0 +1,36 @@
+//! osu! editor grid, for judging object placement in the viewer and player
+
+/// Grid spacing in osupixels when a map doesn't set `GridSize`
+pub const DEFAULT_GRID_SIZE: f32 = 32.0;
+
+/// Editor grid spacing in osupixels for a map's `[Editor]` `GridSize`
+pub fn editor_grid_spacing(grid_size: i32) -> f32 {
+    if grid_size > 0 {
+        grid_size as f32
+    } else {
+        DEFAULT_GRID_SIZE
+    }
+}
+
+/// Positions of grid lines every `spacing` osupixels from 0 up to `extent` inclusive
+pub fn grid_lines(spacing: f32, extent: f32) -> impl Iterator<Item = f32> {
+    let count = if spacing > 0.0 { (extent / spacing).floor() as usize } else { 0 };
+    (0..=count).map(move |i| i as f32 * spacing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unset_grid_size_uses_the_default() {
+        assert_eq!(editor_grid_spacing(0), DEFAULT_GRID_SIZE);
+        assert_eq!(editor_grid_spacing(16), 16.0);
+    }
+
+    #[test]
+    fn grid_lines_include_both_edges() {
+        assert_eq!(grid_lines(32.0, 64.0).collect::<Vec<_>>(), vec![0.0, 32.0, 64.0]);
+        assert_eq!(grid_lines(0.0, 64.0).collect::<Vec<_>>(), vec![0.0]);
+    }
+}
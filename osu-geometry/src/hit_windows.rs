@@ -0,0 +1,35 @@
+//! osu!standard hit windows derived from overall difficulty
+
+/// Hit windows in milliseconds on either side of an object's time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HitWindows {
+    /// 300 window
+    pub great: f64,
+    /// 100 window
+    pub ok: f64,
+    /// 50 window
+    pub meh: f64,
+}
+
+/// osu!standard windows for an OD (the same formulas rosu-pp reports as
+/// `great/ok/meh_hit_window`): 80 - 6·OD, 140 - 8·OD and 200 - 10·OD
+pub fn hit_windows(od: f32) -> HitWindows {
+    let od = f64::from(od);
+    HitWindows {
+        great: 80.0 - 6.0 * od,
+        ok: 140.0 - 8.0 * od,
+        meh: 200.0 - 10.0 * od,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn windows_narrow_linearly_with_od() {
+        assert_eq!(hit_windows(0.0), HitWindows { great: 80.0, ok: 140.0, meh: 200.0 });
+        assert_eq!(hit_windows(5.0), HitWindows { great: 50.0, ok: 100.0, meh: 150.0 });
+        assert_eq!(hit_windows(10.0), HitWindows { great: 20.0, ok: 60.0, meh: 100.0 });
+    }
+}
@@ -0,0 +1,679 @@
+//! osu-geometry: Slider path evaluation shared by the viewer, player and dataset tools
+//!
+//! Turns slider control points into a sampled polyline using the same
+//! approximations as osu!lazer (adaptive bezier subdivision, circular arcs,
+//! catmull splines) and its length clamping against the expected distance.
+//! Also provides slider ball positions over repeats, the OD to hit window
+//! formulas used when inspecting maps, the default combo colours, osu!lazer's
+//! combo colour assignment and the editor grid.
+
+use std::ops::{Add, Mul, Sub};
+
+mod combo_colors;
+mod editor_grid;
+mod hit_windows;
+
+pub use combo_colors::{combo_info, ComboFlags, ComboInfo, DEFAULT_COMBO_COLORS};
+pub use editor_grid::{editor_grid_spacing, grid_lines, DEFAULT_GRID_SIZE};
+pub use hit_windows::{hit_windows, HitWindows};
+
+/// Max deviation of a flattened bezier from the true curve, in osupixels
+const BEZIER_TOLERANCE: f32 = 0.25;
+/// Max deviation of a flattened circular arc from the true arc, in osupixels
+const CIRCULAR_ARC_TOLERANCE: f32 = 0.1;
+/// Line segments per catmull span
+const CATMULL_DETAIL: usize = 50;
+
+/// Curve type of a slider path segment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathKind {
+    Bezier,
+    Linear,
+    PerfectCurve,
+    Catmull,
+}
+
+impl PathKind {
+    /// Parse the curve type letter from a `.osu` hit object line (`B`, `L`, `P`, `C`)
+    pub fn from_letter(letter: &str) -> Option<Self> {
+        match letter {
+            "B" => Some(Self::Bezier),
+            "L" => Some(Self::Linear),
+            "P" => Some(Self::PerfectCurve),
+            "C" => Some(Self::Catmull),
+            _ => None,
+        }
+    }
+}
+
+/// A control point that may start a new segment with its own curve type
+///
+/// This is the shape rosu-map and osu!lazer use: the head carries the path type,
+/// and later points with a type (red anchors in legacy bezier sliders) split segments.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ControlPoint {
+    pub pos: (f32, f32),
+    pub kind: Option<PathKind>,
+}
+
+/// Sample a slider path given in legacy `.osu` form
+///
+/// `control_points` includes the head. As in `.osu` files, a single curve type
+/// applies to the whole slider and, for bezier sliders, a repeated point (red
+/// anchor) splits the path into separate curves. See [`sample_path`] for `length`
+/// and `resolution`.
+pub fn sample_slider_path(
+    control_points: &[(f32, f32)],
+    path_type: PathKind,
+    length: Option<f64>,
+    resolution: f32,
+) -> Vec<(f32, f32)> {
+    let mut points: Vec<ControlPoint> = Vec::with_capacity(control_points.len());
+    for (i, &pos) in control_points.iter().enumerate() {
+        if i == 0 {
+            points.push(ControlPoint { pos, kind: Some(path_type) });
+        } else if path_type == PathKind::Bezier && points.last().is_some_and(|p| p.pos == pos) {
+            // Red anchor: the duplicate becomes a segment boundary
+            if let Some(last) = points.last_mut() {
+                last.kind = Some(PathKind::Bezier);
+            }
+        } else {
+            points.push(ControlPoint { pos, kind: None });
+        }
+    }
+    sample_path(&points, length, resolution)
+}
+
+/// Sample a slider path from typed control points
+///
+/// The path is clamped to `length` (the slider's expected distance) the way
+/// osu! does it: a longer path is cut short and a shorter one has its final
+/// segment extended. With a positive `resolution` the result is resampled to
+/// points evenly spaced that many osupixels apart (plus the exact end point);
+/// otherwise the approximation's own vertices are returned.
+pub fn sample_path(control_points: &[ControlPoint], length: Option<f64>, resolution: f32) -> Vec<(f32, f32)> {
+    let mut path = calculate_path(control_points);
+    clamp_to_length(&mut path, control_points, length);
+
+    let path = if resolution > 0.0 { resample(&path, resolution) } else { path };
+    path.into_iter().map(|p| (p.x, p.y)).collect()
+}
+
+/// Ball position after `slides` spans of a sampled path (tail for odd counts, head for even)
+pub fn end_position(path: &[(f32, f32)], slides: u32) -> Option<(f32, f32)> {
+    if slides.is_multiple_of(2) {
+        path.first().copied()
+    } else {
+        path.last().copied()
+    }
+}
+
+/// Ball position on a sampled slider path at time `t`
+///
+/// The slider runs `repeats + 1` spans of `duration / (repeats + 1)` ms each,
+/// alternating head-to-tail and tail-to-head, and the ball moves at constant
+/// speed along the path. Returns `None` outside `start_time..=start_time + duration`
+/// (the slider is not active) or for an empty path.
+pub fn slider_ball_position(
+    path: &[(f32, f32)],
+    repeats: u32,
+    duration: f64,
+    start_time: f64,
+    t: f64,
+) -> Option<(f32, f32)> {
+    if path.is_empty() || t < start_time || t > start_time + duration {
+        return None;
+    }
+    if duration <= 0.0 {
+        return path.first().copied();
+    }
+
+    let slides = repeats + 1;
+    let progress = (t - start_time) / duration * slides as f64;
+    // The end time itself belongs to the last span
+    let span = (progress.floor() as u32).min(slides - 1);
+    let span_progress = (progress - span as f64).clamp(0.0, 1.0);
+    let along = if span.is_multiple_of(2) { span_progress } else { 1.0 - span_progress };
+
+    Some(position_at_distance(path, along * path_length(path)))
+}
+
+/// Point `distance` osupixels along a polyline, clamped to its ends
+fn position_at_distance(path: &[(f32, f32)], distance: f64) -> (f32, f32) {
+    let mut remaining = distance;
+    for w in path.windows(2) {
+        let (start, end) = (Vec2::from(w[0]), Vec2::from(w[1]));
+        let segment_len = start.distance(end) as f64;
+        if remaining <= segment_len && segment_len > 0.0 {
+            let p = start + (end - start) * (remaining / segment_len) as f32;
+            return (p.x, p.y);
+        }
+        remaining -= segment_len;
+    }
+    path[path.len() - 1]
+}
+
+/// Total length of a polyline in osupixels
+pub fn path_length(path: &[(f32, f32)]) -> f64 {
+    path.windows(2)
+        .map(|w| Vec2::from(w[0]).distance(Vec2::from(w[1])) as f64)
+        .sum()
+}
+
+/// Reduce a polyline to at most `count` points spread evenly along its length
+///
+/// The first and last points are kept, so the shape's extent survives; paths
+/// that already fit are returned unchanged. Used where a renderer can only take
+/// a fixed number of points, instead of truncating the path.
+pub fn resample_to_count(path: &[(f32, f32)], count: usize) -> Vec<(f32, f32)> {
+    let count = count.max(2);
+    if path.len() <= count {
+        return path.to_vec();
+    }
+
+    let total = path_length(path);
+    let mut output = Vec::with_capacity(count);
+    output.push(path[0]);
+
+    let mut segment = 0;
+    // Distance along the path where `segment` starts
+    let mut segment_start = 0.0;
+    for i in 1..count - 1 {
+        let target = total * i as f64 / (count - 1) as f64;
+        let (start, end, segment_len) = loop {
+            let (start, end) = (Vec2::from(path[segment]), Vec2::from(path[segment + 1]));
+            let segment_len = start.distance(end) as f64;
+            if segment_start + segment_len >= target || segment + 2 >= path.len() {
+                break (start, end, segment_len);
+            }
+            segment_start += segment_len;
+            segment += 1;
+        };
+        let t = if segment_len > 0.0 { ((target - segment_start) / segment_len).clamp(0.0, 1.0) } else { 0.0 };
+        let p = start + (end - start) * t as f32;
+        output.push((p.x, p.y));
+    }
+
+    output.push(path[path.len() - 1]);
+    output
+}
+
+// ============ Path construction ============
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Vec2 {
+    x: f32,
+    y: f32,
+}
+
+impl Vec2 {
+    fn new(x: f32, y: f32) -> Self {
+        Self { x, y }
+    }
+
+    fn length_squared(self) -> f32 {
+        self.x * self.x + self.y * self.y
+    }
+
+    fn length(self) -> f32 {
+        self.length_squared().sqrt()
+    }
+
+    fn distance(self, other: Self) -> f32 {
+        (self - other).length()
+    }
+
+    fn dot(self, other: Self) -> f32 {
+        self.x * other.x + self.y * other.y
+    }
+
+    fn normalized(self) -> Self {
+        let len = self.length();
+        if len > 0.0 {
+            self * (1.0 / len)
+        } else {
+            self
+        }
+    }
+}
+
+impl From<(f32, f32)> for Vec2 {
+    fn from((x, y): (f32, f32)) -> Self {
+        Self::new(x, y)
+    }
+}
+
+impl Add for Vec2 {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl Sub for Vec2 {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl Mul<f32> for Vec2 {
+    type Output = Self;
+    fn mul(self, rhs: f32) -> Self {
+        Self::new(self.x * rhs, self.y * rhs)
+    }
+}
+
+/// Approximate every segment and join them, dropping repeated joint vertices
+fn calculate_path(control_points: &[ControlPoint]) -> Vec<Vec2> {
+    let mut path: Vec<Vec2> = Vec::new();
+    if let [only] = control_points {
+        path.push(Vec2::from(only.pos));
+    }
+    if control_points.len() < 2 {
+        return path;
+    }
+
+    let mut segment_start = 0;
+
+    for i in 0..control_points.len() {
+        if control_points[i].kind.is_none() && i < control_points.len() - 1 {
+            continue;
+        }
+
+        // A typed point ends the current segment and starts the next one. As in
+        // osu!lazer, a lone point (the typed head itself) adds nothing; an
+        // approximated segment supplies its own, possibly inexact, start
+        let segment: Vec<Vec2> = control_points[segment_start..=i].iter().map(|p| Vec2::from(p.pos)).collect();
+        if segment.len() > 1 {
+            let kind = control_points[segment_start].kind.unwrap_or(PathKind::Linear);
+            for vertex in approximate_segment(kind, &segment) {
+                if path.last() != Some(&vertex) {
+                    path.push(vertex);
+                }
+            }
+        }
+
+        segment_start = i;
+    }
+
+    path
+}
+
+fn approximate_segment(kind: PathKind, points: &[Vec2]) -> Vec<Vec2> {
+    match kind {
+        PathKind::Linear => points.to_vec(),
+        PathKind::PerfectCurve if points.len() == 3 => {
+            approximate_circular_arc(points).unwrap_or_else(|| approximate_bezier(points))
+        }
+        PathKind::Catmull => approximate_catmull(points),
+        PathKind::PerfectCurve | PathKind::Bezier => approximate_bezier(points),
+    }
+}
+
+/// Flatten a bezier curve by adaptive subdivision (osu!lazer's piecewise-linear approximation)
+fn approximate_bezier(points: &[Vec2]) -> Vec<Vec2> {
+    let count = points.len();
+    let mut output = Vec::new();
+    if count == 0 {
+        return output;
+    }
+
+    let mut to_flatten: Vec<Vec<Vec2>> = vec![points.to_vec()];
+    let mut left = vec![Vec2::new(0.0, 0.0); count];
+    let mut right = vec![Vec2::new(0.0, 0.0); count];
+
+    while let Some(parent) = to_flatten.pop() {
+        if is_flat_enough(&parent) {
+            bezier_approximate(&parent, &mut output);
+            continue;
+        }
+
+        subdivide(&parent, &mut left, &mut right);
+        // Process the left half first so vertices come out in path order
+        to_flatten.push(right.clone());
+        to_flatten.push(left.clone());
+    }
+
+    output.push(points[count - 1]);
+    output
+}
+
+fn is_flat_enough(points: &[Vec2]) -> bool {
+    points.windows(3).all(|w| {
+        (w[0] - w[1] * 2.0 + w[2]).length_squared() <= BEZIER_TOLERANCE * BEZIER_TOLERANCE * 4.0
+    })
+}
+
+/// Split a bezier at t = 0.5 into its left and right halves (de Casteljau)
+fn subdivide(points: &[Vec2], left: &mut [Vec2], right: &mut [Vec2]) {
+    let count = points.len();
+    let mut midpoints = points.to_vec();
+
+    for i in 0..count {
+        left[i] = midpoints[0];
+        right[count - i - 1] = midpoints[count - i - 1];
+
+        for j in 0..count - i - 1 {
+            midpoints[j] = (midpoints[j] + midpoints[j + 1]) * 0.5;
+        }
+    }
+}
+
+/// Emit the vertices of a flat-enough bezier piece (all but its final point)
+fn bezier_approximate(points: &[Vec2], output: &mut Vec<Vec2>) {
+    let count = points.len();
+    let mut left = vec![Vec2::new(0.0, 0.0); count * 2 - 1];
+    let mut right = vec![Vec2::new(0.0, 0.0); count];
+
+    subdivide(points, &mut left[..count], &mut right);
+    left[count..(count - 1 + count)].copy_from_slice(&right[1..count]);
+
+    output.push(points[0]);
+    for i in 1..count - 1 {
+        let index = 2 * i;
+        let p = (left[index - 1] + left[index] * 2.0 + left[index + 1]) * 0.25;
+        output.push(p);
+    }
+}
+
+/// Flatten the circular arc through three points, or None if they are (nearly) collinear
+fn approximate_circular_arc(points: &[Vec2]) -> Option<Vec<Vec2>> {
+    let (a, b, c) = (points[0], points[1], points[2]);
+
+    // Degenerate triangle: the arc is a line or a point
+    if ((b.y - a.y) * (c.x - a.x) - (b.x - a.x) * (c.y - a.y)).abs() < 1e-3 {
+        return None;
+    }
+
+    let d = 2.0 * (a.x * (b - c).y + b.x * (c - a).y + c.x * (a - b).y);
+    let (a_sq, b_sq, c_sq) = (a.length_squared(), b.length_squared(), c.length_squared());
+    let centre = Vec2::new(
+        a_sq * (b - c).y + b_sq * (c - a).y + c_sq * (a - b).y,
+        a_sq * (c - b).x + b_sq * (a - c).x + c_sq * (b - a).x,
+    ) * (1.0 / d);
+
+    let da = a - centre;
+    let dc = c - centre;
+    let radius = da.length();
+
+    let theta_start = da.y.atan2(da.x);
+    let mut theta_end = dc.y.atan2(dc.x);
+    while theta_end < theta_start {
+        theta_end += 2.0 * std::f32::consts::PI;
+    }
+
+    // Go the other way around if b is not on the arc from a to c
+    let mut direction = 1.0;
+    let mut theta_range = theta_end - theta_start;
+    let ortho_a_to_c = {
+        let v = c - a;
+        Vec2::new(v.y, -v.x)
+    };
+    if ortho_a_to_c.dot(b - a) < 0.0 {
+        direction = -1.0;
+        theta_range = 2.0 * std::f32::consts::PI - theta_range;
+    }
+
+    let amount_points = if 2.0 * radius <= CIRCULAR_ARC_TOLERANCE {
+        2
+    } else {
+        let step = 2.0 * (1.0 - CIRCULAR_ARC_TOLERANCE / radius).acos();
+        ((theta_range / step).ceil() as usize).max(2)
+    };
+
+    Some(
+        (0..amount_points)
+            .map(|i| {
+                let fract = i as f32 / (amount_points - 1) as f32;
+                let theta = theta_start + direction * fract * theta_range;
+                centre + Vec2::new(theta.cos(), theta.sin()) * radius
+            })
+            .collect(),
+    )
+}
+
+/// Flatten a catmull-rom spline through the control points
+fn approximate_catmull(points: &[Vec2]) -> Vec<Vec2> {
+    let n = points.len();
+    let mut output = Vec::with_capacity((n.saturating_sub(1)) * CATMULL_DETAIL * 2);
+
+    for i in 0..n.saturating_sub(1) {
+        let v1 = if i > 0 { points[i - 1] } else { points[i] };
+        let v2 = points[i];
+        let v3 = if i < n - 1 { points[i + 1] } else { v2 + v2 - v1 };
+        let v4 = if i < n - 2 { points[i + 2] } else { v3 + v3 - v2 };
+
+        for c in 0..CATMULL_DETAIL {
+            output.push(catmull_point(v1, v2, v3, v4, c as f32 / CATMULL_DETAIL as f32));
+            output.push(catmull_point(v1, v2, v3, v4, (c + 1) as f32 / CATMULL_DETAIL as f32));
+        }
+    }
+
+    output
+}
+
+fn catmull_point(v1: Vec2, v2: Vec2, v3: Vec2, v4: Vec2, t: f32) -> Vec2 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    let axis = |p1: f32, p2: f32, p3: f32, p4: f32| {
+        0.5 * (2.0 * p2
+            + (-p1 + p3) * t
+            + (2.0 * p1 - 5.0 * p2 + 4.0 * p3 - p4) * t2
+            + (-p1 + 3.0 * p2 - 3.0 * p3 + p4) * t3)
+    };
+    Vec2::new(axis(v1.x, v2.x, v3.x, v4.x), axis(v1.y, v2.y, v3.y, v4.y))
+}
+
+// ============ Length handling ============
+
+/// Cut or extend the path so it is exactly `expected` long, as osu! does
+fn clamp_to_length(path: &mut Vec<Vec2>, control_points: &[ControlPoint], expected: Option<f64>) {
+    let Some(expected) = expected else {
+        return;
+    };
+    if path.len() < 2 {
+        return;
+    }
+
+    let mut cumulative: Vec<f64> = Vec::with_capacity(path.len());
+    let mut calculated = 0.0;
+    cumulative.push(0.0);
+    for w in path.windows(2) {
+        calculated += w[0].distance(w[1]) as f64;
+        cumulative.push(calculated);
+    }
+
+    if calculated == expected {
+        return;
+    }
+
+    // osu!stable doesn't extend sliders whose last two control points coincide
+    let n = control_points.len();
+    if n >= 2 && control_points[n - 1].pos == control_points[n - 2].pos && expected > calculated {
+        return;
+    }
+
+    // The final segment is the one that gets adjusted
+    cumulative.pop();
+    let mut end = path.len() - 1;
+
+    if calculated > expected {
+        while end > 0 && cumulative.last().is_some_and(|&len| len >= expected) {
+            cumulative.pop();
+            path.pop();
+            end -= 1;
+        }
+    }
+
+    if end == 0 {
+        path.truncate(1);
+        return;
+    }
+
+    let dir = (path[end] - path[end - 1]).normalized();
+    let remaining = (expected - cumulative.last().copied().unwrap_or(0.0)) as f32;
+    path[end] = path[end - 1] + dir * remaining;
+}
+
+/// Resample a polyline to evenly spaced points, keeping the exact end point
+fn resample(path: &[Vec2], spacing: f32) -> Vec<Vec2> {
+    let Some(&first) = path.first() else {
+        return Vec::new();
+    };
+
+    let mut output = vec![first];
+    // Distance along the path until the next emitted point
+    let mut until_next = spacing;
+
+    for w in path.windows(2) {
+        let (start, end) = (w[0], w[1]);
+        let segment_len = start.distance(end);
+        let mut travelled = 0.0;
+
+        while segment_len - travelled >= until_next {
+            travelled += until_next;
+            output.push(start + (end - start) * (travelled / segment_len));
+            until_next = spacing;
+        }
+        until_next -= segment_len - travelled;
+    }
+
+    let last = path[path.len() - 1];
+    if output.last() != Some(&last) {
+        output.push(last);
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(actual: (f32, f32), expected: (f32, f32)) {
+        let distance = Vec2::from(actual).distance(Vec2::from(expected));
+        assert!(distance < 1e-3, "{:?} is not {:?}", actual, expected);
+    }
+
+    #[test]
+    fn slider_ball_reverses_on_each_repeat() {
+        // 2 repeats = 3 spans of 100 ms over a 90 px path: forward, back, forward
+        let path = [(0.0, 0.0), (45.0, 0.0), (90.0, 0.0)];
+        let ball = |percent: f64| slider_ball_position(&path, 2, 300.0, 1000.0, 1000.0 + 3.0 * percent).unwrap();
+
+        assert_near(ball(0.0), (0.0, 0.0));
+        // 0.75 of the first span, heading to the tail
+        assert_near(ball(25.0), (67.5, 0.0));
+        // The second span runs back towards the head
+        assert_near(ball(40.0), (72.0, 0.0));
+        assert_near(ball(60.0), (18.0, 0.0));
+        // 0.25 of the third span, heading to the tail again
+        assert_near(ball(75.0), (22.5, 0.0));
+        // An odd span count ends on the tail
+        assert_near(ball(100.0), (90.0, 0.0));
+        assert_eq!(end_position(&path, 3), Some((90.0, 0.0)));
+    }
+
+    #[test]
+    fn slider_ball_is_absent_outside_the_active_window() {
+        let path = [(0.0, 0.0), (90.0, 0.0)];
+        assert_eq!(slider_ball_position(&path, 2, 300.0, 1000.0, 999.0), None);
+        assert_eq!(slider_ball_position(&path, 2, 300.0, 1000.0, 1300.5), None);
+        assert_eq!(slider_ball_position(&[], 2, 300.0, 1000.0, 1100.0), None);
+    }
+
+    #[test]
+    fn perfect_curve_follows_the_circle_through_its_points() {
+        let path = sample_slider_path(&[(0.0, 0.0), (50.0, 50.0), (100.0, 0.0)], PathKind::PerfectCurve, None, 0.0);
+
+        // osu!lazer: ceil(θ / (2·acos(1 - 0.1 / r))) vertices for a half circle of r = 50
+        assert_eq!(path.len(), 25);
+        for &point in &path {
+            assert!((Vec2::from(point).distance(Vec2::new(50.0, 0.0)) - 50.0).abs() < 1e-3);
+        }
+        assert_near(path[0], (0.0, 0.0));
+        assert_near(path[12], (50.0, 50.0));
+        assert_near(path[24], (100.0, 0.0));
+    }
+
+    #[test]
+    fn collinear_perfect_curve_falls_back_to_bezier() {
+        let path = sample_slider_path(&[(0.0, 0.0), (100.0, 0.0), (200.0, 0.0)], PathKind::PerfectCurve, None, 0.0);
+        assert!(path.iter().all(|&(_, y)| y == 0.0));
+        assert_near(*path.last().unwrap(), (200.0, 0.0));
+    }
+
+    #[test]
+    fn bezier_stays_within_tolerance_of_the_curve() {
+        let path = sample_slider_path(&[(0.0, 0.0), (100.0, 100.0), (200.0, 0.0)], PathKind::Bezier, None, 0.0);
+
+        // B(t) = (200t, 200t(1 - t)); the first subdivision puts a vertex on t = 0.5
+        assert!(path.contains(&(100.0, 50.0)));
+        for &(x, y) in &path {
+            let t = x / 200.0;
+            assert!((y - 200.0 * t * (1.0 - t)).abs() <= BEZIER_TOLERANCE, "({}, {}) is off the curve", x, y);
+        }
+        assert_eq!(path[0], (0.0, 0.0));
+        assert_eq!(*path.last().unwrap(), (200.0, 0.0));
+    }
+
+    #[test]
+    fn red_anchors_split_bezier_sliders() {
+        let path = sample_slider_path(
+            &[(0.0, 0.0), (100.0, 0.0), (100.0, 0.0), (100.0, 100.0)],
+            PathKind::Bezier,
+            None,
+            0.0,
+        );
+        assert_eq!(path, vec![(0.0, 0.0), (100.0, 0.0), (100.0, 100.0)]);
+    }
+
+    #[test]
+    fn catmull_passes_through_every_control_point() {
+        let anchors = [(0.0, 0.0), (100.0, 50.0), (200.0, 0.0)];
+        let path = sample_slider_path(&anchors, PathKind::Catmull, None, 0.0);
+
+        for &anchor in &anchors {
+            assert!(path.iter().any(|&point| Vec2::from(point).distance(Vec2::from(anchor)) < 1e-3), "{:?} missing", anchor);
+        }
+        // CATMULL_DETAIL line segments per span
+        assert!(path.len() >= 2 * CATMULL_DETAIL);
+    }
+
+    #[test]
+    fn paths_are_clamped_to_the_expected_distance() {
+        let line = [(0.0, 0.0), (100.0, 0.0)];
+        assert_eq!(sample_slider_path(&line, PathKind::Linear, Some(50.0), 0.0), vec![(0.0, 0.0), (50.0, 0.0)]);
+        // A short path has its final segment extended
+        assert_eq!(sample_slider_path(&line, PathKind::Linear, Some(150.0), 0.0), vec![(0.0, 0.0), (150.0, 0.0)]);
+
+        let arc = [(0.0, 0.0), (50.0, 50.0), (100.0, 0.0)];
+        let path = sample_slider_path(&arc, PathKind::PerfectCurve, Some(100.0), 0.0);
+        assert!((path_length(&path) - 100.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn coinciding_last_points_are_not_extended() {
+        let points = [(0.0, 0.0), (100.0, 0.0), (100.0, 0.0)];
+        let path = sample_slider_path(&points, PathKind::Linear, Some(150.0), 0.0);
+        assert_eq!(*path.last().unwrap(), (100.0, 0.0));
+    }
+
+    #[test]
+    fn resolution_spaces_points_evenly_and_keeps_the_end() {
+        let path = sample_slider_path(&[(0.0, 0.0), (25.0, 0.0)], PathKind::Linear, None, 10.0);
+        assert_eq!(path, vec![(0.0, 0.0), (10.0, 0.0), (20.0, 0.0), (25.0, 0.0)]);
+    }
+
+    #[test]
+    fn a_single_control_point_is_a_point() {
+        assert_eq!(sample_slider_path(&[(5.0, 5.0)], PathKind::Bezier, Some(100.0), 0.0), vec![(5.0, 5.0)]);
+    }
+
+    #[test]
+    fn resample_to_count_keeps_both_ends() {
+        let path: Vec<(f32, f32)> = (0..=10).map(|i| (i as f32 * 10.0, 0.0)).collect();
+        assert_eq!(resample_to_count(&path, 3), vec![(0.0, 0.0), (50.0, 0.0), (100.0, 0.0)]);
+        assert_eq!(resample_to_count(&path, 20), path);
+    }
+}
@@ -3,7 +3,10 @@
 
 use bevy::prelude::*;
 use rosu_map::section::general::CountdownType;
-use rosu_map::section::hit_objects::{CurveBuffers, HitObjectKind};
+use osu_geometry::{ComboFlags, ControlPoint, PathKind};
+use rosu_map::section::hit_objects::{CurveBuffers, HitObjectKind, HitObjectSlider, PathType};
+
+use crate::visual_mods::{VisualMods, HIDDEN_FADE_IN, HIDDEN_FADE_OUT};
 
 /// osu! standard playfield dimensions
 pub const PLAYFIELD_WIDTH: f32 = 512.0;
@@ -117,15 +120,9 @@ impl BeatmapView {
             .map(|tp| tp.beat_len)
             .unwrap_or(500.0);
 
-        // Extract combo colors from beatmap (default to white if none specified)
+        // Extract combo colors from beatmap (the default skin's without a [Colours] section)
         let combo_colors: Vec<[u8; 3]> = if beatmap.custom_combo_colors.is_empty() {
-            // Default combo colors similar to osu! defaults
-            vec![
-                [255, 192, 0],   // Orange
-                [0, 202, 0],     // Green
-                [18, 124, 255],  // Blue
-                [242, 24, 57],   // Red
-            ]
+            osu_geometry::DEFAULT_COMBO_COLORS.to_vec()
         } else {
             beatmap.custom_combo_colors.iter()
                 .map(|c| [c.0[0], c.0[1], c.0[2]])
@@ -133,26 +130,29 @@ impl BeatmapView {
         };
         let combo_color_count = combo_colors.len();
 
+        // Combo numbers and colour slots as osu!lazer assigns them
+        let combos = osu_geometry::combo_info(beatmap.hit_objects.iter().map(|hit_object| {
+            let (new_combo, combo_offset) = match &hit_object.kind {
+                HitObjectKind::Circle(c) => (c.new_combo, c.combo_offset),
+                HitObjectKind::Slider(s) => (s.new_combo, s.combo_offset),
+                // The parser already forces the next circle/slider to start a new combo
+                // and adds the spinner's offset to it
+                HitObjectKind::Spinner(_) | HitObjectKind::Hold(_) => (false, 0),
+            };
+            ComboFlags {
+                new_combo,
+                combo_offset: combo_offset as u32,
+                is_spinner: matches!(hit_object.kind, HitObjectKind::Spinner(_)),
+            }
+        }));
+
         // Process hit objects
         let mut objects = Vec::with_capacity(beatmap.hit_objects.len());
-        let mut combo_number = 0u32;
-        let mut combo_color_index = 0usize;
         let mut curve_buffers = CurveBuffers::default();
 
-        for hit_object in beatmap.hit_objects.iter_mut() {
-            let (is_new_combo, color_skip) = match &hit_object.kind {
-                HitObjectKind::Circle(c) => (c.new_combo, c.combo_offset as usize),
-                HitObjectKind::Slider(s) => (s.new_combo, s.combo_offset as usize),
-                HitObjectKind::Spinner(s) => (s.new_combo, 0),
-                HitObjectKind::Hold(_) => (false, 0),
-            };
-
-            if is_new_combo {
-                combo_number = 1;
-                combo_color_index = (combo_color_index + 1 + color_skip) % combo_color_count;
-            } else {
-                combo_number += 1;
-            }
+        for (hit_object, combo) in beatmap.hit_objects.iter_mut().zip(combos) {
+            let combo_number = combo.combo_number();
+            let combo_color_index = combo.colour_index(combo_color_count);
 
             let render_obj = match &mut hit_object.kind {
                 HitObjectKind::Circle(circle) => RenderObject {
@@ -165,17 +165,8 @@ impl BeatmapView {
                     kind: RenderObjectKind::Circle,
                 },
                 HitObjectKind::Slider(slider) => {
-                    let slider_x = slider.pos.x;
-                    let slider_y = slider.pos.y;
-
-                    let path_points: Vec<(f32, f32)> = {
-                        let curve = slider.path.curve_with_bufs(&mut curve_buffers);
-                        curve
-                            .path()
-                            .iter()
-                            .map(|pos| (slider_x + pos.x, slider_y + pos.y))
-                            .collect()
-                    };
+                    // Path points are absolute osu!pixel positions
+                    let path_points = slider_path_points(slider);
 
                     let total_duration = slider.duration_with_bufs(&mut curve_buffers);
                     let span_count = slider.span_count() as u32;
@@ -257,10 +248,11 @@ impl BeatmapView {
         }
     }
 
-    /// Get objects visible at the current time with opacity
-    pub fn visible_objects(&self, current_time: f64) -> Vec<(usize, &RenderObject, f32)> {
+    /// Get objects visible at the current time with opacity, after applying visual mods
+    pub fn visible_objects(&self, current_time: f64, mods: &VisualMods) -> Vec<(usize, &RenderObject, f32)> {
         let approach = self.approach_time;
         let fade_in = self.fade_in_time;
+        let focus = mods.flashlight.then(|| self.focus_position(current_time));
         let start = current_time - 200.0;
         let end = current_time + approach;
 
@@ -290,7 +282,7 @@ impl BeatmapView {
                 let time_until_hit = obj.start_time - current_time;
                 let time_since_end = current_time - obj.end_time;
 
-                let opacity = if time_since_end > 0.0 {
+                let mut opacity = if time_since_end > 0.0 {
                     (1.0 - (time_since_end / 200.0) as f32).max(0.0)
                 } else if time_until_hit > approach {
                     0.0
@@ -299,6 +291,12 @@ impl BeatmapView {
                 } else {
                     1.0
                 };
+                if mods.hidden && !matches!(obj.kind, RenderObjectKind::Spinner { .. }) {
+                    opacity = self.hidden_opacity(obj, current_time);
+                }
+                if let Some((focus_x, focus_y)) = focus {
+                    opacity *= flashlight_factor(obj, focus_x, focus_y, mods.flashlight_radius);
+                }
 
                 if opacity > 0.0 {
                     Some((idx, obj, opacity))
@@ -306,17 +304,60 @@ impl BeatmapView {
                     None
                 }
             })
+            // Sorted by start time, so the cap drops the objects furthest in the future
+            .take(mods.max_visible_objects.unwrap_or(usize::MAX))
             .collect()
     }
 
-    /// Get approach circle scale for a hit object
-    pub fn approach_scale(&self, obj: &RenderObject, current_time: f64) -> f32 {
+    /// Get approach circle scale for a hit object (None when visual mods hide approach circles)
+    pub fn approach_scale(&self, obj: &RenderObject, current_time: f64, mods: &VisualMods) -> Option<f32> {
+        if !mods.shows_approach_circles() {
+            return None;
+        }
         let time_until_hit = obj.start_time - current_time;
         if time_until_hit <= 0.0 {
-            1.0
+            Some(1.0)
         } else {
             let progress = (time_until_hit / self.approach_time) as f32;
-            1.0 + progress * 2.5
+            Some(1.0 + progress * 2.5)
+        }
+    }
+
+    /// Opacity under hidden, following osu!'s HD formula
+    ///
+    /// Objects fade in over 40% of the approach time; circles then fade out over
+    /// the next 30% (gone well before the hit), slider bodies fade out until their end.
+    fn hidden_opacity(&self, obj: &RenderObject, current_time: f64) -> f32 {
+        let appear = obj.start_time - self.approach_time;
+        let fade_in_end = appear + self.approach_time * HIDDEN_FADE_IN;
+        let fade_out_end = match obj.kind {
+            RenderObjectKind::Slider { .. } => obj.end_time,
+            _ => fade_in_end + self.approach_time * HIDDEN_FADE_OUT,
+        };
+
+        let opacity = if current_time < fade_in_end {
+            (current_time - appear) / (fade_in_end - appear)
+        } else {
+            1.0 - (current_time - fade_in_end) / (fade_out_end - fade_in_end).max(1.0)
+        };
+        opacity.clamp(0.0, 1.0) as f32
+    }
+
+    /// Play position the flashlight follows: the slider ball, or the last object reached
+    fn focus_position(&self, current_time: f64) -> (f32, f32) {
+        let reached = self.objects.partition_point(|obj| obj.start_time <= current_time);
+        let Some(obj) = reached.checked_sub(1).and_then(|i| self.objects.get(i)) else {
+            // Before the first object the cursor rests on it
+            return self.objects.first().map_or((PLAYFIELD_WIDTH / 2.0, PLAYFIELD_HEIGHT / 2.0), |obj| (obj.x, obj.y));
+        };
+
+        match &obj.kind {
+            RenderObjectKind::Circle => (obj.x, obj.y),
+            RenderObjectKind::Slider { path_points, repeats, .. } => self
+                .slider_ball_position(obj, current_time)
+                .or_else(|| osu_geometry::end_position(path_points, repeats + 1))
+                .unwrap_or((obj.x, obj.y)),
+            RenderObjectKind::Spinner { .. } => (PLAYFIELD_WIDTH / 2.0, PLAYFIELD_HEIGHT / 2.0),
         }
     }
 
@@ -330,44 +371,15 @@ impl BeatmapView {
 
     /// Get slider ball position at current time
     pub fn slider_ball_position(&self, obj: &RenderObject, current_time: f64) -> Option<(f32, f32)> {
-        if let RenderObjectKind::Slider {
-            path_points,
-            duration,
-            repeats,
-        } = &obj.kind
-        {
-            if current_time < obj.start_time
-                || current_time > obj.end_time
-                || path_points.is_empty()
-            {
-                return None;
-            }
-
-            let elapsed = current_time - obj.start_time;
-            let single_pass_duration = *duration / (*repeats + 1) as f64;
-            let pass_number = (elapsed / single_pass_duration) as u32;
-            let pass_progress = (elapsed % single_pass_duration) / single_pass_duration;
-
-            let progress = if pass_number % 2 == 0 {
-                pass_progress
-            } else {
-                1.0 - pass_progress
-            };
-
-            let path_len = path_points.len();
-            let float_idx = progress * (path_len - 1) as f64;
-            let idx = float_idx as usize;
-            let frac = float_idx.fract() as f32;
-
-            if idx >= path_len - 1 {
-                Some(path_points[path_len - 1])
-            } else {
-                let (x1, y1) = path_points[idx];
-                let (x2, y2) = path_points[idx + 1];
-                Some((x1 + (x2 - x1) * frac, y1 + (y2 - y1) * frac))
-            }
-        } else {
-            None
+        match &obj.kind {
+            RenderObjectKind::Slider { path_points, duration, repeats } => osu_geometry::slider_ball_position(
+                path_points,
+                *repeats,
+                *duration,
+                obj.start_time,
+                current_time,
+            ),
+            _ => None,
         }
     }
 
@@ -442,3 +454,40 @@ impl BeatmapView {
             .sum()
     }
 }
+
+/// Flashlight visibility of an object: 1 inside `radius` of the focus, fading to 0 over a soft edge
+fn flashlight_factor(obj: &RenderObject, focus_x: f32, focus_y: f32, radius: f32) -> f32 {
+    if matches!(obj.kind, RenderObjectKind::Spinner { .. }) {
+        return 1.0;
+    }
+    let distance = (obj.x - focus_x).hypot(obj.y - focus_y);
+    let edge = radius * 0.25;
+    (1.0 - (distance - radius) / edge).clamp(0.0, 1.0)
+}
+
+/// Absolute body path of a slider, sampled with osu-geometry
+fn slider_path_points(slider: &HitObjectSlider) -> Vec<(f32, f32)> {
+    let control_points: Vec<ControlPoint> = slider
+        .path
+        .control_points()
+        .iter()
+        .map(|cp| ControlPoint {
+            pos: (slider.pos.x + cp.pos.x, slider.pos.y + cp.pos.y),
+            kind: cp.path_type.map(path_kind),
+        })
+        .collect();
+    osu_geometry::sample_path(&control_points, slider.path.expected_dist(), 0.0)
+}
+
+fn path_kind(path_type: PathType) -> PathKind {
+    if path_type == PathType::LINEAR {
+        PathKind::Linear
+    } else if path_type == PathType::PERFECT_CURVE {
+        PathKind::PerfectCurve
+    } else if path_type == PathType::CATMULL {
+        PathKind::Catmull
+    } else {
+        // Bezier and higher-degree B-splines
+        PathKind::Bezier
+    }
+}
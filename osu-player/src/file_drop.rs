@@ -0,0 +1,78 @@
+//! Drag-and-drop opening of beatmaps onto the window
+
+use bevy::prelude::*;
+use bevy::window::{FileDragAndDrop, PrimaryWindow};
+use bevy_kira_audio::prelude::*;
+
+use crate::audio::AudioState;
+use crate::beatmap::BeatmapView;
+use crate::loader::load_beatmap;
+use crate::playback::PlaybackStateRes;
+use crate::{AudioFilePath, BeatmapTitle, MaxObjects, OpenedArchive};
+
+pub struct FileDropPlugin;
+
+impl Plugin for FileDropPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, handle_file_drop);
+    }
+}
+
+/// System to replace the loaded beatmap with a dropped .osu file or folder
+fn handle_file_drop(
+    mut commands: Commands,
+    mut drop_events: MessageReader<FileDragAndDrop>,
+    mut playback: ResMut<PlaybackStateRes>,
+    mut audio_state: ResMut<AudioState>,
+    mut audio_instances: ResMut<Assets<AudioInstance>>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    max_objects: Res<MaxObjects>,
+) {
+    // Only the last dropped path is opened when several arrive at once
+    let Some(path) = drop_events
+        .read()
+        .filter_map(|event| match event {
+            FileDragAndDrop::DroppedFile { path_buf, .. } => Some(path_buf.clone()),
+            _ => None,
+        })
+        .last()
+    else {
+        return;
+    };
+
+    let loaded = match load_beatmap(&path, max_objects.0) {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            log::error!("Failed to open {}: {:#}", path.display(), e);
+            return;
+        }
+    };
+    log::info!("Opened {}", loaded.osu_path.display());
+
+    let title = loaded.window_title();
+    if let Ok(mut window) = windows.single_mut() {
+        window.title = title.clone();
+    }
+
+    // Stop the current audio; it restarts from the new path on play
+    if let Some(instance_handle) = audio_state.instance.take() {
+        if let Some(instance) = audio_instances.get_mut(&instance_handle) {
+            instance.stop(AudioTween::default());
+        }
+    }
+    *audio_state = AudioState {
+        audio_path: loaded.audio_path.clone(),
+        speed: 1.0,
+        ..default()
+    };
+
+    let beatmap_view = BeatmapView::new(loaded.beatmap);
+    *playback = PlaybackStateRes::new(beatmap_view.total_duration);
+
+    // Systems watching resource_changed::<BeatmapView> rebuild from here
+    commands.insert_resource(beatmap_view);
+    commands.insert_resource(AudioFilePath(loaded.audio_path));
+    commands.insert_resource(BeatmapTitle(title));
+    // Replacing the resource removes the previous archive, whose audio was stopped above
+    commands.insert_resource(OpenedArchive(loaded.archive));
+}
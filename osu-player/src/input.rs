@@ -2,6 +2,7 @@
 
 use bevy::prelude::*;
 
+use crate::keybinds::{Action, KeyBindings};
 use crate::playback::PlaybackStateRes;
 
 pub struct InputPlugin;
@@ -9,6 +10,7 @@ pub struct InputPlugin;
 impl Plugin for InputPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<SeekConfig>()
+            .init_resource::<KeyBindings>()
             .add_systems(Update, handle_keyboard_input);
     }
 }
@@ -56,13 +58,14 @@ impl SeekConfig {
 /// System to handle keyboard input
 fn handle_keyboard_input(
     keyboard: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
     mut playback: ResMut<PlaybackStateRes>,
     seek_config: Res<SeekConfig>,
     time: Res<Time>,
     mut seek_timer: Local<f32>,
 ) {
     // Space: toggle play/pause
-    if keyboard.just_pressed(KeyCode::Space) {
+    if bindings.just_pressed(Action::TogglePlay, &keyboard) {
         playback.toggle_play();
     }
 
@@ -70,12 +73,12 @@ fn handle_keyboard_input(
     let seek_interval = 0.1; // 100ms between seeks when holding
 
     // Left/Right: seek (supports holding)
-    let seeking_left = keyboard.pressed(KeyCode::ArrowLeft);
-    let seeking_right = keyboard.pressed(KeyCode::ArrowRight);
+    let seeking_left = bindings.pressed(Action::SeekBackward, &keyboard);
+    let seeking_right = bindings.pressed(Action::SeekForward, &keyboard);
 
     if seeking_left || seeking_right {
-        let just_pressed = keyboard.just_pressed(KeyCode::ArrowLeft) 
-            || keyboard.just_pressed(KeyCode::ArrowRight);
+        let just_pressed = bindings.just_pressed(Action::SeekBackward, &keyboard)
+            || bindings.just_pressed(Action::SeekForward, &keyboard);
 
         if just_pressed {
             // Immediate seek on first press
@@ -102,28 +105,28 @@ fn handle_keyboard_input(
     }
 
     // Up/Down: playback speed
-    if keyboard.just_pressed(KeyCode::ArrowUp) {
+    if bindings.just_pressed(Action::SpeedUp, &keyboard) {
         let current_speed = playback.speed;
         playback.set_speed(current_speed + 0.25);
     }
-    if keyboard.just_pressed(KeyCode::ArrowDown) {
+    if bindings.just_pressed(Action::SpeedDown, &keyboard) {
         let current_speed = playback.speed;
         playback.set_speed(current_speed - 0.25);
     }
 
     // Home: go to start
-    if keyboard.just_pressed(KeyCode::Home) {
+    if bindings.just_pressed(Action::SeekStart, &keyboard) {
         playback.seek(0.0);
     }
 
     // End: go to end
-    if keyboard.just_pressed(KeyCode::End) {
+    if bindings.just_pressed(Action::SeekEnd, &keyboard) {
         let total = playback.total_duration;
         playback.seek(total - 1000.0);
     }
 
     // R: toggle reverse
-    if keyboard.just_pressed(KeyCode::KeyR) {
+    if bindings.just_pressed(Action::ToggleReverse, &keyboard) {
         playback.toggle_reverse();
     }
 }
@@ -0,0 +1,303 @@
+//! Configurable key bindings
+//!
+//! Bindings come from the defaults below, then `keybinds.toml` (if present),
+//! then `--bind` overrides. The file maps action names to one key or a list:
+//!
+//! ```toml
+//! toggle_play = "Space"
+//! seek_backward = ["Left", "A"]
+//! ```
+
+use anyhow::{bail, Context, Result};
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// Config file read from the working directory when no `--keybinds` path is given
+pub const DEFAULT_KEYBINDS_FILE: &str = "keybinds.toml";
+
+/// Something a key can be bound to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    TogglePlay,
+    SeekBackward,
+    SeekForward,
+    SpeedUp,
+    SpeedDown,
+    SeekStart,
+    SeekEnd,
+    ToggleReverse,
+    ZoomIn,
+    ZoomOut,
+    ZoomReset,
+    ResetView,
+    ToggleHidden,
+    ToggleFlashlight,
+    ToggleApproachCircles,
+    ToggleGrid,
+}
+
+impl Action {
+    pub const ALL: [Action; 16] = [
+        Action::TogglePlay,
+        Action::SeekBackward,
+        Action::SeekForward,
+        Action::SpeedUp,
+        Action::SpeedDown,
+        Action::SeekStart,
+        Action::SeekEnd,
+        Action::ToggleReverse,
+        Action::ZoomIn,
+        Action::ZoomOut,
+        Action::ZoomReset,
+        Action::ResetView,
+        Action::ToggleHidden,
+        Action::ToggleFlashlight,
+        Action::ToggleApproachCircles,
+        Action::ToggleGrid,
+    ];
+
+    /// Name used in `keybinds.toml` and `--bind`
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::TogglePlay => "toggle_play",
+            Action::SeekBackward => "seek_backward",
+            Action::SeekForward => "seek_forward",
+            Action::SpeedUp => "speed_up",
+            Action::SpeedDown => "speed_down",
+            Action::SeekStart => "seek_start",
+            Action::SeekEnd => "seek_end",
+            Action::ToggleReverse => "toggle_reverse",
+            Action::ZoomIn => "zoom_in",
+            Action::ZoomOut => "zoom_out",
+            Action::ZoomReset => "zoom_reset",
+            Action::ResetView => "reset_view",
+            Action::ToggleHidden => "toggle_hidden",
+            Action::ToggleFlashlight => "toggle_flashlight",
+            Action::ToggleApproachCircles => "toggle_approach_circles",
+            Action::ToggleGrid => "toggle_grid",
+        }
+    }
+
+    fn from_name(name: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|action| action.name() == name)
+            .with_context(|| format!("Unknown action '{}'", name))
+    }
+
+    fn default_keys(self) -> Vec<KeyCode> {
+        match self {
+            Action::TogglePlay => vec![KeyCode::Space],
+            Action::SeekBackward => vec![KeyCode::ArrowLeft],
+            Action::SeekForward => vec![KeyCode::ArrowRight],
+            Action::SpeedUp => vec![KeyCode::ArrowUp],
+            Action::SpeedDown => vec![KeyCode::ArrowDown],
+            Action::SeekStart => vec![KeyCode::Home],
+            Action::SeekEnd => vec![KeyCode::End],
+            Action::ToggleReverse => vec![KeyCode::KeyR],
+            Action::ZoomIn => vec![KeyCode::Equal, KeyCode::NumpadAdd],
+            Action::ZoomOut => vec![KeyCode::Minus, KeyCode::NumpadSubtract],
+            Action::ZoomReset => vec![KeyCode::Digit0, KeyCode::Numpad0],
+            Action::ResetView => vec![KeyCode::KeyF],
+            Action::ToggleHidden => vec![KeyCode::KeyH],
+            Action::ToggleFlashlight => vec![KeyCode::KeyL],
+            Action::ToggleApproachCircles => vec![KeyCode::KeyA],
+            Action::ToggleGrid => vec![KeyCode::KeyG],
+        }
+    }
+}
+
+/// Keys bound to each action, consulted by the input systems
+#[derive(Resource, Debug, Clone)]
+pub struct KeyBindings {
+    keys: HashMap<Action, Vec<KeyCode>>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            keys: Action::ALL.into_iter().map(|action| (action, action.default_keys())).collect(),
+        }
+    }
+}
+
+/// One key or a list of keys in `keybinds.toml`
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum KeySpec {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl KeyBindings {
+    /// Defaults overridden by the actions listed in a `keybinds.toml` file
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let specs: BTreeMap<String, KeySpec> = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+
+        let mut bindings = Self::default();
+        for (name, spec) in specs {
+            let names = match spec {
+                KeySpec::One(key) => vec![key],
+                KeySpec::Many(keys) => keys,
+            };
+            let keys = names.iter().map(|key| parse_key(key)).collect::<Result<Vec<_>>>()
+                .with_context(|| format!("Invalid keys for '{}' in {}", name, path.display()))?;
+            bindings.keys.insert(Action::from_name(&name)?, keys);
+        }
+        Ok(bindings)
+    }
+
+    /// Apply a `--bind action=Key[,Key...]` override
+    pub fn apply_override(&mut self, spec: &str) -> Result<()> {
+        let Some((name, keys)) = spec.split_once('=') else {
+            bail!("Expected action=Key[,Key...], got '{}'", spec);
+        };
+        let action = Action::from_name(name.trim())?;
+        let keys = keys.split(',').map(|key| parse_key(key.trim())).collect::<Result<Vec<_>>>()?;
+        self.keys.insert(action, keys);
+        Ok(())
+    }
+
+    pub fn keys(&self, action: Action) -> &[KeyCode] {
+        self.keys.get(&action).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Whether any key of `action` is held
+    pub fn pressed(&self, action: Action, keyboard: &ButtonInput<KeyCode>) -> bool {
+        keyboard.any_pressed(self.keys(action).iter().copied())
+    }
+
+    /// Whether any key of `action` was pressed this frame
+    pub fn just_pressed(&self, action: Action, keyboard: &ButtonInput<KeyCode>) -> bool {
+        keyboard.any_just_pressed(self.keys(action).iter().copied())
+    }
+
+    /// First bound key of `action` for on-screen hints (empty if unbound)
+    pub fn label(&self, action: Action) -> String {
+        self.keys(action).first().map(|&key| key_label(key)).unwrap_or_default()
+    }
+}
+
+/// Parse a key name, case-insensitively
+///
+/// Accepts bevy's `KeyCode` names (`KeyA`, `Digit1`, `ArrowLeft`) as well as
+/// the short forms `A`, `1` and `Left`.
+pub fn parse_key(name: &str) -> Result<KeyCode> {
+    let lower = name.trim().to_ascii_lowercase();
+    let key = lower.strip_prefix("key").filter(|rest| rest.len() == 1).unwrap_or(&lower);
+    let key = key.strip_prefix("digit").unwrap_or(key);
+    let key = key.strip_prefix("arrow").unwrap_or(key);
+
+    let code = match key {
+        "a" => KeyCode::KeyA,
+        "b" => KeyCode::KeyB,
+        "c" => KeyCode::KeyC,
+        "d" => KeyCode::KeyD,
+        "e" => KeyCode::KeyE,
+        "f" => KeyCode::KeyF,
+        "g" => KeyCode::KeyG,
+        "h" => KeyCode::KeyH,
+        "i" => KeyCode::KeyI,
+        "j" => KeyCode::KeyJ,
+        "k" => KeyCode::KeyK,
+        "l" => KeyCode::KeyL,
+        "m" => KeyCode::KeyM,
+        "n" => KeyCode::KeyN,
+        "o" => KeyCode::KeyO,
+        "p" => KeyCode::KeyP,
+        "q" => KeyCode::KeyQ,
+        "r" => KeyCode::KeyR,
+        "s" => KeyCode::KeyS,
+        "t" => KeyCode::KeyT,
+        "u" => KeyCode::KeyU,
+        "v" => KeyCode::KeyV,
+        "w" => KeyCode::KeyW,
+        "x" => KeyCode::KeyX,
+        "y" => KeyCode::KeyY,
+        "z" => KeyCode::KeyZ,
+        "0" => KeyCode::Digit0,
+        "1" => KeyCode::Digit1,
+        "2" => KeyCode::Digit2,
+        "3" => KeyCode::Digit3,
+        "4" => KeyCode::Digit4,
+        "5" => KeyCode::Digit5,
+        "6" => KeyCode::Digit6,
+        "7" => KeyCode::Digit7,
+        "8" => KeyCode::Digit8,
+        "9" => KeyCode::Digit9,
+        "numpad0" => KeyCode::Numpad0,
+        "numpad1" => KeyCode::Numpad1,
+        "numpad2" => KeyCode::Numpad2,
+        "numpad3" => KeyCode::Numpad3,
+        "numpad4" => KeyCode::Numpad4,
+        "numpad5" => KeyCode::Numpad5,
+        "numpad6" => KeyCode::Numpad6,
+        "numpad7" => KeyCode::Numpad7,
+        "numpad8" => KeyCode::Numpad8,
+        "numpad9" => KeyCode::Numpad9,
+        "numpadadd" => KeyCode::NumpadAdd,
+        "numpadsubtract" => KeyCode::NumpadSubtract,
+        "left" => KeyCode::ArrowLeft,
+        "right" => KeyCode::ArrowRight,
+        "up" => KeyCode::ArrowUp,
+        "down" => KeyCode::ArrowDown,
+        "space" => KeyCode::Space,
+        "enter" => KeyCode::Enter,
+        "escape" => KeyCode::Escape,
+        "tab" => KeyCode::Tab,
+        "backspace" => KeyCode::Backspace,
+        "delete" => KeyCode::Delete,
+        "insert" => KeyCode::Insert,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "minus" => KeyCode::Minus,
+        "equal" => KeyCode::Equal,
+        "comma" => KeyCode::Comma,
+        "period" => KeyCode::Period,
+        "slash" => KeyCode::Slash,
+        "backslash" => KeyCode::Backslash,
+        "semicolon" => KeyCode::Semicolon,
+        "quote" => KeyCode::Quote,
+        "backquote" => KeyCode::Backquote,
+        "bracketleft" => KeyCode::BracketLeft,
+        "bracketright" => KeyCode::BracketRight,
+        "f1" => KeyCode::F1,
+        "f2" => KeyCode::F2,
+        "f3" => KeyCode::F3,
+        "f4" => KeyCode::F4,
+        "f5" => KeyCode::F5,
+        "f6" => KeyCode::F6,
+        "f7" => KeyCode::F7,
+        "f8" => KeyCode::F8,
+        "f9" => KeyCode::F9,
+        "f10" => KeyCode::F10,
+        "f11" => KeyCode::F11,
+        "f12" => KeyCode::F12,
+        _ => bail!("Unknown key '{}'", name),
+    };
+    Ok(code)
+}
+
+/// Short on-screen name of a key
+fn key_label(key: KeyCode) -> String {
+    match key {
+        KeyCode::ArrowLeft => "←".to_string(),
+        KeyCode::ArrowRight => "→".to_string(),
+        KeyCode::ArrowUp => "↑".to_string(),
+        KeyCode::ArrowDown => "↓".to_string(),
+        other => {
+            let name = format!("{:?}", other);
+            name.strip_prefix("Key")
+                .or_else(|| name.strip_prefix("Digit"))
+                .unwrap_or(&name)
+                .to_string()
+        }
+    }
+}
@@ -0,0 +1,81 @@
+//! Beatmap loading from a .osu file, a folder containing one, or a .osz archive
+
+use anyhow::{Context, Result};
+use osz_extractor::ExtractedArchive;
+use std::path::{Path, PathBuf};
+
+/// Default `--max-objects`: far above any playable map
+pub const DEFAULT_MAX_OBJECTS: usize = 100_000;
+
+/// A parsed beatmap with its resolved audio file
+pub struct LoadedBeatmap {
+    /// Parsed beatmap
+    pub beatmap: rosu_map::Beatmap,
+    /// Path of the .osu file that was parsed
+    pub osu_path: PathBuf,
+    /// Audio file next to the .osu, if it exists
+    pub audio_path: Option<PathBuf>,
+    /// Temp folder of an opened .osz; the beatmap's files live there until it is dropped
+    pub archive: Option<ExtractedArchive>,
+}
+
+impl LoadedBeatmap {
+    /// Window title for this beatmap
+    pub fn window_title(&self) -> String {
+        format!(
+            "{} - {} [{}] - osu-player",
+            self.beatmap.artist, self.beatmap.title, self.beatmap.version
+        )
+    }
+}
+
+/// Load a beatmap from a .osu file, or from the first .osu file in a folder or .osz archive
+///
+/// Beatmaps with more than `max_objects` hit objects are refused.
+pub fn load_beatmap(path: &Path, max_objects: usize) -> Result<LoadedBeatmap> {
+    let osz_extractor::ResolvedOsu { osu_path, archive } = osz_extractor::resolve_osu_path(path)?;
+    if let Some(archive) = &archive {
+        log::info!("Extracted {} to {}", path.display(), archive.path().display());
+    }
+
+    log::info!("Loading beatmap: {}", osu_path.display());
+    let beatmap: rosu_map::Beatmap =
+        rosu_map::from_path(&osu_path).context("Failed to parse .osu file")?;
+
+    log::info!(
+        "Loaded: {} - {} [{}]",
+        beatmap.artist,
+        beatmap.title,
+        beatmap.version
+    );
+    log::info!("Hit objects: {}", beatmap.hit_objects.len());
+    if beatmap.hit_objects.len() > max_objects {
+        anyhow::bail!(
+            "{} has {} hit objects, more than --max-objects {}",
+            osu_path.display(),
+            beatmap.hit_objects.len(),
+            max_objects
+        );
+    }
+
+    // Get audio file path
+    let audio_path = osz_extractor::find_audio(&osu_path, &beatmap.audio_file);
+
+    if audio_path.is_none() {
+        if osz_extractor::is_virtual_audio(&beatmap.audio_file) {
+            log::info!("Beatmap has no audio (virtual). Playback will be silent.");
+        } else {
+            log::warn!(
+                "Audio file not found: {}. Playback will be silent.",
+                beatmap.audio_file
+            );
+        }
+    }
+
+    Ok(LoadedBeatmap {
+        beatmap,
+        osu_path,
+        audio_path,
+        archive,
+    })
+}
@@ -1,13 +1,19 @@
 //! osu-player: Bevy-powered .osu beatmap player
 //!
-//! Usage: osu-player <path-to-osu-file>
+//! Usage: osu-player <path-to-osu-file-folder-or-osz>
+//!
+//! Further beatmaps can be opened by dropping them onto the window.
 
 mod audio;
 mod beatmap;
+mod file_drop;
 mod input;
+mod keybinds;
+mod loader;
 mod playback;
 mod rendering;
 mod ui;
+mod visual_mods;
 
 use anyhow::{Context, Result};
 use bevy::asset::UnapprovedPathMode;
@@ -20,18 +26,50 @@ use std::path::PathBuf;
 
 use audio::AudioPlayerPlugin;
 use beatmap::BeatmapView;
+use file_drop::FileDropPlugin;
 use input::InputPlugin;
+use keybinds::{KeyBindings, DEFAULT_KEYBINDS_FILE};
+use loader::{load_beatmap, DEFAULT_MAX_OBJECTS};
 use playback::PlaybackPlugin;
 use rendering::RenderingPlugin;
 use ui::UiPlugin;
+use visual_mods::{VisualMods, VisualModsPlugin, DEFAULT_FLASHLIGHT_RADIUS};
 
 #[derive(Parser, Debug)]
 #[command(name = "osu-player")]
 #[command(about = "Bevy-powered .osu beatmap player with 2D rendering")]
 struct Args {
-    /// Path to the .osu file to play
+    /// Path to the .osu file (or a folder or .osz archive containing one) to play
     #[arg(required = true)]
     osu_file: PathBuf,
+
+    /// Key binding config (default: keybinds.toml in the working directory, if present)
+    #[arg(long)]
+    keybinds: Option<PathBuf>,
+
+    /// Override a key binding, e.g. `--bind seek_backward=A,Left` (repeatable)
+    #[arg(long = "bind", value_name = "ACTION=KEYS")]
+    binds: Vec<String>,
+
+    /// Start with hidden on (objects fade out before their hit time)
+    #[arg(long)]
+    hidden: bool,
+
+    /// Start with flashlight on, optionally with a radius in osu!pixels
+    #[arg(long, value_name = "RADIUS", num_args = 0..=1, default_missing_value = "180")]
+    flashlight: Option<f32>,
+
+    /// Start with approach circles hidden
+    #[arg(long)]
+    no_approach: bool,
+
+    /// Draw at most N objects at once on dense maps, dropping those furthest in the future
+    #[arg(long, value_name = "N")]
+    max_visible_objects: Option<usize>,
+
+    /// Refuse to open beatmaps with more than N hit objects (troll maps make the player unusable)
+    #[arg(long, value_name = "N", default_value_t = DEFAULT_MAX_OBJECTS)]
+    max_objects: usize,
 }
 
 /// Resource holding the path to the audio file
@@ -42,54 +80,48 @@ pub struct AudioFilePath(pub Option<PathBuf>);
 #[derive(Resource)]
 pub struct BeatmapTitle(pub String);
 
+/// Resource keeping the extracted .osz of the current beatmap, whose audio streams from it
+#[derive(Resource)]
+pub struct OpenedArchive(pub Option<osz_extractor::ExtractedArchive>);
+
+/// Resource holding the `--max-objects` limit for dropped beatmaps
+#[derive(Resource)]
+pub struct MaxObjects(pub usize);
+
 fn main() -> Result<()> {
     env_logger::init();
 
     let args = Args::parse();
 
-    // Validate the file exists
-    if !args.osu_file.exists() {
-        anyhow::bail!("File not found: {}", args.osu_file.display());
+    let keybinds_path = args
+        .keybinds
+        .clone()
+        .or_else(|| Some(PathBuf::from(DEFAULT_KEYBINDS_FILE)).filter(|path| path.exists()));
+    let mut key_bindings = match &keybinds_path {
+        Some(path) => KeyBindings::load(path)?,
+        None => KeyBindings::default(),
+    };
+    for bind in &args.binds {
+        key_bindings
+            .apply_override(bind)
+            .with_context(|| format!("Invalid --bind '{}'", bind))?;
     }
 
-    if args.osu_file.extension().map_or(true, |e| e != "osu") {
-        anyhow::bail!("File must have .osu extension");
-    }
-
-    // Parse the beatmap
-    log::info!("Loading beatmap: {}", args.osu_file.display());
-    let beatmap: rosu_map::Beatmap =
-        rosu_map::from_path(&args.osu_file).context("Failed to parse .osu file")?;
-
-    log::info!(
-        "Loaded: {} - {} [{}]",
-        beatmap.artist,
-        beatmap.title,
-        beatmap.version
-    );
-    log::info!("Hit objects: {}", beatmap.hit_objects.len());
-
-    // Get audio file path
-    let audio_path = args
-        .osu_file
-        .parent()
-        .map(|p| p.join(&beatmap.audio_file))
-        .filter(|p| p.exists());
-
-    if audio_path.is_none() {
-        log::warn!(
-            "Audio file not found: {}. Playback will be silent.",
-            beatmap.audio_file
-        );
-    }
+    let visual_mods = VisualMods {
+        hidden: args.hidden,
+        flashlight: args.flashlight.is_some(),
+        flashlight_radius: args.flashlight.unwrap_or(DEFAULT_FLASHLIGHT_RADIUS),
+        approach_circles: !args.no_approach,
+        max_visible_objects: args.max_visible_objects,
+    };
 
-    let title = format!(
-        "{} - {} [{}] - osu-player",
-        beatmap.artist, beatmap.title, beatmap.version
-    );
+    let loaded = load_beatmap(&args.osu_file, args.max_objects)?;
+    let title = loaded.window_title();
+    let audio_path = loaded.audio_path;
+    let archive = loaded.archive;
 
     // Create beatmap view
-    let beatmap_view = BeatmapView::new(beatmap);
+    let beatmap_view = BeatmapView::new(loaded.beatmap);
 
     // Run Bevy app
     App::new()
@@ -117,10 +149,16 @@ fn main() -> Result<()> {
         .add_plugins(RenderingPlugin)
         .add_plugins(UiPlugin)
         .add_plugins(InputPlugin)
+        .add_plugins(FileDropPlugin)
+        .add_plugins(VisualModsPlugin)
         .add_systems(Startup, configure_gizmos)
         .insert_resource(beatmap_view)
+        .insert_resource(key_bindings)
+        .insert_resource(visual_mods)
         .insert_resource(AudioFilePath(audio_path))
         .insert_resource(BeatmapTitle(title))
+        .insert_resource(OpenedArchive(archive))
+        .insert_resource(MaxObjects(args.max_objects))
         .run();
 
     Ok(())
@@ -0,0 +1,61 @@
+//! Editor grid and center crosshair drawn over the playfield (toggled with G)
+
+use bevy::prelude::*;
+
+use crate::beatmap::{BeatmapView, PLAYFIELD_HEIGHT, PLAYFIELD_WIDTH};
+use crate::keybinds::{Action, KeyBindings};
+use crate::rendering::PlayfieldTransform;
+
+/// Grid line color; translucent since gizmos draw on top of objects
+const GRID_COLOR: Color = Color::srgba(0.6, 0.6, 0.8, 0.18);
+/// Center crosshair color
+const CROSSHAIR_COLOR: Color = Color::srgba(0.8, 0.8, 1.0, 0.45);
+
+pub struct GridOverlayPlugin;
+
+impl Plugin for GridOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GridOverlay>()
+            .add_systems(Update, (handle_grid_key, draw_grid_overlay).chain());
+    }
+}
+
+/// Whether the editor grid overlay is shown (off by default)
+#[derive(Resource, Debug, Default)]
+pub struct GridOverlay {
+    pub enabled: bool,
+}
+
+fn handle_grid_key(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut overlay: ResMut<GridOverlay>,
+) {
+    if bindings.just_pressed(Action::ToggleGrid, &keyboard) {
+        overlay.enabled = !overlay.enabled;
+    }
+}
+
+/// Draw grid lines at the map's `GridSize` spacing and a crosshair through the playfield center
+fn draw_grid_overlay(
+    mut gizmos: Gizmos,
+    overlay: Res<GridOverlay>,
+    beatmap: Res<BeatmapView>,
+    transform: Res<PlayfieldTransform>,
+) {
+    if !overlay.enabled {
+        return;
+    }
+
+    let spacing = osu_geometry::editor_grid_spacing(beatmap.beatmap.grid_size);
+    for x in osu_geometry::grid_lines(spacing, PLAYFIELD_WIDTH) {
+        gizmos.line_2d(transform.osu_to_screen(x, 0.0), transform.osu_to_screen(x, PLAYFIELD_HEIGHT), GRID_COLOR);
+    }
+    for y in osu_geometry::grid_lines(spacing, PLAYFIELD_HEIGHT) {
+        gizmos.line_2d(transform.osu_to_screen(0.0, y), transform.osu_to_screen(PLAYFIELD_WIDTH, y), GRID_COLOR);
+    }
+
+    let (cx, cy) = (PLAYFIELD_WIDTH / 2.0, PLAYFIELD_HEIGHT / 2.0);
+    gizmos.line_2d(transform.osu_to_screen(cx, 0.0), transform.osu_to_screen(cx, PLAYFIELD_HEIGHT), CROSSHAIR_COLOR);
+    gizmos.line_2d(transform.osu_to_screen(0.0, cy), transform.osu_to_screen(PLAYFIELD_WIDTH, cy), CROSSHAIR_COLOR);
+}
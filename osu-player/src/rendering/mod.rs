@@ -1,6 +1,7 @@
 //! Rendering module for osu! hit objects
 
 mod circles;
+mod grid_overlay;
 mod playfield;
 pub mod sdf_materials;
 pub mod sdf_render;
@@ -10,6 +11,7 @@ mod spinners;
 use bevy::prelude::*;
 
 pub use circles::*;
+pub use grid_overlay::*;
 pub use playfield::*;
 pub use sdf_materials::SdfMaterialsPlugin;
 pub use sdf_render::SdfRenderPlugin;
@@ -18,6 +20,7 @@ pub use spinners::*;
 
 use crate::beatmap::{BeatmapView, RenderObjectKind};
 use crate::playback::PlaybackStateRes;
+use crate::visual_mods::VisualMods;
 
 pub struct RenderingPlugin;
 
@@ -29,6 +32,7 @@ impl Plugin for RenderingPlugin {
             .add_plugins(SpinnersPlugin)
             .add_plugins(SdfMaterialsPlugin)
             .add_plugins(SdfRenderPlugin)
+            .add_plugins(GridOverlayPlugin)
             .add_systems(Update, render_all_objects);
     }
 }
@@ -41,9 +45,10 @@ fn render_all_objects(
     beatmap: Res<BeatmapView>,
     playback: Res<PlaybackStateRes>,
     transform: Res<PlayfieldTransform>,
+    mods: Res<VisualMods>,
 ) {
     let current_time = playback.current_time;
-    let visible = beatmap.visible_objects(current_time);
+    let visible = beatmap.visible_objects(current_time, &mods);
     let radius = transform.scale_radius(beatmap.circle_radius);
 
     // With SDF rendering, we only use gizmos for:
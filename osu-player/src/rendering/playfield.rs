@@ -3,6 +3,7 @@
 use bevy::prelude::*;
 
 use crate::beatmap::{PLAYFIELD_HEIGHT, PLAYFIELD_WIDTH};
+use crate::keybinds::{Action, KeyBindings};
 use crate::rendering::sdf_materials::GridMaterial;
 
 pub struct PlayfieldPlugin;
@@ -126,6 +127,7 @@ fn setup_playfield(
 /// Handle keyboard and mouse wheel input for zoom
 fn handle_zoom_input(
     keyboard: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
     mut scroll_events: MessageReader<bevy::input::mouse::MouseWheel>,
     mut zoom: ResMut<ZoomLevel>,
 ) {
@@ -135,14 +137,14 @@ fn handle_zoom_input(
     let max_zoom = 2.0;
 
     // Keyboard zoom
-    if keyboard.pressed(KeyCode::Equal) || keyboard.pressed(KeyCode::NumpadAdd) {
+    if bindings.pressed(Action::ZoomIn, &keyboard) {
         zoom.level = (zoom.level + zoom_speed).min(max_zoom);
     }
-    if keyboard.pressed(KeyCode::Minus) || keyboard.pressed(KeyCode::NumpadSubtract) {
+    if bindings.pressed(Action::ZoomOut, &keyboard) {
         zoom.level = (zoom.level - zoom_speed).max(min_zoom);
     }
     // Reset zoom with 0 key
-    if keyboard.just_pressed(KeyCode::Digit0) || keyboard.just_pressed(KeyCode::Numpad0) {
+    if bindings.just_pressed(Action::ZoomReset, &keyboard) {
         zoom.level = 1.0;
     }
 
@@ -158,13 +160,14 @@ fn handle_pan_reset_input(
     mouse_button: Res<ButtonInput<MouseButton>>,
     mut mouse_motion: MessageReader<bevy::input::mouse::MouseMotion>,
     keyboard: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
     mut zoom: ResMut<ZoomLevel>,
     mut transform: ResMut<PlayfieldTransform>,
     ui_interaction_query: Query<&Interaction, With<Node>>,
     mut is_dragging: Local<bool>,
 ) {
     // Reset with F key
-    if keyboard.just_pressed(KeyCode::KeyF) {
+    if bindings.just_pressed(Action::ResetView, &keyboard) {
         zoom.level = 1.0;
         transform.user_offset = Vec2::ZERO;
         transform.generation = transform.generation.wrapping_add(1);
@@ -122,10 +122,13 @@ pub struct SliderUniforms {
     pub bbox_size: Vec2,
 }
 
+/// Most path points the slider shader takes; longer paths are resampled to fit
+pub const SLIDER_MAX_POINTS: usize = 128;
+
 /// Path point data packed as vec4s (xy = point N, zw = point N+1)
 #[derive(Clone, Copy, Debug, ShaderType)]
 pub struct SliderPathData {
-    pub points: [Vec4; 64], // 128 points packed as 64 vec4s
+    pub points: [Vec4; 64], // SLIDER_MAX_POINTS packed as 64 vec4s
 }
 
 impl Default for SliderPathData {
@@ -2,17 +2,20 @@
 //! Spawns and manages mesh entities with SDF materials for sliders and circles
 
 use bevy::prelude::*;
-use bevy::sprite_render::MeshMaterial2d;
+use bevy::sprite_render::{Material2d, MeshMaterial2d};
 
 use crate::beatmap::{BeatmapView, RenderObject, RenderObjectKind, PLAYFIELD_HEIGHT, PLAYFIELD_WIDTH};
 use crate::playback::PlaybackStateRes;
 use crate::rendering::sdf_materials::{
     ArrowMaterial, ArrowUniforms, MsdfMaterial, SliderMaterial, SliderPathData, SliderUniforms, SpinnerMaterial, SpinnerUniforms,
+    SLIDER_MAX_POINTS,
     CircleBatchMaterial, MsdfBatchMaterial,
     ATTRIBUTE_BODY_COLOR, ATTRIBUTE_BORDER_COLOR, ATTRIBUTE_APPROACH_COLOR, ATTRIBUTE_SDF_PARAMS,
     ATTRIBUTE_MSDF_UV_BOUNDS, ATTRIBUTE_MSDF_PARAMS
 };
 use crate::rendering::PlayfieldTransform;
+use crate::ui::UiFont;
+use crate::visual_mods::VisualMods;
 use bevy::render::render_resource::PrimitiveTopology;
 use bevy::asset::RenderAssetUsages;
 use bevy::mesh::Indices;
@@ -46,6 +49,10 @@ pub struct ArrowEntity {
 #[derive(Component)]
 pub struct SpinnerMesh;
 
+/// Marker for hidden entities kept in an `SdfRenderState` pool for reuse
+#[derive(Component)]
+pub struct Pooled;
+
 /// Resource to track currently spawned SDF objects and shared resources
 #[derive(Resource)]
 pub struct SdfRenderState {
@@ -56,9 +63,6 @@ pub struct SdfRenderState {
     /// Mesh for batched MSDF digits (updated every frame)
     pub msdf_batch_mesh: Handle<Mesh>,
     
-    /// Cache for shared arrow materials by opacity
-    pub arrow_cache: std::collections::HashMap<u32, Handle<ArrowMaterial>>,
-    
     /// Indices of currently spawned slider objects
     pub spawned_sliders: Vec<usize>,
     /// Indices of sliders with spawned end arrows
@@ -67,6 +71,12 @@ pub struct SdfRenderState {
     pub spawned_start_arrows: Vec<usize>,
     /// Indices of currently spawned spinners
     pub spawned_spinners: Vec<usize>,
+    /// Hidden slider entities and their materials, reused instead of spawning new ones
+    pub slider_pool: Vec<(Entity, Handle<SliderMaterial>)>,
+    /// Hidden spinner entities and their materials
+    pub spinner_pool: Vec<(Entity, Handle<SpinnerMaterial>)>,
+    /// Hidden arrow entities and their materials
+    pub arrow_pool: Vec<(Entity, Handle<ArrowMaterial>)>,
     /// Current vertex capacity for circle batch (number of quads)
     pub circle_capacity: usize,
     /// Current vertex capacity for MSDF batch (number of quads)
@@ -88,11 +98,13 @@ impl FromWorld for SdfRenderState {
             unit_mesh,
             circle_batch_mesh,
             msdf_batch_mesh,
-            arrow_cache: default(),
             spawned_sliders: default(),
             spawned_end_arrows: default(),
             spawned_start_arrows: default(),
             spawned_spinners: default(),
+            slider_pool: default(),
+            spinner_pool: default(),
+            arrow_pool: default(),
             circle_capacity: 0,
             msdf_capacity: 0,
             last_generation: 0,
@@ -113,6 +125,8 @@ pub struct MsdfAtlas {
     pub digit_sizes: [Vec2; 10],
     /// Distance range from atlas generation
     pub px_range: f32,
+    /// Why the glyph metadata could not be loaded; combo numbers then fall back to `Text2d`
+    pub load_error: Option<String>,
 }
 
 impl Default for MsdfAtlas {
@@ -123,6 +137,7 @@ impl Default for MsdfAtlas {
             digit_advances: [0.5; 10],
             digit_sizes: [Vec2::ONE; 10],
             px_range: 2.0, 
+            load_error: None,
         }
     }
 }
@@ -157,12 +172,14 @@ impl Plugin for SdfRenderPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<SdfRenderState>()
             .init_resource::<MsdfAtlas>()
-            .add_systems(Startup, (setup_msdf_atlas, setup_batch_entities).chain())
+            .add_systems(Startup, (setup_msdf_atlas, setup_batch_entities, spawn_atlas_warning).chain())
             .add_systems(Update, (
                 clear_on_transform_change,
                 spawn_sdf_objects,
                 update_non_batched_materials,
                 despawn_invisible_objects,
+                update_fallback_combo_numbers,
+                log_material_counts,
             ).chain())
             .add_systems(PostUpdate, (
                 update_circle_batches,
@@ -212,57 +229,109 @@ fn setup_msdf_atlas(
 ) {
     atlas.texture = asset_server.load("fonts/digits_msdf.png");
     
-    // Load and parse JSON metadata
-    // Using std::fs for simplicity since we don't need hot-reloading for metrics
-    if let Ok(json_str) = std::fs::read_to_string("assets/fonts/digits_msdf.json") {
-        if let Ok(data) = serde_json::from_str::<MsdfJson>(&json_str) {
-            let width = data.atlas.width;
-            let height = data.atlas.height;
-            
-            for glyph in data.glyphs {
-                // Check if it's a digit 0-9 (unicode 48-57)
-                if glyph.unicode >= 48 && glyph.unicode <= 57 {
-                    let index = (glyph.unicode - 48) as usize;
-                    if let Some(bounds) = glyph.atlas_bounds {
-                        // Convert to normalized UV coordinates (0-1)
-                        // JSON is yOrigin: bottom (Y-up), so we need to flip for GPU (Top-Down)
-                        atlas.digit_uvs[index] = Vec4::new(
-                            bounds.left / width,
-                            1.0 - (bounds.top / height),      // Top edge in Y-up is small Y in Top-Down
-                            bounds.right / width,
-                            1.0 - (bounds.bottom / height)    // Bottom edge in Y-up is large Y in Top-Down
-                        );
+    match load_msdf_metadata(&mut atlas) {
+        Ok(()) => log::info!("Loaded MSDF atlas metadata for digits 0-9 (px_range: {})", atlas.px_range),
+        Err(e) => {
+            log::error!("{:#}; combo numbers fall back to Text2d", e);
+            atlas.load_error = Some(format!("{:#}", e));
+        }
+    }
+}
 
-                        // Calculate aspect ratio from atlas bounds
-                        let w = bounds.right - bounds.left;
-                        let h = (bounds.top - bounds.bottom).abs();
-                        let aspect = if h > 0.001 { w / h } else { 1.0 };
-                        atlas.digit_sizes[index] = Vec2::new(aspect, 1.0);
-                    }
-                    atlas.digit_advances[index] = glyph.advance;
-                }
+/// Parse digit UVs and metrics from the msdf-atlas-gen JSON
+fn load_msdf_metadata(atlas: &mut MsdfAtlas) -> anyhow::Result<()> {
+    use anyhow::Context;
+
+    // Using std::fs for simplicity since we don't need hot-reloading for metrics
+    let json_str = std::fs::read_to_string("assets/fonts/digits_msdf.json")
+        .context("Failed to read digits_msdf.json")?;
+    let data = serde_json::from_str::<MsdfJson>(&json_str)
+        .context("Failed to parse digits_msdf.json")?;
+
+    let width = data.atlas.width;
+    let height = data.atlas.height;
+    let mut found = [false; 10];
+    
+    for glyph in data.glyphs {
+        // Check if it's a digit 0-9 (unicode 48-57)
+        if glyph.unicode >= 48 && glyph.unicode <= 57 {
+            let index = (glyph.unicode - 48) as usize;
+            if let Some(bounds) = glyph.atlas_bounds {
+                // Convert to normalized UV coordinates (0-1)
+                // JSON is yOrigin: bottom (Y-up), so we need to flip for GPU (Top-Down)
+                atlas.digit_uvs[index] = Vec4::new(
+                    bounds.left / width,
+                    1.0 - (bounds.top / height),      // Top edge in Y-up is small Y in Top-Down
+                    bounds.right / width,
+                    1.0 - (bounds.bottom / height)    // Bottom edge in Y-up is large Y in Top-Down
+                );
+
+                // Calculate aspect ratio from atlas bounds
+                let w = bounds.right - bounds.left;
+                let h = (bounds.top - bounds.bottom).abs();
+                let aspect = if h > 0.001 { w / h } else { 1.0 };
+                atlas.digit_sizes[index] = Vec2::new(aspect, 1.0);
+                found[index] = true;
             }
-            
-            // Use the distance range from the JSON (should be 2.0)
-            atlas.px_range = data.atlas.distance_range;
-            
-            log::info!("Loaded MSDF atlas metadata for digits 0-9 (px_range: {})", atlas.px_range);
-        } else {
-            log::error!("Failed to parse digits_msdf.json");
+            atlas.digit_advances[index] = glyph.advance;
         }
-    } else {
-        log::error!("Failed to read digits_msdf.json");
     }
+
+    if let Some(missing) = found.iter().position(|&f| !f) {
+        anyhow::bail!("digits_msdf.json has no atlas bounds for digit {}", missing);
+    }
+    
+    // Use the distance range from the JSON (should be 2.0)
+    atlas.px_range = data.atlas.distance_range;
+    Ok(())
+}
+
+/// Marker for the on-screen warning shown when the MSDF atlas failed to load
+#[derive(Component)]
+pub struct AtlasWarning;
+
+/// Show a prominent warning when combo numbers had to fall back to `Text2d`
+fn spawn_atlas_warning(mut commands: Commands, atlas: Res<MsdfAtlas>, ui_font: Res<UiFont>) {
+    let Some(error) = &atlas.load_error else {
+        return;
+    };
+
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            left: Val::Percent(25.0),
+            width: Val::Percent(50.0),
+            padding: UiRect::all(Val::Px(8.0)),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.6, 0.05, 0.05, 0.85)),
+        AtlasWarning,
+    )).with_children(|parent| {
+        parent.spawn((
+            Text::new(format!("⚠ Digit atlas unavailable ({}): combo numbers use fallback text", error)),
+            TextFont {
+                font: ui_font.0.clone(),
+                font_size: 16.0,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+            TextLayout::new_with_justify(Justify::Center),
+        ));
+    });
 }
 
-/// Clear all spawned state when transform changes (resize/zoom)
+/// Clear all spawned state when transform changes (resize/zoom) or a new beatmap is loaded
 fn clear_on_transform_change(
     mut commands: Commands,
     transform: Res<PlayfieldTransform>,
+    beatmap: Res<BeatmapView>,
     mut state: ResMut<SdfRenderState>,
     query: Query<Entity, Or<(With<SdfHitObject>, With<ArrowEntity>)>>,
 ) {
-    if state.last_generation != transform.generation {
+    // Spawned state is keyed by object index, which is meaningless for a new beatmap
+    if state.last_generation != transform.generation || beatmap.is_changed() {
         // Transform changed - despawn all and clear state
         for entity in query.iter() {
             commands.entity(entity).despawn();
@@ -272,6 +341,9 @@ fn clear_on_transform_change(
         state.spawned_end_arrows.clear();
         state.spawned_start_arrows.clear();
         state.spawned_spinners.clear();
+        state.slider_pool.clear();
+        state.spinner_pool.clear();
+        state.arrow_pool.clear();
         state.last_generation = transform.generation;
     }
 }
@@ -286,6 +358,7 @@ fn spawn_sdf_objects(
     _atlas: Res<MsdfAtlas>, // Keep for potential future use
     beatmap: Res<BeatmapView>,
     playback: Res<PlaybackStateRes>,
+    mods: Res<VisualMods>,
     transform: Res<PlayfieldTransform>,
     mut state_res: ResMut<SdfRenderState>,
 ) {
@@ -296,7 +369,7 @@ fn spawn_sdf_objects(
     }
 
     let current_time = playback.current_time;
-    let visible = beatmap.visible_objects(current_time);
+    let visible = beatmap.visible_objects(current_time, &mods);
     let radius = transform.scale_radius(beatmap.circle_radius);
 
     for (idx, obj, opacity) in visible.iter() {
@@ -338,55 +411,91 @@ fn spawn_sdf_objects(
     }
 }
 
-/// Despawn objects that are no longer visible
+/// Hide objects that are no longer visible and return their entities to the pools
+///
+/// Entities are only despawned when the whole state is cleared (`clear_on_transform_change`),
+/// so scrubbing through dense sections does not churn entity creation every frame.
 fn despawn_invisible_objects(
     mut commands: Commands,
     beatmap: Res<BeatmapView>,
     playback: Res<PlaybackStateRes>,
+    mods: Res<VisualMods>,
     mut state: ResMut<SdfRenderState>,
-    query: Query<(Entity, &SdfHitObject)>,
-    arrow_query: Query<(Entity, &ArrowEntity)>,
+    query: Query<
+        (Entity, &SdfHitObject, Option<&MeshMaterial2d<SliderMaterial>>, Option<&MeshMaterial2d<SpinnerMaterial>>),
+        Without<Pooled>,
+    >,
+    arrow_query: Query<(Entity, &ArrowEntity, &MeshMaterial2d<ArrowMaterial>), Without<Pooled>>,
 ) {
     let current_time = playback.current_time;
-    let visible = beatmap.visible_objects(current_time);
+    let visible = beatmap.visible_objects(current_time, &mods);
     let visible_indices: std::collections::HashSet<usize> = visible
         .iter()
         .map(|(idx, _, _)| *idx)
         .collect();
 
-    for (entity, hit_obj) in query.iter() {
+    for (entity, hit_obj, slider_material, spinner_material) in query.iter() {
         if !visible_indices.contains(&hit_obj.object_index) {
-            commands.entity(entity).despawn();
-            
+            commands.entity(entity).insert((Visibility::Hidden, Pooled));
+            if let Some(material) = slider_material {
+                state.slider_pool.push((entity, material.0.clone()));
+            } else if let Some(material) = spinner_material {
+                state.spinner_pool.push((entity, material.0.clone()));
+            }
+
             // Remove from state tracking
             state.spawned_sliders.retain(|&i| i != hit_obj.object_index);
             state.spawned_spinners.retain(|&i| i != hit_obj.object_index);
         }
     }
 
-    // Despawn arrows separately
-    for (entity, arrow) in arrow_query.iter() {
+    // Pool arrows separately
+    for (entity, arrow, material) in arrow_query.iter() {
         if !visible_indices.contains(&arrow.object_index) {
-            commands.entity(entity).despawn();
+            commands.entity(entity).insert((Visibility::Hidden, Pooled));
+            state.arrow_pool.push((entity, material.0.clone()));
             state.spawned_end_arrows.retain(|&i| i != arrow.object_index);
             state.spawned_start_arrows.retain(|&i| i != arrow.object_index);
         }
     }
 }
 
+/// Log (at debug level) whenever the number of live SDF materials reaches a new high
+///
+/// With pooling this levels off at the largest number of objects visible at once;
+/// `RUST_LOG=osu_player=debug` shows whether it keeps growing over playback.
+fn log_material_counts(
+    slider_materials: Res<Assets<SliderMaterial>>,
+    spinner_materials: Res<Assets<SpinnerMaterial>>,
+    arrow_materials: Res<Assets<ArrowMaterial>>,
+    mut high_water: Local<usize>,
+) {
+    let total = slider_materials.len() + spinner_materials.len() + arrow_materials.len();
+    if total > *high_water {
+        *high_water = total;
+        log::debug!(
+            "SDF materials: {} sliders, {} spinners, {} arrows",
+            slider_materials.len(),
+            spinner_materials.len(),
+            arrow_materials.len()
+        );
+    }
+}
+
 /// Update materials for non-batched items (Spinners, Slider Bodies, Arrows)
 fn update_non_batched_materials(
     beatmap: Res<BeatmapView>,
     playback: Res<PlaybackStateRes>,
+    mods: Res<VisualMods>,
     mut slider_materials: ResMut<Assets<SliderMaterial>>,
     mut spinner_materials: ResMut<Assets<SpinnerMaterial>>,
     mut arrow_materials: ResMut<Assets<ArrowMaterial>>,
-    slider_query: Query<(&SdfHitObject, &MeshMaterial2d<SliderMaterial>), With<SliderMesh>>,
-    spinner_query: Query<(&SdfHitObject, &MeshMaterial2d<SpinnerMaterial>), With<SpinnerMesh>>,
-    arrow_query: Query<(&ArrowEntity, &MeshMaterial2d<ArrowMaterial>)>,
+    slider_query: Query<(&SdfHitObject, &MeshMaterial2d<SliderMaterial>), (With<SliderMesh>, Without<Pooled>)>,
+    spinner_query: Query<(&SdfHitObject, &MeshMaterial2d<SpinnerMaterial>), (With<SpinnerMesh>, Without<Pooled>)>,
+    arrow_query: Query<(&ArrowEntity, &MeshMaterial2d<ArrowMaterial>), Without<Pooled>>,
 ) {
     let current_time = playback.current_time;
-    let visible = beatmap.visible_objects(current_time);
+    let visible = beatmap.visible_objects(current_time, &mods);
     let visible_map: std::collections::HashMap<usize, f32> = visible
         .iter()
         .map(|(idx, _, opacity)| (*idx, *opacity))
@@ -437,28 +546,35 @@ fn spawn_arrow(
     // Z-ordering: reverse arrows (+0.0005 relative to object base)
     let z = -(index as f32 * 0.001) + 0.0005;
     
-    // Use cache for arrow material
-    let _opacity_bits = opacity.to_bits();
-    let material_handle = state.arrow_cache.entry(_opacity_bits).or_insert_with(|| {
-        materials.add(ArrowMaterial {
-            uniforms: ArrowUniforms {
-                color: Color::WHITE.into(),
-                thickness_rel: 0.2,
-                opacity,
-                _padding: Vec2::ZERO,
-            },
-        })
-    }).clone();
+    // One material per arrow: opacity is updated per object every frame
+    let material = ArrowMaterial {
+        uniforms: ArrowUniforms {
+            color: Color::WHITE.into(),
+            thickness_rel: 0.2,
+            opacity,
+            _padding: Vec2::ZERO,
+        },
+    };
 
-    commands.spawn((
-        Mesh2d(state.unit_mesh.clone()),
-        MeshMaterial2d(material_handle),
+    let components = (
         Transform::from_xyz(pos.x, pos.y, z)
             .with_rotation(Quat::from_rotation_arc(Vec3::Y, direction.extend(0.0).normalize()))
             .with_scale(Vec3::new(radius * 2.0, radius * 2.0, 1.0)),
-        ArrowMesh,
         ArrowEntity { object_index: index },
-    ));
+    );
+    match state.arrow_pool.pop() {
+        Some((entity, handle)) => {
+            reuse_pooled(commands, entity, &handle, &mut **materials, material, components);
+        }
+        None => {
+            commands.spawn((
+                Mesh2d(state.unit_mesh.clone()),
+                MeshMaterial2d(materials.add(material)),
+                ArrowMesh,
+                components,
+            ));
+        }
+    }
 }
 
 /// Spawn a spinner mesh entity
@@ -491,19 +607,28 @@ fn spawn_spinner(
             _padding: Vec2::ZERO,
         },
     };
-    let material_handle = materials.add(material);
     
     // Z-ordering: spinner (+0.0000 relative to object base)
     let z = -(index as f32 * 0.001);
     
-    commands.spawn((
-        Mesh2d(state.unit_mesh.clone()),
-        MeshMaterial2d(material_handle),
+    let components = (
         Transform::from_xyz(center.x, center.y, z)
             .with_scale(Vec3::new(max_radius * 2.5, max_radius * 2.5, 1.0)),
-        SpinnerMesh,
         SdfHitObject { object_index: index },
-    ));
+    );
+    match state.spinner_pool.pop() {
+        Some((entity, handle)) => {
+            reuse_pooled(commands, entity, &handle, &mut **materials, material, components);
+        }
+        None => {
+            commands.spawn((
+                Mesh2d(state.unit_mesh.clone()),
+                MeshMaterial2d(materials.add(material)),
+                SpinnerMesh,
+                components,
+            ));
+        }
+    }
 }
 
 fn spawn_slider(
@@ -518,8 +643,9 @@ fn spawn_slider(
     transform: &PlayfieldTransform,
     beatmap: &BeatmapView,
 ) {
-    // Transform path points to screen space
-    let screen_points: Vec<(f32, f32)> = path_points
+    // Transform path points to screen space, spreading long paths over the
+    // shader's point budget so their ends aren't cut off
+    let screen_points: Vec<(f32, f32)> = osu_geometry::resample_to_count(path_points, SLIDER_MAX_POINTS)
         .iter()
         .map(|(x, y)| {
             let pos = transform.osu_to_screen(*x, *y);
@@ -550,7 +676,7 @@ fn spawn_slider(
 
     // Pack path data for shader
     let mut path_data = SliderPathData::default();
-    let count = screen_points.len().min(128);
+    let count = screen_points.len();
     for i in 0..count {
         let vec_idx = i / 2;
         let (x, y) = screen_points[i];
@@ -583,25 +709,54 @@ fn spawn_slider(
         },
         path_data,
     };
-    let material_handle = materials.add(material);
 
     // Z-ordering: slider body (+0.0000 relative to object base)
     let z = -(index as f32 * 0.001);
 
-    commands.spawn((
-        Mesh2d(state.unit_mesh.clone()),
-        MeshMaterial2d(material_handle),
+    let components = (
         Transform::from_xyz(bbox_center.x, bbox_center.y, z)
             .with_scale(Vec3::new(bbox_size.x, bbox_size.y, 1.0)),
-        SliderMesh,
         SdfHitObject { object_index: index },
-    ));
+    );
+    match state.slider_pool.pop() {
+        Some((entity, handle)) => {
+            reuse_pooled(commands, entity, &handle, &mut **materials, material, components);
+        }
+        None => {
+            commands.spawn((
+                Mesh2d(state.unit_mesh.clone()),
+                MeshMaterial2d(materials.add(material)),
+                SliderMesh,
+                components,
+            ));
+        }
+    }
+}
+
+/// Show a pooled entity again with new components, overwriting its material in place
+fn reuse_pooled<M: Material2d>(
+    commands: &mut Commands,
+    entity: Entity,
+    handle: &Handle<M>,
+    materials: &mut Assets<M>,
+    material: M,
+    components: impl Bundle,
+) {
+    let mut entity_commands = commands.entity(entity);
+    match materials.get_mut(handle) {
+        Some(existing) => *existing = material,
+        None => {
+            entity_commands.insert(MeshMaterial2d(materials.add(material)));
+        }
+    }
+    entity_commands.insert((components, Visibility::Visible)).remove::<Pooled>();
 }
 
 /// Update the circle batch mesh from current entity data
 fn update_circle_batches(
     beatmap: Res<BeatmapView>,
     playback: Res<PlaybackStateRes>,
+    mods: Res<VisualMods>,
     transform: Res<PlayfieldTransform>,
     mut state: ResMut<SdfRenderState>,
     mut meshes: ResMut<Assets<Mesh>>,
@@ -612,7 +767,7 @@ fn update_circle_batches(
 
     if let Some(mesh) = meshes.get_mut(&state.circle_batch_mesh) {
         let current_time = playback.current_time;
-        let visible = beatmap.visible_objects(current_time);
+        let visible = beatmap.visible_objects(current_time, &mods);
         
         let count_estimate = visible.len() * 4; // Max 4 circles per object (slider)
         let mut positions = Vec::with_capacity(count_estimate * 4);
@@ -675,8 +830,9 @@ fn update_circle_batches(
 
             match &obj.kind {
                 RenderObjectKind::Circle => {
+                    // A scale of 1.0 draws no ring, which is how hidden/no-approach hide it
                     let screen_pos = transform.osu_to_screen(obj.x, obj.y);
-                    let approach_scale = beatmap.approach_scale(obj, current_time);
+                    let approach_scale = beatmap.approach_scale(obj, current_time, &mods).unwrap_or(1.0);
                     
                     push_quad(
                         screen_pos,
@@ -691,7 +847,7 @@ fn update_circle_batches(
                 RenderObjectKind::Slider { path_points, .. } => {
                     // Slider Head
                     let head_pos = transform.osu_to_screen(obj.x, obj.y);
-                    let approach_scale = beatmap.approach_scale(obj, current_time);
+                    let approach_scale = beatmap.approach_scale(obj, current_time, &mods).unwrap_or(1.0);
                     push_quad(
                         head_pos,
                         radius,
@@ -777,18 +933,20 @@ fn update_circle_batches(
 fn update_msdf_batches(
     beatmap: Res<BeatmapView>,
     playback: Res<PlaybackStateRes>,
+    mods: Res<VisualMods>,
     transform: Res<PlayfieldTransform>,
     atlas: Res<MsdfAtlas>,
     mut state: ResMut<SdfRenderState>,
     mut meshes: ResMut<Assets<Mesh>>,
 ) {
-    if transform.scale <= 0.0 {
+    // A broken atlas would draw garbage quads; update_fallback_combo_numbers draws the digits instead
+    if transform.scale <= 0.0 || atlas.load_error.is_some() {
         return;
     }
 
     if let Some(mesh) = meshes.get_mut(&state.msdf_batch_mesh) {
         let current_time = playback.current_time;
-        let visible = beatmap.visible_objects(current_time);
+        let visible = beatmap.visible_objects(current_time, &mods);
         let radius = transform.scale_radius(beatmap.circle_radius);
         let digit_size = radius * 0.5;
 
@@ -894,3 +1052,229 @@ fn update_msdf_batches(
         mesh.insert_indices(Indices::U32(indices));
     }
 }
+
+/// Combo number drawn as `Text2d` because the MSDF atlas failed to load
+#[derive(Component)]
+pub struct FallbackComboNumber {
+    pub object_index: usize,
+}
+
+/// Keep one `Text2d` combo number per visible circle/slider when the MSDF atlas is unavailable
+fn update_fallback_combo_numbers(
+    mut commands: Commands,
+    atlas: Res<MsdfAtlas>,
+    ui_font: Res<UiFont>,
+    beatmap: Res<BeatmapView>,
+    playback: Res<PlaybackStateRes>,
+    mods: Res<VisualMods>,
+    playfield: Res<PlayfieldTransform>,
+    mut query: Query<(Entity, &FallbackComboNumber, &mut Transform, &mut TextFont, &mut TextColor)>,
+) {
+    if atlas.load_error.is_none() || playfield.scale <= 0.0 {
+        return;
+    }
+
+    let font_size = playfield.scale_radius(beatmap.circle_radius) * 0.6;
+    let mut numbers: std::collections::HashMap<usize, (Vec3, f32)> = beatmap
+        .visible_objects(playback.current_time, &mods)
+        .iter()
+        .filter(|(_, obj, opacity)| {
+            *opacity >= 0.01 && matches!(obj.kind, RenderObjectKind::Circle | RenderObjectKind::Slider { .. })
+        })
+        .map(|(index, obj, opacity)| {
+            let pos = playfield.osu_to_screen(obj.x, obj.y);
+            // Same depth as the MSDF batch digits
+            let z = 0.5 - (*index as f32 * 0.001) + 0.0009;
+            (*index, (pos.extend(z), *opacity))
+        })
+        .collect();
+
+    for (entity, number, mut transform, mut font, mut color) in query.iter_mut() {
+        // Object indices are meaningless once a new beatmap is loaded
+        match numbers.remove(&number.object_index).filter(|_| !beatmap.is_changed()) {
+            Some((pos, opacity)) => {
+                transform.translation = pos;
+                font.font_size = font_size;
+                color.0 = Color::srgba(1.0, 1.0, 1.0, opacity);
+            }
+            None => commands.entity(entity).despawn(),
+        }
+    }
+
+    for (index, (pos, opacity)) in numbers {
+        commands.spawn((
+            Text2d::new(beatmap.objects[index].combo_number.to_string()),
+            TextFont {
+                font: ui_font.0.clone(),
+                font_size,
+                ..default()
+            },
+            TextColor(Color::srgba(1.0, 1.0, 1.0, opacity)),
+            Transform::from_translation(pos),
+            FallbackComboNumber { object_index: index },
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use osu_text::testutil::{SyntheticMap, SyntheticObject};
+    use std::time::{Duration, Instant};
+
+    /// Playback advance per frame, in ms (60 fps)
+    const FRAME_MS: f64 = 1000.0 / 60.0;
+
+    /// Headless app running the SDF spawn, update and pooling systems over `map`
+    ///
+    /// There is no window or renderer: materials and meshes are plain assets, so
+    /// their counts and the systems' CPU time can be measured.
+    fn headless_app(map: &SyntheticMap, mods: VisualMods) -> App {
+        let beatmap: rosu_map::Beatmap = rosu_map::from_str(&map.to_osu_string()).unwrap();
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .init_asset::<Mesh>()
+            .init_asset::<SliderMaterial>()
+            .init_asset::<SpinnerMaterial>()
+            .init_asset::<ArrowMaterial>()
+            .init_asset::<MsdfMaterial>()
+            .init_resource::<MsdfAtlas>()
+            .init_resource::<SdfRenderState>()
+            .insert_resource(BeatmapView::new(beatmap))
+            .insert_resource(PlaybackStateRes::default())
+            .insert_resource(mods)
+            .insert_resource(PlayfieldTransform {
+                scale: 1.0,
+                size: Vec2::new(PLAYFIELD_WIDTH, PLAYFIELD_HEIGHT),
+                ..default()
+            })
+            .add_systems(
+                Update,
+                (clear_on_transform_change, spawn_sdf_objects, update_non_batched_materials, despawn_invisible_objects)
+                    .chain(),
+            )
+            .add_systems(PostUpdate, update_circle_batches);
+        app
+    }
+
+    /// Run one frame at `time` ms
+    fn step(app: &mut App, time: f64) {
+        app.world_mut().resource_mut::<PlaybackStateRes>().current_time = time;
+        app.update();
+    }
+
+    /// Frame times from the start to the end of the map
+    fn frames(app: &App) -> impl Iterator<Item = f64> {
+        let total = app.world().resource::<BeatmapView>().total_duration;
+        (0..).map(|frame| frame as f64 * FRAME_MS).take_while(move |&time| time <= total)
+    }
+
+    /// SDF entities currently shown (pooled ones excluded)
+    fn live_entities(app: &mut App) -> usize {
+        let mut query =
+            app.world_mut().query_filtered::<(), (Or<(With<SdfHitObject>, With<ArrowEntity>)>, Without<Pooled>)>();
+        query.iter(app.world()).count()
+    }
+
+    fn material_count(app: &App) -> usize {
+        let world = app.world();
+        world.resource::<Assets<SliderMaterial>>().len()
+            + world.resource::<Assets<SpinnerMaterial>>().len()
+            + world.resource::<Assets<ArrowMaterial>>().len()
+    }
+
+    /// Time spent in the systems over the whole map, and the most SDF entities shown at once
+    fn play_through(app: &mut App) -> (Duration, usize) {
+        let mut elapsed = Duration::ZERO;
+        let mut most_live = 0;
+        for time in frames(app).collect::<Vec<_>>() {
+            let started = Instant::now();
+            step(app, time);
+            elapsed += started.elapsed();
+            most_live = most_live.max(live_entities(app));
+        }
+        (elapsed, most_live)
+    }
+
+    /// A stream of 1/16 notes at 300 BPM, which keeps around a hundred objects on screen
+    fn dense_map() -> SyntheticMap {
+        SyntheticMap {
+            object_count: 3000,
+            kinds: vec![SyntheticObject::Circle, SyntheticObject::Slider, SyntheticObject::Circle],
+            bpm: 300.0,
+            beat_divisor: 16,
+            ..SyntheticMap::default()
+        }
+    }
+
+    /// Spawn and update cost on a dense map with and without `--max-visible-objects`
+    ///
+    /// Timings depend on the machine and build, so they are printed rather than
+    /// compared; `cargo test --release visible_object_cap -- --nocapture` shows them.
+    #[test]
+    fn visible_object_cap_bounds_spawn_and_update_work() {
+        const CAP: usize = 16;
+        let map = dense_map();
+
+        let mut uncapped = headless_app(&map, VisualMods::default());
+        let (uncapped_time, uncapped_live) = play_through(&mut uncapped);
+        let mut capped = headless_app(&map, VisualMods { max_visible_objects: Some(CAP), ..default() });
+        let (capped_time, capped_live) = play_through(&mut capped);
+
+        let frame_count = frames(&uncapped).count() as u32;
+        println!(
+            "{} frames: uncapped {:?}/frame, at most {} SDF entities; capped at {} {:?}/frame, at most {}",
+            frame_count,
+            uncapped_time / frame_count,
+            uncapped_live,
+            CAP,
+            capped_time / frame_count,
+            capped_live
+        );
+        // Sliders here have one slide, so each visible object has at most one SDF entity
+        assert!(capped_live <= CAP, "{} entities with a cap of {}", capped_live, CAP);
+        assert!(uncapped_live > CAP, "the map is not dense enough to exercise the cap");
+        assert!(material_count(&capped) < material_count(&uncapped));
+    }
+
+    #[test]
+    fn pooled_materials_and_meshes_stop_growing_over_a_long_map() {
+        // Several hundred sliders and spinners, only a few of them on screen at a time
+        let map = SyntheticMap {
+            object_count: 600,
+            kinds: vec![
+                SyntheticObject::Slider,
+                SyntheticObject::Circle,
+                SyntheticObject::Slider,
+                SyntheticObject::Spinner,
+            ],
+            ..SyntheticMap::default()
+        };
+        let mut app = headless_app(&map, VisualMods::default());
+        let times: Vec<f64> = frames(&app).collect();
+        let (first_half, second_half) = times.split_at(times.len() / 2);
+
+        let mesh_count = |app: &App| app.world().resource::<Assets<Mesh>>().len();
+        let meshes = mesh_count(&app);
+        let mut peak_materials = 0;
+        for &time in first_half {
+            step(&mut app, time);
+            peak_materials = peak_materials.max(material_count(&app));
+        }
+        // Every visibility pattern of the repeating map has been seen by now
+        assert!(peak_materials > 0);
+        assert!(
+            peak_materials < map.object_count / 10,
+            "{} materials for {} objects",
+            peak_materials,
+            map.object_count
+        );
+
+        for &time in second_half {
+            step(&mut app, time);
+            assert!(material_count(&app) <= peak_materials, "materials grew past {} at {} ms", peak_materials, time);
+            // Sliders and spinners share the unit quad; the batches rewrite their own meshes
+            assert_eq!(mesh_count(&app), meshes);
+        }
+    }
+}
@@ -4,9 +4,11 @@ use bevy::prelude::*;
 
 use crate::beatmap::BeatmapView;
 use crate::input::SeekConfig;
+use crate::keybinds::{Action, KeyBindings};
 use crate::playback::{PlaybackState, PlaybackStateRes};
 use crate::rendering::ZoomLevel;
 use crate::ui::UiFont;
+use crate::visual_mods::VisualMods;
 
 pub struct ControlsPlugin;
 
@@ -66,7 +68,12 @@ pub struct ReverseButton;
 #[derive(Component)]
 pub struct SeekButton;
 
-fn setup_controls(mut commands: Commands, beatmap: Res<BeatmapView>, ui_font: Res<UiFont>) {
+fn setup_controls(
+    mut commands: Commands,
+    beatmap: Res<BeatmapView>,
+    ui_font: Res<UiFont>,
+    bindings: Res<KeyBindings>,
+) {
     let font = ui_font.0.clone();
 
     // Control bar above timeline
@@ -343,7 +350,20 @@ fn setup_controls(mut commands: Commands, beatmap: Res<BeatmapView>, ui_font: Re
 
             // Controls help
             parent.spawn((
-                Text::new("Space: Play/Pause | R: Reverse | ←/→: Seek | ↑/↓: Speed | L-Drag: Pan | Wheel: Zoom | F: Focus"),
+                Text::new(format!(
+                    "{}: Play/Pause | {}: Reverse | {}/{}: Seek | {}/{}: Speed | L-Drag: Pan | Wheel: Zoom | {}: Focus | {}/{}/{}: HD/FL/Approach | {}: Grid",
+                    bindings.label(Action::TogglePlay),
+                    bindings.label(Action::ToggleReverse),
+                    bindings.label(Action::SeekBackward),
+                    bindings.label(Action::SeekForward),
+                    bindings.label(Action::SpeedUp),
+                    bindings.label(Action::SpeedDown),
+                    bindings.label(Action::ResetView),
+                    bindings.label(Action::ToggleHidden),
+                    bindings.label(Action::ToggleFlashlight),
+                    bindings.label(Action::ToggleApproachCircles),
+                    bindings.label(Action::ToggleGrid),
+                )),
                 TextFont {
                     font: font.clone(),
                     font_size: 12.0,
@@ -388,9 +408,10 @@ fn update_speed_display(
 fn update_object_count(
     beatmap: Res<BeatmapView>,
     playback: Res<PlaybackStateRes>,
+    mods: Res<VisualMods>,
     mut query: Query<&mut Text, With<ObjectCountText>>,
 ) {
-    let visible = beatmap.visible_objects(playback.current_time).len();
+    let visible = beatmap.visible_objects(playback.current_time, &mods).len();
     let total = beatmap.objects.len();
 
     for mut text in query.iter_mut() {
@@ -15,6 +15,7 @@ impl Plugin for HudPlugin {
         app.init_resource::<FrametimeTracker>()
             .add_systems(Startup, setup_hud)
             .add_systems(Update, update_combo_counter)
+            .add_systems(Update, update_map_stats.run_if(resource_changed::<BeatmapView>))
             .add_systems(Update, update_fps_display)
             .add_systems(Update, track_frametime);
     }
@@ -50,6 +51,24 @@ pub struct MapStatsContainer;
 #[derive(Component)]
 pub struct FpsText;
 
+/// Lines shown in the map stats panel
+fn map_stat_lines(bm: &rosu_map::Beatmap) -> [String; 5] {
+    let bpm = bm
+        .control_points
+        .timing_points
+        .first()
+        .map(|tp| 60000.0 / tp.beat_len)
+        .unwrap_or(0.0);
+
+    [
+        format!("AR: {:.1}", bm.approach_rate),
+        format!("CS: {:.1}", bm.circle_size),
+        format!("OD: {:.1}", bm.overall_difficulty),
+        format!("HP: {:.1}", bm.hp_drain_rate),
+        format!("BPM: {:.0}", bpm),
+    ]
+}
+
 fn setup_hud(mut commands: Commands, beatmap: Res<BeatmapView>, ui_font: Res<UiFont>) {
     let bm = &beatmap.beatmap;
     let font = ui_font.0.clone();
@@ -94,22 +113,7 @@ fn setup_hud(mut commands: Commands, beatmap: Res<BeatmapView>, ui_font: Res<UiF
             MapStatsContainer,
         ))
         .with_children(|parent| {
-            let bpm = bm
-                .control_points
-                .timing_points
-                .first()
-                .map(|tp| 60000.0 / tp.beat_len)
-                .unwrap_or(0.0);
-
-            let stats = [
-                format!("AR: {:.1}", bm.approach_rate),
-                format!("CS: {:.1}", bm.circle_size),
-                format!("OD: {:.1}", bm.overall_difficulty),
-                format!("HP: {:.1}", bm.hp_drain_rate),
-                format!("BPM: {:.0}", bpm),
-            ];
-
-            for stat in stats {
+            for stat in map_stat_lines(bm) {
                 parent.spawn((
                     Text::new(stat),
                     TextFont {
@@ -150,6 +154,22 @@ fn setup_hud(mut commands: Commands, beatmap: Res<BeatmapView>, ui_font: Res<UiF
         });
 }
 
+/// Refresh map stats when a new beatmap is loaded
+fn update_map_stats(
+    beatmap: Res<BeatmapView>,
+    container_query: Query<&Children, With<MapStatsContainer>>,
+    mut text_query: Query<&mut Text>,
+) {
+    let lines = map_stat_lines(&beatmap.beatmap);
+    for children in container_query.iter() {
+        for (child, line) in children.iter().zip(&lines) {
+            if let Ok(mut text) = text_query.get_mut(child) {
+                text.0 = line.clone();
+            }
+        }
+    }
+}
+
 fn update_combo_counter(
     beatmap: Res<BeatmapView>,
     playback: Res<PlaybackStateRes>,
@@ -0,0 +1,75 @@
+//! Visual mods for reading practice (hidden, flashlight, no approach circles)
+//!
+//! Only change what is drawn; timing and playback are unaffected.
+
+use bevy::prelude::*;
+
+use crate::keybinds::{Action, KeyBindings};
+
+/// Flashlight radius in osu!pixels when toggled on without `--flashlight`
+pub const DEFAULT_FLASHLIGHT_RADIUS: f32 = 180.0;
+
+/// Share of the approach time over which hidden fades objects in (osu!'s HD formula)
+pub const HIDDEN_FADE_IN: f64 = 0.4;
+/// Share of the approach time over which hidden fades circles out after fading in
+pub const HIDDEN_FADE_OUT: f64 = 0.3;
+
+pub struct VisualModsPlugin;
+
+impl Plugin for VisualModsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<VisualMods>()
+            .add_systems(Update, handle_visual_mod_keys);
+    }
+}
+
+/// Active visual mods, consulted by `BeatmapView::visible_objects` and `approach_scale`
+#[derive(Resource, Debug, Clone)]
+pub struct VisualMods {
+    /// Fade objects out before they are hit and hide approach circles
+    pub hidden: bool,
+    /// Only show objects near the current play position
+    pub flashlight: bool,
+    /// Visible radius around the play position in osu!pixels
+    pub flashlight_radius: f32,
+    /// Draw approach circles (ignored while hidden is on)
+    pub approach_circles: bool,
+    /// Draw at most this many objects at once, dropping the furthest-away ones (None = no cap)
+    pub max_visible_objects: Option<usize>,
+}
+
+impl Default for VisualMods {
+    fn default() -> Self {
+        Self {
+            hidden: false,
+            flashlight: false,
+            flashlight_radius: DEFAULT_FLASHLIGHT_RADIUS,
+            approach_circles: true,
+            max_visible_objects: None,
+        }
+    }
+}
+
+impl VisualMods {
+    /// Whether approach circles should be drawn at all
+    pub fn shows_approach_circles(&self) -> bool {
+        self.approach_circles && !self.hidden
+    }
+}
+
+/// Toggle visual mods with their hotkeys
+fn handle_visual_mod_keys(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut mods: ResMut<VisualMods>,
+) {
+    if bindings.just_pressed(Action::ToggleHidden, &keyboard) {
+        mods.hidden = !mods.hidden;
+    }
+    if bindings.just_pressed(Action::ToggleFlashlight, &keyboard) {
+        mods.flashlight = !mods.flashlight;
+    }
+    if bindings.just_pressed(Action::ToggleApproachCircles, &keyboard) {
+        mods.approach_circles = !mods.approach_circles;
+    }
+}
@@ -0,0 +1,235 @@
+//! osu-pp: Star rating and PP helpers shared by the dataset builder and enricher
+//!
+//! Wraps rosu-pp with the suspicious-map check both tools need, nomod star
+//! ratings for filtering, per-mod ratings with the effective difficulty settings,
+//! and PP for arbitrary scores (mods, combo, misses, accuracy).
+
+use anyhow::Result;
+use rosu_pp::{Beatmap as PpBeatmap, Difficulty, Performance};
+use std::path::Path;
+
+/// Nomod star rating and SS pp of a difficulty
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NomodRating {
+    pub stars: f64,
+    pub max_pp: f64,
+    pub max_combo: u32,
+}
+
+/// Star rating and SS pp of a difficulty with mods, plus the settings rosu-pp played it with
+///
+/// `ar`/`cs`/`od`/`hp` are the effective values after the mods (HR scales them up, EZ
+/// halves them). AR and OD include the DT/HT clock rate, as shown in osu!'s song select.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModRating {
+    /// Legacy mod bitflags
+    pub mods: u32,
+    pub ar: f64,
+    pub cs: f64,
+    pub od: f64,
+    pub hp: f64,
+    pub stars: f64,
+    pub max_pp: f64,
+}
+
+/// Legacy mod bits by acronym; NC and PF come before the DT and SD bits they include
+const MOD_ACRONYMS: [(&str, u32); 13] = [
+    ("NF", 1),
+    ("EZ", 2),
+    ("TD", 4),
+    ("HD", 8),
+    ("HR", 16),
+    ("PF", 16384 | 32),
+    ("SD", 32),
+    ("NC", 512 | 64),
+    ("DT", 64),
+    ("RX", 128),
+    ("HT", 256),
+    ("FL", 1024),
+    ("SO", 4096),
+];
+
+/// Parse a mod combination such as `HDDT` or `hr` into legacy bitflags (`NM` is nomod)
+pub fn parse_mods(acronyms: &str) -> Result<u32> {
+    let acronyms = acronyms.trim().to_uppercase();
+    if acronyms == "NM" {
+        return Ok(0);
+    }
+    if acronyms.is_empty() || !acronyms.len().is_multiple_of(2) || !acronyms.is_ascii() {
+        anyhow::bail!("Invalid mod combination '{}' (expected acronyms like HDDT)", acronyms);
+    }
+
+    let mut mods = 0;
+    for i in (0..acronyms.len()).step_by(2) {
+        let acronym = &acronyms[i..i + 2];
+        let Some((_, bits)) = MOD_ACRONYMS.iter().find(|(name, _)| *name == acronym) else {
+            anyhow::bail!("Unknown mod '{}' in '{}'", acronym, acronyms);
+        };
+        mods |= bits;
+    }
+    Ok(mods)
+}
+
+/// Format legacy mod bitflags as acronyms (`HDDT`), `NM` for nomod
+pub fn mods_acronyms(mods: u32) -> String {
+    let mut acronyms = String::new();
+    let mut covered = 0;
+    for (name, bits) in MOD_ACRONYMS {
+        if mods & bits == bits && covered & bits != bits {
+            acronyms.push_str(name);
+            covered |= bits;
+        }
+    }
+    if acronyms.is_empty() {
+        acronyms.push_str("NM");
+    }
+    acronyms
+}
+
+/// Parse a `.osu` file for rosu-pp, rejecting maps it flags as suspicious
+pub fn load_beatmap(osu_path: &Path) -> Result<PpBeatmap> {
+    let map = PpBeatmap::from_path(osu_path)?;
+
+    if let Err(sus) = map.check_suspicion() {
+        anyhow::bail!("Suspicious map: {:?}", sus);
+    }
+
+    Ok(map)
+}
+
+/// Nomod star rating and SS pp of a `.osu` file
+///
+/// Returns `Ok(None)` for maps rosu-pp flags as suspicious (absurd object
+/// counts or lengths) instead of spending minutes on them.
+pub fn nomod_rating(osu_path: &Path) -> Result<Option<NomodRating>> {
+    let map = PpBeatmap::from_path(osu_path)?;
+    if map.check_suspicion().is_err() {
+        return Ok(None);
+    }
+    Ok(Some(nomod_rating_for_map(&map)))
+}
+
+/// Same as [`nomod_rating`] for an already parsed (and checked) map
+pub fn nomod_rating_for_map(map: &PpBeatmap) -> NomodRating {
+    let diff_attrs = Difficulty::new().calculate(map);
+    let max_combo = diff_attrs.max_combo();
+    let stars = diff_attrs.stars();
+    let max_pp = Performance::new(diff_attrs).calculate().pp();
+    NomodRating { stars, max_pp, max_combo }
+}
+
+/// Star rating, SS pp and effective AR/CS/OD/HP of an already parsed map with mods
+pub fn mod_rating_for_map(map: &PpBeatmap, mods: u32) -> ModRating {
+    let settings = map.attributes().mods(mods).build();
+    let diff_attrs = Difficulty::new().mods(mods).calculate(map);
+    let stars = diff_attrs.stars();
+    let max_pp = Performance::new(diff_attrs).mods(mods).calculate().pp();
+    ModRating {
+        mods,
+        ar: settings.ar,
+        cs: settings.cs,
+        od: settings.od,
+        hp: settings.hp,
+        stars,
+        max_pp,
+    }
+}
+
+/// PP of a score on the given map
+///
+/// - `mods`: legacy mod bitflags (e.g. 8 = HD, 64 = DT, 72 = HDDT)
+/// - `combo`: max combo reached, or None for a full combo
+/// - `misses`: miss count
+/// - `acc`: accuracy in percent (0-100)
+pub fn calc_pp(osu_path: &Path, mods: u32, combo: Option<u32>, misses: u32, acc: f64) -> Result<f64> {
+    let map = load_beatmap(osu_path)?;
+    calc_pp_for_map(&map, mods, combo, misses, acc)
+}
+
+/// Same as [`calc_pp`] for an already parsed map, to score many plays without re-parsing
+pub fn calc_pp_for_map(map: &PpBeatmap, mods: u32, combo: Option<u32>, misses: u32, acc: f64) -> Result<f64> {
+    if !(0.0..=100.0).contains(&acc) {
+        anyhow::bail!("Accuracy must be between 0 and 100, got {}", acc);
+    }
+
+    let mut perf = Performance::new(map).mods(mods).n_misses(misses).accuracy(acc);
+    if let Some(combo) = combo {
+        perf = perf.combo(combo);
+    }
+
+    Ok(perf.calculate().pp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use osu_text::testutil::SyntheticMap;
+
+    fn synthetic_map() -> PpBeatmap {
+        let map = SyntheticMap { object_count: 64, ..SyntheticMap::default() };
+        PpBeatmap::from_bytes(map.to_osu_string().as_bytes()).unwrap()
+    }
+
+    fn pp(map: &PpBeatmap, mods: u32, combo: Option<u32>, misses: u32, acc: f64) -> f64 {
+        calc_pp_for_map(map, mods, combo, misses, acc).unwrap()
+    }
+
+    #[test]
+    fn full_combo_ss_matches_the_nomod_rating() {
+        let map = synthetic_map();
+        let rating = nomod_rating_for_map(&map);
+        let ss = pp(&map, 0, None, 0, 100.0);
+        assert!(ss > 0.0);
+        assert!((ss - rating.max_pp).abs() < 1e-6 * rating.max_pp, "{} vs {}", ss, rating.max_pp);
+        assert!((pp(&map, 0, Some(rating.max_combo), 0, 100.0) - ss).abs() < 1e-6 * ss);
+    }
+
+    #[test]
+    fn worse_plays_give_less_pp() {
+        let map = synthetic_map();
+        let ss = pp(&map, 0, None, 0, 100.0);
+        let max_combo = nomod_rating_for_map(&map).max_combo;
+
+        assert!(pp(&map, 0, None, 0, 95.0) < ss);
+        assert!(pp(&map, 0, None, 2, 100.0) < ss);
+        assert!(pp(&map, 0, Some(max_combo / 2), 0, 100.0) < ss);
+    }
+
+    #[test]
+    fn hard_rock_raises_settings_and_pp() {
+        let map = synthetic_map();
+        let nomod = mod_rating_for_map(&map, 0);
+        let hard_rock = mod_rating_for_map(&map, parse_mods("HR").unwrap());
+
+        assert!(hard_rock.od > nomod.od && hard_rock.cs > nomod.cs);
+        assert!(hard_rock.max_pp > nomod.max_pp);
+        assert!(pp(&map, 16, None, 0, 100.0) > pp(&map, 0, None, 0, 100.0));
+    }
+
+    #[test]
+    fn scoring_a_file_matches_the_parsed_map() {
+        let map = SyntheticMap { object_count: 64, ..SyntheticMap::default() };
+        let folder = map.write_temp_folder().unwrap();
+        let from_file = calc_pp(&folder.join(map.file_name()), 8, None, 1, 98.0).unwrap();
+        assert_eq!(from_file, pp(&synthetic_map(), 8, None, 1, 98.0));
+    }
+
+    #[test]
+    fn accuracy_outside_percent_range_is_rejected() {
+        let map = synthetic_map();
+        assert!(calc_pp_for_map(&map, 0, None, 0, 100.5).is_err());
+        assert!(calc_pp_for_map(&map, 0, None, 0, -1.0).is_err());
+    }
+
+    #[test]
+    fn mod_acronyms_round_trip() {
+        assert_eq!(parse_mods("hddt").unwrap(), 72);
+        assert_eq!(mods_acronyms(72), "HDDT");
+        assert_eq!(parse_mods("NC").unwrap(), 576);
+        assert_eq!(mods_acronyms(576), "NC");
+        assert_eq!(parse_mods("NM").unwrap(), 0);
+        assert_eq!(mods_acronyms(0), "NM");
+        assert!(parse_mods("HDX").is_err());
+        assert!(parse_mods("ZZ").is_err());
+    }
+}
@@ -1,6 +1,5 @@
 //! Beatmap reconstruction from parquet rows
 
-use anyhow::Result;
 use rosu_map::Beatmap;
 use rosu_map::section::colors::Color;
 use rosu_map::section::events::BreakPeriod;
@@ -8,12 +7,17 @@ use rosu_map::section::general::{GameMode, CountdownType};
 use rosu_map::section::hit_objects::{
     HitObject, HitObjectKind, HitObjectCircle, HitObjectSlider, HitObjectSpinner, HitObjectHold,
     SliderPath, PathControlPoint, PathType,
-    hit_samples::{HitSampleInfo, HitSampleInfoName, SampleBank},
+    hit_samples::{HitSampleInfo, SampleBank},
 };
-use rosu_map::section::timing_points::{TimingPoint, DifficultyPoint, EffectPoint};
+use rosu_map::section::timing_points::{TimingPoint, DifficultyPoint, EffectPoint, SamplePoint};
 use rosu_map::util::Pos;
 use std::collections::HashMap;
 
+use crate::error::{ReconstructError, Result};
+use crate::kinds::{self, HitSoundName, ObjectType};
+use crate::manifest::DatasetFeatures;
+use crate::sections::{OsuSections, Section};
+use crate::timing::osu_slider_length;
 use crate::types::*;
 
 /// Reconstructor for building Beatmap objects from parquet row data
@@ -30,6 +34,7 @@ impl BeatmapReconstructor {
         break_rows: &[BreakRow],
         combo_color_rows: &[ComboColorRow],
         hit_sample_rows: &[HitSampleRow],
+        features: &DatasetFeatures,
     ) -> Result<Beatmap> {
         let mut beatmap = Beatmap::default();
         let folder_id = &beatmap_row.folder_id;
@@ -111,53 +116,88 @@ impl BeatmapReconstructor {
             .filter(|ho| ho.folder_id == *folder_id && ho.osu_file == *osu_file)
             .collect();
 
+        // Every stored mania column must address one of the map's keys
+        let key_count = beatmap_row.key_count.unwrap_or_else(|| beatmap.circle_size.round().max(1.0) as i32);
+        if let Some(ho) = matching_hit_objects
+            .iter()
+            .find(|ho| ho.column.is_some_and(|column| !(0..key_count).contains(&column)))
+        {
+            return Err(ReconstructError::IntegrityViolation(format!(
+                "{}/{}: hit object {} is in column {}, outside the map's {} keys",
+                folder_id,
+                osu_file,
+                ho.index,
+                ho.column.unwrap_or_default(),
+                key_count
+            )));
+        }
+
         for ho in &matching_hit_objects {
-            if let Some(mut hit_obj) = Self::reconstruct_hit_object(ho, &beatmap.mode, &slider_data_map, &slider_cp_map) {
-                // Add samples for this hit object
+            let object_type: ObjectType = ho.object_type.parse()?;
+            if let Some(mut hit_obj) = Self::reconstruct_hit_object(ho, object_type, &beatmap.mode, key_count, &slider_data_map, &slider_cp_map, features) {
+                // Add samples for this hit object; node samples go to the slider's edges
                 if let Some(samples) = hit_sample_map.get(&ho.index) {
-                    hit_obj.samples = samples
+                    let (node_rows, object_rows): (Vec<&HitSampleRow>, Vec<&HitSampleRow>) =
+                        samples.iter().copied().partition(|s| s.node_index.is_some());
+                    hit_obj.samples = object_rows
                         .iter()
                         .map(|s| Self::reconstruct_hit_sample(s))
-                        .collect();
+                        .collect::<Result<_>>()?;
+                    if let HitObjectKind::Slider(slider) = &mut hit_obj.kind {
+                        for row in node_rows {
+                            let node = row.node_index.unwrap_or(0) as usize;
+                            if slider.node_samples.len() <= node {
+                                slider.node_samples.resize(node + 1, Vec::new());
+                            }
+                            slider.node_samples[node].push(Self::reconstruct_hit_sample(row)?);
+                        }
+                        // Every node (head, repeats, tail) needs an entry once any is set
+                        let node_count = slider.repeat_count as usize + 2;
+                        if !slider.node_samples.is_empty() && slider.node_samples.len() < node_count {
+                            slider.node_samples.resize(node_count, Vec::new());
+                        }
+                    }
                 }
                 beatmap.hit_objects.push(hit_obj);
             }
         }
 
-        // Reconstruct timing points
-        for tp in timing_point_rows
-            .iter()
-            .filter(|tp| tp.folder_id == *folder_id && tp.osu_file == *osu_file)
-        {
+        for tp in Self::ordered_timing_points(timing_point_rows, folder_id, osu_file) {
             Self::add_timing_point(&mut beatmap, tp);
         }
 
+        // Without stored sample points, fall back to the [General] defaults so
+        // each timing line still carries a sample set and volume
+        if !features.sample_points {
+            let fallback: Vec<SamplePoint> = beatmap
+                .control_points
+                .timing_points
+                .iter()
+                .map(|tp| SamplePoint {
+                    time: tp.time,
+                    sample_bank: beatmap.default_sample_bank,
+                    sample_volume: beatmap.default_sample_volume,
+                    custom_sample_bank: 0,
+                })
+                .collect();
+            beatmap.control_points.sample_points = fallback;
+        }
+
         Ok(beatmap)
     }
 
-    fn reconstruct_hit_sample(hs: &HitSampleRow) -> HitSampleInfo {
-        let name = match hs.name.as_str() {
-            "Normal" => HitSampleInfoName::Default(rosu_map::section::hit_objects::hit_samples::HitSampleDefaultName::Normal),
-            "Whistle" => HitSampleInfoName::Default(rosu_map::section::hit_objects::hit_samples::HitSampleDefaultName::Whistle),
-            "Finish" => HitSampleInfoName::Default(rosu_map::section::hit_objects::hit_samples::HitSampleDefaultName::Finish),
-            "Clap" => HitSampleInfoName::Default(rosu_map::section::hit_objects::hit_samples::HitSampleDefaultName::Clap),
-            other => HitSampleInfoName::File(other.to_string()),
-        };
-        let bank = match hs.bank.as_str() {
-            "Normal" => SampleBank::Normal,
-            "Soft" => SampleBank::Soft,
-            "Drum" => SampleBank::Drum,
-            _ => SampleBank::None,
-        };
-        HitSampleInfo {
-            name,
-            bank,
+    fn reconstruct_hit_sample(hs: &HitSampleRow) -> Result<HitSampleInfo> {
+        let name: HitSoundName = hs.name.parse()?;
+        let bank: kinds::SampleBank = hs.bank.parse()?;
+        Ok(HitSampleInfo {
+            name: name.into(),
+            bank: bank.into(),
             suffix: hs.suffix.as_ref().and_then(|s| s.parse().ok()).and_then(std::num::NonZeroU32::new),
             volume: hs.volume,
             custom_sample_bank: 0,
             bank_specified: true,
             is_layered: false,
-        }
+        })
     }
 
 
@@ -176,15 +216,10 @@ impl BeatmapReconstructor {
         };
         beatmap.default_sample_volume = row.default_sample_volume;
         beatmap.stack_leniency = row.stack_leniency;
-        beatmap.mode = match row.mode {
-            0 => GameMode::Osu,
-            1 => GameMode::Taiko,
-            2 => GameMode::Catch,
-            3 => GameMode::Mania,
-            _ => GameMode::Osu,
-        };
+        beatmap.mode = game_mode(row.mode);
         beatmap.letterbox_in_breaks = row.letterbox_in_breaks;
-        beatmap.special_style = row.special_style;
+        // Only mania reads SpecialStyle (the N+1 key layout)
+        beatmap.special_style = row.special_style && beatmap.mode == GameMode::Mania;
         beatmap.widescreen_storyboard = row.widescreen_storyboard;
         beatmap.epilepsy_warning = row.epilepsy_warning;
         beatmap.samples_match_playback_rate = row.samples_match_playback_rate;
@@ -224,17 +259,330 @@ impl BeatmapReconstructor {
         beatmap.background_file = row.background_file.clone();
     }
 
-    fn reconstruct_hit_object(
+    /// Hit objects starting in `start_ms..end_ms`
+    ///
+    /// Binary search over start times, which are in order in a reconstructed beatmap,
+    /// so windowed analysis and seeking don't scan the whole map. An object starting
+    /// exactly at `start_ms` is included, one at `end_ms` is not; an empty or inverted
+    /// range gives an empty slice.
+    pub fn objects_in_range(beatmap: &Beatmap, start_ms: f64, end_ms: f64) -> &[HitObject] {
+        let objects = beatmap.hit_objects.as_slice();
+        let start = objects.partition_point(|object| object.start_time < start_ms);
+        let end = objects.partition_point(|object| object.start_time < end_ms).max(start);
+        &objects[start..end]
+    }
+
+    /// Index of the first hit object starting at or after `time_ms` (None past the last one)
+    pub fn object_at_or_after(beatmap: &Beatmap, time_ms: f64) -> Option<usize> {
+        let index = beatmap.hit_objects.partition_point(|object| object.start_time < time_ms);
+        (index < beatmap.hit_objects.len()).then_some(index)
+    }
+
+    /// Drop `[General]` lines that carry no meaning for the beatmap's mode
+    ///
+    /// The encoder writes every field for every mode. Only lines that hold the
+    /// decoder's default are dropped, so re-parsing the output gives the same values:
+    /// `SpecialStyle` outside mania, `Countdown: 1` in mania (which never plays a
+    /// countdown) and a zero `CountdownOffset`.
+    pub fn prune_general_fields(osu_content: &str, mode: GameMode) -> String {
+        OsuSections::edit(osu_content, |osu| {
+            let Some(general) = osu.section_mut("General") else {
+                return;
+            };
+            general.retain_fields(|key, value| {
+                let redundant = match (key, value) {
+                    ("SpecialStyle", _) => mode != GameMode::Mania,
+                    ("Countdown", "1") => mode == GameMode::Mania,
+                    ("CountdownOffset", "0") => true,
+                    _ => false,
+                };
+                !redundant
+            });
+        })
+    }
+
+    /// Remove a `[Colours]` section that has no entries
+    ///
+    /// Maps without combo or custom colours rely on the skin's defaults and have no
+    /// `[Colours]` section; an empty header is dropped so the output matches.
+    pub fn drop_empty_colours(osu_content: &str) -> String {
+        OsuSections::edit(osu_content, |osu| {
+            if osu.section("Colours").is_some_and(Section::is_blank) {
+                osu.remove_section("Colours");
+            }
+        })
+    }
+
+    /// Write `AudioLeadIn` and `PreviewTime` in `[General]` with the exact stored values
+    ///
+    /// Existing lines are replaced and missing ones are inserted after `AudioFilename`, so
+    /// the output does not depend on how the encoder formats or omits them. `PreviewTime: -1`
+    /// (no preview point) is written as is, and an integral lead-in is written without a
+    /// fractional part like osu! does.
+    pub fn set_audio_timing(osu_content: &str, audio_lead_in: f64, preview_time: i32) -> String {
+        let lead_in = if audio_lead_in.fract() == 0.0 {
+            format!("AudioLeadIn: {}", audio_lead_in as i64)
+        } else {
+            format!("AudioLeadIn: {}", audio_lead_in)
+        };
+        let preview = format!("PreviewTime: {}", preview_time);
+
+        OsuSections::edit(osu_content, |osu| {
+            let Some(general) = osu.section_mut("General") else {
+                return;
+            };
+            general.retain_fields(|key, _| !matches!(key, "AudioLeadIn" | "PreviewTime"));
+            let at = general.position("AudioFilename").map_or(0, |idx| idx + 1);
+            general.lines.splice(at..at, [lead_in, preview]);
+        })
+    }
+
+    /// Write the skin settings of `[General]` that differ from osu!'s defaults
+    ///
+    /// The encoder has no fields for `UseSkinSprites`, `AlwaysShowPlayfield`,
+    /// `OverlayPosition` and `SkinPreference`. Non-default values are inserted after
+    /// `LetterboxInBreaks` (or at the end of the section) in the order osu! writes them;
+    /// defaults are left out like osu! does.
+    pub fn set_skin_settings(osu_content: &str, row: &BeatmapRow) -> String {
+        let mut skin_lines = Vec::new();
+        if row.use_skin_sprites {
+            skin_lines.push("UseSkinSprites: 1".to_string());
+        }
+        if row.always_show_playfield {
+            skin_lines.push("AlwaysShowPlayfield: 1".to_string());
+        }
+        if row.overlay_position != "NoChange" && !row.overlay_position.is_empty() {
+            skin_lines.push(format!("OverlayPosition: {}", row.overlay_position));
+        }
+        if !row.skin_preference.is_empty() {
+            skin_lines.push(format!("SkinPreference: {}", row.skin_preference));
+        }
+        if skin_lines.is_empty() {
+            return osu_content.to_string();
+        }
+
+        OsuSections::edit(osu_content, |osu| {
+            let Some(general) = osu.section_mut("General") else {
+                return;
+            };
+            // End of the section, before the blank lines separating it from the next one
+            let at = general.position("LetterboxInBreaks").map_or(general.content_end(), |idx| idx + 1);
+            general.lines.splice(at..at, skin_lines);
+        })
+    }
+
+    /// Write the `[Editor]` section from the stored editor state
+    ///
+    /// Editor keys the encoder wrote are replaced, and the section is inserted before
+    /// `[Metadata]` when missing, so editors see the same bookmarks, spacing, divisor,
+    /// grid and zoom. `Bookmarks` is left out when there are none. Floats use Rust's
+    /// shortest round-trip formatting (`1.4`, `1` for `1.0`), so the stored value is
+    /// written back without precision drift.
+    pub fn set_editor_section(osu_content: &str, row: &BeatmapRow) -> String {
+        let bookmarks: Vec<&str> = row
+            .bookmarks
+            .split(',')
+            .map(str::trim)
+            .filter(|bookmark| !bookmark.is_empty())
+            .collect();
+        let mut editor_lines = Vec::new();
+        if !bookmarks.is_empty() {
+            editor_lines.push(format!("Bookmarks: {}", bookmarks.join(",")));
+        }
+        editor_lines.push(format!("DistanceSpacing: {}", row.distance_spacing));
+        editor_lines.push(format!("BeatDivisor: {}", row.beat_divisor));
+        editor_lines.push(format!("GridSize: {}", row.grid_size));
+        editor_lines.push(format!("TimelineZoom: {}", row.timeline_zoom));
+
+        OsuSections::edit(osu_content, |osu| match osu.section_mut("Editor") {
+            Some(editor) => {
+                editor.retain_fields(|key, _| {
+                    !matches!(key, "Bookmarks" | "DistanceSpacing" | "BeatDivisor" | "GridSize" | "TimelineZoom")
+                });
+                editor.lines.splice(0..0, editor_lines);
+            }
+            None => {
+                editor_lines.push(String::new());
+                osu.insert_section_before("Metadata", "Editor", editor_lines);
+            }
+        })
+    }
+
+    /// Write each slider's curve field from its stored anchors, as they were in the `.osu`
+    ///
+    /// The encoder re-derives red anchors from typed points, which osu! files don't
+    /// always match (e.g. an anchor repeating the head). With literal anchors (schema
+    /// v18) the field is rebuilt from the rows instead. Slider lines are matched to
+    /// rows by head position and time in object order; unmatched lines are kept.
+    pub fn set_slider_anchors(
+        osu_content: &str,
+        folder_id: &str,
+        osu_file: &str,
+        hit_object_rows: &[HitObjectRow],
+        control_point_rows: &[SliderControlPointRow],
+    ) -> String {
+        let mut sliders: Vec<&HitObjectRow> = hit_object_rows
+            .iter()
+            .filter(|ho| {
+                ho.folder_id == folder_id && ho.osu_file == osu_file && ho.object_type == ObjectType::Slider.as_str()
+            })
+            .collect();
+        sliders.sort_by_key(|ho| ho.index);
+        let mut anchors: HashMap<i32, Vec<&SliderControlPointRow>> = HashMap::new();
+        for cp in control_point_rows
+            .iter()
+            .filter(|cp| cp.folder_id == folder_id && cp.osu_file == osu_file)
+        {
+            anchors.entry(cp.hit_object_index).or_default().push(cp);
+        }
+        for points in anchors.values_mut() {
+            points.sort_by_key(|cp| cp.point_index);
+        }
+
+        OsuSections::edit(osu_content, |osu| {
+            let Some(hit_objects) = osu.section_mut("HitObjects") else {
+                return;
+            };
+            let mut next = 0;
+            for line in &mut hit_objects.lines {
+                let mut fields: Vec<&str> = line.split(',').collect();
+                let head = match fields.as_slice() {
+                    [x, y, time, kind, _, _, ..] if kind.trim().parse::<i32>().is_ok_and(|kind| kind & 2 != 0) => {
+                        x.trim().parse::<f32>().ok().zip(y.trim().parse::<f32>().ok()).zip(time.trim().parse::<f64>().ok())
+                    }
+                    _ => None,
+                };
+                let matched = head.and_then(|((x, y), time)| {
+                    let offset = sliders[next.min(sliders.len())..].iter().position(|ho| {
+                        ho.pos_x.map(|px| px as f32) == Some(x)
+                            && ho.pos_y.map(|py| py as f32) == Some(y)
+                            && (ho.start_time - time).abs() < 1.0
+                    })?;
+                    next += offset + 1;
+                    let ho = sliders[next - 1];
+                    slider_anchor_field((x, y), anchors.get(&ho.index)?, ho.curve_type.as_deref())
+                });
+                if let Some(field) = matched {
+                    fields[5] = &field;
+                    let rebuilt = fields.join(",");
+                    *line = rebuilt;
+                }
+            }
+        })
+    }
+
+    /// Rebuild the `[Events]` section of an encoded `.osu` in the order osu! writes it
+    ///
+    /// The encoder's background and break lines are merged with the raw lines from
+    /// events.parquet (videos, storyboard samples, background colour transformations)
+    /// and grouped under osu!'s section comments; see `EventGroup` for the order.
+    /// Lines of the same group keep their relative order.
+    pub fn assemble_events(osu_content: &str, folder_id: &str, osu_file: &str, event_rows: &[EventRow]) -> String {
+        let mut events: Vec<&EventRow> = event_rows
+            .iter()
+            .filter(|e| e.folder_id == folder_id && e.osu_file == osu_file)
+            .collect();
+        events.sort_by_key(|e| e.event_index);
+
+        OsuSections::edit(osu_content, |osu| {
+            let mut groups: Vec<Vec<String>> = vec![Vec::new(); EventGroup::ALL.len()];
+            let mut group = EventGroup::Other;
+            for line in osu.section("Events").map_or(&[][..], |section| section.lines.as_slice()) {
+                let trimmed = line.trim_end();
+                if trimmed.is_empty() || trimmed.starts_with("//") {
+                    continue;
+                }
+                // Indented lines are storyboard commands of the element above them
+                if !trimmed.starts_with([' ', '\t', '_']) {
+                    group = EventGroup::of(trimmed.split(',').next().unwrap_or(""));
+                }
+                groups[group as usize].push(trimmed.to_string());
+            }
+
+            for event in events {
+                let line = if event.params.is_empty() {
+                    event.event_type.clone()
+                } else {
+                    format!("{},{}", event.event_type, event.params)
+                };
+                groups[EventGroup::of(&event.event_type) as usize].push(line);
+            }
+
+            let mut lines: Vec<String> = Vec::new();
+            for group in EventGroup::ALL {
+                lines.extend(group.headers().iter().map(|header| header.to_string()));
+                lines.append(&mut groups[group as usize]);
+            }
+
+            match osu.section_mut("Events") {
+                Some(section) => {
+                    lines.push(String::new());
+                    section.lines = lines;
+                }
+                // The encoder left [Events] out entirely
+                None => osu.push_section("Events", &lines),
+            }
+        })
+    }
+
+    /// Add the unrecognised `[General]`/`[Metadata]` keys of a difficulty to its encoded `.osu` content
+    ///
+    /// The keys are appended to the end of their section in their original order,
+    /// using the spacing osu! writes (`Key: value` in General, `Key:value` in Metadata).
+    pub fn insert_extra_fields(osu_content: &str, folder_id: &str, osu_file: &str, field_rows: &[ExtraFieldRow]) -> String {
+        let mut fields: Vec<&ExtraFieldRow> = field_rows
+            .iter()
+            .filter(|f| f.folder_id == folder_id && f.osu_file == osu_file)
+            .collect();
+        if fields.is_empty() {
+            return osu_content.to_string();
+        }
+        fields.sort_by_key(|f| f.field_index);
+
+        OsuSections::edit(osu_content, |osu| {
+            for section in ["General", "Metadata"] {
+                let separator = if section == "General" { ": " } else { ":" };
+                let lines: Vec<String> = fields
+                    .iter()
+                    .filter(|f| f.section == section)
+                    .map(|f| format!("{}{}{}", f.key, separator, f.value))
+                    .collect();
+                if lines.is_empty() {
+                    continue;
+                }
+                match osu.section_mut(section) {
+                    Some(existing) => existing.append(&lines),
+                    // The encoder always writes both sections, but don't drop keys if one is missing
+                    None => osu.push_section(section, &lines),
+                }
+            }
+        })
+    }
+
+    pub(crate) fn reconstruct_hit_object(
         ho: &HitObjectRow,
+        object_type: ObjectType,
         mode: &GameMode,
+        key_count: i32,
         slider_data_map: &HashMap<i32, &SliderDataRow>,
         slider_cp_map: &HashMap<i32, Vec<&SliderControlPointRow>>,
+        features: &DatasetFeatures,
     ) -> Option<HitObject> {
-        match ho.object_type.as_str() {
-            "circle" => {
+        // Spinners and holds store their end time; convert back to the duration rosu-map expects
+        let duration = match ho.end_time {
+            Some(end_time) if features.absolute_end_times => end_time - ho.start_time,
+            Some(duration) => duration,
+            None => 0.0,
+        };
+
+        // Mania notes encode their column in x; rebuild it from the column if x is missing
+        let pos_x = ho.pos_x.or_else(|| ho.column.map(|column| column_center_x(column, key_count)));
+
+        match object_type {
+            ObjectType::Circle => {
                 let circle = HitObjectCircle {
                     pos: Pos {
-                        x: ho.pos_x.unwrap_or(0) as f32,
+                        x: pos_x.unwrap_or(0) as f32,
                         y: ho.pos_y.unwrap_or(0) as f32,
                     },
                     new_combo: ho.new_combo,
@@ -246,7 +594,7 @@ impl BeatmapReconstructor {
                     samples: Vec::new(),
                 })
             }
-            "slider" => {
+            ObjectType::Slider => {
                 let sd = slider_data_map.get(&ho.index)?;
                 let control_points: Vec<PathControlPoint> = slider_cp_map
                     .get(&ho.index)
@@ -262,6 +610,19 @@ impl BeatmapReconstructor {
                                         _ => None,
                                     }
                                 });
+                                // Fall back to the object-level curve type for the head
+                                let path_type = path_type.or_else(|| {
+                                    if cp.point_index != 0 {
+                                        return None;
+                                    }
+                                    match ho.curve_type.as_deref() {
+                                        Some("B") => Some(PathType::BEZIER),
+                                        Some("L") => Some(PathType::LINEAR),
+                                        Some("C") => Some(PathType::CATMULL),
+                                        Some("P") => Some(PathType::PERFECT_CURVE),
+                                        _ => None,
+                                    }
+                                });
                                 PathControlPoint {
                                     pos: Pos { x: cp.pos_x, y: cp.pos_y },
                                     path_type,
@@ -270,8 +631,23 @@ impl BeatmapReconstructor {
                             .collect()
                     })
                     .unwrap_or_default();
+                // Literal anchors repeat red anchors; rosu-map expects them as typed points
+                let control_points = if features.literal_slider_anchors {
+                    merge_red_anchors(control_points)
+                } else {
+                    control_points
+                };
 
-                let slider_path = SliderPath::new(*mode, control_points, sd.expected_dist);
+                // Without a stored length (hit_objects.length holds 0 then), recover it
+                // from the velocity and duration rather than letting the path's full
+                // control point length stand in
+                let span_count = sd.repeat_count.max(0) + 1;
+                let expected_dist = sd.expected_dist.or(ho.length.filter(|length| *length > 0.0)).or_else(|| {
+                    ho.duration
+                        .filter(|_| sd.velocity.is_finite() && sd.velocity > 0.0)
+                        .map(|duration| osu_slider_length(sd.velocity, duration, span_count))
+                });
+                let slider_path = SliderPath::new(*mode, control_points, expected_dist);
 
                 let slider = HitObjectSlider {
                     pos: Pos {
@@ -282,7 +658,9 @@ impl BeatmapReconstructor {
                     combo_offset: ho.combo_offset,
                     path: slider_path,
                     node_samples: Vec::new(),
-                    repeat_count: sd.repeat_count,
+                    // The encoder writes slides = repeat_count + 1; clamp bad rows so
+                    // a negative count can't reach it (or the node sample count)
+                    repeat_count: sd.repeat_count.max(0),
                     velocity: sd.velocity,
                 };
                 Some(HitObject {
@@ -291,13 +669,13 @@ impl BeatmapReconstructor {
                     samples: Vec::new(),
                 })
             }
-            "spinner" => {
+            ObjectType::Spinner => {
                 let spinner = HitObjectSpinner {
                     pos: Pos {
                         x: ho.pos_x.unwrap_or(256) as f32,
                         y: ho.pos_y.unwrap_or(192) as f32,
                     },
-                    duration: ho.end_time.unwrap_or(0.0),
+                    duration,
                     new_combo: ho.new_combo,
                 };
                 Some(HitObject {
@@ -306,10 +684,11 @@ impl BeatmapReconstructor {
                     samples: Vec::new(),
                 })
             }
-            "hold" => {
+            ObjectType::Hold => {
+                // Encoded as `x,192,time,128,hitSound,endTime:hitSample` from the duration
                 let hold = HitObjectHold {
-                    pos_x: ho.pos_x.unwrap_or(0) as f32,
-                    duration: ho.end_time.unwrap_or(0.0),
+                    pos_x: pos_x.unwrap_or(0) as f32,
+                    duration,
                 };
                 Some(HitObject {
                     start_time: ho.start_time,
@@ -317,10 +696,24 @@ impl BeatmapReconstructor {
                     samples: Vec::new(),
                 })
             }
-            _ => None,
         }
     }
 
+    /// Timing point rows of one difficulty in their original line order when it was
+    /// recorded, so same-time red and green lines keep their sequence
+    ///
+    /// Rows from datasets without `order_index` (or with gaps) keep their stored order.
+    fn ordered_timing_points<'a>(rows: &'a [TimingPointRow], folder_id: &str, osu_file: &str) -> Vec<&'a TimingPointRow> {
+        let mut timing_points: Vec<&TimingPointRow> = rows
+            .iter()
+            .filter(|tp| tp.folder_id == folder_id && tp.osu_file == osu_file)
+            .collect();
+        if timing_points.iter().all(|tp| tp.order_index.is_some()) {
+            timing_points.sort_by_key(|tp| tp.order_index);
+        }
+        timing_points
+    }
+
     fn add_timing_point(beatmap: &mut Beatmap, tp: &TimingPointRow) {
         match tp.point_type.as_str() {
             "timing" => {
@@ -344,7 +737,815 @@ impl BeatmapReconstructor {
                     ..Default::default()
                 });
             }
+            "sample" => {
+                let sample_bank = match tp.sample_bank.as_deref() {
+                    Some("Soft") => SampleBank::Soft,
+                    Some("Drum") => SampleBank::Drum,
+                    _ => SampleBank::Normal,
+                };
+                beatmap.control_points.sample_points.push(SamplePoint {
+                    time: tp.time,
+                    sample_bank,
+                    sample_volume: tp.sample_volume.unwrap_or(100),
+                    custom_sample_bank: tp.sample_index.unwrap_or(0),
+                });
+            }
             _ => {}
         }
     }
 }
+
+/// x at the centre of a mania column, which maps back to the same column via `floor(x * keys / 512)`
+fn column_center_x(column: i32, key_count: i32) -> i32 {
+    (column * 512 + 256) / key_count.max(1)
+}
+
+/// Turn repeated anchors into the typed points rosu-map decodes them as
+///
+/// As in osu!, an anchor repeating the previous one ends the current segment: the
+/// first copy takes the segment's type and the second is dropped. Catmull paths and
+/// the last anchor of a segment are left as they are.
+fn merge_red_anchors(points: Vec<PathControlPoint>) -> Vec<PathControlPoint> {
+    let segment_ends: Vec<bool> = (0..points.len())
+        .map(|i| points.get(i + 1).is_none_or(|next| next.path_type.is_some()))
+        .collect();
+    let mut merged: Vec<PathControlPoint> = Vec::with_capacity(points.len());
+    let mut segment_type = None;
+    for (point, segment_end) in points.into_iter().zip(segment_ends) {
+        if point.path_type.is_some() {
+            segment_type = point.path_type;
+        }
+        let repeated = point.path_type.is_none() && merged.last().is_some_and(|last| last.pos == point.pos);
+        if repeated && !segment_end && segment_type.is_some_and(|kind| kind != PathType::CATMULL) {
+            if let Some(last) = merged.last_mut() {
+                last.path_type = segment_type;
+            }
+            continue;
+        }
+        merged.push(point);
+    }
+    merged
+}
+
+/// `.osu` curve field (`B|x:y|x:y`) of stored anchors, with positions made absolute
+///
+/// Type letters are written for the head and before anchors that carry a type.
+/// `None` if the head's curve type is unknown.
+fn slider_anchor_field(head: (f32, f32), points: &[&SliderControlPointRow], curve_type: Option<&str>) -> Option<String> {
+    let (first, rest) = points.split_first()?;
+    let letter = |path_type: &str| match path_type {
+        "Bezier" => Some("B"),
+        "Linear" => Some("L"),
+        "Catmull" => Some("C"),
+        "PerfectCurve" => Some("P"),
+        _ => None,
+    };
+    let mut tokens = vec![first.path_type.as_deref().and_then(letter).or(curve_type)?.to_string()];
+    for point in rest {
+        if let Some(letter) = point.path_type.as_deref().and_then(letter) {
+            tokens.push(letter.to_string());
+        }
+        tokens.push(format!("{}:{}", head.0 + point.pos_x, head.1 + point.pos_y));
+    }
+    Some(tokens.join("|"))
+}
+
+/// Groups of `[Events]` lines, in the order osu! writes them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EventGroup {
+    BackgroundAndVideo,
+    Breaks,
+    Storyboard,
+    Samples,
+    Colours,
+    /// Types osu! doesn't know, kept last so they can't split a known group
+    Other,
+}
+
+impl EventGroup {
+    const ALL: [EventGroup; 6] = [
+        EventGroup::BackgroundAndVideo,
+        EventGroup::Breaks,
+        EventGroup::Storyboard,
+        EventGroup::Samples,
+        EventGroup::Colours,
+        EventGroup::Other,
+    ];
+
+    /// Group of an event type field, numeric or named
+    fn of(event_type: &str) -> Self {
+        match event_type.trim() {
+            "0" | "Background" | "1" | "Video" => EventGroup::BackgroundAndVideo,
+            "2" | "Break" => EventGroup::Breaks,
+            "4" | "Sprite" | "6" | "Animation" => EventGroup::Storyboard,
+            "5" | "Sample" => EventGroup::Samples,
+            "3" | "Colour" => EventGroup::Colours,
+            _ => EventGroup::Other,
+        }
+    }
+
+    /// Comments osu! writes above the group; sprites and animations of an embedded
+    /// storyboard go to the `.osb`, so the storyboard group is usually just its layer headers
+    fn headers(self) -> &'static [&'static str] {
+        match self {
+            EventGroup::BackgroundAndVideo => &["//Background and Video events"],
+            EventGroup::Breaks => &["//Break Periods"],
+            EventGroup::Storyboard => &[
+                "//Storyboard Layer 0 (Background)",
+                "//Storyboard Layer 1 (Fail)",
+                "//Storyboard Layer 2 (Pass)",
+                "//Storyboard Layer 3 (Foreground)",
+            ],
+            EventGroup::Samples => &["//Storyboard Sound Samples"],
+            EventGroup::Colours => &["//Background Colour Transformations"],
+            EventGroup::Other => &[],
+        }
+    }
+}
+
+/// Game mode of a `beatmaps.mode` value; unknown values fall back to osu!standard
+pub(crate) fn game_mode(mode: i32) -> GameMode {
+    match mode {
+        0 => GameMode::Osu,
+        1 => GameMode::Taiko,
+        2 => GameMode::Catch,
+        3 => GameMode::Mania,
+        _ => GameMode::Osu,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_rows;
+
+    /// Reconstruct and encode a difficulty from the given rows
+    fn encode(
+        row: &BeatmapRow,
+        hit_objects: &[HitObjectRow],
+        timing_points: &[TimingPointRow],
+        features: &DatasetFeatures,
+    ) -> String {
+        encode_dataset(&Dataset {
+            beatmaps: vec![row.clone()],
+            hit_objects: hit_objects.to_vec(),
+            timing_points: timing_points.to_vec(),
+            features: features.clone(),
+            ..Dataset::default()
+        })
+    }
+
+    /// Reconstruct the first difficulty of a dataset
+    fn reconstruct_dataset(dataset: &Dataset) -> Beatmap {
+        BeatmapReconstructor::reconstruct(
+            &dataset.beatmaps[0],
+            &dataset.hit_objects,
+            &dataset.timing_points,
+            &dataset.slider_control_points,
+            &dataset.slider_data,
+            &dataset.breaks,
+            &dataset.combo_colors,
+            &dataset.hit_samples,
+            &dataset.features,
+        )
+        .unwrap()
+    }
+
+    /// Reconstruct and encode the first difficulty of a dataset
+    fn encode_dataset(dataset: &Dataset) -> String {
+        let mut beatmap = reconstruct_dataset(dataset);
+        let mut encoded = Vec::new();
+        beatmap.encode(&mut encoded).unwrap();
+        String::from_utf8(encoded).unwrap()
+    }
+
+    /// Lines of one `[Section]`, blank lines excluded
+    fn section_lines<'a>(content: &'a str, section: &str) -> Vec<&'a str> {
+        let header = format!("[{}]", section);
+        content
+            .lines()
+            .skip_while(|line| line.trim() != header)
+            .skip(1)
+            .take_while(|line| !line.starts_with('['))
+            .filter(|line| !line.trim().is_empty())
+            .collect()
+    }
+
+    /// (time, volume) of the `[TimingPoints]` lines, a red and green line at the same time counted once
+    fn timing_line_volumes(content: &str) -> Vec<(f64, i32)> {
+        let mut volumes: Vec<(f64, i32)> = section_lines(content, "TimingPoints")
+            .iter()
+            .map(|line| {
+                let fields: Vec<&str> = line.split(',').collect();
+                (fields[0].parse().unwrap(), fields[5].parse().unwrap())
+            })
+            .collect();
+        volumes.dedup();
+        volumes
+    }
+
+    #[test]
+    fn timing_lines_carry_a_mid_song_volume_change() {
+        let timing_points = vec![
+            test_rows::timing(0.0, 500.0),
+            test_rows::sample(0.0, "Soft", 80),
+            test_rows::sample(2000.0, "Soft", 30),
+        ];
+        let content = encode(&test_rows::beatmap(0), &[], &timing_points, &DatasetFeatures::default());
+
+        assert_eq!(timing_line_volumes(&content), vec![(0.0, 80), (2000.0, 30)]);
+    }
+
+    #[test]
+    fn missing_sample_points_fall_back_to_the_default_volume() {
+        let mut row = test_rows::beatmap(0);
+        row.default_sample_volume = 65;
+        let timing_points = vec![test_rows::timing(0.0, 500.0), test_rows::timing(4000.0, 400.0)];
+        let features = DatasetFeatures {
+            sample_points: false,
+            ..DatasetFeatures::default()
+        };
+        let content = encode(&row, &[], &timing_points, &features);
+
+        assert_eq!(timing_line_volumes(&content), vec![(0.0, 65), (4000.0, 65)]);
+    }
+
+    /// End time field of the encoded spinner line
+    fn spinner_end_time(spinner: HitObjectRow, features: &DatasetFeatures) -> String {
+        let content = encode(&test_rows::beatmap(0), &[spinner], &[test_rows::timing(0.0, 500.0)], features);
+        let line = section_lines(&content, "HitObjects")[0];
+        line.split(',').nth(5).unwrap().to_string()
+    }
+
+    #[test]
+    fn spinner_lines_end_at_the_stored_absolute_time() {
+        let features = DatasetFeatures::default();
+        assert_eq!(spinner_end_time(test_rows::spinner(0, 1000.0, 3000.0), &features), "3000");
+    }
+
+    #[test]
+    fn legacy_spinner_durations_become_absolute_end_times() {
+        // Before schema v3 the end_time column held the duration
+        let spinner = HitObjectRow {
+            end_time: Some(2000.0),
+            ..test_rows::spinner(0, 1000.0, 3000.0)
+        };
+        let features = DatasetFeatures {
+            absolute_end_times: false,
+            ..DatasetFeatures::default()
+        };
+        assert_eq!(spinner_end_time(spinner, &features), "3000");
+    }
+
+    #[test]
+    fn slider_body_and_edge_samples_are_encoded_separately() {
+        let mut dataset = test_rows::dataset(vec![test_rows::slider(0, 1000.0, 100, 100, "L", 1, 100.0)]);
+        dataset.slider_control_points = test_rows::control_points(0, &[(0.0, 0.0), (100.0, 0.0)]);
+        dataset.slider_data = vec![test_rows::slider_data(0, 0, 100.0)];
+        let node = |node_index: i32, sample_index: i32, name: &str| HitSampleRow {
+            scope: if node_index == 0 { "head" } else { "tail" }.to_string(),
+            node_index: Some(node_index),
+            ..test_rows::hit_sample(0, sample_index, name, 0)
+        };
+        let body = |sample_index: i32, name: &str| HitSampleRow {
+            scope: "body".to_string(),
+            ..test_rows::hit_sample(0, sample_index, name, 0)
+        };
+        dataset.hit_samples = vec![
+            body(0, "Default(Normal)"),
+            body(1, "Default(Whistle)"),
+            node(0, 0, "Default(Normal)"),
+            node(1, 0, "Default(Normal)"),
+            node(1, 1, "Default(Clap)"),
+        ];
+
+        let content = encode_dataset(&dataset);
+        let line = section_lines(&content, "HitObjects")[0];
+        let fields: Vec<&str> = line.split(',').collect();
+        // Whistle on the body, nothing extra on the head, a clap on the tail
+        assert_eq!(fields[4], "2", "{}", line);
+        assert_eq!(fields[8], "0|8", "{}", line);
+    }
+
+    #[test]
+    fn mode_specific_general_fields_are_pruned() {
+        let mut mania = test_rows::beatmap(3);
+        mania.special_style = true;
+        mania.countdown = 1;
+        let content = encode(&mania, &[], &[test_rows::timing(0.0, 500.0)], &DatasetFeatures::default());
+        let pruned = BeatmapReconstructor::prune_general_fields(&content, GameMode::Mania);
+        let general = section_lines(&pruned, "General");
+        assert!(general.contains(&"SpecialStyle: 1"), "{:?}", general);
+        assert!(!general.iter().any(|line| line.starts_with("Countdown")), "{:?}", general);
+
+        let mut standard = test_rows::beatmap(0);
+        standard.special_style = true;
+        standard.countdown = 1;
+        let content = encode(&standard, &[], &[test_rows::timing(0.0, 500.0)], &DatasetFeatures::default());
+        let pruned = BeatmapReconstructor::prune_general_fields(&content, GameMode::Osu);
+        let general = section_lines(&pruned, "General");
+        assert!(!general.iter().any(|line| line.starts_with("SpecialStyle")), "{:?}", general);
+        // A standard map keeps its countdown
+        assert!(general.contains(&"Countdown: 1"), "{:?}", general);
+    }
+
+    #[test]
+    fn timing_points_follow_their_recorded_line_order() {
+        let indexed = |row: TimingPointRow, order_index: i32| TimingPointRow { order_index: Some(order_index), ..row };
+        // A green line before a red one at the same time, stored in table order (red first)
+        let rows = vec![
+            indexed(test_rows::timing(0.0, 500.0), 0),
+            indexed(test_rows::timing(1000.0, 400.0), 2),
+            indexed(test_rows::difficulty(1000.0, 2.0), 1),
+            TimingPointRow { folder_id: "other".to_string(), ..indexed(test_rows::difficulty(0.0, 0.5), 0) },
+        ];
+        let kinds = |rows: &[TimingPointRow]| -> Vec<(f64, String)> {
+            BeatmapReconstructor::ordered_timing_points(rows, test_rows::FOLDER, test_rows::OSU_FILE)
+                .iter()
+                .map(|tp| (tp.time, tp.point_type.clone()))
+                .collect()
+        };
+
+        assert_eq!(
+            kinds(&rows),
+            vec![(0.0, "timing".to_string()), (1000.0, "difficulty".to_string()), (1000.0, "timing".to_string())]
+        );
+
+        // Without an order_index on every row, the stored order is kept
+        let mut unordered = rows.clone();
+        unordered[2].order_index = None;
+        assert_eq!(
+            kinds(&unordered),
+            vec![(0.0, "timing".to_string()), (1000.0, "timing".to_string()), (1000.0, "difficulty".to_string())]
+        );
+    }
+
+    #[test]
+    fn audio_lead_in_and_no_preview_survive_the_round_trip() {
+        let row = BeatmapRow { audio_lead_in: 1500.0, preview_time: -1, ..test_rows::beatmap(0) };
+        let encoded = encode(&row, &[], &[test_rows::timing(0.0, 500.0)], &DatasetFeatures::default());
+        let content = BeatmapReconstructor::set_audio_timing(&encoded, row.audio_lead_in, row.preview_time);
+
+        let general = section_lines(&content, "General");
+        let audio_file = general.iter().position(|line| line.starts_with("AudioFilename")).unwrap();
+        assert_eq!(general[audio_file + 1], "AudioLeadIn: 1500");
+        assert_eq!(general[audio_file + 2], "PreviewTime: -1");
+        assert_eq!(general.iter().filter(|line| line.starts_with("AudioLeadIn")).count(), 1);
+        assert_eq!(general.iter().filter(|line| line.starts_with("PreviewTime")).count(), 1);
+
+        let parsed = rosu_map::from_str::<Beatmap>(&content).unwrap();
+        assert_eq!(parsed.audio_lead_in, 1500.0);
+        assert_eq!(parsed.preview_time, -1);
+    }
+
+    #[test]
+    fn audio_timing_lines_are_replaced_in_place_of_missing_ones() {
+        let content = "osu file format v14\r\n\r\n[General]\r\nAudioFilename: a.mp3\r\nPreviewTime: 200\r\nMode: 0\r\n\r\n[Metadata]\r\nTitle:PreviewTime: 5\r\n";
+        let rewritten = BeatmapReconstructor::set_audio_timing(content, 12.5, 4000);
+        assert_eq!(
+            rewritten,
+            "osu file format v14\r\n\r\n[General]\r\nAudioFilename: a.mp3\r\nAudioLeadIn: 12.5\r\nPreviewTime: 4000\r\nMode: 0\r\n\r\n[Metadata]\r\nTitle:PreviewTime: 5\r\n"
+        );
+        // Without a [General] section there is nowhere to write them
+        assert_eq!(BeatmapReconstructor::set_audio_timing("[Metadata]\n", 0.0, -1), "[Metadata]\n");
+    }
+
+    #[test]
+    fn empty_colours_section_is_dropped() {
+        let content = "[General]\nMode: 0\n\n[Colours]\n\n[HitObjects]\n1,2,3,1,0\n";
+        assert_eq!(
+            BeatmapReconstructor::drop_empty_colours(content),
+            "[General]\nMode: 0\n\n[HitObjects]\n1,2,3,1,0\n"
+        );
+        let coloured = "[Colours]\nCombo1 : 255,0,0\n\n[HitObjects]\n";
+        assert_eq!(BeatmapReconstructor::drop_empty_colours(coloured), coloured);
+    }
+
+    #[test]
+    fn skin_settings_follow_letterbox_in_breaks() {
+        let row = BeatmapRow {
+            use_skin_sprites: true,
+            overlay_position: "Above".to_string(),
+            skin_preference: "Default".to_string(),
+            ..test_rows::beatmap(0)
+        };
+        let content = "[General]\nAudioFilename: a.mp3\nLetterboxInBreaks: 0\nWidescreenStoryboard: 0\n\n[Editor]\n";
+        assert_eq!(
+            BeatmapReconstructor::set_skin_settings(content, &row),
+            "[General]\nAudioFilename: a.mp3\nLetterboxInBreaks: 0\nUseSkinSprites: 1\nOverlayPosition: Above\nSkinPreference: Default\nWidescreenStoryboard: 0\n\n[Editor]\n"
+        );
+        // Without LetterboxInBreaks they end the section
+        assert_eq!(
+            BeatmapReconstructor::set_skin_settings("[General]\nMode: 0\n\n\n[Editor]\n", &row),
+            "[General]\nMode: 0\nUseSkinSprites: 1\nOverlayPosition: Above\nSkinPreference: Default\n\n\n[Editor]\n"
+        );
+        // Default settings write nothing
+        assert_eq!(BeatmapReconstructor::set_skin_settings(content, &test_rows::beatmap(0)), content);
+    }
+
+    #[test]
+    fn editor_section_is_rewritten_or_added_before_metadata() {
+        let row = BeatmapRow {
+            bookmarks: " 1000, ,2000,3000".to_string(),
+            distance_spacing: 1.2,
+            timeline_zoom: 1.5,
+            ..test_rows::beatmap(0)
+        };
+        let content = "[Editor]\nDistanceSpacing: 1\nGridSize: 32\n\n[Metadata]\nTitle:a\n";
+        assert_eq!(
+            BeatmapReconstructor::set_editor_section(content, &row),
+            "[Editor]\nBookmarks: 1000,2000,3000\nDistanceSpacing: 1.2\nBeatDivisor: 4\nGridSize: 4\nTimelineZoom: 1.5\n\n[Metadata]\nTitle:a\n"
+        );
+        // No bookmarks and no [Editor] in the encoded content
+        assert_eq!(
+            BeatmapReconstructor::set_editor_section("[General]\nMode: 0\n\n[Metadata]\nTitle:a\n", &test_rows::beatmap(0)),
+            "[General]\nMode: 0\n\n[Editor]\nDistanceSpacing: 1\nBeatDivisor: 4\nGridSize: 4\nTimelineZoom: 1\n\n[Metadata]\nTitle:a\n"
+        );
+    }
+
+    #[test]
+    fn mania_holds_are_written_at_their_column_centre_with_the_end_time() {
+        let holds: Vec<HitObjectRow> =
+            (0..4).map(|column| test_rows::hold(column, 1000.0, 1500.0 + column as f64, column)).collect();
+        let timing = [test_rows::timing(0.0, 500.0)];
+        let content = encode(&test_rows::beatmap(3), &holds, &timing, &DatasetFeatures::default());
+
+        let lines = section_lines(&content, "HitObjects");
+        assert_eq!(lines.len(), 4);
+        for (column, line) in lines.iter().enumerate() {
+            // x,y,time,128,hitSound,endTime:hitSample
+            let fields: Vec<&str> = line.split(',').collect();
+            let x: i32 = fields[0].parse().unwrap();
+            assert_eq!(x, column_center_x(column as i32, 4));
+            assert_eq!(x * 4 / 512, column as i32, "{}", line);
+            assert_eq!(&fields[2..5], ["1000", "128", "0"]);
+            assert!(fields[5].starts_with(&format!("{}:", 1500 + column)), "{}", line);
+        }
+    }
+
+    #[test]
+    fn stored_columns_outside_the_keys_are_refused() {
+        let reconstruct = |column: i32| {
+            let dataset = Dataset {
+                beatmaps: vec![test_rows::beatmap(3)],
+                hit_objects: vec![test_rows::hold(0, 1000.0, 1500.0, 3), test_rows::hold(1, 2000.0, 2500.0, column)],
+                timing_points: vec![test_rows::timing(0.0, 500.0)],
+                ..Dataset::default()
+            };
+            BeatmapReconstructor::reconstruct(
+                &dataset.beatmaps[0],
+                &dataset.hit_objects,
+                &dataset.timing_points,
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &dataset.features,
+            )
+        };
+
+        assert!(reconstruct(0).is_ok());
+        for column in [4, -1] {
+            match reconstruct(column) {
+                Err(ReconstructError::IntegrityViolation(message)) => {
+                    assert!(message.contains(&format!("hit object 1 is in column {}", column)), "{}", message)
+                }
+                other => panic!("column {} was accepted: {:?}", column, other.map(|_| ())),
+            }
+        }
+    }
+
+    #[test]
+    fn slider_curves_are_written_from_their_stored_anchors() {
+        let hit_objects = vec![
+            test_rows::circle(0, 500.0, 10, 10),
+            test_rows::slider(1, 1000.0, 100, 100, "B", 1, 100.0),
+            test_rows::slider(2, 2000.0, 50, 60, "L", 1, 50.0),
+        ];
+        let mut control_points = test_rows::control_points(1, &[(0.0, 0.0), (20.0, 0.0), (20.0, 20.0)]);
+        control_points.extend(
+            test_rows::control_points(2, &[(0.0, 0.0), (5.0, 5.0)])
+                .into_iter()
+                .map(|cp| SliderControlPointRow { folder_id: "other".to_string(), ..cp }),
+        );
+        let content = "[HitObjects]\n10,10,500,1,0,0:0:0:0:\n100,100,1000,2,0,B|150:150,1,100\n50,60,2000,2,0,L|100:60,1,50\n";
+
+        assert_eq!(
+            BeatmapReconstructor::set_slider_anchors(
+                content,
+                test_rows::FOLDER,
+                test_rows::OSU_FILE,
+                &hit_objects,
+                &control_points
+            ),
+            // The second slider has no anchors of its own in this difficulty and keeps its encoded curve
+            "[HitObjects]\n10,10,500,1,0,0:0:0:0:\n100,100,1000,2,0,B|120:100|120:120,1,100\n50,60,2000,2,0,L|100:60,1,50\n"
+        );
+    }
+
+    #[test]
+    fn events_are_regrouped_in_osu_order() {
+        let storyboard = "//Storyboard Layer 0 (Background)\n//Storyboard Layer 1 (Fail)\n//Storyboard Layer 2 (Pass)\n//Storyboard Layer 3 (Foreground)\n";
+        let event = |event_index: i32, event_type: &str, params: &str| EventRow {
+            folder_id: test_rows::FOLDER.to_string(),
+            osu_file: test_rows::OSU_FILE.to_string(),
+            event_index,
+            event_type: event_type.to_string(),
+            start_time: None,
+            params: params.to_string(),
+        };
+        let rows = vec![
+            event(0, "Sample", "1000,0,\"hit.wav\",70"),
+            EventRow { osu_file: "other.osu".to_string(), ..event(0, "Sample", "0,0,\"miss.wav\"") },
+        ];
+        let content = "[Events]\n2,3000,4000\n0,0,\"bg.jpg\",0,0\n\n[TimingPoints]\n0,500,4,2,0,100,1,0\n";
+
+        assert_eq!(
+            BeatmapReconstructor::assemble_events(content, test_rows::FOLDER, test_rows::OSU_FILE, &rows),
+            format!(
+                "[Events]\n//Background and Video events\n0,0,\"bg.jpg\",0,0\n//Break Periods\n2,3000,4000\n{}//Storyboard Sound Samples\nSample,1000,0,\"hit.wav\",70\n//Background Colour Transformations\n\n[TimingPoints]\n0,500,4,2,0,100,1,0\n",
+                storyboard
+            )
+        );
+        // An encoding without [Events] gets the section at the end
+        assert_eq!(
+            BeatmapReconstructor::assemble_events("[General]\nMode: 0\n", test_rows::FOLDER, test_rows::OSU_FILE, &[]),
+            format!(
+                "[General]\nMode: 0\n\n[Events]\n//Background and Video events\n//Break Periods\n{}//Storyboard Sound Samples\n//Background Colour Transformations\n\n",
+                storyboard
+            )
+        );
+    }
+
+    #[test]
+    fn extra_fields_go_at_the_end_of_their_section() {
+        let field = |section: &str, field_index: i32, key: &str, value: &str| ExtraFieldRow {
+            folder_id: test_rows::FOLDER.to_string(),
+            osu_file: test_rows::OSU_FILE.to_string(),
+            section: section.to_string(),
+            field_index,
+            key: key.to_string(),
+            value: value.to_string(),
+        };
+        let rows = vec![
+            field("General", 1, "CustomKey", "x"),
+            field("Metadata", 2, "Source2", "y"),
+            field("General", 0, "OtherKey", "1"),
+            ExtraFieldRow { folder_id: "other".to_string(), ..field("General", 0, "Elsewhere", "1") },
+        ];
+        let extra = |content: &str, rows: &[ExtraFieldRow]| {
+            BeatmapReconstructor::insert_extra_fields(content, test_rows::FOLDER, test_rows::OSU_FILE, rows)
+        };
+
+        assert_eq!(
+            extra("[General]\nMode: 0\n\n[Metadata]\nTitle:a\n", &rows),
+            "[General]\nMode: 0\nOtherKey: 1\nCustomKey: x\n\n[Metadata]\nTitle:a\nSource2:y\n\n"
+        );
+        // A missing section is added at the end instead of dropping its keys
+        assert_eq!(
+            extra("[General]\nMode: 0\n", &rows[1..2]),
+            "[General]\nMode: 0\n\n[Metadata]\nSource2:y\n\n"
+        );
+        assert_eq!(extra("[General]\nMode: 0\n", &rows[3..]), "[General]\nMode: 0\n");
+    }
+
+    #[test]
+    fn maps_without_combo_colours_get_no_colours_section() {
+        let mut dataset = test_rows::dataset(vec![test_rows::circle(0, 1000.0, 256, 192)]);
+        let content = BeatmapReconstructor::drop_empty_colours(&encode_dataset(&dataset));
+        assert!(!content.contains("[Colours]"), "{}", content);
+        let parsed = rosu_map::from_str::<Beatmap>(&content).unwrap();
+        assert!(parsed.custom_combo_colors.is_empty());
+
+        dataset.combo_colors = vec![ComboColorRow {
+            folder_id: test_rows::FOLDER.to_string(),
+            osu_file: test_rows::OSU_FILE.to_string(),
+            color_index: 0,
+            color_type: "combo".to_string(),
+            custom_name: None,
+            red: 255,
+            green: 0,
+            blue: 0,
+        }];
+        let content = BeatmapReconstructor::drop_empty_colours(&encode_dataset(&dataset));
+        assert!(content.contains("[Colours]"), "{}", content);
+        let parsed = rosu_map::from_str::<Beatmap>(&content).unwrap();
+        assert_eq!(parsed.custom_combo_colors, vec![Color::new(255, 0, 0, 255)]);
+    }
+
+    #[test]
+    fn every_kind_of_event_survives_a_reparse() {
+        let mut dataset = test_rows::dataset(vec![test_rows::circle(0, 1000.0, 256, 192)]);
+        dataset.beatmaps[0].background_file = "bg.jpg".to_string();
+        dataset.breaks = vec![BreakRow {
+            folder_id: test_rows::FOLDER.to_string(),
+            osu_file: test_rows::OSU_FILE.to_string(),
+            start_time: 3000.0,
+            end_time: 6000.0,
+        }];
+        let event = |event_index: i32, event_type: &str, params: &str| EventRow {
+            folder_id: test_rows::FOLDER.to_string(),
+            osu_file: test_rows::OSU_FILE.to_string(),
+            event_index,
+            event_type: event_type.to_string(),
+            start_time: params.split(',').next().and_then(|t| t.parse().ok()),
+            params: params.to_string(),
+        };
+        // In the order the builder reads them, which is not osu!'s
+        dataset.events = vec![
+            event(0, "3", "100,163,162,255"),
+            event(1, "5", "1500,0,\"hit.wav\",70"),
+            event(2, "Video", "-200,\"intro.avi\""),
+        ];
+
+        let content = BeatmapReconstructor::assemble_events(
+            &encode_dataset(&dataset),
+            test_rows::FOLDER,
+            test_rows::OSU_FILE,
+            &dataset.events,
+        );
+        let events: Vec<&str> =
+            section_lines(&content, "Events").into_iter().filter(|line| !line.starts_with("//")).collect();
+        assert_eq!(
+            events,
+            vec![
+                "0,0,\"bg.jpg\",0,0",
+                "Video,-200,\"intro.avi\"",
+                "2,3000,6000",
+                "5,1500,0,\"hit.wav\",70",
+                "3,100,163,162,255"
+            ]
+        );
+
+        let parsed = rosu_map::from_str::<Beatmap>(&content).unwrap();
+        assert_eq!(parsed.background_file, "bg.jpg");
+        assert_eq!(parsed.breaks.len(), 1);
+        assert_eq!((parsed.breaks[0].start_time, parsed.breaks[0].end_time), (3000.0, 6000.0));
+    }
+
+    #[test]
+    fn slider_lines_get_repeats_plus_one_slides() {
+        let repeats = [0, 1, 8, -1];
+        let mut dataset = test_rows::dataset(
+            repeats
+                .iter()
+                .enumerate()
+                .map(|(i, &repeat_count)| {
+                    test_rows::slider(i as i32, 1000.0 + 5000.0 * i as f64, 100, 100, "L", repeat_count + 1, 100.0)
+                })
+                .collect(),
+        );
+        for (i, &repeat_count) in repeats.iter().enumerate() {
+            dataset.slider_control_points.extend(test_rows::control_points(i as i32, &[(0.0, 0.0), (100.0, 0.0)]));
+            dataset.slider_data.push(test_rows::slider_data(i as i32, repeat_count, 100.0));
+        }
+
+        let content = encode_dataset(&dataset);
+        let slides: Vec<&str> = section_lines(&content, "HitObjects")
+            .iter()
+            .map(|line| line.split(',').nth(6).unwrap())
+            .collect();
+        // A negative repeat count is clamped to a single span
+        assert_eq!(slides, vec!["1", "2", "9", "1"]);
+
+        let parsed = rosu_map::from_str::<Beatmap>(&content).unwrap();
+        let parsed_repeats: Vec<i32> = parsed
+            .hit_objects
+            .iter()
+            .map(|ho| match &ho.kind {
+                HitObjectKind::Slider(slider) => slider.repeat_count,
+                _ => panic!("not a slider"),
+            })
+            .collect();
+        assert_eq!(parsed_repeats, vec![0, 1, 8, 0]);
+    }
+
+    #[test]
+    fn red_anchors_become_typed_points_except_at_segment_ends() {
+        let point = |x: f32, y: f32, path_type: Option<PathType>| PathControlPoint { pos: Pos { x, y }, path_type };
+        let positions_and_types = |points: Vec<PathControlPoint>| -> Vec<(f32, f32, Option<PathType>)> {
+            points.iter().map(|p| (p.pos.x, p.pos.y, p.path_type)).collect()
+        };
+
+        let bezier = vec![
+            point(0.0, 0.0, Some(PathType::BEZIER)),
+            point(50.0, 0.0, None),
+            point(100.0, 0.0, None),
+            point(100.0, 0.0, None),
+            point(150.0, 50.0, None),
+            point(150.0, 50.0, None),
+        ];
+        assert_eq!(
+            positions_and_types(merge_red_anchors(bezier)),
+            vec![
+                (0.0, 0.0, Some(PathType::BEZIER)),
+                (50.0, 0.0, None),
+                (100.0, 0.0, Some(PathType::BEZIER)),
+                (150.0, 50.0, None),
+                // A repeat as the last anchor ends no segment
+                (150.0, 50.0, None)
+            ]
+        );
+
+        // Catmull paths have no red anchors
+        let catmull = vec![
+            point(0.0, 0.0, Some(PathType::CATMULL)),
+            point(50.0, 0.0, None),
+            point(50.0, 0.0, None),
+            point(90.0, 9.0, None),
+        ];
+        assert_eq!(merge_red_anchors(catmull).len(), 4);
+    }
+
+    #[test]
+    fn red_anchors_are_written_back_byte_for_byte() {
+        let mut dataset = test_rows::dataset(vec![test_rows::slider(0, 1000.0, 100, 100, "B", 1, 200.0)]);
+        dataset.features.literal_slider_anchors = true;
+        dataset.slider_control_points =
+            test_rows::control_points(0, &[(0.0, 0.0), (50.0, 0.0), (100.0, 0.0), (100.0, 0.0), (150.0, 50.0)]);
+        dataset.slider_data = vec![test_rows::slider_data(0, 0, 200.0)];
+
+        let encoded = encode_dataset(&dataset);
+        let content = BeatmapReconstructor::set_slider_anchors(
+            &encoded,
+            test_rows::FOLDER,
+            test_rows::OSU_FILE,
+            &dataset.hit_objects,
+            &dataset.slider_control_points,
+        );
+        let line = section_lines(&content, "HitObjects")[0];
+        assert_eq!(line.split(',').nth(5), Some("B|150:100|200:100|200:100|250:150"), "{}", line);
+
+        // A typed anchor gets its letter in front of it
+        let mut typed = dataset.slider_control_points.clone();
+        typed[2].path_type = Some("Linear".to_string());
+        let points: Vec<&SliderControlPointRow> = typed.iter().collect();
+        assert_eq!(
+            slider_anchor_field((100.0, 100.0), &points, Some("B")).as_deref(),
+            Some("B|150:100|L|200:100|200:100|250:150")
+        );
+        assert_eq!(slider_anchor_field((100.0, 100.0), &points, None), None);
+    }
+
+    #[test]
+    fn use_skin_sprites_is_written_back_once() {
+        let row = BeatmapRow { use_skin_sprites: true, ..test_rows::beatmap(0) };
+        let encoded = encode(&row, &[], &[test_rows::timing(0.0, 500.0)], &DatasetFeatures::default());
+        let content = BeatmapReconstructor::set_skin_settings(&encoded, &row);
+
+        let general = section_lines(&content, "General");
+        assert_eq!(general.iter().filter(|line| line.starts_with("UseSkinSprites")).collect::<Vec<_>>(), vec![&"UseSkinSprites: 1"]);
+        // Defaults stay out of the section
+        assert!(!general.iter().any(|line| line.starts_with("OverlayPosition") || line.starts_with("AlwaysShowPlayfield")));
+        rosu_map::from_str::<Beatmap>(&content).unwrap();
+    }
+
+    #[test]
+    fn range_queries_include_the_start_and_exclude_the_end() {
+        let beatmap = reconstruct_dataset(&test_rows::dataset(vec![
+            test_rows::circle(0, 1000.0, 0, 0),
+            test_rows::circle(1, 2000.0, 0, 0),
+            test_rows::circle(2, 2000.0, 10, 0),
+            test_rows::spinner(3, 3000.0, 4000.0),
+        ]));
+        let starts = |start_ms: f64, end_ms: f64| -> Vec<f64> {
+            BeatmapReconstructor::objects_in_range(&beatmap, start_ms, end_ms)
+                .iter()
+                .map(|object| object.start_time)
+                .collect()
+        };
+
+        assert_eq!(starts(2000.0, 3000.0), vec![2000.0, 2000.0]);
+        assert_eq!(starts(0.0, 1000.0), Vec::<f64>::new());
+        assert_eq!(starts(999.0, 3000.5), vec![1000.0, 2000.0, 2000.0, 3000.0]);
+        // Empty, inverted and past-the-end ranges
+        assert!(starts(2000.0, 2000.0).is_empty());
+        assert!(starts(3000.0, 1000.0).is_empty());
+        assert!(starts(3500.0, 10_000.0).is_empty());
+
+        assert_eq!(BeatmapReconstructor::object_at_or_after(&beatmap, 0.0), Some(0));
+        assert_eq!(BeatmapReconstructor::object_at_or_after(&beatmap, 2000.0), Some(1));
+        assert_eq!(BeatmapReconstructor::object_at_or_after(&beatmap, 2000.5), Some(3));
+        assert_eq!(BeatmapReconstructor::object_at_or_after(&beatmap, 3000.5), None);
+    }
+
+    #[test]
+    fn missing_slider_lengths_are_recovered_from_velocity_and_duration() {
+        let encoded_length = |length: Option<f64>| {
+            let slider = HitObjectRow { length, duration: Some(1000.0), ..test_rows::slider(0, 1000.0, 100, 100, "L", 2, 0.0) };
+            let mut dataset = test_rows::dataset(vec![slider]);
+            dataset.slider_control_points = test_rows::control_points(0, &[(0.0, 0.0), (200.0, 0.0)]);
+            dataset.slider_data =
+                vec![SliderDataRow { velocity: 0.25, expected_dist: None, ..test_rows::slider_data(0, 1, 0.0) }];
+            let content = encode_dataset(&dataset);
+            let line = section_lines(&content, "HitObjects")[0].to_string();
+            line.split(',').nth(7).unwrap().to_string()
+        };
+
+        // 0.25 px/ms over two spans of 500 ms each
+        assert_eq!(encoded_length(None), "125");
+        // A stored hit_objects.length wins over the estimate
+        assert_eq!(encoded_length(Some(150.0)), "150");
+    }
+}
@@ -0,0 +1,110 @@
+//! CLI tool for comparing two parquet datasets
+
+use anyhow::Result;
+use clap::Parser;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use osu_reconstructor::{diff_datasets, DatasetManifest};
+
+/// Folders/values listed per section before the rest are summarized
+const MAX_LISTED: usize = 10;
+
+#[derive(Parser, Debug)]
+#[command(name = "dataset-diff")]
+#[command(about = "Compare two parquet datasets table by table")]
+struct Args {
+    /// First (baseline) dataset directory
+    #[arg(long)]
+    a: PathBuf,
+
+    /// Second dataset directory
+    #[arg(long)]
+    b: PathBuf,
+
+    /// Also compare every beatmap field for difficulties present in both
+    #[arg(long)]
+    deep: bool,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    println!("=== osu! Dataset Diff ===");
+    println!("A: {}", args.a.display());
+    println!("B: {}", args.b.display());
+
+    let diff = diff_datasets(&args.a, &args.b, args.deep)?;
+
+    println!(
+        "Schema version: {} -> {}",
+        manifest_field(&diff.manifest_a, |m| m.schema_version.to_string()),
+        manifest_field(&diff.manifest_b, |m| m.schema_version.to_string())
+    );
+    println!(
+        "Builder version: {} -> {}",
+        manifest_field(&diff.manifest_a, |m| m.builder_version.clone()),
+        manifest_field(&diff.manifest_b, |m| m.builder_version.clone())
+    );
+
+    for table in &diff.tables {
+        println!(
+            "\n{}: {} -> {} rows{}",
+            table.table,
+            row_count(table.rows_a),
+            row_count(table.rows_b),
+            if table.is_unchanged() { " (unchanged)" } else { "" }
+        );
+        print_folders("only in A", &table.only_in_a);
+        print_folders("only in B", &table.only_in_b);
+        if !table.count_changes.is_empty() {
+            println!("  {} folders with changed row counts:", table.count_changes.len());
+            for (folder_id, rows_a, rows_b) in table.count_changes.iter().take(MAX_LISTED) {
+                println!("    {}: {} -> {}", folder_id, rows_a, rows_b);
+            }
+            print_remaining(table.count_changes.len());
+        }
+    }
+
+    if args.deep {
+        println!("\nBeatmap field differences: {}", diff.beatmap_fields.len());
+        let mut by_field: BTreeMap<&str, Vec<_>> = BTreeMap::new();
+        for field_diff in &diff.beatmap_fields {
+            by_field.entry(field_diff.field.as_str()).or_default().push(field_diff);
+        }
+        for (field, diffs) in by_field {
+            println!("  {} ({} difficulties):", field, diffs.len());
+            for d in diffs.iter().take(MAX_LISTED) {
+                println!("    {}/{}: {:?} -> {:?}", d.folder_id, d.osu_file, d.a, d.b);
+            }
+            print_remaining(diffs.len());
+        }
+    }
+
+    Ok(())
+}
+
+fn manifest_field(manifest: &Option<DatasetManifest>, field: impl Fn(&DatasetManifest) -> String) -> String {
+    manifest.as_ref().map(field).unwrap_or_else(|| "none".to_string())
+}
+
+fn row_count(rows: Option<usize>) -> String {
+    rows.map(|r| r.to_string()).unwrap_or_else(|| "missing".to_string())
+}
+
+fn print_folders(label: &str, folders: &[String]) {
+    if folders.is_empty() {
+        return;
+    }
+    println!("  {} folders {}:", folders.len(), label);
+    for folder_id in folders.iter().take(MAX_LISTED) {
+        println!("    {}", folder_id);
+    }
+    print_remaining(folders.len());
+}
+
+fn print_remaining(total: usize) {
+    if total > MAX_LISTED {
+        println!("    ... and {} more", total - MAX_LISTED);
+    }
+}
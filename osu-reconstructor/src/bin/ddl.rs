@@ -0,0 +1,52 @@
+//! CLI tool for printing SQL DDL matching the dataset's tables
+
+use anyhow::{Context, Result};
+use clap::{Parser, ValueEnum};
+use std::fs;
+use std::path::PathBuf;
+
+use osu_reconstructor::ddl::{dataset_ddl, Dialect};
+use osu_reconstructor::ParquetReader;
+
+#[derive(Parser, Debug)]
+#[command(name = "ddl")]
+#[command(about = "Print CREATE TABLE statements for loading the dataset into a SQL warehouse")]
+struct Args {
+    /// Path to the dataset directory containing parquet files
+    #[arg(short, long)]
+    dataset: PathBuf,
+
+    /// SQL dialect of the column types
+    #[arg(long, value_enum, default_value_t = DialectArg::Duckdb)]
+    dialect: DialectArg,
+
+    /// Leave out the suggested primary and foreign keys
+    #[arg(long)]
+    no_keys: bool,
+
+    /// Write the statements to this file instead of stdout
+    #[arg(short, long)]
+    out: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum DialectArg {
+    Duckdb,
+    Postgres,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let reader = ParquetReader::new(&args.dataset);
+    let dialect = match args.dialect {
+        DialectArg::Duckdb => Dialect::DuckDb,
+        DialectArg::Postgres => Dialect::Postgres,
+    };
+
+    let ddl = dataset_ddl(&reader, dialect, !args.no_keys).context("Failed to generate DDL")?;
+    match &args.out {
+        Some(path) => fs::write(path, ddl).context(format!("Failed to write {}", path.display()))?,
+        None => print!("{}", ddl),
+    }
+    Ok(())
+}
@@ -0,0 +1,31 @@
+//! CLI tool for joining beatmaps with their enriched API data into one table
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use std::path::PathBuf;
+
+use osu_reconstructor::merge::{merge_enriched, MERGED_TABLE};
+use osu_reconstructor::ParquetReader;
+
+#[derive(Parser, Debug)]
+#[command(name = "merge-enriched")]
+#[command(about = "Join beatmaps with beatmap_enriched into beatmaps_enriched_full.parquet")]
+struct Args {
+    /// Path to the dataset directory containing parquet files
+    #[arg(short, long)]
+    dataset: PathBuf,
+
+    /// Output file (default: beatmaps_enriched_full.parquet in the dataset directory)
+    #[arg(short, long)]
+    out: Option<PathBuf>,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let reader = ParquetReader::new(&args.dataset);
+    let out = args.out.unwrap_or_else(|| args.dataset.join(format!("{}.parquet", MERGED_TABLE)));
+
+    let rows = merge_enriched(&reader, &out).context("Failed to merge enriched data")?;
+    println!("Wrote {} rows to {}", rows, out.display());
+    Ok(())
+}
@@ -3,10 +3,15 @@
 use anyhow::{Context, Result};
 use clap::Parser;
 use rayon::prelude::*;
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
 
-use osu_reconstructor::{ParquetReader, FolderReconstructor};
+use osu_batch::say;
+use osu_batch::summary::{self, RunSummary};
+use osu_reconstructor::fidelity::{self, FIDELITY_FILE};
+use osu_reconstructor::{FidelityWeights, ParquetReader, FolderReconstructor};
 
 #[derive(Parser, Debug)]
 #[command(name = "reconstruct")]
@@ -28,6 +33,10 @@ struct Args {
     #[arg(short, long)]
     folder_id: Option<String>,
 
+    /// File with folder IDs to reconstruct, one per line (`sample` output; a tab and anything after it is ignored)
+    #[arg(long, value_name = "PATH", conflicts_with = "folder_id")]
+    folders_file: Option<PathBuf>,
+
     /// Limit number of folders to reconstruct (for testing)
     #[arg(long)]
     limit: Option<usize>,
@@ -35,16 +44,41 @@ struct Args {
     /// Number of parallel threads (default: 1 for low memory, increase for speed)
     #[arg(short = 't', long, default_value = "1")]
     threads: usize,
+
+    /// Check every table file against the manifest's sizes and blake3 hashes before reconstructing
+    #[arg(long)]
+    verify: bool,
+
+    /// No per-folder messages; print one JSON summary line at the end
+    #[arg(short, long)]
+    quiet: bool,
+
+    /// Folder of the original extracted beatmaps; score each reconstruction against it into fidelity.parquet
+    #[arg(long, value_name = "DIR")]
+    fidelity_source: Option<PathBuf>,
+
+    /// Weight of matching hit objects in the fidelity score
+    #[arg(long, default_value_t = FidelityWeights::default().hit_objects)]
+    weight_hit_objects: f64,
+
+    /// Weight of matching metadata fields in the fidelity score
+    #[arg(long, default_value_t = FidelityWeights::default().metadata)]
+    weight_metadata: f64,
+
+    /// Weight of matching timing points in the fidelity score
+    #[arg(long, default_value_t = FidelityWeights::default().timing)]
+    weight_timing: f64,
 }
 
 fn main() -> Result<()> {
     let args = Args::parse();
+    summary::set_quiet(args.quiet);
 
-    println!("=== osu! Beatmap Reconstructor ===");
-    println!("Dataset: {}", args.dataset.display());
-    println!("Assets: {}", args.assets.display());
-    println!("Output: {}", args.output.display());
-    println!("Threads: {}", args.threads);
+    say!("=== osu! Beatmap Reconstructor ===");
+    say!("Dataset: {}", args.dataset.display());
+    say!("Assets: {}", args.assets.display());
+    say!("Output: {}", args.output.display());
+    say!("Threads: {}", args.threads);
 
     // Configure thread pool
     rayon::ThreadPoolBuilder::new()
@@ -55,13 +89,46 @@ fn main() -> Result<()> {
     let reader = ParquetReader::new(&args.dataset);
     let reconstructor = FolderReconstructor::new(&args.assets);
 
+    if args.verify {
+        let verified = reader.verify_integrity().context("Dataset integrity check failed")?;
+        say!("Verified {} table files against manifest.json", verified);
+    }
+
+    // Report the fidelity ceiling of this dataset up front
+    let features = reader.detect_features().context("Failed to inspect dataset")?;
+    match features.schema_version {
+        Some(version) => say!("Schema version: {}", version),
+        None => say!("Schema version: unknown (no manifest.json)"),
+    }
+    let missing = features.missing();
+    if !missing.is_empty() {
+        say!("⚠ Dataset lacks the following; reconstructed .osu files will omit them:");
+        for field in missing {
+            say!("    - {}", field);
+        }
+    }
+
     // Determine folder IDs to process
     let folder_ids: Vec<String> = if let Some(ref id) = args.folder_id {
         vec![id.clone()]
+    } else if let Some(ref path) = args.folders_file {
+        let content = std::fs::read_to_string(path).context(format!("Failed to read {}", path.display()))?;
+        let mut ids: Vec<String> = content
+            .lines()
+            .filter_map(|line| line.split('\t').next())
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map(str::to_string)
+            .collect();
+        ids.dedup();
+        if let Some(limit) = args.limit {
+            ids.truncate(limit);
+        }
+        ids
     } else {
-        println!("\nLoading folder IDs...");
+        say!("\nLoading folder IDs...");
         let mut ids = reader.load_folder_ids().context("Failed to load folder IDs")?;
-        println!("Found {} folders", ids.len());
+        say!("Found {} folders", ids.len());
         if let Some(limit) = args.limit {
             ids.truncate(limit);
         }
@@ -69,10 +136,19 @@ fn main() -> Result<()> {
     };
 
     let total = folder_ids.len();
-    println!("\nReconstructing {} folder(s)...", total);
+    say!("\nReconstructing {} folder(s)...", total);
 
     let success = AtomicUsize::new(0);
     let failed = AtomicUsize::new(0);
+    let osu_files = AtomicUsize::new(0);
+    let storyboard_elements = AtomicUsize::new(0);
+    let assets = AtomicUsize::new(0);
+    let weights = FidelityWeights {
+        hit_objects: args.weight_hit_objects,
+        metadata: args.weight_metadata,
+        timing: args.weight_timing,
+    };
+    let scores = Mutex::new(Vec::new());
 
     folder_ids.par_iter().for_each(|folder_id| {
         // Each thread creates its own reader for parallel file access
@@ -90,13 +166,37 @@ fn main() -> Result<()> {
         match reconstructor.reconstruct_folder(folder_id, &args.output, &dataset) {
             Ok(result) => {
                 let s = success.fetch_add(1, Ordering::Relaxed) + 1;
-                println!(
+                osu_files.fetch_add(result.osu_files.len(), Ordering::Relaxed);
+                storyboard_elements.fetch_add(result.storyboard_elements, Ordering::Relaxed);
+                assets.fetch_add(result.assets_copied, Ordering::Relaxed);
+                say!(
                     "  [{}/{}] ✓ {}: {} .osu files, {} storyboard elements, {} assets",
                     s, total, folder_id,
                     result.osu_files.len(),
                     result.storyboard_elements,
                     result.assets_copied
                 );
+
+                for osu_file in &result.skipped_too_large {
+                    eprintln!(
+                        "  ⚠ {}/{}: not written, its hit objects were skipped by the builder (--max-objects)",
+                        folder_id, osu_file
+                    );
+                }
+
+                if let Some(source) = &args.fidelity_source {
+                    for osu_file in &result.osu_files {
+                        let original = source.join(folder_id).join(osu_file);
+                        let reconstructed = result.output_path.join(osu_file);
+                        match fidelity::score_file(folder_id, osu_file, &original, &reconstructed, &weights) {
+                            Ok(score) => scores.lock().unwrap().push(score),
+                            Err(e) => eprintln!(
+                                "  ⚠ {}/{}: no fidelity score: {:#}",
+                                folder_id, osu_file, anyhow::Error::from(e)
+                            ),
+                        }
+                    }
+                }
             }
             Err(e) => {
                 failed.fetch_add(1, Ordering::Relaxed);
@@ -106,9 +206,37 @@ fn main() -> Result<()> {
         // dataset is dropped here, freeing memory
     });
 
-    println!("\n=== Summary ===");
-    println!("Reconstructed: {}", success.load(Ordering::Relaxed));
-    println!("Failed: {}", failed.load(Ordering::Relaxed));
-
-    Ok(())
+    say!("\n=== Summary ===");
+    say!("Reconstructed: {}", success.load(Ordering::Relaxed));
+    say!("Failed: {}", failed.load(Ordering::Relaxed));
+
+    let mut scores = scores.into_inner().unwrap();
+    if args.fidelity_source.is_some() {
+        scores.sort_by(|a, b| a.score.total_cmp(&b.score));
+        let path = args.output.join(FIDELITY_FILE);
+        fidelity::write_fidelity_parquet(&path, &scores)?;
+        let mean = scores.iter().map(|s| s.score).sum::<f64>() / scores.len().max(1) as f64;
+        say!("Fidelity: {} difficulties scored, mean {:.3} -> {}", scores.len(), mean, path.display());
+        for worst in scores.iter().take(5).filter(|s| s.score < 1.0) {
+            say!("    {:.3}  {}/{}", worst.score, worst.folder_id, worst.osu_file);
+        }
+    }
+
+    let succeeded = success.load(Ordering::Relaxed);
+    let failed = failed.load(Ordering::Relaxed);
+    let mut summary = RunSummary {
+        processed: succeeded + failed,
+        succeeded,
+        failed,
+        rows: BTreeMap::from([
+            ("osu_files".to_string(), osu_files.load(Ordering::Relaxed)),
+            ("storyboard_elements".to_string(), storyboard_elements.load(Ordering::Relaxed)),
+            ("assets".to_string(), assets.load(Ordering::Relaxed)),
+        ]),
+        interrupted: false,
+    };
+    if args.fidelity_source.is_some() {
+        summary.rows.insert("fidelity".to_string(), scores.len());
+    }
+    summary.print()
 }
@@ -0,0 +1,130 @@
+//! CLI tool for drawing a star-rating-balanced subset of a dataset
+
+use anyhow::{Context, Result};
+use clap::{Parser, ValueEnum};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::PathBuf;
+
+use osu_reconstructor::sample::{stratified_sample, Allocation};
+use osu_reconstructor::ParquetReader;
+
+#[derive(Parser, Debug)]
+#[command(name = "sample")]
+#[command(about = "Sample difficulties evenly across star-rating bins")]
+struct Args {
+    /// Path to the dataset directory containing parquet files
+    #[arg(short, long)]
+    dataset: PathBuf,
+
+    /// Number of difficulties to select
+    #[arg(short, long)]
+    n: usize,
+
+    /// Value the bins are drawn over
+    #[arg(long, value_enum, default_value_t = Strata::Stars)]
+    strata: Strata,
+
+    /// Number of equal-width bins between the lowest and highest value
+    #[arg(long, default_value = "10")]
+    bins: usize,
+
+    /// Split --n evenly between bins, or in proportion to their size
+    #[arg(long, value_enum, default_value_t = AllocationArg::Uniform)]
+    allocation: AllocationArg,
+
+    /// Seed for the draw (random if not set; the seed used is printed)
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// What to write per line: `folder_id<TAB>osu_file`, unique folder IDs, or beatmap IDs
+    #[arg(long, value_enum, default_value_t = Emit::Pairs)]
+    emit: Emit,
+
+    /// Output file, one selected entry per line
+    #[arg(short, long)]
+    out: PathBuf,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Strata {
+    /// Star rating from beatmap_enriched (stars_calc, else stars_api) or beatmaps.stars_calc
+    Stars,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum AllocationArg {
+    Uniform,
+    Proportional,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Emit {
+    /// `folder_id<TAB>osu_file`
+    Pairs,
+    /// Unique folder IDs, for `reconstruct --folders-file`
+    Folders,
+    /// Beatmap IDs, for `osu-enricher --ids-file`
+    BeatmapIds,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let reader = ParquetReader::new(&args.dataset);
+
+    let maps = match args.strata {
+        Strata::Stars => reader.load_star_ratings().context("Failed to load star ratings")?,
+    };
+
+    let seed = args.seed.unwrap_or_else(|| rand::rng().random());
+    let mut rng = StdRng::seed_from_u64(seed);
+    let allocation = match args.allocation {
+        AllocationArg::Uniform => Allocation::Uniform,
+        AllocationArg::Proportional => Allocation::Proportional,
+    };
+    let sample = stratified_sample(&maps, args.n, args.bins, allocation, &mut rng);
+
+    if sample.unrated > 0 {
+        eprintln!(
+            "⚠ {} of {} difficulties have no star rating and were skipped (run osu-enricher or build with --compute-stars)",
+            sample.unrated,
+            maps.len()
+        );
+    }
+    eprintln!("Seed: {}", seed);
+    eprintln!("{:>15}  {:>9}  {:>8}", "stars", "available", "selected");
+    for bin in &sample.bins {
+        eprintln!(
+            "{:>6.2} - {:>6.2}  {:>9}  {:>8}",
+            bin.low, bin.high, bin.available, bin.selected
+        );
+    }
+
+    let lines: Vec<String> = match args.emit {
+        Emit::Pairs => sample
+            .selected
+            .iter()
+            .map(|map| format!("{}\t{}", map.folder_id, map.osu_file))
+            .collect(),
+        Emit::Folders => sample
+            .selected
+            .iter()
+            .map(|map| map.folder_id.clone())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect(),
+        Emit::BeatmapIds => sample
+            .selected
+            .iter()
+            .filter(|map| map.beatmap_id > 0)
+            .map(|map| map.beatmap_id.to_string())
+            .collect(),
+    };
+    let content: String = lines.iter().map(|line| format!("{}\n", line)).collect();
+    fs::write(&args.out, content).context(format!("Failed to write {}", args.out.display()))?;
+    eprintln!("Selected {} difficulties, wrote {} lines to {}", sample.selected.len(), lines.len(), args.out.display());
+
+    Ok(())
+}
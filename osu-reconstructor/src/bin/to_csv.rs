@@ -0,0 +1,60 @@
+//! CLI tool for exporting dataset tables as CSV
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use std::path::PathBuf;
+
+use osu_reconstructor::ParquetReader;
+
+#[derive(Parser, Debug)]
+#[command(name = "to-csv")]
+#[command(about = "Export dataset tables as CSV for inspection in a spreadsheet")]
+struct Args {
+    /// Path to the dataset directory containing parquet files
+    #[arg(short, long)]
+    dataset: PathBuf,
+
+    /// Table to export (file stem, e.g. beatmaps); without it, --folder exports every table
+    #[arg(short, long, required_unless_present = "folder")]
+    table: Option<String>,
+
+    /// Only export rows of this folder ID
+    #[arg(short, long)]
+    folder: Option<String>,
+
+    /// Output CSV file with --table, otherwise a directory receiving one <table>.csv per table
+    #[arg(short, long)]
+    out: PathBuf,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let reader = ParquetReader::new(&args.dataset);
+    let folder = args.folder.as_deref();
+
+    if let Some(table) = &args.table {
+        let rows = reader.write_table_csv(table, folder, &args.out)?;
+        eprintln!("Wrote {} rows to {}", rows, args.out.display());
+        return Ok(());
+    }
+
+    std::fs::create_dir_all(&args.out).context(format!("Failed to create {}", args.out.display()))?;
+    for table in reader.available_tables()? {
+        if !reader.has_column(&table, "folder_id")? {
+            eprintln!("{}: skipped (no folder_id column)", table);
+            continue;
+        }
+        let out = args.out.join(format!("{}.csv", table));
+        match reader.write_table_csv(&table, folder, &out) {
+            Ok(rows) => eprintln!("{}: {} rows", table, rows),
+            // e.g. unreadable or truncated files
+            Err(e) => {
+                let _ = std::fs::remove_file(&out);
+                eprintln!("{}: skipped ({:#})", table, e);
+            }
+        }
+    }
+    eprintln!("Wrote CSVs to {}", args.out.display());
+
+    Ok(())
+}
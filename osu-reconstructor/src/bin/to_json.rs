@@ -0,0 +1,49 @@
+//! CLI tool for exporting one difficulty from the parquet dataset as JSON
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use std::path::PathBuf;
+
+use osu_reconstructor::ParquetReader;
+
+#[derive(Parser, Debug)]
+#[command(name = "to-json")]
+#[command(about = "Export one difficulty from the parquet dataset as JSON")]
+struct Args {
+    /// Path to the dataset directory containing parquet files
+    #[arg(short, long)]
+    dataset: PathBuf,
+
+    /// Folder ID of the beatmapset
+    #[arg(short, long)]
+    folder: String,
+
+    /// .osu file name of the difficulty
+    #[arg(long)]
+    osu: String,
+
+    /// Write to this file instead of stdout
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    let reader = ParquetReader::new(&args.dataset);
+    let dataset = reader
+        .load_dataset_for_folder(&args.folder)
+        .context(format!("Failed to load folder {}", args.folder))?;
+    let beatmap = dataset.beatmap_json(&args.folder, &args.osu)?;
+    let json = serde_json::to_string_pretty(&beatmap)?;
+
+    match &args.output {
+        Some(path) => {
+            std::fs::write(path, json).context(format!("Failed to write {}", path.display()))?;
+            eprintln!("Wrote {}", path.display());
+        }
+        None => println!("{}", json),
+    }
+
+    Ok(())
+}
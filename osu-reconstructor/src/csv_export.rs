@@ -0,0 +1,82 @@
+//! Streaming CSV export of dataset tables for inspecting values by hand
+
+use arrow::array::{Array, ArrayRef, RecordBatch, StringArray};
+use arrow::compute::filter_record_batch;
+use arrow::csv::WriterBuilder;
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::util::display::array_value_to_string;
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::error::{IoContext, ReconstructError, Result};
+use crate::reader::{create_string_eq_filter, open_batch_reader};
+use crate::ParquetReader;
+
+impl ParquetReader {
+    /// Stream one table to a CSV file, optionally keeping only one folder's rows
+    ///
+    /// Null values become empty fields and list columns (such as `tags_list`) are
+    /// written as their display string. Returns the number of rows written.
+    pub fn write_table_csv(&self, table: &str, folder_id: Option<&str>, out: &Path) -> Result<usize> {
+        let path = self.table_path(table);
+        let reader = open_batch_reader(&path)?;
+        if folder_id.is_some() && reader.schema().column_with_name("folder_id").is_none() {
+            return Err(ReconstructError::MissingColumn(format!("folder_id in {}", path.display())));
+        }
+
+        let file = File::create(out).io_context(format!("Failed to create {}", out.display()))?;
+        let mut writer = WriterBuilder::new().with_header(true).build(file);
+
+        let mut rows = 0;
+        for batch in reader {
+            let batch = batch?;
+            let batch = match folder_id {
+                Some(folder_id) => {
+                    let column = batch
+                        .column_by_name("folder_id")
+                        .ok_or_else(|| ReconstructError::MissingColumn("folder_id".to_string()))?;
+                    let mask = create_string_eq_filter(column.as_ref(), "folder_id", folder_id)?;
+                    filter_record_batch(&batch, &mask)?
+                }
+                None => batch,
+            };
+            // The header comes with the first batch, even an empty one
+            rows += batch.num_rows();
+            writer.write(&stringify_nested_columns(&batch)?)?;
+        }
+
+        Ok(rows)
+    }
+}
+
+/// Replace nested columns, which CSV can't represent, with their display strings
+fn stringify_nested_columns(batch: &RecordBatch) -> Result<RecordBatch> {
+    let schema = batch.schema();
+    if !schema.fields().iter().any(|field| field.data_type().is_nested()) {
+        return Ok(batch.clone());
+    }
+
+    let mut fields = Vec::with_capacity(schema.fields().len());
+    let mut columns: Vec<ArrayRef> = Vec::with_capacity(schema.fields().len());
+    for (field, column) in schema.fields().iter().zip(batch.columns()) {
+        if !field.data_type().is_nested() {
+            fields.push(field.as_ref().clone());
+            columns.push(column.clone());
+            continue;
+        }
+        let values = (0..column.len())
+            .map(|i| {
+                if column.is_null(i) {
+                    Ok(None)
+                } else {
+                    array_value_to_string(column, i).map(Some)
+                }
+            })
+            .collect::<Result<StringArray, _>>()?;
+        fields.push(Field::new(field.name(), DataType::Utf8, true));
+        columns.push(Arc::new(values));
+    }
+
+    Ok(RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)?)
+}
@@ -0,0 +1,228 @@
+//! SQL DDL for loading a dataset into a warehouse
+//!
+//! Each `CREATE TABLE` is generated from the Arrow schema stored in the table
+//! file itself (the schema the builder or enricher wrote it with), so the DDL
+//! always matches the data being loaded. Keys are suggestions from the known
+//! table layout and are only emitted when every key column is present and
+//! non-nullable.
+
+use arrow::datatypes::{DataType, Schema};
+use std::fmt::Write as _;
+
+use crate::error::{ReconstructError, Result};
+use crate::reader::ParquetReader;
+
+/// SQL flavour of the generated statements
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dialect {
+    DuckDb,
+    Postgres,
+}
+
+/// Suggested keys of one table
+struct TableKeys {
+    table: &'static str,
+    primary_key: &'static [&'static str],
+    /// Local columns, referenced table and its columns
+    foreign_key: Option<(&'static [&'static str], &'static str, &'static [&'static str])>,
+}
+
+const BEATMAP_KEY: &[&str] = &["folder_id", "osu_file"];
+const HIT_OBJECT_KEY: &[&str] = &["folder_id", "osu_file", "index"];
+const HIT_OBJECT_REF: &[&str] = &["folder_id", "osu_file", "hit_object_index"];
+const ELEMENT_KEY: &[&str] = &["folder_id", "source_file", "element_index"];
+
+/// Referenced tables come first so foreign keys can be created in order
+const TABLE_KEYS: &[TableKeys] = &[
+    TableKeys { table: "beatmaps", primary_key: BEATMAP_KEY, foreign_key: None },
+    TableKeys {
+        table: "hit_objects",
+        primary_key: HIT_OBJECT_KEY,
+        foreign_key: Some((BEATMAP_KEY, "beatmaps", BEATMAP_KEY)),
+    },
+    TableKeys { table: "storyboard_elements", primary_key: ELEMENT_KEY, foreign_key: None },
+    TableKeys { table: "beatmap_enriched", primary_key: &["beatmap_id"], foreign_key: None },
+    TableKeys {
+        table: "slider_control_points",
+        primary_key: &["folder_id", "osu_file", "hit_object_index", "point_index"],
+        foreign_key: Some((HIT_OBJECT_REF, "hit_objects", HIT_OBJECT_KEY)),
+    },
+    TableKeys {
+        table: "slider_data",
+        primary_key: HIT_OBJECT_REF,
+        foreign_key: Some((HIT_OBJECT_REF, "hit_objects", HIT_OBJECT_KEY)),
+    },
+    TableKeys {
+        table: "hit_samples",
+        primary_key: &[],
+        foreign_key: Some((HIT_OBJECT_REF, "hit_objects", HIT_OBJECT_KEY)),
+    },
+    TableKeys {
+        table: "timing_points",
+        primary_key: &["folder_id", "osu_file", "order_index"],
+        foreign_key: Some((BEATMAP_KEY, "beatmaps", BEATMAP_KEY)),
+    },
+    TableKeys {
+        table: "breaks",
+        primary_key: &[],
+        foreign_key: Some((BEATMAP_KEY, "beatmaps", BEATMAP_KEY)),
+    },
+    TableKeys {
+        table: "events",
+        primary_key: &["folder_id", "osu_file", "event_index"],
+        foreign_key: Some((BEATMAP_KEY, "beatmaps", BEATMAP_KEY)),
+    },
+    TableKeys {
+        table: "extra_fields",
+        primary_key: &["folder_id", "osu_file", "section", "field_index"],
+        foreign_key: Some((BEATMAP_KEY, "beatmaps", BEATMAP_KEY)),
+    },
+    TableKeys {
+        table: "custom_features",
+        primary_key: &["folder_id", "osu_file", "name"],
+        foreign_key: Some((BEATMAP_KEY, "beatmaps", BEATMAP_KEY)),
+    },
+    TableKeys {
+        table: "combo_colors",
+        primary_key: &["folder_id", "osu_file", "color_type", "color_index"],
+        foreign_key: Some((BEATMAP_KEY, "beatmaps", BEATMAP_KEY)),
+    },
+    TableKeys {
+        table: "storyboard_commands",
+        primary_key: &[],
+        foreign_key: Some((ELEMENT_KEY, "storyboard_elements", ELEMENT_KEY)),
+    },
+    TableKeys {
+        table: "storyboard_loops",
+        primary_key: &["folder_id", "source_file", "element_index", "loop_index"],
+        foreign_key: Some((ELEMENT_KEY, "storyboard_elements", ELEMENT_KEY)),
+    },
+    TableKeys {
+        table: "storyboard_triggers",
+        primary_key: &["folder_id", "source_file", "element_index", "trigger_index"],
+        foreign_key: Some((ELEMENT_KEY, "storyboard_elements", ELEMENT_KEY)),
+    },
+    TableKeys { table: "assets", primary_key: &["folder_id", "original_path"], foreign_key: None },
+    TableKeys {
+        table: "beatmap_mod_settings",
+        primary_key: &["beatmap_id", "mods"],
+        foreign_key: Some((&["beatmap_id"], "beatmap_enriched", &["beatmap_id"])),
+    },
+    TableKeys { table: "beatmap_comments", primary_key: &["comment_id"], foreign_key: None },
+];
+
+/// `CREATE TABLE` statements for every table in the dataset
+///
+/// Known tables are ordered so referenced tables come first; tables the key list
+/// doesn't know (e.g. from newer builders) follow without keys.
+pub fn dataset_ddl(reader: &ParquetReader, dialect: Dialect, with_keys: bool) -> Result<String> {
+    let available = reader.available_tables()?;
+    let mut ordered: Vec<&str> = TABLE_KEYS
+        .iter()
+        .map(|keys| keys.table)
+        .filter(|table| available.iter().any(|name| name == table))
+        .collect();
+    ordered.extend(
+        available
+            .iter()
+            .map(String::as_str)
+            .filter(|name| !TABLE_KEYS.iter().any(|keys| keys.table == *name)),
+    );
+
+    let mut out = String::new();
+    let mut keyed: Vec<&str> = Vec::new();
+    for table in ordered {
+        let schema = reader.table_schema(table)?;
+        let keys = TABLE_KEYS.iter().find(|keys| keys.table == table).filter(|_| with_keys);
+        out.push_str(&create_table(table, &schema, dialect, keys, &keyed)?);
+        out.push('\n');
+        if keys.is_some_and(|keys| usable_key(&schema, keys.primary_key)) {
+            keyed.push(table);
+        }
+    }
+    Ok(out)
+}
+
+/// Key columns must exist and be non-nullable to be a primary key
+fn usable_key(schema: &Schema, columns: &[&str]) -> bool {
+    !columns.is_empty()
+        && columns
+            .iter()
+            .all(|name| schema.field_with_name(name).is_ok_and(|field| !field.is_nullable()))
+}
+
+fn create_table(
+    table: &str,
+    schema: &Schema,
+    dialect: Dialect,
+    keys: Option<&TableKeys>,
+    keyed: &[&str],
+) -> Result<String> {
+    let mut lines = Vec::with_capacity(schema.fields().len() + 2);
+    for field in schema.fields() {
+        let null = if field.is_nullable() { "" } else { " NOT NULL" };
+        lines.push(format!("    {} {}{}", quote(field.name()), sql_type(field.data_type(), dialect)?, null));
+    }
+
+    if let Some(keys) = keys {
+        if usable_key(schema, keys.primary_key) {
+            lines.push(format!("    PRIMARY KEY ({})", quote_list(keys.primary_key)));
+        }
+        if let Some((columns, target, target_columns)) = keys.foreign_key {
+            let has_columns = columns.iter().all(|name| schema.field_with_name(name).is_ok());
+            if has_columns && keyed.contains(&target) {
+                lines.push(format!(
+                    "    FOREIGN KEY ({}) REFERENCES {} ({})",
+                    quote_list(columns),
+                    quote(target),
+                    quote_list(target_columns)
+                ));
+            }
+        }
+    }
+
+    let mut out = String::new();
+    let _ = writeln!(out, "CREATE TABLE {} (", quote(table));
+    out.push_str(&lines.join(",\n"));
+    out.push_str("\n);\n");
+    Ok(out)
+}
+
+/// Column type for an Arrow type; unsigned types widen to the next signed type in Postgres
+fn sql_type(data_type: &DataType, dialect: Dialect) -> Result<String> {
+    let postgres = dialect == Dialect::Postgres;
+    Ok(match data_type {
+        DataType::Boolean => "BOOLEAN".to_string(),
+        DataType::Int8 | DataType::Int16 => "SMALLINT".to_string(),
+        DataType::Int32 => "INTEGER".to_string(),
+        DataType::Int64 => "BIGINT".to_string(),
+        DataType::UInt8 if postgres => "SMALLINT".to_string(),
+        DataType::UInt16 if postgres => "INTEGER".to_string(),
+        DataType::UInt32 if postgres => "BIGINT".to_string(),
+        DataType::UInt64 if postgres => "NUMERIC(20)".to_string(),
+        DataType::UInt8 => "UTINYINT".to_string(),
+        DataType::UInt16 => "USMALLINT".to_string(),
+        DataType::UInt32 => "UINTEGER".to_string(),
+        DataType::UInt64 => "UBIGINT".to_string(),
+        DataType::Float32 => "REAL".to_string(),
+        DataType::Float64 if postgres => "DOUBLE PRECISION".to_string(),
+        DataType::Float64 => "DOUBLE".to_string(),
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View if postgres => "TEXT".to_string(),
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View => "VARCHAR".to_string(),
+        DataType::Binary | DataType::LargeBinary if postgres => "BYTEA".to_string(),
+        DataType::Binary | DataType::LargeBinary => "BLOB".to_string(),
+        DataType::Date32 | DataType::Date64 => "DATE".to_string(),
+        DataType::Timestamp(_, None) => "TIMESTAMP".to_string(),
+        DataType::Timestamp(_, Some(_)) => "TIMESTAMPTZ".to_string(),
+        DataType::List(item) | DataType::LargeList(item) => format!("{}[]", sql_type(item.data_type(), dialect)?),
+        other => return Err(ReconstructError::ParseError(format!("No SQL type for Arrow type {}", other))),
+    })
+}
+
+fn quote(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+fn quote_list(identifiers: &[&str]) -> String {
+    identifiers.iter().map(|name| quote(name)).collect::<Vec<_>>().join(", ")
+}
@@ -0,0 +1,376 @@
+//! Comparison of two datasets
+//!
+//! Used to check that a builder change only affects what it was meant to:
+//! per table, which folders appear in only one dataset and which folders'
+//! row counts changed, plus (optionally) per-field beatmap differences.
+
+use arrow::datatypes::DataType;
+use arrow::util::display::array_value_to_string;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::ProjectionMask;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::path::Path;
+
+use crate::error::{IoContext, ReconstructError, Result};
+use crate::manifest::DatasetManifest;
+
+/// Row-level comparison of one parquet table
+#[derive(Debug, Clone, Default)]
+pub struct TableDiff {
+    /// Parquet file stem
+    pub table: String,
+    /// Total rows in each dataset (None if the file is missing there)
+    pub rows_a: Option<usize>,
+    pub rows_b: Option<usize>,
+    /// Folders with rows only in A / only in B
+    pub only_in_a: Vec<String>,
+    pub only_in_b: Vec<String>,
+    /// Folders present in both whose row counts differ: (folder_id, rows in A, rows in B)
+    pub count_changes: Vec<(String, usize, usize)>,
+}
+
+impl TableDiff {
+    pub fn is_unchanged(&self) -> bool {
+        self.rows_a == self.rows_b
+            && self.only_in_a.is_empty()
+            && self.only_in_b.is_empty()
+            && self.count_changes.is_empty()
+    }
+}
+
+/// One differing beatmap value
+#[derive(Debug, Clone)]
+pub struct FieldDiff {
+    pub folder_id: String,
+    pub osu_file: String,
+    pub field: String,
+    pub a: String,
+    pub b: String,
+}
+
+/// Result of comparing two datasets
+#[derive(Debug)]
+pub struct DatasetDiff {
+    pub manifest_a: Option<DatasetManifest>,
+    pub manifest_b: Option<DatasetManifest>,
+    pub tables: Vec<TableDiff>,
+    /// Per-field beatmap differences for difficulties in both datasets (only with `deep`)
+    pub beatmap_fields: Vec<FieldDiff>,
+}
+
+/// Compare two dataset directories
+///
+/// Every parquet file in either directory is compared by per-folder row counts;
+/// with `deep`, beatmap rows present in both are compared column by column.
+pub fn diff_datasets(a: &Path, b: &Path, deep: bool) -> Result<DatasetDiff> {
+    let mut tables = Vec::new();
+    for table in table_names(a)?.union(&table_names(b)?) {
+        tables.push(diff_table(a, b, table)?);
+    }
+
+    let beatmap_fields = if deep && a.join("beatmaps.parquet").exists() && b.join("beatmaps.parquet").exists() {
+        diff_beatmap_fields(a, b)?
+    } else {
+        Vec::new()
+    };
+
+    Ok(DatasetDiff {
+        manifest_a: DatasetManifest::load(a)?,
+        manifest_b: DatasetManifest::load(b)?,
+        tables,
+        beatmap_fields,
+    })
+}
+
+/// Parquet file stems in a dataset directory
+fn table_names(dir: &Path) -> Result<BTreeSet<String>> {
+    let mut names = BTreeSet::new();
+    for entry in std::fs::read_dir(dir).io_context(format!("Failed to read {}", dir.display()))? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "parquet") {
+            if let Some(stem) = path.file_stem() {
+                names.insert(stem.to_string_lossy().to_string());
+            }
+        }
+    }
+    Ok(names)
+}
+
+fn diff_table(a: &Path, b: &Path, table: &str) -> Result<TableDiff> {
+    let file_name = format!("{}.parquet", table);
+    let counts_a = folder_row_counts(&a.join(&file_name))?;
+    let counts_b = folder_row_counts(&b.join(&file_name))?;
+
+    let mut diff = TableDiff {
+        table: table.to_string(),
+        rows_a: counts_a.as_ref().map(|c| c.values().sum()),
+        rows_b: counts_b.as_ref().map(|c| c.values().sum()),
+        ..Default::default()
+    };
+    let (Some(counts_a), Some(counts_b)) = (counts_a, counts_b) else {
+        return Ok(diff);
+    };
+
+    // Both maps are sorted by folder_id, so walk them in lockstep
+    let mut iter_a = counts_a.into_iter().peekable();
+    let mut iter_b = counts_b.into_iter().peekable();
+    loop {
+        let order = match (iter_a.peek(), iter_b.peek()) {
+            (Some((folder_a, _)), Some((folder_b, _))) => folder_a.cmp(folder_b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => break,
+        };
+        match order {
+            Ordering::Less => diff.only_in_a.extend(iter_a.next().map(|(folder, _)| folder)),
+            Ordering::Greater => diff.only_in_b.extend(iter_b.next().map(|(folder, _)| folder)),
+            Ordering::Equal => {
+                let (folder, rows_a) = iter_a.next().expect("peeked");
+                let (_, rows_b) = iter_b.next().expect("peeked");
+                if rows_a != rows_b {
+                    diff.count_changes.push((folder, rows_a, rows_b));
+                }
+            }
+        }
+    }
+
+    Ok(diff)
+}
+
+/// Rows per folder_id, reading only that column (None if the file doesn't exist)
+///
+/// Tables without a folder_id column are counted under a single empty key.
+fn folder_row_counts(path: &Path) -> Result<Option<BTreeMap<String, usize>>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let file = File::open(path).io_context(format!("Failed to open {}", path.display()))?;
+    let builder = ParquetRecordBatchReaderBuilder::try_new(file)?;
+
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    if builder.schema().column_with_name("folder_id").is_none() {
+        let rows = builder.metadata().file_metadata().num_rows() as usize;
+        counts.insert(String::new(), rows);
+        return Ok(Some(counts));
+    }
+
+    let mask = ProjectionMask::columns(builder.parquet_schema(), ["folder_id"]);
+    let reader = builder.with_projection(mask).with_batch_size(8192).build()?;
+    for batch in reader {
+        let batch = batch?;
+        let folder_id = batch
+            .column_by_name("folder_id")
+            .and_then(|col| col.as_any().downcast_ref::<arrow::array::StringArray>())
+            .ok_or_else(|| ReconstructError::SchemaMismatch {
+                column: "folder_id".to_string(),
+                expected: "StringArray",
+            })?;
+        for i in 0..batch.num_rows() {
+            // Rows of a folder are contiguous, so avoid allocating a key per row
+            match counts.get_mut(folder_id.value(i)) {
+                Some(count) => *count += 1,
+                None => {
+                    counts.insert(folder_id.value(i).to_string(), 1);
+                }
+            }
+        }
+    }
+    Ok(Some(counts))
+}
+
+fn diff_beatmap_fields(a: &Path, b: &Path) -> Result<Vec<FieldDiff>> {
+    let table_a = SortedBeatmaps::read(&a.join("beatmaps.parquet"))?;
+    let table_b = SortedBeatmaps::read(&b.join("beatmaps.parquet"))?;
+
+    // Every field of either schema, with its column in each (None if missing there)
+    let names: BTreeSet<&str> = table_a.fields().chain(table_b.fields()).collect();
+    let fields: Vec<(&str, Option<usize>, Option<usize>)> =
+        names.into_iter().map(|name| (name, table_a.column(name), table_b.column(name))).collect();
+
+    // Both readers yield rows sorted by (folder_id, osu_file), so walk them in lockstep
+    let mut diffs = Vec::new();
+    let mut iter_a = table_a.rows().peekable();
+    let mut iter_b = table_b.rows().peekable();
+    loop {
+        let order = match (iter_a.peek(), iter_b.peek()) {
+            (Some(row_a), Some(row_b)) => row_a.key.cmp(&row_b.key),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => break,
+        };
+        match order {
+            Ordering::Less => {
+                iter_a.next();
+            }
+            Ordering::Greater => {
+                iter_b.next();
+            }
+            Ordering::Equal => {
+                let row_a = iter_a.next().expect("peeked");
+                let row_b = iter_b.next().expect("peeked");
+                for &(field, column_a, column_b) in &fields {
+                    let value_a = table_a.value(&row_a, column_a)?;
+                    let value_b = table_b.value(&row_b, column_b)?;
+                    if value_a != value_b {
+                        diffs.push(FieldDiff {
+                            folder_id: row_a.key.0.to_string(),
+                            osu_file: row_a.key.1.to_string(),
+                            field: field.to_string(),
+                            a: value_a,
+                            b: value_b,
+                        });
+                    }
+                }
+            }
+        }
+    }
+    Ok(diffs)
+}
+
+/// One row of a [`SortedBeatmaps`]: its (folder_id, osu_file) and where it lives
+struct BeatmapRowRef<'a> {
+    key: (&'a str, &'a str),
+    batch: usize,
+    row: usize,
+}
+
+/// beatmaps.parquet as read, plus its row order by (folder_id, osu_file)
+///
+/// The builder appends folders as they finish, so files aren't sorted on disk.
+/// Only the keys are sorted; values are displayed one row at a time while
+/// comparing, so datasets with different schema versions compare too.
+struct SortedBeatmaps {
+    schema: arrow::datatypes::SchemaRef,
+    batches: Vec<arrow::array::RecordBatch>,
+    order: Vec<(usize, usize)>,
+}
+
+impl SortedBeatmaps {
+    fn read(path: &Path) -> Result<Self> {
+        let file = File::open(path).io_context(format!("Failed to open {}", path.display()))?;
+        let builder = ParquetRecordBatchReaderBuilder::try_new(file)?;
+        let schema = builder.schema().clone();
+        let batches = builder.with_batch_size(8192).build()?.collect::<std::result::Result<Vec<_>, _>>()?;
+
+        for column in ["folder_id", "osu_file"] {
+            let is_string = schema.field_with_name(column).is_ok_and(|field| field.data_type() == &DataType::Utf8);
+            if !is_string {
+                return Err(ReconstructError::SchemaMismatch { column: column.to_string(), expected: "StringArray" });
+            }
+        }
+
+        let mut table = Self { schema, batches, order: Vec::new() };
+        let mut order: Vec<(usize, usize)> = table
+            .batches
+            .iter()
+            .enumerate()
+            .flat_map(|(b, batch)| (0..batch.num_rows()).map(move |r| (b, r)))
+            .collect();
+        order.sort_by(|&(batch_a, row_a), &(batch_b, row_b)| table.key(batch_a, row_a).cmp(&table.key(batch_b, row_b)));
+        table.order = order;
+        Ok(table)
+    }
+
+    fn fields(&self) -> impl Iterator<Item = &str> {
+        self.schema.fields().iter().map(|field| field.name().as_str())
+    }
+
+    fn column(&self, name: &str) -> Option<usize> {
+        self.schema.index_of(name).ok()
+    }
+
+    fn key(&self, batch: usize, row: usize) -> (&str, &str) {
+        let string = |name: &str| {
+            self.batches[batch]
+                .column_by_name(name)
+                .and_then(|col| col.as_any().downcast_ref::<arrow::array::StringArray>())
+                .map_or("", |col| col.value(row))
+        };
+        (string("folder_id"), string("osu_file"))
+    }
+
+    fn rows(&self) -> impl Iterator<Item = BeatmapRowRef<'_>> {
+        self.order.iter().map(|&(batch, row)| BeatmapRowRef { key: self.key(batch, row), batch, row })
+    }
+
+    fn value(&self, row: &BeatmapRowRef, column: Option<usize>) -> Result<String> {
+        match column {
+            Some(column) => Ok(array_value_to_string(self.batches[row.batch].column(column), row.row)?),
+            None => Ok("<missing>".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_rows;
+    use arrow::array::{ArrayRef, Float32Array, Int32Array, RecordBatch, StringArray};
+    use std::sync::Arc;
+
+    fn strings(values: &[&str]) -> ArrayRef {
+        Arc::new(StringArray::from(values.to_vec()))
+    }
+
+    fn hit_objects(folders: &[&str]) -> RecordBatch {
+        RecordBatch::try_from_iter([("folder_id", strings(folders))]).unwrap()
+    }
+
+    #[test]
+    fn folders_row_counts_and_beatmap_fields_are_compared() {
+        let a = test_rows::test_dir();
+        let beatmaps_a = RecordBatch::try_from_iter([
+            ("folder_id", strings(&["f1", "f2"])),
+            ("osu_file", strings(&["a.osu", "b.osu"])),
+            ("beatmap_id", Arc::new(Int32Array::from(vec![1, 2])) as ArrayRef),
+        ])
+        .unwrap();
+        test_rows::write_table(&a, "beatmaps", &[beatmaps_a]);
+        test_rows::write_table(&a, "hit_objects", &[hit_objects(&["f1", "f1", "f2"])]);
+
+        // f2 was removed and f3 added; B's rows are out of key order and carry a newer column
+        let b = test_rows::test_dir();
+        let beatmaps_b = RecordBatch::try_from_iter([
+            ("folder_id", strings(&["f3", "f1"])),
+            ("osu_file", strings(&["c.osu", "a.osu"])),
+            ("beatmap_id", Arc::new(Int32Array::from(vec![3, 10])) as ArrayRef),
+            ("stars_calc", Arc::new(Float32Array::from(vec![1.5, 2.5])) as ArrayRef),
+        ])
+        .unwrap();
+        test_rows::write_table(&b, "beatmaps", &[beatmaps_b]);
+        test_rows::write_table(&b, "hit_objects", &[hit_objects(&["f3"]), hit_objects(&["f1", "f1", "f1"])]);
+
+        let diff = diff_datasets(&a, &b, true).unwrap();
+        let table = |name: &str| diff.tables.iter().find(|t| t.table == name).unwrap();
+
+        let beatmaps = table("beatmaps");
+        assert_eq!((beatmaps.rows_a, beatmaps.rows_b), (Some(2), Some(2)));
+        assert_eq!(beatmaps.only_in_a, ["f2"]);
+        assert_eq!(beatmaps.only_in_b, ["f3"]);
+        assert!(beatmaps.count_changes.is_empty());
+
+        let hit_objects = table("hit_objects");
+        assert_eq!((hit_objects.rows_a, hit_objects.rows_b), (Some(3), Some(4)));
+        assert_eq!(hit_objects.count_changes, [("f1".to_string(), 2, 3)]);
+        assert!(!hit_objects.is_unchanged());
+
+        // Only the difficulty in both datasets is compared field by field
+        let fields: Vec<(&str, &str, &str, &str)> = diff
+            .beatmap_fields
+            .iter()
+            .map(|d| (d.folder_id.as_str(), d.field.as_str(), d.a.as_str(), d.b.as_str()))
+            .collect();
+        assert_eq!(fields, [("f1", "beatmap_id", "1", "10"), ("f1", "stars_calc", "<missing>", "2.5")]);
+    }
+
+    #[test]
+    fn identical_datasets_have_no_differences() {
+        let dir = test_rows::test_dir();
+        test_rows::write_table(&dir, "hit_objects", &[hit_objects(&["f1", "f2"])]);
+        let diff = diff_datasets(&dir, &dir, true).unwrap();
+        assert!(diff.tables.iter().all(TableDiff::is_unchanged));
+        assert!(diff.beatmap_fields.is_empty());
+    }
+}
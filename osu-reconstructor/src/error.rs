@@ -0,0 +1,59 @@
+//! Error type of the library API
+//!
+//! Library functions return [`ReconstructError`] so embedders can match on the
+//! kind of failure; the binaries turn it into `anyhow::Error` at their boundary.
+
+use arrow::error::ArrowError;
+use parquet::errors::ParquetError;
+use std::io;
+
+/// Why reading a dataset or reconstructing from it failed
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum ReconstructError {
+    /// A table lacks a column the reader needs
+    #[error("Missing column: {0}")]
+    MissingColumn(String),
+    /// A column is stored with a different type than the reader expects
+    #[error("Column {column} is not {expected}")]
+    SchemaMismatch { column: String, expected: &'static str },
+    /// No beatmaps row for the requested difficulty
+    #[error("Beatmap not found: {folder_id}/{osu_file}")]
+    BeatmapNotFound { folder_id: String, osu_file: String },
+    /// The dataset contradicts its manifest (missing or corrupt table file, no checksums, ...)
+    #[error("{0}")]
+    IntegrityViolation(String),
+    /// Reading or writing a file failed
+    #[error("{context}")]
+    IoError {
+        context: String,
+        #[source]
+        source: io::Error,
+    },
+    /// A stored value or file could not be parsed
+    #[error("{0}")]
+    ParseError(String),
+    /// Arrow failed to decode or process a batch
+    #[error(transparent)]
+    Arrow(#[from] ArrowError),
+    /// Parquet failed to read or write a file
+    #[error(transparent)]
+    Parquet(#[from] ParquetError),
+}
+
+/// Result of library functions
+pub type Result<T, E = ReconstructError> = std::result::Result<T, E>;
+
+/// Describe what an I/O operation was doing, like `anyhow::Context` for I/O errors
+pub(crate) trait IoContext<T> {
+    fn io_context(self, context: impl Into<String>) -> Result<T>;
+}
+
+impl<T> IoContext<T> for std::result::Result<T, io::Error> {
+    fn io_context(self, context: impl Into<String>) -> Result<T> {
+        self.map_err(|source| ReconstructError::IoError {
+            context: context.into(),
+            source,
+        })
+    }
+}
@@ -0,0 +1,323 @@
+//! Flat per-object feature rows for model training
+//!
+//! Joins hit objects with their slider data, control points and timing so
+//! consumers get one resolved struct per object instead of re-doing the joins.
+
+use osu_geometry::ComboFlags;
+use rosu_map::section::hit_objects::{CurveBuffers, HitObject, HitObjectKind};
+
+use crate::beatmap::BeatmapReconstructor;
+use crate::error::{ReconstructError, Result};
+use crate::kinds::ObjectType;
+use crate::timing::{TimingResolver, BASE_SCORING_DISTANCE};
+use crate::types::Dataset;
+
+/// Slider ticks closer than this many ms to the end of a span are dropped, as in osu!
+const TICK_END_MARGIN_MS: f64 = 10.0;
+
+/// One hit object with its geometry, combo and timing state resolved
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectFeatures {
+    /// Object index within the difficulty
+    pub index: usize,
+    /// Circle, slider, spinner or hold
+    pub object_type: ObjectType,
+    /// Start time in ms
+    pub start_time: f64,
+    /// End time in ms (equal to start_time for circles)
+    pub end_time: f64,
+    /// Start position in osupixels (holds have no y and use 0)
+    pub x: f32,
+    pub y: f32,
+    /// Position where the object ends (the slider ball's position after the last span)
+    pub end_x: f32,
+    pub end_y: f32,
+    /// Whether this object starts a new combo (always false for spinners and holds)
+    pub is_new_combo: bool,
+    /// Number shown on the object, 1-based within its combo (0 for spinners and holds)
+    pub combo_number: u32,
+    /// Combo colour slot as osu!lazer counts it (combos plus colour skips); the colour is
+    /// this modulo the map's colour count. Spinners keep the previous object's, holds have 0
+    pub combo_colour: u32,
+    /// Time since the previous object's start (None for the first object)
+    pub delta_time: Option<f64>,
+    /// Distance from the previous object's start position (None for the first object and holds)
+    pub delta_distance: Option<f64>,
+    /// Slider path length in osupixels
+    pub slider_length: Option<f64>,
+    /// Slider velocity in osupixels per ms
+    pub slider_velocity: Option<f64>,
+    /// Slider ticks across all spans
+    pub slider_ticks: Option<u32>,
+    /// BPM of the timing point active at start_time (None without timing points)
+    pub bpm: Option<f64>,
+}
+
+impl Dataset {
+    /// Build one feature row per hit object of a difficulty
+    ///
+    /// Composes `BeatmapReconstructor` (slider paths and durations) with
+    /// `TimingResolver` (BPM and slider velocity at each object).
+    pub fn feature_rows(&self, folder_id: &str, osu_file: &str) -> Result<Vec<ObjectFeatures>> {
+        let beatmap_row = self
+            .beatmaps
+            .iter()
+            .find(|b| b.folder_id == folder_id && b.osu_file == osu_file)
+            .ok_or_else(|| ReconstructError::BeatmapNotFound {
+                folder_id: folder_id.to_string(),
+                osu_file: osu_file.to_string(),
+            })?;
+
+        let beatmap = BeatmapReconstructor::reconstruct(
+            beatmap_row,
+            &self.hit_objects,
+            &self.timing_points,
+            &self.slider_control_points,
+            &self.slider_data,
+            &self.breaks,
+            &self.combo_colors,
+            &self.hit_samples,
+            &self.features,
+        )?;
+        let timing = TimingResolver::new(&self.timing_points, folder_id, osu_file);
+
+        let mut curve_buffers = CurveBuffers::default();
+        let mut rows: Vec<ObjectFeatures> = Vec::with_capacity(beatmap.hit_objects.len());
+        let combos = osu_geometry::combo_info(beatmap.hit_objects.iter().map(combo_flags));
+
+        for (index, (hit_object, combo)) in beatmap.hit_objects.iter().zip(combos).enumerate() {
+            let start_time = hit_object.start_time;
+            let bpm = timing.beat_length_at(start_time).map(|beat_length| 60_000.0 / beat_length);
+
+            let mut row = ObjectFeatures {
+                index,
+                object_type: ObjectType::Circle,
+                start_time,
+                end_time: start_time,
+                x: 0.0,
+                y: 0.0,
+                end_x: 0.0,
+                end_y: 0.0,
+                is_new_combo: false,
+                combo_number: 0,
+                combo_colour: 0,
+                delta_time: None,
+                delta_distance: None,
+                slider_length: None,
+                slider_velocity: None,
+                slider_ticks: None,
+                bpm,
+            };
+
+            match &hit_object.kind {
+                HitObjectKind::Circle(circle) => {
+                    row.x = circle.pos.x;
+                    row.y = circle.pos.y;
+                    row.end_x = circle.pos.x;
+                    row.end_y = circle.pos.y;
+                }
+                HitObjectKind::Slider(slider) => {
+                    let (length, end_offset) = {
+                        let curve = slider.path.curve_with_bufs(&mut curve_buffers);
+                        // Odd span counts end at the tail, even ones back at the head
+                        let progress = if slider.span_count() % 2 == 1 { 1.0 } else { 0.0 };
+                        (curve.dist(), curve.position_at(progress))
+                    };
+                    let duration = slider.duration_with_bufs(&mut curve_buffers);
+
+                    row.object_type = ObjectType::Slider;
+                    row.end_time = start_time + duration;
+                    row.x = slider.pos.x;
+                    row.y = slider.pos.y;
+                    row.end_x = slider.pos.x + end_offset.x;
+                    row.end_y = slider.pos.y + end_offset.y;
+                    row.slider_length = Some(length);
+                    row.slider_velocity = Some(slider.velocity);
+                    row.slider_ticks = Some(
+                        slider_ticks_per_span(
+                            length,
+                            slider.velocity,
+                            beatmap.slider_multiplier,
+                            beatmap.slider_tick_rate,
+                            timing.slider_velocity_at(start_time),
+                            beatmap.format_version,
+                        ) * slider.span_count() as u32,
+                    );
+                }
+                HitObjectKind::Spinner(spinner) => {
+                    row.object_type = ObjectType::Spinner;
+                    row.end_time = start_time + spinner.duration;
+                    row.x = spinner.pos.x;
+                    row.y = spinner.pos.y;
+                    row.end_x = spinner.pos.x;
+                    row.end_y = spinner.pos.y;
+                }
+                HitObjectKind::Hold(hold) => {
+                    row.object_type = ObjectType::Hold;
+                    row.end_time = start_time + hold.duration;
+                    row.x = hold.pos_x;
+                    row.end_x = hold.pos_x;
+                }
+            }
+
+            // Spinners and holds take no combo number; a spinner forces a new combo on the next object
+            match &hit_object.kind {
+                HitObjectKind::Circle(_) | HitObjectKind::Slider(_) => {
+                    row.is_new_combo = combo.index_in_combo == 0;
+                    row.combo_number = combo.combo_number();
+                    row.combo_colour = combo.combo_index_with_offsets;
+                }
+                HitObjectKind::Spinner(_) => row.combo_colour = combo.combo_index_with_offsets,
+                HitObjectKind::Hold(_) => {}
+            }
+
+            if let Some(prev) = rows.last() {
+                row.delta_time = Some(start_time - prev.start_time);
+                if row.object_type != ObjectType::Hold && prev.object_type != ObjectType::Hold {
+                    let (dx, dy) = ((row.x - prev.x) as f64, (row.y - prev.y) as f64);
+                    row.delta_distance = Some(dx.hypot(dy));
+                }
+            }
+
+            rows.push(row);
+        }
+
+        Ok(rows)
+    }
+}
+
+/// Combo bits of a parsed hit object for [`osu_geometry::combo_info`]
+pub(crate) fn combo_flags(hit_object: &HitObject) -> ComboFlags {
+    match &hit_object.kind {
+        HitObjectKind::Circle(circle) => ComboFlags {
+            new_combo: circle.new_combo,
+            combo_offset: circle.combo_offset as u32,
+            is_spinner: false,
+        },
+        HitObjectKind::Slider(slider) => ComboFlags {
+            new_combo: slider.new_combo,
+            combo_offset: slider.combo_offset as u32,
+            is_spinner: false,
+        },
+        HitObjectKind::Spinner(_) => ComboFlags { is_spinner: true, ..ComboFlags::default() },
+        HitObjectKind::Hold(_) => ComboFlags::default(),
+    }
+}
+
+/// Number of slider ticks in a single span
+///
+/// Ticks are spaced by the scoring distance per beat divided by the tick rate;
+/// maps before format v8 ignore slider velocity for tick spacing.
+fn slider_ticks_per_span(
+    length: f64,
+    velocity: f64,
+    slider_multiplier: f64,
+    tick_rate: f64,
+    slider_velocity: f64,
+    format_version: i32,
+) -> u32 {
+    let velocity_factor = if format_version < 8 { 1.0 } else { slider_velocity };
+    let tick_distance = BASE_SCORING_DISTANCE * slider_multiplier * velocity_factor / tick_rate;
+    if !tick_distance.is_finite() || tick_distance <= 0.0 {
+        return 0;
+    }
+
+    let limit = length - velocity * TICK_END_MARGIN_MS;
+    if limit <= 0.0 {
+        return 0;
+    }
+    ((limit / tick_distance).ceil() as u32).saturating_sub(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_rows::{self, FOLDER, OSU_FILE};
+    use crate::types::{HitObjectRow, SliderDataRow};
+
+    /// A straight slider at (100, 100) with `slides` spans, moving at the
+    /// velocity of a 120 BPM line with slider multiplier 1.4 and `sv`
+    fn slider_dataset(length: f64, slides: i32, sv: f64) -> Dataset {
+        let mut dataset = test_rows::dataset(vec![test_rows::slider(0, 1000.0, 100, 100, "L", slides, length)]);
+        dataset.slider_control_points = test_rows::control_points(0, &[(0.0, 0.0), (length as f32, 0.0)]);
+        dataset.slider_data = vec![SliderDataRow {
+            velocity: BASE_SCORING_DISTANCE * 1.4 * sv / 500.0,
+            ..test_rows::slider_data(0, slides - 1, length)
+        }];
+        if sv != 1.0 {
+            dataset.timing_points.push(test_rows::difficulty(0.0, sv));
+        }
+        dataset
+    }
+
+    fn only_row(dataset: &Dataset) -> ObjectFeatures {
+        let rows = dataset.feature_rows(FOLDER, OSU_FILE).unwrap();
+        assert_eq!(rows.len(), 1);
+        rows[0].clone()
+    }
+
+    #[test]
+    fn ticks_are_counted_on_every_span() {
+        // Ticks every 140 px: two per 350 px span
+        let row = only_row(&slider_dataset(350.0, 2, 1.0));
+        assert_eq!(row.slider_ticks, Some(4));
+        assert!((row.end_time - (1000.0 + 2.0 * 1250.0)).abs() < 1e-6);
+        // An even span count ends back at the head
+        assert_eq!((row.end_x, row.end_y), (100.0, 100.0));
+    }
+
+    #[test]
+    fn ticks_within_ten_ms_of_the_span_end_are_dropped() {
+        // The 280 px tick is 4 px (14 ms) before the end of a 284 px slider, 2 px (7 ms) before a 282 px one
+        assert_eq!(only_row(&slider_dataset(284.0, 1, 1.0)).slider_ticks, Some(2));
+        assert_eq!(only_row(&slider_dataset(282.0, 1, 1.0)).slider_ticks, Some(1));
+    }
+
+    #[test]
+    fn tick_rate_and_slider_velocity_space_the_ticks() {
+        let mut dataset = slider_dataset(350.0, 1, 1.0);
+        dataset.beatmaps[0].slider_tick_rate = 2.0;
+        assert_eq!(only_row(&dataset).slider_ticks, Some(4));
+
+        // Doubled velocity doubles the tick spacing to 280 px
+        let mut dataset = slider_dataset(350.0, 1, 2.0);
+        assert_eq!(only_row(&dataset).slider_ticks, Some(1));
+        // which maps before v8 ignore
+        dataset.beatmaps[0].format_version = 7;
+        assert_eq!(only_row(&dataset).slider_ticks, Some(2));
+    }
+
+    #[test]
+    fn deltas_and_combo_numbers_follow_the_previous_object() {
+        let mut second = test_rows::circle(1, 1500.0, 30, 40);
+        second.new_combo = false;
+        let rows = test_rows::dataset(vec![test_rows::circle(0, 1000.0, 0, 0), second, test_rows::spinner(2, 2000.0, 3000.0)])
+            .feature_rows(FOLDER, OSU_FILE)
+            .unwrap();
+
+        assert_eq!((rows[0].delta_time, rows[0].delta_distance), (None, None));
+        assert_eq!((rows[1].delta_time, rows[1].delta_distance), (Some(500.0), Some(50.0)));
+        assert_eq!(rows.iter().map(|row| row.combo_number).collect::<Vec<_>>(), vec![1, 2, 0]);
+        assert_eq!(rows[2].end_time, 3000.0);
+        assert_eq!(rows[0].bpm, Some(120.0));
+    }
+
+    #[test]
+    fn combo_colours_count_skips_and_spinners() {
+        let skip_two = HitObjectRow { new_combo: true, combo_offset: 2, ..test_rows::circle(1, 1500.0, 0, 0) };
+        let rows = test_rows::dataset(vec![
+            test_rows::circle(0, 1000.0, 0, 0),
+            skip_two,
+            test_rows::circle(2, 2000.0, 0, 0),
+            test_rows::spinner(3, 2500.0, 3000.0),
+            test_rows::circle(4, 3500.0, 0, 0),
+        ])
+        .feature_rows(FOLDER, OSU_FILE)
+        .unwrap();
+
+        // Combo2 first, three more after the skip; the spinner keeps it and the next object moves on
+        assert_eq!(rows.iter().map(|row| row.combo_colour).collect::<Vec<_>>(), vec![1, 4, 4, 4, 5]);
+        assert_eq!(rows.iter().map(|row| row.combo_number).collect::<Vec<_>>(), vec![1, 1, 2, 0, 1]);
+        assert!(rows[4].is_new_combo);
+    }
+}
@@ -0,0 +1,277 @@
+//! Reconstruction fidelity scores
+//!
+//! Re-parses an original `.osu` file and its reconstruction and scores how
+//! much of each field category survived, so maps that reconstruct poorly can
+//! be ranked and the emit logic responsible fixed first.
+
+use arrow::array::{Float64Array, RecordBatch, StringArray};
+use arrow::datatypes::{DataType, Field, Schema};
+use parquet::arrow::ArrowWriter;
+use rosu_map::section::hit_objects::{HitObject, HitObjectKind};
+use rosu_map::Beatmap;
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::error::{IoContext, Result};
+use crate::features::combo_flags;
+
+/// File name of the score table written next to the reconstructed folders
+pub const FIDELITY_FILE: &str = "fidelity.parquet";
+
+/// Largest difference for two times or values to count as equal
+const TOLERANCE: f64 = 1e-3;
+
+/// Share of each category in the combined score (normalised, so only ratios matter)
+#[derive(Debug, Clone, Copy)]
+pub struct FidelityWeights {
+    pub hit_objects: f64,
+    pub metadata: f64,
+    pub timing: f64,
+}
+
+impl Default for FidelityWeights {
+    fn default() -> Self {
+        Self {
+            hit_objects: 0.6,
+            metadata: 0.15,
+            timing: 0.25,
+        }
+    }
+}
+
+/// Fidelity of one reconstructed difficulty; every score is between 0 and 1
+#[derive(Debug, Clone, PartialEq)]
+pub struct FidelityScore {
+    pub folder_id: String,
+    pub osu_file: String,
+    /// Objects with the same type, start time, position and combo colour, out of the larger object count
+    pub hit_objects: f64,
+    /// [General]/[Metadata]/[Difficulty]/[Events] fields that match
+    pub metadata: f64,
+    /// Timing, difficulty, effect and sample points that match, out of the larger count
+    pub timing: f64,
+    /// Weighted mean of the three categories
+    pub score: f64,
+}
+
+/// Parse both files and score the reconstruction against the original
+pub fn score_file(
+    folder_id: &str,
+    osu_file: &str,
+    original: &Path,
+    reconstructed: &Path,
+    weights: &FidelityWeights,
+) -> Result<FidelityScore> {
+    let original_map: Beatmap = rosu_map::from_path(original)
+        .io_context(format!("Failed to parse original: {}", original.display()))?;
+    let reconstructed_map: Beatmap = rosu_map::from_path(reconstructed)
+        .io_context(format!("Failed to parse reconstruction: {}", reconstructed.display()))?;
+    Ok(score_beatmaps(folder_id, osu_file, &original_map, &reconstructed_map, weights))
+}
+
+/// Score a reconstructed beatmap against the original
+pub fn score_beatmaps(
+    folder_id: &str,
+    osu_file: &str,
+    original: &Beatmap,
+    reconstructed: &Beatmap,
+    weights: &FidelityWeights,
+) -> FidelityScore {
+    let hit_objects = hit_object_fraction(original, reconstructed);
+    let metadata = metadata_fraction(original, reconstructed);
+    let timing = timing_fraction(original, reconstructed);
+
+    let total_weight = weights.hit_objects + weights.metadata + weights.timing;
+    let score = if total_weight > 0.0 {
+        (hit_objects * weights.hit_objects + metadata * weights.metadata + timing * weights.timing) / total_weight
+    } else {
+        0.0
+    };
+
+    FidelityScore {
+        folder_id: folder_id.to_string(),
+        osu_file: osu_file.to_string(),
+        hit_objects,
+        metadata,
+        timing,
+        score,
+    }
+}
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() <= TOLERANCE
+}
+
+/// Pairs up items by position; missing or extra items count as mismatches
+fn matching_fraction<T>(a: &[T], b: &[T], same: impl Fn(&T, &T) -> bool) -> f64 {
+    let total = a.len().max(b.len());
+    if total == 0 {
+        return 1.0;
+    }
+    let matched = a.iter().zip(b).filter(|(x, y)| same(x, y)).count();
+    matched as f64 / total as f64
+}
+
+/// Objects are compared together with their combo colour slot, so a lost new combo or
+/// colour skip counts against every object it recolours
+fn hit_object_fraction(a: &Beatmap, b: &Beatmap) -> f64 {
+    let combos = |map: &Beatmap| {
+        let infos = osu_geometry::combo_info(map.hit_objects.iter().map(combo_flags));
+        map.hit_objects.iter().zip(infos).collect::<Vec<_>>()
+    };
+    matching_fraction(&combos(a), &combos(b), |(x, x_combo), (y, y_combo)| {
+        x_combo.combo_index_with_offsets == y_combo.combo_index_with_offsets && hit_objects_match(x, y)
+    })
+}
+
+fn hit_objects_match(a: &HitObject, b: &HitObject) -> bool {
+    if !close(a.start_time, b.start_time) {
+        return false;
+    }
+    match (&a.kind, &b.kind) {
+        (HitObjectKind::Circle(x), HitObjectKind::Circle(y)) => x.pos == y.pos,
+        (HitObjectKind::Slider(x), HitObjectKind::Slider(y)) => x.pos == y.pos,
+        (HitObjectKind::Spinner(x), HitObjectKind::Spinner(y)) => x.pos == y.pos,
+        (HitObjectKind::Hold(x), HitObjectKind::Hold(y)) => x.pos_x == y.pos_x,
+        _ => false,
+    }
+}
+
+fn metadata_fraction(a: &Beatmap, b: &Beatmap) -> f64 {
+    let checks = [
+        a.audio_file == b.audio_file,
+        close(a.audio_lead_in, b.audio_lead_in),
+        a.preview_time == b.preview_time,
+        a.mode == b.mode,
+        close(f64::from(a.stack_leniency), f64::from(b.stack_leniency)),
+        a.title == b.title,
+        a.title_unicode == b.title_unicode,
+        a.artist == b.artist,
+        a.artist_unicode == b.artist_unicode,
+        a.creator == b.creator,
+        a.version == b.version,
+        a.source == b.source,
+        a.tags == b.tags,
+        a.beatmap_id == b.beatmap_id,
+        a.beatmap_set_id == b.beatmap_set_id,
+        close(f64::from(a.hp_drain_rate), f64::from(b.hp_drain_rate)),
+        close(f64::from(a.circle_size), f64::from(b.circle_size)),
+        close(f64::from(a.overall_difficulty), f64::from(b.overall_difficulty)),
+        close(f64::from(a.approach_rate), f64::from(b.approach_rate)),
+        close(a.slider_multiplier, b.slider_multiplier),
+        close(a.slider_tick_rate, b.slider_tick_rate),
+        a.background_file == b.background_file,
+    ];
+    checks.iter().filter(|&&matched| matched).count() as f64 / checks.len() as f64
+}
+
+/// All control point kinds pooled, so a map's score reflects its point counts
+fn timing_fraction(a: &Beatmap, b: &Beatmap) -> f64 {
+    let (a, b) = (&a.control_points, &b.control_points);
+    let pairs = [
+        (
+            a.timing_points.len().max(b.timing_points.len()),
+            count_matches(&a.timing_points, &b.timing_points, |x, y| {
+                close(x.time, y.time) && close(x.beat_len, y.beat_len)
+            }),
+        ),
+        (
+            a.difficulty_points.len().max(b.difficulty_points.len()),
+            count_matches(&a.difficulty_points, &b.difficulty_points, |x, y| {
+                close(x.time, y.time) && close(x.slider_velocity, y.slider_velocity)
+            }),
+        ),
+        (
+            a.effect_points.len().max(b.effect_points.len()),
+            count_matches(&a.effect_points, &b.effect_points, |x, y| close(x.time, y.time) && x.kiai == y.kiai),
+        ),
+        (
+            a.sample_points.len().max(b.sample_points.len()),
+            count_matches(&a.sample_points, &b.sample_points, |x, y| {
+                close(x.time, y.time)
+                    && x.sample_bank as i32 == y.sample_bank as i32
+                    && x.sample_volume == y.sample_volume
+                    && x.custom_sample_bank == y.custom_sample_bank
+            }),
+        ),
+    ];
+    let total: usize = pairs.iter().map(|(total, _)| total).sum();
+    if total == 0 {
+        return 1.0;
+    }
+    let matched: usize = pairs.iter().map(|(_, matched)| matched).sum();
+    matched as f64 / total as f64
+}
+
+fn count_matches<T>(a: &[T], b: &[T], same: impl Fn(&T, &T) -> bool) -> usize {
+    a.iter().zip(b).filter(|(x, y)| same(x, y)).count()
+}
+
+/// Write scores to a parquet file (one row per difficulty)
+pub fn write_fidelity_parquet(path: &Path, scores: &[FidelityScore]) -> Result<()> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("folder_id", DataType::Utf8, false),
+        Field::new("osu_file", DataType::Utf8, false),
+        Field::new("hit_objects", DataType::Float64, false),
+        Field::new("metadata", DataType::Float64, false),
+        Field::new("timing", DataType::Float64, false),
+        Field::new("score", DataType::Float64, false),
+    ]));
+    let batch = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(StringArray::from_iter_values(scores.iter().map(|s| s.folder_id.as_str()))),
+            Arc::new(StringArray::from_iter_values(scores.iter().map(|s| s.osu_file.as_str()))),
+            Arc::new(Float64Array::from_iter_values(scores.iter().map(|s| s.hit_objects))),
+            Arc::new(Float64Array::from_iter_values(scores.iter().map(|s| s.metadata))),
+            Arc::new(Float64Array::from_iter_values(scores.iter().map(|s| s.timing))),
+            Arc::new(Float64Array::from_iter_values(scores.iter().map(|s| s.score))),
+        ],
+    )?;
+
+    let file = File::create(path).io_context(format!("Failed to create {}", path.display()))?;
+    let mut writer = ArrowWriter::try_new(file, schema, None)?;
+    writer.write(&batch)?;
+    writer.close()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beatmap::BeatmapReconstructor;
+    use crate::test_rows;
+    use crate::types::HitObjectRow;
+
+    fn beatmap(hit_objects: Vec<HitObjectRow>) -> Beatmap {
+        let dataset = test_rows::dataset(hit_objects);
+        BeatmapReconstructor::reconstruct(
+            &dataset.beatmaps[0],
+            &dataset.hit_objects,
+            &dataset.timing_points,
+            &dataset.slider_control_points,
+            &dataset.slider_data,
+            &dataset.breaks,
+            &dataset.combo_colors,
+            &dataset.hit_samples,
+            &dataset.features,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn a_lost_colour_skip_counts_against_every_recoloured_object() {
+        let objects = |combo_offset: i32| {
+            vec![
+                test_rows::circle(0, 1000.0, 100, 100),
+                HitObjectRow { new_combo: true, combo_offset, ..test_rows::circle(1, 1500.0, 200, 100) },
+                test_rows::circle(2, 2000.0, 300, 100),
+            ]
+        };
+        let original = beatmap(objects(2));
+        assert_eq!(hit_object_fraction(&original, &beatmap(objects(2))), 1.0);
+        // Same positions and times, but the last two objects change colour
+        assert!((hit_object_fraction(&original, &beatmap(objects(0))) - 1.0 / 3.0).abs() < 1e-9);
+    }
+}
@@ -1,10 +1,10 @@
 //! Folder reconstruction - combines beatmaps, storyboards, and assets
 
-use anyhow::{Context, Result};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use crate::beatmap::BeatmapReconstructor;
+use crate::error::{IoContext, Result};
 use crate::storyboard::StoryboardReconstructor;
 use crate::types::*;
 
@@ -34,12 +34,13 @@ impl FolderReconstructor {
         // Create output folder
         let folder_output = output_dir.join(folder_id);
         fs::create_dir_all(&folder_output)
-            .context(format!("Failed to create output folder: {}", folder_output.display()))?;
+            .io_context(format!("Failed to create output folder: {}", folder_output.display()))?;
 
         let mut result = ReconstructedFolder {
             folder_id: folder_id.to_string(),
             output_path: folder_output.clone(),
             osu_files: Vec::new(),
+            skipped_too_large: Vec::new(),
             storyboard_elements: 0,
             assets_copied: 0,
         };
@@ -52,6 +53,12 @@ impl FolderReconstructor {
 
         // Reconstruct each .osu file
         for beatmap_row in &beatmap_rows {
+            // Without its hit objects the difficulty would be written as an empty map
+            if beatmap_row.skipped_too_large {
+                result.skipped_too_large.push(beatmap_row.osu_file.clone());
+                continue;
+            }
+
             let mut beatmap = BeatmapReconstructor::reconstruct(
                 beatmap_row,
                 &dataset.hit_objects,
@@ -61,11 +68,50 @@ impl FolderReconstructor {
                 &dataset.breaks,
                 &dataset.combo_colors,
                 &dataset.hit_samples,
+                &dataset.features,
             )?;
 
             let osu_path = folder_output.join(&beatmap_row.osu_file);
-            beatmap.encode_to_path(&osu_path)
-                .context(format!("Failed to write beatmap: {}", osu_path.display()))?;
+            let mut encoded = Vec::new();
+            beatmap.encode(&mut encoded)
+                .io_context(format!("Failed to encode beatmap: {}", osu_path.display()))?;
+            let osu_content = BeatmapReconstructor::prune_general_fields(
+                &String::from_utf8_lossy(&encoded),
+                beatmap.mode,
+            );
+            let osu_content = BeatmapReconstructor::set_audio_timing(
+                &osu_content,
+                beatmap_row.audio_lead_in,
+                beatmap_row.preview_time,
+            );
+            let osu_content = BeatmapReconstructor::set_skin_settings(&osu_content, beatmap_row);
+            let osu_content = BeatmapReconstructor::set_editor_section(&osu_content, beatmap_row);
+            let osu_content = BeatmapReconstructor::drop_empty_colours(&osu_content);
+            let osu_content = if dataset.features.literal_slider_anchors {
+                BeatmapReconstructor::set_slider_anchors(
+                    &osu_content,
+                    folder_id,
+                    &beatmap_row.osu_file,
+                    &dataset.hit_objects,
+                    &dataset.slider_control_points,
+                )
+            } else {
+                osu_content
+            };
+            let osu_content = BeatmapReconstructor::assemble_events(
+                &osu_content,
+                folder_id,
+                &beatmap_row.osu_file,
+                &dataset.events,
+            );
+            let osu_content = BeatmapReconstructor::insert_extra_fields(
+                &osu_content,
+                folder_id,
+                &beatmap_row.osu_file,
+                &dataset.extra_fields,
+            );
+            fs::write(&osu_path, osu_content)
+                .io_context(format!("Failed to write beatmap: {}", osu_path.display()))?;
             
             result.osu_files.push(beatmap_row.osu_file.clone());
 
@@ -85,7 +131,7 @@ impl FolderReconstructor {
                 let osb_content = StoryboardReconstructor::to_osb_content(&embedded_sb);
                 let osb_path = folder_output.join(&osb_filename);
                 fs::write(&osb_path, osb_content)
-                    .context(format!("Failed to write embedded storyboard: {}", osb_path.display()))?;
+                    .io_context(format!("Failed to write embedded storyboard: {}", osb_path.display()))?;
                 result.storyboard_elements += embedded_sb.len();
             }
         }
@@ -114,34 +160,72 @@ impl FolderReconstructor {
                     let osb_content = StoryboardReconstructor::to_osb_content(&elements);
                     let osb_path = folder_output.join(sb_file);
                     fs::write(&osb_path, osb_content)
-                        .context(format!("Failed to write storyboard: {}", osb_path.display()))?;
+                        .io_context(format!("Failed to write storyboard: {}", osb_path.display()))?;
                 }
             }
             
             result.storyboard_elements += elements.len();
         }
 
-        // Copy assets
+        // Copy assets, preferring the assets.parquet index over scanning the directory
         let assets_source = self.assets_dir.join(folder_id);
-        if assets_source.exists() {
+        let asset_rows: Vec<_> = dataset.assets
+            .iter()
+            .filter(|a| a.folder_id == folder_id)
+            .collect();
+        if !asset_rows.is_empty() {
+            result.assets_copied = self.copy_indexed_assets(&asset_rows, &folder_output)?;
+        } else if assets_source.exists() {
             result.assets_copied = self.copy_assets(&assets_source, &folder_output)?;
         }
 
         // Copy audio file if exists
         if let Some(first_beatmap) = beatmap_rows.first() {
-            let audio_source = assets_source.join(&first_beatmap.audio_file);
+            let audio_source = assets_source.join(relative_path(&first_beatmap.audio_file_resolved));
             if audio_source.exists() {
-                let audio_dest = folder_output.join(&first_beatmap.audio_file);
+                let audio_dest = folder_output.join(relative_path(&first_beatmap.audio_file_resolved));
                 if let Some(parent) = audio_dest.parent() {
-                    fs::create_dir_all(parent)?;
+                    fs::create_dir_all(parent)
+                        .io_context(format!("Failed to create folder: {}", parent.display()))?;
                 }
-                fs::copy(&audio_source, &audio_dest)?;
+                fs::copy(&audio_source, &audio_dest)
+                    .io_context(format!("Failed to copy audio: {}", audio_source.display()))?;
             }
         }
 
         Ok(result)
     }
 
+    /// Copy the assets listed in assets.parquet to their original paths
+    fn copy_indexed_assets(&self, assets: &[&AssetRow], dest: &Path) -> Result<usize> {
+        let mut count = 0;
+
+        for asset in assets {
+            // stored_path is relative to the dataset root (assets/{folder_id}/...)
+            let relative = asset.stored_path.strip_prefix("assets/").unwrap_or(&asset.stored_path);
+            let source_path = self.assets_dir.join(relative);
+            if !source_path.exists() {
+                continue;
+            }
+
+            let dest_path = dest.join(relative_path(&asset.original_path));
+            if let Some(parent) = dest_path.parent() {
+                fs::create_dir_all(parent)
+                    .io_context(format!("Failed to create folder: {}", parent.display()))?;
+            }
+
+            if asset.stored_path.ends_with(".zst") {
+                decompress_asset(&source_path, &dest_path)?;
+            } else {
+                fs::copy(&source_path, &dest_path)
+                    .io_context(format!("Failed to copy asset: {}", source_path.display()))?;
+            }
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
     /// Copy all assets from source to destination
     fn copy_assets(&self, source: &Path, dest: &Path) -> Result<usize> {
         let mut count = 0;
@@ -153,14 +237,27 @@ impl FolderReconstructor {
         for entry in walkdir::WalkDir::new(source).into_iter().flatten() {
             let path: &std::path::Path = entry.path();
             if path.is_file() {
-                let rel_path = path.strip_prefix(source)?;
-                let dest_path = dest.join(rel_path);
+                let Ok(rel_path) = path.strip_prefix(source) else {
+                    continue;
+                };
+                let is_compressed = rel_path.extension().is_some_and(|e| e == "zst");
+                let dest_path = if is_compressed {
+                    dest.join(rel_path.with_extension(""))
+                } else {
+                    dest.join(rel_path)
+                };
                 
                 if let Some(parent) = dest_path.parent() {
-                    fs::create_dir_all(parent)?;
+                    fs::create_dir_all(parent)
+                        .io_context(format!("Failed to create folder: {}", parent.display()))?;
                 }
                 
-                fs::copy(path, &dest_path)?;
+                if is_compressed {
+                    decompress_asset(path, &dest_path)?;
+                } else {
+                    fs::copy(path, &dest_path)
+                        .io_context(format!("Failed to copy asset: {}", path.display()))?;
+                }
                 count += 1;
             }
         }
@@ -180,12 +277,31 @@ impl FolderReconstructor {
     }
 }
 
+/// Turn a path referenced by a beatmap into a relative `PathBuf`, accepting both
+/// `/` and `\` so older datasets that stored `SB\bg.jpg` still land in a subfolder
+fn relative_path(path: &str) -> PathBuf {
+    path.split(['/', '\\']).filter(|part| !part.is_empty()).collect()
+}
+
+/// Decompress a `.zst` asset written by `--compress-assets`
+fn decompress_asset(source: &Path, dest: &Path) -> Result<()> {
+    let input = fs::File::open(source)
+        .io_context(format!("Failed to open asset: {}", source.display()))?;
+    let output = fs::File::create(dest)
+        .io_context(format!("Failed to create asset: {}", dest.display()))?;
+    zstd::stream::copy_decode(input, output)
+        .io_context(format!("Failed to decompress asset: {}", source.display()))?;
+    Ok(())
+}
+
 /// Result of folder reconstruction
 #[derive(Debug)]
 pub struct ReconstructedFolder {
     pub folder_id: String,
     pub output_path: std::path::PathBuf,
     pub osu_files: Vec<String>,
+    /// Difficulties left out because the builder skipped their hit objects (`--max-objects`)
+    pub skipped_too_large: Vec<String>,
     pub storyboard_elements: usize,
     pub assets_copied: usize,
 }
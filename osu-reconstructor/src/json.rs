@@ -0,0 +1,247 @@
+//! JSON export of a single difficulty for web tooling
+//!
+//! Mirrors the reconstructed beatmap as plain serde structs, with slider paths
+//! already sampled so consumers don't need to reimplement osu! curve math.
+
+use osu_geometry::{ControlPoint, PathKind};
+use rosu_map::section::hit_objects::{CurveBuffers, HitObjectKind, HitObjectSlider, PathType};
+use serde::Serialize;
+
+use crate::beatmap::BeatmapReconstructor;
+use crate::error::{ReconstructError, Result};
+use crate::types::*;
+
+/// One difficulty with everything needed to render it
+#[derive(Debug, Clone, Serialize)]
+pub struct BeatmapJson {
+    /// Metadata, general, editor and difficulty settings
+    pub beatmap: BeatmapRow,
+    pub timing_points: Vec<TimingPointRow>,
+    pub breaks: Vec<BreakRow>,
+    pub combo_colors: Vec<ComboColorRow>,
+    /// Raw `[Events]` lines not covered by other tables
+    pub events: Vec<EventRow>,
+    /// Unrecognised `[General]`/`[Metadata]` keys (datasets built with `--capture-extra`)
+    pub extra_fields: Vec<ExtraFieldRow>,
+    pub hit_objects: Vec<HitObjectJson>,
+    /// Per-object hit samples, joined by `hit_object_index`
+    pub hit_samples: Vec<HitSampleRow>,
+}
+
+/// A hit object with its end time and shape resolved
+#[derive(Debug, Clone, Serialize)]
+pub struct HitObjectJson {
+    pub index: usize,
+    pub start_time: f64,
+    pub end_time: f64,
+    #[serde(flatten)]
+    pub kind: HitObjectJsonKind,
+}
+
+/// Type-specific data, tagged by `type` (`circle`, `slider`, `spinner`, `hold`)
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HitObjectJsonKind {
+    Circle {
+        x: f32,
+        y: f32,
+        new_combo: bool,
+        combo_offset: i32,
+    },
+    Slider {
+        x: f32,
+        y: f32,
+        new_combo: bool,
+        combo_offset: i32,
+        /// Number of spans (1 + repeats)
+        span_count: usize,
+        velocity: f64,
+        /// Control points in absolute osupixels, with the curve type on segment starts
+        control_points: Vec<ControlPointJson>,
+        /// Sampled path in absolute osupixels, clamped to the expected distance
+        path: Vec<(f32, f32)>,
+    },
+    Spinner {
+        x: f32,
+        y: f32,
+        new_combo: bool,
+    },
+    Hold {
+        x: f32,
+    },
+}
+
+/// Slider control point in absolute osupixels
+#[derive(Debug, Clone, Serialize)]
+pub struct ControlPointJson {
+    pub x: f32,
+    pub y: f32,
+    /// `bezier`, `linear`, `perfect_curve` or `catmull` on segment starts
+    pub path_type: Option<&'static str>,
+}
+
+impl Dataset {
+    /// Assemble the JSON tree of a difficulty
+    ///
+    /// Hit objects come from `BeatmapReconstructor`, so they match what the
+    /// reconstructed `.osu` file would contain.
+    pub fn beatmap_json(&self, folder_id: &str, osu_file: &str) -> Result<BeatmapJson> {
+        let beatmap_row = self
+            .beatmaps
+            .iter()
+            .find(|b| b.folder_id == folder_id && b.osu_file == osu_file)
+            .ok_or_else(|| ReconstructError::BeatmapNotFound {
+                folder_id: folder_id.to_string(),
+                osu_file: osu_file.to_string(),
+            })?;
+
+        let beatmap = BeatmapReconstructor::reconstruct(
+            beatmap_row,
+            &self.hit_objects,
+            &self.timing_points,
+            &self.slider_control_points,
+            &self.slider_data,
+            &self.breaks,
+            &self.combo_colors,
+            &self.hit_samples,
+            &self.features,
+        )?;
+
+        let mut curve_buffers = CurveBuffers::default();
+        let hit_objects = beatmap
+            .hit_objects
+            .iter()
+            .enumerate()
+            .map(|(index, hit_object)| {
+                let start_time = hit_object.start_time;
+                let (end_time, kind) = match &hit_object.kind {
+                    HitObjectKind::Circle(circle) => (
+                        start_time,
+                        HitObjectJsonKind::Circle {
+                            x: circle.pos.x,
+                            y: circle.pos.y,
+                            new_combo: circle.new_combo,
+                            combo_offset: circle.combo_offset,
+                        },
+                    ),
+                    HitObjectKind::Slider(slider) => (
+                        start_time + slider.duration_with_bufs(&mut curve_buffers),
+                        slider_json(slider),
+                    ),
+                    HitObjectKind::Spinner(spinner) => (
+                        start_time + spinner.duration,
+                        HitObjectJsonKind::Spinner {
+                            x: spinner.pos.x,
+                            y: spinner.pos.y,
+                            new_combo: spinner.new_combo,
+                        },
+                    ),
+                    HitObjectKind::Hold(hold) => {
+                        (start_time + hold.duration, HitObjectJsonKind::Hold { x: hold.pos_x })
+                    }
+                };
+                HitObjectJson {
+                    index,
+                    start_time,
+                    end_time,
+                    kind,
+                }
+            })
+            .collect();
+
+        let in_difficulty = |row_folder: &str, row_file: &str| row_folder == folder_id && row_file == osu_file;
+        Ok(BeatmapJson {
+            beatmap: beatmap_row.clone(),
+            timing_points: self
+                .timing_points
+                .iter()
+                .filter(|r| in_difficulty(&r.folder_id, &r.osu_file))
+                .cloned()
+                .collect(),
+            breaks: self
+                .breaks
+                .iter()
+                .filter(|r| in_difficulty(&r.folder_id, &r.osu_file))
+                .cloned()
+                .collect(),
+            combo_colors: self
+                .combo_colors
+                .iter()
+                .filter(|r| in_difficulty(&r.folder_id, &r.osu_file))
+                .cloned()
+                .collect(),
+            events: self
+                .events
+                .iter()
+                .filter(|r| in_difficulty(&r.folder_id, &r.osu_file))
+                .cloned()
+                .collect(),
+            extra_fields: self
+                .extra_fields
+                .iter()
+                .filter(|r| in_difficulty(&r.folder_id, &r.osu_file))
+                .cloned()
+                .collect(),
+            hit_objects,
+            hit_samples: self
+                .hit_samples
+                .iter()
+                .filter(|r| in_difficulty(&r.folder_id, &r.osu_file))
+                .cloned()
+                .collect(),
+        })
+    }
+}
+
+fn slider_json(slider: &HitObjectSlider) -> HitObjectJsonKind {
+    let control_points: Vec<ControlPoint> = slider
+        .path
+        .control_points()
+        .iter()
+        .map(|cp| ControlPoint {
+            pos: (slider.pos.x + cp.pos.x, slider.pos.y + cp.pos.y),
+            kind: cp.path_type.map(path_kind),
+        })
+        .collect();
+    let path = osu_geometry::sample_path(&control_points, slider.path.expected_dist(), 0.0);
+
+    HitObjectJsonKind::Slider {
+        x: slider.pos.x,
+        y: slider.pos.y,
+        new_combo: slider.new_combo,
+        combo_offset: slider.combo_offset,
+        span_count: slider.span_count(),
+        velocity: slider.velocity,
+        control_points: control_points
+            .iter()
+            .map(|cp| ControlPointJson {
+                x: cp.pos.0,
+                y: cp.pos.1,
+                path_type: cp.kind.map(path_kind_name),
+            })
+            .collect(),
+        path,
+    }
+}
+
+pub(crate) fn path_kind(path_type: PathType) -> PathKind {
+    if path_type == PathType::LINEAR {
+        PathKind::Linear
+    } else if path_type == PathType::PERFECT_CURVE {
+        PathKind::PerfectCurve
+    } else if path_type == PathType::CATMULL {
+        PathKind::Catmull
+    } else {
+        // Bezier and higher-degree B-splines
+        PathKind::Bezier
+    }
+}
+
+fn path_kind_name(kind: PathKind) -> &'static str {
+    match kind {
+        PathKind::Bezier => "bezier",
+        PathKind::Linear => "linear",
+        PathKind::PerfectCurve => "perfect_curve",
+        PathKind::Catmull => "catmull",
+    }
+}
@@ -0,0 +1,214 @@
+//! Typed values for string columns of the row structs
+//!
+//! The `*Row` structs keep the strings as stored in parquet; the typed layer
+//! (`ObjectFeatures`, `TimingResolver`, beatmap reconstruction) parses them
+//! once with these enums so unexpected values fail instead of silently
+//! falling through a string match.
+
+use rosu_map::section::hit_objects::hit_samples::{self, HitSampleDefaultName, HitSampleInfoName};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::{ReconstructError, Result};
+
+/// Hit object type from `hit_objects.object_type`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ObjectType {
+    Circle,
+    Slider,
+    Spinner,
+    Hold,
+}
+
+impl ObjectType {
+    /// Column value: `circle`, `slider`, `spinner` or `hold`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ObjectType::Circle => "circle",
+            ObjectType::Slider => "slider",
+            ObjectType::Spinner => "spinner",
+            ObjectType::Hold => "hold",
+        }
+    }
+}
+
+impl fmt::Display for ObjectType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ObjectType {
+    type Err = ReconstructError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "circle" => ObjectType::Circle,
+            "slider" => ObjectType::Slider,
+            "spinner" => ObjectType::Spinner,
+            "hold" => ObjectType::Hold,
+            other => return Err(ReconstructError::ParseError(format!("Unknown object type: {:?}", other))),
+        })
+    }
+}
+
+/// Sample bank from `hit_samples.bank` and `timing_points.sample_bank`
+///
+/// `None` on a hit sample means it inherits the bank of the active sample point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SampleBank {
+    None,
+    Normal,
+    Soft,
+    Drum,
+}
+
+impl SampleBank {
+    /// Column value: `None`, `Normal`, `Soft` or `Drum`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SampleBank::None => "None",
+            SampleBank::Normal => "Normal",
+            SampleBank::Soft => "Soft",
+            SampleBank::Drum => "Drum",
+        }
+    }
+}
+
+impl fmt::Display for SampleBank {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for SampleBank {
+    type Err = ReconstructError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "None" => SampleBank::None,
+            "Normal" => SampleBank::Normal,
+            "Soft" => SampleBank::Soft,
+            "Drum" => SampleBank::Drum,
+            other => return Err(ReconstructError::ParseError(format!("Unknown sample bank: {:?}", other))),
+        })
+    }
+}
+
+impl From<SampleBank> for hit_samples::SampleBank {
+    fn from(bank: SampleBank) -> Self {
+        match bank {
+            SampleBank::None => hit_samples::SampleBank::None,
+            SampleBank::Normal => hit_samples::SampleBank::Normal,
+            SampleBank::Soft => hit_samples::SampleBank::Soft,
+            SampleBank::Drum => hit_samples::SampleBank::Drum,
+        }
+    }
+}
+
+/// Hitsound sample name from `hit_samples.name`
+///
+/// Anything other than the four default names is a custom sample file.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum HitSoundName {
+    Normal,
+    Whistle,
+    Finish,
+    Clap,
+    File(String),
+}
+
+impl fmt::Display for HitSoundName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HitSoundName::Normal => f.write_str("Normal"),
+            HitSoundName::Whistle => f.write_str("Whistle"),
+            HitSoundName::Finish => f.write_str("Finish"),
+            HitSoundName::Clap => f.write_str("Clap"),
+            HitSoundName::File(file) => f.write_str(file),
+        }
+    }
+}
+
+impl FromStr for HitSoundName {
+    type Err = ReconstructError;
+
+    /// Accepts the plain names as well as the `Default(Normal)` / `File("x.wav")`
+    /// form the builder writes with `{:?}`
+    fn from_str(s: &str) -> Result<Self> {
+        let name = s
+            .strip_prefix("Default(")
+            .and_then(|rest| rest.strip_suffix(')'))
+            .unwrap_or(s);
+        if let Some(file) = name.strip_prefix("File(\"").and_then(|rest| rest.strip_suffix("\")")) {
+            return Ok(HitSoundName::File(file.to_string()));
+        }
+        Ok(match name {
+            "Normal" | "normal" => HitSoundName::Normal,
+            "Whistle" | "whistle" => HitSoundName::Whistle,
+            "Finish" | "finish" => HitSoundName::Finish,
+            "Clap" | "clap" => HitSoundName::Clap,
+            "" => return Err(ReconstructError::ParseError("Empty hit sample name".to_string())),
+            file => HitSoundName::File(file.to_string()),
+        })
+    }
+}
+
+impl From<HitSoundName> for HitSampleInfoName {
+    fn from(name: HitSoundName) -> Self {
+        match name {
+            HitSoundName::Normal => HitSampleInfoName::Default(HitSampleDefaultName::Normal),
+            HitSoundName::Whistle => HitSampleInfoName::Default(HitSampleDefaultName::Whistle),
+            HitSoundName::Finish => HitSampleInfoName::Default(HitSampleDefaultName::Finish),
+            HitSoundName::Clap => HitSampleInfoName::Default(HitSampleDefaultName::Clap),
+            HitSoundName::File(file) => HitSampleInfoName::File(file),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn object_types_round_trip() {
+        for kind in [ObjectType::Circle, ObjectType::Slider, ObjectType::Spinner, ObjectType::Hold] {
+            assert_eq!(kind.to_string().parse::<ObjectType>().unwrap(), kind);
+        }
+        assert!("Circle".parse::<ObjectType>().is_err());
+        assert!("".parse::<ObjectType>().is_err());
+    }
+
+    #[test]
+    fn sample_banks_round_trip() {
+        for bank in [SampleBank::None, SampleBank::Normal, SampleBank::Soft, SampleBank::Drum] {
+            assert_eq!(bank.to_string().parse::<SampleBank>().unwrap(), bank);
+        }
+        assert!("soft".parse::<SampleBank>().is_err());
+    }
+
+    #[test]
+    fn hit_sound_names_round_trip() {
+        let names = [
+            HitSoundName::Normal,
+            HitSoundName::Whistle,
+            HitSoundName::Finish,
+            HitSoundName::Clap,
+            HitSoundName::File("soft-hitclap2.wav".to_string()),
+        ];
+        for name in names {
+            assert_eq!(name.to_string().parse::<HitSoundName>().unwrap(), name);
+        }
+        assert!("".parse::<HitSoundName>().is_err());
+    }
+
+    #[test]
+    fn hit_sound_names_parse_the_builder_debug_form() {
+        assert_eq!("Default(Whistle)".parse::<HitSoundName>().unwrap(), HitSoundName::Whistle);
+        assert_eq!("clap".parse::<HitSoundName>().unwrap(), HitSoundName::Clap);
+        assert_eq!(
+            "File(\"drum-hitnormal.wav\")".parse::<HitSoundName>().unwrap(),
+            HitSoundName::File("drum-hitnormal.wav".to_string())
+        );
+    }
+}
@@ -3,14 +3,39 @@
 //! This library provides utilities to read parquet files exported by osu-validator
 //! and reconstruct complete beatmap folders including .osu files, storyboards, and assets.
 
+pub mod error;
 pub mod types;
+pub mod kinds;
 pub mod reader;
 pub mod beatmap;
 pub mod storyboard;
 pub mod folder;
+pub mod slider_path;
+pub mod timing;
+pub mod manifest;
+pub mod features;
+pub mod diff;
+pub mod json;
+pub mod csv_export;
+pub mod fidelity;
+pub mod sample;
+pub mod ddl;
+pub mod merge;
+mod sections;
+#[cfg(test)]
+mod test_rows;
 
+pub use error::{ReconstructError, Result};
 pub use types::*;
-pub use reader::ParquetReader;
+pub use reader::{ParquetReader, TableRow};
 pub use beatmap::BeatmapReconstructor;
 pub use storyboard::StoryboardReconstructor;
 pub use folder::FolderReconstructor;
+pub use timing::{osu_slider_length, slider_velocity, SampleState, TimingResolver};
+pub use manifest::{DatasetFeatures, DatasetManifest};
+pub use features::ObjectFeatures;
+pub use kinds::{HitSoundName, ObjectType, SampleBank};
+pub use json::{BeatmapJson, HitObjectJson, HitObjectJsonKind};
+pub use diff::{diff_datasets, DatasetDiff, FieldDiff, TableDiff};
+pub use fidelity::{FidelityScore, FidelityWeights};
+pub use sample::{Allocation, StarBin, StratifiedSample};
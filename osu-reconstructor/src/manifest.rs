@@ -0,0 +1,225 @@
+//! Dataset manifest and detection of optional data
+//!
+//! Newer builder versions add columns and tables. The reconstructor checks what
+//! a dataset actually contains so it can emit the most faithful `.osu` the data
+//! supports and report what had to be left out.
+
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::error::{IoContext, ReconstructError, Result};
+
+/// Manifest filename inside the dataset directory
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Contents of manifest.json written by osu-dataset-builder
+#[derive(Debug, Clone, Deserialize)]
+pub struct DatasetManifest {
+    /// Dataset schema version (see SCHEMA.md)
+    pub schema_version: u32,
+    /// Version of the builder that wrote the dataset
+    #[serde(default)]
+    pub builder_version: String,
+    /// Row count per table
+    #[serde(default)]
+    pub tables: BTreeMap<String, usize>,
+    /// Size and blake3 hash per table file (empty for datasets written before checksums)
+    #[serde(default)]
+    pub files: BTreeMap<String, FileChecksum>,
+}
+
+/// Size and content hash of one table file
+#[derive(Debug, Clone, Deserialize)]
+pub struct FileChecksum {
+    pub size: u64,
+    /// Hex-encoded blake3 hash of the file contents
+    pub blake3: String,
+}
+
+impl DatasetManifest {
+    /// Load manifest.json from a dataset directory, if present
+    ///
+    /// Datasets written before the manifest was introduced have none.
+    pub fn load(dataset_path: &Path) -> Result<Option<Self>> {
+        let path = dataset_path.join(MANIFEST_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(&path)
+            .io_context(format!("Failed to read {}", path.display()))?;
+        let manifest = serde_json::from_str(&content)
+            .map_err(|e| ReconstructError::ParseError(format!("Failed to parse {}: {}", path.display(), e)))?;
+        Ok(Some(manifest))
+    }
+
+    /// Check every table file against its recorded size and hash
+    ///
+    /// Returns the number of files verified. Fails on the first missing, truncated or
+    /// corrupt file, naming it.
+    pub fn verify(&self, dataset_path: &Path) -> Result<usize> {
+        for (name, expected) in &self.files {
+            let path = dataset_path.join(name);
+            let file = match std::fs::File::open(&path) {
+                Ok(file) => file,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    return Err(ReconstructError::IntegrityViolation(format!(
+                        "{} is listed in the manifest but missing",
+                        path.display()
+                    )));
+                }
+                Err(e) => return Err(e).io_context(format!("Failed to open {}", path.display())),
+            };
+
+            let size = file.metadata().io_context(format!("Failed to read {}", path.display()))?.len();
+            if size != expected.size {
+                return Err(ReconstructError::IntegrityViolation(format!(
+                    "{} is corrupt or incomplete: {} bytes, manifest records {}",
+                    path.display(), size, expected.size
+                )));
+            }
+
+            let mut hasher = blake3::Hasher::new();
+            hasher.update_reader(file).io_context(format!("Failed to hash {}", path.display()))?;
+            let hash = hasher.finalize().to_hex();
+            if hash.as_str() != expected.blake3 {
+                return Err(ReconstructError::IntegrityViolation(format!(
+                    "{} is corrupt: blake3 {} does not match manifest {}",
+                    path.display(), hash, expected.blake3
+                )));
+            }
+        }
+        Ok(self.files.len())
+    }
+}
+
+/// Optional data present in a dataset
+#[derive(Debug, Clone)]
+pub struct DatasetFeatures {
+    /// Schema version from the manifest (None for datasets without one)
+    pub schema_version: Option<u32>,
+    /// Sample points in timing_points (schema v2)
+    pub sample_points: bool,
+    /// Per-object hitsounds in hit_samples.parquet
+    pub hit_samples: bool,
+    /// Copied asset index in assets.parquet (schema v2)
+    pub asset_index: bool,
+    /// Per-node samples of slider heads, repeats and tails in hit_samples (schema v6)
+    pub slider_edge_samples: bool,
+    /// Spinner/hold `end_time` is an absolute time (schema v3); older datasets stored the duration
+    pub absolute_end_times: bool,
+    /// Raw `[Events]` lines such as background colour transformations in events.parquet (schema v5)
+    pub events: bool,
+    /// Unrecognised `[General]`/`[Metadata]` keys in extra_fields.parquet (schema v11, opt-in)
+    pub extra_fields: bool,
+    /// slider_control_points holds anchors as written, red anchors included (schema v18)
+    pub literal_slider_anchors: bool,
+}
+
+impl Default for DatasetFeatures {
+    /// Everything the current schema provides
+    fn default() -> Self {
+        Self {
+            schema_version: None,
+            sample_points: true,
+            hit_samples: true,
+            asset_index: true,
+            slider_edge_samples: false,
+            absolute_end_times: true,
+            events: true,
+            extra_fields: true,
+            literal_slider_anchors: true,
+        }
+    }
+}
+
+impl DatasetFeatures {
+    /// Human-readable names of optional data the dataset lacks
+    pub fn missing(&self) -> Vec<&'static str> {
+        let mut missing = Vec::new();
+        if !self.sample_points {
+            missing.push("timing point sample settings (using [General] defaults)");
+        }
+        if !self.hit_samples {
+            missing.push("hit object hitsounds");
+        }
+        if !self.asset_index {
+            missing.push("asset index (falling back to directory walk)");
+        }
+        if !self.events {
+            missing.push("raw [Events] lines (background colour transformations)");
+        }
+        if !self.slider_edge_samples {
+            missing.push("slider edge sounds");
+        }
+        if !self.literal_slider_anchors {
+            missing.push("slider anchors as written (re-encoded from curve segments)");
+        }
+        missing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_rows;
+
+    const TABLE_FILES: [(&str, &[u8]); 2] =
+        [("beatmaps.parquet", b"PAR1 beatmaps PAR1"), ("hit_objects.parquet", b"PAR1 objects PAR1")];
+
+    /// Dataset with two table files and a manifest recording their checksums
+    fn dataset_with_manifest() -> std::path::PathBuf {
+        let dir = test_rows::test_dir();
+        let mut files = serde_json::Map::new();
+        for (name, content) in TABLE_FILES {
+            std::fs::write(dir.join(name), content).unwrap();
+            let blake3 = blake3::hash(content).to_hex().to_string();
+            files.insert(name.to_string(), serde_json::json!({ "size": content.len(), "blake3": blake3 }));
+        }
+        let manifest = serde_json::json!({ "schema_version": 18, "tables": {}, "files": files });
+        std::fs::write(dir.join(MANIFEST_FILE), manifest.to_string()).unwrap();
+        dir
+    }
+
+    fn verify(dir: &Path) -> Result<usize> {
+        DatasetManifest::load(dir).unwrap().unwrap().verify(dir)
+    }
+
+    fn assert_violation_names(result: Result<usize>, file: &str) {
+        match result {
+            Err(ReconstructError::IntegrityViolation(message)) => assert!(message.contains(file), "{message}"),
+            other => panic!("expected an integrity violation for {file}, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn intact_files_verify() {
+        assert_eq!(verify(&dataset_with_manifest()).unwrap(), 2);
+    }
+
+    #[test]
+    fn a_flipped_byte_is_caught_by_the_hash() {
+        let dir = dataset_with_manifest();
+        let path = dir.join("hit_objects.parquet");
+        let mut content = std::fs::read(&path).unwrap();
+        content[6] ^= 0x01;
+        std::fs::write(&path, content).unwrap();
+        assert_violation_names(verify(&dir), "hit_objects.parquet");
+    }
+
+    #[test]
+    fn a_truncated_file_is_caught_by_its_size() {
+        let dir = dataset_with_manifest();
+        let path = dir.join("beatmaps.parquet");
+        let content = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &content[..content.len() - 4]).unwrap();
+        assert_violation_names(verify(&dir), "beatmaps.parquet");
+    }
+
+    #[test]
+    fn a_deleted_file_is_reported_missing() {
+        let dir = dataset_with_manifest();
+        std::fs::remove_file(dir.join("hit_objects.parquet")).unwrap();
+        assert_violation_names(verify(&dir), "hit_objects.parquet");
+    }
+}
@@ -0,0 +1,220 @@
+//! One wide table of beatmaps joined with their enriched API data
+//!
+//! `beatmap_enriched` (one row per API beatmap) is loaded as the build side of a
+//! hash join keyed by `beatmap_id`, `folder_id` and `osu_file`; `beatmaps` is
+//! streamed through it batch by batch, so memory grows with the enriched table
+//! only. Every beatmaps row is kept, with null enriched columns when the
+//! difficulty wasn't enriched.
+
+use arrow::array::{Array, ArrayRef, Int64Array, StringArray, UInt32Array};
+use arrow::compute::{cast, concat_batches, take};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::error::{IoContext, ReconstructError, Result};
+use crate::reader::{open_batch_reader, ParquetReader};
+
+/// File stem of the merged table
+pub const MERGED_TABLE: &str = "beatmaps_enriched_full";
+
+/// Columns both tables share and are joined on; the merged table keeps the beatmaps copy
+const JOIN_KEYS: [&str; 3] = ["beatmap_id", "folder_id", "osu_file"];
+
+/// Prefix of enriched columns whose name is already taken by a beatmaps column
+const ENRICHED_PREFIX: &str = "enriched_";
+
+/// Write `beatmaps` left-joined with `beatmap_enriched` to a parquet file at `output`
+///
+/// The merged table has every beatmaps column followed by every enriched column
+/// except the join keys. Enriched columns become nullable, and those sharing a
+/// name with a beatmaps column (`mode`, `version`, `ar`, ...) get an `enriched_`
+/// prefix. Returns the number of rows written, one per beatmaps row.
+pub fn merge_enriched(reader: &ParquetReader, output: &Path) -> Result<usize> {
+    let enriched_path = reader.table_path("beatmap_enriched");
+    if !enriched_path.exists() {
+        return Err(ReconstructError::IntegrityViolation(format!(
+            "No beatmap_enriched table in the dataset ({}); run osu-enricher first",
+            enriched_path.display()
+        )));
+    }
+
+    // Build side: the whole enriched table, indexed by join key
+    let enriched_reader = open_batch_reader(&enriched_path)?;
+    let enriched_schema = enriched_reader.schema();
+    let batches = enriched_reader.collect::<std::result::Result<Vec<_>, _>>()?;
+    let enriched = concat_batches(&enriched_schema, &batches)?;
+    drop(batches);
+    let enriched_keys = JoinKeys::from_batch(&enriched)?;
+    let mut index: HashMap<(i64, &str, &str), u32> = HashMap::with_capacity(enriched.num_rows());
+    for row in 0..enriched.num_rows() {
+        if let Some(key) = enriched_keys.get(row) {
+            // The enricher keeps one row per beatmap_id; keep the first of any duplicates
+            index.entry(key).or_insert(row as u32);
+        }
+    }
+
+    // Probe side: stream beatmaps and gather the matching enriched rows
+    let beatmaps_reader = open_batch_reader(&reader.table_path("beatmaps"))?;
+    let (schema, enriched_columns) = merged_schema(&beatmaps_reader.schema(), &enriched_schema);
+
+    let file = File::create(output).io_context(format!("Failed to create {}", output.display()))?;
+    let props = WriterProperties::builder().set_compression(Compression::SNAPPY).build();
+    let mut writer = ArrowWriter::try_new(file, schema.clone(), Some(props))?;
+    let mut rows = 0;
+
+    for batch in beatmaps_reader {
+        let batch = batch?;
+        let keys = JoinKeys::from_batch(&batch)?;
+        let matches: UInt32Array = (0..batch.num_rows())
+            .map(|row| keys.get(row).and_then(|key| index.get(&key).copied()))
+            .collect();
+
+        let mut columns: Vec<ArrayRef> = batch.columns().to_vec();
+        for &column in &enriched_columns {
+            columns.push(take(enriched.column(column).as_ref(), &matches, None)?);
+        }
+        writer.write(&RecordBatch::try_new(schema.clone(), columns)?)?;
+        rows += batch.num_rows();
+    }
+
+    writer.close()?;
+    Ok(rows)
+}
+
+/// Merged schema and the indices of the enriched columns it appends
+fn merged_schema(beatmaps: &Schema, enriched: &Schema) -> (SchemaRef, Vec<usize>) {
+    let mut fields: Vec<Field> = beatmaps.fields().iter().map(|field| field.as_ref().clone()).collect();
+    let mut enriched_columns = Vec::new();
+    for (column, field) in enriched.fields().iter().enumerate() {
+        if JOIN_KEYS.contains(&field.name().as_str()) {
+            continue;
+        }
+        let name = if beatmaps.field_with_name(field.name()).is_ok() {
+            format!("{}{}", ENRICHED_PREFIX, field.name())
+        } else {
+            field.name().clone()
+        };
+        fields.push(field.as_ref().clone().with_name(name).with_nullable(true));
+        enriched_columns.push(column);
+    }
+    (Arc::new(Schema::new(fields)), enriched_columns)
+}
+
+/// Join key columns of one batch; `beatmap_id` is widened since the builder
+/// stores it as Int32 and the enricher as UInt32
+struct JoinKeys {
+    beatmap_id: Int64Array,
+    folder_id: StringArray,
+    osu_file: StringArray,
+}
+
+impl JoinKeys {
+    fn from_batch(batch: &RecordBatch) -> Result<Self> {
+        let column = |name: &str| {
+            batch
+                .column_by_name(name)
+                .ok_or_else(|| ReconstructError::MissingColumn(name.to_string()))
+        };
+        let beatmap_id = cast(column("beatmap_id")?, &DataType::Int64)?;
+        let string = |name: &str| -> Result<StringArray> {
+            column(name)?
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .cloned()
+                .ok_or_else(|| ReconstructError::SchemaMismatch { column: name.to_string(), expected: "StringArray" })
+        };
+        Ok(Self {
+            beatmap_id: beatmap_id.as_any().downcast_ref::<Int64Array>().cloned().ok_or_else(|| {
+                ReconstructError::SchemaMismatch { column: "beatmap_id".to_string(), expected: "Int64Array" }
+            })?,
+            folder_id: string("folder_id")?,
+            osu_file: string("osu_file")?,
+        })
+    }
+
+    /// Key of a row, `None` if any part is null
+    fn get(&self, row: usize) -> Option<(i64, &str, &str)> {
+        if self.beatmap_id.is_null(row) || self.folder_id.is_null(row) || self.osu_file.is_null(row) {
+            return None;
+        }
+        Some((self.beatmap_id.value(row), self.folder_id.value(row), self.osu_file.value(row)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_rows;
+    use arrow::array::{Float32Array, Int32Array};
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    fn strings(values: &[&str]) -> ArrayRef {
+        Arc::new(StringArray::from(values.to_vec()))
+    }
+
+    #[test]
+    fn beatmaps_are_left_joined_with_their_enriched_rows() {
+        let dir = test_rows::test_dir();
+        let beatmaps = RecordBatch::try_from_iter([
+            ("folder_id", strings(&["f", "f", "g"])),
+            ("osu_file", strings(&["a.osu", "b.osu", "c.osu"])),
+            ("beatmap_id", Arc::new(Int32Array::from(vec![1, 2, 3])) as ArrayRef),
+            ("version", strings(&["Easy", "Hard", "Insane"])),
+        ])
+        .unwrap();
+        test_rows::write_table(&dir, "beatmaps", &[beatmaps]);
+        // The enricher's UInt32 ids; b.osu wasn't enriched and 9 has no beatmaps row
+        let enriched = RecordBatch::try_from_iter([
+            ("beatmap_id", Arc::new(UInt32Array::from(vec![3, 9, 1])) as ArrayRef),
+            ("folder_id", strings(&["g", "x", "f"])),
+            ("osu_file", strings(&["c.osu", "x.osu", "a.osu"])),
+            ("version", strings(&["Insane (API)", "X", "Easy (API)"])),
+            ("stars_api", Arc::new(Float32Array::from(vec![5.5, 1.0, 1.5])) as ArrayRef),
+        ])
+        .unwrap();
+        test_rows::write_table(&dir, "beatmap_enriched", &[enriched]);
+
+        let output = dir.join("merged.parquet");
+        let rows = merge_enriched(&ParquetReader::new(&dir), &output).unwrap();
+        assert_eq!(rows, 3);
+
+        let batches: Vec<RecordBatch> = ParquetRecordBatchReaderBuilder::try_new(File::open(&output).unwrap())
+            .unwrap()
+            .build()
+            .unwrap()
+            .collect::<std::result::Result<_, _>>()
+            .unwrap();
+        let merged = concat_batches(&batches[0].schema(), &batches).unwrap();
+        assert_eq!(merged.num_rows(), 3);
+
+        let schema = merged.schema();
+        let names: Vec<&str> = schema.fields().iter().map(|f| f.name().as_str()).collect();
+        assert_eq!(names, ["folder_id", "osu_file", "beatmap_id", "version", "enriched_version", "stars_api"]);
+
+        let column = |name: &str| merged.column_by_name(name).unwrap().clone();
+        let enriched_version = column("enriched_version");
+        let enriched_version = enriched_version.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(enriched_version.iter().collect::<Vec<_>>(), [Some("Easy (API)"), None, Some("Insane (API)")]);
+        let stars = column("stars_api");
+        let stars = stars.as_any().downcast_ref::<Float32Array>().unwrap();
+        assert_eq!(stars.iter().collect::<Vec<_>>(), [Some(1.5), None, Some(5.5)]);
+        // The beatmaps copy of a shared column is untouched
+        let version = column("version");
+        let version = version.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(version.iter().collect::<Vec<_>>(), [Some("Easy"), Some("Hard"), Some("Insane")]);
+    }
+
+    #[test]
+    fn merging_without_an_enriched_table_is_refused() {
+        let dir = test_rows::test_dir();
+        let err = merge_enriched(&ParquetReader::new(&dir), &dir.join("merged.parquet")).unwrap_err();
+        assert!(matches!(err, ReconstructError::IntegrityViolation(_)));
+    }
+}
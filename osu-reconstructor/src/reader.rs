@@ -2,20 +2,34 @@
 //! 
 //! This module uses Arrow's filter capabilities to only keep rows that match
 //! the specified folder_id, significantly reducing memory usage.
+//!
+//! Tables written with `--format arrow` (`.arrow`/`.feather` Arrow IPC files)
+//! are read the same way; the reader is picked by file extension.
 
-use anyhow::{Context, Result};
 use arrow::array::{
-    Array, AsArray, BooleanArray, Float32Array, Float64Array, Int32Array, RecordBatch, StringArray,
+    Array, AsArray, BooleanArray, Float32Array, Float64Array, Int32Array, Int64Array, ListArray,
+    RecordBatch, StringArray,
 };
 use arrow::compute::kernels::cmp::eq;
 use arrow::compute::filter_record_batch;
 use arrow::datatypes::DataType;
+use arrow::ipc::reader::FileReader;
+use arrow::record_batch::RecordBatchReader;
 use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::file::properties::ReaderProperties;
+use parquet::file::reader::FileReader as ParquetFileReader;
+use parquet::file::serialized_reader::{ReadOptionsBuilder, SerializedFileReader};
+use std::collections::BTreeMap;
 use std::fs::File;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
+use crate::error::{IoContext, ReconstructError, Result};
+use crate::manifest::{DatasetFeatures, DatasetManifest};
 use crate::types::*;
 
+/// Extensions of Arrow IPC table files, checked after `.parquet`
+const TABLE_IPC_EXTENSIONS: [&str; 2] = ["arrow", "feather"];
+
 /// Reader for loading parquet files into Dataset
 pub struct ParquetReader {
     dataset_path: std::path::PathBuf,
@@ -31,23 +45,31 @@ impl ParquetReader {
 
     /// Load just the unique folder IDs from beatmaps.parquet
     /// 
-    /// This is memory-efficient as it reads in batches
+    /// This is memory-efficient as it reads in batches. A dataset without a
+    /// beatmaps table, or with an empty one, has no folders; a beatmaps file that
+    /// can't be read or lacks a string `folder_id` column is an error rather than
+    /// an empty result.
     pub fn load_folder_ids(&self) -> Result<Vec<String>> {
-        let path = self.dataset_path.join("beatmaps.parquet");
-        let file = File::open(&path).context(format!("Failed to open {}", path.display()))?;
-        let builder = ParquetRecordBatchReaderBuilder::try_new(file)?;
-        let reader = builder.with_batch_size(8192).build()?;
+        let path = self.table_path("beatmaps");
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let reader = open_batch_reader(&path)?;
+        match reader.schema().field_with_name("folder_id").map(|field| field.data_type().clone()) {
+            Ok(DataType::Utf8) => {}
+            Ok(_) => {
+                return Err(ReconstructError::SchemaMismatch { column: "folder_id".to_string(), expected: "StringArray" })
+            }
+            Err(_) => return Err(ReconstructError::MissingColumn("folder_id".to_string())),
+        }
         
         let mut ids = std::collections::HashSet::new();
         for batch_result in reader {
             let batch = batch_result?;
-            if let Some(col) = batch.column_by_name("folder_id") {
-                if let Some(arr) = col.as_any().downcast_ref::<StringArray>() {
-                    for i in 0..arr.len() {
-                        if !arr.is_null(i) {
-                            ids.insert(arr.value(i).to_string());
-                        }
-                    }
+            let arr = get_string_array(&batch, "folder_id")?;
+            for i in 0..arr.len() {
+                if !arr.is_null(i) {
+                    ids.insert(arr.value(i).to_string());
                 }
             }
         }
@@ -57,502 +79,377 @@ impl ParquetReader {
         Ok(sorted)
     }
 
-    /// Load dataset for a specific folder only using row-level filtering
-    /// 
-    /// This only loads rows that match the folder_id, using Arrow's filter
-    /// capabilities to minimize memory usage.
-    pub fn load_dataset_for_folder(&self, folder_id: &str) -> Result<Dataset> {
-        let mut dataset = Dataset::default();
-        
-        dataset.beatmaps = self.load_beatmaps_filtered(folder_id)?;
-        dataset.hit_objects = self.load_hit_objects_filtered(folder_id)?;
-        dataset.timing_points = self.load_timing_points_filtered(folder_id)?;
-        dataset.storyboard_elements = self.load_storyboard_elements_filtered(folder_id)?;
-        dataset.storyboard_commands = self.load_storyboard_commands_filtered(folder_id)?;
-        dataset.slider_control_points = self.load_slider_control_points_filtered(folder_id)?;
-        dataset.slider_data = self.load_slider_data_filtered(folder_id)?;
-        dataset.breaks = self.load_breaks_filtered(folder_id)?;
-        dataset.combo_colors = self.load_combo_colors_filtered(folder_id)?;
-        dataset.hit_samples = self.load_hit_samples_filtered(folder_id)?;
-        dataset.storyboard_loops = self.load_storyboard_loops_filtered(folder_id)?;
-        dataset.storyboard_triggers = self.load_storyboard_triggers_filtered(folder_id)?;
-        
-        Ok(dataset)
+    /// Path of a table file: `<table>.parquet`, or its Arrow IPC `.arrow`/`.feather` counterpart
+    ///
+    /// Falls back to the parquet path when none exists, so errors name the usual file.
+    pub(crate) fn table_path(&self, table: &str) -> PathBuf {
+        let parquet = self.dataset_path.join(format!("{}.parquet", table));
+        if parquet.exists() {
+            return parquet;
+        }
+        TABLE_IPC_EXTENSIONS
+            .iter()
+            .map(|ext| self.dataset_path.join(format!("{}.{}", table, ext)))
+            .find(|path| path.exists())
+            .unwrap_or(parquet)
     }
 
-    // ============ Filtered loading methods ============
-
-    fn load_beatmaps_filtered(&self, target_folder: &str) -> Result<Vec<BeatmapRow>> {
-        let path = self.dataset_path.join("beatmaps.parquet");
-        let mut rows = Vec::new();
-        
-        for batch in read_filtered_batches(&path, "folder_id", target_folder)? {
-            let folder_id = get_string_array(&batch, "folder_id")?;
-            let osu_file = get_string_array(&batch, "osu_file")?;
-            let format_version = get_i32_array(&batch, "format_version")?;
-            let audio_file = get_string_array(&batch, "audio_file")?;
-            let audio_lead_in = get_f64_array(&batch, "audio_lead_in")?;
-            let preview_time = get_i32_array(&batch, "preview_time")?;
-            let default_sample_bank = get_i32_array(&batch, "default_sample_bank")?;
-            let default_sample_volume = get_i32_array(&batch, "default_sample_volume")?;
-            let stack_leniency = get_f32_array(&batch, "stack_leniency")?;
-            let mode = get_i32_array(&batch, "mode")?;
-            let letterbox_in_breaks = get_bool_array(&batch, "letterbox_in_breaks")?;
-            let special_style = get_bool_array(&batch, "special_style")?;
-            let widescreen_storyboard = get_bool_array(&batch, "widescreen_storyboard")?;
-            let epilepsy_warning = get_bool_array(&batch, "epilepsy_warning")?;
-            let samples_match_playback_rate = get_bool_array(&batch, "samples_match_playback_rate")?;
-            let countdown = get_i32_array(&batch, "countdown")?;
-            let countdown_offset = get_i32_array(&batch, "countdown_offset")?;
-            let bookmarks = get_string_array(&batch, "bookmarks")?;
-            let distance_spacing = get_f64_array(&batch, "distance_spacing")?;
-            let beat_divisor = get_i32_array(&batch, "beat_divisor")?;
-            let grid_size = get_i32_array(&batch, "grid_size")?;
-            let timeline_zoom = get_f64_array(&batch, "timeline_zoom")?;
-            let title = get_string_array(&batch, "title")?;
-            let title_unicode = get_string_array(&batch, "title_unicode")?;
-            let artist = get_string_array(&batch, "artist")?;
-            let artist_unicode = get_string_array(&batch, "artist_unicode")?;
-            let creator = get_string_array(&batch, "creator")?;
-            let version = get_string_array(&batch, "version")?;
-            let source = get_string_array(&batch, "source")?;
-            let tags = get_string_array(&batch, "tags")?;
-            let beatmap_id = get_i32_array(&batch, "beatmap_id")?;
-            let beatmap_set_id = get_i32_array(&batch, "beatmap_set_id")?;
-            let hp_drain_rate = get_f32_array(&batch, "hp_drain_rate")?;
-            let circle_size = get_f32_array(&batch, "circle_size")?;
-            let overall_difficulty = get_f32_array(&batch, "overall_difficulty")?;
-            let approach_rate = get_f32_array(&batch, "approach_rate")?;
-            let slider_multiplier = get_f64_array(&batch, "slider_multiplier")?;
-            let slider_tick_rate = get_f64_array(&batch, "slider_tick_rate")?;
-            let background_file = get_string_array(&batch, "background_file")?;
-            let audio_path = get_string_array(&batch, "audio_path")?;
-            let background_path = get_string_array(&batch, "background_path")?;
-            
-            for i in 0..batch.num_rows() {
-                rows.push(BeatmapRow {
-                    folder_id: folder_id.value(i).to_string(),
-                    osu_file: osu_file.value(i).to_string(),
-                    format_version: format_version.value(i),
-                    audio_file: audio_file.value(i).to_string(),
-                    audio_lead_in: audio_lead_in.value(i),
-                    preview_time: preview_time.value(i),
-                    default_sample_bank: default_sample_bank.value(i),
-                    default_sample_volume: default_sample_volume.value(i),
-                    stack_leniency: stack_leniency.value(i),
-                    mode: mode.value(i),
-                    letterbox_in_breaks: letterbox_in_breaks.value(i),
-                    special_style: special_style.value(i),
-                    widescreen_storyboard: widescreen_storyboard.value(i),
-                    epilepsy_warning: epilepsy_warning.value(i),
-                    samples_match_playback_rate: samples_match_playback_rate.value(i),
-                    countdown: countdown.value(i),
-                    countdown_offset: countdown_offset.value(i),
-                    bookmarks: bookmarks.value(i).to_string(),
-                    distance_spacing: distance_spacing.value(i),
-                    beat_divisor: beat_divisor.value(i),
-                    grid_size: grid_size.value(i),
-                    timeline_zoom: timeline_zoom.value(i),
-                    title: title.value(i).to_string(),
-                    title_unicode: title_unicode.value(i).to_string(),
-                    artist: artist.value(i).to_string(),
-                    artist_unicode: artist_unicode.value(i).to_string(),
-                    creator: creator.value(i).to_string(),
-                    version: version.value(i).to_string(),
-                    source: source.value(i).to_string(),
-                    tags: tags.value(i).to_string(),
-                    beatmap_id: beatmap_id.value(i),
-                    beatmap_set_id: beatmap_set_id.value(i),
-                    hp_drain_rate: hp_drain_rate.value(i),
-                    circle_size: circle_size.value(i),
-                    overall_difficulty: overall_difficulty.value(i),
-                    approach_rate: approach_rate.value(i),
-                    slider_multiplier: slider_multiplier.value(i),
-                    slider_tick_rate: slider_tick_rate.value(i),
-                    background_file: background_file.value(i).to_string(),
-                    audio_path: audio_path.value(i).to_string(),
-                    background_path: background_path.value(i).to_string(),
-                });
+    /// Stems of the table files in the dataset directory (parquet or Arrow IPC), sorted
+    ///
+    /// Lets consumers adapt to whatever a dataset contains, e.g. the optional
+    /// `assets`, `events` or `extra_fields` tables, or tables written by newer builders.
+    pub fn available_tables(&self) -> Result<Vec<String>> {
+        let mut names = std::collections::BTreeSet::new();
+        let context = format!("Failed to read {}", self.dataset_path.display());
+        let entries = std::fs::read_dir(&self.dataset_path).io_context(context.as_str())?;
+        for entry in entries {
+            let path = entry.io_context(context.as_str())?.path();
+            let is_table = path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| ext == "parquet" || TABLE_IPC_EXTENSIONS.contains(&ext));
+            if let (true, Some(stem)) = (is_table, path.file_stem()) {
+                names.insert(stem.to_string_lossy().to_string());
             }
         }
-        Ok(rows)
+        Ok(names.into_iter().collect())
     }
 
-    fn load_hit_objects_filtered(&self, target_folder: &str) -> Result<Vec<HitObjectRow>> {
-        let path = self.dataset_path.join("hit_objects.parquet");
-        let mut rows = Vec::new();
-        
-        for batch in read_filtered_batches(&path, "folder_id", target_folder)? {
-            let folder_id = get_string_array(&batch, "folder_id")?;
-            let osu_file = get_string_array(&batch, "osu_file")?;
-            let index = get_i32_array(&batch, "index")?;
-            let start_time = get_f64_array(&batch, "start_time")?;
-            let object_type = get_string_array(&batch, "object_type")?;
-            let pos_x = get_nullable_i32_array(&batch, "pos_x")?;
-            let pos_y = get_nullable_i32_array(&batch, "pos_y")?;
-            let new_combo = get_bool_array(&batch, "new_combo")?;
-            let combo_offset = get_i32_array(&batch, "combo_offset")?;
-            let curve_type = get_nullable_string_array(&batch, "curve_type")?;
-            let slides = get_nullable_i32_array(&batch, "slides")?;
-            let length = get_nullable_f64_array(&batch, "length")?;
-            let end_time = get_nullable_f64_array(&batch, "end_time")?;
-            
-            for i in 0..batch.num_rows() {
-                rows.push(HitObjectRow {
-                    folder_id: folder_id.value(i).to_string(),
-                    osu_file: osu_file.value(i).to_string(),
-                    index: index.value(i),
-                    start_time: start_time.value(i),
-                    object_type: object_type.value(i).to_string(),
-                    pos_x: pos_x.get(i),
-                    pos_y: pos_y.get(i),
-                    new_combo: new_combo.value(i),
-                    combo_offset: combo_offset.value(i),
-                    curve_type: curve_type.get(i),
-                    slides: slides.get(i),
-                    length: length.get(i),
-                    end_time: end_time.get(i),
-                });
-            }
-        }
-        Ok(rows)
+    /// Load the dataset manifest, if the dataset has one
+    pub fn manifest(&self) -> Result<Option<DatasetManifest>> {
+        DatasetManifest::load(&self.dataset_path)
     }
 
-    fn load_timing_points_filtered(&self, target_folder: &str) -> Result<Vec<TimingPointRow>> {
-        let path = self.dataset_path.join("timing_points.parquet");
-        let mut rows = Vec::new();
-        
-        for batch in read_filtered_batches(&path, "folder_id", target_folder)? {
-            let folder_id = get_string_array(&batch, "folder_id")?;
-            let osu_file = get_string_array(&batch, "osu_file")?;
-            let time = get_f64_array(&batch, "time")?;
-            let point_type = get_string_array(&batch, "point_type")?;
-            let beat_length = get_nullable_f64_array(&batch, "beat_length")?;
-            let time_signature = get_nullable_string_array(&batch, "time_signature")?;
-            let slider_velocity = get_nullable_f64_array(&batch, "slider_velocity")?;
-            let kiai = get_nullable_bool_array(&batch, "kiai")?;
-            let sample_bank = get_nullable_string_array(&batch, "sample_bank")?;
-            let sample_volume = get_nullable_i32_array(&batch, "sample_volume")?;
-            
-            for i in 0..batch.num_rows() {
-                rows.push(TimingPointRow {
-                    folder_id: folder_id.value(i).to_string(),
-                    osu_file: osu_file.value(i).to_string(),
-                    time: time.value(i),
-                    point_type: point_type.value(i).to_string(),
-                    beat_length: beat_length.get(i),
-                    time_signature: time_signature.get(i),
-                    slider_velocity: slider_velocity.get(i),
-                    kiai: kiai.get(i),
-                    sample_bank: sample_bank.get(i),
-                    sample_volume: sample_volume.get(i),
-                });
-            }
+    /// Verify the table files against the checksums in the manifest (`--verify`)
+    ///
+    /// Returns the number of files checked; datasets without a manifest or
+    /// without recorded checksums are an error, since nothing can be verified.
+    pub fn verify_integrity(&self) -> Result<usize> {
+        let manifest = self.manifest()?.ok_or_else(|| {
+            ReconstructError::IntegrityViolation(format!(
+                "{} has no manifest.json to verify against",
+                self.dataset_path.display()
+            ))
+        })?;
+        if manifest.files.is_empty() {
+            return Err(ReconstructError::IntegrityViolation(
+                "manifest.json predates file checksums; rebuild the dataset to record them".to_string(),
+            ));
         }
-        Ok(rows)
+        manifest.verify(&self.dataset_path)
     }
 
-    fn load_storyboard_elements_filtered(&self, target_folder: &str) -> Result<Vec<StoryboardElementRow>> {
-        let path = self.dataset_path.join("storyboard_elements.parquet");
-        let mut rows = Vec::new();
-        
-        for batch in read_filtered_batches(&path, "folder_id", target_folder)? {
-            let folder_id = get_string_array(&batch, "folder_id")?;
-            let source_file = get_string_array(&batch, "source_file")?;
-            let element_index = get_i32_array(&batch, "element_index")?;
-            let layer_name = get_string_array(&batch, "layer_name")?;
-            let element_path = get_string_array(&batch, "element_path")?;
-            let element_type = get_string_array(&batch, "element_type")?;
-            let origin = get_string_array(&batch, "origin")?;
-            let initial_pos_x = get_f32_array(&batch, "initial_pos_x")?;
-            let initial_pos_y = get_f32_array(&batch, "initial_pos_y")?;
-            let frame_count = get_nullable_i32_array(&batch, "frame_count")?;
-            let frame_delay = get_nullable_f64_array(&batch, "frame_delay")?;
-            let loop_type = get_nullable_string_array(&batch, "loop_type")?;
-            let is_embedded = get_bool_array(&batch, "is_embedded")?;
-            
-            for i in 0..batch.num_rows() {
-                rows.push(StoryboardElementRow {
-                    folder_id: folder_id.value(i).to_string(),
-                    source_file: source_file.value(i).to_string(),
-                    element_index: element_index.value(i),
-                    layer_name: layer_name.value(i).to_string(),
-                    element_path: element_path.value(i).to_string(),
-                    element_type: element_type.value(i).to_string(),
-                    origin: origin.value(i).to_string(),
-                    initial_pos_x: initial_pos_x.value(i),
-                    initial_pos_y: initial_pos_y.value(i),
-                    frame_count: frame_count.get(i),
-                    frame_delay: frame_delay.get(i),
-                    loop_type: loop_type.get(i),
-                    is_embedded: is_embedded.value(i),
-                });
-            }
-        }
-        Ok(rows)
+    /// Detect which optional data this dataset contains
+    ///
+    /// Uses the manifest schema version when present and falls back to
+    /// inspecting parquet schemas for datasets written without a manifest.
+    pub fn detect_features(&self) -> Result<DatasetFeatures> {
+        let schema_version = self.manifest()?.map(|m| m.schema_version);
+
+        let sample_points = match schema_version {
+            Some(version) => version >= 2,
+            None => self.has_column("timing_points", "sample_index")?,
+        };
+
+        // Pre-manifest datasets all predate v3, so their end_time is a duration
+        let absolute_end_times = schema_version.is_some_and(|version| version >= 3);
+
+        // Node samples were added in v6, after the manifest
+        let slider_edge_samples = schema_version.is_some_and(|version| version >= 6);
+        let literal_slider_anchors = schema_version.is_some_and(|version| version >= 18);
+
+        Ok(DatasetFeatures {
+            schema_version,
+            sample_points,
+            hit_samples: self.table_path("hit_samples").exists(),
+            asset_index: self.table_path("assets").exists(),
+            events: self.table_path("events").exists(),
+            extra_fields: self.table_path("extra_fields").exists(),
+            slider_edge_samples,
+            absolute_end_times,
+            literal_slider_anchors,
+        })
     }
 
-    fn load_storyboard_commands_filtered(&self, target_folder: &str) -> Result<Vec<StoryboardCommandRow>> {
-        let path = self.dataset_path.join("storyboard_commands.parquet");
-        let mut rows = Vec::new();
-        
-        for batch in read_filtered_batches(&path, "folder_id", target_folder)? {
-            let folder_id = get_string_array(&batch, "folder_id")?;
-            let source_file = get_string_array(&batch, "source_file")?;
-            let element_index = get_i32_array(&batch, "element_index")?;
-            let command_type = get_string_array(&batch, "command_type")?;
-            let start_time = get_f64_array(&batch, "start_time")?;
-            let end_time = get_f64_array(&batch, "end_time")?;
-            let start_value = get_string_array(&batch, "start_value")?;
-            let end_value = get_string_array(&batch, "end_value")?;
-            let easing = get_i32_array(&batch, "easing")?;
-            let is_embedded = get_bool_array(&batch, "is_embedded")?;
-            
-            for i in 0..batch.num_rows() {
-                rows.push(StoryboardCommandRow {
-                    folder_id: folder_id.value(i).to_string(),
-                    source_file: source_file.value(i).to_string(),
-                    element_index: element_index.value(i),
-                    command_type: command_type.value(i).to_string(),
-                    start_time: start_time.value(i),
-                    end_time: end_time.value(i),
-                    start_value: start_value.value(i).to_string(),
-                    end_value: end_value.value(i).to_string(),
-                    easing: easing.value(i),
-                    is_embedded: is_embedded.value(i),
-                });
-            }
-        }
-        Ok(rows)
+    /// Arrow schema of a table file, read from its parquet footer or IPC header
+    pub fn table_schema(&self, table: &str) -> Result<arrow::datatypes::SchemaRef> {
+        Ok(open_batch_reader(&self.table_path(table))?.schema())
     }
 
-    fn load_slider_control_points_filtered(&self, target_folder: &str) -> Result<Vec<SliderControlPointRow>> {
-        let path = self.dataset_path.join("slider_control_points.parquet");
-        let mut rows = Vec::new();
-        
-        for batch in read_filtered_batches(&path, "folder_id", target_folder)? {
-            let folder_id = get_string_array(&batch, "folder_id")?;
-            let osu_file = get_string_array(&batch, "osu_file")?;
-            let hit_object_index = get_i32_array(&batch, "hit_object_index")?;
-            let point_index = get_i32_array(&batch, "point_index")?;
-            let pos_x = get_f32_array(&batch, "pos_x")?;
-            let pos_y = get_f32_array(&batch, "pos_y")?;
-            let path_type = get_nullable_string_array(&batch, "path_type")?;
-            
-            for i in 0..batch.num_rows() {
-                rows.push(SliderControlPointRow {
-                    folder_id: folder_id.value(i).to_string(),
-                    osu_file: osu_file.value(i).to_string(),
-                    hit_object_index: hit_object_index.value(i),
-                    point_index: point_index.value(i),
-                    pos_x: pos_x.value(i),
-                    pos_y: pos_y.value(i),
-                    path_type: path_type.get(i),
-                });
-            }
+    /// Check whether a table file exists and has the given column
+    ///
+    /// Only the file's schema (parquet footer or IPC header) is read, not its data.
+    pub fn has_column(&self, table: &str, column: &str) -> Result<bool> {
+        let path = self.table_path(table);
+        if !path.exists() {
+            return Ok(false);
         }
-        Ok(rows)
+        Ok(open_batch_reader(&path)?.schema().column_with_name(column).is_some())
     }
 
-    fn load_slider_data_filtered(&self, target_folder: &str) -> Result<Vec<SliderDataRow>> {
-        let path = self.dataset_path.join("slider_data.parquet");
-        let mut rows = Vec::new();
-        
-        for batch in read_filtered_batches(&path, "folder_id", target_folder)? {
-            let folder_id = get_string_array(&batch, "folder_id")?;
-            let osu_file = get_string_array(&batch, "osu_file")?;
-            let hit_object_index = get_i32_array(&batch, "hit_object_index")?;
-            let repeat_count = get_i32_array(&batch, "repeat_count")?;
-            let velocity = get_f64_array(&batch, "velocity")?;
-            let expected_dist = get_nullable_f64_array(&batch, "expected_dist")?;
-            
-            for i in 0..batch.num_rows() {
-                rows.push(SliderDataRow {
-                    folder_id: folder_id.value(i).to_string(),
-                    osu_file: osu_file.value(i).to_string(),
-                    hit_object_index: hit_object_index.value(i),
-                    repeat_count: repeat_count.value(i),
-                    velocity: velocity.value(i),
-                    expected_dist: expected_dist.get(i),
-                });
-            }
+    /// Build provenance stored in a table file's footer
+    ///
+    /// The builder writes `osu_dataset.*` keys (builder version, git hash, schema
+    /// version, build time, input path hash); files from older builders return an
+    /// empty map. Appended files carry the provenance of the latest build. Only
+    /// the footer is read, not the data.
+    pub fn provenance(&self, table: &str) -> Result<BTreeMap<String, String>> {
+        let path = self.table_path(table);
+        let file = File::open(&path).io_context(format!("Failed to open {}", path.display()))?;
+
+        if is_ipc_path(&path) {
+            let reader = FileReader::try_new(file, None)?;
+            return Ok(reader.custom_metadata().iter().map(|(k, v)| (k.clone(), v.clone())).collect());
         }
-        Ok(rows)
+
+        let reader = SerializedFileReader::new(file)?;
+        let key_values = reader.metadata().file_metadata().key_value_metadata().cloned().unwrap_or_default();
+        Ok(key_values
+            .into_iter()
+            // The Arrow writer embeds its serialized schema here as well
+            .filter(|kv| kv.key != "ARROW:schema")
+            .map(|kv| (kv.key, kv.value.unwrap_or_default()))
+            .collect())
     }
 
-    fn load_breaks_filtered(&self, target_folder: &str) -> Result<Vec<BreakRow>> {
-        let path = self.dataset_path.join("breaks.parquet");
-        let mut rows = Vec::new();
+    /// Load dataset for a specific folder only using row-level filtering
+    /// 
+    /// This only loads rows that match the folder_id, using Arrow's filter
+    /// capabilities to minimize memory usage.
+    pub fn load_dataset_for_folder(&self, folder_id: &str) -> Result<Dataset> {
+        self.load_dataset(Some(folder_id))
+    }
+
+    /// Load every row of every table into memory
+    ///
+    /// Each file is read straight through without the folder filter. Memory use
+    /// grows with the whole dataset (hit objects and storyboard commands alone can
+    /// reach tens of GB on a full corpus), so only use this for small datasets and
+    /// tests; prefer `load_dataset_for_folder` or the `iter_*` methods otherwise.
+    pub fn load_all(&self) -> Result<Dataset> {
+        self.load_dataset(None)
+    }
+
+    fn load_dataset(&self, folder_id: Option<&str>) -> Result<Dataset> {
+        let mut dataset = Dataset::default();
+        dataset.features = self.detect_features()?;
         
-        for batch in read_filtered_batches(&path, "folder_id", target_folder)? {
-            let folder_id = get_string_array(&batch, "folder_id")?;
-            let osu_file = get_string_array(&batch, "osu_file")?;
-            let start_time = get_f64_array(&batch, "start_time")?;
-            let end_time = get_f64_array(&batch, "end_time")?;
-            
-            for i in 0..batch.num_rows() {
-                rows.push(BreakRow {
-                    folder_id: folder_id.value(i).to_string(),
-                    osu_file: osu_file.value(i).to_string(),
-                    start_time: start_time.value(i),
-                    end_time: end_time.value(i),
-                });
-            }
+        dataset.beatmaps = self.load_filtered(folder_id)?;
+        dataset.hit_objects = self.load_filtered(folder_id)?;
+        dataset.timing_points = self.load_filtered(folder_id)?;
+        dataset.storyboard_elements = self.load_filtered(folder_id)?;
+        dataset.storyboard_commands = self.load_filtered(folder_id)?;
+        dataset.slider_control_points = self.load_filtered(folder_id)?;
+        dataset.slider_data = self.load_filtered(folder_id)?;
+        dataset.breaks = self.load_filtered(folder_id)?;
+        if dataset.features.events {
+            dataset.events = self.load_filtered(folder_id)?;
         }
-        Ok(rows)
+        if dataset.features.extra_fields {
+            dataset.extra_fields = self.load_filtered(folder_id)?;
+        }
+        dataset.combo_colors = self.load_filtered(folder_id)?;
+        if dataset.features.hit_samples {
+            dataset.hit_samples = self.load_filtered(folder_id)?;
+        }
+        dataset.storyboard_loops = self.load_filtered(folder_id)?;
+        dataset.storyboard_triggers = self.load_filtered(folder_id)?;
+        dataset.assets = self.load_assets(folder_id)?;
+        
+        Ok(dataset)
     }
 
-    fn load_combo_colors_filtered(&self, target_folder: &str) -> Result<Vec<ComboColorRow>> {
-        let path = self.dataset_path.join("combo_colors.parquet");
-        let mut rows = Vec::new();
-        
-        for batch in read_filtered_batches(&path, "folder_id", target_folder)? {
-            let folder_id = get_string_array(&batch, "folder_id")?;
-            let osu_file = get_string_array(&batch, "osu_file")?;
-            let color_index = get_i32_array(&batch, "color_index")?;
-            let color_type = get_string_array(&batch, "color_type")?;
-            let custom_name = get_nullable_string_array(&batch, "custom_name")?;
-            let red = get_i32_array(&batch, "red")?;
-            let green = get_i32_array(&batch, "green")?;
-            let blue = get_i32_array(&batch, "blue")?;
-            
-            for i in 0..batch.num_rows() {
-                rows.push(ComboColorRow {
-                    folder_id: folder_id.value(i).to_string(),
-                    osu_file: osu_file.value(i).to_string(),
-                    color_index: color_index.value(i),
-                    color_type: color_type.value(i).to_string(),
-                    custom_name: custom_name.get(i),
-                    red: red.value(i),
-                    green: green.value(i),
-                    blue: blue.value(i),
-                });
+    // ============ Streaming methods ============
+
+    /// Iterate over every row of beatmaps.parquet without filtering
+    ///
+    /// Rows are decoded one batch at a time, so memory use stays bounded
+    /// regardless of dataset size. Read errors are yielded as items.
+    pub fn iter_beatmaps(&self) -> impl Iterator<Item = Result<BeatmapRow>> {
+        self.stream_table::<BeatmapRow>()
+    }
+
+    /// Iterate over every row of hit_objects.parquet without filtering
+    ///
+    /// Rows are decoded one batch at a time, so memory use stays bounded
+    /// regardless of dataset size. Read errors are yielded as items.
+    pub fn iter_hit_objects(&self) -> impl Iterator<Item = Result<HitObjectRow>> {
+        self.stream_table::<HitObjectRow>()
+    }
+
+    /// Iterate over every row of `T`'s table without filtering
+    ///
+    /// Generalizes `iter_beatmaps`/`iter_hit_objects` to any table with a
+    /// [`TableRow`] type, e.g. `stream_table::<SliderDataRow>()`. Rows are decoded
+    /// one batch at a time; read errors are yielded as items.
+    pub fn stream_table<T: TableRow>(&self) -> impl Iterator<Item = Result<T>> {
+        stream_rows(self.table_path(T::TABLE), T::from_batch)
+    }
+
+    /// Star rating of every difficulty in beatmaps.parquet
+    ///
+    /// Takes `stars_calc` from beatmap_enriched.parquet when it is positive, then its
+    /// `stars_api`, then the builder's `beatmaps.stars_calc` (`--compute-stars`).
+    /// Only the key and rating columns are decoded.
+    pub fn load_star_ratings(&self) -> Result<Vec<StarRatingRow>> {
+        let mut enriched = std::collections::HashMap::new();
+        let enriched_path = self.table_path("beatmap_enriched");
+        if enriched_path.exists() {
+            for batch in open_batch_reader(&enriched_path)? {
+                let batch = batch?;
+                let folder_id = get_string_array(&batch, "folder_id")?;
+                let osu_file = get_string_array(&batch, "osu_file")?;
+                let stars_calc = get_f64_array(&batch, "stars_calc")?;
+                let stars_api = get_f32_array(&batch, "stars_api")?;
+                for i in 0..batch.num_rows() {
+                    let stars = if stars_calc.value(i) > 0.0 {
+                        stars_calc.value(i)
+                    } else {
+                        f64::from(stars_api.value(i))
+                    };
+                    enriched.insert((folder_id.value(i).to_string(), osu_file.value(i).to_string()), stars);
+                }
             }
         }
-        Ok(rows)
-    }
 
-    fn load_hit_samples_filtered(&self, target_folder: &str) -> Result<Vec<HitSampleRow>> {
-        let path = self.dataset_path.join("hit_samples.parquet");
         let mut rows = Vec::new();
-        
-        for batch in read_filtered_batches(&path, "folder_id", target_folder)? {
+        for batch in open_batch_reader(&self.table_path("beatmaps"))? {
+            let batch = batch?;
             let folder_id = get_string_array(&batch, "folder_id")?;
             let osu_file = get_string_array(&batch, "osu_file")?;
-            let hit_object_index = get_i32_array(&batch, "hit_object_index")?;
-            let sample_index = get_i32_array(&batch, "sample_index")?;
-            let name = get_string_array(&batch, "name")?;
-            let bank = get_string_array(&batch, "bank")?;
-            let suffix = get_nullable_string_array(&batch, "suffix")?;
-            let volume = get_i32_array(&batch, "volume")?;
-            
+            let beatmap_id = get_i32_array(&batch, "beatmap_id")?;
+            let stars_calc = get_optional_nullable_f64_array(&batch, "stars_calc")?;
             for i in 0..batch.num_rows() {
-                rows.push(HitSampleRow {
-                    folder_id: folder_id.value(i).to_string(),
-                    osu_file: osu_file.value(i).to_string(),
-                    hit_object_index: hit_object_index.value(i),
-                    sample_index: sample_index.value(i),
-                    name: name.value(i).to_string(),
-                    bank: bank.value(i).to_string(),
-                    suffix: suffix.get(i),
-                    volume: volume.value(i),
+                let key = (folder_id.value(i).to_string(), osu_file.value(i).to_string());
+                let stars = enriched
+                    .get(&key)
+                    .copied()
+                    .filter(|stars| *stars > 0.0)
+                    .or_else(|| stars_calc.as_ref().and_then(|col| col.get(i)));
+                rows.push(StarRatingRow {
+                    folder_id: key.0,
+                    osu_file: key.1,
+                    beatmap_id: beatmap_id.value(i),
+                    stars,
                 });
             }
         }
         Ok(rows)
     }
 
-    fn load_storyboard_loops_filtered(&self, target_folder: &str) -> Result<Vec<StoryboardLoopRow>> {
-        let path = self.dataset_path.join("storyboard_loops.parquet");
-        let mut rows = Vec::new();
-        
-        for batch in read_filtered_batches(&path, "folder_id", target_folder)? {
-            let folder_id = get_string_array(&batch, "folder_id")?;
-            let source_file = get_string_array(&batch, "source_file")?;
-            let element_index = get_i32_array(&batch, "element_index")?;
-            let loop_index = get_i32_array(&batch, "loop_index")?;
-            let loop_start_time = get_f64_array(&batch, "loop_start_time")?;
-            let loop_count = get_i32_array(&batch, "loop_count")?;
-            let is_embedded = get_bool_array(&batch, "is_embedded")?;
-            
-            for i in 0..batch.num_rows() {
-                rows.push(StoryboardLoopRow {
-                    folder_id: folder_id.value(i).to_string(),
-                    source_file: source_file.value(i).to_string(),
-                    element_index: element_index.value(i),
-                    loop_index: loop_index.value(i),
-                    loop_start_time: loop_start_time.value(i),
-                    loop_count: loop_count.value(i),
-                    is_embedded: is_embedded.value(i),
-                });
-            }
+    // ============ Filtered loading methods ============
+
+    /// Load copied asset rows for a folder (empty for datasets built before assets.parquet existed)
+    pub fn load_assets_filtered(&self, target_folder: &str) -> Result<Vec<AssetRow>> {
+        self.load_assets(Some(target_folder))
+    }
+
+    fn load_assets(&self, target_folder: Option<&str>) -> Result<Vec<AssetRow>> {
+        if !self.table_path("assets").exists() {
+            return Ok(Vec::new());
         }
-        Ok(rows)
+        self.load_filtered(target_folder)
     }
 
-    fn load_storyboard_triggers_filtered(&self, target_folder: &str) -> Result<Vec<StoryboardTriggerRow>> {
-        let path = self.dataset_path.join("storyboard_triggers.parquet");
+    /// Rows of `T`'s table, only those of `target_folder` if given
+    fn load_filtered<T: TableRow>(&self, target_folder: Option<&str>) -> Result<Vec<T>> {
         let mut rows = Vec::new();
-        
-        for batch in read_filtered_batches(&path, "folder_id", target_folder)? {
-            let folder_id = get_string_array(&batch, "folder_id")?;
-            let source_file = get_string_array(&batch, "source_file")?;
-            let element_index = get_i32_array(&batch, "element_index")?;
-            let trigger_index = get_i32_array(&batch, "trigger_index")?;
-            let trigger_name = get_string_array(&batch, "trigger_name")?;
-            let trigger_start_time = get_f64_array(&batch, "trigger_start_time")?;
-            let trigger_end_time = get_f64_array(&batch, "trigger_end_time")?;
-            let group_number = get_i32_array(&batch, "group_number")?;
-            let is_embedded = get_bool_array(&batch, "is_embedded")?;
-            
-            for i in 0..batch.num_rows() {
-                rows.push(StoryboardTriggerRow {
-                    folder_id: folder_id.value(i).to_string(),
-                    source_file: source_file.value(i).to_string(),
-                    element_index: element_index.value(i),
-                    trigger_index: trigger_index.value(i),
-                    trigger_name: trigger_name.value(i).to_string(),
-                    trigger_start_time: trigger_start_time.value(i),
-                    trigger_end_time: trigger_end_time.value(i),
-                    group_number: group_number.value(i),
-                    is_embedded: is_embedded.value(i),
-                });
-            }
+        for batch in read_filtered_batches(&self.table_path(T::TABLE), "folder_id", target_folder)? {
+            rows.extend(T::from_batch(&batch)?);
         }
         Ok(rows)
     }
 }
 
+// ============ Typed table rows ============
+
+/// Row type of a dataset table, decoded from its record batches
+///
+/// Implemented for every table the reader loads; used by
+/// [`ParquetReader::stream_table`].
+pub trait TableRow: Sized {
+    /// Table file stem, e.g. `slider_data`
+    const TABLE: &'static str;
+
+    /// Decode every row of one batch of the table
+    fn from_batch(batch: &RecordBatch) -> Result<Vec<Self>>;
+}
+
+macro_rules! impl_table_row {
+    ($($row:ty => $table:literal, $decode:ident;)*) => {
+        $(
+            impl TableRow for $row {
+                const TABLE: &'static str = $table;
+
+                fn from_batch(batch: &RecordBatch) -> Result<Vec<Self>> {
+                    $decode(batch)
+                }
+            }
+        )*
+    };
+}
+
+impl_table_row! {
+    BeatmapRow => "beatmaps", beatmap_rows_from_batch;
+    HitObjectRow => "hit_objects", hit_object_rows_from_batch;
+    TimingPointRow => "timing_points", timing_point_rows_from_batch;
+    StoryboardElementRow => "storyboard_elements", storyboard_element_rows_from_batch;
+    StoryboardCommandRow => "storyboard_commands", storyboard_command_rows_from_batch;
+    SliderControlPointRow => "slider_control_points", slider_control_point_rows_from_batch;
+    SliderDataRow => "slider_data", slider_data_rows_from_batch;
+    BreakRow => "breaks", break_rows_from_batch;
+    EventRow => "events", event_rows_from_batch;
+    ExtraFieldRow => "extra_fields", extra_field_rows_from_batch;
+    ComboColorRow => "combo_colors", combo_color_rows_from_batch;
+    HitSampleRow => "hit_samples", hit_sample_rows_from_batch;
+    StoryboardLoopRow => "storyboard_loops", storyboard_loop_rows_from_batch;
+    StoryboardTriggerRow => "storyboard_triggers", storyboard_trigger_rows_from_batch;
+    AssetRow => "assets", asset_rows_from_batch;
+}
+
 // ============ Helper functions with filtering ============
 
 /// Read parquet file with row-level filtering using Arrow compute
 /// 
 /// This reads the file in batches and filters each batch to only include
 /// rows where the filter_column equals filter_value. This significantly
-/// reduces memory usage compared to loading all rows.
+/// reduces memory usage compared to loading all rows. Without a filter_value
+/// every batch is kept as read.
 fn read_filtered_batches(
     path: &Path,
     filter_column: &str,
-    filter_value: &str,
+    filter_value: Option<&str>,
 ) -> Result<Vec<RecordBatch>> {
-    let file = File::open(path).context(format!("Failed to open {}", path.display()))?;
-    let builder = ParquetRecordBatchReaderBuilder::try_new(file)?;
-    
-    // Use smaller batch size to reduce peak memory
-    let reader = builder.with_batch_size(8192).build()?;
+    let reader: Box<dyn RecordBatchReader + Send> = match filter_value {
+        Some(value) if !is_ipc_path(path) => {
+            // Skip row groups whose Bloom filter (builder `--bloom`) rules the value out
+            let row_groups = bloom_row_groups(path, filter_column, value)?;
+            if row_groups.is_empty() {
+                return Ok(Vec::new());
+            }
+            let file = File::open(path).io_context(format!("Failed to open {}", path.display()))?;
+            let builder = ParquetRecordBatchReaderBuilder::try_new(file)?;
+            Box::new(builder.with_row_groups(row_groups).with_batch_size(8192).build()?)
+        }
+        _ => open_batch_reader(path)?,
+    };
     
     let mut filtered_batches = Vec::new();
     
     for batch_result in reader {
-        let batch = batch_result.context("Failed to read batch")?;
+        let batch = batch_result?;
+        let Some(filter_value) = filter_value else {
+            filtered_batches.push(batch);
+            continue;
+        };
         
         // Get the filter column
         let col = batch
             .column_by_name(filter_column)
-            .context(format!("Missing column: {}", filter_column))?;
+            .ok_or_else(|| ReconstructError::MissingColumn(filter_column.to_string()))?;
         
         // Create filter mask: true where column == filter_value
-        let filter_mask = create_string_eq_filter(col.as_ref(), filter_value)?;
+        let filter_mask = create_string_eq_filter(col.as_ref(), filter_column, filter_value)?;
         
         // Apply filter - only keep rows where filter_mask is true
         let filtered = filter_record_batch(&batch, &filter_mask)?;
@@ -566,8 +463,621 @@ fn read_filtered_batches(
     Ok(filtered_batches)
 }
 
+/// Lazily read every row of a parquet file, converting one batch at a time
+///
+/// Only the current batch is held in memory. Failing to open the file or decode
+/// a batch is yielded as an error item rather than ending the iteration silently.
+fn stream_rows<T>(
+    path: PathBuf,
+    convert: fn(&RecordBatch) -> Result<Vec<T>>,
+) -> impl Iterator<Item = Result<T>> {
+    let batches: Box<dyn Iterator<Item = Result<RecordBatch>>> = match open_batch_reader(&path) {
+        Ok(reader) => Box::new(reader.map(|batch| batch.map_err(ReconstructError::from))),
+        Err(e) => Box::new(std::iter::once(Err(e))),
+    };
+
+    batches.flat_map(move |batch| {
+        let rows: Vec<Result<T>> = match batch.and_then(|batch| convert(&batch)) {
+            Ok(rows) => rows.into_iter().map(Ok).collect(),
+            Err(e) => vec![Err(e)],
+        };
+        rows
+    })
+}
+
+/// Open a table file as a batch reader, as Arrow IPC for `.arrow`/`.feather` and parquet otherwise
+pub(crate) fn open_batch_reader(path: &Path) -> Result<Box<dyn RecordBatchReader + Send>> {
+    let file = File::open(path).io_context(format!("Failed to open {}", path.display()))?;
+
+    if is_ipc_path(path) {
+        // IPC files keep the batches they were written with
+        return Ok(Box::new(FileReader::try_new(file, None)?));
+    }
+
+    let builder = ParquetRecordBatchReaderBuilder::try_new(file)?;
+
+    // Use smaller batch size to reduce peak memory
+    Ok(Box::new(builder.with_batch_size(8192).build()?))
+}
+
+fn is_ipc_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| TABLE_IPC_EXTENSIONS.contains(&ext))
+}
+
+/// Indices of the row groups of a parquet file that may contain `value` in `column`
+///
+/// Row groups without a Bloom filter on the column are always kept, so files
+/// written without `--bloom` read every row group as before.
+fn bloom_row_groups(path: &Path, column: &str, value: &str) -> Result<Vec<usize>> {
+    let file = File::open(path).io_context(format!("Failed to open {}", path.display()))?;
+    let options = ReadOptionsBuilder::new()
+        .with_reader_properties(ReaderProperties::builder().set_read_bloom_filter(true).build())
+        .build();
+    let reader = SerializedFileReader::new_with_options(file, options)?;
+
+    let metadata = reader.metadata();
+    let num_row_groups = metadata.num_row_groups();
+    let Some(column_index) = metadata
+        .file_metadata()
+        .schema_descr()
+        .columns()
+        .iter()
+        .position(|col| col.path().string() == column)
+    else {
+        return Ok((0..num_row_groups).collect());
+    };
+
+    let mut row_groups = Vec::with_capacity(num_row_groups);
+    for i in 0..num_row_groups {
+        let row_group = reader.get_row_group(i)?;
+        let may_contain = row_group
+            .get_column_bloom_filter(column_index)
+            .is_none_or(|filter| filter.check(&value));
+        if may_contain {
+            row_groups.push(i);
+        }
+    }
+    Ok(row_groups)
+}
+
+/// Convert one beatmaps.parquet batch into rows
+fn beatmap_rows_from_batch(batch: &RecordBatch) -> Result<Vec<BeatmapRow>> {
+    let mut rows = Vec::with_capacity(batch.num_rows());
+
+    let folder_id = get_string_array(batch, "folder_id")?;
+    let osu_file = get_string_array(batch, "osu_file")?;
+    let format_version = get_i32_array(batch, "format_version")?;
+    let audio_file = get_string_array(batch, "audio_file")?;
+    let audio_lead_in = get_f64_array(batch, "audio_lead_in")?;
+    let preview_time = get_i32_array(batch, "preview_time")?;
+    let default_sample_bank = get_i32_array(batch, "default_sample_bank")?;
+    let default_sample_volume = get_i32_array(batch, "default_sample_volume")?;
+    let stack_leniency = get_f32_array(batch, "stack_leniency")?;
+    let mode = get_i32_array(batch, "mode")?;
+    let letterbox_in_breaks = get_bool_array(batch, "letterbox_in_breaks")?;
+    let special_style = get_bool_array(batch, "special_style")?;
+    let widescreen_storyboard = get_bool_array(batch, "widescreen_storyboard")?;
+    let epilepsy_warning = get_bool_array(batch, "epilepsy_warning")?;
+    let samples_match_playback_rate = get_bool_array(batch, "samples_match_playback_rate")?;
+    let countdown = get_i32_array(batch, "countdown")?;
+    let countdown_offset = get_i32_array(batch, "countdown_offset")?;
+    let bookmarks = get_string_array(batch, "bookmarks")?;
+    let distance_spacing = get_f64_array(batch, "distance_spacing")?;
+    let beat_divisor = get_i32_array(batch, "beat_divisor")?;
+    let grid_size = get_i32_array(batch, "grid_size")?;
+    let timeline_zoom = get_f64_array(batch, "timeline_zoom")?;
+    let title = get_string_array(batch, "title")?;
+    let title_unicode = get_string_array(batch, "title_unicode")?;
+    let artist = get_string_array(batch, "artist")?;
+    let artist_unicode = get_string_array(batch, "artist_unicode")?;
+    let creator = get_string_array(batch, "creator")?;
+    let version = get_string_array(batch, "version")?;
+    let source = get_string_array(batch, "source")?;
+    let tags = get_string_array(batch, "tags")?;
+    // Datasets built before tags_list was added only have the raw string
+    let tags_list = get_optional_string_list_array(batch, "tags_list")?;
+    let beatmap_id = get_i32_array(batch, "beatmap_id")?;
+    let beatmap_set_id = get_i32_array(batch, "beatmap_set_id")?;
+    let hp_drain_rate = get_f32_array(batch, "hp_drain_rate")?;
+    let circle_size = get_f32_array(batch, "circle_size")?;
+    let overall_difficulty = get_f32_array(batch, "overall_difficulty")?;
+    let approach_rate = get_f32_array(batch, "approach_rate")?;
+    let slider_multiplier = get_f64_array(batch, "slider_multiplier")?;
+    let slider_tick_rate = get_f64_array(batch, "slider_tick_rate")?;
+    let background_file = get_string_array(batch, "background_file")?;
+    let audio_path = get_string_array(batch, "audio_path")?;
+    let background_path = get_string_array(batch, "background_path")?;
+    // Skin settings were added in schema v19
+    let overlay_position = get_optional_string_array(batch, "overlay_position")?;
+    let skin_preference = get_optional_string_array(batch, "skin_preference")?;
+    let use_skin_sprites = get_optional_bool_array(batch, "use_skin_sprites")?;
+    let always_show_playfield = get_optional_bool_array(batch, "always_show_playfield")?;
+    let skipped_too_large = get_optional_bool_array(batch, "skipped_too_large")?;
+    // Mania key layout was added in schema v21
+    let key_count = get_optional_nullable_i32_array(batch, "key_count")?;
+    let columns_out_of_range = get_optional_bool_array(batch, "columns_out_of_range")?;
+    // Resolved names were split off in schema v25; before that audio_file/background_file held them
+    let audio_file_resolved = get_optional_string_array(batch, "audio_file_resolved")?;
+    let background_file_resolved = get_optional_string_array(batch, "background_file_resolved")?;
+    
+    for i in 0..batch.num_rows() {
+        rows.push(BeatmapRow {
+            folder_id: folder_id.value(i).to_string(),
+            osu_file: osu_file.value(i).to_string(),
+            format_version: format_version.value(i),
+            audio_file: audio_file.value(i).to_string(),
+            audio_lead_in: audio_lead_in.value(i),
+            preview_time: preview_time.value(i),
+            default_sample_bank: default_sample_bank.value(i),
+            default_sample_volume: default_sample_volume.value(i),
+            stack_leniency: stack_leniency.value(i),
+            mode: mode.value(i),
+            letterbox_in_breaks: letterbox_in_breaks.value(i),
+            special_style: special_style.value(i),
+            widescreen_storyboard: widescreen_storyboard.value(i),
+            epilepsy_warning: epilepsy_warning.value(i),
+            samples_match_playback_rate: samples_match_playback_rate.value(i),
+            countdown: countdown.value(i),
+            countdown_offset: countdown_offset.value(i),
+            bookmarks: bookmarks.value(i).to_string(),
+            distance_spacing: distance_spacing.value(i),
+            beat_divisor: beat_divisor.value(i),
+            grid_size: grid_size.value(i),
+            timeline_zoom: timeline_zoom.value(i),
+            title: title.value(i).to_string(),
+            title_unicode: title_unicode.value(i).to_string(),
+            artist: artist.value(i).to_string(),
+            artist_unicode: artist_unicode.value(i).to_string(),
+            creator: creator.value(i).to_string(),
+            version: version.value(i).to_string(),
+            source: source.value(i).to_string(),
+            tags: tags.value(i).to_string(),
+            tags_list: match tags_list {
+                Some(list) => string_list_value(list, i),
+                None => tags.value(i).split_whitespace().map(str::to_string).collect(),
+            },
+            beatmap_id: beatmap_id.value(i),
+            beatmap_set_id: beatmap_set_id.value(i),
+            hp_drain_rate: hp_drain_rate.value(i),
+            circle_size: circle_size.value(i),
+            overall_difficulty: overall_difficulty.value(i),
+            approach_rate: approach_rate.value(i),
+            slider_multiplier: slider_multiplier.value(i),
+            slider_tick_rate: slider_tick_rate.value(i),
+            background_file: background_file.value(i).to_string(),
+            audio_path: audio_path.value(i).to_string(),
+            background_path: background_path.value(i).to_string(),
+            overlay_position: overlay_position.map_or_else(|| "NoChange".to_string(), |a| a.value(i).to_string()),
+            skin_preference: skin_preference.map_or_else(String::new, |a| a.value(i).to_string()),
+            use_skin_sprites: use_skin_sprites.is_some_and(|a| a.value(i)),
+            always_show_playfield: always_show_playfield.is_some_and(|a| a.value(i)),
+            skipped_too_large: skipped_too_large.is_some_and(|a| a.value(i)),
+            key_count: match &key_count {
+                Some(a) => a.get(i),
+                None => (mode.value(i) == 3).then(|| circle_size.value(i).round().max(1.0) as i32),
+            },
+            columns_out_of_range: columns_out_of_range.is_some_and(|a| a.value(i)),
+            audio_file_resolved: audio_file_resolved.unwrap_or(audio_file).value(i).to_string(),
+            background_file_resolved: background_file_resolved.unwrap_or(background_file).value(i).to_string(),
+        });
+    }
+
+    Ok(rows)
+}
+
+/// Convert one hit_objects.parquet batch into rows
+fn hit_object_rows_from_batch(batch: &RecordBatch) -> Result<Vec<HitObjectRow>> {
+    let mut rows = Vec::with_capacity(batch.num_rows());
+
+    let folder_id = get_string_array(batch, "folder_id")?;
+    let osu_file = get_string_array(batch, "osu_file")?;
+    let index = get_i32_array(batch, "index")?;
+    let start_time = get_f64_array(batch, "start_time")?;
+    let object_type = get_string_array(batch, "object_type")?;
+    let pos_x = get_nullable_i32_array(batch, "pos_x")?;
+    let pos_y = get_nullable_i32_array(batch, "pos_y")?;
+    let new_combo = get_bool_array(batch, "new_combo")?;
+    let combo_offset = get_i32_array(batch, "combo_offset")?;
+    let curve_type = get_nullable_string_array(batch, "curve_type")?;
+    let slides = get_nullable_i32_array(batch, "slides")?;
+    let length = get_nullable_f64_array(batch, "length")?;
+    let end_time = get_nullable_f64_array(batch, "end_time")?;
+    let duration = get_optional_nullable_f64_array(batch, "duration")?;
+    let column = get_optional_nullable_i32_array(batch, "column")?;
+    
+    for i in 0..batch.num_rows() {
+        rows.push(HitObjectRow {
+            folder_id: folder_id.value(i).to_string(),
+            osu_file: osu_file.value(i).to_string(),
+            index: index.value(i),
+            start_time: start_time.value(i),
+            object_type: object_type.value(i).to_string(),
+            pos_x: pos_x.get(i),
+            pos_y: pos_y.get(i),
+            new_combo: new_combo.value(i),
+            combo_offset: combo_offset.value(i),
+            curve_type: curve_type.get(i),
+            slides: slides.get(i),
+            length: length.get(i),
+            end_time: end_time.get(i),
+            duration: duration.as_ref().and_then(|d| d.get(i)),
+            column: column.as_ref().and_then(|c| c.get(i)),
+        });
+    }
+
+    Ok(rows)
+}
+
+/// Convert one timing_points batch into rows
+fn timing_point_rows_from_batch(batch: &RecordBatch) -> Result<Vec<TimingPointRow>> {
+    let mut rows = Vec::with_capacity(batch.num_rows());
+
+    let folder_id = get_string_array(batch, "folder_id")?;
+    let osu_file = get_string_array(batch, "osu_file")?;
+    let time = get_f64_array(batch, "time")?;
+    let point_type = get_string_array(batch, "point_type")?;
+    let beat_length = get_nullable_f64_array(batch, "beat_length")?;
+    let time_signature = get_nullable_string_array(batch, "time_signature")?;
+    let slider_velocity = get_nullable_f64_array(batch, "slider_velocity")?;
+    let kiai = get_nullable_bool_array(batch, "kiai")?;
+    let sample_bank = get_nullable_string_array(batch, "sample_bank")?;
+    let sample_volume = get_nullable_i32_array(batch, "sample_volume")?;
+    // Absent in datasets written before schema v2
+    let sample_index = get_optional_nullable_i32_array(batch, "sample_index")?;
+    // Absent before schema v12
+    let order_index = get_optional_nullable_i32_array(batch, "order_index")?;
+
+    for i in 0..batch.num_rows() {
+        rows.push(TimingPointRow {
+            folder_id: folder_id.value(i).to_string(),
+            osu_file: osu_file.value(i).to_string(),
+            time: time.value(i),
+            point_type: point_type.value(i).to_string(),
+            beat_length: beat_length.get(i),
+            time_signature: time_signature.get(i),
+            slider_velocity: slider_velocity.get(i),
+            kiai: kiai.get(i),
+            sample_bank: sample_bank.get(i),
+            sample_volume: sample_volume.get(i),
+            sample_index: sample_index.as_ref().and_then(|a| a.get(i)),
+            order_index: order_index.as_ref().and_then(|a| a.get(i)),
+        });
+    }
+    Ok(rows)
+}
+
+/// Convert one storyboard_elements batch into rows
+fn storyboard_element_rows_from_batch(batch: &RecordBatch) -> Result<Vec<StoryboardElementRow>> {
+    let mut rows = Vec::with_capacity(batch.num_rows());
+
+    let folder_id = get_string_array(batch, "folder_id")?;
+    let source_file = get_string_array(batch, "source_file")?;
+    let element_index = get_i32_array(batch, "element_index")?;
+    let layer_name = get_string_array(batch, "layer_name")?;
+    let element_path = get_string_array(batch, "element_path")?;
+    let element_path_original = get_optional_nullable_string_array(batch, "element_path_original")?;
+    let element_type = get_string_array(batch, "element_type")?;
+    let origin = get_string_array(batch, "origin")?;
+    let initial_pos_x = get_f32_array(batch, "initial_pos_x")?;
+    let initial_pos_y = get_f32_array(batch, "initial_pos_y")?;
+    let frame_count = get_nullable_i32_array(batch, "frame_count")?;
+    let frame_delay = get_nullable_f64_array(batch, "frame_delay")?;
+    let loop_type = get_nullable_string_array(batch, "loop_type")?;
+    let is_embedded = get_bool_array(batch, "is_embedded")?;
+
+    for i in 0..batch.num_rows() {
+        rows.push(StoryboardElementRow {
+            folder_id: folder_id.value(i).to_string(),
+            source_file: source_file.value(i).to_string(),
+            element_index: element_index.value(i),
+            layer_name: layer_name.value(i).to_string(),
+            element_path: element_path.value(i).to_string(),
+            element_path_original: element_path_original.as_ref().and_then(|a| a.get(i)),
+            element_type: element_type.value(i).to_string(),
+            origin: origin.value(i).to_string(),
+            initial_pos_x: initial_pos_x.value(i),
+            initial_pos_y: initial_pos_y.value(i),
+            frame_count: frame_count.get(i),
+            frame_delay: frame_delay.get(i),
+            loop_type: loop_type.get(i),
+            is_embedded: is_embedded.value(i),
+        });
+    }
+    Ok(rows)
+}
+
+/// Convert one storyboard_commands batch into rows
+fn storyboard_command_rows_from_batch(batch: &RecordBatch) -> Result<Vec<StoryboardCommandRow>> {
+    let mut rows = Vec::with_capacity(batch.num_rows());
+
+    let folder_id = get_string_array(batch, "folder_id")?;
+    let source_file = get_string_array(batch, "source_file")?;
+    let element_index = get_i32_array(batch, "element_index")?;
+    let command_type = get_string_array(batch, "command_type")?;
+    let start_time = get_f64_array(batch, "start_time")?;
+    let end_time = get_f64_array(batch, "end_time")?;
+    let start_value = get_string_array(batch, "start_value")?;
+    let end_value = get_string_array(batch, "end_value")?;
+    let easing = get_i32_array(batch, "easing")?;
+    let is_embedded = get_bool_array(batch, "is_embedded")?;
+
+    for i in 0..batch.num_rows() {
+        rows.push(StoryboardCommandRow {
+            folder_id: folder_id.value(i).to_string(),
+            source_file: source_file.value(i).to_string(),
+            element_index: element_index.value(i),
+            command_type: command_type.value(i).to_string(),
+            start_time: start_time.value(i),
+            end_time: end_time.value(i),
+            start_value: start_value.value(i).to_string(),
+            end_value: end_value.value(i).to_string(),
+            easing: easing.value(i),
+            is_embedded: is_embedded.value(i),
+        });
+    }
+    Ok(rows)
+}
+
+/// Convert one slider_control_points batch into rows
+fn slider_control_point_rows_from_batch(batch: &RecordBatch) -> Result<Vec<SliderControlPointRow>> {
+    let mut rows = Vec::with_capacity(batch.num_rows());
+
+    let folder_id = get_string_array(batch, "folder_id")?;
+    let osu_file = get_string_array(batch, "osu_file")?;
+    let hit_object_index = get_i32_array(batch, "hit_object_index")?;
+    let point_index = get_i32_array(batch, "point_index")?;
+    let pos_x = get_f32_array(batch, "pos_x")?;
+    let pos_y = get_f32_array(batch, "pos_y")?;
+    let path_type = get_nullable_string_array(batch, "path_type")?;
+
+    for i in 0..batch.num_rows() {
+        rows.push(SliderControlPointRow {
+            folder_id: folder_id.value(i).to_string(),
+            osu_file: osu_file.value(i).to_string(),
+            hit_object_index: hit_object_index.value(i),
+            point_index: point_index.value(i),
+            pos_x: pos_x.value(i),
+            pos_y: pos_y.value(i),
+            path_type: path_type.get(i),
+        });
+    }
+    Ok(rows)
+}
+
+/// Convert one slider_data batch into rows
+fn slider_data_rows_from_batch(batch: &RecordBatch) -> Result<Vec<SliderDataRow>> {
+    let mut rows = Vec::with_capacity(batch.num_rows());
+
+    let folder_id = get_string_array(batch, "folder_id")?;
+    let osu_file = get_string_array(batch, "osu_file")?;
+    let hit_object_index = get_i32_array(batch, "hit_object_index")?;
+    let repeat_count = get_i32_array(batch, "repeat_count")?;
+    let velocity = get_f64_array(batch, "velocity")?;
+    let expected_dist = get_nullable_f64_array(batch, "expected_dist")?;
+
+    for i in 0..batch.num_rows() {
+        rows.push(SliderDataRow {
+            folder_id: folder_id.value(i).to_string(),
+            osu_file: osu_file.value(i).to_string(),
+            hit_object_index: hit_object_index.value(i),
+            repeat_count: repeat_count.value(i),
+            velocity: velocity.value(i),
+            expected_dist: expected_dist.get(i),
+        });
+    }
+    Ok(rows)
+}
+
+/// Convert one breaks batch into rows
+fn break_rows_from_batch(batch: &RecordBatch) -> Result<Vec<BreakRow>> {
+    let mut rows = Vec::with_capacity(batch.num_rows());
+
+    let folder_id = get_string_array(batch, "folder_id")?;
+    let osu_file = get_string_array(batch, "osu_file")?;
+    let start_time = get_f64_array(batch, "start_time")?;
+    let end_time = get_f64_array(batch, "end_time")?;
+
+    for i in 0..batch.num_rows() {
+        rows.push(BreakRow {
+            folder_id: folder_id.value(i).to_string(),
+            osu_file: osu_file.value(i).to_string(),
+            start_time: start_time.value(i),
+            end_time: end_time.value(i),
+        });
+    }
+    Ok(rows)
+}
+
+/// Convert one events batch into rows
+fn event_rows_from_batch(batch: &RecordBatch) -> Result<Vec<EventRow>> {
+    let mut rows = Vec::with_capacity(batch.num_rows());
+
+    let folder_id = get_string_array(batch, "folder_id")?;
+    let osu_file = get_string_array(batch, "osu_file")?;
+    let event_index = get_i32_array(batch, "event_index")?;
+    let event_type = get_string_array(batch, "event_type")?;
+    let start_time = get_nullable_f64_array(batch, "start_time")?;
+    let params = get_string_array(batch, "params")?;
+
+    for i in 0..batch.num_rows() {
+        rows.push(EventRow {
+            folder_id: folder_id.value(i).to_string(),
+            osu_file: osu_file.value(i).to_string(),
+            event_index: event_index.value(i),
+            event_type: event_type.value(i).to_string(),
+            start_time: start_time.get(i),
+            params: params.value(i).to_string(),
+        });
+    }
+    Ok(rows)
+}
+
+/// Convert one extra_fields batch into rows
+fn extra_field_rows_from_batch(batch: &RecordBatch) -> Result<Vec<ExtraFieldRow>> {
+    let mut rows = Vec::with_capacity(batch.num_rows());
+
+    let folder_id = get_string_array(batch, "folder_id")?;
+    let osu_file = get_string_array(batch, "osu_file")?;
+    let section = get_string_array(batch, "section")?;
+    let field_index = get_i32_array(batch, "field_index")?;
+    let key = get_string_array(batch, "key")?;
+    let value = get_string_array(batch, "value")?;
+
+    for i in 0..batch.num_rows() {
+        rows.push(ExtraFieldRow {
+            folder_id: folder_id.value(i).to_string(),
+            osu_file: osu_file.value(i).to_string(),
+            section: section.value(i).to_string(),
+            field_index: field_index.value(i),
+            key: key.value(i).to_string(),
+            value: value.value(i).to_string(),
+        });
+    }
+    Ok(rows)
+}
+
+/// Convert one combo_colors batch into rows
+fn combo_color_rows_from_batch(batch: &RecordBatch) -> Result<Vec<ComboColorRow>> {
+    let mut rows = Vec::with_capacity(batch.num_rows());
+
+    let folder_id = get_string_array(batch, "folder_id")?;
+    let osu_file = get_string_array(batch, "osu_file")?;
+    let color_index = get_i32_array(batch, "color_index")?;
+    let color_type = get_string_array(batch, "color_type")?;
+    let custom_name = get_nullable_string_array(batch, "custom_name")?;
+    let red = get_i32_array(batch, "red")?;
+    let green = get_i32_array(batch, "green")?;
+    let blue = get_i32_array(batch, "blue")?;
+
+    for i in 0..batch.num_rows() {
+        rows.push(ComboColorRow {
+            folder_id: folder_id.value(i).to_string(),
+            osu_file: osu_file.value(i).to_string(),
+            color_index: color_index.value(i),
+            color_type: color_type.value(i).to_string(),
+            custom_name: custom_name.get(i),
+            red: red.value(i),
+            green: green.value(i),
+            blue: blue.value(i),
+        });
+    }
+    Ok(rows)
+}
+
+/// Convert one hit_samples batch into rows
+fn hit_sample_rows_from_batch(batch: &RecordBatch) -> Result<Vec<HitSampleRow>> {
+    let mut rows = Vec::with_capacity(batch.num_rows());
+
+    let folder_id = get_string_array(batch, "folder_id")?;
+    let osu_file = get_string_array(batch, "osu_file")?;
+    let hit_object_index = get_i32_array(batch, "hit_object_index")?;
+    let sample_index = get_i32_array(batch, "sample_index")?;
+    let name = get_string_array(batch, "name")?;
+    let bank = get_string_array(batch, "bank")?;
+    let suffix = get_nullable_string_array(batch, "suffix")?;
+    let volume = get_i32_array(batch, "volume")?;
+    let scope = get_optional_string_array(batch, "scope")?;
+    let node_index = get_optional_nullable_i32_array(batch, "node_index")?;
+
+    for i in 0..batch.num_rows() {
+        rows.push(HitSampleRow {
+            folder_id: folder_id.value(i).to_string(),
+            osu_file: osu_file.value(i).to_string(),
+            hit_object_index: hit_object_index.value(i),
+            sample_index: sample_index.value(i),
+            name: name.value(i).to_string(),
+            bank: bank.value(i).to_string(),
+            suffix: suffix.get(i),
+            volume: volume.value(i),
+            scope: scope.map(|s| s.value(i).to_string()).unwrap_or_default(),
+            node_index: node_index.as_ref().and_then(|n| n.get(i)),
+        });
+    }
+    Ok(rows)
+}
+
+/// Convert one storyboard_loops batch into rows
+fn storyboard_loop_rows_from_batch(batch: &RecordBatch) -> Result<Vec<StoryboardLoopRow>> {
+    let mut rows = Vec::with_capacity(batch.num_rows());
+
+    let folder_id = get_string_array(batch, "folder_id")?;
+    let source_file = get_string_array(batch, "source_file")?;
+    let element_index = get_i32_array(batch, "element_index")?;
+    let loop_index = get_i32_array(batch, "loop_index")?;
+    let loop_start_time = get_f64_array(batch, "loop_start_time")?;
+    let loop_count = get_i32_array(batch, "loop_count")?;
+    let is_embedded = get_bool_array(batch, "is_embedded")?;
+
+    for i in 0..batch.num_rows() {
+        rows.push(StoryboardLoopRow {
+            folder_id: folder_id.value(i).to_string(),
+            source_file: source_file.value(i).to_string(),
+            element_index: element_index.value(i),
+            loop_index: loop_index.value(i),
+            loop_start_time: loop_start_time.value(i),
+            loop_count: loop_count.value(i),
+            is_embedded: is_embedded.value(i),
+        });
+    }
+    Ok(rows)
+}
+
+/// Convert one storyboard_triggers batch into rows
+fn storyboard_trigger_rows_from_batch(batch: &RecordBatch) -> Result<Vec<StoryboardTriggerRow>> {
+    let mut rows = Vec::with_capacity(batch.num_rows());
+
+    let folder_id = get_string_array(batch, "folder_id")?;
+    let source_file = get_string_array(batch, "source_file")?;
+    let element_index = get_i32_array(batch, "element_index")?;
+    let trigger_index = get_i32_array(batch, "trigger_index")?;
+    let trigger_name = get_string_array(batch, "trigger_name")?;
+    let trigger_start_time = get_f64_array(batch, "trigger_start_time")?;
+    let trigger_end_time = get_f64_array(batch, "trigger_end_time")?;
+    let group_number = get_i32_array(batch, "group_number")?;
+    let is_embedded = get_bool_array(batch, "is_embedded")?;
+
+    for i in 0..batch.num_rows() {
+        rows.push(StoryboardTriggerRow {
+            folder_id: folder_id.value(i).to_string(),
+            source_file: source_file.value(i).to_string(),
+            element_index: element_index.value(i),
+            trigger_index: trigger_index.value(i),
+            trigger_name: trigger_name.value(i).to_string(),
+            trigger_start_time: trigger_start_time.value(i),
+            trigger_end_time: trigger_end_time.value(i),
+            group_number: group_number.value(i),
+            is_embedded: is_embedded.value(i),
+        });
+    }
+    Ok(rows)
+}
+
+/// Convert one assets batch into rows
+fn asset_rows_from_batch(batch: &RecordBatch) -> Result<Vec<AssetRow>> {
+    let mut rows = Vec::with_capacity(batch.num_rows());
+
+    let folder_id = get_string_array(batch, "folder_id")?;
+    let original_path = get_string_array(batch, "original_path")?;
+    let stored_path = get_string_array(batch, "stored_path")?;
+    let kind = get_string_array(batch, "kind")?;
+    let size_bytes = get_i64_array(batch, "size_bytes")?;
+
+    for i in 0..batch.num_rows() {
+        rows.push(AssetRow {
+            folder_id: folder_id.value(i).to_string(),
+            original_path: original_path.value(i).to_string(),
+            stored_path: stored_path.value(i).to_string(),
+            kind: kind.value(i).to_string(),
+            size_bytes: size_bytes.value(i),
+        });
+    }
+    Ok(rows)
+}
+
 /// Create a boolean filter mask for string equality comparison
-fn create_string_eq_filter(array: &dyn Array, value: &str) -> Result<BooleanArray> {
+pub(crate) fn create_string_eq_filter(array: &dyn Array, column: &str, value: &str) -> Result<BooleanArray> {
     match array.data_type() {
         DataType::Utf8 => {
             let arr = array.as_string::<i32>();
@@ -580,55 +1090,100 @@ fn create_string_eq_filter(array: &dyn Array, value: &str) -> Result<BooleanArra
             let scalar = arrow::array::LargeStringArray::from(vec![value; arr.len()]);
             Ok(eq(arr, &scalar)?)
         }
-        _ => {
-            anyhow::bail!("Unsupported column type for filtering: {:?}", array.data_type());
-        }
+        _ => Err(ReconstructError::SchemaMismatch {
+            column: column.to_string(),
+            expected: "a string column",
+        }),
     }
 }
 
 fn get_string_array<'a>(batch: &'a RecordBatch, name: &str) -> Result<&'a StringArray> {
     batch
         .column_by_name(name)
-        .context(format!("Missing column: {}", name))?
+        .ok_or_else(|| ReconstructError::MissingColumn(name.to_string()))?
         .as_any()
         .downcast_ref::<StringArray>()
-        .context(format!("Column {} is not StringArray", name))
+        .ok_or_else(|| ReconstructError::SchemaMismatch { column: name.to_string(), expected: "StringArray" })
 }
 
 fn get_i32_array<'a>(batch: &'a RecordBatch, name: &str) -> Result<&'a Int32Array> {
     batch
         .column_by_name(name)
-        .context(format!("Missing column: {}", name))?
+        .ok_or_else(|| ReconstructError::MissingColumn(name.to_string()))?
         .as_any()
         .downcast_ref::<Int32Array>()
-        .context(format!("Column {} is not Int32Array", name))
+        .ok_or_else(|| ReconstructError::SchemaMismatch { column: name.to_string(), expected: "Int32Array" })
+}
+
+fn get_i64_array<'a>(batch: &'a RecordBatch, name: &str) -> Result<&'a Int64Array> {
+    batch
+        .column_by_name(name)
+        .ok_or_else(|| ReconstructError::MissingColumn(name.to_string()))?
+        .as_any()
+        .downcast_ref::<Int64Array>()
+        .ok_or_else(|| ReconstructError::SchemaMismatch { column: name.to_string(), expected: "Int64Array" })
 }
 
 fn get_f32_array<'a>(batch: &'a RecordBatch, name: &str) -> Result<&'a Float32Array> {
     batch
         .column_by_name(name)
-        .context(format!("Missing column: {}", name))?
+        .ok_or_else(|| ReconstructError::MissingColumn(name.to_string()))?
         .as_any()
         .downcast_ref::<Float32Array>()
-        .context(format!("Column {} is not Float32Array", name))
+        .ok_or_else(|| ReconstructError::SchemaMismatch { column: name.to_string(), expected: "Float32Array" })
 }
 
 fn get_f64_array<'a>(batch: &'a RecordBatch, name: &str) -> Result<&'a Float64Array> {
     batch
         .column_by_name(name)
-        .context(format!("Missing column: {}", name))?
+        .ok_or_else(|| ReconstructError::MissingColumn(name.to_string()))?
         .as_any()
         .downcast_ref::<Float64Array>()
-        .context(format!("Column {} is not Float64Array", name))
+        .ok_or_else(|| ReconstructError::SchemaMismatch { column: name.to_string(), expected: "Float64Array" })
 }
 
 fn get_bool_array<'a>(batch: &'a RecordBatch, name: &str) -> Result<&'a BooleanArray> {
     batch
         .column_by_name(name)
-        .context(format!("Missing column: {}", name))?
+        .ok_or_else(|| ReconstructError::MissingColumn(name.to_string()))?
         .as_any()
         .downcast_ref::<BooleanArray>()
-        .context(format!("Column {} is not BooleanArray", name))
+        .ok_or_else(|| ReconstructError::SchemaMismatch { column: name.to_string(), expected: "BooleanArray" })
+}
+
+fn get_optional_string_array<'a>(batch: &'a RecordBatch, name: &str) -> Result<Option<&'a StringArray>> {
+    if batch.column_by_name(name).is_none() {
+        return Ok(None);
+    }
+    Ok(Some(get_string_array(batch, name)?))
+}
+
+fn get_optional_bool_array<'a>(batch: &'a RecordBatch, name: &str) -> Result<Option<&'a BooleanArray>> {
+    if batch.column_by_name(name).is_none() {
+        return Ok(None);
+    }
+    Ok(Some(get_bool_array(batch, name)?))
+}
+
+fn get_optional_string_list_array<'a>(batch: &'a RecordBatch, name: &str) -> Result<Option<&'a ListArray>> {
+    match batch.column_by_name(name) {
+        Some(col) => Ok(Some(
+            col.as_any()
+                .downcast_ref::<ListArray>()
+                .ok_or_else(|| ReconstructError::SchemaMismatch { column: name.to_string(), expected: "ListArray" })?,
+        )),
+        None => Ok(None),
+    }
+}
+
+/// Read the string values of one row of a ListArray<Utf8>
+fn string_list_value(list: &ListArray, i: usize) -> Vec<String> {
+    if list.is_null(i) {
+        return Vec::new();
+    }
+    let values = list.value(i);
+    let values = values.as_string::<i32>();
+    values.iter().flatten().map(str::to_string).collect()
 }
 
 /// Wrapper for nullable i32 values
@@ -643,6 +1198,13 @@ fn get_nullable_i32_array<'a>(batch: &'a RecordBatch, name: &str) -> Result<Null
     Ok(NullableI32Array(get_i32_array(batch, name)?))
 }
 
+fn get_optional_nullable_i32_array<'a>(batch: &'a RecordBatch, name: &str) -> Result<Option<NullableI32Array<'a>>> {
+    if batch.column_by_name(name).is_none() {
+        return Ok(None);
+    }
+    Ok(Some(get_nullable_i32_array(batch, name)?))
+}
+
 /// Wrapper for nullable f64 values
 struct NullableF64Array<'a>(&'a Float64Array);
 impl<'a> NullableF64Array<'a> {
@@ -655,6 +1217,13 @@ fn get_nullable_f64_array<'a>(batch: &'a RecordBatch, name: &str) -> Result<Null
     Ok(NullableF64Array(get_f64_array(batch, name)?))
 }
 
+fn get_optional_nullable_f64_array<'a>(batch: &'a RecordBatch, name: &str) -> Result<Option<NullableF64Array<'a>>> {
+    if batch.column_by_name(name).is_none() {
+        return Ok(None);
+    }
+    Ok(Some(get_nullable_f64_array(batch, name)?))
+}
+
 /// Wrapper for nullable string values
 struct NullableStringArray<'a>(&'a StringArray);
 impl<'a> NullableStringArray<'a> {
@@ -667,6 +1236,13 @@ fn get_nullable_string_array<'a>(batch: &'a RecordBatch, name: &str) -> Result<N
     Ok(NullableStringArray(get_string_array(batch, name)?))
 }
 
+fn get_optional_nullable_string_array<'a>(batch: &'a RecordBatch, name: &str) -> Result<Option<NullableStringArray<'a>>> {
+    if batch.column_by_name(name).is_none() {
+        return Ok(None);
+    }
+    Ok(Some(get_nullable_string_array(batch, name)?))
+}
+
 /// Wrapper for nullable bool values
 struct NullableBoolArray<'a>(&'a BooleanArray);
 impl<'a> NullableBoolArray<'a> {
@@ -678,3 +1254,86 @@ impl<'a> NullableBoolArray<'a> {
 fn get_nullable_bool_array<'a>(batch: &'a RecordBatch, name: &str) -> Result<NullableBoolArray<'a>> {
     Ok(NullableBoolArray(get_bool_array(batch, name)?))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_rows;
+    use arrow::array::ArrayRef;
+    use arrow::datatypes::{Field, Schema};
+    use std::sync::Arc;
+
+    #[test]
+    fn folder_ids_tell_an_empty_table_from_a_malformed_one() {
+        let folder_ids = |dir: &Path| ParquetReader::new(dir).load_folder_ids();
+        let table = |column: &str, values: ArrayRef| {
+            let dir = test_rows::test_dir();
+            test_rows::write_table(&dir, "beatmaps", &[RecordBatch::try_from_iter([(column, values)]).unwrap()]);
+            dir
+        };
+
+        // A dataset without beatmaps and one with a zero-row table have no folders
+        assert_eq!(folder_ids(&test_rows::test_dir()).unwrap(), Vec::<String>::new());
+        let empty = table("folder_id", Arc::new(StringArray::from(Vec::<&str>::new())));
+        assert_eq!(folder_ids(&empty).unwrap(), Vec::<String>::new());
+        let ids = table("folder_id", Arc::new(StringArray::from(vec![Some("b"), None, Some("a"), Some("b")])));
+        assert_eq!(folder_ids(&ids).unwrap(), vec!["a", "b"]);
+
+        let missing = table("osu_file", Arc::new(StringArray::from(vec!["map.osu"])));
+        assert!(matches!(folder_ids(&missing), Err(ReconstructError::MissingColumn(column)) if column == "folder_id"));
+        let numeric = table("folder_id", Arc::new(Int32Array::from(vec![1])));
+        assert!(matches!(folder_ids(&numeric), Err(ReconstructError::SchemaMismatch { .. })));
+        let corrupt = test_rows::test_dir();
+        std::fs::write(corrupt.join("beatmaps.parquet"), b"not parquet").unwrap();
+        assert!(folder_ids(&corrupt).is_err());
+    }
+
+    #[test]
+    fn slider_data_streams_every_folder_in_file_order() {
+        let dir = test_rows::test_dir();
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("folder_id", DataType::Utf8, false),
+            Field::new("osu_file", DataType::Utf8, false),
+            Field::new("hit_object_index", DataType::Int32, false),
+            Field::new("repeat_count", DataType::Int32, false),
+            Field::new("velocity", DataType::Float64, false),
+            Field::new("expected_dist", DataType::Float64, true),
+        ]));
+        let batch = |folder_id: &str| {
+            RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(StringArray::from(vec![folder_id; 2])),
+                    Arc::new(StringArray::from(vec![test_rows::OSU_FILE; 2])),
+                    Arc::new(Int32Array::from(vec![0, 1])),
+                    Arc::new(Int32Array::from(vec![0, 2])),
+                    Arc::new(Float64Array::from(vec![1.0, 1.5])),
+                    Arc::new(Float64Array::from(vec![Some(100.0), None])),
+                ],
+            )
+            .unwrap()
+        };
+        test_rows::write_table(&dir, "slider_data", &[batch("b"), batch("a")]);
+
+        let reader = ParquetReader::new(&dir);
+        let rows: Vec<SliderDataRow> = reader.stream_table().collect::<Result<_>>().unwrap();
+        let fields: Vec<(&str, i32, i32, f64, Option<f64>)> = rows
+            .iter()
+            .map(|row| (row.folder_id.as_str(), row.hit_object_index, row.repeat_count, row.velocity, row.expected_dist))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                ("b", 0, 0, 1.0, Some(100.0)),
+                ("b", 1, 2, 1.5, None),
+                ("a", 0, 0, 1.0, Some(100.0)),
+                ("a", 1, 2, 1.5, None)
+            ]
+        );
+
+        // A table the dataset doesn't have is a single error item
+        let breaks: Vec<Result<BreakRow>> = reader.stream_table().collect();
+        assert_eq!(breaks.len(), 1);
+        assert!(breaks[0].is_err());
+    }
+}
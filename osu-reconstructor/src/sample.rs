@@ -0,0 +1,132 @@
+//! Stratified subsampling of a dataset by star rating
+//!
+//! Difficulties are split into equal-width star-rating bins and drawn from each
+//! bin, so a training subset can cover easy and hard maps evenly instead of
+//! mirroring the corpus (which is dominated by mid-range difficulties).
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::types::StarRatingRow;
+
+/// How the sample size is split between bins
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Allocation {
+    /// The same count from every bin; bins with too few maps give all they have
+    /// and the shortfall is spread over the others
+    Uniform,
+    /// Counts in proportion to each bin's share of the rated maps
+    Proportional,
+}
+
+/// One star-rating bin of a [`StratifiedSample`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct StarBin {
+    /// Lower edge in stars (inclusive)
+    pub low: f64,
+    /// Upper edge in stars (exclusive, except for the last bin)
+    pub high: f64,
+    pub available: usize,
+    pub selected: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct StratifiedSample<'a> {
+    /// Chosen difficulties, sorted by folder_id and osu_file
+    pub selected: Vec<&'a StarRatingRow>,
+    pub bins: Vec<StarBin>,
+    /// Difficulties left out because they have no star rating
+    pub unrated: usize,
+}
+
+/// Draw up to `n` difficulties spread over `bins` equal-width star-rating bins
+///
+/// The bins span the lowest to the highest rating present. The result only
+/// depends on the rows and the RNG, not on their order in the input.
+pub fn stratified_sample<'a>(
+    maps: &'a [StarRatingRow],
+    n: usize,
+    bins: usize,
+    allocation: Allocation,
+    rng: &mut impl Rng,
+) -> StratifiedSample<'a> {
+    let bins = bins.max(1);
+    let mut rated: Vec<(&StarRatingRow, f64)> = maps
+        .iter()
+        .filter_map(|map| map.stars.filter(|stars| stars.is_finite()).map(|stars| (map, stars)))
+        .collect();
+    let unrated = maps.len() - rated.len();
+    rated.sort_by(|(a, _), (b, _)| (&a.folder_id, &a.osu_file).cmp(&(&b.folder_id, &b.osu_file)));
+
+    let min = rated.iter().map(|(_, stars)| *stars).fold(f64::INFINITY, f64::min);
+    let max = rated.iter().map(|(_, stars)| *stars).fold(f64::NEG_INFINITY, f64::max);
+    let width = if rated.is_empty() { 0.0 } else { (max - min) / bins as f64 };
+
+    let mut members: Vec<Vec<&StarRatingRow>> = vec![Vec::new(); bins];
+    for (map, stars) in &rated {
+        let bin = if width > 0.0 { ((stars - min) / width) as usize } else { 0 };
+        members[bin.min(bins - 1)].push(map);
+    }
+
+    let sizes: Vec<usize> = members.iter().map(Vec::len).collect();
+    let quotas = match allocation {
+        Allocation::Uniform => uniform_quotas(&sizes, n),
+        Allocation::Proportional => proportional_quotas(&sizes, n),
+    };
+
+    let mut selected = Vec::new();
+    let mut star_bins = Vec::with_capacity(bins);
+    for (i, (mut bin, quota)) in members.into_iter().zip(quotas).enumerate() {
+        star_bins.push(StarBin {
+            low: min + width * i as f64,
+            high: if i + 1 == bins { max } else { min + width * (i + 1) as f64 },
+            available: bin.len(),
+            selected: quota,
+        });
+        bin.shuffle(rng);
+        selected.extend(bin.into_iter().take(quota));
+    }
+    selected.sort_by(|a, b| (&a.folder_id, &a.osu_file).cmp(&(&b.folder_id, &b.osu_file)));
+
+    StratifiedSample {
+        selected,
+        bins: star_bins,
+        unrated,
+    }
+}
+
+/// Fill bins evenly; leftover single picks go to the lowest bins with room
+fn uniform_quotas(sizes: &[usize], n: usize) -> Vec<usize> {
+    let mut quotas = vec![0; sizes.len()];
+    let mut remaining = n.min(sizes.iter().sum());
+    while remaining > 0 {
+        let open: Vec<usize> = (0..sizes.len()).filter(|&i| quotas[i] < sizes[i]).collect();
+        let share = (remaining / open.len()).max(1);
+        for i in open {
+            let take = share.min(sizes[i] - quotas[i]).min(remaining);
+            quotas[i] += take;
+            remaining -= take;
+            if remaining == 0 {
+                break;
+            }
+        }
+    }
+    quotas
+}
+
+/// Largest-remainder rounding of `n * size / total`, so the quotas sum to `n`
+fn proportional_quotas(sizes: &[usize], n: usize) -> Vec<usize> {
+    let total: usize = sizes.iter().sum();
+    let n = n.min(total);
+    if total == 0 {
+        return vec![0; sizes.len()];
+    }
+    let mut quotas: Vec<usize> = sizes.iter().map(|size| n * size / total).collect();
+    let mut by_remainder: Vec<usize> = (0..sizes.len()).collect();
+    by_remainder.sort_by_key(|&i| std::cmp::Reverse(n * sizes[i] % total));
+    let short = n - quotas.iter().sum::<usize>();
+    for &i in by_remainder.iter().take(short) {
+        quotas[i] += 1;
+    }
+    quotas
+}
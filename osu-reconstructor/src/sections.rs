@@ -0,0 +1,164 @@
+//! Line-level editing of an encoded `.osu` by section
+//!
+//! rosu-map's encoder can't express everything a dataset stores (literal slider
+//! anchors, raw events, skin settings, ...), so the reconstructor edits its
+//! output afterwards. Every such pass goes through [`OsuSections`]: the content
+//! is split into `[Section]`s once, edited as lines, and joined again with the
+//! original line ending.
+
+/// An `.osu` file split into its sections
+pub(crate) struct OsuSections {
+    line_ending: &'static str,
+    /// Lines before the first header (the format version line)
+    preamble: Vec<String>,
+    sections: Vec<Section>,
+}
+
+/// One `[Name]` section with every line up to the next header, blank lines included
+pub(crate) struct Section {
+    /// Header line as written, e.g. `[General]`
+    header: String,
+    pub lines: Vec<String>,
+}
+
+impl OsuSections {
+    pub fn parse(osu_content: &str) -> Self {
+        let line_ending = if osu_content.contains("\r\n") { "\r\n" } else { "\n" };
+        let mut preamble = Vec::new();
+        let mut sections: Vec<Section> = Vec::new();
+        for line in osu_content.lines() {
+            if line.starts_with('[') {
+                sections.push(Section { header: line.to_string(), lines: Vec::new() });
+            } else {
+                match sections.last_mut() {
+                    Some(section) => section.lines.push(line.to_string()),
+                    None => preamble.push(line.to_string()),
+                }
+            }
+        }
+        Self { line_ending, preamble, sections }
+    }
+
+    /// Parse `osu_content`, apply `edit` and join it again
+    pub fn edit(osu_content: &str, edit: impl FnOnce(&mut Self)) -> String {
+        let mut sections = Self::parse(osu_content);
+        edit(&mut sections);
+        sections.to_content()
+    }
+
+    pub fn section(&self, name: &str) -> Option<&Section> {
+        self.sections.iter().find(|section| section.name() == name)
+    }
+
+    pub fn section_mut(&mut self, name: &str) -> Option<&mut Section> {
+        self.sections.iter_mut().find(|section| section.name() == name)
+    }
+
+    pub fn remove_section(&mut self, name: &str) {
+        self.sections.retain(|section| section.name() != name);
+    }
+
+    /// Insert a section right before `before`; nothing happens if `before` is missing
+    pub fn insert_section_before(&mut self, before: &str, name: &str, lines: Vec<String>) {
+        if let Some(at) = self.sections.iter().position(|section| section.name() == before) {
+            self.sections.insert(at, Section { header: format!("[{}]", name), lines });
+        }
+    }
+
+    /// Add a section at the end, after a blank line, and end it with one
+    pub fn push_section(&mut self, name: &str, lines: &[String]) {
+        match self.sections.last_mut() {
+            Some(last) => last.lines.push(String::new()),
+            None => self.preamble.push(String::new()),
+        }
+        let mut section = Section { header: format!("[{}]", name), lines: Vec::new() };
+        section.append(lines);
+        self.sections.push(section);
+    }
+
+    fn to_content(&self) -> String {
+        let mut lines: Vec<&str> = self.preamble.iter().map(String::as_str).collect();
+        for section in &self.sections {
+            lines.push(&section.header);
+            lines.extend(section.lines.iter().map(String::as_str));
+        }
+        let mut content = lines.join(self.line_ending);
+        content.push_str(self.line_ending);
+        content
+    }
+}
+
+impl Section {
+    /// Section name without brackets
+    pub fn name(&self) -> &str {
+        let header = self.header.trim_end();
+        header.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')).unwrap_or(header)
+    }
+
+    /// Keep the `Key: value` lines for which `keep(key, value)` holds, and every other line
+    pub fn retain_fields(&mut self, mut keep: impl FnMut(&str, &str) -> bool) {
+        self.lines.retain(|line| match line.split_once(':') {
+            Some((key, value)) => keep(key.trim(), value.trim()),
+            None => true,
+        });
+    }
+
+    /// Index of the first line starting with `key`
+    pub fn position(&self, key: &str) -> Option<usize> {
+        self.lines.iter().position(|line| line.starts_with(key))
+    }
+
+    /// Index after the last non-blank line, where new lines go before the separating blanks
+    pub fn content_end(&self) -> usize {
+        self.lines.iter().rposition(|line| !line.trim().is_empty()).map_or(0, |idx| idx + 1)
+    }
+
+    pub fn is_blank(&self) -> bool {
+        self.lines.iter().all(|line| line.trim().is_empty())
+    }
+
+    /// Append lines to the end of the section, keeping one blank line before the next header
+    pub fn append(&mut self, lines: &[String]) {
+        self.lines.truncate(self.content_end());
+        self.lines.extend(lines.iter().cloned());
+        self.lines.push(String::new());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENT: &str = "osu file format v14\r\n\r\n[General]\r\nAudioFilename: a.mp3\r\nMode: 0\r\n\r\n[Colours]\r\n\r\n[HitObjects]\r\n1,2,3,1,0\r\n";
+
+    #[test]
+    fn unedited_content_is_joined_back_unchanged() {
+        assert_eq!(OsuSections::edit(CONTENT, |_| {}), CONTENT);
+        assert_eq!(OsuSections::edit("a\nb", |_| {}), "a\nb\n");
+    }
+
+    #[test]
+    fn sections_are_edited_by_name() {
+        let content = OsuSections::edit(CONTENT, |osu| {
+            osu.section_mut("General").unwrap().retain_fields(|key, _| key != "Mode");
+            assert!(osu.section("Colours").unwrap().is_blank());
+            osu.remove_section("Colours");
+            osu.insert_section_before("HitObjects", "Editor", vec!["GridSize: 8".to_string(), String::new()]);
+            osu.push_section("Extra", &["Key:1".to_string()]);
+        });
+        assert_eq!(
+            content,
+            "osu file format v14\r\n\r\n[General]\r\nAudioFilename: a.mp3\r\n\r\n[Editor]\r\nGridSize: 8\r\n\r\n[HitObjects]\r\n1,2,3,1,0\r\n\r\n[Extra]\r\nKey:1\r\n\r\n"
+        );
+    }
+
+    #[test]
+    fn appended_lines_go_before_the_separating_blanks() {
+        let mut osu = OsuSections::parse(CONTENT);
+        let general = osu.section_mut("General").unwrap();
+        assert_eq!(general.position("Mode"), Some(1));
+        assert_eq!(general.content_end(), 2);
+        general.append(&["Custom: 1".to_string()]);
+        assert_eq!(general.lines, vec!["AudioFilename: a.mp3", "Mode: 0", "Custom: 1", ""]);
+    }
+}
@@ -0,0 +1,155 @@
+//! Slider paths evaluated from the stored control points
+//!
+//! Rebuilds a single slider's curve with the same `osu_geometry` sampling the
+//! JSON export uses (bezier, linear, catmull and perfect-curve segments, clamped
+//! to the expected distance) so consumers get fixed-size point sequences without
+//! osu! curve math.
+
+use osu_geometry::ControlPoint;
+use rosu_map::section::hit_objects::HitObjectKind;
+use std::collections::HashMap;
+
+use crate::beatmap::{game_mode, BeatmapReconstructor};
+use crate::error::{ReconstructError, Result};
+use crate::json::path_kind;
+use crate::kinds::ObjectType;
+use crate::types::*;
+
+impl Dataset {
+    /// `n` points evenly spaced by arc length along one slider's path
+    ///
+    /// `hit_object_index` is the `index` column of `hit_objects` (the key of
+    /// `slider_data` and `slider_control_points`). Points are absolute osupixel
+    /// positions from the head to the end of the first span, fewer only for a
+    /// zero-length path. Returns `None` when the object isn't a slider or has no
+    /// slider data.
+    pub fn sample_path(
+        &self,
+        folder_id: &str,
+        osu_file: &str,
+        hit_object_index: i32,
+        n: usize,
+    ) -> Result<Option<Vec<(f32, f32)>>> {
+        let beatmap_row = self
+            .beatmaps
+            .iter()
+            .find(|b| b.folder_id == folder_id && b.osu_file == osu_file)
+            .ok_or_else(|| ReconstructError::BeatmapNotFound {
+                folder_id: folder_id.to_string(),
+                osu_file: osu_file.to_string(),
+            })?;
+        let in_object = |row_folder: &str, row_file: &str, index: i32| {
+            row_folder == folder_id && row_file == osu_file && index == hit_object_index
+        };
+
+        let Some(ho) = self.hit_objects.iter().find(|ho| in_object(&ho.folder_id, &ho.osu_file, ho.index)) else {
+            return Ok(None);
+        };
+        if ho.object_type.parse::<ObjectType>()? != ObjectType::Slider {
+            return Ok(None);
+        }
+
+        let slider_data_map: HashMap<i32, &SliderDataRow> = self
+            .slider_data
+            .iter()
+            .filter(|sd| in_object(&sd.folder_id, &sd.osu_file, sd.hit_object_index))
+            .map(|sd| (sd.hit_object_index, sd))
+            .collect();
+        let mut control_points: Vec<&SliderControlPointRow> = self
+            .slider_control_points
+            .iter()
+            .filter(|cp| in_object(&cp.folder_id, &cp.osu_file, cp.hit_object_index))
+            .collect();
+        control_points.sort_by_key(|cp| cp.point_index);
+        let slider_cp_map = HashMap::from([(hit_object_index, control_points)]);
+
+        let hit_object = BeatmapReconstructor::reconstruct_hit_object(
+            ho,
+            ObjectType::Slider,
+            &game_mode(beatmap_row.mode),
+            beatmap_row.key_count.unwrap_or_else(|| beatmap_row.circle_size.round().max(1.0) as i32),
+            &slider_data_map,
+            &slider_cp_map,
+            &self.features,
+        );
+        let Some(HitObjectKind::Slider(slider)) = hit_object.map(|hit_object| hit_object.kind) else {
+            return Ok(None);
+        };
+
+        let control_points: Vec<ControlPoint> = slider
+            .path
+            .control_points()
+            .iter()
+            .map(|cp| ControlPoint {
+                pos: (slider.pos.x + cp.pos.x, slider.pos.y + cp.pos.y),
+                kind: cp.path_type.map(path_kind),
+            })
+            .collect();
+        let length = slider.path.expected_dist();
+        let mut path = osu_geometry::sample_path(&control_points, length, 0.0);
+        if path.len() < n {
+            // Straight runs approximate to their two ends, so resample densely
+            // enough that every requested point falls on a real vertex pair
+            let spacing = osu_geometry::path_length(&path) as f32 / (2 * n) as f32;
+            if spacing > 0.0 {
+                path = osu_geometry::sample_path(&control_points, length, spacing);
+            }
+        }
+        let points = match n {
+            0 => Vec::new(),
+            1 => path.into_iter().take(1).collect(),
+            _ => osu_geometry::resample_to_count(&path, n),
+        };
+        Ok(Some(points))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_rows;
+
+    fn slider_dataset(curve_type: &str, anchors: &[(f32, f32)], length: f64) -> Dataset {
+        Dataset {
+            slider_data: vec![test_rows::slider_data(0, 0, length)],
+            slider_control_points: test_rows::control_points(0, anchors),
+            ..test_rows::dataset(vec![test_rows::slider(0, 1000.0, 100, 100, curve_type, 1, length)])
+        }
+    }
+
+    #[test]
+    fn linear_sliders_are_sampled_evenly_along_the_clamped_length() {
+        let dataset = slider_dataset("L", &[(0.0, 0.0), (200.0, 0.0)], 100.0);
+        let points = dataset.sample_path(test_rows::FOLDER, test_rows::OSU_FILE, 0, 5).unwrap().unwrap();
+
+        let xs: Vec<f32> = points.iter().map(|p| p.0).collect();
+        assert_eq!(points.len(), 5);
+        for (x, expected) in xs.iter().zip([100.0, 125.0, 150.0, 175.0, 200.0]) {
+            assert!((x - expected).abs() < 0.01, "{xs:?}");
+        }
+        assert!(points.iter().all(|p| p.1 == 100.0));
+    }
+
+    #[test]
+    fn sampled_paths_end_where_the_json_path_ends() {
+        let dataset = slider_dataset("P", &[(0.0, 0.0), (50.0, 50.0), (100.0, 0.0)], 120.0);
+        let points = dataset.sample_path(test_rows::FOLDER, test_rows::OSU_FILE, 0, 16).unwrap().unwrap();
+
+        let control_points = [
+            ControlPoint { pos: (100.0, 100.0), kind: Some(osu_geometry::PathKind::PerfectCurve) },
+            ControlPoint { pos: (150.0, 150.0), kind: None },
+            ControlPoint { pos: (200.0, 100.0), kind: None },
+        ];
+        let full = osu_geometry::sample_path(&control_points, Some(120.0), 0.0);
+        let (end, expected) = (points[points.len() - 1], full[full.len() - 1]);
+        assert_eq!(points.len(), 16);
+        assert_eq!(points[0], (100.0, 100.0));
+        assert!((end.0 - expected.0).abs() < 0.01 && (end.1 - expected.1).abs() < 0.01, "{end:?} vs {expected:?}");
+    }
+
+    #[test]
+    fn non_sliders_have_no_path() {
+        let dataset = test_rows::dataset(vec![test_rows::circle(0, 1000.0, 10, 10)]);
+        assert_eq!(dataset.sample_path(test_rows::FOLDER, test_rows::OSU_FILE, 0, 4).unwrap(), None);
+    }
+}
@@ -145,7 +145,7 @@ impl StoryboardReconstructor {
 
                 ReconstructedElement {
                     layer_name: elem.layer_name.clone(),
-                    element_path: elem.element_path.clone(),
+                    element_path: elem.element_path_original.clone().unwrap_or_else(|| elem.element_path.clone()),
                     element_type: elem.element_type.clone(),
                     origin: elem.origin.clone(),
                     initial_pos_x: elem.initial_pos_x,
@@ -0,0 +1,247 @@
+//! Row constructors for unit tests
+//!
+//! Every row belongs to folder `f`, difficulty `map.osu`, with the values a
+//! plain osu!standard map would have unless the test sets them.
+
+use arrow::array::RecordBatch;
+use parquet::arrow::ArrowWriter;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::types::*;
+
+pub const FOLDER: &str = "f";
+pub const OSU_FILE: &str = "map.osu";
+
+/// Fresh directory under the system temp directory for tests that write tables
+pub fn test_dir() -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let dir = std::env::temp_dir().join(format!(
+        "osu-reconstructor-test-{}-{}",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Write `batches` as `<table>.parquet` in `dir`
+pub fn write_table(dir: &Path, table: &str, batches: &[RecordBatch]) {
+    let file = File::create(dir.join(format!("{}.parquet", table))).unwrap();
+    let mut writer = ArrowWriter::try_new(file, batches[0].schema(), None).unwrap();
+    for batch in batches {
+        writer.write(batch).unwrap();
+    }
+    writer.close().unwrap();
+}
+
+pub fn beatmap(mode: i32) -> BeatmapRow {
+    BeatmapRow {
+        folder_id: FOLDER.to_string(),
+        osu_file: OSU_FILE.to_string(),
+        format_version: 14,
+        audio_file: "audio.mp3".to_string(),
+        audio_lead_in: 0.0,
+        preview_time: -1,
+        default_sample_bank: 1,
+        default_sample_volume: 100,
+        stack_leniency: 0.7,
+        mode,
+        letterbox_in_breaks: false,
+        special_style: false,
+        widescreen_storyboard: false,
+        epilepsy_warning: false,
+        samples_match_playback_rate: false,
+        countdown: 0,
+        countdown_offset: 0,
+        bookmarks: String::new(),
+        distance_spacing: 1.0,
+        beat_divisor: 4,
+        grid_size: 4,
+        timeline_zoom: 1.0,
+        title: "Title".to_string(),
+        title_unicode: "Title".to_string(),
+        artist: "Artist".to_string(),
+        artist_unicode: "Artist".to_string(),
+        creator: "Mapper".to_string(),
+        version: "Normal".to_string(),
+        source: String::new(),
+        tags: String::new(),
+        tags_list: Vec::new(),
+        beatmap_id: 1,
+        beatmap_set_id: 1,
+        hp_drain_rate: 5.0,
+        circle_size: 4.0,
+        overall_difficulty: 5.0,
+        approach_rate: 5.0,
+        slider_multiplier: 1.4,
+        slider_tick_rate: 1.0,
+        background_file: String::new(),
+        audio_path: String::new(),
+        background_path: String::new(),
+        overlay_position: "NoChange".to_string(),
+        skin_preference: String::new(),
+        use_skin_sprites: false,
+        always_show_playfield: false,
+        skipped_too_large: false,
+        key_count: (mode == 3).then_some(4),
+        columns_out_of_range: false,
+        audio_file_resolved: "audio.mp3".to_string(),
+        background_file_resolved: String::new(),
+    }
+}
+
+fn hit_object(index: i32, start_time: f64, object_type: &str) -> HitObjectRow {
+    HitObjectRow {
+        folder_id: FOLDER.to_string(),
+        osu_file: OSU_FILE.to_string(),
+        index,
+        start_time,
+        object_type: object_type.to_string(),
+        pos_x: None,
+        pos_y: None,
+        new_combo: index == 0,
+        combo_offset: 0,
+        curve_type: None,
+        slides: None,
+        length: None,
+        end_time: None,
+        duration: None,
+        column: None,
+    }
+}
+
+pub fn circle(index: i32, start_time: f64, x: i32, y: i32) -> HitObjectRow {
+    HitObjectRow {
+        pos_x: Some(x),
+        pos_y: Some(y),
+        ..hit_object(index, start_time, "circle")
+    }
+}
+
+/// Slider with `slides` spans, `length` osupixels long
+pub fn slider(index: i32, start_time: f64, x: i32, y: i32, curve_type: &str, slides: i32, length: f64) -> HitObjectRow {
+    HitObjectRow {
+        pos_x: Some(x),
+        pos_y: Some(y),
+        curve_type: Some(curve_type.to_string()),
+        slides: Some(slides),
+        length: Some(length),
+        ..hit_object(index, start_time, "slider")
+    }
+}
+
+pub fn spinner(index: i32, start_time: f64, end_time: f64) -> HitObjectRow {
+    HitObjectRow {
+        pos_x: Some(256),
+        pos_y: Some(192),
+        end_time: Some(end_time),
+        duration: Some(end_time - start_time),
+        ..hit_object(index, start_time, "spinner")
+    }
+}
+
+/// Mania hold in `column` with an absolute `end_time`; x is left to the column
+pub fn hold(index: i32, start_time: f64, end_time: f64, column: i32) -> HitObjectRow {
+    HitObjectRow {
+        end_time: Some(end_time),
+        duration: Some(end_time - start_time),
+        column: Some(column),
+        ..hit_object(index, start_time, "hold")
+    }
+}
+
+fn timing_point(time: f64, point_type: &str) -> TimingPointRow {
+    TimingPointRow {
+        folder_id: FOLDER.to_string(),
+        osu_file: OSU_FILE.to_string(),
+        time,
+        point_type: point_type.to_string(),
+        beat_length: None,
+        time_signature: None,
+        slider_velocity: None,
+        kiai: None,
+        sample_bank: None,
+        sample_volume: None,
+        sample_index: None,
+        order_index: None,
+    }
+}
+
+pub fn timing(time: f64, beat_length: f64) -> TimingPointRow {
+    TimingPointRow {
+        beat_length: Some(beat_length),
+        time_signature: Some("4/4".to_string()),
+        ..timing_point(time, "timing")
+    }
+}
+
+pub fn difficulty(time: f64, slider_velocity: f64) -> TimingPointRow {
+    TimingPointRow {
+        slider_velocity: Some(slider_velocity),
+        ..timing_point(time, "difficulty")
+    }
+}
+
+pub fn sample(time: f64, bank: &str, volume: i32) -> TimingPointRow {
+    TimingPointRow {
+        sample_bank: Some(bank.to_string()),
+        sample_volume: Some(volume),
+        sample_index: Some(0),
+        ..timing_point(time, "sample")
+    }
+}
+
+/// Anchors of one slider, the head first, all without a type of their own
+pub fn control_points(hit_object_index: i32, anchors: &[(f32, f32)]) -> Vec<SliderControlPointRow> {
+    anchors
+        .iter()
+        .enumerate()
+        .map(|(i, &(pos_x, pos_y))| SliderControlPointRow {
+            folder_id: FOLDER.to_string(),
+            osu_file: OSU_FILE.to_string(),
+            hit_object_index,
+            point_index: i as i32,
+            pos_x,
+            pos_y,
+            path_type: None,
+        })
+        .collect()
+}
+
+pub fn slider_data(hit_object_index: i32, repeat_count: i32, expected_dist: f64) -> SliderDataRow {
+    SliderDataRow {
+        folder_id: FOLDER.to_string(),
+        osu_file: OSU_FILE.to_string(),
+        hit_object_index,
+        repeat_count,
+        velocity: 1.0,
+        expected_dist: Some(expected_dist),
+    }
+}
+
+pub fn hit_sample(hit_object_index: i32, sample_index: i32, name: &str, volume: i32) -> HitSampleRow {
+    HitSampleRow {
+        folder_id: FOLDER.to_string(),
+        osu_file: OSU_FILE.to_string(),
+        hit_object_index,
+        sample_index,
+        name: name.to_string(),
+        bank: "None".to_string(),
+        suffix: None,
+        volume,
+        scope: String::new(),
+        node_index: None,
+    }
+}
+
+/// osu!standard difficulty at 120 BPM (one red line at 0) holding `hit_objects`
+pub fn dataset(hit_objects: Vec<HitObjectRow>) -> Dataset {
+    Dataset {
+        beatmaps: vec![beatmap(0)],
+        hit_objects,
+        timing_points: vec![timing(0.0, 500.0)],
+        ..Dataset::default()
+    }
+}
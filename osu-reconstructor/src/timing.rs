@@ -0,0 +1,250 @@
+//! Control point lookup for resolving timing state at a given time
+
+use crate::kinds::SampleBank;
+use crate::types::TimingPointRow;
+
+/// Slider distance covered per beat at slider multiplier 1.0 and velocity 1.0
+pub const BASE_SCORING_DISTANCE: f64 = 100.0;
+
+/// Slider velocity in osupixels per ms, as stored in `slider_data.velocity`
+///
+/// The stored velocity already includes the slider multiplier and the active
+/// difficulty point's SV, folded into the effective beat length:
+///
+/// `velocity = BASE_SCORING_DISTANCE * slider_multiplier / effective_beat_length`
+///
+/// The `.osu` file only holds the multiplier and the timing points, so this is
+/// what a reconstructed file reproduces once osu! re-reads it.
+pub fn slider_velocity(slider_multiplier: f64, effective_beat_length: f64) -> f64 {
+    BASE_SCORING_DISTANCE * slider_multiplier / effective_beat_length
+}
+
+/// `.osu` length field (osupixels) of a slider from its stored velocity and duration
+///
+/// The length is a distance, not a time: a span takes `length / velocity` ms, so
+/// `length = velocity * duration / span_count`. Writing the duration, or scaling
+/// the length by the SV a second time, yields sliders of the wrong length.
+///
+/// Worked example: `SliderMultiplier:1.4`, a 180 BPM timing point (beat length
+/// 333.33 ms) and an inherited point of `-200` (SV 0.5) give an effective beat
+/// length of 666.67 ms and a velocity of `100 * 1.4 / 666.67 = 0.21` px/ms. A
+/// slider with one repeat (2 spans) lasting 1000 ms then has
+/// `length = 0.21 * 1000 / 2 = 105`.
+pub fn osu_slider_length(velocity: f64, duration: f64, span_count: i32) -> f64 {
+    velocity * duration / span_count.max(1) as f64
+}
+
+/// Sample settings from the sample point active at a given time
+#[derive(Debug, Clone, PartialEq)]
+pub struct SampleState {
+    /// Sample bank (Normal when the row has none or an unrecognised one)
+    pub bank: SampleBank,
+    /// Volume (0-100)
+    pub volume: i32,
+    /// Custom sample index (0 = default samples)
+    pub index: i32,
+}
+
+/// Resolves the control point state of a single difficulty at arbitrary times
+///
+/// Follows osu! semantics: the last point at or before the given time applies,
+/// and times before the first point use the first point.
+#[derive(Debug, Clone, Default)]
+pub struct TimingResolver {
+    /// (time, beat_length) from uninherited timing points
+    timing: Vec<(f64, f64)>,
+    /// (time, slider_velocity) from difficulty points
+    difficulty: Vec<(f64, f64)>,
+    /// (time, kiai) from effect points
+    effect: Vec<(f64, bool)>,
+    /// (time, sample state) from sample points
+    sample: Vec<(f64, SampleState)>,
+}
+
+impl TimingResolver {
+    /// Build a resolver from the timing point rows of one `.osu` file
+    pub fn new(rows: &[TimingPointRow], folder_id: &str, osu_file: &str) -> Self {
+        let mut resolver = Self::default();
+
+        for tp in rows
+            .iter()
+            .filter(|tp| tp.folder_id == folder_id && tp.osu_file == osu_file)
+        {
+            match tp.point_type.as_str() {
+                "timing" => {
+                    if let Some(beat_length) = tp.beat_length {
+                        resolver.timing.push((tp.time, beat_length));
+                    }
+                }
+                "difficulty" => {
+                    resolver.difficulty.push((tp.time, tp.slider_velocity.unwrap_or(1.0)));
+                }
+                "effect" => {
+                    resolver.effect.push((tp.time, tp.kiai.unwrap_or(false)));
+                }
+                "sample" => {
+                    resolver.sample.push((
+                        tp.time,
+                        SampleState {
+                            bank: tp
+                                .sample_bank
+                                .as_deref()
+                                .and_then(|bank| bank.parse().ok())
+                                .unwrap_or(SampleBank::Normal),
+                            volume: tp.sample_volume.unwrap_or(100),
+                            index: tp.sample_index.unwrap_or(0),
+                        },
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        // Stable sort keeps file order for points sharing a timestamp
+        resolver.timing.sort_by(|a, b| a.0.total_cmp(&b.0));
+        resolver.difficulty.sort_by(|a, b| a.0.total_cmp(&b.0));
+        resolver.effect.sort_by(|a, b| a.0.total_cmp(&b.0));
+        resolver.sample.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        resolver
+    }
+
+    /// Beat length (ms per beat) at the given time
+    pub fn beat_length_at(&self, time: f64) -> Option<f64> {
+        point_at(&self.timing, time).copied()
+    }
+
+    /// Slider velocity multiplier at the given time (1.0 if no difficulty points)
+    pub fn slider_velocity_at(&self, time: f64) -> f64 {
+        point_at(&self.difficulty, time).copied().unwrap_or(1.0)
+    }
+
+    /// Beat length scaled by the slider velocity multiplier (see [`slider_velocity`])
+    pub fn effective_beat_length_at(&self, time: f64) -> Option<f64> {
+        self.beat_length_at(time).map(|beat_length| beat_length / self.slider_velocity_at(time))
+    }
+
+    /// Whether kiai is active at the given time
+    pub fn kiai_at(&self, time: f64) -> bool {
+        point_at(&self.effect, time).copied().unwrap_or(false)
+    }
+
+    /// Sample settings at the given time
+    pub fn sample_at(&self, time: f64) -> Option<&SampleState> {
+        point_at(&self.sample, time)
+    }
+
+    /// Effective volume for a hit sample at the given time
+    ///
+    /// An object volume of 0 means the sample inherits the sample point volume.
+    pub fn effective_sample_volume(&self, time: f64, object_volume: i32) -> i32 {
+        if object_volume > 0 {
+            object_volume
+        } else {
+            self.sample_at(time).map(|s| s.volume).unwrap_or(100)
+        }
+    }
+
+    /// Effective bank for a hit sample at the given time
+    ///
+    /// An object bank of `None` means the sample inherits the sample point bank.
+    pub fn effective_sample_bank(&self, time: f64, object_bank: SampleBank) -> SampleBank {
+        if object_bank != SampleBank::None {
+            object_bank
+        } else {
+            self.sample_at(time)
+                .map(|s| s.bank)
+                .unwrap_or(SampleBank::Normal)
+        }
+    }
+}
+
+/// Find the value of the last point at or before `time`, falling back to the first point
+fn point_at<T>(points: &[(f64, T)], time: f64) -> Option<&T> {
+    let idx = points.partition_point(|(t, _)| *t <= time);
+    points.get(idx.saturating_sub(1)).map(|(_, v)| v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_rows::{self, FOLDER, OSU_FILE};
+
+    fn volume_change() -> TimingResolver {
+        let rows = vec![
+            test_rows::timing(0.0, 500.0),
+            test_rows::sample(0.0, "Soft", 80),
+            test_rows::sample(2000.0, "Drum", 30),
+        ];
+        TimingResolver::new(&rows, FOLDER, OSU_FILE)
+    }
+
+    #[test]
+    fn sample_volume_follows_the_active_point() {
+        let resolver = volume_change();
+        assert_eq!(resolver.effective_sample_volume(1999.0, 0), 80);
+        assert_eq!(resolver.effective_sample_volume(2000.0, 0), 30);
+        assert_eq!(resolver.effective_sample_volume(5000.0, 0), 30);
+        // Before the first point the first point applies
+        assert_eq!(resolver.effective_sample_volume(-100.0, 0), 80);
+    }
+
+    #[test]
+    fn object_volume_overrides_the_sample_point() {
+        let resolver = volume_change();
+        assert_eq!(resolver.effective_sample_volume(2500.0, 55), 55);
+    }
+
+    #[test]
+    fn sample_bank_inherits_only_from_none() {
+        let resolver = volume_change();
+        assert_eq!(resolver.effective_sample_bank(1000.0, SampleBank::None), SampleBank::Soft);
+        assert_eq!(resolver.effective_sample_bank(3000.0, SampleBank::None), SampleBank::Drum);
+        assert_eq!(resolver.effective_sample_bank(3000.0, SampleBank::Normal), SampleBank::Normal);
+    }
+
+    #[test]
+    fn rows_of_other_difficulties_are_ignored() {
+        let mut other = test_rows::sample(1000.0, "Normal", 5);
+        other.osu_file = "other.osu".to_string();
+        let rows = vec![test_rows::sample(0.0, "Soft", 80), other];
+        let resolver = TimingResolver::new(&rows, FOLDER, OSU_FILE);
+        assert_eq!(resolver.effective_sample_volume(1500.0, 0), 80);
+    }
+
+    #[test]
+    fn no_sample_points_default_to_full_normal() {
+        let resolver = TimingResolver::new(&[test_rows::timing(0.0, 500.0)], FOLDER, OSU_FILE);
+        assert_eq!(resolver.effective_sample_volume(0.0, 0), 100);
+        assert_eq!(resolver.effective_sample_bank(0.0, SampleBank::None), SampleBank::Normal);
+    }
+
+    #[test]
+    fn stored_velocity_gives_back_the_fixture_lengths() {
+        let fixture = include_str!("../fixtures/slider_sv/fixture - Slider Velocity (osu-reconstructor) [SV].osu");
+        // The fixture's red line and its two inherited points (SV 0.5 and 2)
+        let rows = vec![
+            test_rows::timing(0.0, 333.333333333333),
+            test_rows::difficulty(2000.0, 0.5),
+            test_rows::difficulty(4000.0, 2.0),
+        ];
+        let resolver = TimingResolver::new(&rows, FOLDER, OSU_FILE);
+        let sliders = fixture.lines().skip_while(|line| *line != "[HitObjects]").skip(1).filter(|line| !line.is_empty());
+
+        let mut lengths = Vec::new();
+        for (line, duration) in sliders.zip([333.333333333333, 1000.0, 250.0]) {
+            let fields: Vec<&str> = line.split(',').collect();
+            let (time, spans, length): (f64, i32, f64) =
+                (fields[2].parse().unwrap(), fields[6].parse().unwrap(), fields[7].parse().unwrap());
+            let velocity = slider_velocity(1.4, resolver.effective_beat_length_at(time).unwrap());
+            let recovered = osu_slider_length(velocity, duration, spans);
+            assert!((recovered - length).abs() < 1e-6, "{}: {} != {}", line, recovered, length);
+            lengths.push(length);
+        }
+        assert_eq!(lengths, vec![140.0, 105.0, 210.0]);
+
+        // The README's worked example is the second slider
+        assert!((slider_velocity(1.4, resolver.effective_beat_length_at(2000.0).unwrap()) - 0.21).abs() < 1e-9);
+        assert_eq!(osu_slider_length(0.5, 100.0, 0), 50.0);
+    }
+}
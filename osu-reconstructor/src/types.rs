@@ -1,7 +1,11 @@
 //! Core types for representing parquet row data
 
+use serde::Serialize;
+
+use crate::manifest::DatasetFeatures;
+
 /// Beatmap metadata row from beatmaps.parquet
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct BeatmapRow {
     pub folder_id: String,
     pub osu_file: String,
@@ -36,6 +40,8 @@ pub struct BeatmapRow {
     pub version: String,
     pub source: String,
     pub tags: String,
+    /// Tags split on whitespace
+    pub tags_list: Vec<String>,
     pub beatmap_id: i32,
     pub beatmap_set_id: i32,
     // Difficulty section
@@ -49,6 +55,22 @@ pub struct BeatmapRow {
     pub background_file: String,
     pub audio_path: String,
     pub background_path: String,
+    // Skin settings (schema v19; older datasets read as osu!'s defaults)
+    pub overlay_position: String,
+    pub skin_preference: String,
+    pub use_skin_sprites: bool,
+    pub always_show_playfield: bool,
+    /// Built with more hit objects than `--max-objects`, so the per-object tables have no rows for it
+    /// (schema v20; false for older datasets)
+    pub skipped_too_large: bool,
+    /// Mania key count (schema v21; derived from the circle size for older mania datasets, None outside mania)
+    pub key_count: Option<i32>,
+    /// Some mania object lies outside the key columns, so its `column` is clamped (schema v21)
+    pub columns_out_of_range: bool,
+    /// `audio_file` with its on-disk casing (schema v25; `audio_file` itself before that)
+    pub audio_file_resolved: String,
+    /// `background_file` with its on-disk casing (schema v25; `background_file` itself before that)
+    pub background_file_resolved: String,
 }
 
 /// Hit object row from hit_objects.parquet
@@ -64,13 +86,19 @@ pub struct HitObjectRow {
     pub new_combo: bool,
     pub combo_offset: i32,
     pub curve_type: Option<String>,
+    /// `.osu` slides value (repeats + 1) from schema v17; the repeat count before that.
+    /// Use `SliderDataRow::repeat_count`, which means the same in every version.
     pub slides: Option<i32>,
     pub length: Option<f64>,
     pub end_time: Option<f64>,
+    /// Total active time in ms (schema v8; None in older datasets)
+    pub duration: Option<f64>,
+    /// Mania key column (schema v10; None outside mania and in older datasets)
+    pub column: Option<i32>,
 }
 
 /// Timing point row from timing_points.parquet
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct TimingPointRow {
     pub folder_id: String,
     pub osu_file: String,
@@ -82,6 +110,9 @@ pub struct TimingPointRow {
     pub kiai: Option<bool>,
     pub sample_bank: Option<String>,
     pub sample_volume: Option<i32>,
+    pub sample_index: Option<i32>,
+    /// `[TimingPoints]` line the point came from (schema v12)
+    pub order_index: Option<i32>,
 }
 
 /// Storyboard element row from storyboard_elements.parquet
@@ -92,6 +123,8 @@ pub struct StoryboardElementRow {
     pub element_index: i32,
     pub layer_name: String,
     pub element_path: String,
+    /// `element_path` as written in the source, when it used backslashes
+    pub element_path_original: Option<String>,
     pub element_type: String,
     pub origin: String,
     pub initial_pos_x: f32,
@@ -141,7 +174,7 @@ pub struct SliderDataRow {
 }
 
 /// Break period row from breaks.parquet
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct BreakRow {
     pub folder_id: String,
     pub osu_file: String,
@@ -149,8 +182,40 @@ pub struct BreakRow {
     pub end_time: f64,
 }
 
+/// Raw `[Events]` line from events.parquet (videos, samples and other events no structured table covers)
+#[derive(Debug, Clone, Serialize)]
+pub struct EventRow {
+    pub folder_id: String,
+    pub osu_file: String,
+    pub event_index: i32,
+    pub event_type: String,
+    pub start_time: Option<f64>,
+    pub params: String,
+}
+
+/// Star rating of one difficulty from `ParquetReader::load_star_ratings`
+#[derive(Debug, Clone, Serialize)]
+pub struct StarRatingRow {
+    pub folder_id: String,
+    pub osu_file: String,
+    pub beatmap_id: i32,
+    /// None when neither the enriched table nor `--compute-stars` rated the map
+    pub stars: Option<f64>,
+}
+
+/// Unrecognised `[General]`/`[Metadata]` key from extra_fields.parquet
+#[derive(Debug, Clone, Serialize)]
+pub struct ExtraFieldRow {
+    pub folder_id: String,
+    pub osu_file: String,
+    pub section: String,
+    pub field_index: i32,
+    pub key: String,
+    pub value: String,
+}
+
 /// Combo color row from combo_colors.parquet
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ComboColorRow {
     pub folder_id: String,
     pub osu_file: String,
@@ -163,7 +228,7 @@ pub struct ComboColorRow {
 }
 
 /// Hit sample row from hit_samples.parquet
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct HitSampleRow {
     pub folder_id: String,
     pub osu_file: String,
@@ -173,6 +238,10 @@ pub struct HitSampleRow {
     pub bank: String,
     pub suffix: Option<String>,
     pub volume: i32,
+    /// `head`, `body`, `repeat` or `tail` (empty for datasets before schema v6)
+    pub scope: String,
+    /// Slider node the sample belongs to (None for object-level samples)
+    pub node_index: Option<i32>,
 }
 
 /// Storyboard loop row from storyboard_loops.parquet
@@ -201,6 +270,16 @@ pub struct StoryboardTriggerRow {
     pub is_embedded: bool,
 }
 
+/// Copied asset row from assets.parquet
+#[derive(Debug, Clone)]
+pub struct AssetRow {
+    pub folder_id: String,
+    pub original_path: String,
+    pub stored_path: String,
+    pub kind: String,
+    pub size_bytes: i64,
+}
+
 /// Complete dataset loaded from parquet files
 #[derive(Debug, Default)]
 pub struct Dataset {
@@ -212,8 +291,13 @@ pub struct Dataset {
     pub slider_control_points: Vec<SliderControlPointRow>,
     pub slider_data: Vec<SliderDataRow>,
     pub breaks: Vec<BreakRow>,
+    pub events: Vec<EventRow>,
+    pub extra_fields: Vec<ExtraFieldRow>,
     pub combo_colors: Vec<ComboColorRow>,
     pub hit_samples: Vec<HitSampleRow>,
     pub storyboard_loops: Vec<StoryboardLoopRow>,
     pub storyboard_triggers: Vec<StoryboardTriggerRow>,
+    pub assets: Vec<AssetRow>,
+    /// Optional data available in the dataset these rows came from
+    pub features: DatasetFeatures,
 }
@@ -0,0 +1,74 @@
+//! osu-text: Text decoding for hand-parsed `.osu`/`.osb` files
+//!
+//! Most tools read `.osu` files through rosu-map, but a few scan sections by
+//! hand (image references in the extractor, raw `[Events]` lines in the builder).
+//! Those scans compare whole lines like `[Events]`, which breaks on a leading
+//! byte order mark, UTF-16 files, or old Mac `\r` line endings.
+
+#[cfg(feature = "test-utils")]
+pub mod testutil;
+
+/// Decode `.osu`/`.osb` bytes into text with `\n` line endings
+///
+/// - A UTF-8 BOM is stripped
+/// - UTF-16 LE/BE files (detected by their BOM) are decoded as UTF-16
+/// - Anything else is decoded as UTF-8, replacing invalid sequences
+/// - `\r\n` and lone `\r` become `\n`
+pub fn normalize_osu_text(bytes: &[u8]) -> String {
+    let text = match bytes {
+        [0xEF, 0xBB, 0xBF, rest @ ..] => String::from_utf8_lossy(rest).into_owned(),
+        [0xFF, 0xFE, rest @ ..] => decode_utf16(rest, u16::from_le_bytes),
+        [0xFE, 0xFF, rest @ ..] => decode_utf16(rest, u16::from_be_bytes),
+        _ => String::from_utf8_lossy(bytes).into_owned(),
+    };
+
+    // A BOM can also survive inside the text (e.g. files concatenated by tools)
+    let text = text.trim_start_matches('\u{feff}');
+
+    if text.contains('\r') {
+        text.replace("\r\n", "\n").replace('\r', "\n")
+    } else {
+        text.to_string()
+    }
+}
+
+fn decode_utf16(bytes: &[u8], from_bytes: fn([u8; 2]) -> u16) -> String {
+    let units = bytes.chunks_exact(2).map(|pair| from_bytes([pair[0], pair[1]]));
+    char::decode_utf16(units)
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16(text: &str, bom: [u8; 2], to_bytes: fn(u16) -> [u8; 2]) -> Vec<u8> {
+        let mut bytes = bom.to_vec();
+        bytes.extend(text.encode_utf16().flat_map(to_bytes));
+        bytes
+    }
+
+    #[test]
+    fn utf8_bom_is_stripped() {
+        assert_eq!(normalize_osu_text(b"\xEF\xBB\xBF[General]\n"), "[General]\n");
+        assert_eq!(normalize_osu_text("\u{feff}[General]\n".as_bytes()), "[General]\n");
+    }
+
+    #[test]
+    fn utf16_is_decoded_by_its_bom() {
+        let text = "[Metadata]\nTitle:\u{00e9}t\u{00e9}\n";
+        assert_eq!(normalize_osu_text(&utf16(text, [0xFF, 0xFE], u16::to_le_bytes)), text);
+        assert_eq!(normalize_osu_text(&utf16(text, [0xFE, 0xFF], u16::to_be_bytes)), text);
+    }
+
+    #[test]
+    fn line_endings_become_newlines() {
+        assert_eq!(normalize_osu_text(b"a\r\nb\rc\n"), "a\nb\nc\n");
+    }
+
+    #[test]
+    fn invalid_utf8_is_replaced() {
+        assert_eq!(normalize_osu_text(b"a\xFFb"), "a\u{fffd}b");
+    }
+}
@@ -0,0 +1,324 @@
+//! Synthetic `.osu` files for tests (`test-utils` feature)
+//!
+//! [`SyntheticMap`] writes a valid osu!standard beatmap from a handful of knobs,
+//! so tests can build known inputs instead of depending on real (copyrighted)
+//! maps. The same settings and seed always give byte-identical output.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Hit object kinds the generator can emit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntheticObject {
+    Circle,
+    /// Linear or bezier slider of one beat per slide
+    Slider,
+    /// Two-beat spinner in the playfield centre
+    Spinner,
+}
+
+/// Settings of a generated beatmap
+#[derive(Debug, Clone)]
+pub struct SyntheticMap {
+    /// Seed for positions, slider shapes and inherited point velocities
+    pub seed: u64,
+    pub object_count: usize,
+    /// Kinds of the objects in order, repeated as needed (circles only if empty)
+    pub kinds: Vec<SyntheticObject>,
+    pub bpm: f64,
+    /// Time of the first timing point and object, in ms
+    pub offset: f64,
+    /// Gap between objects as a fraction of a beat (2 = 1/2 beat)
+    pub beat_divisor: u32,
+    /// Inherited timing points spread over the map, each with a random slider velocity
+    pub velocity_changes: usize,
+    /// Objects per combo; spinners always start a new combo
+    pub combo_length: usize,
+    /// Fading sprites written to the `[Events]` background layer
+    pub storyboard_sprites: usize,
+    pub slider_multiplier: f64,
+    pub circle_size: f32,
+    pub approach_rate: f32,
+    pub overall_difficulty: f32,
+    pub title: String,
+    pub version: String,
+    pub audio_file: String,
+}
+
+impl Default for SyntheticMap {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            object_count: 16,
+            kinds: vec![SyntheticObject::Circle, SyntheticObject::Circle, SyntheticObject::Slider],
+            bpm: 120.0,
+            offset: 1000.0,
+            beat_divisor: 2,
+            velocity_changes: 0,
+            combo_length: 4,
+            storyboard_sprites: 0,
+            slider_multiplier: 1.4,
+            circle_size: 4.0,
+            approach_rate: 9.0,
+            overall_difficulty: 8.0,
+            title: "Synthetic".to_string(),
+            version: "Generated".to_string(),
+            audio_file: "audio.mp3".to_string(),
+        }
+    }
+}
+
+/// Slider velocities inherited points choose from
+const VELOCITIES: [f64; 4] = [0.5, 0.75, 1.0, 1.5];
+
+impl SyntheticMap {
+    /// File name the map is written under: `Artist - Title (Creator) [Version].osu`
+    pub fn file_name(&self) -> String {
+        format!("synthetic - {} (testutil) [{}].osu", self.title, self.version)
+    }
+
+    /// Render the `.osu` file
+    pub fn to_osu_string(&self) -> String {
+        let mut rng = SplitMix64(self.seed);
+        let beat_len = 60_000.0 / self.bpm;
+        let step = beat_len / self.beat_divisor.max(1) as f64;
+        let kind_at = |i: usize| {
+            if self.kinds.is_empty() {
+                SyntheticObject::Circle
+            } else {
+                self.kinds[i % self.kinds.len()]
+            }
+        };
+
+        // Lay out object times first so inherited points can be placed among them
+        let mut times = Vec::with_capacity(self.object_count);
+        let mut time = self.offset;
+        for i in 0..self.object_count {
+            times.push(time);
+            let beats = match kind_at(i) {
+                SyntheticObject::Circle => 0.0,
+                SyntheticObject::Slider => 1.0,
+                SyntheticObject::Spinner => 2.0,
+            };
+            time += beats * beat_len + step;
+        }
+        let end_time = time;
+
+        // (time, slider velocity) of every inherited point
+        let mut velocity_points = Vec::with_capacity(self.velocity_changes);
+        for i in 0..self.velocity_changes {
+            let at = self.offset + (end_time - self.offset) * (i + 1) as f64 / (self.velocity_changes + 1) as f64;
+            let velocity = VELOCITIES[rng.below(VELOCITIES.len() as u64) as usize];
+            velocity_points.push((at.round(), velocity));
+        }
+        let velocity_at = |t: f64| {
+            velocity_points
+                .iter()
+                .take_while(|(at, _)| *at <= t)
+                .last()
+                .map_or(1.0, |(_, velocity)| *velocity)
+        };
+
+        let mut out = String::new();
+        out.push_str("osu file format v14\n\n");
+        out.push_str("[General]\n");
+        let _ = writeln!(out, "AudioFilename: {}", self.audio_file);
+        out.push_str("AudioLeadIn: 0\nPreviewTime: -1\nCountdown: 0\nSampleSet: Normal\n");
+        out.push_str("StackLeniency: 0.7\nMode: 0\nLetterboxInBreaks: 0\nWidescreenStoryboard: 0\n\n");
+
+        out.push_str("[Metadata]\n");
+        let _ = writeln!(out, "Title:{}\nTitleUnicode:{}", self.title, self.title);
+        out.push_str("Artist:synthetic\nArtistUnicode:synthetic\nCreator:testutil\n");
+        let _ = writeln!(out, "Version:{}", self.version);
+        out.push_str("Source:\nTags:synthetic\nBeatmapID:0\nBeatmapSetID:-1\n\n");
+
+        out.push_str("[Difficulty]\n");
+        let _ = writeln!(out, "HPDrainRate:5\nCircleSize:{}", self.circle_size);
+        let _ = writeln!(out, "OverallDifficulty:{}\nApproachRate:{}", self.overall_difficulty, self.approach_rate);
+        let _ = writeln!(out, "SliderMultiplier:{}\nSliderTickRate:1\n", self.slider_multiplier);
+
+        out.push_str("[Events]\n//Background and Video events\n//Break Periods\n");
+        out.push_str("//Storyboard Layer 0 (Background)\n");
+        for i in 0..self.storyboard_sprites {
+            let start = (self.offset + (end_time - self.offset) * i as f64 / self.storyboard_sprites as f64).round();
+            let x = 120 + rng.below(400);
+            let y = 100 + rng.below(280);
+            let _ = writeln!(out, "Sprite,Background,Centre,\"sb/sprite{}.png\",{},{}", i, x, y);
+            let _ = writeln!(out, " F,0,{},{},1,0", start, (start + beat_len * 4.0).round());
+        }
+        out.push_str("//Storyboard Layer 1 (Fail)\n//Storyboard Layer 2 (Pass)\n//Storyboard Layer 3 (Foreground)\n");
+        out.push_str("//Storyboard Sound Samples\n\n");
+
+        out.push_str("[TimingPoints]\n");
+        let _ = writeln!(out, "{},{},4,1,0,100,1,0", self.offset, beat_len);
+        for (at, velocity) in &velocity_points {
+            let _ = writeln!(out, "{},{},4,1,0,100,0,0", at, -100.0 / velocity);
+        }
+        out.push('\n');
+
+        out.push_str("[HitObjects]\n");
+        for (i, &time) in times.iter().enumerate() {
+            let kind = kind_at(i);
+            let new_combo = i == 0
+                || kind == SyntheticObject::Spinner
+                || (self.combo_length > 0 && i % self.combo_length == 0);
+            let combo_bit = if new_combo { 4 } else { 0 };
+            let time = time.round();
+            match kind {
+                SyntheticObject::Circle => {
+                    let (x, y) = (64 + rng.below(385), 48 + rng.below(289));
+                    let _ = writeln!(out, "{},{},{},{},0,0:0:0:0:", x, y, time, 1 | combo_bit);
+                }
+                SyntheticObject::Slider => {
+                    // One beat per slide at the velocity in effect
+                    let length = self.slider_multiplier * 100.0 * velocity_at(time);
+                    let (x, y) = (128 + rng.below(257), 112 + rng.below(161));
+                    // Head towards the centre (±60°) so the tail stays on the playfield
+                    let to_centre = (192.0 - y as f64).atan2(256.0 - x as f64);
+                    let angle = to_centre + (rng.below(121) as f64 - 60.0).to_radians();
+                    let end_x = x as f64 + angle.cos() * length;
+                    let end_y = y as f64 + angle.sin() * length;
+                    let curve = if rng.below(2) == 0 {
+                        format!("L|{}:{}", end_x.round(), end_y.round())
+                    } else {
+                        // Bezier bulging sideways; its length is clamped to `length` by osu!
+                        let mid_x = (x as f64 + end_x) / 2.0 - angle.sin() * length * 0.25;
+                        let mid_y = (y as f64 + end_y) / 2.0 + angle.cos() * length * 0.25;
+                        format!("B|{}:{}|{}:{}", mid_x.round(), mid_y.round(), end_x.round(), end_y.round())
+                    };
+                    let _ = writeln!(
+                        out,
+                        "{},{},{},{},0,{},1,{},0|0,0:0|0:0,0:0:0:0:",
+                        x, y, time, 2 | combo_bit, curve, length
+                    );
+                }
+                SyntheticObject::Spinner => {
+                    let _ = writeln!(
+                        out,
+                        "256,192,{},{},0,{},0:0:0:0:",
+                        time, 8 | combo_bit, (time + 2.0 * beat_len).round()
+                    );
+                }
+            }
+        }
+        out
+    }
+
+    /// Write the map into `dir` (created if missing) and return the `.osu` path
+    pub fn write_to(&self, dir: &Path) -> io::Result<PathBuf> {
+        fs::create_dir_all(dir)?;
+        let path = dir.join(self.file_name());
+        fs::write(&path, self.to_osu_string())?;
+        Ok(path)
+    }
+
+    /// Write the map into a fresh folder under the system temp directory
+    ///
+    /// The folder is named like a beatmap folder and is not removed; tests own it.
+    pub fn write_temp_folder(&self) -> io::Result<PathBuf> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "osu-testutil-{}-{}-{}",
+            std::process::id(),
+            self.seed,
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        self.write_to(&dir)?;
+        Ok(dir)
+    }
+}
+
+/// Small seedable PRNG so the crate stays dependency-free
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform-enough value in `0..n` for test data
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n.max(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Lines of one `[Section]`, blank lines excluded
+    fn section_lines(content: &str, section: &str) -> Vec<String> {
+        let header = format!("[{}]", section);
+        content
+            .lines()
+            .skip_while(|line| *line != header)
+            .skip(1)
+            .take_while(|line| !line.starts_with('['))
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn same_settings_give_the_same_file() {
+        let map = SyntheticMap { seed: 7, velocity_changes: 3, storyboard_sprites: 2, ..SyntheticMap::default() };
+        assert_eq!(map.to_osu_string(), map.clone().to_osu_string());
+        assert_ne!(map.to_osu_string(), SyntheticMap { seed: 8, ..map }.to_osu_string());
+    }
+
+    #[test]
+    fn objects_follow_the_requested_kinds_and_combos() {
+        let map = SyntheticMap {
+            object_count: 5,
+            kinds: vec![SyntheticObject::Circle, SyntheticObject::Slider, SyntheticObject::Spinner],
+            combo_length: 2,
+            ..SyntheticMap::default()
+        };
+        let objects = section_lines(&map.to_osu_string(), "HitObjects");
+        let fields: Vec<Vec<&str>> = objects.iter().map(|line| line.split(',').collect()).collect();
+        let types: Vec<i32> = fields.iter().map(|f| f[3].parse().unwrap()).collect();
+        // Circle, slider, spinner, circle, slider; new combos every two objects and on the spinner
+        assert_eq!(types, vec![1 | 4, 2, 8 | 4, 1, 2 | 4]);
+
+        // 120 BPM at 1/2 beat spacing: a slider lasts a beat, a spinner two
+        let times: Vec<&str> = fields.iter().map(|f| f[2]).collect();
+        assert_eq!(times, vec!["1000", "1250", "2000", "3250", "3500"]);
+        assert_eq!(fields[2][5], "3000");
+        assert_eq!(fields[1][6], "1");
+        assert_eq!(fields[1][7], "140");
+    }
+
+    #[test]
+    fn velocity_changes_and_sprites_are_spread_over_the_map() {
+        let map = SyntheticMap { velocity_changes: 2, storyboard_sprites: 3, ..SyntheticMap::default() };
+        let content = map.to_osu_string();
+
+        let timing = section_lines(&content, "TimingPoints");
+        assert_eq!(timing.len(), 3);
+        assert_eq!(timing[0], "1000,500,4,1,0,100,1,0");
+        assert!(timing[1..].iter().all(|line| line.ends_with(",4,1,0,100,0,0")), "{:?}", timing);
+
+        let events = section_lines(&content, "Events");
+        assert_eq!(events.iter().filter(|line| line.starts_with("Sprite,Background")).count(), 3);
+        assert_eq!(events.iter().filter(|line| line.starts_with(" F,")).count(), 3);
+    }
+
+    #[test]
+    fn temp_folders_are_unique_and_hold_the_map() {
+        let map = SyntheticMap::default();
+        let first = map.write_temp_folder().unwrap();
+        let second = map.write_temp_folder().unwrap();
+        assert_ne!(first, second);
+        let written = fs::read_to_string(first.join(map.file_name())).unwrap();
+        assert_eq!(written, map.to_osu_string());
+        fs::remove_dir_all(first).unwrap();
+        fs::remove_dir_all(second).unwrap();
+    }
+}
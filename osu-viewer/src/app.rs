@@ -1,12 +1,15 @@
 //! Main application implementing eframe::App
 
 use crate::audio::AudioPlayer;
-use crate::beatmap::BeatmapView;
+use crate::beatmap::{BeatmapView, RenderObjectKind};
+use crate::loader::{self, LoadedBeatmap};
 use crate::playback::{PlaybackManager, PlaybackState};
 use crate::renderer::PlayfieldRenderer;
 use crate::timeline::Timeline;
 use egui::{Color32, Key, Pos2, Rect, Stroke, Vec2};
-use std::path::PathBuf;
+use osz_extractor::ExtractedArchive;
+use rosu_map::section::hit_objects::HitObjectKind;
+use std::path::{Path, PathBuf};
 use std::time::Instant;
 use std::collections::VecDeque;
 
@@ -35,44 +38,134 @@ pub struct OsuViewerApp {
     all_samples: VecDeque<f32>,
     /// Last frame time
     last_frame_time: Instant,
+    /// Index into `beatmap.objects` of the object selected for inspection
+    selected: Option<usize>,
+    /// Index into `beatmap.objects` of the object under the cursor this frame
+    hovered: Option<usize>,
+    /// Draw the hovered (or selected) object's hit windows on the timeline
+    show_hit_windows: bool,
+    /// Draw the editor grid and center crosshair on the playfield
+    show_grid: bool,
+    /// Error from the last failed drag-and-drop load
+    load_error: Option<String>,
+    /// Extracted .osz the current beatmap and audio are read from, removed when replaced
+    _archive: Option<ExtractedArchive>,
 }
 
 impl OsuViewerApp {
-    pub fn new(
-        _cc: &eframe::CreationContext<'_>,
-        beatmap: rosu_map::Beatmap,
-        audio_path: Option<PathBuf>,
-    ) -> Self {
+    pub fn new(_cc: &eframe::CreationContext<'_>, loaded: LoadedBeatmap) -> Self {
+        let LoadedBeatmap { beatmap, audio_path, archive, .. } = loaded;
         let beatmap_view = BeatmapView::new(beatmap);
         let total_duration = beatmap_view.total_duration;
 
+        let mut timeline = Timeline::new();
+        if let Some(path) = &audio_path {
+            timeline.load_waveform(path.clone());
+        }
+
         let mut audio = AudioPlayer::new().expect("Failed to create audio player");
-        let has_audio = if let Some(path) = audio_path {
-            match audio.load(&path) {
-                Ok(()) => {
-                    log::info!("Loaded audio: {}", path.display());
-                    true
-                }
-                Err(e) => {
-                    log::warn!("Failed to load audio: {}", e);
-                    false
-                }
-            }
-        } else {
-            false
-        };
+        let has_audio = Self::load_audio(&mut audio, audio_path);
 
         Self {
             beatmap: beatmap_view,
             audio,
             playback: PlaybackManager::new(total_duration),
-            timeline: Timeline::new(),
+            timeline,
             has_audio,
             frametime_history: VecDeque::with_capacity(FRAMETIME_BAR_COUNT),
             raw_samples: Vec::with_capacity(SAMPLES_PER_BAR),
             all_samples: VecDeque::with_capacity(500), // ~8 seconds at 60fps
             last_frame_time: Instant::now(),
+            selected: None,
+            hovered: None,
+            show_hit_windows: false,
+            show_grid: false,
+            load_error: None,
+            _archive: archive,
+        }
+    }
+
+    /// Load audio into the player, returning whether it succeeded
+    fn load_audio(audio: &mut AudioPlayer, audio_path: Option<PathBuf>) -> bool {
+        let Some(path) = audio_path else {
+            return false;
+        };
+        match audio.load(&path) {
+            Ok(()) => {
+                log::info!("Loaded audio: {}", path.display());
+                true
+            }
+            Err(e) => {
+                log::warn!("Failed to load audio: {}", e);
+                false
+            }
+        }
+    }
+
+    /// Open a dropped .osu file or folder, replacing the current beatmap
+    fn open_path(&mut self, ctx: &egui::Context, path: &Path) {
+        match loader::load_beatmap(path) {
+            Ok(loaded) => {
+                log::info!("Opened {}", loaded.osu_path.display());
+                self.replace_beatmap(ctx, loaded);
+                self.load_error = None;
+            }
+            Err(e) => {
+                log::error!("Failed to open {}: {:#}", path.display(), e);
+                self.load_error = Some(format!("{:#}", e));
+            }
+        }
+    }
+
+    /// Rebuild the view, playback and audio for a newly loaded beatmap
+    fn replace_beatmap(&mut self, ctx: &egui::Context, loaded: LoadedBeatmap) {
+        ctx.send_viewport_cmd(egui::ViewportCommand::Title(loaded.window_title()));
+
+        self.timeline = Timeline::new();
+        if let Some(path) = &loaded.audio_path {
+            self.timeline.load_waveform(path.clone());
         }
+
+        self.audio.stop();
+        self.has_audio = Self::load_audio(&mut self.audio, loaded.audio_path);
+        // The old archive's audio is no longer playing
+        self._archive = loaded.archive;
+
+        self.beatmap = BeatmapView::new(loaded.beatmap);
+        self.playback = PlaybackManager::new(self.beatmap.total_duration);
+        self.selected = None;
+    }
+
+    /// Handle files dropped onto the window
+    fn handle_dropped_files(&mut self, ctx: &egui::Context) {
+        // Only the last dropped path is opened when several arrive at once
+        let dropped = ctx.input(|input| {
+            input.raw.dropped_files.iter().rev().find_map(|f| f.path.clone())
+        });
+        if let Some(path) = dropped {
+            self.open_path(ctx, &path);
+        }
+    }
+
+    /// Draw a hint over the window while files are dragged over it
+    fn draw_drop_hint(&self, ctx: &egui::Context) {
+        if ctx.input(|input| input.raw.hovered_files.is_empty()) {
+            return;
+        }
+
+        let painter = ctx.layer_painter(egui::LayerId::new(
+            egui::Order::Foreground,
+            egui::Id::new("drop_hint"),
+        ));
+        let screen = ctx.screen_rect();
+        painter.rect_filled(screen, 0.0, Color32::from_black_alpha(180));
+        painter.text(
+            screen.center(),
+            egui::Align2::CENTER_CENTER,
+            "Drop a .osu file or beatmap folder to open",
+            egui::FontId::proportional(24.0),
+            Color32::WHITE,
+        );
     }
 
     /// Handle keyboard input
@@ -108,6 +201,16 @@ impl OsuViewerApp {
             if input.key_pressed(Key::End) {
                 self.seek(self.playback.total_duration - 1000.0);
             }
+
+            // Escape: clear selection
+            if input.key_pressed(Key::Escape) {
+                self.selected = None;
+            }
+
+            // G: toggle editor grid
+            if input.key_pressed(Key::G) {
+                self.show_grid = !self.show_grid;
+            }
         });
     }
 
@@ -196,6 +299,128 @@ impl OsuViewerApp {
         sorted[idx]
     }
     
+    /// Collect raw properties of an object for the inspector panel
+    fn object_details(&self, idx: usize) -> Vec<(&'static str, String)> {
+        let Some(obj) = self.beatmap.objects.get(idx) else {
+            return Vec::new();
+        };
+        let Some(hit_object) = self.beatmap.beatmap.hit_objects.get(obj.source_index) else {
+            return Vec::new();
+        };
+
+        let mut details = vec![
+            ("Index", obj.source_index.to_string()),
+            ("Time", format!("{:.0} ms", hit_object.start_time)),
+        ];
+
+        match &hit_object.kind {
+            HitObjectKind::Circle(circle) => {
+                details.push(("Type", "Circle".to_string()));
+                details.push(("Position", format!("{}, {}", circle.pos.x, circle.pos.y)));
+                details.push(("New combo", circle.new_combo.to_string()));
+                details.push(("Combo offset", circle.combo_offset.to_string()));
+            }
+            HitObjectKind::Slider(slider) => {
+                details.push(("Type", "Slider".to_string()));
+                details.push(("Position", format!("{}, {}", slider.pos.x, slider.pos.y)));
+                details.push(("New combo", slider.new_combo.to_string()));
+                details.push(("Combo offset", slider.combo_offset.to_string()));
+                let control_points = slider.path.control_points();
+                details.push((
+                    "Curve type",
+                    control_points
+                        .first()
+                        .and_then(|cp| cp.path_type)
+                        .map(|pt| format!("{:?}", pt))
+                        .unwrap_or_else(|| "-".to_string()),
+                ));
+                details.push(("Control points", control_points.len().to_string()));
+                details.push(("Repeats", slider.repeat_count.to_string()));
+                details.push((
+                    "Length",
+                    slider
+                        .path
+                        .expected_dist()
+                        .map(|d| format!("{:.2}", d))
+                        .unwrap_or_else(|| "-".to_string()),
+                ));
+                details.push(("Velocity", format!("{:.4}", slider.velocity)));
+                details.push(("Node samples", slider.node_samples.len().to_string()));
+            }
+            HitObjectKind::Spinner(spinner) => {
+                details.push(("Type", "Spinner".to_string()));
+                details.push(("Position", format!("{}, {}", spinner.pos.x, spinner.pos.y)));
+                details.push(("New combo", spinner.new_combo.to_string()));
+                details.push(("Duration", format!("{:.0} ms", spinner.duration)));
+            }
+            HitObjectKind::Hold(hold) => {
+                details.push(("Type", "Hold".to_string()));
+                details.push(("Duration", format!("{:.0} ms", hold.duration)));
+            }
+        }
+
+        if let RenderObjectKind::Slider { duration, .. } = &obj.kind {
+            details.push(("Duration", format!("{:.0} ms", duration)));
+        }
+        details.push(("End time", format!("{:.0} ms", obj.end_time)));
+        details.push(("Combo number", obj.combo_number.to_string()));
+
+        for (i, sample) in hit_object.samples.iter().enumerate() {
+            let label = if i == 0 { "Hitsounds" } else { "" };
+            details.push((
+                label,
+                format!("{:?} ({:?}, vol {})", sample.name, sample.bank, sample.volume),
+            ));
+        }
+        if hit_object.samples.is_empty() {
+            details.push(("Hitsounds", "-".to_string()));
+        }
+
+        details
+    }
+
+    /// Draw the side panel with details of the selected object
+    fn draw_inspector(&mut self, ctx: &egui::Context) {
+        let Some(idx) = self.selected else {
+            return;
+        };
+        let details = self.object_details(idx);
+
+        egui::SidePanel::right("inspector")
+            .resizable(true)
+            .default_width(240.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.heading("Object");
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if ui.button("✕").clicked() {
+                            self.selected = None;
+                        }
+                    });
+                });
+                ui.separator();
+
+                egui::Grid::new("inspector_grid")
+                    .num_columns(2)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for (label, value) in &details {
+                            ui.label(*label);
+                            ui.monospace(value);
+                            ui.end_row();
+                        }
+                    });
+
+                ui.add_space(10.0);
+                let start_time = self.beatmap.objects.get(idx).map(|obj| obj.start_time);
+                if let Some(start_time) = start_time {
+                    if ui.button("Seek to object").clicked() {
+                        self.seek(start_time);
+                    }
+                }
+            });
+    }
+
     /// Draw frametime graph
     fn draw_frametime_graph(&self, painter: &egui::Painter, rect: Rect) {
         if self.frametime_history.is_empty() {
@@ -314,10 +539,14 @@ impl eframe::App for OsuViewerApp {
 
         // Handle input
         self.handle_input(ctx);
+        self.handle_dropped_files(ctx);
 
         // Update playback
         self.update_playback();
 
+        // Inspector panel for the selected object (must be added before the central panel)
+        self.draw_inspector(ctx);
+
         // Main panel with playfield
         egui::CentralPanel::default()
             .frame(egui::Frame::none().fill(Color32::from_rgb(15, 15, 20)))
@@ -336,8 +565,14 @@ impl eframe::App for OsuViewerApp {
                 let renderer = PlayfieldRenderer::new(playfield_rect);
                 let painter = ui.painter_at(playfield_rect);
                 
-                renderer.draw_playfield_bg(&painter);
+                let grid_spacing = self
+                    .show_grid
+                    .then(|| osu_geometry::editor_grid_spacing(self.beatmap.beatmap.grid_size));
+                renderer.draw_playfield_bg(&painter, grid_spacing);
                 renderer.draw_objects(&painter, &self.beatmap, self.playback.current_time);
+                if let Some(idx) = self.selected {
+                    renderer.draw_selection(&painter, &self.beatmap, idx);
+                }
                 
                 // Draw countdown and break overlays
                 renderer.draw_countdown(&painter, &self.beatmap, self.playback.current_time);
@@ -358,8 +593,16 @@ impl eframe::App for OsuViewerApp {
                 );
                 self.draw_frametime_graph(&painter, graph_rect);
 
-                // Allocate the playfield space
-                ui.allocate_rect(playfield_rect, egui::Sense::hover());
+                // Allocate the playfield space; clicks select the object under the cursor
+                let response = ui.allocate_rect(playfield_rect, egui::Sense::click());
+                self.hovered = response
+                    .hover_pos()
+                    .and_then(|pos| renderer.hit_test(&self.beatmap, self.playback.current_time, pos));
+                if response.clicked() {
+                    if let Some(pos) = response.interact_pointer_pos() {
+                        self.selected = renderer.hit_test(&self.beatmap, self.playback.current_time, pos);
+                    }
+                }
 
                 // Controls bar
                 ui.horizontal(|ui| {
@@ -389,6 +632,14 @@ impl eframe::App for OsuViewerApp {
 
                     ui.separator();
 
+                    ui.checkbox(&mut self.show_hit_windows, "Hit windows")
+                        .on_hover_text("Show the 300/100/50 windows of the hovered or selected object on the timeline");
+
+                    ui.checkbox(&mut self.show_grid, "Grid (G)")
+                        .on_hover_text("Show the editor grid (the map's grid size) and a center crosshair");
+
+                    ui.separator();
+
                     // Object count
                     let visible_count = self.beatmap
                         .visible_objects(self.playback.current_time)
@@ -401,6 +652,12 @@ impl eframe::App for OsuViewerApp {
 
                     ui.separator();
 
+                    // Last load error from drag-and-drop
+                    if let Some(error) = &self.load_error {
+                        ui.colored_label(Color32::from_rgb(255, 110, 110), error);
+                        ui.separator();
+                    }
+
                     // Audio status
                     if self.has_audio {
                         ui.label("🔊 Audio");
@@ -418,6 +675,15 @@ impl eframe::App for OsuViewerApp {
                 // Timeline
                 let current_str = self.playback.format_time(self.playback.current_time);
                 let total_str = self.playback.format_time(self.playback.total_duration);
+                let hit_windows = self
+                    .hovered
+                    .or(self.selected)
+                    .filter(|_| self.show_hit_windows)
+                    .and_then(|idx| self.beatmap.objects.get(idx))
+                    .map(|obj| {
+                        let windows = osu_geometry::hit_windows(self.beatmap.beatmap.overall_difficulty);
+                        (obj.start_time, windows)
+                    });
                 
                 if let Some(seek_time) = self.timeline.show(
                     ui,
@@ -426,9 +692,12 @@ impl eframe::App for OsuViewerApp {
                     self.playback.total_duration,
                     &current_str,
                     &total_str,
+                    hit_windows,
                 ) {
                     self.seek(seek_time);
                 }
             });
+
+        self.draw_drop_hint(ctx);
     }
 }
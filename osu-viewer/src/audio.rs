@@ -32,8 +32,10 @@ impl AudioPlayer {
         })
     }
 
-    /// Load audio from a file
+    /// Load audio from a file, replacing any loaded audio
     pub fn load<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        self.stop();
+
         let sound_data = StaticSoundData::from_file(path.as_ref())
             .context("Failed to load audio file")?;
 
@@ -47,6 +49,14 @@ impl AudioPlayer {
         Ok(())
     }
 
+    /// Stop and unload the current audio
+    pub fn stop(&mut self) {
+        if let Some(mut handle) = self.sound_handle.take() {
+            let _ = handle.stop(Tween::default());
+        }
+        self.has_audio = false;
+    }
+
     /// Check if audio is loaded
     pub fn has_audio(&self) -> bool {
         self.has_audio
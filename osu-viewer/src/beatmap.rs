@@ -1,7 +1,8 @@
 //! Beatmap wrapper with rendering-optimized data structures
 
-use rosu_map::section::hit_objects::{HitObjectKind, CurveBuffers};
-use rosu_map::section::general::CountdownType;
+use osu_geometry::{ControlPoint, PathKind};
+use rosu_map::section::hit_objects::{HitObjectKind, HitObjectSlider, CurveBuffers, PathType};
+use rosu_map::section::general::{CountdownType, GameMode};
 
 /// osu! standard playfield dimensions
 pub const PLAYFIELD_WIDTH: f32 = 512.0;
@@ -19,6 +20,8 @@ pub struct RenderObject {
     pub y: f32,
     /// Combo number (1-indexed within combo)
     pub combo_number: u32,
+    /// Index into the source beatmap's hit_objects
+    pub source_index: usize,
     /// Object-specific data
     pub kind: RenderObjectKind,
 }
@@ -72,7 +75,7 @@ pub struct BeatmapView {
     pub total_duration: f64,
     /// Break periods
     pub breaks: Vec<BreakPeriod>,
-    /// Countdown type (None, Normal, HalfSpeed, DoubleSpeed)
+    /// Countdown type (None, Normal, HalfSpeed, DoubleSpeed), always None for mania
     pub countdown_type: CountdownType,
     /// First object time
     pub first_object_time: f64,
@@ -102,8 +105,12 @@ impl BeatmapView {
         // Fade in is typically 400ms or 2/3 of approach time, whichever is smaller
         let fade_in_time = (approach_time * 2.0 / 3.0).min(400.0);
 
-        // Get countdown type
-        let countdown_type = beatmap.countdown;
+        // Get countdown type; mania never plays a countdown, whatever the file says
+        let countdown_type = if beatmap.mode == GameMode::Mania {
+            CountdownType::None
+        } else {
+            beatmap.countdown
+        };
         
         // Get beat length from first timing point for countdown
         let countdown_beat_length = beatmap.control_points.timing_points
@@ -116,7 +123,7 @@ impl BeatmapView {
         let mut combo_number = 0u32;
         let mut curve_buffers = CurveBuffers::default();
 
-        for hit_object in beatmap.hit_objects.iter_mut() {
+        for (source_index, hit_object) in beatmap.hit_objects.iter_mut().enumerate() {
             // Check for new combo
             let is_new_combo = match &hit_object.kind {
                 HitObjectKind::Circle(c) => c.new_combo,
@@ -138,22 +145,12 @@ impl BeatmapView {
                     x: circle.pos.x,
                     y: circle.pos.y,
                     combo_number,
+                    source_index,
                     kind: RenderObjectKind::Circle,
                 },
                 HitObjectKind::Slider(slider) => {
-                    // Compute slider path points using the curve's path() method
-                    // Note: curve.path() returns points relative to the first control point,
-                    // so we need to offset them by the slider's absolute position
-                    let slider_x = slider.pos.x;
-                    let slider_y = slider.pos.y;
-                    
-                    let path_points: Vec<(f32, f32)> = {
-                        let curve = slider.path.curve_with_bufs(&mut curve_buffers);
-                        curve.path()
-                            .iter()
-                            .map(|pos| (slider_x + pos.x, slider_y + pos.y))
-                            .collect()
-                    };
+                    // Path points are absolute osu!pixel positions
+                    let path_points = slider_path_points(slider);
                     
                     // Get slider duration (total duration including all spans/repeats)
                     let total_duration = slider.duration_with_bufs(&mut curve_buffers);
@@ -166,6 +163,7 @@ impl BeatmapView {
                         x: slider.pos.x,
                         y: slider.pos.y,
                         combo_number,
+                        source_index,
                         kind: RenderObjectKind::Slider {
                             path_points,
                             duration: total_duration,
@@ -181,6 +179,7 @@ impl BeatmapView {
                         x: PLAYFIELD_WIDTH / 2.0,
                         y: PLAYFIELD_HEIGHT / 2.0,
                         combo_number: 0, // Spinners don't show combo numbers
+                        source_index,
                         kind: RenderObjectKind::Spinner {
                             duration: spinner.duration,
                         },
@@ -287,41 +286,18 @@ impl BeatmapView {
 
     /// Get slider ball position at current time
     pub fn slider_ball_position(&self, obj: &RenderObject, current_time: f64) -> Option<(f32, f32)> {
-        if let RenderObjectKind::Slider { path_points, duration, repeats } = &obj.kind {
-            if current_time < obj.start_time || current_time > obj.end_time || path_points.is_empty() {
-                return None;
-            }
-
-            let elapsed = current_time - obj.start_time;
-            let single_pass_duration = *duration / (*repeats + 1) as f64;
-            let pass_number = (elapsed / single_pass_duration) as u32;
-            let pass_progress = (elapsed % single_pass_duration) / single_pass_duration;
-
-            // Reverse direction on odd passes
-            let progress = if pass_number % 2 == 0 {
-                pass_progress
-            } else {
-                1.0 - pass_progress
-            };
-
-            // Interpolate along path
-            let path_len = path_points.len();
-            let float_idx = progress * (path_len - 1) as f64;
-            let idx = float_idx as usize;
-            let frac = float_idx.fract() as f32;
-
-            if idx >= path_len - 1 {
-                Some(path_points[path_len - 1])
-            } else {
-                let (x1, y1) = path_points[idx];
-                let (x2, y2) = path_points[idx + 1];
-                Some((x1 + (x2 - x1) * frac, y1 + (y2 - y1) * frac))
-            }
-        } else {
-            None
+        match &obj.kind {
+            RenderObjectKind::Slider { path_points, duration, repeats } => osu_geometry::slider_ball_position(
+                path_points,
+                *repeats,
+                *duration,
+                obj.start_time,
+                current_time,
+            ),
+            _ => None,
         }
     }
-    
+
     /// Check if we're in a break period
     pub fn is_in_break(&self, current_time: f64) -> Option<&BreakPeriod> {
         self.breaks.iter().find(|b| current_time >= b.start_time && current_time <= b.end_time)
@@ -394,3 +370,60 @@ impl BeatmapView {
     }
 }
 
+/// Absolute body path of a slider, sampled with osu-geometry
+fn slider_path_points(slider: &HitObjectSlider) -> Vec<(f32, f32)> {
+    let control_points: Vec<ControlPoint> = slider
+        .path
+        .control_points()
+        .iter()
+        .map(|cp| ControlPoint {
+            pos: (slider.pos.x + cp.pos.x, slider.pos.y + cp.pos.y),
+            kind: cp.path_type.map(path_kind),
+        })
+        .collect();
+    osu_geometry::sample_path(&control_points, slider.path.expected_dist(), 0.0)
+}
+
+fn path_kind(path_type: PathType) -> PathKind {
+    if path_type == PathType::LINEAR {
+        PathKind::Linear
+    } else if path_type == PathType::PERFECT_CURVE {
+        PathKind::PerfectCurve
+    } else if path_type == PathType::CATMULL {
+        PathKind::Catmull
+    } else {
+        // Bezier and higher-degree B-splines
+        PathKind::Bezier
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Map of `mode` with `Countdown: 1`, a 500 ms beat and its first object at 3000 ms
+    fn view(mode: u8) -> BeatmapView {
+        let content = format!(
+            "osu file format v14\n\n[General]\nCountdown: 1\nMode: {}\n\n[Difficulty]\nCircleSize:4\n\n\
+             [TimingPoints]\n0,500,4,1,0,100,1,0\n\n[HitObjects]\n64,192,3000,1,0,0:0:0:0:\n",
+            mode
+        );
+        BeatmapView::new(rosu_map::from_str(&content).unwrap())
+    }
+
+    #[test]
+    fn standard_counts_down_to_the_first_object() {
+        let view = view(0);
+        assert_eq!(view.get_countdown_state(900.0), CountdownState::None);
+        assert_eq!(view.get_countdown_state(1000.0), CountdownState::Number(3));
+        assert_eq!(view.get_countdown_state(2600.0), CountdownState::Go);
+    }
+
+    #[test]
+    fn mania_never_counts_down() {
+        let view = view(3);
+        assert_eq!(view.countdown_type, CountdownType::None);
+        assert_eq!(view.get_countdown_state(1000.0), CountdownState::None);
+        assert_eq!(view.get_countdown_state(2600.0), CountdownState::None);
+    }
+}
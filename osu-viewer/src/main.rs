@@ -1,15 +1,19 @@
 //! osu-viewer: GPU-accelerated .osu file viewer
 //!
-//! Usage: osu-viewer <path-to-osu-file>
+//! Usage: osu-viewer <path-to-osu-file-folder-or-osz>
+//!
+//! Further beatmaps can be opened by dropping them onto the window.
 
 mod app;
 mod audio;
 mod beatmap;
+mod loader;
 mod playback;
 mod renderer;
 mod timeline;
+mod waveform;
 
-use anyhow::{Context, Result};
+use anyhow::Result;
 use clap::Parser;
 use std::path::PathBuf;
 
@@ -17,7 +21,7 @@ use std::path::PathBuf;
 #[command(name = "osu-viewer")]
 #[command(about = "GPU-accelerated .osu file viewer with timeline scrubbing")]
 struct Args {
-    /// Path to the .osu file to view
+    /// Path to the .osu file (or a folder or .osz archive containing one) to view
     #[arg(required = true)]
     osu_file: PathBuf,
 }
@@ -27,50 +31,15 @@ fn main() -> Result<()> {
 
     let args = Args::parse();
 
-    // Validate the file exists
-    if !args.osu_file.exists() {
-        anyhow::bail!("File not found: {}", args.osu_file.display());
-    }
-
-    if args.osu_file.extension().map_or(true, |e| e != "osu") {
-        anyhow::bail!("File must have .osu extension");
-    }
-
-    // Parse the beatmap
-    log::info!("Loading beatmap: {}", args.osu_file.display());
-    let beatmap: rosu_map::Beatmap =
-        rosu_map::from_path(&args.osu_file).context("Failed to parse .osu file")?;
-
-    log::info!(
-        "Loaded: {} - {} [{}]",
-        beatmap.artist,
-        beatmap.title,
-        beatmap.version
-    );
-    log::info!("Hit objects: {}", beatmap.hit_objects.len());
-
-    // Get audio file path
-    let audio_path = args
-        .osu_file
-        .parent()
-        .map(|p| p.join(&beatmap.audio_file))
-        .filter(|p| p.exists());
-
-    if audio_path.is_none() {
-        log::warn!(
-            "Audio file not found: {}. Playback will be silent.",
-            beatmap.audio_file
-        );
-    }
+    let loaded = loader::load_beatmap(&args.osu_file)?;
+    let title = loaded.window_title();
 
     // Run the application
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([1280.0, 720.0])
-            .with_title(format!(
-                "{} - {} [{}] - osu-viewer",
-                beatmap.artist, beatmap.title, beatmap.version
-            )),
+            .with_title(title)
+            .with_drag_and_drop(true),
         vsync: true,
         ..Default::default()
     };
@@ -79,7 +48,7 @@ fn main() -> Result<()> {
         "osu-viewer",
         options,
         Box::new(move |cc| {
-            Ok(Box::new(app::OsuViewerApp::new(cc, beatmap, audio_path)))
+            Ok(Box::new(app::OsuViewerApp::new(cc, loaded)))
         }),
     )
     .map_err(|e| anyhow::anyhow!("Failed to run application: {}", e))
@@ -3,6 +3,44 @@
 use crate::beatmap::{BeatmapView, RenderObject, RenderObjectKind, PLAYFIELD_WIDTH, PLAYFIELD_HEIGHT};
 use egui::{Color32, FontId, Pos2, Rect, Stroke, Vec2, Mesh, epaint::Vertex};
 
+/// Slider border width in screen pixels
+const SLIDER_BORDER_WIDTH: f32 = 2.0;
+/// Width of the transparent fringe used to anti-alias mesh edges
+const AA_FRINGE_WIDTH: f32 = 1.0;
+/// Maximum miter length relative to the radius before a join is clamped
+const MAX_MITER_SCALE: f32 = 2.0;
+/// Joins turning more sharply than this (cos of half the turn angle) get a round join
+const SHARP_JOIN_COS: f32 = 0.9;
+
+/// Outline color for the selected object
+const SELECTION_COLOR: Color32 = Color32::from_rgb(255, 170, 40);
+/// Editor grid line color
+const GRID_COLOR: Color32 = Color32::from_rgb(45, 45, 62);
+/// Center crosshair color, brighter than the grid
+const CROSSHAIR_COLOR: Color32 = Color32::from_rgb(90, 90, 120);
+
+/// Normalize a vector, returning zero for degenerate (near zero-length) input
+fn normalize_or_zero(v: Vec2) -> Vec2 {
+    let len = v.length();
+    if len > 0.001 { v / len } else { Vec2::ZERO }
+}
+
+/// Distance between two points in osu! coordinates
+fn distance(x1: f32, y1: f32, x2: f32, y2: f32) -> f32 {
+    ((x2 - x1).powi(2) + (y2 - y1).powi(2)).sqrt()
+}
+
+/// Distance from a point to a line segment in osu! coordinates
+fn distance_to_segment(p: (f32, f32), a: (f32, f32), b: (f32, f32)) -> f32 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let len_sq = dx * dx + dy * dy;
+    if len_sq < 0.0001 {
+        return distance(p.0, p.1, a.0, a.1);
+    }
+    let t = (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / len_sq).clamp(0.0, 1.0);
+    distance(p.0, p.1, a.0 + t * dx, a.1 + t * dy)
+}
+
 /// Playfield renderer with coordinate transformation
 pub struct PlayfieldRenderer {
     /// Scale factor to fit playfield in available space
@@ -59,8 +97,83 @@ impl PlayfieldRenderer {
         radius * self.scale
     }
 
-    /// Draw the playfield background
-    pub fn draw_playfield_bg(&self, painter: &egui::Painter) {
+    /// Convert screen coordinates back to osu! coordinates
+    pub fn screen_to_osu(&self, pos: Pos2) -> (f32, f32) {
+        (
+            (pos.x - self.offset.x) / self.scale,
+            (pos.y - self.offset.y) / self.scale,
+        )
+    }
+
+    /// Find the visible object under a screen position
+    ///
+    /// Returns the index into `beatmap.objects` of the nearest object whose
+    /// body contains the position. Ties go to the earlier object, which is
+    /// drawn on top.
+    pub fn hit_test(&self, beatmap: &BeatmapView, current_time: f64, pos: Pos2) -> Option<usize> {
+        let (x, y) = self.screen_to_osu(pos);
+        let mut best: Option<(usize, f32)> = None;
+
+        for (idx, obj, _) in beatmap.visible_objects(current_time) {
+            let (distance, reach) = match &obj.kind {
+                RenderObjectKind::Circle => (
+                    distance(x, y, obj.x, obj.y),
+                    beatmap.circle_radius,
+                ),
+                RenderObjectKind::Slider { path_points, .. } => {
+                    let body = path_points
+                        .windows(2)
+                        .map(|w| distance_to_segment((x, y), w[0], w[1]))
+                        .fold(f32::INFINITY, f32::min);
+                    (body.min(distance(x, y, obj.x, obj.y)), beatmap.circle_radius)
+                }
+                RenderObjectKind::Spinner { .. } => (
+                    distance(x, y, obj.x, obj.y),
+                    // Matches the outer ring drawn by draw_spinner
+                    PLAYFIELD_HEIGHT * 0.4 * 0.9,
+                ),
+            };
+
+            if distance <= reach && best.map_or(true, |(_, d)| distance < d) {
+                best = Some((idx, distance));
+            }
+        }
+
+        best.map(|(idx, _)| idx)
+    }
+
+    /// Draw a colored outline around the selected object
+    pub fn draw_selection(&self, painter: &egui::Painter, beatmap: &BeatmapView, idx: usize) {
+        let Some(obj) = beatmap.objects.get(idx) else {
+            return;
+        };
+        let stroke = Stroke::new(3.0, SELECTION_COLOR);
+        let radius = self.scale_radius(beatmap.circle_radius) + 4.0;
+
+        match &obj.kind {
+            RenderObjectKind::Circle => {
+                painter.circle_stroke(self.osu_to_screen(obj.x, obj.y), radius, stroke);
+            }
+            RenderObjectKind::Slider { path_points, .. } => {
+                let screen_points: Vec<Pos2> = path_points
+                    .iter()
+                    .map(|(x, y)| self.osu_to_screen(*x, *y))
+                    .collect();
+                painter.add(egui::Shape::line(screen_points, Stroke::new(2.0, SELECTION_COLOR)));
+                painter.circle_stroke(self.osu_to_screen(obj.x, obj.y), radius, stroke);
+                if let Some(&(end_x, end_y)) = path_points.last() {
+                    painter.circle_stroke(self.osu_to_screen(end_x, end_y), radius, stroke);
+                }
+            }
+            RenderObjectKind::Spinner { .. } => {
+                let center = self.osu_to_screen(obj.x, obj.y);
+                painter.circle_stroke(center, self.playfield_rect.height() * 0.4 * 0.9 + 4.0, stroke);
+            }
+        }
+    }
+
+    /// Draw the playfield background, with the editor grid every `grid_spacing` osupixels if given
+    pub fn draw_playfield_bg(&self, painter: &egui::Painter, grid_spacing: Option<f32>) {
         // Dark background
         painter.rect_filled(
             self.playfield_rect,
@@ -68,6 +181,22 @@ impl PlayfieldRenderer {
             Color32::from_rgb(20, 20, 30),
         );
 
+        if let Some(spacing) = grid_spacing {
+            let grid_stroke = Stroke::new(1.0, GRID_COLOR);
+            for x in osu_geometry::grid_lines(spacing, PLAYFIELD_WIDTH) {
+                painter.line_segment([self.osu_to_screen(x, 0.0), self.osu_to_screen(x, PLAYFIELD_HEIGHT)], grid_stroke);
+            }
+            for y in osu_geometry::grid_lines(spacing, PLAYFIELD_HEIGHT) {
+                painter.line_segment([self.osu_to_screen(0.0, y), self.osu_to_screen(PLAYFIELD_WIDTH, y)], grid_stroke);
+            }
+
+            // Center crosshair
+            let (cx, cy) = (PLAYFIELD_WIDTH / 2.0, PLAYFIELD_HEIGHT / 2.0);
+            let crosshair_stroke = Stroke::new(1.5, CROSSHAIR_COLOR);
+            painter.line_segment([self.osu_to_screen(cx, 0.0), self.osu_to_screen(cx, PLAYFIELD_HEIGHT)], crosshair_stroke);
+            painter.line_segment([self.osu_to_screen(0.0, cy), self.osu_to_screen(PLAYFIELD_WIDTH, cy)], crosshair_stroke);
+        }
+
         // Border
         painter.rect_stroke(
             self.playfield_rect,
@@ -123,129 +252,137 @@ impl PlayfieldRenderer {
         }
     }
 
-    /// Generate a tessellated mesh for a slider body
+    /// Generate tessellated meshes for a slider body
+    ///
+    /// Returns (border, body). The border mesh spans the full radius plus an
+    /// anti-aliased fringe; the body mesh is drawn on top of it with a gradient
+    /// from `body_center` on the path to `body_edge` at the inner radius.
+    /// Miter length is clamped and sharp turns and both ends get round joins.
     fn generate_slider_mesh(
         &self,
         screen_points: &[Pos2],
         radius: f32,
-        body_color: Color32,
+        body_center: Color32,
+        body_edge: Color32,
         border_color: Color32,
     ) -> (Mesh, Mesh) {
-        let mut body_mesh = Mesh::default();
         let mut border_mesh = Mesh::default();
+        let mut body_mesh = Mesh::default();
         
         if screen_points.len() < 2 {
-            return (body_mesh, border_mesh);
+            return (border_mesh, body_mesh);
         }
         
-        let border_width = 2.0;
-        let inner_radius = radius - border_width;
+        let inner_radius = radius - SLIDER_BORDER_WIDTH;
+        let fringe_radius = radius + AA_FRINGE_WIDTH;
+        let last = screen_points.len() - 1;
         
-        // Generate offset points along the path
-        let mut left_points_outer: Vec<Pos2> = Vec::with_capacity(screen_points.len());
-        let mut right_points_outer: Vec<Pos2> = Vec::with_capacity(screen_points.len());
-        let mut left_points_inner: Vec<Pos2> = Vec::with_capacity(screen_points.len());
-        let mut right_points_inner: Vec<Pos2> = Vec::with_capacity(screen_points.len());
+        // Offset direction (unit normal scaled by clamped miter length) per point
+        let mut offsets: Vec<Vec2> = Vec::with_capacity(screen_points.len());
+        let mut round_joins: Vec<Pos2> = vec![screen_points[0], screen_points[last]];
         
         for i in 0..screen_points.len() {
-            // Calculate tangent direction
-            let tangent = if i == 0 {
-                let dx = screen_points[1].x - screen_points[0].x;
-                let dy = screen_points[1].y - screen_points[0].y;
-                let len = (dx * dx + dy * dy).sqrt().max(0.001);
-                Vec2::new(dx / len, dy / len)
-            } else if i == screen_points.len() - 1 {
-                let dx = screen_points[i].x - screen_points[i - 1].x;
-                let dy = screen_points[i].y - screen_points[i - 1].y;
-                let len = (dx * dx + dy * dy).sqrt().max(0.001);
-                Vec2::new(dx / len, dy / len)
+            let incoming = if i > 0 {
+                normalize_or_zero(screen_points[i] - screen_points[i - 1])
             } else {
-                // Average of incoming and outgoing tangent
-                let dx1 = screen_points[i].x - screen_points[i - 1].x;
-                let dy1 = screen_points[i].y - screen_points[i - 1].y;
-                let dx2 = screen_points[i + 1].x - screen_points[i].x;
-                let dy2 = screen_points[i + 1].y - screen_points[i].y;
-                let dx = dx1 + dx2;
-                let dy = dy1 + dy2;
-                let len = (dx * dx + dy * dy).sqrt().max(0.001);
-                Vec2::new(dx / len, dy / len)
+                Vec2::ZERO
             };
+            let outgoing = if i < last {
+                normalize_or_zero(screen_points[i + 1] - screen_points[i])
+            } else {
+                Vec2::ZERO
+            };
+            
+            // Average of incoming and outgoing tangent, falling back on reversals
+            let mut tangent = normalize_or_zero(incoming + outgoing);
+            if tangent == Vec2::ZERO {
+                tangent = if outgoing != Vec2::ZERO { outgoing } else { incoming };
+            }
+            if tangent == Vec2::ZERO {
+                tangent = Vec2::X;
+            }
             
             // Perpendicular (normal) direction
             let normal = Vec2::new(-tangent.y, tangent.x);
             
-            let point = screen_points[i];
-            left_points_outer.push(Pos2::new(point.x + normal.x * radius, point.y + normal.y * radius));
-            right_points_outer.push(Pos2::new(point.x - normal.x * radius, point.y - normal.y * radius));
-            left_points_inner.push(Pos2::new(point.x + normal.x * inner_radius, point.y + normal.y * inner_radius));
-            right_points_inner.push(Pos2::new(point.x - normal.x * inner_radius, point.y - normal.y * inner_radius));
+            // cos of the half turn angle; 1.0 on straight sections
+            let half_turn_cos = if i > 0 && i < last && incoming != Vec2::ZERO && outgoing != Vec2::ZERO {
+                tangent.dot(outgoing).max(0.0)
+            } else {
+                1.0
+            };
+            if half_turn_cos < SHARP_JOIN_COS {
+                round_joins.push(screen_points[i]);
+            }
+            let miter_scale = (1.0 / half_turn_cos.max(1.0 / MAX_MITER_SCALE)).min(MAX_MITER_SCALE);
+            
+            offsets.push(normal * miter_scale);
         }
         
-        // Build body mesh (inner fill)
         let white_uv = Pos2::new(0.0, 0.0); // egui uses this for solid colors
         
-        for i in 0..screen_points.len() - 1 {
-            let base_idx = body_mesh.vertices.len() as u32;
-            
-            // Add 4 vertices for this segment
-            body_mesh.vertices.push(Vertex { pos: left_points_inner[i], uv: white_uv, color: body_color });
-            body_mesh.vertices.push(Vertex { pos: right_points_inner[i], uv: white_uv, color: body_color });
-            body_mesh.vertices.push(Vertex { pos: left_points_inner[i + 1], uv: white_uv, color: body_color });
-            body_mesh.vertices.push(Vertex { pos: right_points_inner[i + 1], uv: white_uv, color: body_color });
-            
-            // Two triangles for the quad
-            body_mesh.indices.extend_from_slice(&[
-                base_idx, base_idx + 1, base_idx + 2,
-                base_idx + 1, base_idx + 3, base_idx + 2,
-            ]);
+        // Border strip: [left fringe, left outer, right outer, right fringe] per point
+        for (point, offset) in screen_points.iter().zip(&offsets) {
+            border_mesh.vertices.push(Vertex { pos: *point + *offset * fringe_radius, uv: white_uv, color: Color32::TRANSPARENT });
+            border_mesh.vertices.push(Vertex { pos: *point + *offset * radius, uv: white_uv, color: border_color });
+            border_mesh.vertices.push(Vertex { pos: *point - *offset * radius, uv: white_uv, color: border_color });
+            border_mesh.vertices.push(Vertex { pos: *point - *offset * fringe_radius, uv: white_uv, color: Color32::TRANSPARENT });
         }
         
-        // Build border mesh (outer ring)
-        for i in 0..screen_points.len() - 1 {
-            let base_idx = border_mesh.vertices.len() as u32;
-            
-            // Left border strip
-            border_mesh.vertices.push(Vertex { pos: left_points_outer[i], uv: white_uv, color: border_color });
-            border_mesh.vertices.push(Vertex { pos: left_points_inner[i], uv: white_uv, color: border_color });
-            border_mesh.vertices.push(Vertex { pos: left_points_outer[i + 1], uv: white_uv, color: border_color });
-            border_mesh.vertices.push(Vertex { pos: left_points_inner[i + 1], uv: white_uv, color: border_color });
-            
-            border_mesh.indices.extend_from_slice(&[
-                base_idx, base_idx + 1, base_idx + 2,
-                base_idx + 1, base_idx + 3, base_idx + 2,
-            ]);
-            
-            // Right border strip
-            let base_idx = border_mesh.vertices.len() as u32;
-            border_mesh.vertices.push(Vertex { pos: right_points_inner[i], uv: white_uv, color: border_color });
-            border_mesh.vertices.push(Vertex { pos: right_points_outer[i], uv: white_uv, color: border_color });
-            border_mesh.vertices.push(Vertex { pos: right_points_inner[i + 1], uv: white_uv, color: border_color });
-            border_mesh.vertices.push(Vertex { pos: right_points_outer[i + 1], uv: white_uv, color: border_color });
+        // Body strip: [left inner, center, right inner] per point
+        for (point, offset) in screen_points.iter().zip(&offsets) {
+            body_mesh.vertices.push(Vertex { pos: *point + *offset * inner_radius, uv: white_uv, color: body_edge });
+            body_mesh.vertices.push(Vertex { pos: *point, uv: white_uv, color: body_center });
+            body_mesh.vertices.push(Vertex { pos: *point - *offset * inner_radius, uv: white_uv, color: body_edge });
+        }
+        
+        for i in 0..last as u32 {
+            // Three bands across the border strip (fringe, full width, fringe)
+            let a = i * 4;
+            let b = (i + 1) * 4;
+            for band in 0..3 {
+                border_mesh.indices.extend_from_slice(&[
+                    a + band, a + band + 1, b + band,
+                    a + band + 1, b + band + 1, b + band,
+                ]);
+            }
             
-            border_mesh.indices.extend_from_slice(&[
-                base_idx, base_idx + 1, base_idx + 2,
-                base_idx + 1, base_idx + 3, base_idx + 2,
-            ]);
+            // Two bands across the body strip (left half, right half)
+            let a = i * 3;
+            let b = (i + 1) * 3;
+            for band in 0..2 {
+                body_mesh.indices.extend_from_slice(&[
+                    a + band, a + band + 1, b + band,
+                    a + band + 1, b + band + 1, b + band,
+                ]);
+            }
         }
         
-        (body_mesh, border_mesh)
+        // Round caps and joins cover the gaps left by clamped miters
+        for center in round_joins {
+            border_mesh.append(self.generate_circle_mesh(center, radius, border_color, border_color, 24));
+            border_mesh.append(self.generate_circle_border_mesh(center, fringe_radius, radius, Color32::TRANSPARENT, border_color, 24));
+            body_mesh.append(self.generate_circle_mesh(center, inner_radius, body_center, body_edge, 24));
+        }
+        
+        (border_mesh, body_mesh)
     }
     
-    /// Generate a circle mesh for slider caps
-    fn generate_circle_mesh(&self, center: Pos2, radius: f32, color: Color32, segments: usize) -> Mesh {
+    /// Generate a circle mesh with a radial gradient from `center_color` to `edge_color`
+    fn generate_circle_mesh(&self, center: Pos2, radius: f32, center_color: Color32, edge_color: Color32, segments: usize) -> Mesh {
         let mut mesh = Mesh::default();
         let white_uv = Pos2::new(0.0, 0.0);
         
         // Center vertex
         let center_idx = mesh.vertices.len() as u32;
-        mesh.vertices.push(Vertex { pos: center, uv: white_uv, color });
+        mesh.vertices.push(Vertex { pos: center, uv: white_uv, color: center_color });
         
         // Perimeter vertices
         for i in 0..=segments {
             let angle = (i as f32 / segments as f32) * std::f32::consts::TAU;
             let x = center.x + angle.cos() * radius;
             let y = center.y + angle.sin() * radius;
-            mesh.vertices.push(Vertex { pos: Pos2::new(x, y), uv: white_uv, color });
+            mesh.vertices.push(Vertex { pos: Pos2::new(x, y), uv: white_uv, color: edge_color });
         }
         
         // Triangle fan
@@ -256,8 +393,16 @@ impl PlayfieldRenderer {
         mesh
     }
     
-    /// Generate a circle border mesh (ring)
-    fn generate_circle_border_mesh(&self, center: Pos2, outer_radius: f32, inner_radius: f32, color: Color32, segments: usize) -> Mesh {
+    /// Generate a circle border mesh (ring), blending from `outer_color` to `inner_color`
+    fn generate_circle_border_mesh(
+        &self,
+        center: Pos2,
+        outer_radius: f32,
+        inner_radius: f32,
+        outer_color: Color32,
+        inner_color: Color32,
+        segments: usize,
+    ) -> Mesh {
         let mut mesh = Mesh::default();
         let white_uv = Pos2::new(0.0, 0.0);
         
@@ -269,12 +414,12 @@ impl PlayfieldRenderer {
             mesh.vertices.push(Vertex { 
                 pos: Pos2::new(center.x + cos_a * outer_radius, center.y + sin_a * outer_radius), 
                 uv: white_uv, 
-                color 
+                color: outer_color,
             });
             mesh.vertices.push(Vertex { 
                 pos: Pos2::new(center.x + cos_a * inner_radius, center.y + sin_a * inner_radius), 
                 uv: white_uv, 
-                color 
+                color: inner_color,
             });
         }
         
@@ -302,10 +447,11 @@ impl PlayfieldRenderer {
         if let RenderObjectKind::Slider { path_points, repeats, .. } = &obj.kind {
             let alpha = (opacity * 255.0) as u8;
             let stroke_color = Color32::from_rgba_unmultiplied(255, 255, 255, alpha);
-            let body_color = Color32::from_rgba_unmultiplied(60, 60, 80, alpha);
+            // Dark center fading to a lighter edge, like osu!'s default skin
+            let body_center = Color32::from_rgba_unmultiplied(30, 30, 45, alpha);
+            let body_edge = Color32::from_rgba_unmultiplied(85, 85, 110, alpha);
             let radius = self.scale_radius(circle_radius);
-            let border_width = 2.0;
-            let inner_radius = radius - border_width;
+            let inner_radius = radius - SLIDER_BORDER_WIDTH;
 
             if path_points.len() >= 2 {
                 // Adaptive detail: more points for complex sliders
@@ -330,27 +476,29 @@ impl PlayfieldRenderer {
                     .map(|(x, y)| self.osu_to_screen(*x, *y))
                     .collect();
 
-                // Generate and draw slider body mesh
-                let (body_mesh, border_mesh) = self.generate_slider_mesh(&screen_points, radius, body_color, stroke_color);
+                // Generate and draw slider border, then the gradient body on top
+                let (border_mesh, body_mesh) =
+                    self.generate_slider_mesh(&screen_points, radius, body_center, body_edge, stroke_color);
                 
-                painter.add(egui::Shape::mesh(body_mesh));
                 painter.add(egui::Shape::mesh(border_mesh));
-
-                // Draw end cap (filled circle + border ring)
-                if let Some(&last) = screen_points.last() {
-                    let cap_body = self.generate_circle_mesh(last, inner_radius, body_color, 24);
-                    let cap_border = self.generate_circle_border_mesh(last, radius, inner_radius, stroke_color, 24);
-                    painter.add(egui::Shape::mesh(cap_body));
-                    painter.add(egui::Shape::mesh(cap_border));
-                }
+                painter.add(egui::Shape::mesh(body_mesh));
             }
 
             // Draw start circle (head) with mesh
             let center = self.osu_to_screen(obj.x, obj.y);
-            let head_body = self.generate_circle_mesh(center, inner_radius, body_color, 24);
-            let head_border = self.generate_circle_border_mesh(center, radius, inner_radius, stroke_color, 24);
+            let head_body = self.generate_circle_mesh(center, inner_radius, body_center, body_edge, 24);
+            let head_border = self.generate_circle_border_mesh(center, radius, inner_radius, stroke_color, stroke_color, 24);
+            let head_fringe = self.generate_circle_border_mesh(
+                center,
+                radius + AA_FRINGE_WIDTH,
+                radius,
+                Color32::TRANSPARENT,
+                stroke_color,
+                24,
+            );
             painter.add(egui::Shape::mesh(head_body));
             painter.add(egui::Shape::mesh(head_border));
+            painter.add(egui::Shape::mesh(head_fringe));
 
             // Approach circle
             let time_until_hit = obj.start_time - current_time;
@@ -1,7 +1,12 @@
 //! Timeline UI component with scrubbing and mini-map
 
 use crate::beatmap::BeatmapView;
+use crate::waveform::Waveform;
+use anyhow::Result;
 use egui::{Color32, Pos2, Rect, Sense, Stroke, Ui, Vec2};
+use osu_geometry::HitWindows;
+use std::path::PathBuf;
+use std::sync::mpsc::Receiver;
 
 /// Timeline height in pixels
 const TIMELINE_HEIGHT: f32 = 60.0;
@@ -14,6 +19,13 @@ pub struct Timeline {
     density_cache: Vec<f32>,
     /// Number of buckets for density calculation
     num_buckets: usize,
+    /// Decoded audio peaks, once the background decode finishes
+    waveform: Option<Waveform>,
+    /// Pending background decode
+    waveform_rx: Option<Receiver<Result<Waveform>>>,
+    /// Waveform (min, max) per scrubber column, cached for (width, total duration)
+    waveform_columns: Vec<(f32, f32)>,
+    waveform_key: (u32, u64),
 }
 
 impl Timeline {
@@ -21,6 +33,36 @@ impl Timeline {
         Self {
             density_cache: Vec::new(),
             num_buckets: 200,
+            waveform: None,
+            waveform_rx: None,
+            waveform_columns: Vec::new(),
+            waveform_key: (0, 0),
+        }
+    }
+
+    /// Start decoding the audio waveform shown behind the scrubber
+    pub fn load_waveform(&mut self, audio_path: PathBuf) {
+        self.waveform = None;
+        self.waveform_columns.clear();
+        self.waveform_rx = Some(Waveform::load_in_background(audio_path));
+    }
+
+    /// Pick up the waveform once the background decode is done
+    fn poll_waveform(&mut self) {
+        let Some(rx) = &self.waveform_rx else {
+            return;
+        };
+        match rx.try_recv() {
+            Ok(Ok(waveform)) => {
+                self.waveform = Some(waveform);
+                self.waveform_rx = None;
+            }
+            Ok(Err(e)) => {
+                log::warn!("Failed to decode waveform: {:#}", e);
+                self.waveform_rx = None;
+            }
+            Err(std::sync::mpsc::TryRecvError::Empty) => {}
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => self.waveform_rx = None,
         }
     }
 
@@ -55,11 +97,13 @@ impl Timeline {
         total_duration: f64,
         current_time_str: &str,
         total_time_str: &str,
+        hit_windows: Option<(f64, HitWindows)>,
     ) -> Option<f64> {
         // Compute density if not cached
         if self.density_cache.is_empty() {
             self.compute_density(beatmap);
         }
+        self.poll_waveform();
 
         let available_width = ui.available_width();
         let (rect, response) = ui.allocate_exact_size(
@@ -97,6 +141,14 @@ impl Timeline {
         );
         painter.rect_filled(progress_rect, 4.0, Color32::from_rgb(100, 150, 255));
 
+        // Draw audio waveform over the track, brighter on the played side
+        self.draw_waveform(&painter, scrubber_rect, progress_width, total_duration);
+
+        // Draw hit windows of the hovered/selected object
+        if let Some((object_time, windows)) = hit_windows {
+            Self::draw_hit_windows(&painter, scrubber_rect, object_time, windows, total_duration);
+        }
+
         // Draw playhead
         let playhead_x = scrubber_rect.min.x + progress_width;
         let playhead_rect = Rect::from_center_size(
@@ -140,6 +192,69 @@ impl Timeline {
         None
     }
 
+    /// Draw the waveform as min/max lines centred in `rect`
+    fn draw_waveform(&mut self, painter: &egui::Painter, rect: Rect, progress_width: f32, total_duration: f64) {
+        let Some(waveform) = &self.waveform else {
+            return;
+        };
+
+        let key = (rect.width().round() as u32, total_duration.to_bits());
+        if self.waveform_key != key {
+            self.waveform_columns = waveform.columns(key.0 as usize, total_duration);
+            self.waveform_key = key;
+        }
+
+        let center_y = rect.center().y;
+        let half_height = rect.height() * 0.45;
+        for (i, &(lo, hi)) in self.waveform_columns.iter().enumerate() {
+            if lo >= 0.0 && hi <= 0.0 {
+                continue;
+            }
+            let x = rect.min.x + i as f32 + 0.5;
+            let color = if (i as f32) < progress_width {
+                Color32::from_white_alpha(140)
+            } else {
+                Color32::from_rgba_unmultiplied(150, 170, 220, 90)
+            };
+            painter.line_segment(
+                [
+                    Pos2::new(x, center_y - hi * half_height),
+                    Pos2::new(x, center_y - lo * half_height),
+                ],
+                Stroke::new(1.0, color),
+            );
+        }
+    }
+
+    /// Draw the 50/100/300 windows around `object_time` as nested bands, widest and faintest first
+    ///
+    /// Each band is at least a few pixels wide so the windows stay visible on long maps.
+    fn draw_hit_windows(painter: &egui::Painter, rect: Rect, object_time: f64, windows: HitWindows, total_duration: f64) {
+        let x = rect.min.x + (object_time / total_duration).clamp(0.0, 1.0) as f32 * rect.width();
+        let bands = [
+            (windows.meh, 6.0, Color32::from_rgba_unmultiplied(255, 200, 80, 50)),
+            (windows.ok, 4.0, Color32::from_rgba_unmultiplied(120, 220, 120, 80)),
+            (windows.great, 2.0, Color32::from_rgba_unmultiplied(110, 200, 255, 120)),
+        ];
+
+        for (window, min_half_width, color) in bands {
+            let half_width = ((window / total_duration) as f32 * rect.width()).max(min_half_width);
+            let band = Rect::from_min_max(
+                Pos2::new(x - half_width, rect.min.y),
+                Pos2::new(x + half_width, rect.max.y),
+            );
+            painter.rect_filled(band, 0.0, color);
+        }
+
+        painter.text(
+            Pos2::new(x, rect.min.y - 1.0),
+            egui::Align2::CENTER_BOTTOM,
+            format!("±{:.0} / ±{:.0} / ±{:.0} ms", windows.great, windows.ok, windows.meh),
+            egui::FontId::monospace(10.0),
+            Color32::from_rgb(220, 220, 220),
+        );
+    }
+
     /// Draw the minimap showing object density
     fn draw_minimap(&self, painter: &egui::Painter, rect: Rect) {
         if self.density_cache.is_empty() {
@@ -0,0 +1,225 @@
+//! Audio waveform extraction for the timeline
+//!
+//! Decoding a long track takes seconds, so the envelope is cached in a
+//! `<audio>.waveform` sidecar next to the audio file and reused on later launches
+//! as long as the audio hash and resolution match.
+
+use anyhow::{bail, Context, Result};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+/// Audio time covered by each stored peak
+const MS_PER_PEAK: f64 = 10.0;
+
+/// Magic bytes at the start of a `.waveform` sidecar, bumped when the layout changes
+const CACHE_MAGIC: &[u8; 8] = b"OSUWAVE1";
+
+/// Downsampled min/max envelope of a song
+pub struct Waveform {
+    /// (min, max) sample per `MS_PER_PEAK` window, normalized to -1..1
+    peaks: Vec<(f32, f32)>,
+}
+
+impl Waveform {
+    /// Load the envelope from the sidecar cache, or decode it and write the cache
+    pub fn load(path: &Path) -> Result<Self> {
+        let audio = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let hash = fnv1a(&audio);
+        let cache_path = cache_path(path);
+
+        match Self::read_cache(&cache_path, hash) {
+            Ok(waveform) => return Ok(waveform),
+            Err(e) if cache_path.exists() => log::debug!("Ignoring waveform cache {}: {:#}", cache_path.display(), e),
+            Err(_) => {}
+        }
+
+        let waveform = Self::decode(path)?;
+        // Beatmap folders may be read-only; the cache is only an optimization
+        if let Err(e) = waveform.write_cache(&cache_path, hash) {
+            log::debug!("Failed to write waveform cache {}: {:#}", cache_path.display(), e);
+        }
+        Ok(waveform)
+    }
+
+    /// Decode an audio file and compute its envelope
+    pub fn decode(path: &Path) -> Result<Self> {
+        let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        let stream = MediaSourceStream::new(Box::new(file), Default::default());
+
+        let mut hint = Hint::new();
+        if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+            hint.with_extension(ext);
+        }
+
+        let probed = symphonia::default::get_probe()
+            .format(&hint, stream, &FormatOptions::default(), &MetadataOptions::default())
+            .context("Unsupported audio format")?;
+        let mut format = probed.format;
+
+        let track = format
+            .tracks()
+            .iter()
+            .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+            .context("No audio track")?;
+        let track_id = track.id;
+        let sample_rate = track.codec_params.sample_rate.context("Unknown sample rate")?;
+        let mut decoder = symphonia::default::get_codecs()
+            .make(&track.codec_params, &DecoderOptions::default())
+            .context("Unsupported audio codec")?;
+
+        let frames_per_peak = ((sample_rate as f64 * MS_PER_PEAK / 1000.0).round() as usize).max(1);
+        let mut peaks = Vec::new();
+        let mut current_peak = (0.0f32, 0.0f32);
+        let mut frames_in_peak = 0;
+
+        loop {
+            let packet = match format.next_packet() {
+                Ok(packet) => packet,
+                Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e).context("Failed to read audio"),
+            };
+            if packet.track_id() != track_id {
+                continue;
+            }
+
+            let decoded = match decoder.decode(&packet) {
+                Ok(decoded) => decoded,
+                // Skip corrupt frames rather than dropping the whole waveform
+                Err(SymphoniaError::DecodeError(_)) => continue,
+                Err(e) => return Err(e).context("Failed to decode audio"),
+            };
+
+            let spec = *decoded.spec();
+            let channels = spec.channels.count().max(1);
+            let mut samples = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+            samples.copy_interleaved_ref(decoded);
+
+            for frame in samples.samples().chunks(channels) {
+                for &sample in frame {
+                    current_peak.0 = current_peak.0.min(sample);
+                    current_peak.1 = current_peak.1.max(sample);
+                }
+                frames_in_peak += 1;
+                if frames_in_peak == frames_per_peak {
+                    peaks.push(current_peak);
+                    current_peak = (0.0, 0.0);
+                    frames_in_peak = 0;
+                }
+            }
+        }
+        if frames_in_peak > 0 {
+            peaks.push(current_peak);
+        }
+
+        // Normalize so quiet masters still show their structure
+        let max_peak = peaks.iter().fold(0.0f32, |max, &(lo, hi)| max.max(-lo).max(hi));
+        if max_peak > 0.0 {
+            for peak in &mut peaks {
+                peak.0 /= max_peak;
+                peak.1 /= max_peak;
+            }
+        }
+
+        Ok(Self { peaks })
+    }
+
+    /// Load on a background thread so decoding doesn't stall the UI
+    pub fn load_in_background(path: PathBuf) -> Receiver<Result<Self>> {
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = tx.send(Self::load(&path));
+        });
+        rx
+    }
+
+    /// Read a sidecar written by `write_cache`, failing if it belongs to other audio or another resolution
+    fn read_cache(cache_path: &Path, hash: u64) -> Result<Self> {
+        let data = fs::read(cache_path)?;
+        let Some((header, body)) = data.split_at_checked(CACHE_MAGIC.len() + 16) else {
+            bail!("Truncated header");
+        };
+        if &header[..8] != CACHE_MAGIC {
+            bail!("Not a waveform cache");
+        }
+        if u64::from_le_bytes(header[8..16].try_into()?) != hash {
+            bail!("Audio changed");
+        }
+        if f64::from_le_bytes(header[16..24].try_into()?) != MS_PER_PEAK {
+            bail!("Different resolution");
+        }
+        if body.len() % 8 != 0 {
+            bail!("Truncated envelope");
+        }
+
+        let peaks = body
+            .chunks_exact(8)
+            .map(|chunk| {
+                let lo = f32::from_le_bytes(chunk[..4].try_into().unwrap());
+                let hi = f32::from_le_bytes(chunk[4..].try_into().unwrap());
+                (lo, hi)
+            })
+            .collect();
+        Ok(Self { peaks })
+    }
+
+    /// Write the envelope as magic, audio hash, resolution, then little-endian (min, max) pairs
+    fn write_cache(&self, cache_path: &Path, hash: u64) -> Result<()> {
+        let mut data = Vec::with_capacity(CACHE_MAGIC.len() + 16 + self.peaks.len() * 8);
+        data.extend_from_slice(CACHE_MAGIC);
+        data.extend_from_slice(&hash.to_le_bytes());
+        data.extend_from_slice(&MS_PER_PEAK.to_le_bytes());
+        for &(lo, hi) in &self.peaks {
+            data.extend_from_slice(&lo.to_le_bytes());
+            data.extend_from_slice(&hi.to_le_bytes());
+        }
+        fs::write(cache_path, data)?;
+        Ok(())
+    }
+
+    /// (min, max) per column when `total_duration` ms is spread over `columns`
+    pub fn columns(&self, columns: usize, total_duration: f64) -> Vec<(f32, f32)> {
+        if columns == 0 || total_duration <= 0.0 {
+            return Vec::new();
+        }
+
+        let ms_per_column = total_duration / columns as f64;
+        (0..columns)
+            .map(|col| {
+                let start = ((col as f64 * ms_per_column) / MS_PER_PEAK) as usize;
+                let end = (((col + 1) as f64 * ms_per_column) / MS_PER_PEAK).ceil() as usize;
+                let end = end.max(start + 1).min(self.peaks.len());
+                self.peaks
+                    .get(start..end)
+                    .map(|window| {
+                        window
+                            .iter()
+                            .fold((0.0f32, 0.0f32), |(lo, hi), &(l, h)| (lo.min(l), hi.max(h)))
+                    })
+                    .unwrap_or((0.0, 0.0))
+            })
+            .collect()
+    }
+}
+
+/// Sidecar path for an audio file, e.g. `audio.mp3` -> `audio.mp3.waveform`
+fn cache_path(audio_path: &Path) -> PathBuf {
+    let mut name = audio_path.file_name().unwrap_or_default().to_os_string();
+    name.push(".waveform");
+    audio_path.with_file_name(name)
+}
+
+/// FNV-1a hash of the audio bytes, stable across builds unlike `DefaultHasher`
+fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf29ce484222325u64, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
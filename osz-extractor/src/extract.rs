@@ -0,0 +1,580 @@
+//! Archive extraction shared by the CLI and the viewer/player
+//!
+//! Keeps `.osu`/`.osb` files, audio and referenced images, decompresses
+//! packed beatmap entries and optionally flattens paths (`--flatten`).
+
+use anyhow::{Context, Result};
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use zip::ZipArchive;
+
+/// Detect if file content is audio using magic bytes
+fn is_audio_content(data: &[u8]) -> bool {
+    infer::get(data)
+        .map(|kind| kind.matcher_type() == infer::MatcherType::Audio)
+        .unwrap_or(false)
+}
+
+/// Check if a path has .osu extension
+pub fn is_osu_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("osu"))
+}
+
+/// Check if a path has .osb extension
+fn is_osb_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("osb"))
+}
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Largest decompressed `.osu`/`.osb` entry accepted, far above any real beatmap or storyboard
+const MAX_DECOMPRESSED_ENTRY_SIZE: u64 = 256 * 1024 * 1024;
+
+/// Unwrap a gzip/zstd-compressed beatmap entry
+///
+/// `.osu.gz`/`.osb.zst` style entries lose their compression extension, and
+/// `.osu`/`.osb` entries whose content starts with gzip or zstd magic are
+/// decompressed in place. Everything else is returned unchanged. Entries that
+/// decompress to more than 256 MiB are rejected instead of exhausting memory.
+pub(crate) fn decompress_beatmap_entry(path: PathBuf, data: Vec<u8>) -> Result<(PathBuf, Vec<u8>)> {
+    decompress_entry_with_limit(path, data, MAX_DECOMPRESSED_ENTRY_SIZE)
+}
+
+fn decompress_entry_with_limit(path: PathBuf, data: Vec<u8>, limit: u64) -> Result<(PathBuf, Vec<u8>)> {
+    let compressed_ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("gz") || ext.eq_ignore_ascii_case("zst"));
+    let path = if compressed_ext {
+        let inner = path.with_extension("");
+        if !(is_osu_file(&inner) || is_osb_file(&inner)) {
+            return Ok((path, data));
+        }
+        inner
+    } else if is_osu_file(&path) || is_osb_file(&path) {
+        path
+    } else {
+        return Ok((path, data));
+    };
+
+    let decompressed = if data.starts_with(&GZIP_MAGIC) {
+        read_capped(flate2::read::MultiGzDecoder::new(data.as_slice()), limit, "gzip", &path)?
+    } else if data.starts_with(&ZSTD_MAGIC) {
+        let decoder = zstd::stream::read::Decoder::new(data.as_slice())
+            .with_context(|| format!("Failed to decompress zstd entry: {}", path.display()))?;
+        read_capped(decoder, limit, "zstd", &path)?
+    } else {
+        // Misnamed plain text; keep the content, drop the bogus extension
+        data
+    };
+    Ok((path, decompressed))
+}
+
+/// Read a decompressing reader to the end, failing once it yields more than `limit` bytes
+fn read_capped(reader: impl io::Read, limit: u64, format: &str, path: &Path) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    io::Read::read_to_end(&mut reader.take(limit + 1), &mut out)
+        .with_context(|| format!("Failed to decompress {} entry: {}", format, path.display()))?;
+    if out.len() as u64 > limit {
+        anyhow::bail!(
+            "{} entry {} decompresses to more than {} bytes",
+            format,
+            path.display(),
+            limit
+        );
+    }
+    Ok(out)
+}
+
+/// Parsed image references from an .osu file
+struct OsuImageRefs {
+    /// The main background image (from 0,0 line) - required
+    background: Option<String>,
+    /// Optional storyboard images (sprites, animations) - not required to exist
+    storyboard: Vec<String>,
+}
+
+/// Whether an `AudioFilename` value means the map has no audio file
+///
+/// Storyboard-only and tutorial maps use `virtual` (or leave it empty).
+pub fn is_virtual_audio(audio_filename: &str) -> bool {
+    let name = audio_filename.trim();
+    name.is_empty() || name.eq_ignore_ascii_case("virtual")
+}
+
+/// Read the `AudioFilename` value from the `[General]` section
+///
+/// Expects text from `osu_text::normalize_osu_text`. Returns an empty string
+/// when the key is missing.
+fn parse_audio_filename(content: &str) -> &str {
+    content
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("AudioFilename:"))
+        .map(str::trim)
+        .unwrap_or("")
+}
+
+/// Normalize path separators for consistent comparison (backslash to forward slash, lowercase)
+fn normalize_path(path: &str) -> String {
+    path.replace('\\', "/").to_lowercase()
+}
+
+/// Type of an `[Events]` line, the first comma-separated field
+fn event_type(line: &str) -> &str {
+    line.split(',').next().unwrap_or("").trim()
+}
+
+/// Byte range of the file name in an `[Events]` line, without its quotes
+///
+/// Fields are split on commas outside quotes, so quoted (`0,0,"bg 1.jpg",0,0`)
+/// and bare (`0,0,bg.jpg,0,0`) names are both found. Storyboard commands
+/// (indented lines) and events without a file give `None`.
+fn event_file_span(line: &str) -> Option<std::ops::Range<usize>> {
+    if line.starts_with([' ', '_']) {
+        return None;
+    }
+    let index = match event_type(line) {
+        "0" | "Background" | "1" | "Video" => 2,
+        "4" | "Sprite" | "5" | "Sample" | "6" | "Animation" => 3,
+        _ => return None,
+    };
+
+    let mut fields = Vec::new();
+    let mut start = 0;
+    let mut in_quotes = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => {
+                fields.push(start..i);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    fields.push(start..line.len());
+
+    let field = fields.get(index)?;
+    let raw = &line[field.clone()];
+    let value = raw.trim();
+    let value_start = field.start + (raw.len() - raw.trim_start().len());
+    let span = match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        Some(unquoted) => value_start + 1..value_start + 1 + unquoted.len(),
+        None => value_start..value_start + value.len(),
+    };
+    (!span.is_empty()).then_some(span)
+}
+
+/// Parse .osu file content to extract image references from [Events] section
+///
+/// Expects text from `osu_text::normalize_osu_text` (no BOM, `\n` line endings).
+fn parse_images_from_osu(content: &str) -> OsuImageRefs {
+    let mut refs = OsuImageRefs {
+        background: None,
+        storyboard: Vec::new(),
+    };
+    let mut in_events = false;
+    
+    for line in content.lines() {
+        let line = line.trim();
+        
+        if line == "[Events]" {
+            in_events = true;
+            continue;
+        }
+        
+        // Check if we've left the Events section
+        if in_events && line.starts_with('[') {
+            break;
+        }
+        
+        if !in_events {
+            continue;
+        }
+
+        // Skip comments and empty lines
+        if line.is_empty() || line.starts_with("//") {
+            continue;
+        }
+
+        let Some(span) = event_file_span(line) else {
+            continue;
+        };
+        let filename = line[span].to_string();
+        match event_type(line) {
+            "0" | "Background" => {
+                if refs.background.is_none() {
+                    refs.background = Some(filename);
+                }
+            }
+            // Videos aren't extracted
+            "1" | "Video" => {}
+            // Storyboard sprite, animation or sample
+            _ => refs.storyboard.push(filename),
+        }
+    }
+    
+    refs
+}
+
+/// Extract an archive's beatmap files into `extract_folder`
+///
+/// Fails if the archive has no `.osu` file, no audio, or lacks a background a
+/// difficulty references. With `allow_no_audio`, an archive without audio is
+/// kept when every difficulty declares `AudioFilename: virtual` (or none).
+/// Files may already have been written when it fails; the caller removes the
+/// partial folder.
+pub fn extract_archive(osz_path: &Path, extract_folder: &Path, flatten: bool, allow_no_audio: bool) -> Result<()> {
+    use std::collections::{HashMap, HashSet};
+    
+    // Open the .osz file (which is just a zip archive)
+    let file = File::open(osz_path)
+        .with_context(|| format!("Failed to open: {}", osz_path.display()))?;
+
+    let mut archive = ZipArchive::new(file)
+        .with_context(|| format!("Failed to read zip: {}", osz_path.display()))?;
+
+    // First pass: read all files
+    let mut files_data: Vec<(PathBuf, Vec<u8>)> = Vec::new();
+
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+
+        // Skip directories
+        if file.is_dir() {
+            continue;
+        }
+
+        // Get the file path, handling potential directory entries
+        let inner_path = match file.enclosed_name() {
+            Some(path) => path.to_path_buf(),
+            None => continue,
+        };
+
+        // Read file content
+        let mut data = Vec::new();
+        io::Read::read_to_end(&mut file, &mut data)?;
+
+        files_data.push(decompress_beatmap_entry(inner_path, data)?);
+    }
+
+    // Second pass: parse .osu files to find referenced images
+    let mut required_backgrounds: HashSet<String> = HashSet::new();
+    let mut optional_images: HashSet<String> = HashSet::new();
+    let mut has_osu_files = false;
+    let mut all_virtual_audio = true;
+    
+    for (path, data) in &files_data {
+        if is_osu_file(path) || is_osb_file(path) {
+            let content = osu_text::normalize_osu_text(data);
+            if is_osu_file(path) {
+                has_osu_files = true;
+                all_virtual_audio &= is_virtual_audio(parse_audio_filename(&content));
+            }
+            let refs = parse_images_from_osu(&content);
+            if let Some(bg) = refs.background {
+                required_backgrounds.insert(normalize_path(&bg));
+            }
+            for img in refs.storyboard {
+                optional_images.insert(normalize_path(&img));
+            }
+        }
+    }
+
+    // Validate: must have at least one .osu file
+    if !has_osu_files {
+        anyhow::bail!("No .osu files found");
+    }
+
+    // Build set of available files (lowercased for case-insensitive matching)
+    let available_files: HashSet<String> = files_data
+        .iter()
+        .map(|(path, _)| normalize_path(&path.to_string_lossy()))
+        .collect();
+
+    // Check that all required backgrounds exist
+    for bg in &required_backgrounds {
+        if !available_files.contains(bg) {
+            anyhow::bail!("Required background not found: {}", bg);
+        }
+    }
+
+    // Third pass: decide which files to keep
+    let mut audio_found = false;
+    let mut kept: Vec<(&PathBuf, &Vec<u8>, bool)> = Vec::new();
+
+    for (inner_path, data) in &files_data {
+        let normalized = normalize_path(&inner_path.to_string_lossy());
+        let is_audio = is_audio_content(data);
+
+        // Always keep: .osu files, .osb files
+        let keep = is_osu_file(inner_path) 
+            || is_osb_file(inner_path)
+            || is_audio 
+            || required_backgrounds.contains(&normalized)
+            || optional_images.contains(&normalized);
+
+        if !keep {
+            continue;
+        }
+
+        // Track if we found audio
+        if is_audio {
+            audio_found = true;
+        }
+
+        // Warn about missing optional storyboard images
+        if optional_images.contains(&normalized) && !available_files.contains(&normalized) {
+            eprintln!("⚠ Storyboard image not found: {}", inner_path.display());
+            continue;
+        }
+
+        // Storyboard-only images keep their paths since storyboard scripts reference them
+        let flattenable = is_osu_file(inner_path)
+            || is_osb_file(inner_path)
+            || is_audio
+            || required_backgrounds.contains(&normalized);
+
+        kept.push((inner_path, data, flattenable));
+    }
+
+    // Map normalized original paths to their flattened names
+    let renames: HashMap<String, String> = if flatten {
+        flatten_file_names(kept.iter().map(|(path, _, flattenable)| (path.as_path(), *flattenable)))
+    } else {
+        HashMap::new()
+    };
+
+    // Fourth pass: write files
+    for (inner_path, data, _) in &kept {
+        let normalized = normalize_path(&inner_path.to_string_lossy());
+
+        let outpath = match renames.get(&normalized) {
+            Some(flat_name) => extract_folder.join(flat_name),
+            None => extract_folder.join(inner_path),
+        };
+
+        // Rewrite path references inside beatmap and storyboard files
+        let rewritten = if !renames.is_empty() && (is_osu_file(inner_path) || is_osb_file(inner_path)) {
+            std::str::from_utf8(data)
+                .ok()
+                .map(|content| rewrite_path_references(content, &renames))
+        } else {
+            None
+        };
+
+        // Ensure parent directory exists
+        if let Some(parent) = outpath.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut outfile = File::create(&outpath)
+            .with_context(|| format!("Failed to create file: {}", outpath.display()))?;
+
+        match &rewritten {
+            Some(content) => io::Write::write_all(&mut outfile, content.as_bytes())?,
+            None => io::Write::write_all(&mut outfile, data)?,
+        }
+    }
+
+    // Validate: must have audio, unless allowed and no difficulty references any
+    if !audio_found {
+        if !all_virtual_audio {
+            anyhow::bail!("No audio file found");
+        }
+        if !allow_no_audio {
+            anyhow::bail!("No audio file found (AudioFilename: virtual; use --allow-no-audio to keep)");
+        }
+    }
+
+    Ok(())
+}
+
+/// Assign root-level file names to flattenable files, suffixing on collisions.
+/// Returns a map from normalized original path to the new file name.
+fn flatten_file_names<'a>(
+    files: impl Iterator<Item = (&'a Path, bool)> + Clone,
+) -> std::collections::HashMap<String, String> {
+    use std::collections::{HashMap, HashSet};
+
+    // Reserve paths of files that stay where they are (lowercased, the filesystem may be case-insensitive)
+    let mut used: HashSet<String> = files
+        .clone()
+        .filter(|(_, flattenable)| !flattenable)
+        .map(|(path, _)| normalize_path(&path.to_string_lossy()))
+        .collect();
+
+    let mut renames = HashMap::new();
+
+    for (path, flattenable) in files {
+        if !flattenable {
+            continue;
+        }
+
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+        let extension = path.extension().map(|e| e.to_string_lossy().to_string());
+
+        let mut candidate = file_name;
+        let mut suffix = 1;
+        while used.contains(&candidate.to_lowercase()) {
+            candidate = match &extension {
+                Some(ext) => format!("{}_{}.{}", stem, suffix, ext),
+                None => format!("{}_{}", stem, suffix),
+            };
+            suffix += 1;
+        }
+
+        used.insert(candidate.to_lowercase());
+        renames.insert(normalize_path(&path.to_string_lossy()), candidate);
+    }
+
+    renames
+}
+
+/// Rewrite the audio filename and [Events] file references to their flattened names
+fn rewrite_path_references(content: &str, renames: &std::collections::HashMap<String, String>) -> String {
+    let mut output = String::with_capacity(content.len());
+    let mut in_events = false;
+
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim();
+
+        if trimmed.starts_with('[') {
+            in_events = trimmed == "[Events]";
+            output.push_str(line);
+            continue;
+        }
+
+        // AudioFilename: path in [General]
+        if let Some(value) = trimmed.strip_prefix("AudioFilename:") {
+            if let Some(new_name) = renames.get(&normalize_path(value.trim())) {
+                let ending = &line[line.trim_end().len()..];
+                output.push_str(&format!("AudioFilename: {}{}", new_name, ending));
+                continue;
+            }
+        }
+
+        // Quoted or bare filename in [Events] (backgrounds, videos, sprites, samples)
+        if in_events {
+            if let Some(span) = event_file_span(line.trim_end_matches(['\r', '\n'])) {
+                if let Some(new_name) = renames.get(&normalize_path(&line[span.clone()])) {
+                    output.push_str(&line[..span.start]);
+                    output.push_str(new_name);
+                    output.push_str(&line[span.end..]);
+                    continue;
+                }
+            }
+        }
+
+        output.push_str(line);
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn renames() -> HashMap<String, String> {
+        HashMap::from([
+            ("sb/bg.jpg".to_string(), "bg.jpg".to_string()),
+            ("music/audio.mp3".to_string(), "audio.mp3".to_string()),
+        ])
+    }
+
+    #[test]
+    fn event_file_span_finds_quoted_and_bare_names() {
+        let quoted = "0,0,\"sb/bg 1.jpg\",0,0";
+        assert_eq!(&quoted[event_file_span(quoted).unwrap()], "sb/bg 1.jpg");
+        let bare = "0,0,sb/bg.jpg,0,0";
+        assert_eq!(&bare[event_file_span(bare).unwrap()], "sb/bg.jpg");
+        let sprite = "Sprite,Foreground,Centre,sb\\star.png,320,240";
+        assert_eq!(&sprite[event_file_span(sprite).unwrap()], "sb\\star.png");
+        let sample = "5,1000,0,\"hit.wav\",70";
+        assert_eq!(&sample[event_file_span(sample).unwrap()], "hit.wav");
+
+        assert_eq!(event_file_span("2,1000,2000"), None);
+        assert_eq!(event_file_span(" F,0,0,1000,0,1"), None);
+        assert_eq!(event_file_span("0,0,\"\",0,0"), None);
+    }
+
+    #[test]
+    fn rewrites_quoted_and_unquoted_event_paths() {
+        let content = "[General]\r\nAudioFilename: music/audio.mp3\r\n\r\n[Events]\r\n0,0,sb/bg.jpg,0,0\r\nSprite,Background,Centre,\"SB/BG.jpg\",320,240\r\n";
+        let rewritten = rewrite_path_references(content, &renames());
+        assert_eq!(
+            rewritten,
+            "[General]\r\nAudioFilename: audio.mp3\r\n\r\n[Events]\r\n0,0,bg.jpg,0,0\r\nSprite,Background,Centre,\"bg.jpg\",320,240\r\n"
+        );
+    }
+
+    #[test]
+    fn paths_outside_events_are_untouched() {
+        let content = "[Metadata]\nTitle:0,0,sb/bg.jpg\n";
+        assert_eq!(rewrite_path_references(content, &renames()), content);
+    }
+
+    #[test]
+    fn unquoted_background_is_required() {
+        let refs = parse_images_from_osu("[Events]\n0,0,bg.jpg,0,0\nVideo,0,\"intro.mp4\"\nSprite,Foreground,Centre,star.png,0,0\n");
+        assert_eq!(refs.background.as_deref(), Some("bg.jpg"));
+        assert_eq!(refs.storyboard, vec!["star.png".to_string()]);
+    }
+
+    #[test]
+    fn background_is_found_behind_a_bom() {
+        let bytes = b"\xEF\xBB\xBF[Events]\r\n0,0,\"bg.jpg\",0,0\r\n";
+        let refs = parse_images_from_osu(&osu_text::normalize_osu_text(bytes));
+        assert_eq!(refs.background.as_deref(), Some("bg.jpg"));
+    }
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        io::Write::write_all(&mut encoder, data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn compressed_entries_lose_their_extension() {
+        let text = b"osu file format v14\n".to_vec();
+        let (path, data) = decompress_beatmap_entry(PathBuf::from("map.osu.gz"), gzip(&text)).unwrap();
+        assert_eq!((path, data), (PathBuf::from("map.osu"), text.clone()));
+
+        let zstd = zstd::stream::encode_all(text.as_slice(), 0).unwrap();
+        let (path, data) = decompress_beatmap_entry(PathBuf::from("sb.OSB.zst"), zstd).unwrap();
+        assert_eq!((path, data), (PathBuf::from("sb.OSB"), text));
+    }
+
+    #[test]
+    fn gzip_magic_is_unwrapped_without_an_extension() {
+        let (path, data) = decompress_beatmap_entry(PathBuf::from("map.osu"), gzip(b"[General]\n")).unwrap();
+        assert_eq!((path, data), (PathBuf::from("map.osu"), b"[General]\n".to_vec()));
+    }
+
+    #[test]
+    fn other_entries_are_untouched() {
+        let packed = gzip(b"not a map");
+        let (path, data) = decompress_beatmap_entry(PathBuf::from("skin.png.gz"), packed.clone()).unwrap();
+        assert_eq!((path, data), (PathBuf::from("skin.png.gz"), packed));
+        // Misnamed plain text keeps its content
+        let (path, data) = decompress_beatmap_entry(PathBuf::from("map.osu.gz"), b"plain".to_vec()).unwrap();
+        assert_eq!((path, data), (PathBuf::from("map.osu"), b"plain".to_vec()));
+    }
+
+    #[test]
+    fn oversized_decompression_is_rejected() {
+        let bomb = gzip(&vec![b'a'; 4096]);
+        let err = decompress_entry_with_limit(PathBuf::from("map.osu.gz"), bomb.clone(), 1024).unwrap_err();
+        assert!(err.to_string().contains("more than 1024 bytes"), "{}", err);
+        assert!(decompress_entry_with_limit(PathBuf::from("map.osu.gz"), bomb, 4096).is_ok());
+    }
+}
@@ -0,0 +1,13 @@
+//! osz-extractor: Extract .osz archives into beatmap folders
+//!
+//! The library half exposes archive extraction so the viewer and player can
+//! open `.osz` files directly; the binary does the batch extraction.
+
+mod extract;
+pub mod metadata_index;
+mod open;
+
+pub use extract::{extract_archive, is_osu_file, is_virtual_audio};
+pub use open::{extract_to_temp, find_audio, has_extension, resolve_osu_path, ExtractedArchive, ResolvedOsu};
+
+pub(crate) use extract::decompress_beatmap_entry;
@@ -2,14 +2,21 @@ use anyhow::{Context, Result};
 use clap::Parser;
 use indicatif::{ProgressBar, ProgressStyle};
 use std::fs::{self, File};
-use std::io::{self, Write};
+use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
 use std::sync::Arc;
 use std::thread;
-use std::time::{Duration, Instant};
+use std::time::Duration;
 use walkdir::WalkDir;
-use zip::ZipArchive;
+
+mod rate_limit;
+
+use osu_batch::summary::{self, RunSummary};
+use osu_batch::{say, FailedFormat, FailedLog};
+use osz_extractor::metadata_index::{self, IndexWriter};
+use rate_limit::MirrorLimiter;
 
 /// Extract .osz files from osu! songs folder
 #[derive(Parser, Debug)]
@@ -26,36 +33,66 @@ struct Args {
     /// Force re-extraction even if output folder exists
     #[arg(long, short)]
     force: bool,
-}
 
-/// Rate limiter state for nerinyan API (25 requests per minute)
-struct RateLimiter {
-    last_request: Option<Instant>,
-    min_interval: Duration,
+    /// Strip subdirectories from .osu/.osb/audio/background files and rewrite .osu path references
+    #[arg(long)]
+    flatten: bool,
+
+    /// Keep archives without audio whose difficulties all use `AudioFilename: virtual` (storyboard-only maps)
+    #[arg(long)]
+    allow_no_audio: bool,
+
+    /// Give up on an archive after this many seconds and record it as a timeout failure
+    #[arg(long)]
+    per_item_timeout_secs: Option<u64>,
+
+    /// Path of the failed list (default: failed.<format extension> in output_dir)
+    #[arg(long, value_name = "PATH")]
+    failed_log: Option<PathBuf>,
+
+    /// Format of the failed list
+    #[arg(long, value_enum, default_value_t = FailedFormat::Txt)]
+    failed_format: FailedFormat,
+
+    /// Delete each .osz after it has been fully extracted and validated
+    #[arg(long)]
+    delete_source: bool,
+
+    /// Only read .osu metadata into a JSON Lines index instead of extracting anything
+    #[arg(long, conflicts_with_all = ["force", "flatten", "delete_source"])]
+    metadata_only: bool,
+
+    /// Path of the --metadata-only index (default: metadata_index.jsonl in output_dir)
+    #[arg(long, value_name = "PATH")]
+    index_path: Option<PathBuf>,
+
+    /// No progress output; print a single JSON summary line on stdout instead
+    #[arg(long, short)]
+    quiet: bool,
 }
 
-impl RateLimiter {
-    fn new(requests_per_minute: u32) -> Self {
-        Self {
-            last_request: None,
-            min_interval: Duration::from_secs(60) / requests_per_minute,
-        }
-    }
+/// Error returned when an archive exceeds --per-item-timeout-secs
+#[derive(Debug)]
+struct ItemTimeout(u64);
 
-    fn wait(&mut self) {
-        if let Some(last) = self.last_request {
-            let elapsed = last.elapsed();
-            if elapsed < self.min_interval {
-                thread::sleep(self.min_interval - elapsed);
-            }
-        }
-        self.last_request = Some(Instant::now());
+impl std::fmt::Display for ItemTimeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "timeout after {}s", self.0)
     }
 }
 
-/// Download beatmapset from nerinyan mirror
-fn download_from_nerinyan(beatmapset_id: &str, dest_path: &Path) -> Result<()> {
-    let url = format!("https://api.nerinyan.moe/d/{}", beatmapset_id);
+impl std::error::Error for ItemTimeout {}
+
+/// Host of the nerinyan mirror, also its rate limiter key
+const NERINYAN_HOST: &str = "api.nerinyan.moe";
+
+/// Requests per minute allowed to each mirror
+const MIRROR_REQUESTS_PER_MINUTE: u32 = 25;
+
+/// Download beatmapset from nerinyan mirror, waiting for the mirror's rate budget first
+fn download_from_nerinyan(beatmapset_id: &str, dest_path: &Path, limiter: &MirrorLimiter) -> Result<()> {
+    let url = format!("https://{}/d/{}", NERINYAN_HOST, beatmapset_id);
+    limiter.wait(NERINYAN_HOST);
     
     let response = reqwest::blocking::Client::new()
         .get(&url)
@@ -80,13 +117,14 @@ fn download_from_nerinyan(beatmapset_id: &str, dest_path: &Path) -> Result<()> {
 
 fn main() -> Result<()> {
     let args = Args::parse();
+    summary::set_quiet(args.quiet);
 
     // Set up graceful shutdown flag
     let shutdown_requested = Arc::new(AtomicBool::new(false));
     let shutdown_clone = shutdown_requested.clone();
 
     ctrlc::set_handler(move || {
-        println!("\n⏳ Ctrl+C received! Finishing current file then stopping...");
+        say!("\n⏳ Ctrl+C received! Finishing current file then stopping...");
         shutdown_clone.store(true, Ordering::SeqCst);
     })
     .expect("Error setting Ctrl+C handler");
@@ -98,9 +136,18 @@ fn main() -> Result<()> {
     // Create output directory if it doesn't exist
     fs::create_dir_all(&args.output_dir)?;
 
-    println!("Scanning for .osz files in: {}", args.input_dir.display());
+    say!("Scanning for .osz files in: {}", args.input_dir.display());
     if args.force {
-        println!("Force mode: will re-extract existing folders");
+        say!("Force mode: will re-extract existing folders");
+    }
+    if args.flatten {
+        say!("Flatten mode: beatmap files will be extracted to the folder root");
+    }
+    if let Some(secs) = args.per_item_timeout_secs {
+        say!("Per-archive timeout: {}s", secs);
+    }
+    if args.delete_source {
+        say!("Delete mode: archives will be removed after successful extraction");
     }
 
     // Collect all .osz files
@@ -118,14 +165,18 @@ fn main() -> Result<()> {
         .collect();
 
     if osz_files.is_empty() {
-        println!("No .osz files found.");
-        return Ok(());
+        say!("No .osz files found.");
+        return RunSummary::default().print();
     }
 
-    println!("Found {} .osz files", osz_files.len());
-    println!("Press Ctrl+C to stop gracefully (will finish current file)\n");
+    say!("Found {} .osz files", osz_files.len());
+    say!("Press Ctrl+C to stop gracefully (will finish current file)\n");
 
-    let pb = ProgressBar::new(osz_files.len() as u64);
+    if args.metadata_only {
+        return build_metadata_index(&args, &osz_files, &shutdown_requested);
+    }
+
+    let pb = summary::progress_bar(osz_files.len() as u64);
     pb.set_style(
         ProgressStyle::default_bar()
             .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} ({eta})")
@@ -138,32 +189,18 @@ fn main() -> Result<()> {
     let mut skipped_count = 0;
     let mut already_extracted_count = 0;
     let mut downloaded_count = 0;
+    let mut deleted_count = 0;
     
     // Load failed list (beatmapset IDs that permanently failed)
-    // Format: "id: reason" - we extract the ID for skip checks, store full line
-    let failed_list_path = args.output_dir.join("failed.txt");
-    let failed_lines: Vec<String> = if failed_list_path.exists() {
-        std::fs::read_to_string(&failed_list_path)
-            .unwrap_or_default()
-            .lines()
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty())
-            .collect()
-    } else {
-        Vec::new()
-    };
-    // Extract just IDs for fast lookup
-    let failed_id_set: std::collections::HashSet<String> = failed_lines
-        .iter()
-        .map(|line| line.split(':').next().unwrap_or("").trim().to_string())
-        .filter(|s| !s.is_empty())
-        .collect();
-    // Keep full lines for saving (preserves reasons)
-    let mut failed_ids: std::collections::HashSet<String> = failed_lines.into_iter().collect();
-    let initial_failed_count = failed_id_set.len();
+    let failed_log_path = args
+        .failed_log
+        .clone()
+        .unwrap_or_else(|| args.output_dir.join(format!("failed.{}", args.failed_format.extension())));
+    let mut failed_log = FailedLog::load(&failed_log_path, args.failed_format)?;
+    let initial_failed_count = failed_log.initial_count();
     
-    // Rate limiter for nerinyan API (25 requests per minute)
-    let mut rate_limiter = RateLimiter::new(25);
+    // Shared per-mirror download budget
+    let mirror_limiter = MirrorLimiter::per_minute(MIRROR_REQUESTS_PER_MINUTE);
 
     for osz_path in &osz_files {
         // Check if shutdown was requested before starting next file
@@ -183,7 +220,7 @@ fn main() -> Result<()> {
             .unwrap_or("");
 
         // Check if already known to be permanently failed
-        if failed_id_set.contains(beatmapset_id) {
+        if failed_log.contains(beatmapset_id) {
             skipped_count += 1;
             pb.inc(1);
             continue;
@@ -203,9 +240,18 @@ fn main() -> Result<()> {
         pb.set_message(format!("{}", osz_name));
 
         // Try to extract
-        match extract_osz(osz_path, &args.output_dir) {
-            Ok(_) => {
+        match extract_then_delete(osz_path, &args, &pb) {
+            Ok(deleted) => {
                 extracted_count += 1;
+                if deleted {
+                    deleted_count += 1;
+                }
+            }
+            Err(e) if e.is::<ItemTimeout>() => {
+                // The worker thread may still be running; don't touch the archive or retry
+                pb.println(format!("⏱ {} - {}", osz_name, e));
+                failed_log.record(beatmapset_id, "timeout", &e);
+                failed_count += 1;
             }
             Err(e) => {
                 // Always try downloading from nerinyan on any failure
@@ -213,12 +259,9 @@ fn main() -> Result<()> {
                 if beatmapset_id.chars().all(|c| c.is_ascii_digit()) && !beatmapset_id.is_empty() {
                     pb.println(format!("⬇️  {} - Downloading from nerinyan...", osz_name));
                     
-                    // Rate limit
-                    rate_limiter.wait();
-                    
                     // Download to a temp file
                     let temp_path = osz_path.with_extension("osz.tmp");
-                    match download_from_nerinyan(beatmapset_id, &temp_path) {
+                    match download_from_nerinyan(beatmapset_id, &temp_path, &mirror_limiter) {
                         Ok(_) => {
                             downloaded_count += 1;
                             
@@ -226,19 +269,23 @@ fn main() -> Result<()> {
                             if let Err(e) = fs::rename(&temp_path, osz_path) {
                                 pb.println(format!("❌ {} - Failed to replace file: {}", osz_name, e));
                                 let _ = fs::remove_file(&temp_path);
-                                failed_ids.insert(format!("{}: {}", beatmapset_id, e));
+                                failed_log.record(beatmapset_id, "download", &e.into());
                                 failed_count += 1;
                             } else {
                                 // Retry extraction with the new file
-                                match extract_osz(osz_path, &args.output_dir) {
-                                    Ok(_) => {
+                                match extract_then_delete(osz_path, &args, &pb) {
+                                    Ok(deleted) => {
                                         pb.println(format!("✅ {} - Downloaded and extracted", osz_name));
                                         extracted_count += 1;
+                                        if deleted {
+                                            deleted_count += 1;
+                                        }
                                     }
                                     Err(e) => {
                                         pb.println(format!("❌ {} - Still failed: {}", osz_name, e));
                                         // Add to failed list with reason
-                                        failed_ids.insert(format!("{}: {}", beatmapset_id, e));
+                                        let category = if e.is::<ItemTimeout>() { "timeout" } else { "extract" };
+                                        failed_log.record(beatmapset_id, category, &e);
                                         failed_count += 1;
                                     }
                                 }
@@ -247,7 +294,7 @@ fn main() -> Result<()> {
                         Err(e) => {
                             pb.println(format!("❌ {} - Download failed: {}", osz_name, e));
                             let _ = fs::remove_file(&temp_path);
-                            failed_ids.insert(format!("{}: {}", beatmapset_id, e));
+                            failed_log.record(beatmapset_id, "download", &e);
                             failed_count += 1;
                         }
                     }
@@ -264,268 +311,324 @@ fn main() -> Result<()> {
     pb.finish_and_clear();
 
     // Save failed list if there are new failures
-    let new_failures = failed_ids.len() - initial_failed_count;
+    let new_failures = failed_log.new_count();
     if new_failures > 0 {
-        let content: String = failed_ids.iter().map(|s| format!("{}\n", s)).collect();
-        let _ = std::fs::write(&failed_list_path, content);
+        if let Err(e) = failed_log.save() {
+            say!("⚠ {}", e);
+        }
     }
 
-    println!("\n✅ Summary:");
-    println!("   Extracted:  {}", extracted_count);
-    println!("   Skipped:    {} (already extracted)", already_extracted_count);
+    say!("\n✅ Summary:");
+    say!("   Extracted:  {}", extracted_count);
+    say!("   Skipped:    {} (already extracted)", already_extracted_count);
     if initial_failed_count > 0 {
-        println!("   Skipped:    {} (permanently failed)", initial_failed_count);
+        say!("   Skipped:    {} (permanently failed)", initial_failed_count);
+    }
+    say!("   Downloaded: {}", downloaded_count);
+    say!("   Failed:     {}", failed_count);
+    if args.delete_source {
+        say!("   Deleted:    {} (source archives)", deleted_count);
     }
-    println!("   Downloaded: {}", downloaded_count);
-    println!("   Failed:     {}", failed_count);
     if new_failures > 0 {
-        println!("   Added to failed.txt: {}", new_failures);
+        say!("   Added to {}: {}", failed_log.path().display(), new_failures);
     }
     if skipped_count > 0 {
-        println!("   Interrupted: {} (due to Ctrl+C)", skipped_count);
+        say!("   Interrupted: {} (due to Ctrl+C)", skipped_count);
     }
 
-    Ok(())
+    RunSummary {
+        processed: extracted_count + failed_count,
+        succeeded: extracted_count,
+        failed: failed_count,
+        rows: Default::default(),
+        interrupted: shutdown_requested.load(Ordering::SeqCst),
+    }
+    .print()
 }
 
-/// Detect if file content is audio using magic bytes
-fn is_audio_content(data: &[u8]) -> bool {
-    infer::get(data)
-        .map(|kind| kind.matcher_type() == infer::MatcherType::Audio)
-        .unwrap_or(false)
-}
+/// Write the --metadata-only index: one JSON line per difficulty, nothing extracted
+fn build_metadata_index(args: &Args, osz_files: &[PathBuf], shutdown_requested: &AtomicBool) -> Result<()> {
+    let index_path = args
+        .index_path
+        .clone()
+        .unwrap_or_else(|| args.output_dir.join("metadata_index.jsonl"));
+    let mut index = IndexWriter::create(&index_path)?;
 
-/// Check if a path has .osu extension
-fn is_osu_file(path: &Path) -> bool {
-    path.extension()
-        .and_then(|e| e.to_str())
-        .is_some_and(|ext| ext.eq_ignore_ascii_case("osu"))
-}
+    let pb = summary::progress_bar(osz_files.len() as u64);
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} ({eta})")
+            .unwrap()
+            .progress_chars("#>-"),
+    );
 
-/// Check if a path has .osb extension
-fn is_osb_file(path: &Path) -> bool {
-    path.extension()
-        .and_then(|e| e.to_str())
-        .is_some_and(|ext| ext.eq_ignore_ascii_case("osb"))
-}
+    let mut indexed_count = 0;
+    let mut failed_count = 0;
+    let mut interrupted = false;
+
+    for osz_path in osz_files {
+        if shutdown_requested.load(Ordering::SeqCst) {
+            interrupted = true;
+            pb.println("🛑 Stopping gracefully...");
+            break;
+        }
 
-/// Parsed image references from an .osu file
-struct OsuImageRefs {
-    /// The main background image (from 0,0 line) - required
-    background: Option<String>,
-    /// Optional storyboard images (sprites, animations) - not required to exist
-    storyboard: Vec<String>,
+        match metadata_index::read_osz_metadata(osz_path) {
+            Ok(metas) => {
+                for meta in &metas {
+                    index.write(meta)?;
+                }
+                indexed_count += 1;
+            }
+            Err(e) => {
+                let osz_name = osz_path.file_name().unwrap_or_default().to_string_lossy();
+                pb.println(format!("❌ {} - {}", osz_name, e));
+                failed_count += 1;
+            }
+        }
+        pb.inc(1);
+    }
+
+    pb.finish_and_clear();
+    let rows = index.finish()?;
+
+    say!("\n✅ Summary:");
+    say!("   Indexed:    {} archives ({} difficulties)", indexed_count, rows);
+    say!("   Failed:     {}", failed_count);
+    say!("   Index:      {}", index_path.display());
+    if interrupted {
+        say!("   Interrupted by Ctrl+C; the index only covers the archives above");
+    }
+
+    RunSummary {
+        processed: indexed_count + failed_count,
+        succeeded: indexed_count,
+        failed: failed_count,
+        rows: [("metadata_index".to_string(), rows)].into(),
+        interrupted,
+    }
+    .print()
 }
 
-/// Normalize path separators for consistent comparison (backslash to forward slash, lowercase)
-fn normalize_path(path: &str) -> String {
-    path.replace('\\', "/").to_lowercase()
+/// Extract one archive with the run's settings and, with --delete-source, remove it afterwards.
+///
+/// Returns whether the archive was deleted. Any error (including a timeout) returns
+/// before the deletion, so failed, timed-out and partial extractions keep their source.
+fn extract_then_delete(osz_path: &Path, args: &Args, pb: &ProgressBar) -> Result<bool> {
+    extract_osz_with_timeout(osz_path, &args.output_dir, args.flatten, args.allow_no_audio, args.per_item_timeout_secs)?;
+    Ok(args.delete_source && delete_source(osz_path, pb))
 }
 
-/// Parse .osu file content to extract image references from [Events] section
-fn parse_images_from_osu(content: &str) -> OsuImageRefs {
-    let mut refs = OsuImageRefs {
-        background: None,
-        storyboard: Vec::new(),
-    };
-    let mut in_events = false;
-    
-    for line in content.lines() {
-        let line = line.trim();
-        
-        if line == "[Events]" {
-            in_events = true;
-            continue;
-        }
-        
-        // Check if we've left the Events section
-        if in_events && line.starts_with('[') {
-            break;
-        }
-        
-        if !in_events {
-            continue;
+/// Remove an archive that was fully extracted (--delete-source).
+fn delete_source(osz_path: &Path, pb: &ProgressBar) -> bool {
+    match fs::remove_file(osz_path) {
+        Ok(()) => true,
+        Err(e) => {
+            pb.println(format!("⚠ Failed to delete {}: {}", osz_path.display(), e));
+            false
         }
+    }
+}
 
-        // Skip comments, empty lines, and videos
-        if line.is_empty() || line.starts_with("//") || line.starts_with("Video,") {
-            continue;
-        }
+/// Run `extract_osz` on a worker thread, giving up after `timeout_secs` if set.
+///
+/// A timed-out worker can't be killed, so it extracts into a staging folder and
+/// only moves it into place if it finishes before the timeout; otherwise it
+/// discards its output. A timed-out archive therefore never leaves a folder that
+/// later runs would skip as already extracted.
+fn extract_osz_with_timeout(
+    osz_path: &Path,
+    output_dir: &Path,
+    flatten: bool,
+    allow_no_audio: bool,
+    timeout_secs: Option<u64>,
+) -> Result<()> {
+    let Some(secs) = timeout_secs else {
+        return extract_osz(osz_path, output_dir, flatten, allow_no_audio, &AtomicBool::new(false));
+    };
 
-        // Extract filename from quoted string in the line
-        if let Some(start) = line.find('"') {
-            if let Some(end) = line[start + 1..].find('"') {
-                let filename = line[start + 1..start + 1 + end].to_string();
-                if filename.is_empty() {
-                    continue;
-                }
-                
-                // Background line: 0,0,"filename",...
-                if line.starts_with("0,0,") {
-                    if refs.background.is_none() {
-                        refs.background = Some(filename);
-                    }
-                } else {
-                    // Storyboard sprite/animation
-                    refs.storyboard.push(filename);
-                }
+    let (tx, rx) = mpsc::channel();
+    let osz_path_owned = osz_path.to_path_buf();
+    let output_dir_owned = output_dir.to_path_buf();
+    let settled = Arc::new(AtomicBool::new(false));
+    let worker_settled = Arc::clone(&settled);
+
+    thread::spawn(move || {
+        let _ = tx.send(extract_osz(&osz_path_owned, &output_dir_owned, flatten, allow_no_audio, &worker_settled));
+    });
+
+    match rx.recv_timeout(Duration::from_secs(secs)) {
+        Ok(result) => result,
+        Err(RecvTimeoutError::Timeout) => {
+            if settled.swap(true, Ordering::SeqCst) {
+                // The worker finished just in time and is moving its folder into place
+                return rx.recv().unwrap_or_else(|_| Err(anyhow::anyhow!("Extraction worker panicked")));
             }
+            Err(ItemTimeout(secs).into())
         }
+        Err(RecvTimeoutError::Disconnected) => anyhow::bail!("Extraction worker panicked"),
     }
-    
-    refs
 }
 
-fn extract_osz(osz_path: &Path, output_dir: &Path) -> Result<()> {
-    
+/// Extract an archive to `output_dir/{archive stem}`
+///
+/// Files are written to a hidden staging folder next to it, which replaces the
+/// final folder only on success and if `settled` wasn't already set (by a
+/// timeout, see extract_osz_with_timeout). Failed or abandoned extractions
+/// leave no folder behind, and a failed `--force` re-extraction keeps the old one.
+fn extract_osz(osz_path: &Path, output_dir: &Path, flatten: bool, allow_no_audio: bool, settled: &AtomicBool) -> Result<()> {
     // Get the filename without extension to use as folder name
     let folder_name = osz_path
         .file_stem()
         .context("Failed to get file stem")?
         .to_string_lossy();
 
-    // Extract to output_dir/{folder_name}
+    // Extract to output_dir/{folder_name}, staged in the same directory so the move is a rename
     let extract_folder = output_dir.join(folder_name.as_ref());
+    let staging_folder = output_dir.join(format!(".{}.partial", folder_name));
 
-    // Create the extraction folder
-    fs::create_dir_all(&extract_folder)
-        .with_context(|| format!("Failed to create folder: {}", extract_folder.display()))?;
-
-    // Run extraction - if it fails, clean up the folder
-    let result = extract_osz_inner(osz_path, &extract_folder);
-    
-    if result.is_err() {
-        // Clean up empty or partial folder on failure
-        let _ = fs::remove_dir_all(&extract_folder);
+    // A staging folder left by an interrupted run is stale
+    if staging_folder.exists() {
+        fs::remove_dir_all(&staging_folder)
+            .with_context(|| format!("Failed to remove stale folder: {}", staging_folder.display()))?;
     }
-    
-    result
-}
-
-fn extract_osz_inner(osz_path: &Path, extract_folder: &Path) -> Result<()> {
-    use std::collections::HashSet;
-    
-    // Open the .osz file (which is just a zip archive)
-    let file = File::open(osz_path)
-        .with_context(|| format!("Failed to open: {}", osz_path.display()))?;
+    fs::create_dir_all(&staging_folder)
+        .with_context(|| format!("Failed to create folder: {}", staging_folder.display()))?;
 
-    let mut archive = ZipArchive::new(file)
-        .with_context(|| format!("Failed to read zip: {}", osz_path.display()))?;
+    let result = osz_extractor::extract_archive(osz_path, &staging_folder, flatten, allow_no_audio);
 
-    // First pass: read all files
-    let mut files_data: Vec<(PathBuf, Vec<u8>)> = Vec::new();
-
-    for i in 0..archive.len() {
-        let mut file = archive.by_index(i)?;
-
-        // Skip directories
-        if file.is_dir() {
-            continue;
-        }
+    // Whoever sets `settled` first decides: this worker commits, or the timeout discards
+    if result.is_err() || settled.swap(true, Ordering::SeqCst) {
+        let _ = fs::remove_dir_all(&staging_folder);
+        return result;
+    }
 
-        // Get the file path, handling potential directory entries
-        let inner_path = match file.enclosed_name() {
-            Some(path) => path.to_path_buf(),
-            None => continue,
-        };
+    // --force re-extracts over an existing folder
+    if extract_folder.exists() {
+        fs::remove_dir_all(&extract_folder)
+            .with_context(|| format!("Failed to replace folder: {}", extract_folder.display()))?;
+    }
+    fs::rename(&staging_folder, &extract_folder)
+        .with_context(|| format!("Failed to move {} into place", staging_folder.display()))
+}
 
-        // Read file content
-        let mut data = Vec::new();
-        io::Read::read_to_end(&mut file, &mut data)?;
 
-        files_data.push((inner_path, data));
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use zip::write::SimpleFileOptions;
+
+    /// Fresh directory under the system temp directory
+    fn temp_dir() -> PathBuf {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "osz-extractor-test-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        dir
     }
 
-    // Second pass: parse .osu files to find referenced images
-    let mut required_backgrounds: HashSet<String> = HashSet::new();
-    let mut optional_images: HashSet<String> = HashSet::new();
-    let mut has_osu_files = false;
-    
-    for (path, data) in &files_data {
-        if is_osu_file(path) || is_osb_file(path) {
-            if is_osu_file(path) {
-                has_osu_files = true;
-            }
-            if let Ok(content) = std::str::from_utf8(data) {
-                let refs = parse_images_from_osu(content);
-                if let Some(bg) = refs.background {
-                    required_backgrounds.insert(normalize_path(&bg));
-                }
-                for img in refs.storyboard {
-                    optional_images.insert(normalize_path(&img));
-                }
-            }
-        }
+    /// `123.osz` in `dir` holding a single storyboard-only (virtual audio) difficulty
+    fn write_archive(dir: &Path) -> PathBuf {
+        let path = dir.join("123.osz");
+        let mut zip = zip::ZipWriter::new(File::create(&path).unwrap());
+        zip.start_file("map.osu", SimpleFileOptions::default()).unwrap();
+        zip.write_all(b"osu file format v14\n\n[General]\nAudioFilename: virtual\n").unwrap();
+        zip.finish().unwrap();
+        path
     }
 
-    // Validate: must have at least one .osu file
-    if !has_osu_files {
-        anyhow::bail!("No .osu files found");
+    #[test]
+    fn extraction_is_moved_into_place() {
+        let dir = temp_dir();
+        let osz = write_archive(&dir);
+        let output = dir.join("out");
+
+        extract_osz(&osz, &output, false, true, &AtomicBool::new(false)).unwrap();
+        assert!(output.join("123").join("map.osu").is_file());
+        assert!(!output.join(".123.partial").exists());
     }
 
-    // Build set of available files (lowercased for case-insensitive matching)
-    let available_files: HashSet<String> = files_data
-        .iter()
-        .map(|(path, _)| normalize_path(&path.to_string_lossy()))
-        .collect();
+    #[test]
+    fn settled_extraction_is_discarded() {
+        let dir = temp_dir();
+        let osz = write_archive(&dir);
+        let output = dir.join("out");
 
-    // Check that all required backgrounds exist
-    for bg in &required_backgrounds {
-        if !available_files.contains(bg) {
-            anyhow::bail!("Required background not found: {}", bg);
-        }
+        // As after a timeout: the worker finishes but must not leave a folder behind
+        extract_osz(&osz, &output, false, true, &AtomicBool::new(true)).unwrap();
+        assert!(!output.join("123").exists());
+        assert!(!output.join(".123.partial").exists());
     }
 
-    // Third pass: extract files
-    let mut audio_found = false;
-    
-    for (inner_path, data) in &files_data {
-        let normalized = normalize_path(&inner_path.to_string_lossy());
-        
-        // Always keep: .osu files, .osb files
-        let keep = is_osu_file(inner_path) 
-            || is_osb_file(inner_path)
-            || is_audio_content(data) 
-            || required_backgrounds.contains(&normalized)
-            || optional_images.contains(&normalized);
-
-        if !keep {
-            continue;
-        }
+    #[test]
+    fn failed_reextraction_keeps_the_existing_folder() {
+        let dir = temp_dir();
+        let osz = dir.join("123.osz");
+        fs::write(&osz, b"not a zip").unwrap();
+        let existing = dir.join("out").join("123");
+        fs::create_dir_all(&existing).unwrap();
+        fs::write(existing.join("map.osu"), b"old").unwrap();
+
+        assert!(extract_osz(&osz, &dir.join("out"), false, true, &AtomicBool::new(false)).is_err());
+        assert_eq!(fs::read(existing.join("map.osu")).unwrap(), b"old");
+    }
 
-        // Track if we found audio
-        if is_audio_content(data) {
-            audio_found = true;
-        }
+    #[test]
+    fn timed_out_extraction_never_lands() {
+        let dir = temp_dir();
+        let osz = write_archive(&dir);
+        let output = dir.join("out");
 
-        // Warn about missing optional storyboard images
-        if optional_images.contains(&normalized) && !available_files.contains(&normalized) {
-            eprintln!("⚠ Storyboard image not found: {}", inner_path.display());
-            continue;
+        // A zero-second budget races the worker: it may finish first, but once the
+        // timeout is reported its folder must never appear
+        match extract_osz_with_timeout(&osz, &output, false, true, Some(0)) {
+            Ok(()) => assert!(output.join("123").join("map.osu").is_file()),
+            Err(e) => {
+                assert!(e.is::<ItemTimeout>());
+                thread::sleep(Duration::from_millis(200));
+                assert!(!output.join("123").exists());
+            }
         }
+    }
 
-        let outpath = extract_folder.join(&inner_path);
-
-        // Ensure parent directory exists
-        if let Some(parent) = outpath.parent() {
-            fs::create_dir_all(parent)?;
-        }
+    /// Arguments extracting `dir` into `dir/out` with --delete-source
+    fn delete_source_args(dir: &Path) -> Args {
+        let (input, output) = (dir.to_string_lossy(), dir.join("out").to_string_lossy().into_owned());
+        Args::parse_from(["osz-extractor", "--input-dir", &input, "--output-dir", &output, "--allow-no-audio", "--delete-source"])
+    }
 
-        let mut outfile = File::create(&outpath)
-            .with_context(|| format!("Failed to create file: {}", outpath.display()))?;
+    #[test]
+    fn delete_source_removes_an_extracted_archive() {
+        let dir = temp_dir();
+        let osz = write_archive(&dir);
 
-        io::Write::write_all(&mut outfile, &data)?;
+        assert!(extract_then_delete(&osz, &delete_source_args(&dir), &ProgressBar::hidden()).unwrap());
+        assert!(!osz.exists());
+        assert!(dir.join("out").join("123").join("map.osu").is_file());
     }
 
-    // Validate: must have audio
-    if !audio_found {
-        anyhow::bail!("No audio file found");
+    #[test]
+    fn delete_source_keeps_an_archive_that_failed() {
+        let dir = temp_dir();
+        let osz = dir.join("123.osz");
+        fs::write(&osz, b"not a zip").unwrap();
+
+        assert!(extract_then_delete(&osz, &delete_source_args(&dir), &ProgressBar::hidden()).is_err());
+        assert!(osz.exists());
     }
 
-    // Delete the original .osz file after successful extraction
-    // Commented out for now.
-    // fs::remove_file(osz_path)
-    //     .with_context(|| format!("Failed to delete: {}", osz_path.display()))?;
+    #[test]
+    fn archives_are_kept_without_delete_source() {
+        let dir = temp_dir();
+        let osz = write_archive(&dir);
+        let mut args = delete_source_args(&dir);
+        args.delete_source = false;
 
-    Ok(())
+        assert!(!extract_then_delete(&osz, &args, &ProgressBar::hidden()).unwrap());
+        assert!(osz.exists());
+    }
 }
@@ -0,0 +1,142 @@
+//! Lightweight metadata index (`--metadata-only`)
+//!
+//! Reads only the `.osu` entries of each archive and writes one JSON line per
+//! difficulty, without extracting anything to disk.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use zip::ZipArchive;
+
+use crate::{decompress_beatmap_entry, is_osu_file};
+
+/// One difficulty in the index
+#[derive(Debug, Default, Serialize)]
+pub struct BeatmapMeta {
+    /// `BeatmapSetID` from the file, or the archive file stem if missing
+    pub beatmapset_id: Option<i32>,
+    pub beatmap_id: Option<i32>,
+    pub artist: String,
+    pub title: String,
+    pub creator: String,
+    pub version: String,
+    /// 0 = osu, 1 = taiko, 2 = catch, 3 = mania (same as `beatmaps.parquet`)
+    pub mode: i32,
+    /// `.osu` path inside the archive
+    pub osu_file: String,
+}
+
+/// Read the metadata of every `.osu` entry in an archive
+pub fn read_osz_metadata(osz_path: &Path) -> Result<Vec<BeatmapMeta>> {
+    let file = File::open(osz_path)
+        .with_context(|| format!("Failed to open: {}", osz_path.display()))?;
+    let mut archive = ZipArchive::new(file)
+        .with_context(|| format!("Failed to read zip: {}", osz_path.display()))?;
+
+    let stem_id = osz_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .and_then(|s| s.parse::<i32>().ok());
+
+    let mut metas = Vec::new();
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)?;
+        if entry.is_dir() {
+            continue;
+        }
+        let Some(inner_path) = entry.enclosed_name() else {
+            continue;
+        };
+
+        // Skip audio and images without reading them; compressed entries are checked after decompression
+        let name = inner_path.to_string_lossy().to_lowercase();
+        if !name.contains(".osu") {
+            continue;
+        }
+
+        let mut data = Vec::new();
+        io::Read::read_to_end(&mut entry, &mut data)?;
+        let (inner_path, data) = decompress_beatmap_entry(inner_path, data)?;
+        if !is_osu_file(&inner_path) {
+            continue;
+        }
+
+        let mut meta = parse_osu_metadata(&osu_text::normalize_osu_text(&data));
+        meta.beatmapset_id = meta.beatmapset_id.or(stem_id);
+        meta.osu_file = inner_path.to_string_lossy().replace('\\', "/");
+        metas.push(meta);
+    }
+
+    if metas.is_empty() {
+        anyhow::bail!("No .osu files found");
+    }
+    Ok(metas)
+}
+
+/// Parse `[General]` mode and `[Metadata]` fields
+///
+/// Expects text from `osu_text::normalize_osu_text` (no BOM, `\n` line endings).
+fn parse_osu_metadata(content: &str) -> BeatmapMeta {
+    let mut meta = BeatmapMeta::default();
+    let mut section = "";
+
+    for line in content.lines() {
+        let line = line.trim();
+
+        if line.starts_with('[') && line.ends_with(']') {
+            // Everything of interest precedes [Difficulty]
+            if section == "Metadata" {
+                break;
+            }
+            section = &line[1..line.len() - 1];
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+
+        match (section, key.trim()) {
+            ("General", "Mode") => meta.mode = value.parse().unwrap_or(0),
+            ("Metadata", "Artist") => meta.artist = value.to_string(),
+            ("Metadata", "Title") => meta.title = value.to_string(),
+            ("Metadata", "Creator") => meta.creator = value.to_string(),
+            ("Metadata", "Version") => meta.version = value.to_string(),
+            ("Metadata", "BeatmapID") => meta.beatmap_id = value.parse().ok(),
+            ("Metadata", "BeatmapSetID") => meta.beatmapset_id = value.parse().ok(),
+            _ => {}
+        }
+    }
+
+    meta
+}
+
+/// JSON Lines writer for the index
+pub struct IndexWriter {
+    writer: BufWriter<File>,
+    rows: usize,
+}
+
+impl IndexWriter {
+    pub fn create(path: &Path) -> Result<Self> {
+        let file = File::create(path)
+            .with_context(|| format!("Failed to create index: {}", path.display()))?;
+        Ok(Self { writer: BufWriter::new(file), rows: 0 })
+    }
+
+    pub fn write(&mut self, meta: &BeatmapMeta) -> Result<()> {
+        serde_json::to_writer(&mut self.writer, meta)?;
+        self.writer.write_all(b"\n")?;
+        self.rows += 1;
+        Ok(())
+    }
+
+    /// Flush the file and return the number of rows written
+    pub fn finish(mut self) -> Result<usize> {
+        self.writer.flush()?;
+        Ok(self.rows)
+    }
+}
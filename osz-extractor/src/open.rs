@@ -0,0 +1,197 @@
+//! Resolving the `.osu` file to open from a `.osu` path, a mapset folder or a `.osz` archive
+//!
+//! Shared by the viewer and the player. Archives are extracted into a fresh temp
+//! folder owned by an [`ExtractedArchive`], which removes it when dropped, so
+//! callers keep the guard alive for as long as they stream audio from it.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::extract::extract_archive;
+
+/// Temp folder a `.osz` was extracted into, removed on drop
+#[derive(Debug)]
+pub struct ExtractedArchive {
+    path: PathBuf,
+}
+
+impl ExtractedArchive {
+    /// Folder holding the extracted files
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for ExtractedArchive {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+/// The `.osu` file to open, with the extracted archive it lives in (if any)
+#[derive(Debug)]
+pub struct ResolvedOsu {
+    pub osu_path: PathBuf,
+    pub archive: Option<ExtractedArchive>,
+}
+
+/// Resolve the `.osu` file for a `.osu` path, a folder or a `.osz` archive
+///
+/// Folders and archives open their first difficulty by file name, so the choice
+/// is stable. Extensions are compared case-insensitively.
+pub fn resolve_osu_path(path: &Path) -> Result<ResolvedOsu> {
+    if !path.exists() {
+        anyhow::bail!("File not found: {}", path.display());
+    }
+
+    if path.is_dir() {
+        return Ok(ResolvedOsu { osu_path: first_osu_file(path)?, archive: None });
+    }
+
+    if has_extension(path, "osz") {
+        let archive = extract_to_temp(path)?;
+        let osu_path = first_osu_file(archive.path())?;
+        return Ok(ResolvedOsu { osu_path, archive: Some(archive) });
+    }
+
+    if !has_extension(path, "osu") {
+        anyhow::bail!("File must have .osu extension");
+    }
+
+    Ok(ResolvedOsu { osu_path: path.to_path_buf(), archive: None })
+}
+
+/// First `.osu` file in a folder, by name
+fn first_osu_file(folder: &Path) -> Result<PathBuf> {
+    let mut osu_files: Vec<PathBuf> = fs::read_dir(folder)
+        .context(format!("Failed to read {}", folder.display()))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| p.is_file() && has_extension(p, "osu"))
+        .collect();
+    osu_files.sort();
+
+    osu_files
+        .into_iter()
+        .next()
+        .context(format!("No .osu files in {}", folder.display()))
+}
+
+/// Extract a `.osz` archive into a new temp folder so it opens like a mapset folder
+///
+/// Every call gets its own folder, so two open archives with the same name (or two
+/// running processes) never clear each other's files.
+pub fn extract_to_temp(osz_path: &Path) -> Result<ExtractedArchive> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let stem = osz_path.file_stem().context("Invalid .osz path")?;
+    let path = std::env::temp_dir().join(format!(
+        "osz-extractor-{}-{}-{}",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed),
+        stem.to_string_lossy()
+    ));
+    fs::create_dir_all(&path).context(format!("Failed to create {}", path.display()))?;
+    // Owned from here, so a failed extraction is cleaned up too
+    let archive = ExtractedArchive { path };
+
+    extract_archive(osz_path, archive.path(), false, true)
+        .context(format!("Failed to extract {}", osz_path.display()))?;
+    Ok(archive)
+}
+
+/// Audio file named by a beatmap in the folder of its `.osu`, if it is a file there
+pub fn find_audio(osu_path: &Path, audio_file: &str) -> Option<PathBuf> {
+    osu_path
+        .parent()
+        .map(|p| p.join(audio_file))
+        .filter(|p| p.is_file())
+}
+
+/// Whether `path` has extension `ext`, ignoring ASCII case
+pub fn has_extension(path: &Path, ext: &str) -> bool {
+    path.extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case(ext))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+
+    fn temp_dir() -> PathBuf {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "osz-extractor-open-test-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_archive(path: &Path) {
+        let mut zip = zip::ZipWriter::new(File::create(path).unwrap());
+        for name in ["b [Hard].osu", "a [Easy].osu"] {
+            zip.start_file(name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(b"osu file format v14\n\n[General]\nAudioFilename: virtual\n").unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn extensions_match_in_any_case() {
+        assert!(has_extension(Path::new("Map.OSU"), "osu"));
+        assert!(has_extension(Path::new("set.Osz"), "osz"));
+        assert!(!has_extension(Path::new("map.osu.bak"), "osu"));
+        assert!(!has_extension(Path::new("osu"), "osu"));
+    }
+
+    #[test]
+    fn folders_open_their_first_difficulty() {
+        let dir = temp_dir();
+        fs::write(dir.join("b [Hard].OSU"), "").unwrap();
+        fs::write(dir.join("a [Easy].osu"), "").unwrap();
+        fs::write(dir.join("audio.mp3"), "").unwrap();
+
+        let resolved = resolve_osu_path(&dir).unwrap();
+        assert_eq!(resolved.osu_path, dir.join("a [Easy].osu"));
+        assert!(resolved.archive.is_none());
+
+        assert!(resolve_osu_path(&dir.join("audio.mp3")).is_err());
+        assert!(resolve_osu_path(&dir.join("missing.osu")).is_err());
+    }
+
+    #[test]
+    fn archives_extract_to_unique_folders_removed_on_drop() {
+        let dir = temp_dir();
+        let osz = dir.join("123.OSZ");
+        write_archive(&osz);
+
+        let first = resolve_osu_path(&osz).unwrap();
+        let second = resolve_osu_path(&osz).unwrap();
+        let (a, b) = (first.archive.as_ref().unwrap(), second.archive.as_ref().unwrap());
+        assert_ne!(a.path(), b.path());
+        assert_eq!(first.osu_path, a.path().join("a [Easy].osu"));
+
+        let folder = a.path().to_path_buf();
+        drop(first);
+        assert!(!folder.exists());
+        assert!(b.path().join("b [Hard].osu").is_file());
+    }
+
+    #[test]
+    fn audio_must_be_a_file_next_to_the_osu() {
+        let dir = temp_dir();
+        fs::write(dir.join("audio.mp3"), "").unwrap();
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        let osu = dir.join("map.osu");
+
+        assert_eq!(find_audio(&osu, "audio.mp3"), Some(dir.join("audio.mp3")));
+        assert_eq!(find_audio(&osu, "sub"), None);
+        assert_eq!(find_audio(&osu, "missing.mp3"), None);
+    }
+}
@@ -0,0 +1,34 @@
+//! Download rate limiting shared across worker threads
+//!
+//! Each mirror host has its own token bucket, so every worker downloading
+//! from the same mirror draws on one budget while different mirrors don't
+//! slow each other down.
+
+use governor::clock::Clock;
+use governor::{DefaultKeyedRateLimiter, Quota};
+use std::thread;
+use std::time::Duration;
+
+/// Per-host request budget; takes `&self`, so one instance can be shared by all workers
+pub struct MirrorLimiter {
+    limiter: DefaultKeyedRateLimiter<String>,
+}
+
+impl MirrorLimiter {
+    /// Allow `requests_per_minute` requests to each host, evenly spaced (no bursts)
+    pub fn per_minute(requests_per_minute: u32) -> Self {
+        let period = Duration::from_secs(60) / requests_per_minute.max(1);
+        let quota = Quota::with_period(period).expect("request period is non-zero");
+        Self {
+            limiter: DefaultKeyedRateLimiter::keyed(quota),
+        }
+    }
+
+    /// Block until a request to `host` is allowed
+    pub fn wait(&self, host: &str) {
+        let key = host.to_string();
+        while let Err(not_until) = self.limiter.check_key(&key) {
+            thread::sleep(not_until.wait_time_from(self.limiter.clock().now()));
+        }
+    }
+}